@@ -0,0 +1,50 @@
+//! Abstractions for processors that analyze audio without changing it.
+
+use crate::audio::Buffer;
+use crate::{parameters, parameters::BufferStates, Processor};
+
+/// A trait for audio analyzers
+///
+/// An analyzer is a processor that observes audio without changing it, for example
+/// a tuner, a spectrum analyzer, or a loudness meter. Like an [`crate::effect::Effect`],
+/// it has an input and an output audio stream with the same channel layout, but
+/// wrappers always pass the input through to the output unchanged. Since the audio
+/// is never changed, analyzers do not need a bypass parameter.
+///
+/// Any results of the analysis must be sent to the UI by the analyzer itself.
+pub trait Analyzer: Processor {
+    /// Handle parameter changes without processing any audio data.
+    ///
+    /// Must not allocate or block.
+    fn handle_parameters<P: parameters::States>(&mut self, parameters: P);
+
+    /// Analyze a buffer of audio data.
+    ///
+    /// Must not allocate or block.
+    ///
+    /// `input` contains the audio that is passed through to the output.
+    ///
+    /// Like [`crate::effect::Effect::process`], this also receives information
+    /// about the state of the parameters throughout the buffer.
+    fn analyze<P: BufferStates, I: Buffer>(&mut self, parameters: P, input: &I);
+
+    /// Analyze a buffer of double-precision audio data.
+    ///
+    /// Must not allocate or block.
+    ///
+    /// This is called instead of [`Self::analyze`] when the host processes audio
+    /// in 64-bit, which only happens for processors that set
+    /// [`crate::Processor::SUPPORTS_DOUBLE_PRECISION`].
+    ///
+    /// Analyzers that support double precision must implement this. The default
+    /// implementation is never called, and fails to build for those analyzers.
+    fn analyze_f64<P: BufferStates, I: Buffer<f64>>(&mut self, parameters: P, input: &I) {
+        const {
+            assert!(
+                !Self::SUPPORTS_DOUBLE_PRECISION,
+                "Analyzers that support double precision must implement `analyze_f64`"
+            );
+        };
+        let _ = (parameters, input);
+    }
+}
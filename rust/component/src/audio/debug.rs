@@ -0,0 +1,41 @@
+//! Debug-only checks for audio buffers.
+
+use super::{channels, BufferMut};
+
+/// Asserts that no two channels of `buffer` share any memory.
+///
+/// Some hosts may pass the same memory for multiple channels, which can
+/// cause silent corruption when a processor writes to one channel and then
+/// reads from another. This is useful to call at the start of `process`
+/// during development to catch these configurations.
+///
+/// This only performs the check in debug builds - in release builds it does nothing.
+///
+/// # Examples
+///
+/// ```
+/// # use conformal_component::audio::{debug_assert_no_alias, BufferData};
+/// let buffer = BufferData::new_stereo([1.0, 2.0], [3.0, 4.0]);
+/// debug_assert_no_alias(&buffer);
+/// ```
+///
+/// # Panics
+///
+/// In debug builds, panics if any two channels of `buffer` overlap in memory.
+pub fn debug_assert_no_alias<B: BufferMut>(buffer: &B) {
+    if cfg!(debug_assertions) {
+        for (index, channel) in channels(buffer).enumerate() {
+            let range = channel.as_ptr_range();
+            for (other_index, other) in channels(buffer).enumerate().skip(index + 1) {
+                let other_range = other.as_ptr_range();
+                assert!(
+                    range.end <= other_range.start || other_range.end <= range.start,
+                    "Channels {index} and {other_index} of buffer share memory"
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,56 @@
+#[cfg(debug_assertions)]
+use crate::audio::{Buffer, BufferMut};
+use crate::audio::{BufferData, ChannelLayout};
+
+use super::*;
+
+/// A buffer that (incorrectly) uses the same memory for every channel.
+#[cfg(debug_assertions)]
+struct AliasedBuffer {
+    data: Vec<f32>,
+}
+
+#[cfg(debug_assertions)]
+impl Buffer for AliasedBuffer {
+    fn channel_layout(&self) -> ChannelLayout {
+        ChannelLayout::Stereo
+    }
+
+    fn num_frames(&self) -> usize {
+        self.data.len()
+    }
+
+    fn channel(&self, _channel: usize) -> &[f32] {
+        &self.data
+    }
+}
+
+#[cfg(debug_assertions)]
+impl BufferMut for AliasedBuffer {
+    fn channel_mut(&mut self, _channel: usize) -> &mut [f32] {
+        &mut self.data
+    }
+}
+
+#[test]
+fn distinct_channels_pass() {
+    let buffer = BufferData::new_stereo([1.0, 2.0], [3.0, 4.0]);
+    debug_assert_no_alias(&buffer);
+}
+
+#[test]
+fn empty_channels_pass() {
+    let buffer = BufferData::new(ChannelLayout::Stereo, 0);
+    debug_assert_no_alias(&buffer);
+}
+
+// The check is only made in debug builds.
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "share memory")]
+fn aliased_channels_panic() {
+    let buffer = AliasedBuffer {
+        data: vec![1.0, 2.0],
+    };
+    debug_assert_no_alias(&buffer);
+}
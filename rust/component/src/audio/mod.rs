@@ -13,11 +13,68 @@ pub enum ChannelLayout {
     ///
     /// Channel 0 is the left channel, and channel 1 is the right channel.
     Stereo,
+
+    /// A four channel surround buffer.
+    ///
+    /// The channels are left, right, surround left, and surround right, in that order.
+    Quad,
+
+    /// A six channel 5.1 surround buffer.
+    ///
+    /// The channels are left, right, center, low-frequency effects, surround left,
+    /// and surround right, in that order.
+    FivePointOne,
+
+    /// An eight channel 7.1 surround buffer.
+    ///
+    /// The channels are left, right, center, low-frequency effects, surround left,
+    /// surround right, side left, and side right, in that order. Here, the surround
+    /// channels are behind the listener, and the side channels are to either side.
+    SevenPointOne,
+}
+
+/// The role of a single channel within a [`ChannelLayout`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ChannelRole {
+    /// The left channel.
+    Left,
+
+    /// The right channel.
+    Right,
+
+    /// The center channel.
+    ///
+    /// This is also the role of the only channel of a [`ChannelLayout::Mono`] buffer.
+    Center,
+
+    /// The low-frequency effects channel.
+    Lfe,
+
+    /// The left surround channel, behind the listener.
+    SurroundLeft,
+
+    /// The right surround channel, behind the listener.
+    SurroundRight,
+
+    /// The left side channel, used in [`ChannelLayout::SevenPointOne`].
+    SideLeft,
+
+    /// The right side channel, used in [`ChannelLayout::SevenPointOne`].
+    SideRight,
 }
 
 mod compare;
 pub use compare::*;
 
+mod debug;
+pub use debug::*;
+
+mod ops;
+pub use ops::*;
+
+mod ramp;
+pub use ramp::*;
+
 mod slice;
 pub use slice::*;
 
@@ -30,14 +87,86 @@ impl ChannelLayout {
     /// # use conformal_component::audio::ChannelLayout;
     /// assert_eq!(ChannelLayout::Mono.num_channels(), 1);
     /// assert_eq!(ChannelLayout::Stereo.num_channels(), 2);
+    /// assert_eq!(ChannelLayout::FivePointOne.num_channels(), 6);
     /// ```
     #[must_use]
     pub fn num_channels(self) -> usize {
+        self.roles().len()
+    }
+
+    fn roles(self) -> &'static [ChannelRole] {
         match self {
-            ChannelLayout::Mono => 1,
-            ChannelLayout::Stereo => 2,
+            ChannelLayout::Mono => &[ChannelRole::Center],
+            ChannelLayout::Stereo => &[ChannelRole::Left, ChannelRole::Right],
+            ChannelLayout::Quad => &[
+                ChannelRole::Left,
+                ChannelRole::Right,
+                ChannelRole::SurroundLeft,
+                ChannelRole::SurroundRight,
+            ],
+            ChannelLayout::FivePointOne => &[
+                ChannelRole::Left,
+                ChannelRole::Right,
+                ChannelRole::Center,
+                ChannelRole::Lfe,
+                ChannelRole::SurroundLeft,
+                ChannelRole::SurroundRight,
+            ],
+            ChannelLayout::SevenPointOne => &[
+                ChannelRole::Left,
+                ChannelRole::Right,
+                ChannelRole::Center,
+                ChannelRole::Lfe,
+                ChannelRole::SurroundLeft,
+                ChannelRole::SurroundRight,
+                ChannelRole::SideLeft,
+                ChannelRole::SideRight,
+            ],
         }
     }
+
+    /// Returns an iterator over the channel indices of the layout, along with the role of each channel.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use conformal_component::audio::{ChannelLayout, ChannelRole};
+    /// assert!(ChannelLayout::Mono.channels().eq([(0, ChannelRole::Center)]));
+    /// assert!(ChannelLayout::Stereo
+    ///     .channels()
+    ///     .eq([(0, ChannelRole::Left), (1, ChannelRole::Right)]));
+    /// ```
+    pub fn channels(self) -> impl Iterator<Item = (usize, ChannelRole)> + Clone {
+        self.roles().iter().copied().enumerate()
+    }
+
+    /// Returns the index of the channel with the given role, if the layout has one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use conformal_component::audio::{ChannelLayout, ChannelRole};
+    /// assert_eq!(ChannelLayout::FivePointOne.channel_index(ChannelRole::Lfe), Some(3));
+    /// assert_eq!(ChannelLayout::Stereo.channel_index(ChannelRole::Lfe), None);
+    /// ```
+    #[must_use]
+    pub fn channel_index(self, role: ChannelRole) -> Option<usize> {
+        self.roles().iter().position(|r| *r == role)
+    }
+
+    /// Returns the role of the channel at `index`, or `None` if the layout has fewer channels.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use conformal_component::audio::{ChannelLayout, ChannelRole};
+    /// assert_eq!(ChannelLayout::FivePointOne.channel_role(2), Some(ChannelRole::Center));
+    /// assert_eq!(ChannelLayout::Mono.channel_role(1), None);
+    /// ```
+    #[must_use]
+    pub fn channel_role(self, index: usize) -> Option<ChannelRole> {
+        self.roles().get(index).copied()
+    }
 }
 
 /// Represents a (potentially multi-channel) buffer of audio samples
@@ -45,7 +174,11 @@ impl ChannelLayout {
 /// A [Buffer] doesn't specify the exact storage format of the samples, but
 /// each channel must be a contiguous slice of samples. All channels must have
 /// the same number of samples, that is, [`Buffer::num_frames`].
-pub trait Buffer {
+///
+/// Samples are `f32` unless otherwise specified. Processors that opt in to
+/// double-precision processing with [`crate::Processor::SUPPORTS_DOUBLE_PRECISION`]
+/// may also receive `Buffer<f64>`s.
+pub trait Buffer<S = f32> {
     /// The layout of the channels in the buffer.
     fn channel_layout(&self) -> ChannelLayout;
 
@@ -67,7 +200,7 @@ pub trait Buffer {
     /// # Panics
     ///
     /// Panics if `channel` is greater than or equal to [`Self::num_channels`].
-    fn channel(&self, channel: usize) -> &[f32];
+    fn channel(&self, channel: usize) -> &[S];
 }
 
 /// Returns an iterator for the channels of a buffer.
@@ -81,16 +214,16 @@ pub trait Buffer {
 /// let buffer = BufferData::new_stereo([1.0, 2.0], [3.0, 4.0]);
 /// assert!(channels(&buffer).eq([[1.0, 2.0], [3.0, 4.0]]));
 /// ```
-pub fn channels<B: Buffer>(buffer: &B) -> impl Iterator<Item = &[f32]> {
+pub fn channels<'a, S: 'a, B: Buffer<S>>(buffer: &'a B) -> impl Iterator<Item = &'a [S]> {
     (0..buffer.num_channels()).map(move |channel| buffer.channel(channel))
 }
 
 /// A mutable (potentially multi-channel) buffer of audio samples.
 ///
 /// This is a mutable version of [`Buffer`].
-pub trait BufferMut: Buffer {
+pub trait BufferMut<S = f32>: Buffer<S> {
     /// Get a channel from the buffer as a mutable slice
-    fn channel_mut(&mut self, channel: usize) -> &mut [f32];
+    fn channel_mut(&mut self, channel: usize) -> &mut [S];
 }
 
 /// Returns an iterator for the channels of a mutable buffer.
@@ -109,7 +242,9 @@ pub trait BufferMut: Buffer {
 /// }
 /// assert_eq!(buffer.channel(0), [2.0, 4.0, 6.0]);
 /// ```
-pub fn channels_mut<B: BufferMut>(buffer: &mut B) -> impl Iterator<Item = &mut [f32]> {
+pub fn channels_mut<'a, S: 'a, B: BufferMut<S>>(
+    buffer: &'a mut B,
+) -> impl Iterator<Item = &'a mut [S]> {
     (0..buffer.num_channels()).map(move |channel| unsafe {
         std::slice::from_raw_parts_mut(
             buffer.channel_mut(channel).as_mut_ptr(),
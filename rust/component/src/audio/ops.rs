@@ -0,0 +1,209 @@
+//! Utilities for mixing, scaling, and crossfading audio buffers.
+
+use super::{channels, channels_mut, Buffer, BufferMut, ChannelLayout, ChannelRole};
+
+/// Adds the samples of `src` into `dst`.
+///
+/// # Examples
+///
+/// ```
+/// # use conformal_component::audio::{add_in_place, channels, BufferData};
+/// let src = BufferData::new_stereo([1.0, 2.0], [3.0, 4.0]);
+/// let mut dst = BufferData::new_stereo([10.0, 20.0], [30.0, 40.0]);
+/// add_in_place(&src, &mut dst);
+/// assert!(channels(&dst).eq([[11.0, 22.0], [33.0, 44.0]]));
+/// ```
+///
+/// # Panics
+///
+/// Panics if `src` and `dst` do not have the same channel layout or number of frames.
+///
+/// ```should_panic
+/// # use conformal_component::audio::{add_in_place, BufferData};
+/// let src = BufferData::new_mono(vec![1.0, 2.0]);
+/// let mut dst = BufferData::new_mono(vec![1.0, 2.0, 3.0]);
+/// add_in_place(&src, &mut dst);
+/// ```
+pub fn add_in_place<S: Buffer, D: BufferMut>(src: &S, dst: &mut D) {
+    assert_eq!(
+        src.channel_layout(),
+        dst.channel_layout(),
+        "Source and destination buffers have different channel layouts"
+    );
+    assert_eq!(
+        src.num_frames(),
+        dst.num_frames(),
+        "Source and destination buffers have different numbers of frames"
+    );
+    for (src, dst) in channels(src).zip(channels_mut(dst)) {
+        for (src, dst) in src.iter().zip(dst.iter_mut()) {
+            *dst += *src;
+        }
+    }
+}
+
+/// Multiplies every sample of `buffer` by `gain`.
+///
+/// # Examples
+///
+/// ```
+/// # use conformal_component::audio::{scale_in_place, channels, BufferData};
+/// let mut buffer = BufferData::new_stereo([1.0, 2.0], [3.0, 4.0]);
+/// scale_in_place(0.5, &mut buffer);
+/// assert!(channels(&buffer).eq([[0.5, 1.0], [1.5, 2.0]]));
+/// ```
+pub fn scale_in_place<B: BufferMut>(gain: f32, buffer: &mut B) {
+    for channel in channels_mut(buffer) {
+        for sample in channel {
+            *sample *= gain;
+        }
+    }
+}
+
+/// Returns the gains of a linear crossfade from `a` to `b`, at position `t`.
+///
+/// The result is `(1 - t, t)`, that is, the gain to apply to `a` and the gain to
+/// apply to `b`. The gains always sum to 1, so crossfading between two copies of
+/// the same signal leaves it unchanged. This is the right choice for crossfading
+/// between correlated signals, such as an effect's dry and wet signals.
+///
+/// `t` is clamped to the range `[0, 1]`.
+///
+/// # Examples
+///
+/// ```
+/// # use conformal_component::audio::linear_gains;
+/// assert_eq!(linear_gains(0.0), (1.0, 0.0));
+/// assert_eq!(linear_gains(0.25), (0.75, 0.25));
+/// assert_eq!(linear_gains(2.0), (0.0, 1.0));
+/// ```
+#[must_use]
+pub fn linear_gains(t: f32) -> (f32, f32) {
+    let t = t.clamp(0.0, 1.0);
+    (1.0 - t, t)
+}
+
+/// Returns the gains of an equal-power crossfade from `a` to `b`, at position `t`.
+///
+/// The result is `(cos(t * π / 2), sin(t * π / 2))`, that is, the gain to apply to
+/// `a` and the gain to apply to `b`. The squares of the gains always sum to 1, so
+/// crossfading between two uncorrelated signals of the same level keeps the level
+/// constant, without the dip in the middle of a [`linear_gains`] crossfade.
+///
+/// `t` is clamped to the range `[0, 1]`.
+///
+/// # Examples
+///
+/// ```
+/// # use conformal_component::audio::{approx_eq, equal_power_gains};
+/// assert_eq!(equal_power_gains(0.0), (1.0, 0.0));
+/// let (a, b) = equal_power_gains(0.5);
+/// assert!(approx_eq(a, std::f32::consts::FRAC_1_SQRT_2, 1e-6));
+/// assert!(approx_eq(b, std::f32::consts::FRAC_1_SQRT_2, 1e-6));
+/// ```
+#[must_use]
+pub fn equal_power_gains(t: f32) -> (f32, f32) {
+    let (b, a) = (t.clamp(0.0, 1.0) * std::f32::consts::FRAC_PI_2).sin_cos();
+    (a, b)
+}
+
+/// The gain used when splitting a center channel into left and right, or when
+/// summing left and right into a center channel. This is -3 dB.
+const CENTER_MIX_GAIN: f32 = std::f32::consts::FRAC_1_SQRT_2;
+
+/// Returns how much of a channel with role `src` ends up in the channel with role `dst`
+/// when converting to `dst_layout`.
+fn layout_conversion_gain(src: ChannelRole, dst: ChannelRole, dst_layout: ChannelLayout) -> f32 {
+    if src == dst {
+        return 1.0;
+    }
+    if dst_layout.channels().any(|(_, role)| role == src) {
+        // The source channel has a direct counterpart, so it isn't mixed anywhere else.
+        return 0.0;
+    }
+    let has = |role| dst_layout.channels().any(|(_, r)| r == role);
+    match (src, dst) {
+        (ChannelRole::Center, ChannelRole::Left | ChannelRole::Right)
+        | (ChannelRole::Left | ChannelRole::Right, ChannelRole::Center)
+        | (ChannelRole::SideLeft, ChannelRole::SurroundLeft)
+        | (ChannelRole::SideRight, ChannelRole::SurroundRight)
+        | (ChannelRole::SurroundLeft, ChannelRole::Left)
+        | (ChannelRole::SurroundRight, ChannelRole::Right) => CENTER_MIX_GAIN,
+        (ChannelRole::SideLeft, ChannelRole::Left) if !has(ChannelRole::SurroundLeft) => {
+            CENTER_MIX_GAIN
+        }
+        (ChannelRole::SideRight, ChannelRole::Right) if !has(ChannelRole::SurroundRight) => {
+            CENTER_MIX_GAIN
+        }
+        (
+            ChannelRole::SurroundLeft
+            | ChannelRole::SurroundRight
+            | ChannelRole::SideLeft
+            | ChannelRole::SideRight,
+            ChannelRole::Center,
+        ) if !has(ChannelRole::Left) => CENTER_MIX_GAIN * CENTER_MIX_GAIN,
+        _ => 0.0,
+    }
+}
+
+/// Converts `src` to the channel layout of `dst`, overwriting `dst`.
+///
+/// This uses conventional up- and downmix coefficients, following ITU-R BS.775:
+///
+///  - Channels with the same [`ChannelRole`] in both layouts are copied unchanged.
+///  - If `dst` has no center channel, the center channel of `src` is mixed into both
+///    left and right at -3 dB (a gain of 1/√2).
+///  - If `dst` has no left or right channels, the left and right channels of `src`
+///    are each mixed into the center channel at -3 dB.
+///  - If `dst` has no surround channels, the surround channels of `src` are mixed into
+///    left and right at -3 dB. If `dst` has no left or right channels either, they are
+///    mixed into the center channel at -6 dB.
+///  - If `dst` has no side channels, the side channels of `src` are mixed into the
+///    surround channels at -3 dB, or, if `dst` has no surround channels, treated like
+///    surround channels.
+///  - If `dst` has no low-frequency effects channel, the low-frequency effects channel
+///    of `src` is dropped.
+///
+/// For example, a stereo buffer is downmixed to mono as `(L + R) / √2`, and a mono buffer
+/// is upmixed to stereo by sending `C / √2` to each side. Both keep the overall power of
+/// uncorrelated signals the same.
+///
+/// # Examples
+///
+/// ```
+/// # use conformal_component::audio::{all_approx_eq, convert_layout, Buffer, BufferData, ChannelLayout};
+/// let src = BufferData::new_stereo([1.0, 0.0], [1.0, 1.0]);
+/// let mut dst = BufferData::new(ChannelLayout::Mono, 2);
+/// convert_layout(&src, &mut dst);
+/// let half_sqrt_2 = std::f32::consts::FRAC_1_SQRT_2;
+/// assert!(all_approx_eq(
+///     dst.channel(0).iter().copied(),
+///     [2.0 * half_sqrt_2, half_sqrt_2],
+///     1e-6,
+/// ));
+/// ```
+///
+/// # Panics
+///
+/// Panics if `src` and `dst` do not have the same number of frames.
+pub fn convert_layout<S: Buffer, D: BufferMut>(src: &S, dst: &mut D) {
+    assert_eq!(
+        src.num_frames(),
+        dst.num_frames(),
+        "Source and destination buffers have different numbers of frames"
+    );
+    let dst_layout = dst.channel_layout();
+    for (dst_index, dst_role) in dst_layout.channels() {
+        let dst_channel = dst.channel_mut(dst_index);
+        dst_channel.fill(0.0);
+        for (src_index, src_role) in src.channel_layout().channels() {
+            let gain = layout_conversion_gain(src_role, dst_role, dst_layout);
+            for (src, dst) in src.channel(src_index).iter().zip(dst_channel.iter_mut()) {
+                *dst += *src * gain;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,195 @@
+use crate::audio::{all_approx_eq, approx_eq, channels, BufferData};
+
+use super::*;
+
+#[test]
+fn add_in_place_mono() {
+    let src = BufferData::new_mono(vec![1.0, 2.0, 3.0]);
+    let mut dst = BufferData::new_mono(vec![1.0, 1.0, 1.0]);
+    add_in_place(&src, &mut dst);
+    assert_eq!(dst.channel(0), [2.0, 3.0, 4.0]);
+}
+
+#[test]
+fn scale_in_place_mono() {
+    let mut buffer = BufferData::new_mono(vec![1.0, 2.0, 3.0]);
+    scale_in_place(2.0, &mut buffer);
+    assert!(channels(&buffer).eq([[2.0, 4.0, 6.0]]));
+}
+
+#[test]
+fn equal_power_gains_have_constant_power() {
+    for step in 0..=100 {
+        #[allow(clippy::cast_precision_loss)]
+        let (a, b) = equal_power_gains(step as f32 / 100.0);
+        assert!(approx_eq(a * a + b * b, 1.0, 1e-6));
+        assert!(a >= -1e-6 && b >= 0.0);
+    }
+}
+
+#[test]
+fn equal_power_gains_endpoints() {
+    let (a, b) = equal_power_gains(1.0);
+    assert!(approx_eq(a, 0.0, 1e-6));
+    assert!(approx_eq(b, 1.0, 1e-6));
+    assert_eq!(equal_power_gains(-1.0), (1.0, 0.0));
+}
+
+#[test]
+fn linear_gains_sum_to_one() {
+    for step in 0..=100 {
+        #[allow(clippy::cast_precision_loss)]
+        let (a, b) = linear_gains(step as f32 / 100.0);
+        assert!(approx_eq(a + b, 1.0, 1e-6));
+    }
+}
+
+#[test]
+#[should_panic(expected = "different channel layouts")]
+fn add_in_place_mismatched_layouts_panics() {
+    let src = BufferData::new_mono(vec![1.0, 2.0]);
+    let mut dst = BufferData::new_stereo([1.0, 2.0], [3.0, 4.0]);
+    add_in_place(&src, &mut dst);
+}
+
+#[test]
+#[should_panic(expected = "different numbers of frames")]
+fn add_in_place_mismatched_lengths_panics() {
+    let src = BufferData::new_mono(vec![1.0, 2.0]);
+    let mut dst = BufferData::new_mono(vec![1.0, 2.0, 3.0]);
+    add_in_place(&src, &mut dst);
+}
+
+#[test]
+fn convert_layout_stereo_to_mono_sums_at_minus_3_db() {
+    let src = BufferData::new_stereo([1.0, 0.0, 0.5], [1.0, 1.0, -0.5]);
+    let mut dst = BufferData::new_mono(vec![10.0; 3]);
+    convert_layout(&src, &mut dst);
+    let gain = std::f32::consts::FRAC_1_SQRT_2;
+    assert!(all_approx_eq(
+        dst.channel(0).iter().copied(),
+        [2.0 * gain, gain, 0.0],
+        1e-6
+    ));
+    // A full-scale signal on one side comes out 3 dB down.
+    assert!(approx_eq(20.0 * dst.channel(0)[1].log10(), -3.0103, 1e-3));
+}
+
+#[test]
+fn convert_layout_mono_to_stereo_splits_at_minus_3_db() {
+    let src = BufferData::new_mono(vec![1.0, -0.5]);
+    let mut dst = BufferData::new(ChannelLayout::Stereo, 2);
+    convert_layout(&src, &mut dst);
+    let gain = std::f32::consts::FRAC_1_SQRT_2;
+    for channel in channels(&dst) {
+        assert!(all_approx_eq(
+            channel.iter().copied(),
+            [gain, -0.5 * gain],
+            1e-6
+        ));
+    }
+}
+
+#[test]
+fn convert_layout_same_layout_copies() {
+    let src = BufferData::new_stereo([1.0, 2.0], [3.0, 4.0]);
+    let mut dst = BufferData::new_stereo([10.0, 20.0], [30.0, 40.0]);
+    convert_layout(&src, &mut dst);
+    assert!(channels(&dst).eq([[1.0, 2.0], [3.0, 4.0]]));
+}
+
+#[test]
+fn layout_conversion_gain_drops_lfe() {
+    for layout in [ChannelLayout::Mono, ChannelLayout::Stereo] {
+        for (_, role) in layout.channels() {
+            assert!(approx_eq(
+                layout_conversion_gain(ChannelRole::Lfe, role, layout),
+                0.0,
+                1e-6
+            ));
+        }
+    }
+}
+
+#[test]
+#[should_panic(expected = "different numbers of frames")]
+fn convert_layout_mismatched_lengths_panics() {
+    let src = BufferData::new_mono(vec![1.0, 2.0]);
+    let mut dst = BufferData::new(ChannelLayout::Stereo, 3);
+    convert_layout(&src, &mut dst);
+}
+
+#[test]
+fn convert_layout_five_point_one_to_stereo() {
+    // L, R, C, LFE, Ls, Rs
+    let mut src = BufferData::new(ChannelLayout::FivePointOne, 1);
+    for (index, value) in [1.0, 2.0, 3.0, 4.0, 5.0, 6.0].into_iter().enumerate() {
+        src.channel_mut(index)[0] = value;
+    }
+    let mut dst = BufferData::new(ChannelLayout::Stereo, 1);
+    convert_layout(&src, &mut dst);
+    let gain = std::f32::consts::FRAC_1_SQRT_2;
+    assert!(approx_eq(dst.channel(0)[0], 1.0 + gain * (3.0 + 5.0), 1e-6));
+    assert!(approx_eq(dst.channel(1)[0], 2.0 + gain * (3.0 + 6.0), 1e-6));
+}
+
+#[test]
+fn convert_layout_seven_point_one_to_five_point_one_folds_sides() {
+    let mut src = BufferData::new(ChannelLayout::SevenPointOne, 1);
+    for index in 0..8 {
+        src.channel_mut(index)[0] = 1.0;
+    }
+    let mut dst = BufferData::new(ChannelLayout::FivePointOne, 1);
+    convert_layout(&src, &mut dst);
+    let gain = std::f32::consts::FRAC_1_SQRT_2;
+    assert!(all_approx_eq(
+        channels(&dst).map(|channel| channel[0]),
+        [1.0, 1.0, 1.0, 1.0, 1.0 + gain, 1.0 + gain],
+        1e-6
+    ));
+}
+
+#[test]
+fn convert_layout_quad_to_mono() {
+    let src = {
+        let mut src = BufferData::new(ChannelLayout::Quad, 1);
+        for index in 0..4 {
+            src.channel_mut(index)[0] = 1.0;
+        }
+        src
+    };
+    let mut dst = BufferData::new(ChannelLayout::Mono, 1);
+    convert_layout(&src, &mut dst);
+    let gain = std::f32::consts::FRAC_1_SQRT_2;
+    assert!(approx_eq(dst.channel(0)[0], 2.0 * gain + 2.0 * 0.5, 1e-6));
+}
+
+#[test]
+fn convert_layout_stereo_to_surround_leaves_new_channels_silent() {
+    let src = BufferData::new_stereo([1.0], [2.0]);
+    let mut dst = BufferData::new(ChannelLayout::SevenPointOne, 1);
+    for index in 0..8 {
+        dst.channel_mut(index)[0] = 10.0;
+    }
+    convert_layout(&src, &mut dst);
+    assert!(channels(&dst)
+        .map(|channel| channel[0])
+        .eq([1.0, 2.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0]));
+}
+
+#[test]
+fn surround_layouts_have_unique_roles() {
+    for layout in [
+        ChannelLayout::Mono,
+        ChannelLayout::Stereo,
+        ChannelLayout::Quad,
+        ChannelLayout::FivePointOne,
+        ChannelLayout::SevenPointOne,
+    ] {
+        for (index, role) in layout.channels() {
+            assert_eq!(layout.channel_index(role), Some(index));
+            assert_eq!(layout.channel_role(index), Some(role));
+        }
+        assert_eq!(layout.channel_role(layout.num_channels()), None);
+    }
+}
@@ -0,0 +1,94 @@
+//! A linear ramp for fading gains in and out without clicks.
+
+/// A value that ramps linearly between 0 and 1 over a fixed number of samples.
+///
+/// This is the building block of helpers that fade audio in and out to avoid
+/// clicks, such as [`crate::effect::SoftBypass`] and [`crate::effect::Freeze`].
+/// Each call to [`Self::step`] moves the value one sample towards either 0 or 1.
+///
+/// A newly created (or [reset](Self::reset)) ramp is at 0, but its first step
+/// jumps straight to its target instead of ramping. This way, processing starts
+/// in the right state without fading from an arbitrary one.
+///
+/// # Examples
+///
+/// ```
+/// # use conformal_component::audio::LinearRamp;
+/// let mut ramp = LinearRamp::new(4);
+/// assert_eq!(ramp.step(false), 0.0);
+/// let rising: Vec<_> = (0..5).map(|_| ramp.step(true)).collect();
+/// assert_eq!(rising, [0.25, 0.5, 0.75, 1.0, 1.0]);
+/// assert!(ramp.is_one());
+///
+/// ramp.reset();
+/// assert_eq!(ramp.step(true), 1.0);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinearRamp {
+    ramp_samples: usize,
+    position: usize,
+    settled: bool,
+}
+
+impl LinearRamp {
+    /// Creates a new ramp that takes `ramp_samples` samples to go between 0 and 1.
+    ///
+    /// A `ramp_samples` of 0 is treated as 1, that is, the value jumps.
+    #[must_use]
+    pub fn new(ramp_samples: usize) -> Self {
+        Self {
+            ramp_samples: ramp_samples.max(1),
+            position: 0,
+            settled: false,
+        }
+    }
+
+    /// Returns the ramp to 0, and makes the next step jump straight to its target.
+    ///
+    /// This is also the state of a newly created ramp.
+    pub fn reset(&mut self) {
+        self.position = 0;
+        self.settled = false;
+    }
+
+    /// Jumps to 1 if `one` is `true`, or to 0 otherwise, without ramping.
+    pub fn jump(&mut self, one: bool) {
+        self.position = if one { self.ramp_samples } else { 0 };
+        self.settled = true;
+    }
+
+    /// Advances the ramp by one sample towards 1 if `rising` is `true`, or
+    /// towards 0 otherwise, and returns the new value.
+    pub fn step(&mut self, rising: bool) -> f32 {
+        if !self.settled {
+            self.jump(rising);
+        } else if rising {
+            self.position = (self.position + 1).min(self.ramp_samples);
+        } else {
+            self.position = self.position.saturating_sub(1);
+        }
+        self.value()
+    }
+
+    /// The current value of the ramp, from 0 to 1.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn value(&self) -> f32 {
+        self.position as f32 / self.ramp_samples as f32
+    }
+
+    /// Returns `true` if the ramp is at 0.
+    #[must_use]
+    pub fn is_zero(&self) -> bool {
+        self.position == 0
+    }
+
+    /// Returns `true` if the ramp is at 1.
+    #[must_use]
+    pub fn is_one(&self) -> bool {
+        self.position == self.ramp_samples
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,40 @@
+use super::LinearRamp;
+
+#[test]
+fn first_step_jumps_to_target() {
+    let mut ramp = LinearRamp::new(4);
+    assert!(ramp.is_zero());
+    ramp.step(true);
+    assert!(ramp.is_one());
+}
+
+#[test]
+fn ramps_down_from_current_value() {
+    let mut ramp = LinearRamp::new(4);
+    ramp.jump(false);
+    ramp.step(true);
+    ramp.step(true);
+    let falling: Vec<_> = (0..3).map(|_| ramp.step(false)).collect();
+    assert_eq!(falling, [0.25, 0.0, 0.0]);
+    assert!(ramp.is_zero());
+}
+
+#[test]
+fn zero_ramp_samples_jumps() {
+    let mut ramp = LinearRamp::new(0);
+    ramp.jump(false);
+    ramp.step(true);
+    assert!(ramp.is_one());
+    ramp.step(false);
+    assert!(ramp.is_zero());
+}
+
+#[test]
+fn reset_jumps_on_next_step() {
+    let mut ramp = LinearRamp::new(4);
+    ramp.jump(true);
+    ramp.reset();
+    assert!(ramp.is_zero());
+    ramp.step(true);
+    assert!(ramp.is_one());
+}
@@ -1,19 +1,381 @@
 //! Abstractions for processors that effect audio.
 
-use crate::audio::{Buffer, BufferMut};
+use crate::audio::{linear_gains, Buffer, BufferMut, LinearRamp};
+use crate::events::{self, Event, Events};
+use crate::parameters::{
+    BufferState, CurveHint, Flags, IdHash, InfoRef, InternalValue, PiecewiseLinearCurvePoint,
+    States, TimedValue, TypeSpecificInfoRef,
+};
 use crate::{parameters, parameters::BufferStates, Processor};
 
+#[cfg(test)]
+mod tests;
+
+/// The parameter ID that an effect can use to read the state of its bypass parameter.
+///
+/// Plug-in formats require every effect to declare a bypass parameter. Rather than
+/// re-hardcoding the unique ID of that parameter in the processor, effects can read
+/// a switch parameter with this ID, which wrappers provide as an alias of the declared
+/// bypass parameter using [`WithBypassAlias`].
+///
+/// This ID starts with [`parameters::UNIQUE_ID_INTERNAL_PREFIX`], so no component can
+/// declare a parameter that would be shadowed by the alias - [`parameters::validate_infos`]
+/// rejects this ID, as well as any other ID with the same [`IdHash`].
+pub const BYPASS_PARAMETER: &str = "_conformal_internal_bypass";
+
+/// Wraps a set of parameter states so that [`BYPASS_PARAMETER`] refers to the
+/// effect's declared bypass parameter.
+///
+/// This works with both [`States`] and [`BufferStates`]. All other parameters are
+/// passed through unchanged.
+///
+/// # Examples
+///
+/// ```
+/// # use conformal_component::effect::{WithBypassAlias, BYPASS_PARAMETER};
+/// # use conformal_component::parameters::{StaticInfoRef, InternalValue, TypeSpecificInfoRef, StatesMap, States};
+/// let infos = vec![StaticInfoRef {
+///   title: "Bypass",
+///   short_title: "Bypass",
+///   unique_id: "my_bypass",
+///   flags: Default::default(),
+///   type_specific: TypeSpecificInfoRef::Switch { default: false },
+/// }];
+///
+/// let states = WithBypassAlias::new(StatesMap::new_defaults(infos.iter().cloned()), "my_bypass");
+/// assert_eq!(states.get_switch(BYPASS_PARAMETER), Some(false));
+///
+/// let overrides = vec![("my_bypass", InternalValue::Switch(true))].into_iter().collect();
+/// let states = WithBypassAlias::new(
+///   StatesMap::new_override_defaults(infos.iter().cloned(), &overrides),
+///   "my_bypass",
+/// );
+/// assert_eq!(states.get_switch(BYPASS_PARAMETER), Some(true));
+/// ```
+#[derive(Clone, Debug)]
+pub struct WithBypassAlias<P> {
+    states: P,
+    bypass_hash: IdHash,
+    alias_hash: IdHash,
+}
+
+impl<P> WithBypassAlias<P> {
+    /// Create a new [`WithBypassAlias`] where [`BYPASS_PARAMETER`] refers to the parameter
+    /// with the unique ID `bypass_id`.
+    pub fn new(states: P, bypass_id: &str) -> Self {
+        Self {
+            states,
+            bypass_hash: parameters::hash_id(bypass_id),
+            alias_hash: parameters::hash_id(BYPASS_PARAMETER),
+        }
+    }
+
+    fn resolve(&self, id_hash: IdHash) -> IdHash {
+        if id_hash == self.alias_hash {
+            self.bypass_hash
+        } else {
+            id_hash
+        }
+    }
+}
+
+impl<P: States> States for WithBypassAlias<P> {
+    fn get_by_hash(&self, id_hash: IdHash) -> Option<InternalValue> {
+        self.states.get_by_hash(self.resolve(id_hash))
+    }
+}
+
+impl<P: BufferStates> BufferStates for WithBypassAlias<P> {
+    fn get_by_hash(
+        &self,
+        id_hash: IdHash,
+    ) -> Option<
+        BufferState<
+            impl Iterator<Item = PiecewiseLinearCurvePoint> + Clone,
+            impl Iterator<Item = TimedValue<u32>> + Clone,
+            impl Iterator<Item = TimedValue<bool>> + Clone,
+        >,
+    > {
+        self.states.get_by_hash(self.resolve(id_hash))
+    }
+}
+
+/// The default duration of the crossfade applied by [`SoftBypass`], in seconds.
+pub const DEFAULT_SOFT_BYPASS_SECONDS: f32 = 0.01;
+
+/// Crossfades an effect's output to its dry input when it is bypassed.
+///
+/// The crossfade uses [`linear_gains`], since the dry and wet signals of an effect
+/// are usually correlated.
+///
+/// With a "hard" bypass, an effect stops producing its wet signal as soon as it is
+/// bypassed, cutting off any tail (for example, the echoes of a delay) and
+/// starting from silence when un-bypassed. With a "soft" bypass, the effect keeps
+/// processing as if it weren't bypassed, so its tail keeps ringing internally,
+/// and only the output is crossfaded to the dry signal.
+///
+/// Note that wrappers call [`Effect::process`] even while the effect is bypassed,
+/// so to implement a soft bypass, an effect should process its wet signal as
+/// normal, ignoring the bypass parameter, and then pass the per-sample state of
+/// [`BYPASS_PARAMETER`] to [`Self::process`]. Wrappers also keep reporting
+/// [`Processor::tail_samples`] to the host while the effect is bypassed, so
+/// an effect with a soft bypass should report its tail whether or not it's bypassed.
+///
+/// # Examples
+///
+/// ```
+/// # use conformal_component::audio::{Buffer, BufferData};
+/// # use conformal_component::effect::SoftBypass;
+/// let mut bypass = SoftBypass::new_with_ramp_samples(4);
+/// let dry = BufferData::new_mono(vec![1.0; 6]);
+/// let mut output = BufferData::new_mono(vec![0.0; 6]);
+/// bypass.process([false, true, true, true, true, true], &dry, &mut output);
+/// assert_eq!(output.channel(0), [0.0, 0.25, 0.5, 0.75, 1.0, 1.0]);
+/// assert!(bypass.bypassed());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct SoftBypass {
+    ramp: LinearRamp,
+}
+
+impl SoftBypass {
+    /// Creates a new soft bypass that crossfades over [`DEFAULT_SOFT_BYPASS_SECONDS`].
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn new(sampling_rate: f32) -> Self {
+        Self::new_with_ramp_samples((sampling_rate * DEFAULT_SOFT_BYPASS_SECONDS).round() as usize)
+    }
+
+    /// Creates a new soft bypass whose crossfades last `ramp_samples` samples.
+    ///
+    /// A `ramp_samples` of 0 is treated as 1, that is, no crossfade.
+    #[must_use]
+    pub fn new_with_ramp_samples(ramp_samples: usize) -> Self {
+        Self {
+            ramp: LinearRamp::new(ramp_samples),
+        }
+    }
+
+    /// Resets the crossfade, for example when processing is restarted.
+    ///
+    /// After a reset, the next sample processed jumps straight to the
+    /// bypass state without crossfading. This is also the state of a
+    /// newly created [`SoftBypass`].
+    pub fn reset(&mut self) {
+        self.ramp.reset();
+    }
+
+    /// Returns `true` if the output is entirely the dry signal.
+    #[must_use]
+    pub fn bypassed(&self) -> bool {
+        self.ramp.is_one()
+    }
+
+    /// Crossfades `output`, which should contain the effect's wet signal, towards `input`.
+    ///
+    /// `bypass` is the state of the bypass parameter for each sample, for example from
+    /// [`parameters::switch_per_sample`] with the state of [`BYPASS_PARAMETER`]. If it has fewer items than
+    /// `output` has frames, the rest of `output` is left unchanged.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `input` and `output` do not have the same channel layout or number of frames.
+    pub fn process<I: Buffer, O: BufferMut>(
+        &mut self,
+        bypass: impl IntoIterator<Item = bool>,
+        input: &I,
+        output: &mut O,
+    ) {
+        assert_eq!(input.channel_layout(), output.channel_layout());
+        assert_eq!(input.num_frames(), output.num_frames());
+        for (frame, bypass) in (0..output.num_frames()).zip(bypass) {
+            let amount = self.ramp.step(bypass);
+            if self.ramp.is_zero() {
+                continue;
+            }
+            let (wet_gain, dry_gain) = linear_gains(amount);
+            for channel in 0..output.num_channels() {
+                let dry = input.channel(channel)[frame];
+                let wet = &mut output.channel_mut(channel)[frame];
+                *wet = *wet * wet_gain + dry * dry_gain;
+            }
+        }
+    }
+}
+
+/// The unique ID of the standard freeze parameter. See [`FREEZE_INFO`] for more.
+pub const FREEZE_PARAMETER: &str = "freeze";
+
+/// Parameter info for a standard freeze switch.
+///
+/// Effects that support freezing can include this in their
+/// [`crate::Component::parameter_infos`], and pass the per-sample state of
+/// [`FREEZE_PARAMETER`] to [`Freeze::process`].
+pub const FREEZE_INFO: InfoRef<'static, &'static str> = InfoRef {
+    title: "Freeze",
+    short_title: "Freeze",
+    unique_id: FREEZE_PARAMETER,
+    flags: Flags {
+        automatable: true,
+        curve_hint: CurveHint::Auto,
+    },
+    type_specific: TypeSpecificInfoRef::Switch { default: false },
+};
+
+/// The default duration of the crossfade applied by [`Freeze`], in seconds.
+pub const DEFAULT_FREEZE_SECONDS: f32 = 0.05;
+
+/// The gains an effect should use for a single sample, as returned by [`Freeze::process`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FreezeGains {
+    amount: f32,
+}
+
+impl FreezeGains {
+    /// How frozen the effect is, from 0 (not frozen) to 1 (fully frozen).
+    #[must_use]
+    pub fn amount(&self) -> f32 {
+        self.amount
+    }
+
+    /// The gain to apply to new input before writing it into the effect's buffer.
+    ///
+    /// This is 1 when not frozen and 0 when fully frozen.
+    #[must_use]
+    pub fn input(&self) -> f32 {
+        linear_gains(self.amount).0
+    }
+
+    /// The gain to use in place of `feedback` when feeding the buffer back into itself.
+    ///
+    /// This is `feedback` when not frozen and 1 when fully frozen, so that the
+    /// existing content of the buffer loops forever without decaying.
+    #[must_use]
+    pub fn feedback(&self, feedback: f32) -> f32 {
+        let (unfrozen, frozen) = linear_gains(self.amount);
+        feedback * unfrozen + frozen
+    }
+}
+
+/// Crossfades an effect into and out of a "frozen" state that loops its buffer.
+///
+/// Effects with internal buffers, such as delays, reverbs, or granular effects,
+/// can offer a freeze mode that holds the current content of their buffers. While
+/// frozen, the effect stops writing new input into its buffers, and feeds them
+/// back into themselves at unity gain, so the existing content repeats forever.
+///
+/// Switching between these states instantly would cause clicks, so this ramps
+/// both the input gain and the feedback gain over a short crossfade. Each call to
+/// [`Self::process`] returns [`FreezeGains`] for every sample, and the effect should
+/// write `input * gains.input() + delayed * gains.feedback(feedback)` into its buffer.
+///
+/// # Examples
+///
+/// ```
+/// # use conformal_component::effect::Freeze;
+/// // A delay line that loops its content when frozen.
+/// let mut buffer = vec![0.0; 4];
+/// let mut position = 0;
+/// let mut freeze = Freeze::new_with_ramp_samples(2);
+/// let input: Vec<f32> = (1..=16).map(|i| i as f32).collect();
+/// let frozen = input.iter().map(|&i| i > 4.0);
+/// let mut output = vec![];
+/// for (gains, &input) in freeze.process(frozen).zip(&input) {
+///     let delayed = buffer[position];
+///     output.push(delayed);
+///     buffer[position] = input * gains.input() + delayed * gains.feedback(0.0);
+///     position = (position + 1) % buffer.len();
+/// }
+/// assert!(freeze.frozen());
+/// // Once frozen, the output repeats with the period of the delay line.
+/// assert_eq!(output[12..16], output[8..12]);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Freeze {
+    ramp: LinearRamp,
+}
+
+impl Freeze {
+    /// Creates a new freeze helper that crossfades over [`DEFAULT_FREEZE_SECONDS`].
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn new(sampling_rate: f32) -> Self {
+        Self::new_with_ramp_samples((sampling_rate * DEFAULT_FREEZE_SECONDS).round() as usize)
+    }
+
+    /// Creates a new freeze helper whose crossfades last `ramp_samples` samples.
+    ///
+    /// A `ramp_samples` of 0 is treated as 1, that is, no crossfade.
+    #[must_use]
+    pub fn new_with_ramp_samples(ramp_samples: usize) -> Self {
+        Self {
+            ramp: LinearRamp::new(ramp_samples),
+        }
+    }
+
+    /// Resets the crossfade, for example when processing is restarted.
+    ///
+    /// After a reset, the next sample processed jumps straight to the
+    /// freeze state without crossfading. This is also the state of a
+    /// newly created [`Freeze`].
+    pub fn reset(&mut self) {
+        self.ramp.reset();
+    }
+
+    /// Returns `true` if the effect is fully frozen.
+    #[must_use]
+    pub fn frozen(&self) -> bool {
+        self.ramp.is_one()
+    }
+
+    /// Returns the gains for each sample, given the state of the freeze switch for each sample.
+    ///
+    /// `freeze` is the state of the freeze parameter for each sample, for example from
+    /// [`parameters::switch_per_sample`] with the state of [`FREEZE_PARAMETER`]. The
+    /// crossfade advances as the returned iterator is consumed.
+    pub fn process<'a>(
+        &'a mut self,
+        freeze: impl IntoIterator<Item = bool> + 'a,
+    ) -> impl Iterator<Item = FreezeGains> + 'a {
+        freeze.into_iter().map(move |freeze| FreezeGains {
+            amount: self.ramp.step(freeze),
+        })
+    }
+}
+
 /// A trait for audio effects
 ///
 /// An effect is a processor that processes audio, and has both an input and an output
 /// audio stream. It will receive information about the current state of the parameters
 /// specified by the [`crate::Component`] that created it.
+///
+/// Effects that opt in to an event input when they are wrapped additionally receive
+/// events, such as notes and note expressions, through [`Self::handle_events`] and
+/// [`Self::process_with_events`]. This is useful for effects that react to individual
+/// notes, for example per-note filters placed after an MPE instrument.
 pub trait Effect: Processor {
     /// Handle parameter changes without processing any audio data.
     ///
     /// Must not allocate or block.
     fn handle_parameters<P: parameters::States>(&mut self, parameters: P);
 
+    /// Handle parameter changes and events without processing any audio data.
+    ///
+    /// Must not allocate or block.
+    ///
+    /// This is called instead of [`Self::handle_parameters`] for effects with an
+    /// event input.
+    ///
+    /// The default implementation ignores the events and calls [`Self::handle_parameters`].
+    fn handle_events<E: Iterator<Item = events::Data> + Clone, P: parameters::States>(
+        &mut self,
+        events: E,
+        parameters: P,
+    ) {
+        let _ = events;
+        self.handle_parameters(parameters);
+    }
+
     /// Actually process audio data.
     ///
     /// Must not allocate or block.
@@ -43,4 +405,83 @@ pub trait Effect: Processor {
         input: &I,
         output: &mut O,
     );
+
+    /// Process audio data along with the events that occur during the buffer.
+    ///
+    /// Must not allocate or block.
+    ///
+    /// This is called instead of [`Self::process`] for effects with an
+    /// event input. `events` will be sorted by `sample_offset`, and otherwise this
+    /// works the same as [`Self::process`].
+    ///
+    /// Unlike synths, effects do not receive [`crate::synth::CONTROLLER_PARAMETERS`], so
+    /// per-note state must come from the events themselves, for example
+    /// [`events::Data::NoteExpression`].
+    ///
+    /// The default implementation ignores the events and calls [`Self::process`].
+    fn process_with_events<
+        E: Iterator<Item = Event> + Clone,
+        P: BufferStates,
+        I: Buffer,
+        O: BufferMut,
+    >(
+        &mut self,
+        events: Events<E>,
+        parameters: P,
+        input: &I,
+        output: &mut O,
+    ) {
+        let _ = events;
+        self.process(parameters, input, output);
+    }
+
+    /// Process double-precision audio data.
+    ///
+    /// Must not allocate or block.
+    ///
+    /// This is called instead of [`Self::process`] when the host processes audio
+    /// in 64-bit, which only happens for processors that set
+    /// [`Processor::SUPPORTS_DOUBLE_PRECISION`]. Otherwise, this works the
+    /// same as [`Self::process`].
+    ///
+    /// Effects that support double precision must implement this. The default
+    /// implementation is never called, and fails to build for those effects.
+    fn process_f64<P: BufferStates, I: Buffer<f64>, O: BufferMut<f64>>(
+        &mut self,
+        parameters: P,
+        input: &I,
+        output: &mut O,
+    ) {
+        const {
+            assert!(
+                !Self::SUPPORTS_DOUBLE_PRECISION,
+                "Effects that support double precision must implement `process_f64`"
+            );
+        };
+        let _ = (parameters, input, output);
+    }
+
+    /// Process double-precision audio data along with the events that occur during the buffer.
+    ///
+    /// Must not allocate or block.
+    ///
+    /// This is the double-precision version of [`Self::process_with_events`], see
+    /// [`Self::process_f64`].
+    ///
+    /// The default implementation ignores the events and calls [`Self::process_f64`].
+    fn process_with_events_f64<
+        E: Iterator<Item = Event> + Clone,
+        P: BufferStates,
+        I: Buffer<f64>,
+        O: BufferMut<f64>,
+    >(
+        &mut self,
+        events: Events<E>,
+        parameters: P,
+        input: &I,
+        output: &mut O,
+    ) {
+        let _ = events;
+        self.process_f64(parameters, input, output);
+    }
 }
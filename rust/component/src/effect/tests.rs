@@ -0,0 +1,204 @@
+use super::{Freeze, FreezeGains, SoftBypass};
+use crate::audio::{all_approx_eq, Buffer, BufferData, BufferMut};
+
+const EPSILON: f32 = 1e-6;
+const RAMP_SAMPLES: usize = 8;
+
+/// A simple effect with a long tail - a one-pole lowpass with lots of feedback.
+struct Resonator {
+    state: f32,
+}
+
+impl Resonator {
+    fn process(&mut self, input: &BufferData, output: &mut BufferData) {
+        for (input, output) in input.channel(0).iter().zip(output.channel_mut(0)) {
+            self.state = self.state * 0.99 + input;
+            *output = self.state;
+        }
+    }
+}
+
+fn impulse(num_frames: usize) -> BufferData {
+    let mut data = vec![0.0; num_frames];
+    data[0] = 1.0;
+    BufferData::new_mono(data)
+}
+
+#[test]
+fn tail_is_preserved_through_bypass_toggle() {
+    let input = impulse(200);
+
+    let mut reference = BufferData::new_mono(vec![0.0; 200]);
+    Resonator { state: 0.0 }.process(&input, &mut reference);
+
+    let mut output = BufferData::new_mono(vec![0.0; 200]);
+    Resonator { state: 0.0 }.process(&input, &mut output);
+    let mut bypass = SoftBypass::new_with_ramp_samples(RAMP_SAMPLES);
+    bypass.process(
+        (0..200).map(|i| (50..100).contains(&i)),
+        &input,
+        &mut output,
+    );
+
+    // Before bypassing, we hear the effect.
+    assert!(all_approx_eq(
+        output.channel(0)[..50].iter().copied(),
+        reference.channel(0)[..50].iter().copied(),
+        EPSILON
+    ));
+    // While fully bypassed, we hear only the dry signal.
+    assert!(all_approx_eq(
+        output.channel(0)[50 + RAMP_SAMPLES..100].iter().copied(),
+        input.channel(0)[50 + RAMP_SAMPLES..100].iter().copied(),
+        EPSILON
+    ));
+    // After un-bypassing, the tail continues as if it had never been bypassed.
+    assert!(all_approx_eq(
+        output.channel(0)[100 + RAMP_SAMPLES..].iter().copied(),
+        reference.channel(0)[100 + RAMP_SAMPLES..].iter().copied(),
+        EPSILON
+    ));
+    assert!(output.channel(0)[100 + RAMP_SAMPLES] > 0.01);
+}
+
+#[test]
+fn crossfade_is_gradual() {
+    let input = BufferData::new_mono(vec![1.0; 32]);
+    let mut output = BufferData::new_mono(vec![0.0; 32]);
+    let mut bypass = SoftBypass::new_with_ramp_samples(RAMP_SAMPLES);
+    bypass.process((0..32).map(|i| (1..17).contains(&i)), &input, &mut output);
+    let max_step = output
+        .channel(0)
+        .windows(2)
+        .map(|pair| (pair[1] - pair[0]).abs())
+        .fold(0.0, f32::max);
+    assert!(max_step <= 1.0 / 8.0 + EPSILON);
+    assert!(!bypass.bypassed());
+}
+
+#[test]
+fn starts_without_crossfade() {
+    let input = BufferData::new_mono(vec![1.0; 4]);
+    let mut output = BufferData::new_mono(vec![0.0; 4]);
+    let mut bypass = SoftBypass::new_with_ramp_samples(RAMP_SAMPLES);
+    bypass.process([true; 4], &input, &mut output);
+    assert!(all_approx_eq(
+        output.channel(0).iter().copied(),
+        [1.0; 4],
+        EPSILON
+    ));
+    assert!(bypass.bypassed());
+
+    // After a reset, we jump straight back to the effect.
+    bypass.reset();
+    let mut output = BufferData::new_mono(vec![0.0; 4]);
+    bypass.process([false; 4], &input, &mut output);
+    assert!(all_approx_eq(
+        output.channel(0).iter().copied(),
+        [0.0; 4],
+        EPSILON
+    ));
+}
+
+#[test]
+#[should_panic(expected = "assertion `left == right` failed")]
+fn mismatched_layouts_panics() {
+    let input = BufferData::new_mono(vec![1.0; 4]);
+    let mut output = BufferData::new_stereo([0.0; 4], [0.0; 4]);
+    SoftBypass::new_with_ramp_samples(RAMP_SAMPLES).process([true; 4], &input, &mut output);
+}
+
+/// A simple delay line that loops its content when frozen.
+struct Looper {
+    buffer: Vec<f32>,
+    position: usize,
+}
+
+impl Looper {
+    fn new(length: usize) -> Self {
+        Self {
+            buffer: vec![0.0; length],
+            position: 0,
+        }
+    }
+
+    fn process(&mut self, gains: impl IntoIterator<Item = FreezeGains>, input: &[f32]) -> Vec<f32> {
+        gains
+            .into_iter()
+            .zip(input)
+            .map(|(gains, input)| {
+                let delayed = self.buffer[self.position];
+                self.buffer[self.position] = input * gains.input() + delayed * gains.feedback(0.5);
+                self.position = (self.position + 1) % self.buffer.len();
+                delayed
+            })
+            .collect()
+    }
+}
+
+#[test]
+fn output_is_stationary_while_frozen() {
+    const LENGTH: usize = 10;
+    let input: Vec<f32> = (0..200u16).map(|i| (f32::from(i) * 0.37).sin()).collect();
+    let mut freeze = Freeze::new_with_ramp_samples(RAMP_SAMPLES);
+    let mut looper = Looper::new(LENGTH);
+    let output = looper.process(freeze.process((0..200).map(|i| i >= 50)), &input);
+    assert!(freeze.frozen());
+
+    // Once the crossfade is done and the buffer has been fully rewritten, the
+    // output repeats exactly with the period of the delay line, ignoring the input.
+    let settled = 50 + RAMP_SAMPLES + LENGTH;
+    assert!(all_approx_eq(
+        output[settled + LENGTH..].iter().copied(),
+        output[settled..output.len() - LENGTH].iter().copied(),
+        EPSILON
+    ));
+    assert!(output[settled..].iter().any(|x| x.abs() > 0.1));
+}
+
+#[test]
+fn freeze_crossfade_is_gradual() {
+    let mut freeze = Freeze::new_with_ramp_samples(RAMP_SAMPLES);
+    let amounts: Vec<f32> = freeze
+        .process((0..32).map(|i| (1..17).contains(&i)))
+        .map(|gains| gains.amount())
+        .collect();
+    assert!(amounts
+        .windows(2)
+        .all(|pair| (pair[1] - pair[0]).abs() <= 1.0 / 8.0 + EPSILON));
+    assert!((amounts[16] - 1.0).abs() < EPSILON);
+    assert!(amounts[31].abs() < EPSILON);
+    assert!(!freeze.frozen());
+}
+
+#[test]
+fn freeze_gains_interpolate_feedback() {
+    let mut freeze = Freeze::new_with_ramp_samples(2);
+    let gains: Vec<_> = freeze.process([false, true, true]).collect();
+    assert!(all_approx_eq(
+        gains.iter().map(FreezeGains::input),
+        [1.0, 0.5, 0.0],
+        EPSILON
+    ));
+    assert!(all_approx_eq(
+        gains.iter().map(|gains| gains.feedback(0.5)),
+        [0.5, 0.75, 1.0],
+        EPSILON
+    ));
+}
+
+#[test]
+fn freeze_starts_without_crossfade() {
+    let mut freeze = Freeze::new_with_ramp_samples(RAMP_SAMPLES);
+    assert!(freeze
+        .process([true; 4])
+        .all(|gains| (gains.amount() - 1.0).abs() < EPSILON));
+    assert!(freeze.frozen());
+
+    // After a reset, we jump straight back to not frozen.
+    freeze.reset();
+    assert!(freeze
+        .process([false; 4])
+        .all(|gains| gains.amount() == 0.0));
+    assert!(!freeze.frozen());
+}
@@ -6,7 +6,7 @@ mod tests;
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 enum NoteIDInternals {
     NoteIDWithID(i32),
-    NoteIDFromPitch(u8),
+    NoteIDFromPitch { channel: u8, pitch: u8 },
     NoteIDFromChannelID(i16),
 }
 
@@ -42,8 +42,14 @@ impl NoteID {
     #[doc(hidden)]
     #[must_use]
     pub const fn from_pitch(pitch: u8) -> Self {
+        Self::from_channel_and_pitch(0, pitch)
+    }
+
+    #[doc(hidden)]
+    #[must_use]
+    pub const fn from_channel_and_pitch(channel: u8, pitch: u8) -> Self {
         Self {
-            internals: NoteIDInternals::NoteIDFromPitch(pitch),
+            internals: NoteIDInternals::NoteIDFromPitch { channel, pitch },
         }
     }
 
@@ -61,7 +67,7 @@ impl NoteID {
 pub fn to_vst_note_id(note_id: NoteID) -> i32 {
     match note_id.internals {
         NoteIDInternals::NoteIDWithID(id) => id,
-        NoteIDInternals::NoteIDFromPitch(_) | NoteIDInternals::NoteIDFromChannelID(_) => -1,
+        NoteIDInternals::NoteIDFromPitch { .. } | NoteIDInternals::NoteIDFromChannelID(_) => -1,
     }
 }
 
@@ -70,7 +76,7 @@ pub fn to_vst_note_id(note_id: NoteID) -> i32 {
 pub fn to_vst_note_channel_for_mpe_quirks(note_id: NoteID) -> i16 {
     match note_id.internals {
         NoteIDInternals::NoteIDFromChannelID(id) => id,
-        NoteIDInternals::NoteIDFromPitch(_) | NoteIDInternals::NoteIDWithID(_) => 0,
+        NoteIDInternals::NoteIDFromPitch { .. } | NoteIDInternals::NoteIDWithID(_) => 0,
     }
 }
 
@@ -88,6 +94,17 @@ pub struct NoteData {
 
     /// Microtuning of the note in cents.
     pub tuning: f32,
+
+    /// The MIDI channel the note was played on, from 0 to 15.
+    ///
+    /// Multitimbral or drum synths can use this to route notes to different
+    /// internal parts. Hosts that don't distinguish between channels will send
+    /// all notes on channel 0. Wrappers only deliver the channel to synths that
+    /// opt in to it; otherwise, this is always 0.
+    ///
+    /// Note that IDs are unique across all channels, so the same [`NoteID`] will
+    /// never be playing on two channels at once.
+    pub channel: u8,
 }
 
 /// A specific type of note expression.
@@ -136,6 +153,18 @@ pub struct NoteExpressionData {
     pub expression: NoteExpression,
 }
 
+/// Contains data about a parameter change.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ParameterChangeData {
+    /// The hash of the parameter's `unique_id`.
+    ///
+    /// Compare this against [`crate::parameters::hash_id`] of the parameters you're interested in.
+    pub id: crate::parameters::IdHash,
+
+    /// The value the parameter takes at the time of this event.
+    pub value: crate::parameters::InternalValue,
+}
+
 /// The data associated with an event, independent of the time it occurred.
 #[derive(Clone, Debug, PartialEq)]
 pub enum Data {
@@ -162,6 +191,19 @@ pub enum Data {
         /// Data associated with the note expression.
         data: NoteExpressionData,
     },
+
+    /// A parameter changed.
+    ///
+    /// Wrappers only send these to components that opt in to them. Parameter
+    /// changes are always available through the `parameters` argument of processing
+    /// calls - these events only mark _when_ the host changed them, so they can be
+    /// correlated with other events at the same sample offset.
+    ///
+    /// Parameter changes come before any other events at the same sample offset.
+    ParameterChange {
+        /// Data associated with the parameter change.
+        data: ParameterChangeData,
+    },
 }
 
 /// An event that occurred at a specific time within a buffer.
@@ -221,3 +263,57 @@ impl<I: Iterator<Item = Event>> IntoIterator for Events<I> {
         self.events
     }
 }
+
+/// Splits a buffer into contiguous ranges of samples that contain no events.
+///
+/// Each range yielded starts at the beginning of the buffer or at an event,
+/// and ends just before the next event or at the end of the buffer. This is
+/// useful for components that need to process the samples between events in
+/// one go - each range can be passed to [`crate::audio::slice_buffer`] to get
+/// the corresponding part of the buffer.
+///
+/// Multiple events at the same sample offset produce a single boundary, and
+/// no empty ranges are yielded. `num_frames` should be the buffer size used to
+/// create `events`.
+///
+/// # Examples
+///
+/// ```
+/// # use conformal_component::events::{segment_boundaries, Data, Event, Events, NoteData, NoteID};
+/// let note = NoteData {
+///     id: NoteID::from_pitch(60),
+///     pitch: 60,
+///     velocity: 1.0,
+///     tuning: 0.0,
+///     channel: 0,
+/// };
+/// let events = Events::new(
+///     [
+///         Event { sample_offset: 3, data: Data::NoteOn { data: note } },
+///         Event { sample_offset: 7, data: Data::NoteOff { data: note } },
+///     ]
+///     .into_iter(),
+///     10,
+/// )
+/// .unwrap();
+/// assert!(segment_boundaries(events, 10).eq([0..3, 3..7, 7..10]));
+/// ```
+pub fn segment_boundaries<I: Iterator<Item = Event>>(
+    events: Events<I>,
+    num_frames: usize,
+) -> impl Iterator<Item = std::ops::Range<usize>> {
+    let mut start = 0;
+    events
+        .into_iter()
+        .map(|event| event.sample_offset)
+        .chain(std::iter::once(num_frames))
+        .filter_map(move |end| {
+            if end > start {
+                let range = start..end;
+                start = end;
+                Some(range)
+            } else {
+                None
+            }
+        })
+}
@@ -1,10 +1,11 @@
-use super::{Data, Event, Events, NoteData, NoteID};
+use super::{segment_boundaries, Data, Event, Events, NoteData, NoteID};
 
 static EXAMPLE_NOTE: NoteData = NoteData {
     id: NoteID::from_pitch(60),
     pitch: 60,
     velocity: 1.0,
     tuning: 0.0,
+    channel: 0,
 };
 
 #[test]
@@ -51,3 +52,38 @@ fn out_of_bounds_events_rejected() {
 fn empty_events_accepted() {
     assert!(Events::new((&[]).iter().cloned(), 10).is_some())
 }
+
+fn note_on_at(sample_offset: usize) -> Event {
+    Event {
+        sample_offset,
+        data: Data::NoteOn { data: EXAMPLE_NOTE },
+    }
+}
+
+#[test]
+fn segment_boundaries_without_events() {
+    let events = Events::new(std::iter::empty(), 10).unwrap();
+    assert!(segment_boundaries(events, 10).eq(std::iter::once(0..10)));
+}
+
+#[test]
+fn segment_boundaries_with_event_at_start() {
+    let events = Events::new([note_on_at(0), note_on_at(4)].into_iter(), 10).unwrap();
+    assert!(segment_boundaries(events, 10).eq([0..4, 4..10]));
+}
+
+#[test]
+fn segment_boundaries_with_simultaneous_events() {
+    let events = Events::new(
+        [note_on_at(2), note_on_at(2), note_on_at(2), note_on_at(9)].into_iter(),
+        10,
+    )
+    .unwrap();
+    assert!(segment_boundaries(events, 10).eq([0..2, 2..9, 9..10]));
+}
+
+#[test]
+fn segment_boundaries_with_empty_buffer() {
+    let events = Events::new(std::iter::empty(), 0).unwrap();
+    assert_eq!(segment_boundaries(events, 0).count(), 0);
+}
@@ -17,12 +17,16 @@
 #![doc = include_str!("../docs_boilerplate.md")]
 #![doc = include_str!("../README.md")]
 
+pub mod analyzer;
 pub mod audio;
 pub mod effect;
 pub mod events;
 pub mod parameters;
 pub mod synth;
 
+#[cfg(test)]
+mod tests;
+
 #[doc(hidden)]
 pub use itertools;
 
@@ -39,8 +43,44 @@ pub enum ProcessingMode {
     Offline,
 }
 
+impl ProcessingMode {
+    /// Returns whether the component may use more lookahead than it would in realtime.
+    ///
+    /// In `Prefetch` and `Offline` modes, the host is not waiting on the output of the
+    /// processor to play it back, so the processor may choose a larger internal lookahead.
+    /// Note that the processor must still correctly account for whatever lookahead
+    /// it chooses in the latency it reports to the host.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use conformal_component::ProcessingMode;
+    /// fn lookahead_samples(mode: ProcessingMode) -> usize {
+    ///     if mode.allows_extra_lookahead() {
+    ///         1024
+    ///     } else {
+    ///         64
+    ///     }
+    /// }
+    /// assert_eq!(lookahead_samples(ProcessingMode::Realtime), 64);
+    /// assert_eq!(lookahead_samples(ProcessingMode::Prefetch), 1024);
+    /// assert_eq!(lookahead_samples(ProcessingMode::Offline), 1024);
+    /// ```
+    #[must_use]
+    pub fn allows_extra_lookahead(&self) -> bool {
+        match self {
+            ProcessingMode::Realtime => false,
+            ProcessingMode::Prefetch | ProcessingMode::Offline => true,
+        }
+    }
+}
+
 /// Information about the processing environment that the processor will run in.
+///
+/// New information may be added here over time, so wrappers create this
+/// with [`Self::new`] rather than listing every field.
 #[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
 pub struct ProcessingEnvironment {
     /// The sample rate of the audio.
     pub sampling_rate: f32,
@@ -55,6 +95,223 @@ pub struct ProcessingEnvironment {
 
     /// The processing mode that the processor will run in.
     pub processing_mode: ProcessingMode,
+
+    /// The name of the host application, if the plug-in format provides it.
+    ///
+    /// This can be used by processors to work around host-specific behavior.
+    /// This is never `Some` of an empty name - hosts that report an empty name
+    /// are treated as not providing one.
+    pub host_name: Option<String>,
+}
+
+impl ProcessingEnvironment {
+    /// Create a new [`ProcessingEnvironment`] without a host name.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use conformal_component::audio::ChannelLayout;
+    /// use conformal_component::{ProcessingEnvironment, ProcessingMode};
+    ///
+    /// let env = ProcessingEnvironment::new(
+    ///     48000.0,
+    ///     512,
+    ///     ChannelLayout::Stereo,
+    ///     ProcessingMode::Realtime,
+    /// )
+    /// .with_host_name("My Host".to_string());
+    /// assert_eq!(env.host_name.as_deref(), Some("My Host"));
+    /// ```
+    #[must_use]
+    pub const fn new(
+        sampling_rate: f32,
+        max_samples_per_process_call: usize,
+        channel_layout: audio::ChannelLayout,
+        processing_mode: ProcessingMode,
+    ) -> Self {
+        Self {
+            sampling_rate,
+            max_samples_per_process_call,
+            channel_layout,
+            processing_mode,
+            host_name: None,
+        }
+    }
+
+    /// Set the name of the host application.
+    ///
+    /// An empty `host_name` leaves [`Self::host_name`] as `None`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use conformal_component::audio::ChannelLayout;
+    /// # use conformal_component::{ProcessingEnvironment, ProcessingMode};
+    /// let env = ProcessingEnvironment::new(
+    ///     48000.0,
+    ///     512,
+    ///     ChannelLayout::Stereo,
+    ///     ProcessingMode::Realtime,
+    /// )
+    /// .with_host_name(String::new());
+    /// assert_eq!(env.host_name, None);
+    /// ```
+    #[must_use]
+    pub fn with_host_name(self, host_name: String) -> Self {
+        Self {
+            host_name: Some(host_name).filter(|name| !name.is_empty()),
+            ..self
+        }
+    }
+}
+
+/// A musical time signature, such as 3/4 or 6/8.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TimeSignature {
+    /// The number of beats in each bar, for example 3 in 3/4.
+    pub numerator: u32,
+
+    /// The note value that represents one beat, for example 4 in 3/4.
+    pub denominator: u32,
+}
+
+/// Information about the host's tempo and transport at the start of a buffer.
+///
+/// This is passed to [`Processor::set_transport`] before each processing call.
+/// Hosts may not supply all (or any) of this information, so each field is
+/// `None` when the host didn't provide it.
+///
+/// Musical positions are measured in quarter notes, regardless of the time signature.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Transport {
+    /// The tempo in beats (quarter notes) per minute.
+    pub tempo: Option<f64>,
+
+    /// The current time signature.
+    pub time_signature: Option<TimeSignature>,
+
+    /// The position of the start of the buffer in quarter notes since the start of the project.
+    pub position: Option<f64>,
+
+    /// The position of the start of the current bar in quarter notes since the start of the project.
+    ///
+    /// The position within the current bar is `position - bar_start`.
+    pub bar_start: Option<f64>,
+
+    /// Whether the host's transport is playing.
+    pub playing: Option<bool>,
+
+    /// Whether the host is recording.
+    pub recording: Option<bool>,
+}
+
+impl Transport {
+    /// Returns the length of a quarter note in samples, if the host supplied a tempo.
+    ///
+    /// This is useful for tempo-synced effects such as delays.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use conformal_component::Transport;
+    /// let transport = Transport {
+    ///     tempo: Some(120.0),
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(transport.samples_per_beat(48000.0), Some(24000.0));
+    /// assert_eq!(Transport::default().samples_per_beat(48000.0), None);
+    /// ```
+    #[must_use]
+    pub fn samples_per_beat(&self, sampling_rate: f32) -> Option<f64> {
+        self.tempo
+            .filter(|tempo| *tempo > 0.0)
+            .map(|tempo| f64::from(sampling_rate) * 60.0 / tempo)
+    }
+}
+
+/// A factory preset, see [`Component::factory_presets`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Preset {
+    /// The name of the preset, as shown in the host's program menu.
+    pub name: String,
+
+    /// The values of the parameters in this preset, keyed by unique ID.
+    ///
+    /// Parameters that aren't included are set to their defaults when the preset is selected.
+    pub values: std::collections::HashMap<String, parameters::Value>,
+}
+
+/// A problem with a component found by [`validate_component`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ComponentError {
+    /// A problem with the component's parameters or factory presets.
+    Parameter(parameters::ParameterError),
+
+    /// The component's [`synth::NoteExpressionRanges::pitch_bend`] is not
+    /// between 0 and [`synth::NoteExpressionRanges::MAX_PITCH_BEND`].
+    InvalidPitchBendRange,
+
+    /// The component's [`Component::supported_channel_layouts`] doesn't include
+    /// [`audio::ChannelLayout::Stereo`].
+    MissingStereoLayout,
+}
+
+/// Check that a component is valid.
+///
+/// Wrappers will panic when loading an invalid component, so it's a good idea to
+/// call this from a unit test for each of your components. This checks the parameters
+/// with [`Component::validate_parameters`], the factory presets with
+/// [`parameters::validate_presets`], and that the note expression ranges and
+/// channel layouts are supported.
+///
+/// # Errors
+///
+/// Returns every problem found with the component.
+///
+/// # Examples
+///
+/// ```
+/// # use conformal_component::{validate_component, Component, ProcessingEnvironment};
+/// struct MyComponent;
+///
+/// impl Component for MyComponent {
+///     type Processor = ();
+///
+///     fn create_processor(&self, _env: &ProcessingEnvironment) -> Self::Processor {}
+/// }
+///
+/// assert_eq!(validate_component(&MyComponent), Ok(()));
+/// ```
+pub fn validate_component<C: Component>(component: &C) -> Result<(), Vec<ComponentError>> {
+    let mut errors: Vec<_> = component
+        .validate_parameters()
+        .err()
+        .into_iter()
+        .chain(
+            parameters::validate_presets(
+                &component.parameter_infos(),
+                &component.factory_presets(),
+            )
+            .err(),
+        )
+        .flatten()
+        .map(ComponentError::Parameter)
+        .collect();
+    let pitch_bend = component.note_expression_ranges().pitch_bend;
+    if !(0.0..=synth::NoteExpressionRanges::MAX_PITCH_BEND).contains(&pitch_bend) {
+        errors.push(ComponentError::InvalidPitchBendRange);
+    }
+    if !component
+        .supported_channel_layouts()
+        .contains(&audio::ChannelLayout::Stereo)
+    {
+        errors.push(ComponentError::MissingStereoLayout);
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
 }
 
 /// The main plug-in abstraction in Conformal.
@@ -80,15 +337,185 @@ pub trait Component {
         Default::default()
     }
 
+    /// Check that the parameters returned by [`Self::parameter_infos`] pass
+    /// [`parameters::validate_infos`]. To check the rest of the component, use [`validate_component`].
+    ///
+    /// # Errors
+    ///
+    /// Returns every problem found with the parameters.
+    fn validate_parameters(&self) -> Result<(), Vec<parameters::ParameterError>> {
+        parameters::validate_infos(&self.parameter_infos())
+    }
+
+    /// Get the ranges of note expressions that this component responds to.
+    ///
+    /// This is only relevant for components whose processors implement [`synth::Synth`].
+    ///
+    /// This must return the same value every time it is called.
+    fn note_expression_ranges(&self) -> synth::NoteExpressionRanges {
+        Default::default()
+    }
+
+    /// Returns whether this component behaves correctly when its output is summed to mono.
+    ///
+    /// Some components, for example effects that rely on the phase relationship between
+    /// channels, only make sense when run with more than one channel. Such components
+    /// should return `false` here.
+    ///
+    /// Wrappers may use this to avoid running the component in configurations where
+    /// it would break. For example, a component returning `false` will not accept mono
+    /// bus arrangements from the host.
+    ///
+    /// The default is `true`.
+    ///
+    /// This must return the same value every time it is called.
+    fn mono_compatible(&self) -> bool {
+        true
+    }
+
+    /// Get the channel layouts that this component supports.
+    ///
+    /// Wrappers will only accept bus arrangements from the host that match one of
+    /// these layouts. Effects always use the same layout for their input and output.
+    ///
+    /// Components start out in [`audio::ChannelLayout::Stereo`] until the host
+    /// chooses a layout, so this must include `Stereo`. [`validate_component`]
+    /// checks this.
+    ///
+    /// The default is `Mono` and `Stereo` if [`Self::mono_compatible`] returns `true`,
+    /// and only `Stereo` otherwise. Surround layouts such as
+    /// [`audio::ChannelLayout::FivePointOne`] are never supported by default, so
+    /// components that handle them must include them here.
+    ///
+    /// This must return the same value every time it is called.
+    fn supported_channel_layouts(&self) -> Vec<audio::ChannelLayout> {
+        if self.mono_compatible() {
+            vec![audio::ChannelLayout::Mono, audio::ChannelLayout::Stereo]
+        } else {
+            vec![audio::ChannelLayout::Stereo]
+        }
+    }
+
+    /// Get the schema version of the state saved by this component.
+    ///
+    /// Every saved state records the schema version of the component that saved it.
+    /// Many changes to parameters can be made without changing this version, but if you
+    /// change the meaning of existing parameters (for example, changing the units of a
+    /// numeric parameter), you should increase this version and handle states saved with
+    /// older versions in [`Self::upgrade_parameters`].
+    ///
+    /// States saved with a _newer_ version than this will not be loaded, and
+    /// parameters will be reset to their defaults instead.
+    ///
+    /// States saved before schema versions existed are treated as version 0,
+    /// which is also the default.
+    ///
+    /// This must return the same value every time it is called.
+    fn state_version(&self) -> u32 {
+        0
+    }
+
+    /// Upgrade parameter values loaded from a state saved with an older [`Self::state_version`].
+    ///
+    /// `values` contains the values exactly as they were saved, keyed by the `unique_id`
+    /// they were saved with. This is called _before_ the values are checked against the
+    /// current parameters, so `values` may contain parameters that no longer exist, values
+    /// outside the range of their parameters, or values of the wrong type. This makes it
+    /// possible to move the value of a renamed parameter to its new `unique_id`, or to
+    /// convert a value whose units changed.
+    ///
+    /// Implementations should adjust `values` in place to match the current schema.
+    /// Afterwards, values for unknown parameters are ignored, and missing parameters get
+    /// their defaults. If any remaining values are invalid for the current parameters,
+    /// the state will fail to load.
+    ///
+    /// This is called separately wherever the state is loaded, and every call must
+    /// produce the same result, so this must be deterministic. Since saved states can
+    /// come from anywhere, this must not panic on unexpected values.
+    ///
+    /// The default implementation does nothing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use conformal_component::parameters::Value;
+    /// // In version 1, "gain" was a fraction from 0 to 1 called "volume",
+    /// // but in version 2 it's a percentage.
+    /// fn upgrade_parameters(saved_version: u32, values: &mut HashMap<String, Value>) {
+    ///     if saved_version < 2 {
+    ///         if let Some(Value::Numeric(volume)) = values.remove("volume") {
+    ///             values.insert("gain".to_string(), Value::Numeric(volume * 100.0));
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// let mut values = HashMap::from([("volume".to_string(), Value::Numeric(0.5))]);
+    /// upgrade_parameters(1, &mut values);
+    /// assert_eq!(values, HashMap::from([("gain".to_string(), Value::Numeric(50.0))]));
+    /// ```
+    fn upgrade_parameters(
+        &self,
+        _saved_version: u32,
+        _values: &mut std::collections::HashMap<String, parameters::Value>,
+    ) {
+    }
+
+    /// Get the factory presets of this component.
+    ///
+    /// Hosts show these presets in their program menu. Selecting a preset sets every
+    /// parameter returned by [`Self::parameter_infos`] to its value in the preset, just
+    /// as if a saved state had been loaded. The selected preset isn't remembered, so
+    /// saving and reloading the state afterwards restores the same parameter values.
+    ///
+    /// Every value must be valid for its parameter. Invalid values are ignored, and
+    /// reported by [`validate_component`].
+    /// Since a program menu with only one entry isn't useful, presets are only
+    /// shown if there are at least two.
+    ///
+    /// The default is no presets.
+    ///
+    /// This must return the same value every time it is called.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use conformal_component::Preset;
+    /// # use conformal_component::parameters::Value;
+    /// fn factory_presets() -> Vec<Preset> {
+    ///     vec![
+    ///         Preset {
+    ///             name: "Init".to_string(),
+    ///             values: HashMap::new(),
+    ///         },
+    ///         Preset {
+    ///             name: "Loud".to_string(),
+    ///             values: HashMap::from([("gain".to_string(), Value::Numeric(100.0))]),
+    ///         },
+    ///     ]
+    /// }
+    /// # assert_eq!(factory_presets().len(), 2);
+    /// ```
+    fn factory_presets(&self) -> Vec<Preset> {
+        vec![]
+    }
+
     /// Create the processor that will actually process audio.
     ///
     /// Note any state needed to process audio should be allocated here.
     fn create_processor(&self, environment: &ProcessingEnvironment) -> Self::Processor;
 }
 
+/// A tail length that tells the host the processor's output may never fall silent.
+///
+/// See [`Processor::tail_samples`].
+pub const INFINITE_TAIL_SAMPLES: usize = usize::MAX;
+
 /// A base trait for audio processors.
 ///
-/// Most audio processors should additionally implement [`effect::Effect`] or [`synth::Synth`].
+/// Most audio processors should additionally implement [`effect::Effect`], [`synth::Synth`],
+/// or [`analyzer::Analyzer`].
 pub trait Processor {
     /// Enable or disable processing. Must not allocate or block.
     ///
@@ -100,4 +527,98 @@ pub trait Processor {
     ///
     /// Note that `process` will only ever be called _after_ `set_processing(true)`
     fn set_processing(&mut self, processing: bool);
+
+    /// Perform any expensive one-time setup needed before processing.
+    ///
+    /// This is called exactly once for each processor, after it is created by
+    /// [`Component::create_processor`] and before the first call to `set_processing(true)`.
+    /// Unlike `set_processing`, this may allocate or block, so it is a good place for
+    /// precomputation that should not be repeated each time processing is toggled.
+    ///
+    /// The default implementation does nothing.
+    fn prepare(&mut self) {}
+
+    /// Whether this processor can process double-precision (64-bit) audio.
+    ///
+    /// If this is `true`, hosts may choose to process audio in 64-bit, in which
+    /// case the wrapper calls the `f64` variant of the processing method instead, for
+    /// example [`effect::Effect::process_f64`] or [`synth::Synth::process_f64`]. Hosts
+    /// may still process audio in 32-bit, so the processor must support both.
+    ///
+    /// Processors that set this to `true` must implement the `f64` variant.
+    /// Using the default implementation of it is a build error.
+    ///
+    /// The default is `false`, in which case audio is always processed in 32-bit.
+    const SUPPORTS_DOUBLE_PRECISION: bool = false;
+
+    /// The latency this processor adds to its output, in samples.
+    ///
+    /// Hosts use this to delay other tracks so they stay aligned with this
+    /// processor's output, so processors that look ahead or use linear-phase filters
+    /// should report how far their output lags their input.
+    ///
+    /// This is queried once after the processor is created by
+    /// [`Component::create_processor`] and prepared, so it may depend on the
+    /// [`ProcessingEnvironment`], but must not change for the lifetime of the processor.
+    ///
+    /// The default implementation reports no latency.
+    fn latency_samples(&self) -> usize {
+        0
+    }
+
+    /// How long this processor keeps producing output after its input falls silent, in samples.
+    ///
+    /// Hosts use this to decide how long to keep processing after the input ends, most
+    /// importantly when rendering in [`ProcessingMode::Offline`], so reverbs, delays, and
+    /// other processors that ring out should report how long their tail lasts.
+    /// Processors whose output may never decay to silence (for example, a delay with
+    /// infinite feedback) should return [`INFINITE_TAIL_SAMPLES`].
+    ///
+    /// Processors are not told when the input has ended. Instead, the host keeps
+    /// calling the processing method with silent input for the length of the tail, so
+    /// processors must keep producing their tail from silent input. In particular,
+    /// during an offline render, the echoes of a delay appear in the output even if the
+    /// rendered input is shorter than the delay time, as long as the delay reports a
+    /// tail at least as long as the delay time.
+    ///
+    /// Like [`Self::latency_samples`], this is queried once after the processor is
+    /// created and prepared, and must not change for the lifetime of the processor.
+    ///
+    /// The default implementation reports no tail.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use conformal_component::{Processor, INFINITE_TAIL_SAMPLES};
+    /// struct Delay {
+    ///     delay_samples: usize,
+    ///     freeze: bool,
+    /// }
+    ///
+    /// impl Processor for Delay {
+    ///     fn set_processing(&mut self, _processing: bool) {}
+    ///
+    ///     fn tail_samples(&self) -> usize {
+    ///         if self.freeze {
+    ///             INFINITE_TAIL_SAMPLES
+    ///         } else {
+    ///             self.delay_samples
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    fn tail_samples(&self) -> usize {
+        0
+    }
+
+    /// Receive the host's tempo and transport state. Must not allocate or block.
+    ///
+    /// This is called before every processing call with the state at the start of
+    /// that buffer, so processors that need to sync to the host (for example, tempo-synced
+    /// LFOs, delays, or arpeggiators) should store whatever they need from it.
+    ///
+    /// The default implementation does nothing.
+    fn set_transport(&mut self, transport: &Transport) {
+        let _ = transport;
+    }
 }
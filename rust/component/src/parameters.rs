@@ -24,6 +24,7 @@
 //! [`crate::effect::Effect::handle_parameters`] methods, Components can update any
 //! internal state in these methods.
 use std::{
+    collections::{hash_map::Entry, HashMap},
     ops::{Range, RangeBounds, RangeInclusive},
     string::ToString,
 };
@@ -31,6 +32,8 @@ use std::{
 mod utils;
 pub use utils::*;
 
+pub mod test_utils;
+
 #[cfg(test)]
 mod tests;
 
@@ -52,7 +55,8 @@ macro_rules! info_enum_values_doc {
     () => {
         "A list of possible values for the parameter.
 
-Note that values _must_ contain at least 2 elements."
+Note that values _must_ contain at least 2 elements, and should be distinct.
+You can check this with [`validate_enum`]."
     };
 }
 
@@ -85,6 +89,14 @@ indicates the logical units of a parmater, e.g., \"hz\""
     };
 }
 
+macro_rules! info_numeric_options_doc {
+    () => {
+        "Optional settings for the parameter, like its smoothing and scaling.
+
+Most parameters can leave these at their defaults, see [`NumericOptions`]."
+    };
+}
+
 macro_rules! info_switch_doc {
     () => {
         "Information specific to a switch parameter."
@@ -107,7 +119,7 @@ macro_rules! info_switch_default_doc {
 /// # Examples
 ///
 /// ```
-/// # use conformal_component::parameters::{TypeSpecificInfoRef};
+/// # use conformal_component::parameters::TypeSpecificInfoRef;
 /// let enum_info = TypeSpecificInfoRef::Enum {
 ///    default: 0,
 ///    values: &["A", "B", "C"],
@@ -117,6 +129,7 @@ macro_rules! info_switch_default_doc {
 ///   default: 0.0,
 ///   valid_range: 0.0..=1.0,
 ///   units: None,
+///   options: Default::default(),
 /// };
 ///
 /// let switch_info: TypeSpecificInfoRef<'static, &'static str> = TypeSpecificInfoRef::Switch {
@@ -144,6 +157,9 @@ pub enum TypeSpecificInfoRef<'a, S> {
 
         #[doc = info_numeric_units_doc!()]
         units: Option<&'a str>,
+
+        #[doc = info_numeric_options_doc!()]
+        options: NumericOptions,
     },
 
     #[doc = info_switch_doc!()]
@@ -160,7 +176,7 @@ pub enum TypeSpecificInfoRef<'a, S> {
 /// # Examples
 ///
 /// ```
-/// # use conformal_component::parameters::{TypeSpecificInfo};
+/// # use conformal_component::parameters::TypeSpecificInfo;
 /// let enum_info = TypeSpecificInfo::Enum {
 ///   default: 0,
 ///   values: vec!["A".to_string(), "B".to_string(), "C".to_string()],
@@ -169,6 +185,7 @@ pub enum TypeSpecificInfoRef<'a, S> {
 ///   default: 0.0,
 ///   valid_range: 0.0..=1.0,
 ///   units: None,
+///   options: Default::default(),
 /// };
 /// let switch_info = TypeSpecificInfo::Switch {
 ///   default: false,
@@ -195,6 +212,9 @@ pub enum TypeSpecificInfo {
 
         #[doc = info_numeric_units_doc!()]
         units: Option<String>,
+
+        #[doc = info_numeric_options_doc!()]
+        options: NumericOptions,
     },
 
     #[doc = info_switch_doc!()]
@@ -219,10 +239,12 @@ impl<'a, S: AsRef<str>> From<&'a TypeSpecificInfoRef<'a, S>> for TypeSpecificInf
                 default,
                 valid_range,
                 units,
+                options,
             } => TypeSpecificInfo::Numeric {
                 default: *default,
                 valid_range: valid_range.clone(),
                 units: (*units).map(ToString::to_string),
+                options: *options,
             },
             TypeSpecificInfoRef::Switch { default } => {
                 TypeSpecificInfo::Switch { default: *default }
@@ -242,10 +264,12 @@ impl<'a> From<&'a TypeSpecificInfo> for TypeSpecificInfoRef<'a, String> {
                 default,
                 valid_range,
                 units,
+                options,
             } => TypeSpecificInfoRef::Numeric {
                 default: *default,
                 valid_range: valid_range.clone(),
                 units: units.as_ref().map(String::as_str),
+                options: *options,
             },
             TypeSpecificInfo::Switch { default } => {
                 TypeSpecificInfoRef::Switch { default: *default }
@@ -269,11 +293,187 @@ pub struct Flags {
     /// sound good when it is change frequently, or if it is a parameter
     /// that may be confusing to users if it appeared in an automation UI.
     pub automatable: bool,
+
+    /// How the host should draw automation for this parameter.
+    ///
+    /// The default, [`CurveHint::Auto`], picks a hint based on the type of the parameter.
+    pub curve_hint: CurveHint,
 }
 
 impl Default for Flags {
     fn default() -> Self {
-        Flags { automatable: true }
+        Flags {
+            automatable: true,
+            curve_hint: CurveHint::Auto,
+        }
+    }
+}
+
+/// A hint to the host about how to draw and edit automation for a parameter.
+///
+/// Hosts usually draw automation for continuous parameters as a smooth curve, and
+/// automation for parameters with a few discrete values as steps. This hint only
+/// affects how the parameter is presented to the host - the parameter's value still
+/// has the type given by its [`TypeSpecificInfo`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CurveHint {
+    /// Pick a hint based on the type of the parameter.
+    ///
+    /// Numeric parameters are continuous, enum parameters are presented as a list
+    /// of their values, and switch parameters are toggles. This is the default.
+    #[default]
+    Auto,
+
+    /// Automation is drawn as a smooth curve.
+    ///
+    /// For enum and switch parameters, the host may send in-between values,
+    /// which are rounded to the nearest value of the parameter.
+    Continuous,
+
+    /// Automation is drawn as a series of discrete steps.
+    ///
+    /// For enum parameters, there is one step per value, but unlike [`Self::Auto`],
+    /// the host won't present the parameter as a list. For numeric parameters, there
+    /// is one step per whole number in the parameter's range, so this is best suited
+    /// to parameters whose range starts and ends at whole numbers, like a number of
+    /// voices or a transposition in semitones.
+    Stepped,
+
+    /// Automation is drawn as a toggle between two states.
+    ///
+    /// For numeric parameters, the host will only send the start and end of the
+    /// parameter's range. For enum parameters, only the first and last values.
+    Toggle,
+}
+
+/// Optional settings of a numeric parameter.
+///
+/// Most parameters only need a few of these, so set those and leave the rest
+/// at their defaults with `..Default::default()`. In a `const` or `static`,
+/// where `Default::default()` can't be called, use `..NumericOptions::DEFAULT`.
+///
+/// # Examples
+///
+/// ```
+/// # use conformal_component::parameters::{NumericOptions, Scaling};
+/// let options = NumericOptions {
+///     smoothing_ms: Some(10.0),
+///     ..Default::default()
+/// };
+/// assert_eq!(options.scaling, Scaling::Linear);
+///
+/// const CUTOFF_OPTIONS: NumericOptions = NumericOptions {
+///     scaling: Scaling::Logarithmic,
+///     ..NumericOptions::DEFAULT
+/// };
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NumericOptions {
+    /// How long changes to the parameter should be smoothed over, in milliseconds.
+    ///
+    /// If this is set, Conformal smooths changes to the parameter before passing them
+    /// to the processor, so that sudden jumps in value don't cause "zipper noise".
+    /// The smoothing is a one-pole low-pass filter with this time constant, and it is
+    /// applied on top of any ramps in the host's automation.
+    ///
+    /// If this is `None` (the default), the processor sees changes exactly as they were made.
+    pub smoothing_ms: Option<f32>,
+
+    /// How many decimal places to show when displaying the parameter's value.
+    ///
+    /// Hosts show this when displaying the value as text, for example in automation lanes.
+    /// If this is `None` (the default), the value is shown with 2 decimal places.
+    pub display_precision: Option<u8>,
+
+    /// How the parameter's value is spread over its normalized range.
+    ///
+    /// Hosts and UIs usually edit parameters with a fixed-size control, like a knob or a
+    /// slider. The scaling controls how positions on that control map to values within
+    /// `valid_range`. For example, frequencies are usually easier to edit with
+    /// [`Scaling::Logarithmic`]. The default, [`Scaling::Linear`], spreads values evenly.
+    pub scaling: Scaling,
+}
+
+impl NumericOptions {
+    /// The default options, usable in `const` and `static` items.
+    pub const DEFAULT: Self = Self {
+        smoothing_ms: None,
+        display_precision: None,
+        scaling: Scaling::Linear,
+    };
+}
+
+impl Default for NumericOptions {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// How a numeric parameter's value is spread over its normalized range.
+///
+/// Hosts deal in "normalized" values between 0 and 1, which map onto the
+/// parameter's `valid_range`. The scaling picks the shape of this mapping.
+/// It only affects how the parameter is presented to the host and the UI -
+/// the processor always receives values within `valid_range`.
+///
+/// # Examples
+///
+/// ```
+/// # use conformal_component::parameters::{NumericOptions, Scaling, TypeSpecificInfoRef};
+/// let cutoff: TypeSpecificInfoRef<'static, &'static str> = TypeSpecificInfoRef::Numeric {
+///   default: 1000.0,
+///   valid_range: 20.0..=20000.0,
+///   units: Some("Hz"),
+///   options: NumericOptions {
+///       display_precision: Some(0),
+///       scaling: Scaling::Logarithmic,
+///       ..Default::default()
+///   },
+/// };
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Scaling {
+    /// Values are spread evenly over the normalized range. This is the default.
+    #[default]
+    Linear,
+
+    /// Each step in the normalized range multiplies the value by the same amount.
+    ///
+    /// This suits parameters like frequencies or times, where the ratio between
+    /// values matters more than their difference. The start of `valid_range`
+    /// must be greater than zero.
+    Logarithmic,
+
+    /// The value is the normalized value raised to this exponent, scaled to `valid_range`.
+    ///
+    /// Exponents above 1 give more of the normalized range to the start of
+    /// `valid_range`, while exponents below 1 favor the end. The exponent must be
+    /// finite and greater than zero.
+    Power(f32),
+}
+
+impl Scaling {
+    /// Returns whether this scaling can be used with a numeric parameter's `valid_range`.
+    ///
+    /// [`Self::Logarithmic`] requires the range to start above zero, and
+    /// [`Self::Power`] requires a finite, positive exponent.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use conformal_component::parameters::Scaling;
+    /// assert!(Scaling::Logarithmic.is_valid_for(&(20.0..=20000.0)));
+    /// assert!(!Scaling::Logarithmic.is_valid_for(&(0.0..=1.0)));
+    /// assert!(Scaling::Power(2.0).is_valid_for(&(0.0..=1.0)));
+    /// assert!(!Scaling::Power(0.0).is_valid_for(&(0.0..=1.0)));
+    /// ```
+    #[must_use]
+    pub fn is_valid_for(&self, valid_range: &RangeInclusive<f32>) -> bool {
+        match self {
+            Scaling::Linear => true,
+            Scaling::Logarithmic => *valid_range.start() > 0.0,
+            Scaling::Power(exponent) => exponent.is_finite() && *exponent > 0.0,
+        }
     }
 }
 
@@ -423,6 +623,7 @@ impl<'a> From<&'a Info> for InfoRef<'a, String> {
 ///     default: 0.0,
 ///     valid_range: 0.0..=1.0,
 ///     units: None,
+///     options: Default::default(),
 ///   },
 /// };
 /// let switch_info = StaticInfoRef {
@@ -459,6 +660,379 @@ pub fn to_infos(v: &[InfoRef<'_, &'_ str>]) -> Vec<Info> {
     v.iter().map(Into::into).collect()
 }
 
+/// An error found by [`validate_enum`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EnumError {
+    /// The enum has fewer than 2 values.
+    TooFewValues,
+
+    /// The value at `index` is the same as an earlier value.
+    DuplicateValue {
+        /// The index of the repeated value.
+        index: usize,
+    },
+}
+
+/// Checks that a list of enum values is valid.
+///
+/// Enum parameters must have at least 2 values, and the values must be distinct
+/// so that they can be told apart in host automation. Conformal will panic at
+/// runtime if an enum doesn't have enough values, so it can be useful to call this
+/// from a test for each of your components to catch these mistakes earlier.
+///
+/// # Errors
+///
+/// - [`EnumError::TooFewValues`] if there are fewer than 2 values.
+/// - [`EnumError::DuplicateValue`] if any value appears more than once.
+///
+/// # Examples
+///
+/// ```
+/// # use conformal_component::parameters::{validate_enum, EnumError};
+/// assert_eq!(validate_enum(&["Sine", "Saw", "Square"]), Ok(()));
+/// assert_eq!(validate_enum(&["Sine"]), Err(EnumError::TooFewValues));
+/// assert_eq!(
+///     validate_enum(&["Sine", "Saw", "Sine"]),
+///     Err(EnumError::DuplicateValue { index: 2 })
+/// );
+/// ```
+pub fn validate_enum(values: &[&str]) -> Result<(), EnumError> {
+    if values.len() < 2 {
+        return Err(EnumError::TooFewValues);
+    }
+    for (index, value) in values.iter().enumerate() {
+        if values[..index].contains(value) {
+            return Err(EnumError::DuplicateValue { index });
+        }
+    }
+    Ok(())
+}
+
+/// A problem with a parameter found by [`validate_infos`] or [`validate_presets`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParameterError {
+    /// More than one parameter has this `unique_id`.
+    DuplicateId {
+        /// The repeated `unique_id`.
+        unique_id: String,
+    },
+
+    /// Two parameters have different `unique_id`s with the same [`IdHash`].
+    ///
+    /// Since parameters are referred to by their hash during processing,
+    /// one of these parameters must be renamed.
+    HashCollision {
+        /// The `unique_id` of the earlier parameter.
+        first: String,
+
+        /// The `unique_id` of the later parameter.
+        second: String,
+    },
+
+    /// The `unique_id` starts with [`UNIQUE_ID_INTERNAL_PREFIX`], which is reserved,
+    /// or its [`IdHash`] is the same as that of [`crate::effect::BYPASS_PARAMETER`].
+    ReservedId {
+        /// The `unique_id` of the parameter.
+        unique_id: String,
+    },
+
+    /// An enum parameter's values are invalid, as checked by [`validate_enum`].
+    InvalidEnum {
+        /// The `unique_id` of the parameter.
+        unique_id: String,
+
+        /// The problem with the values.
+        error: EnumError,
+    },
+
+    /// A numeric parameter's `valid_range` is empty or not finite.
+    InvalidRange {
+        /// The `unique_id` of the parameter.
+        unique_id: String,
+    },
+
+    /// The parameter's default is not one of its valid values.
+    DefaultOutOfRange {
+        /// The `unique_id` of the parameter.
+        unique_id: String,
+    },
+
+    /// A numeric parameter's `scaling` can't be used with its `valid_range`,
+    /// as checked by [`Scaling::is_valid_for`].
+    InvalidScaling {
+        /// The `unique_id` of the parameter.
+        unique_id: String,
+    },
+
+    /// A factory preset has a value for a parameter that doesn't exist, or
+    /// that isn't valid for that parameter, as checked by [`validate_presets`].
+    InvalidPresetValue {
+        /// The name of the preset.
+        preset: String,
+
+        /// The `unique_id` of the parameter.
+        unique_id: String,
+    },
+}
+
+fn validate_info(info: &Info) -> Option<ParameterError> {
+    let unique_id = info.unique_id.clone();
+    if info.unique_id.starts_with(UNIQUE_ID_INTERNAL_PREFIX)
+        || hash_id(&info.unique_id) == hash_id(crate::effect::BYPASS_PARAMETER)
+    {
+        return Some(ParameterError::ReservedId { unique_id });
+    }
+    match &info.type_specific {
+        TypeSpecificInfo::Enum { default, values } => {
+            let values: Vec<&str> = values.iter().map(String::as_str).collect();
+            if let Err(error) = validate_enum(&values) {
+                Some(ParameterError::InvalidEnum { unique_id, error })
+            } else if *default as usize >= values.len() {
+                Some(ParameterError::DefaultOutOfRange { unique_id })
+            } else {
+                None
+            }
+        }
+        TypeSpecificInfo::Numeric {
+            default,
+            valid_range,
+            options: NumericOptions { scaling, .. },
+            ..
+        } => {
+            if !valid_range.start().is_finite()
+                || !valid_range.end().is_finite()
+                || valid_range.start() >= valid_range.end()
+            {
+                Some(ParameterError::InvalidRange { unique_id })
+            } else if !valid_range.contains(default) {
+                Some(ParameterError::DefaultOutOfRange { unique_id })
+            } else if !scaling.is_valid_for(valid_range) {
+                Some(ParameterError::InvalidScaling { unique_id })
+            } else {
+                None
+            }
+        }
+        TypeSpecificInfo::Switch { .. } => None,
+    }
+}
+
+/// Checks that a list of parameters is valid.
+///
+/// Conformal will panic at runtime if a component's parameters are invalid, so it can
+/// be useful to call this (or [`crate::validate_component`]) from a test for
+/// each of your components to catch these mistakes earlier. This checks that:
+///
+/// - Every `unique_id` is distinct, and so is the [`IdHash`] of every `unique_id`.
+/// - No `unique_id` starts with [`UNIQUE_ID_INTERNAL_PREFIX`], or shares its
+///   [`IdHash`] with [`crate::effect::BYPASS_PARAMETER`].
+/// - Enum values pass [`validate_enum`].
+/// - Numeric ranges are finite and non-empty.
+/// - Numeric scalings can be used with their ranges.
+/// - Every default is within its parameter's valid values.
+///
+/// # Errors
+///
+/// Returns every problem found, in the order of the parameters. Each parameter
+/// reports at most one problem with its own definition.
+///
+/// # Examples
+///
+/// ```
+/// # use conformal_component::parameters::{to_infos, validate_infos, Flags, InfoRef, ParameterError, TypeSpecificInfoRef};
+/// let infos = to_infos(&[
+///     InfoRef {
+///         title: "Gain",
+///         short_title: "Gain",
+///         unique_id: "gain",
+///         flags: Flags::default(),
+///         type_specific: TypeSpecificInfoRef::Numeric {
+///             default: 150.0,
+///             valid_range: 0f32..=100.,
+///             units: Some("%"),
+///             options: Default::default(),
+///         },
+///     },
+///     InfoRef {
+///         title: "Bypass",
+///         short_title: "Bypass",
+///         unique_id: "bypass",
+///         flags: Flags::default(),
+///         type_specific: TypeSpecificInfoRef::Switch { default: false },
+///     },
+/// ]);
+/// assert_eq!(
+///     validate_infos(&infos),
+///     Err(vec![ParameterError::DefaultOutOfRange {
+///         unique_id: "gain".to_string()
+///     }])
+/// );
+/// ```
+pub fn validate_infos(infos: &[Info]) -> Result<(), Vec<ParameterError>> {
+    let mut errors = Vec::new();
+    let mut seen: HashMap<IdHash, &str> = HashMap::new();
+    for info in infos {
+        match seen.entry(hash_id(&info.unique_id)) {
+            Entry::Occupied(entry) if *entry.get() == info.unique_id => {
+                errors.push(ParameterError::DuplicateId {
+                    unique_id: info.unique_id.clone(),
+                });
+            }
+            Entry::Occupied(entry) => {
+                errors.push(ParameterError::HashCollision {
+                    first: (*entry.get()).to_string(),
+                    second: info.unique_id.clone(),
+                });
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(&info.unique_id);
+            }
+        }
+        errors.extend(validate_info(info));
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Checks that every value in a list of factory presets is valid.
+///
+/// Each value must be for a parameter in `infos`, and must be a valid value for
+/// that parameter. Wrappers ignore invalid values when a preset is selected.
+///
+/// # Errors
+///
+/// Returns every invalid value, in the order of the presets. Within a preset,
+/// values are reported in order of `unique_id`.
+///
+/// # Examples
+///
+/// ```
+/// # use std::collections::HashMap;
+/// # use conformal_component::Preset;
+/// # use conformal_component::parameters::{to_infos, validate_presets, Flags, InfoRef, ParameterError, TypeSpecificInfoRef, Value};
+/// let infos = to_infos(&[InfoRef {
+///     title: "Bypass",
+///     short_title: "Bypass",
+///     unique_id: "bypass",
+///     flags: Flags::default(),
+///     type_specific: TypeSpecificInfoRef::Switch { default: false },
+/// }]);
+/// let presets = [Preset {
+///     name: "Loud".to_string(),
+///     values: HashMap::from([("gain".to_string(), Value::Numeric(100.0))]),
+/// }];
+/// assert_eq!(
+///     validate_presets(&infos, &presets),
+///     Err(vec![ParameterError::InvalidPresetValue {
+///         preset: "Loud".to_string(),
+///         unique_id: "gain".to_string(),
+///     }])
+/// );
+/// ```
+pub fn validate_presets(
+    infos: &[Info],
+    presets: &[crate::Preset],
+) -> Result<(), Vec<ParameterError>> {
+    let mut errors = Vec::new();
+    for preset in presets {
+        let mut values: Vec<_> = preset.values.iter().collect();
+        values.sort_by_key(|(unique_id, _)| *unique_id);
+        for (unique_id, value) in values {
+            let valid = infos
+                .iter()
+                .find(|info| info.unique_id == *unique_id)
+                .is_some_and(|info| clamp_to_info(value, info) == *value);
+            if !valid {
+                errors.push(ParameterError::InvalidPresetValue {
+                    preset: preset.name.clone(),
+                    unique_id: unique_id.clone(),
+                });
+            }
+        }
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Brings a value into the valid range of a parameter.
+///
+/// This is useful when a value was computed by arithmetic or entered by the user,
+/// and might not be valid for the parameter:
+///
+/// - Numeric values are clamped to the parameter's `valid_range`.
+/// - Enum values that aren't one of the parameter's `values` are replaced
+///   by the parameter's default.
+/// - Switch values are always valid, so they are passed through.
+///
+/// If `value` is a different type than the parameter, the parameter's default is returned.
+///
+/// # Examples
+///
+/// ```
+/// # use conformal_component::parameters::{clamp_to_info, Flags, Info, TypeSpecificInfo, Value};
+/// let info = Info {
+///     unique_id: "gain".to_string(),
+///     title: "Gain".to_string(),
+///     short_title: "Gain".to_string(),
+///     flags: Flags::default(),
+///     type_specific: TypeSpecificInfo::Numeric {
+///         default: 0.0,
+///         valid_range: 0.0..=1.0,
+///         units: None,
+///         options: Default::default(),
+///     },
+/// };
+/// assert_eq!(clamp_to_info(&Value::Numeric(1.5), &info), Value::Numeric(1.0));
+/// assert_eq!(clamp_to_info(&Value::Numeric(0.5), &info), Value::Numeric(0.5));
+/// ```
+#[must_use]
+pub fn clamp_to_info(value: &Value, info: &Info) -> Value {
+    match (value, &info.type_specific) {
+        (Value::Numeric(v), TypeSpecificInfo::Numeric { valid_range, .. }) => {
+            Value::Numeric(v.clamp(*valid_range.start(), *valid_range.end()))
+        }
+        (Value::Enum(v), TypeSpecificInfo::Enum { values, .. }) if values.contains(v) => {
+            Value::Enum(v.clone())
+        }
+        (Value::Switch(v), TypeSpecificInfo::Switch { .. }) => Value::Switch(*v),
+        _ => default_value(info),
+    }
+}
+
+/// Returns the default value of a parameter.
+///
+/// # Examples
+///
+/// ```
+/// # use conformal_component::parameters::{default_value, Flags, Info, TypeSpecificInfo, Value};
+/// let info = Info {
+///     unique_id: "shape".to_string(),
+///     title: "Shape".to_string(),
+///     short_title: "Shape".to_string(),
+///     flags: Flags::default(),
+///     type_specific: TypeSpecificInfo::Enum {
+///         default: 1,
+///         values: vec!["Sine".to_string(), "Saw".to_string()],
+///     },
+/// };
+/// assert_eq!(default_value(&info), Value::Enum("Saw".to_string()));
+/// ```
+#[must_use]
+pub fn default_value(info: &Info) -> Value {
+    match &info.type_specific {
+        TypeSpecificInfo::Numeric { default, .. } => Value::Numeric(*default),
+        TypeSpecificInfo::Enum { default, values } => {
+            Value::Enum(values[*default as usize].clone())
+        }
+        TypeSpecificInfo::Switch { default } => Value::Switch(*default),
+    }
+}
+
 /// A numeric hash of a parameter's ID.
 ///
 /// In contexts where performance is critical, we refer to parameters
@@ -990,6 +1564,73 @@ impl<I: IntoIterator<Item = PiecewiseLinearCurvePoint>> NumericBufferState<I> {
             NumericBufferState::PiecewiseLinear(v) => v.points.into_iter().next().unwrap().value,
         }
     }
+
+    /// Get the value of the parameter at a given sample of the buffer.
+    ///
+    /// This is the same value that [`numeric_per_sample`] would yield for that sample,
+    /// but is cheaper to compute if you only need a single value. If `sample_index`
+    /// is past the end of the buffer, this returns the value at the end of the buffer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use conformal_component::parameters::{NumericBufferState, PiecewiseLinearCurve, PiecewiseLinearCurvePoint};
+    /// let state = NumericBufferState::PiecewiseLinear(PiecewiseLinearCurve::new(
+    ///   vec![PiecewiseLinearCurvePoint { sample_offset: 0, value: 0.0 },
+    ///       PiecewiseLinearCurvePoint { sample_offset: 100, value: 1.0 }],
+    ///   128,
+    ///   0.0..=1.0,
+    /// ).unwrap());
+    /// assert_eq!(state.clone().value_at(50), 0.5);
+    /// assert_eq!(state.value_at(120), 1.0);
+    /// ```
+    #[allow(clippy::missing_panics_doc)] // Only panics when invariants are broken.
+    pub fn value_at(self, sample_index: usize) -> f32 {
+        match self {
+            NumericBufferState::Constant(v) => v,
+            NumericBufferState::PiecewiseLinear(v) => {
+                let mut points = v.points.into_iter();
+                let mut last = points.next().unwrap();
+                for next in points {
+                    if next.sample_offset > sample_index {
+                        // Note this must match the interpolation in `numeric_per_sample`.
+                        #[allow(clippy::cast_precision_loss)]
+                        {
+                            let delta_per_sample = (next.value - last.value)
+                                / ((next.sample_offset - last.sample_offset) as f32);
+                            return last.value
+                                + delta_per_sample * ((sample_index - last.sample_offset) as f32);
+                        }
+                    }
+                    last = next;
+                }
+                last.value
+            }
+        }
+    }
+
+    /// Get the value of the parameter at the last sample of the buffer.
+    ///
+    /// This is useful for components that only update their state once per buffer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use conformal_component::parameters::{NumericBufferState, PiecewiseLinearCurve, PiecewiseLinearCurvePoint};
+    /// assert_eq!(NumericBufferState::PiecewiseLinear(PiecewiseLinearCurve::new(
+    ///   vec![PiecewiseLinearCurvePoint { sample_offset: 0, value: 0.5 },
+    ///       PiecewiseLinearCurvePoint { sample_offset: 100, value: 1.0 }],
+    ///   128,
+    ///   0.0..=1.0,
+    /// ).unwrap()).value_at_end_of_buffer(), 1.0);
+    /// ```
+    #[allow(clippy::missing_panics_doc)] // Only panics when invariants are broken.
+    pub fn value_at_end_of_buffer(self) -> f32 {
+        match self {
+            NumericBufferState::Constant(v) => v,
+            NumericBufferState::PiecewiseLinear(v) => v.points.into_iter().last().unwrap().value,
+        }
+    }
 }
 
 /// Represents the state of an enum value across a buffer
@@ -1155,6 +1796,28 @@ pub trait BufferStates {
         self.numeric_by_hash(hash_id(unique_id))
     }
 
+    /// Get the value of a numeric parameter at a given sample of the buffer.
+    ///
+    /// This is the same value that [`crate::pzip`] would yield for that sample.
+    /// See [`NumericBufferState::value_at`] for more details.
+    ///
+    /// If there is no parameter with the given ID, or the parameter is not numeric,
+    /// this will return `None`.
+    fn numeric_at(&self, unique_id: &str, sample_index: usize) -> Option<f32> {
+        Some(self.get_numeric(unique_id)?.value_at(sample_index))
+    }
+
+    /// Get the value of a numeric parameter at the last sample of the buffer.
+    ///
+    /// This is cheaper than iterating over every sample if you only need the
+    /// final value, for example to update a filter once for the next buffer.
+    ///
+    /// If there is no parameter with the given ID, or the parameter is not numeric,
+    /// this will return `None`.
+    fn numeric_last(&self, unique_id: &str) -> Option<f32> {
+        Some(self.get_numeric(unique_id)?.value_at_end_of_buffer())
+    }
+
     /// Get the state of an enum parameter by it's hashed unique ID.
     ///
     /// You can get the hash of a unique ID using [`hash_id`].
@@ -1208,4 +1871,30 @@ pub trait BufferStates {
     ) -> Option<SwitchBufferState<impl Iterator<Item = TimedValue<bool>> + Clone>> {
         self.switch_by_hash(hash_id(unique_id))
     }
+
+    /// Create a view of these states where `f` is applied to every value of the
+    /// numeric parameter with the given unique ID.
+    ///
+    /// See [`MapNumeric`] for more details.
+    fn map_numeric<F: Fn(f32) -> f32>(self, unique_id: &str, f: F) -> MapNumeric<Self, F>
+    where
+        Self: Sized,
+    {
+        MapNumeric::new(self, unique_id, f)
+    }
+}
+
+impl<B: BufferStates + ?Sized> BufferStates for &B {
+    fn get_by_hash(
+        &self,
+        id_hash: IdHash,
+    ) -> Option<
+        BufferState<
+            impl Iterator<Item = PiecewiseLinearCurvePoint> + Clone,
+            impl Iterator<Item = TimedValue<u32>> + Clone,
+            impl Iterator<Item = TimedValue<bool>> + Clone,
+        >,
+    > {
+        (**self).get_by_hash(id_hash)
+    }
 }
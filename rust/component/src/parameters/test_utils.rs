@@ -0,0 +1,141 @@
+//! Utilities for testing how components use their parameters.
+
+use std::cell::RefCell;
+
+use super::{hash_id, BufferState, BufferStates, IdHash, PiecewiseLinearCurvePoint, TimedValue};
+
+#[cfg(test)]
+mod tests;
+
+/// A single read of a parameter recorded by [`RecordingStates`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Read {
+    /// The hashed unique ID of the parameter that was read.
+    ///
+    /// You can get the hash of a unique ID using [`hash_id`].
+    pub id: IdHash,
+
+    /// The sample index that was read, if the read was for a single sample.
+    ///
+    /// This is `None` for reads of the state of the whole buffer, such as
+    /// [`BufferStates::get_numeric`] or the [`crate::pzip`] macro.
+    pub sample_index: Option<usize>,
+}
+
+/// A wrapper around a [`BufferStates`] that records every parameter read.
+///
+/// This is useful in tests to check that a processor consults the parameters
+/// it should. Reads of the whole buffer's state (for example through [`crate::pzip`])
+/// are recorded once per lookup, and single-sample reads through
+/// [`BufferStates::numeric_at`] are recorded along with their sample index.
+///
+/// Note that this records reads as they happen, so it should only be used in tests.
+///
+/// # Examples
+///
+/// ```
+/// # use conformal_component::pzip;
+/// # use conformal_component::parameters::{ConstantBufferStates, BufferStates, StaticInfoRef, TypeSpecificInfoRef};
+/// # use conformal_component::parameters::test_utils::RecordingStates;
+/// let params = RecordingStates::new(ConstantBufferStates::new_defaults(vec![StaticInfoRef {
+///     title: "Gain",
+///     short_title: "Gain",
+///     unique_id: "gain",
+///     flags: Default::default(),
+///     type_specific: TypeSpecificInfoRef::Numeric {
+///         default: 0.5,
+///         valid_range: 0.0..=1.0,
+///         units: None,
+///         options: Default::default(),
+///     },
+/// }]));
+///
+/// let samples: Vec<_> = pzip!(params[numeric "gain"]).take(4).collect();
+/// assert_eq!(params.buffer_reads("gain"), 1);
+///
+/// for sample_index in 0..4 {
+///     params.numeric_at("gain", sample_index);
+/// }
+/// assert_eq!(params.sample_reads("gain"), vec![0, 1, 2, 3]);
+/// ```
+#[derive(Debug)]
+pub struct RecordingStates<B> {
+    states: B,
+    reads: RefCell<Vec<Read>>,
+}
+
+impl<B> RecordingStates<B> {
+    /// Create a new [`RecordingStates`] that records reads of `states`.
+    pub fn new(states: B) -> Self {
+        Self {
+            states,
+            reads: Default::default(),
+        }
+    }
+
+    /// Returns all recorded reads, in the order they happened.
+    #[must_use]
+    pub fn reads(&self) -> Vec<Read> {
+        self.reads.borrow().clone()
+    }
+
+    /// Returns the number of times the state of the whole buffer was read for
+    /// the parameter with the given unique ID.
+    #[must_use]
+    pub fn buffer_reads(&self, unique_id: &str) -> usize {
+        let id = hash_id(unique_id);
+        self.reads
+            .borrow()
+            .iter()
+            .filter(|read| read.id == id && read.sample_index.is_none())
+            .count()
+    }
+
+    /// Returns the sample indices of every single-sample read of the parameter
+    /// with the given unique ID, in the order they happened.
+    #[must_use]
+    pub fn sample_reads(&self, unique_id: &str) -> Vec<usize> {
+        let id = hash_id(unique_id);
+        self.reads
+            .borrow()
+            .iter()
+            .filter(|read| read.id == id)
+            .filter_map(|read| read.sample_index)
+            .collect()
+    }
+
+    /// Forget all recorded reads, for example between calls to `process`.
+    pub fn clear(&self) {
+        self.reads.borrow_mut().clear();
+    }
+
+    /// Returns the wrapped states.
+    pub fn into_inner(self) -> B {
+        self.states
+    }
+
+    fn record(&self, id: IdHash, sample_index: Option<usize>) {
+        self.reads.borrow_mut().push(Read { id, sample_index });
+    }
+}
+
+impl<B: BufferStates> BufferStates for RecordingStates<B> {
+    fn get_by_hash(
+        &self,
+        id_hash: IdHash,
+    ) -> Option<
+        BufferState<
+            impl Iterator<Item = PiecewiseLinearCurvePoint> + Clone,
+            impl Iterator<Item = TimedValue<u32>> + Clone,
+            impl Iterator<Item = TimedValue<bool>> + Clone,
+        >,
+    > {
+        self.record(id_hash, None);
+        self.states.get_by_hash(id_hash)
+    }
+
+    fn numeric_at(&self, unique_id: &str, sample_index: usize) -> Option<f32> {
+        self.record(hash_id(unique_id), Some(sample_index));
+        self.states.numeric_at(unique_id, sample_index)
+    }
+}
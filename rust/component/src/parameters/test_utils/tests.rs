@@ -0,0 +1,113 @@
+use super::{Read, RecordingStates};
+use crate::audio::all_approx_eq;
+use crate::parameters::{
+    hash_id, numeric_per_sample, switch_per_sample, BufferStates, ConstantBufferStates,
+    NumericOptions, StaticInfoRef, TypeSpecificInfoRef,
+};
+
+// Mirrors the parameters of a typical gain effect.
+static PARAMETERS: [StaticInfoRef; 2] = [
+    StaticInfoRef {
+        title: "Gain",
+        short_title: "Gain",
+        unique_id: "gain",
+        flags: crate::parameters::Flags {
+            automatable: true,
+            curve_hint: crate::parameters::CurveHint::Auto,
+        },
+        type_specific: TypeSpecificInfoRef::Numeric {
+            default: 50.0,
+            valid_range: 0.0..=100.0,
+            units: Some("%"),
+            options: NumericOptions::DEFAULT,
+        },
+    },
+    StaticInfoRef {
+        title: "Bypass",
+        short_title: "Bypass",
+        unique_id: "bypass",
+        flags: crate::parameters::Flags {
+            automatable: true,
+            curve_hint: crate::parameters::CurveHint::Auto,
+        },
+        type_specific: TypeSpecificInfoRef::Switch { default: false },
+    },
+];
+
+fn gain_effect(input: &[f32], params: &impl BufferStates) -> Vec<f32> {
+    // This reads the parameters the same way `pzip!(params[numeric "gain", switch "bypass"])` does.
+    input
+        .iter()
+        .zip(numeric_per_sample(params.get_numeric("gain").unwrap()))
+        .zip(switch_per_sample(params.get_switch("bypass").unwrap()))
+        .map(|((input, gain), bypass)| input * (if bypass { 1.0 } else { gain / 100.0 }))
+        .collect()
+}
+
+fn gain_effect_per_sample(input: &[f32], params: &impl BufferStates) -> Vec<f32> {
+    input
+        .iter()
+        .enumerate()
+        .map(|(sample_index, input)| {
+            input * params.numeric_at("gain", sample_index).unwrap() / 100.0
+        })
+        .collect()
+}
+
+#[test]
+fn records_whole_buffer_reads_once_per_lookup() {
+    let params = RecordingStates::new(ConstantBufferStates::new_defaults(
+        PARAMETERS.iter().cloned(),
+    ));
+    let output = gain_effect(&[1.0; 8], &params);
+    assert!(all_approx_eq(output, [0.5; 8], 1e-6));
+    assert_eq!(params.buffer_reads("gain"), 1);
+    assert_eq!(params.buffer_reads("bypass"), 1);
+    assert_eq!(params.buffer_reads("other"), 0);
+    assert_eq!(
+        params.reads(),
+        vec![
+            Read {
+                id: hash_id("gain"),
+                sample_index: None
+            },
+            Read {
+                id: hash_id("bypass"),
+                sample_index: None
+            },
+        ]
+    );
+}
+
+#[test]
+fn records_sample_reads_once_per_sample() {
+    let params = RecordingStates::new(ConstantBufferStates::new_defaults(
+        PARAMETERS.iter().cloned(),
+    ));
+    let output = gain_effect_per_sample(&[1.0; 4], &params);
+    assert!(all_approx_eq(output, [0.5; 4], 1e-6));
+    assert_eq!(params.sample_reads("gain"), vec![0, 1, 2, 3]);
+    assert_eq!(params.buffer_reads("gain"), 0);
+    assert!(params.sample_reads("bypass").is_empty());
+}
+
+#[test]
+fn clear_forgets_reads() {
+    let params = RecordingStates::new(ConstantBufferStates::new_defaults(
+        PARAMETERS.iter().cloned(),
+    ));
+    gain_effect(&[1.0; 4], &params);
+    params.clear();
+    assert!(params.reads().is_empty());
+    gain_effect(&[1.0; 4], &params);
+    assert_eq!(params.buffer_reads("gain"), 1);
+}
+
+#[test]
+fn passes_through_missing_parameters() {
+    let params = RecordingStates::new(ConstantBufferStates::new_defaults(
+        PARAMETERS.iter().cloned(),
+    ));
+    assert!(params.get_numeric("missing").is_none());
+    assert_eq!(params.buffer_reads("missing"), 1);
+}
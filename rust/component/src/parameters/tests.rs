@@ -1,6 +1,11 @@
+use std::collections::HashMap;
+
 use super::{
-    hash_id, IdHash, InternalValue, PiecewiseLinearCurve, PiecewiseLinearCurvePoint, States,
+    clamp_to_info, hash_id, validate_enum, validate_infos, validate_presets, EnumError, Flags,
+    IdHash, Info, InternalValue, NumericOptions, ParameterError, PiecewiseLinearCurve,
+    PiecewiseLinearCurvePoint, Scaling, States, TypeSpecificInfo, Value, UNIQUE_ID_INTERNAL_PREFIX,
 };
+use crate::Preset;
 
 struct MyState {}
 impl States for MyState {
@@ -189,3 +194,381 @@ fn curve_multiple_points_same_sample_rejected() {
     )
     .is_none())
 }
+
+#[test]
+fn validate_enum_accepts_distinct_values() {
+    assert_eq!(validate_enum(&["a", "b"]), Ok(()));
+}
+
+#[test]
+fn validate_enum_rejects_too_few_values() {
+    assert_eq!(validate_enum(&[]), Err(EnumError::TooFewValues));
+    assert_eq!(validate_enum(&["a"]), Err(EnumError::TooFewValues));
+}
+
+#[test]
+fn validate_enum_rejects_duplicates() {
+    assert_eq!(
+        validate_enum(&["a", "b", "c", "b"]),
+        Err(EnumError::DuplicateValue { index: 3 })
+    );
+}
+
+fn clamp_test_info(type_specific: TypeSpecificInfo) -> Info {
+    Info {
+        unique_id: "test".to_string(),
+        title: "Test".to_string(),
+        short_title: "Test".to_string(),
+        flags: Flags::default(),
+        type_specific,
+    }
+}
+
+#[test]
+fn clamp_to_info_clamps_out_of_range_numeric() {
+    let info = clamp_test_info(TypeSpecificInfo::Numeric {
+        default: 0.0,
+        valid_range: -1.0..=1.0,
+        units: None,
+        options: Default::default(),
+    });
+    assert_eq!(
+        clamp_to_info(&Value::Numeric(3.0), &info),
+        Value::Numeric(1.0)
+    );
+    assert_eq!(
+        clamp_to_info(&Value::Numeric(-3.0), &info),
+        Value::Numeric(-1.0)
+    );
+}
+
+#[test]
+fn clamp_to_info_resets_invalid_enum_to_default() {
+    let info = clamp_test_info(TypeSpecificInfo::Enum {
+        default: 1,
+        values: vec!["Sine".to_string(), "Saw".to_string()],
+    });
+    assert_eq!(
+        clamp_to_info(&Value::Enum("Square".to_string()), &info),
+        Value::Enum("Saw".to_string())
+    );
+}
+
+#[test]
+fn clamp_to_info_passes_through_valid_values() {
+    let numeric = clamp_test_info(TypeSpecificInfo::Numeric {
+        default: 0.0,
+        valid_range: -1.0..=1.0,
+        units: None,
+        options: Default::default(),
+    });
+    let enum_info = clamp_test_info(TypeSpecificInfo::Enum {
+        default: 1,
+        values: vec!["Sine".to_string(), "Saw".to_string()],
+    });
+    let switch = clamp_test_info(TypeSpecificInfo::Switch { default: false });
+    assert_eq!(
+        clamp_to_info(&Value::Numeric(0.5), &numeric),
+        Value::Numeric(0.5)
+    );
+    assert_eq!(
+        clamp_to_info(&Value::Enum("Sine".to_string()), &enum_info),
+        Value::Enum("Sine".to_string())
+    );
+    assert_eq!(
+        clamp_to_info(&Value::Switch(true), &switch),
+        Value::Switch(true)
+    );
+}
+
+#[test]
+fn clamp_to_info_resets_wrong_type_to_default() {
+    let info = clamp_test_info(TypeSpecificInfo::Switch { default: true });
+    assert_eq!(
+        clamp_to_info(&Value::Numeric(0.0), &info),
+        Value::Switch(true)
+    );
+}
+
+fn validate_test_info(unique_id: &str, type_specific: TypeSpecificInfo) -> Info {
+    Info {
+        unique_id: unique_id.to_string(),
+        ..clamp_test_info(type_specific)
+    }
+}
+
+fn test_numeric(default: f32, valid_range: std::ops::RangeInclusive<f32>) -> TypeSpecificInfo {
+    test_scaled(default, valid_range, Scaling::Linear)
+}
+
+fn test_scaled(
+    default: f32,
+    valid_range: std::ops::RangeInclusive<f32>,
+    scaling: Scaling,
+) -> TypeSpecificInfo {
+    TypeSpecificInfo::Numeric {
+        default,
+        valid_range,
+        units: None,
+        options: NumericOptions {
+            scaling,
+            ..Default::default()
+        },
+    }
+}
+
+fn test_enum(default: u32, values: &[&str]) -> TypeSpecificInfo {
+    TypeSpecificInfo::Enum {
+        default,
+        values: values.iter().map(ToString::to_string).collect(),
+    }
+}
+
+#[test]
+fn validate_infos_accepts_valid_parameters() {
+    assert_eq!(
+        validate_infos(&[
+            validate_test_info("numeric", test_numeric(0.5, 0.0..=1.0)),
+            validate_test_info("enum", test_enum(1, &["a", "b"])),
+            validate_test_info("switch", TypeSpecificInfo::Switch { default: true }),
+        ]),
+        Ok(())
+    );
+}
+
+#[test]
+fn validate_infos_rejects_duplicate_ids() {
+    assert_eq!(
+        validate_infos(&[
+            validate_test_info("a", TypeSpecificInfo::Switch { default: true }),
+            validate_test_info("b", TypeSpecificInfo::Switch { default: true }),
+            validate_test_info("a", test_numeric(0.5, 0.0..=1.0)),
+        ]),
+        Err(vec![ParameterError::DuplicateId {
+            unique_id: "a".to_string()
+        }])
+    );
+}
+
+#[test]
+fn validate_infos_rejects_hash_collisions() {
+    // Find two distinct ids with the same hash.
+    let mut seen = std::collections::HashMap::new();
+    let (first, second) = (0..)
+        .map(|i| format!("param_{i}"))
+        .find_map(|id| {
+            seen.insert(hash_id(&id), id.clone())
+                .map(|first| (first, id))
+        })
+        .unwrap();
+    assert_eq!(
+        validate_infos(&[
+            validate_test_info(&first, TypeSpecificInfo::Switch { default: true }),
+            validate_test_info(&second, TypeSpecificInfo::Switch { default: true }),
+        ]),
+        Err(vec![ParameterError::HashCollision { first, second }])
+    );
+}
+
+#[test]
+fn validate_infos_rejects_reserved_ids() {
+    let unique_id = format!("{UNIQUE_ID_INTERNAL_PREFIX}test");
+    assert_eq!(
+        validate_infos(&[validate_test_info(
+            &unique_id,
+            TypeSpecificInfo::Switch { default: true }
+        )]),
+        Err(vec![ParameterError::ReservedId { unique_id }])
+    );
+}
+
+#[test]
+fn validate_infos_rejects_bypass_alias() {
+    assert_eq!(
+        validate_infos(&[validate_test_info(
+            crate::effect::BYPASS_PARAMETER,
+            TypeSpecificInfo::Switch { default: false }
+        )]),
+        Err(vec![ParameterError::ReservedId {
+            unique_id: crate::effect::BYPASS_PARAMETER.to_string()
+        }])
+    );
+}
+
+#[test]
+fn validate_infos_rejects_invalid_enums() {
+    assert_eq!(
+        validate_infos(&[
+            validate_test_info("few", test_enum(0, &["a"])),
+            validate_test_info("duplicate", test_enum(0, &["a", "b", "a"])),
+        ]),
+        Err(vec![
+            ParameterError::InvalidEnum {
+                unique_id: "few".to_string(),
+                error: EnumError::TooFewValues
+            },
+            ParameterError::InvalidEnum {
+                unique_id: "duplicate".to_string(),
+                error: EnumError::DuplicateValue { index: 2 }
+            },
+        ])
+    );
+}
+
+#[test]
+fn validate_infos_rejects_invalid_ranges() {
+    assert_eq!(
+        validate_infos(&[
+            validate_test_info("empty", test_numeric(1.0, 1.0..=1.0)),
+            validate_test_info("reversed", test_numeric(0.5, 1.0..=0.0)),
+            validate_test_info("infinite", test_numeric(0.5, 0.0..=f32::INFINITY)),
+        ]),
+        Err(vec![
+            ParameterError::InvalidRange {
+                unique_id: "empty".to_string()
+            },
+            ParameterError::InvalidRange {
+                unique_id: "reversed".to_string()
+            },
+            ParameterError::InvalidRange {
+                unique_id: "infinite".to_string()
+            },
+        ])
+    );
+}
+
+#[test]
+fn validate_infos_rejects_out_of_range_defaults() {
+    assert_eq!(
+        validate_infos(&[
+            validate_test_info("numeric", test_numeric(2.0, 0.0..=1.0)),
+            validate_test_info("nan", test_numeric(f32::NAN, 0.0..=1.0)),
+            validate_test_info("enum", test_enum(2, &["a", "b"])),
+        ]),
+        Err(vec![
+            ParameterError::DefaultOutOfRange {
+                unique_id: "numeric".to_string()
+            },
+            ParameterError::DefaultOutOfRange {
+                unique_id: "nan".to_string()
+            },
+            ParameterError::DefaultOutOfRange {
+                unique_id: "enum".to_string()
+            },
+        ])
+    );
+}
+
+#[test]
+fn validate_infos_accepts_valid_scalings() {
+    assert_eq!(
+        validate_infos(&[
+            validate_test_info(
+                "log",
+                test_scaled(440.0, 20.0..=20000.0, Scaling::Logarithmic)
+            ),
+            validate_test_info("power", test_scaled(0.5, -1.0..=1.0, Scaling::Power(2.0))),
+        ]),
+        Ok(())
+    );
+}
+
+#[test]
+fn validate_infos_rejects_invalid_scalings() {
+    assert_eq!(
+        validate_infos(&[
+            validate_test_info(
+                "log_zero",
+                test_scaled(0.5, 0.0..=1.0, Scaling::Logarithmic)
+            ),
+            validate_test_info(
+                "power_zero",
+                test_scaled(0.5, 0.0..=1.0, Scaling::Power(0.0))
+            ),
+            validate_test_info(
+                "power_nan",
+                test_scaled(0.5, 0.0..=1.0, Scaling::Power(f32::NAN))
+            ),
+        ]),
+        Err(vec![
+            ParameterError::InvalidScaling {
+                unique_id: "log_zero".to_string()
+            },
+            ParameterError::InvalidScaling {
+                unique_id: "power_zero".to_string()
+            },
+            ParameterError::InvalidScaling {
+                unique_id: "power_nan".to_string()
+            },
+        ])
+    );
+}
+
+fn validate_test_infos() -> Vec<Info> {
+    vec![
+        validate_test_info("numeric", test_numeric(0.5, 0.0..=1.0)),
+        validate_test_info("enum", test_enum(1, &["a", "b"])),
+        validate_test_info("switch", TypeSpecificInfo::Switch { default: true }),
+    ]
+}
+
+#[test]
+fn validate_presets_accepts_valid_values() {
+    assert_eq!(
+        validate_presets(
+            &validate_test_infos(),
+            &[Preset {
+                name: "Valid".to_string(),
+                values: HashMap::from([
+                    ("numeric".to_string(), Value::Numeric(1.0)),
+                    ("enum".to_string(), Value::Enum("a".to_string())),
+                    ("switch".to_string(), Value::Switch(false)),
+                ]),
+            }]
+        ),
+        Ok(())
+    );
+}
+
+#[test]
+fn validate_presets_rejects_invalid_values() {
+    assert_eq!(
+        validate_presets(
+            &validate_test_infos(),
+            &[
+                Preset {
+                    name: "First".to_string(),
+                    values: HashMap::from([
+                        ("numeric".to_string(), Value::Numeric(1.5)),
+                        ("enum".to_string(), Value::Enum("c".to_string())),
+                    ]),
+                },
+                Preset {
+                    name: "Second".to_string(),
+                    values: HashMap::from([
+                        ("switch".to_string(), Value::Numeric(0.5)),
+                        ("missing".to_string(), Value::Switch(true)),
+                    ]),
+                },
+            ]
+        ),
+        Err(vec![
+            ParameterError::InvalidPresetValue {
+                preset: "First".to_string(),
+                unique_id: "enum".to_string()
+            },
+            ParameterError::InvalidPresetValue {
+                preset: "First".to_string(),
+                unique_id: "numeric".to_string()
+            },
+            ParameterError::InvalidPresetValue {
+                preset: "Second".to_string(),
+                unique_id: "missing".to_string()
+            },
+            ParameterError::InvalidPresetValue {
+                preset: "Second".to_string(),
+                unique_id: "switch".to_string()
+            },
+        ])
+    );
+}
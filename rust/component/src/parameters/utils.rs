@@ -269,6 +269,7 @@ macro_rules! pzip_part {
 ///         default: 0.0,
 ///         valid_range: 0.0..=1.0,
 ///         units: None,
+///         options: Default::default(),
 ///       },
 ///     },
 ///     StaticInfoRef {
@@ -332,6 +333,7 @@ macro_rules! pzip {
 ///        default: 0.0,
 ///        valid_range: 0.0..=1.0,
 ///        units: None,
+///        options: Default::default(),
 ///      },
 ///    },
 /// ];
@@ -396,6 +398,7 @@ pub fn override_defaults<'a, S: AsRef<str> + 'a, H: BuildHasher>(
 ///       default: 0.0,
 ///       valid_range: 0.0..=1.0,
 ///       units: None,
+///       options: Default::default(),
 ///     },
 ///   },
 /// ];
@@ -482,6 +485,7 @@ impl StatesMap {
     ///       default: 0.0,
     ///       valid_range: 0.0..=1.0,
     ///       units: None,
+    ///       options: Default::default(),
     ///     },
     ///   },
     /// ];
@@ -524,6 +528,7 @@ impl StatesMap {
     ///       default: 0.0,
     ///       valid_range: 0.0..=1.0,
     ///       units: None,
+    ///       options: Default::default(),
     ///     },
     ///   },
     /// ];
@@ -559,6 +564,7 @@ impl StatesMap {
     ///       default: 0.0,
     ///       valid_range: 0.0..=1.0,
     ///       units: None,
+    ///       options: Default::default(),
     ///     },
     ///   },
     /// ];
@@ -614,6 +620,7 @@ impl StatesMap {
     ///       default: 0.0,
     ///       valid_range: 0.0..=1.0,
     ///       units: None,
+    ///       options: Default::default(),
     ///     },
     ///   },
     /// ];
@@ -675,6 +682,102 @@ impl<S: States> BufferStates for ConstantBufferStates<S> {
     }
 }
 
+/// A view of a [`BufferStates`] where a function is applied to the values of a single
+/// numeric parameter.
+///
+/// This is useful for scaling or offsetting a parameter (for example, applying velocity
+/// to a gain parameter) while still reading it per-sample with [`crate::pzip`].
+///
+/// For parameters that change over the buffer, the function is applied to each point of
+/// the [`PiecewiseLinearCurve`], so the result is exact for affine functions such as
+/// scaling and offsetting, and a piecewise linear approximation otherwise. Note that
+/// the mapped values may lie outside of the parameter's valid range.
+///
+/// All other parameters, including non-numeric parameters with the given ID, are
+/// passed through unchanged.
+///
+/// This can also be created with [`BufferStates::map_numeric`].
+///
+/// # Examples
+///
+/// ```
+/// # use conformal_component::pzip;
+/// # use conformal_component::parameters::{StaticInfoRef, TypeSpecificInfoRef, ConstantBufferStates, BufferStates};
+/// let infos = vec![
+///   StaticInfoRef {
+///     title: "Gain",
+///     short_title: "Gain",
+///     unique_id: "gain",
+///     flags: Default::default(),
+///     type_specific: TypeSpecificInfoRef::Numeric {
+///       default: 0.5,
+///       valid_range: 0.0..=1.0,
+///       units: None,
+///       options: Default::default(),
+///     },
+///   },
+/// ];
+/// let params = ConstantBufferStates::new_defaults(infos);
+/// let velocity = 0.5;
+/// let scaled = params.map_numeric("gain", |gain| gain * velocity);
+/// let samples: Vec<_> = pzip!(scaled[numeric "gain"]).take(2).collect();
+/// assert_eq!(samples, vec![0.25, 0.25]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct MapNumeric<B, F> {
+    states: B,
+    id_hash: IdHash,
+    f: F,
+}
+
+impl<B, F: Fn(f32) -> f32> MapNumeric<B, F> {
+    /// Create a new [`MapNumeric`] that applies `f` to the numeric parameter with
+    /// the unique ID `unique_id`.
+    pub fn new(states: B, unique_id: &str, f: F) -> Self {
+        Self {
+            states,
+            id_hash: hash_id(unique_id),
+            f,
+        }
+    }
+}
+
+impl<B: BufferStates, F: Fn(f32) -> f32> BufferStates for MapNumeric<B, F> {
+    fn get_by_hash(
+        &self,
+        id_hash: IdHash,
+    ) -> Option<
+        BufferState<
+            impl Iterator<Item = PiecewiseLinearCurvePoint> + Clone,
+            impl Iterator<Item = TimedValue<u32>> + Clone,
+            impl Iterator<Item = TimedValue<bool>> + Clone,
+        >,
+    > {
+        let mapped = id_hash == self.id_hash;
+        let f = &self.f;
+        let map = move |value| if mapped { f(value) } else { value };
+        Some(match self.states.get_by_hash(id_hash)? {
+            BufferState::Numeric(NumericBufferState::Constant(value)) => {
+                BufferState::Numeric(NumericBufferState::Constant(map(value)))
+            }
+            BufferState::Numeric(NumericBufferState::PiecewiseLinear(curve)) => {
+                let buffer_size = curve.buffer_size();
+                BufferState::Numeric(NumericBufferState::PiecewiseLinear(PiecewiseLinearCurve {
+                    points: curve
+                        .into_iter()
+                        .map(move |point| PiecewiseLinearCurvePoint {
+                            sample_offset: point.sample_offset,
+                            value: map(point.value),
+                        }),
+                    buffer_size,
+                }))
+            }
+            BufferState::Enum(e) => BufferState::Enum(e),
+            BufferState::Switch(s) => BufferState::Switch(s),
+        })
+    }
+}
+
 impl<S: States> ConstantBufferStates<S> {
     /// Create a new [`ConstantBufferStates`] object from a [`States`] object.
     pub fn new(s: S) -> Self {
@@ -707,6 +810,7 @@ impl ConstantBufferStates<StatesMap> {
     ///       default: 0.0,
     ///       valid_range: 0.0..=1.0,
     ///       units: None,
+    ///       options: Default::default(),
     ///     },
     ///   },
     /// ];
@@ -745,6 +849,7 @@ impl ConstantBufferStates<StatesMap> {
     ///       default: 0.0,
     ///       valid_range: 0.0..=1.0,
     ///       units: None,
+    ///       options: Default::default(),
     ///     },
     ///   },
     /// ];
@@ -783,6 +888,7 @@ impl ConstantBufferStates<StatesMap> {
     ///       default: 0.0,
     ///       valid_range: 0.0..=1.0,
     ///       units: None,
+    ///       options: Default::default(),
     ///     },
     ///   },
     /// ];
@@ -842,6 +948,7 @@ impl ConstantBufferStates<StatesMap> {
     ///       default: 0.0,
     ///       valid_range: 0.0..=1.0,
     ///       units: None,
+    ///       options: Default::default(),
     ///     },
     ///   },
     /// ];
@@ -943,6 +1050,7 @@ impl RampedStatesMap {
     ///       default: 0.0,
     ///       valid_range: 0.0..=1.0,
     ///       units: None,
+    ///       options: Default::default(),
     ///     },
     ///   },
     /// ];
@@ -1075,6 +1183,7 @@ impl RampedStatesMap {
     ///       default: 0.0,
     ///       valid_range: 0.0..=1.0,
     ///       units: None,
+    ///       options: Default::default(),
     ///     },
     ///   },
     /// ];
@@ -1148,6 +1257,7 @@ impl RampedStatesMap {
     ///       default: 0.0,
     ///       valid_range: 0.0..=1.0,
     ///       units: None,
+    ///       options: Default::default(),
     ///     },
     ///   },
     /// ];
@@ -1194,6 +1304,7 @@ impl RampedStatesMap {
     ///       default: 0.0,
     ///       valid_range: 0.0..=1.0,
     ///       units: None,
+    ///       options: Default::default(),
     ///     },
     ///   },
     /// ];
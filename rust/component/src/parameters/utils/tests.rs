@@ -1,4 +1,7 @@
+use std::collections::HashMap;
+
 use crate::audio::all_approx_eq;
+use crate::parameters::BufferStates;
 
 use super::super::{
     PiecewiseLinearCurve, PiecewiseLinearCurvePoint, TimedEnumValues, TimedSwitchValues, TimedValue,
@@ -114,3 +117,109 @@ fn timed_switch_per_sample_basics() {
         )
         .all(|(a, b)| a == b));
 }
+
+static MAP_NUMERIC_INFOS: [super::super::StaticInfoRef; 3] = [
+    super::super::InfoRef {
+        title: "Gain",
+        short_title: "Gain",
+        unique_id: "gain",
+        flags: super::super::Flags {
+            automatable: true,
+            curve_hint: super::super::CurveHint::Auto,
+        },
+        type_specific: super::super::TypeSpecificInfoRef::Numeric {
+            default: 0.0,
+            valid_range: 0.0..=1.0,
+            units: None,
+            options: super::super::NumericOptions::DEFAULT,
+        },
+    },
+    super::super::InfoRef {
+        title: "Other",
+        short_title: "Other",
+        unique_id: "other",
+        flags: super::super::Flags {
+            automatable: true,
+            curve_hint: super::super::CurveHint::Auto,
+        },
+        type_specific: super::super::TypeSpecificInfoRef::Numeric {
+            default: 0.5,
+            valid_range: 0.0..=1.0,
+            units: None,
+            options: super::super::NumericOptions::DEFAULT,
+        },
+    },
+    super::super::InfoRef {
+        title: "Enum",
+        short_title: "Enum",
+        unique_id: "enum",
+        flags: super::super::Flags {
+            automatable: true,
+            curve_hint: super::super::CurveHint::Auto,
+        },
+        type_specific: super::super::TypeSpecificInfoRef::Enum {
+            default: 1,
+            values: &["a", "b", "c"],
+        },
+    },
+];
+
+#[test]
+fn map_numeric_applies_gain_offset() {
+    let params = super::RampedStatesMap::new(
+        MAP_NUMERIC_INFOS.iter().cloned(),
+        &HashMap::new(),
+        &[("gain", super::super::InternalValue::Numeric(1.0))]
+            .into_iter()
+            .collect::<HashMap<_, _>>(),
+        10,
+    );
+    let mapped = (&params).map_numeric("gain", |gain| gain + 0.25);
+
+    let expected: Vec<_> = super::numeric_per_sample(params.get_numeric("gain").unwrap())
+        .take(10)
+        .map(|gain| gain + 0.25)
+        .collect();
+    assert!(all_approx_eq(
+        super::numeric_per_sample(mapped.get_numeric("gain").unwrap()).take(10),
+        expected,
+        TEST_EPSILON
+    ));
+    assert!(all_approx_eq(
+        super::numeric_per_sample(mapped.get_numeric("other").unwrap()).take(10),
+        super::numeric_per_sample(params.get_numeric("other").unwrap()).take(10),
+        TEST_EPSILON
+    ));
+}
+
+#[test]
+fn map_numeric_passes_through_non_numeric() {
+    let params = super::ConstantBufferStates::new_defaults(MAP_NUMERIC_INFOS.iter().cloned());
+    let mapped = params.map_numeric("enum", |_| panic!("Should not map an enum parameter"));
+    assert!(super::enum_per_sample(mapped.get_enum("enum").unwrap())
+        .take(3)
+        .eq([1, 1, 1]));
+}
+
+#[test]
+fn numeric_at_matches_per_sample() {
+    let params = super::RampedStatesMap::new(
+        MAP_NUMERIC_INFOS.iter().cloned(),
+        &HashMap::new(),
+        &[("gain", super::super::InternalValue::Numeric(1.0))]
+            .into_iter()
+            .collect::<HashMap<_, _>>(),
+        10,
+    );
+    let per_sample: Vec<_> =
+        super::numeric_per_sample(params.get_numeric("gain").unwrap()).collect();
+    assert!(all_approx_eq(
+        (0..10).map(|index| params.numeric_at("gain", index).unwrap()),
+        per_sample.iter().copied(),
+        TEST_EPSILON
+    ));
+    assert_eq!(params.numeric_last("gain"), per_sample.last().copied());
+    assert_eq!(params.numeric_last("other"), Some(0.5));
+    assert_eq!(params.numeric_last("enum"), None);
+    assert_eq!(params.numeric_at("missing", 0), None);
+}
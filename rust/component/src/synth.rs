@@ -1,9 +1,11 @@
 //! Abstractions for processors that generate audio.
 
 use crate::{
-    audio::BufferMut,
+    audio::{channels_mut, BufferMut},
     events::{self, Event, Events},
-    parameters::{self, BufferStates, Flags, InfoRef, TypeSpecificInfoRef},
+    parameters::{
+        self, BufferStates, CurveHint, Flags, InfoRef, NumericOptions, TypeSpecificInfoRef,
+    },
     Processor,
 };
 
@@ -22,6 +24,16 @@ pub const EXPRESSION_PARAMETER: &str = "expression_pedal";
 /// The parameter ID of the sustain pedal parameter. See [`CONTROLLER_PARAMETERS`] for more.
 pub const SUSTAIN_PARAMETER: &str = "sustain_pedal";
 
+/// The parameter ID of the sostenuto pedal parameter. See [`CONTROLLER_PARAMETERS`] for more.
+///
+/// The sostenuto pedal sustains only the notes that are held down when it is pressed.
+pub const SOSTENUTO_PARAMETER: &str = "sostenuto_pedal";
+
+/// The parameter ID of the soft pedal parameter. See [`CONTROLLER_PARAMETERS`] for more.
+///
+/// The soft pedal makes notes played while it is held quieter.
+pub const SOFT_PEDAL_PARAMETER: &str = "soft_pedal";
+
 /// The parameter ID of the aftertouch parameter. See [`CONTROLLER_PARAMETERS`] for more.
 ///
 /// Aftertouch is a pressure sensor sent by some controllers.
@@ -51,11 +63,15 @@ pub const PITCH_BEND_INFO: InfoRef<'static, &'static str> = InfoRef {
     title: "Pitch Bend",
     short_title: "Bend",
     unique_id: PITCH_BEND_PARAMETER,
-    flags: Flags { automatable: false },
+    flags: Flags {
+        automatable: false,
+        curve_hint: CurveHint::Auto,
+    },
     type_specific: TypeSpecificInfoRef::Numeric {
         default: 0.0,
         valid_range: -1.0..=1.0,
         units: None,
+        options: NumericOptions::DEFAULT,
     },
 };
 
@@ -64,11 +80,15 @@ pub const MOD_WHEEL_INFO: InfoRef<'static, &'static str> = InfoRef {
     title: "Mod Wheel",
     short_title: "Mod",
     unique_id: MOD_WHEEL_PARAMETER,
-    flags: Flags { automatable: false },
+    flags: Flags {
+        automatable: false,
+        curve_hint: CurveHint::Auto,
+    },
     type_specific: TypeSpecificInfoRef::Numeric {
         default: 0.0,
         valid_range: 0.0..=1.0,
         units: None,
+        options: NumericOptions::DEFAULT,
     },
 };
 
@@ -77,11 +97,15 @@ pub const EXPRESSION_INFO: InfoRef<'static, &'static str> = InfoRef {
     title: "Expression",
     short_title: "Expr",
     unique_id: EXPRESSION_PARAMETER,
-    flags: Flags { automatable: false },
+    flags: Flags {
+        automatable: false,
+        curve_hint: CurveHint::Auto,
+    },
     type_specific: TypeSpecificInfoRef::Numeric {
         default: 0.0,
         valid_range: 0.0..=1.0,
         units: None,
+        options: NumericOptions::DEFAULT,
     },
 };
 
@@ -90,7 +114,34 @@ pub const SUSTAIN_INFO: InfoRef<'static, &'static str> = InfoRef {
     title: "Sustain Pedal",
     short_title: "Sus",
     unique_id: SUSTAIN_PARAMETER,
-    flags: Flags { automatable: false },
+    flags: Flags {
+        automatable: false,
+        curve_hint: CurveHint::Auto,
+    },
+    type_specific: TypeSpecificInfoRef::Switch { default: false },
+};
+
+/// Parameter info for the sostenuto pedal parameter. See [`CONTROLLER_PARAMETERS`] for more.
+pub const SOSTENUTO_INFO: InfoRef<'static, &'static str> = InfoRef {
+    title: "Sostenuto Pedal",
+    short_title: "Sost",
+    unique_id: SOSTENUTO_PARAMETER,
+    flags: Flags {
+        automatable: false,
+        curve_hint: CurveHint::Auto,
+    },
+    type_specific: TypeSpecificInfoRef::Switch { default: false },
+};
+
+/// Parameter info for the soft pedal parameter. See [`CONTROLLER_PARAMETERS`] for more.
+pub const SOFT_PEDAL_INFO: InfoRef<'static, &'static str> = InfoRef {
+    title: "Soft Pedal",
+    short_title: "Soft",
+    unique_id: SOFT_PEDAL_PARAMETER,
+    flags: Flags {
+        automatable: false,
+        curve_hint: CurveHint::Auto,
+    },
     type_specific: TypeSpecificInfoRef::Switch { default: false },
 };
 
@@ -99,11 +150,15 @@ pub const AFTERTOUCH_INFO: InfoRef<'static, &'static str> = InfoRef {
     title: "Aftertouch",
     short_title: "Aftertouch",
     unique_id: AFTERTOUCH_PARAMETER,
-    flags: Flags { automatable: false },
+    flags: Flags {
+        automatable: false,
+        curve_hint: CurveHint::Auto,
+    },
     type_specific: TypeSpecificInfoRef::Numeric {
         default: 0.0,
         valid_range: 0.0..=1.0,
         units: None,
+        options: NumericOptions::DEFAULT,
     },
 };
 
@@ -112,11 +167,15 @@ pub const TIMBRE_INFO: InfoRef<'static, &'static str> = InfoRef {
     title: "Timbre",
     short_title: "Timbre",
     unique_id: TIMBRE_PARAMETER,
-    flags: Flags { automatable: false },
+    flags: Flags {
+        automatable: false,
+        curve_hint: CurveHint::Auto,
+    },
     type_specific: TypeSpecificInfoRef::Numeric {
         default: 0.0,
         valid_range: 0.0..=1.0,
         units: None,
+        options: NumericOptions::DEFAULT,
     },
 };
 
@@ -129,15 +188,131 @@ pub const TIMBRE_INFO: InfoRef<'static, &'static str> = InfoRef {
 ///
 /// Note that synths will receive these regardless of what they returned
 /// from `crate::Component::parameter_infos`.
-pub const CONTROLLER_PARAMETERS: [InfoRef<'static, &'static str>; 6] = [
+///
+/// Plug-in user interfaces can read and set these parameters just like any
+/// other parameter, for example to show an on-screen mod wheel. However,
+/// they describe the live state of the performer's controllers rather than
+/// the state of the synth, so they are never saved in the component state.
+pub const CONTROLLER_PARAMETERS: [InfoRef<'static, &'static str>; 8] = [
     PITCH_BEND_INFO,
     MOD_WHEEL_INFO,
     EXPRESSION_INFO,
     SUSTAIN_INFO,
+    SOSTENUTO_INFO,
+    SOFT_PEDAL_INFO,
     AFTERTOUCH_INFO,
     TIMBRE_INFO,
 ];
 
+/// The most auxiliary outputs a synth can have, in addition to its main output.
+///
+/// Wrappers let each synth declare its auxiliary outputs, and reject synths that
+/// declare more than this.
+pub const MAX_AUX_OUTPUTS: usize = 15;
+
+/// A kind of per-note expression that a physical control can be mapped to.
+///
+/// See [`PhysicalUIMapping`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoteExpressionKind {
+    /// Mapped to [`crate::events::NoteExpression::PitchBend`].
+    PitchBend,
+
+    /// Mapped to [`crate::events::NoteExpression::Timbre`].
+    Timbre,
+
+    /// Mapped to [`crate::events::NoteExpression::Aftertouch`].
+    Aftertouch,
+}
+
+/// Describes which note expressions the physical dimensions of a controller should control.
+///
+/// Some hosts and controllers (for example, MPE controllers) have per-note physical
+/// dimensions, such as horizontal movement, vertical movement, and pressure. Hosts use
+/// this mapping to decide which note expression each dimension should send.
+///
+/// A dimension mapped to `None` will not send any note expression.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PhysicalUIMapping {
+    /// The note expression controlled by horizontal movement of a finger on a key.
+    pub x: Option<NoteExpressionKind>,
+
+    /// The note expression controlled by vertical movement of a finger on a key.
+    pub y: Option<NoteExpressionKind>,
+
+    /// The note expression controlled by the pressure of a finger on a key.
+    pub pressure: Option<NoteExpressionKind>,
+}
+
+impl Default for PhysicalUIMapping {
+    /// By default, horizontal movement controls pitch bend, vertical movement
+    /// controls timbre, and pressure controls aftertouch.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use conformal_component::synth::{NoteExpressionKind, PhysicalUIMapping};
+    /// let mapping = PhysicalUIMapping::default();
+    /// assert_eq!(mapping.x, Some(NoteExpressionKind::PitchBend));
+    /// assert_eq!(mapping.y, Some(NoteExpressionKind::Timbre));
+    /// assert_eq!(mapping.pressure, Some(NoteExpressionKind::Aftertouch));
+    /// ```
+    fn default() -> Self {
+        Self {
+            x: Some(NoteExpressionKind::PitchBend),
+            y: Some(NoteExpressionKind::Timbre),
+            pressure: Some(NoteExpressionKind::Aftertouch),
+        }
+    }
+}
+
+/// The ranges of per-note expressions that a synth responds to.
+///
+/// Hosts use these to decide how to display and edit note expression values.
+/// See [`crate::Component::note_expression_ranges`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NoteExpressionRanges {
+    /// The maximum per-note pitch bend in semitones, in either direction.
+    ///
+    /// Hosts will be asked to keep [`crate::events::NoteExpression::PitchBend`]
+    /// values within `-pitch_bend..=pitch_bend`. This must be between 0 and
+    /// [`Self::MAX_PITCH_BEND`].
+    pub pitch_bend: f32,
+
+    /// Which note expressions the physical dimensions of a controller should control.
+    pub physical_ui_mapping: PhysicalUIMapping,
+}
+
+impl NoteExpressionRanges {
+    /// The largest supported `pitch_bend`, in semitones.
+    ///
+    /// Plug-in formats limit how far a single note can be bent, so larger
+    /// ranges are rejected by [`crate::validate_component`].
+    pub const MAX_PITCH_BEND: f32 = 120.0;
+}
+
+impl Default for NoteExpressionRanges {
+    /// By default, per-note pitch bend can range up to 120 semitones in either direction,
+    /// and the default [`PhysicalUIMapping`] is used.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use conformal_component::synth::{NoteExpressionRanges, PhysicalUIMapping};
+    /// assert_eq!(NoteExpressionRanges::default().pitch_bend, 120.0);
+    /// assert_eq!(
+    ///     NoteExpressionRanges::default().physical_ui_mapping,
+    ///     PhysicalUIMapping::default()
+    /// );
+    /// ```
+    fn default() -> Self {
+        Self {
+            pitch_bend: Self::MAX_PITCH_BEND,
+            physical_ui_mapping: Default::default(),
+        }
+    }
+}
+
 /// A trait for synthesizers
 ///
 /// A synthesizer is a processor that creates audio from a series of _events_,
@@ -182,4 +357,78 @@ pub trait Synth: Processor {
         parameters: P,
         output: &mut O,
     );
+
+    /// Process a buffer of events into a buffer of double-precision audio.
+    /// Must not allocate or block.
+    ///
+    /// This is called instead of [`Self::process`] when the host processes audio
+    /// in 64-bit, which only happens for processors that set
+    /// [`crate::Processor::SUPPORTS_DOUBLE_PRECISION`]. Otherwise, this works the
+    /// same as [`Self::process`].
+    ///
+    /// Synths that support double precision must implement this. The default
+    /// implementation is never called, and fails to build for those synths.
+    fn process_f64<E: Iterator<Item = Event> + Clone, P: BufferStates, O: BufferMut<f64>>(
+        &mut self,
+        events: Events<E>,
+        parameters: P,
+        output: &mut O,
+    ) {
+        const {
+            assert!(
+                !Self::SUPPORTS_DOUBLE_PRECISION,
+                "Synths that support double precision must implement `process_f64`"
+            );
+        };
+        let _ = (events, parameters, output);
+    }
+
+    /// Process a buffer of events into the main output and all auxiliary outputs.
+    /// Must not allocate or block.
+    ///
+    /// `outputs` contains the main output followed by one buffer for each auxiliary
+    /// output declared to the wrapper, in the same order.
+    /// Auxiliary outputs that the host has deactivated have no frames, so they can
+    /// be skipped. Otherwise, this works the same as [`Self::process`].
+    ///
+    /// Synths with auxiliary outputs should implement this. The default implementation
+    /// renders the main output with [`Self::process`] and silences the others, so
+    /// synths without auxiliary outputs don't need to implement it.
+    fn process_buses<E: Iterator<Item = Event> + Clone, P: BufferStates, O: BufferMut>(
+        &mut self,
+        events: Events<E>,
+        parameters: P,
+        outputs: &mut [O],
+    ) {
+        if let Some((main, aux)) = outputs.split_first_mut() {
+            self.process(events, parameters, main);
+            for output in aux {
+                for channel in channels_mut(output) {
+                    channel.fill(0.0);
+                }
+            }
+        }
+    }
+
+    /// Process a buffer of events into the main output and all auxiliary outputs
+    /// in double precision. Must not allocate or block.
+    ///
+    /// This is called instead of [`Self::process_buses`] when the host processes audio
+    /// in 64-bit. The default implementation renders the main output with
+    /// [`Self::process_f64`] and silences the others.
+    fn process_buses_f64<E: Iterator<Item = Event> + Clone, P: BufferStates, O: BufferMut<f64>>(
+        &mut self,
+        events: Events<E>,
+        parameters: P,
+        outputs: &mut [O],
+    ) {
+        if let Some((main, aux)) = outputs.split_first_mut() {
+            self.process_f64(events, parameters, main);
+            for output in aux {
+                for channel in channels_mut(output) {
+                    channel.fill(0.0);
+                }
+            }
+        }
+    }
 }
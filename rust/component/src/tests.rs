@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+
+use super::{validate_component, Component, ComponentError, Preset, ProcessingEnvironment};
+use crate::audio::ChannelLayout;
+use crate::parameters::{
+    to_infos, Flags, Info, InfoRef, ParameterError, TypeSpecificInfoRef, Value,
+};
+use crate::synth::NoteExpressionRanges;
+
+#[derive(Default)]
+struct FakeComponent {
+    parameter_infos: Vec<Info>,
+    presets: Vec<Preset>,
+    note_expression_ranges: NoteExpressionRanges,
+    layouts: Option<Vec<ChannelLayout>>,
+}
+
+impl Component for FakeComponent {
+    type Processor = ();
+
+    fn parameter_infos(&self) -> Vec<Info> {
+        self.parameter_infos.clone()
+    }
+
+    fn note_expression_ranges(&self) -> NoteExpressionRanges {
+        self.note_expression_ranges
+    }
+
+    fn supported_channel_layouts(&self) -> Vec<ChannelLayout> {
+        self.layouts
+            .clone()
+            .unwrap_or(vec![ChannelLayout::Mono, ChannelLayout::Stereo])
+    }
+
+    fn factory_presets(&self) -> Vec<Preset> {
+        self.presets.clone()
+    }
+
+    fn create_processor(&self, _env: &ProcessingEnvironment) -> Self::Processor {}
+}
+
+fn switch_infos(unique_ids: &[&'static str]) -> Vec<Info> {
+    let refs: Vec<_> = unique_ids
+        .iter()
+        .map(|unique_id| InfoRef {
+            title: "Switch",
+            short_title: "Switch",
+            unique_id,
+            flags: Flags::default(),
+            type_specific: TypeSpecificInfoRef::Switch { default: false },
+        })
+        .collect();
+    to_infos(&refs)
+}
+
+#[test]
+fn valid_component_passes() {
+    let component = FakeComponent {
+        parameter_infos: switch_infos(&["a", "b"]),
+        ..Default::default()
+    };
+    assert_eq!(component.validate_parameters(), Ok(()));
+    assert_eq!(validate_component(&component), Ok(()));
+}
+
+#[test]
+fn reports_parameter_errors() {
+    let component = FakeComponent {
+        parameter_infos: switch_infos(&["a", "a"]),
+        ..Default::default()
+    };
+    let error = ParameterError::DuplicateId {
+        unique_id: "a".to_string(),
+    };
+    assert_eq!(component.validate_parameters(), Err(vec![error.clone()]));
+    assert_eq!(
+        validate_component(&component),
+        Err(vec![ComponentError::Parameter(error)])
+    );
+}
+
+#[test]
+fn reports_invalid_presets() {
+    let component = FakeComponent {
+        parameter_infos: switch_infos(&["a"]),
+        presets: vec![Preset {
+            name: "Broken".to_string(),
+            values: HashMap::from([("a".to_string(), Value::Numeric(1.0))]),
+        }],
+        ..Default::default()
+    };
+    assert_eq!(component.validate_parameters(), Ok(()));
+    assert_eq!(
+        validate_component(&component),
+        Err(vec![ComponentError::Parameter(
+            ParameterError::InvalidPresetValue {
+                preset: "Broken".to_string(),
+                unique_id: "a".to_string(),
+            }
+        )])
+    );
+}
+
+#[test]
+fn reports_unsupported_pitch_bend_range() {
+    let component = FakeComponent {
+        note_expression_ranges: NoteExpressionRanges {
+            pitch_bend: NoteExpressionRanges::MAX_PITCH_BEND * 2.0,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    assert_eq!(
+        validate_component(&component),
+        Err(vec![ComponentError::InvalidPitchBendRange])
+    );
+}
+
+#[test]
+fn reports_missing_stereo_layout() {
+    let component = FakeComponent {
+        layouts: Some(vec![ChannelLayout::Mono]),
+        ..Default::default()
+    };
+    assert_eq!(
+        validate_component(&component),
+        Err(vec![ComponentError::MissingStereoLayout])
+    );
+}
@@ -2,12 +2,42 @@ use std::collections::HashMap;
 
 use conformal_component::parameters::Value;
 
+pub mod normalization;
+
 pub mod serialization;
 
 pub mod store;
 
+#[cfg(test)]
+mod tests;
+
 /// This represents the current state of all parameters.
 #[derive(Debug, Clone, PartialEq)]
 pub struct Snapshot {
     pub values: HashMap<String, Value>,
 }
+
+/// Returns the parameters whose values differ between two snapshots.
+///
+/// Each item is the id of a changed parameter, along with its value in `before`
+/// and its value in `after`. This is useful for recording the minimal set of changes
+/// needed to undo or redo an edit. The result is sorted by parameter id.
+///
+/// Parameters that are present in only one of the snapshots are not included,
+/// since there is no value on the other side to revert to.
+#[must_use]
+pub fn diff(before: &Snapshot, after: &Snapshot) -> Vec<(String, Value, Value)> {
+    let mut changes: Vec<_> = before
+        .values
+        .iter()
+        .filter_map(|(id, old)| {
+            after
+                .values
+                .get(id)
+                .filter(|new| *new != old)
+                .map(|new| (id.clone(), old.clone(), new.clone()))
+        })
+        .collect();
+    changes.sort_by(|(a, _, _), (b, _, _)| a.cmp(b));
+    changes
+}
@@ -0,0 +1,213 @@
+//! Conversions between parameter values and normalized values.
+//!
+//! Hosts refer to every parameter value as a "normalized" number between 0 and 1,
+//! regardless of the parameter's type. These functions define how conformal maps
+//! each parameter type to and from this normalized range, so that wrappers, UIs, and
+//! other tools all agree with what the host sees:
+//!
+//! - Numeric parameters are mapped so that the start of the `valid_range` is 0
+//!   and the end is 1. In between, values follow the parameter's [`Scaling`]:
+//!   - [`Scaling::Linear`] spreads values evenly. For bipolar ranges like
+//!     `-1.0..=1.0`, this puts 0 at 0.5.
+//!   - [`Scaling::Logarithmic`] spreads ratios evenly. For `20.0..=20000.0`, this
+//!     puts 200 at 1/3 and 2000 at 2/3.
+//!   - [`Scaling::Power`] raises the normalized value to the exponent before
+//!     mapping it linearly. For `0.0..=1.0` with an exponent of 2, this puts 0.25 at 0.5.
+//!
+//!   Scalings that can't be used with the range (see [`Scaling::is_valid_for`])
+//!   fall back to linear.
+//! - Enum parameters are mapped so that the first value is 0 and the last value
+//!   is 1, with the others evenly spaced in between.
+//! - Switch parameters are 0 when off and 1 when on.
+
+use conformal_component::parameters::{Info, NumericOptions, Scaling, TypeSpecificInfo, Value};
+
+#[cfg(test)]
+mod tests;
+
+/// Returns the scaling to use for a range, falling back to linear if `scaling`
+/// can't be used with it.
+fn effective_scaling(valid_range: &std::ops::RangeInclusive<f32>, scaling: Scaling) -> Scaling {
+    if valid_range.start() < valid_range.end() && scaling.is_valid_for(valid_range) {
+        scaling
+    } else {
+        Scaling::Linear
+    }
+}
+
+/// Converts a normalized value to the value of a numeric parameter with the given range
+/// and scaling.
+///
+/// Normalized values outside of 0 to 1 are clamped.
+// Generally we _expect_ truncation here, so allow it.
+#[allow(clippy::cast_possible_truncation)]
+#[must_use]
+pub fn convert_numeric(
+    value: f64,
+    valid_range: &std::ops::RangeInclusive<f32>,
+    scaling: Scaling,
+) -> f32 {
+    let (start, end) = (*valid_range.start(), *valid_range.end());
+    match effective_scaling(valid_range, scaling) {
+        Scaling::Linear => (value as f32).clamp(0.0, 1.0) * (end - start) + start,
+        Scaling::Logarithmic => {
+            let (start_64, end_64) = (f64::from(start), f64::from(end));
+            ((start_64 * (end_64 / start_64).powf(value.clamp(0.0, 1.0))) as f32).clamp(start, end)
+        }
+        Scaling::Power(exponent) => {
+            let curved = value.clamp(0.0, 1.0).powf(f64::from(exponent));
+            ((curved * f64::from(end - start) + f64::from(start)) as f32).clamp(start, end)
+        }
+    }
+}
+
+/// Converts the value of a numeric parameter with the given range and scaling
+/// to a normalized value.
+///
+/// Values outside of `valid_range` are clamped.
+#[must_use]
+pub fn normalize_numeric(
+    value: f32,
+    valid_range: &std::ops::RangeInclusive<f32>,
+    scaling: Scaling,
+) -> f64 {
+    let (start, end) = (*valid_range.start(), *valid_range.end());
+    let width = end - start;
+    // A zero-width range has only one valid value, which we map to 0.
+    if width <= 0.0 {
+        return 0.0;
+    }
+    let value = value.clamp(start, end);
+    match effective_scaling(valid_range, scaling) {
+        Scaling::Linear => ((value - start) / width).into(),
+        Scaling::Logarithmic => {
+            (f64::from(value) / f64::from(start)).ln() / (f64::from(end) / f64::from(start)).ln()
+        }
+        Scaling::Power(exponent) => {
+            (f64::from(value - start) / f64::from(width)).powf(1.0 / f64::from(exponent))
+        }
+    }
+}
+
+/// Converts a normalized value to the index of a value of an enum parameter with `count` values.
+///
+/// Normalized values outside of 0 to 1 are clamped.
+// Generally we _expect_ truncation here, so allow it.
+#[allow(clippy::cast_possible_truncation)]
+#[must_use]
+pub fn convert_enum(value: f64, count: u32) -> u32 {
+    ((value.clamp(0.0, 1.0) * (f64::from(count))).floor() as u32).min(count - 1)
+}
+
+/// Converts the index of a value of an enum parameter with `count` values to a normalized value.
+#[must_use]
+pub fn normalize_enum(value: u32, count: u32) -> f64 {
+    (f64::from(value.clamp(0, count - 1))) / (f64::from(count - 1))
+}
+
+/// Converts a normalized value to the value of a switch parameter.
+#[must_use]
+pub fn convert_switch(value: f64) -> bool {
+    value > 0.5
+}
+
+/// Converts the value of a switch parameter to a normalized value.
+#[must_use]
+pub fn normalize_switch(value: bool) -> f64 {
+    if value {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+/// Converts a parameter value to the normalized value the host uses for it.
+///
+/// Returns `None` if `value` is not valid for the parameter, that is, if it is
+/// a different type than the parameter, or it is an enum value that isn't one of
+/// the parameter's `values`. Numeric values outside of the `valid_range` are clamped.
+///
+/// # Examples
+///
+/// ```
+/// # use conformal_component::parameters::{Flags, Info, TypeSpecificInfo, Value};
+/// # use conformal_core::parameters::normalization::to_normalized;
+/// let info = Info {
+///     unique_id: "pan".to_string(),
+///     title: "Pan".to_string(),
+///     short_title: "Pan".to_string(),
+///     flags: Flags::default(),
+///     type_specific: TypeSpecificInfo::Numeric {
+///         default: 0.0,
+///         valid_range: -1.0..=1.0,
+///         units: None,
+///         options: Default::default(),
+///     },
+/// };
+/// assert_eq!(to_normalized(&Value::Numeric(0.0), &info), Some(0.5));
+/// assert_eq!(to_normalized(&Value::Switch(true), &info), None);
+/// ```
+#[must_use]
+pub fn to_normalized(value: &Value, info: &Info) -> Option<f64> {
+    match (&info.type_specific, value) {
+        (
+            TypeSpecificInfo::Numeric {
+                valid_range,
+                options: NumericOptions { scaling, .. },
+                ..
+            },
+            Value::Numeric(value),
+        ) => Some(normalize_numeric(*value, valid_range, *scaling)),
+        (TypeSpecificInfo::Enum { values, .. }, Value::Enum(value)) => {
+            let index = values.iter().position(|v| v == value)?;
+            Some(normalize_enum(
+                index.try_into().ok()?,
+                values.len().try_into().ok()?,
+            ))
+        }
+        (TypeSpecificInfo::Switch { .. }, Value::Switch(value)) => Some(normalize_switch(*value)),
+        _ => None,
+    }
+}
+
+/// Converts a normalized value from the host to a value of the parameter.
+///
+/// Normalized values outside of 0 to 1 are clamped.
+///
+/// # Panics
+///
+/// Panics if `info` is an enum parameter with no values.
+///
+/// # Examples
+///
+/// ```
+/// # use conformal_component::parameters::{Flags, Info, TypeSpecificInfo, Value};
+/// # use conformal_core::parameters::normalization::from_normalized;
+/// let info = Info {
+///     unique_id: "shape".to_string(),
+///     title: "Shape".to_string(),
+///     short_title: "Shape".to_string(),
+///     flags: Flags::default(),
+///     type_specific: TypeSpecificInfo::Enum {
+///         default: 0,
+///         values: vec!["Sine".to_string(), "Saw".to_string(), "Square".to_string()],
+///     },
+/// };
+/// assert_eq!(from_normalized(0.5, &info), Value::Enum("Saw".to_string()));
+/// assert_eq!(from_normalized(1.0, &info), Value::Enum("Square".to_string()));
+/// ```
+#[must_use]
+pub fn from_normalized(normalized: f64, info: &Info) -> Value {
+    match &info.type_specific {
+        TypeSpecificInfo::Numeric {
+            valid_range,
+            options: NumericOptions { scaling, .. },
+            ..
+        } => Value::Numeric(convert_numeric(normalized, valid_range, *scaling)),
+        TypeSpecificInfo::Enum { values, .. } => {
+            let index = convert_enum(normalized, values.len().try_into().unwrap());
+            Value::Enum(values[index as usize].clone())
+        }
+        TypeSpecificInfo::Switch { .. } => Value::Switch(convert_switch(normalized)),
+    }
+}
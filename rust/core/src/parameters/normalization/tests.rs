@@ -0,0 +1,336 @@
+use assert_approx_eq::assert_approx_eq;
+use conformal_component::parameters::{
+    Flags, Info, NumericOptions, Scaling, TypeSpecificInfo, Value,
+};
+
+use super::{
+    convert_enum, convert_numeric, convert_switch, from_normalized, normalize_enum,
+    normalize_numeric, normalize_switch, to_normalized,
+};
+
+const STEPS: u16 = 1000;
+
+fn numeric(valid_range: std::ops::RangeInclusive<f32>) -> TypeSpecificInfo {
+    scaled(valid_range, Scaling::Linear)
+}
+
+fn scaled(valid_range: std::ops::RangeInclusive<f32>, scaling: Scaling) -> TypeSpecificInfo {
+    TypeSpecificInfo::Numeric {
+        default: *valid_range.start(),
+        valid_range,
+        units: None,
+        options: NumericOptions {
+            scaling,
+            ..Default::default()
+        },
+    }
+}
+
+fn enumeration(count: usize) -> TypeSpecificInfo {
+    TypeSpecificInfo::Enum {
+        default: 0,
+        values: (0..count).map(|i| i.to_string()).collect(),
+    }
+}
+
+fn infos() -> Vec<TypeSpecificInfo> {
+    vec![
+        numeric(0.0..=1.0),
+        numeric(-1.0..=1.0),
+        numeric(-10.0..=-2.0),
+        numeric(0.5..=10.0),
+        numeric(20.0..=20000.0),
+        numeric(1.0..=1.0001),
+        numeric(-1e-4..=1e-4),
+        numeric(3.0..=3.0),
+        scaled(20.0..=20000.0, Scaling::Logarithmic),
+        scaled(0.001..=1.0, Scaling::Logarithmic),
+        scaled(0.0..=1.0, Scaling::Power(2.0)),
+        scaled(-10.0..=10.0, Scaling::Power(3.0)),
+        scaled(1.0..=100.0, Scaling::Power(0.5)),
+        enumeration(2),
+        enumeration(3),
+        enumeration(7),
+        enumeration(128),
+        TypeSpecificInfo::Switch { default: false },
+    ]
+}
+
+fn assert_normalized(value: f64) {
+    assert!((0.0..=1.0).contains(&value), "{value} is not normalized");
+}
+
+fn check_round_trip(info: &TypeSpecificInfo) {
+    match info {
+        TypeSpecificInfo::Numeric {
+            valid_range,
+            options: NumericOptions { scaling, .. },
+            ..
+        } => {
+            let (start, end) = (*valid_range.start(), *valid_range.end());
+            let tolerance = 4.0 * f32::EPSILON * start.abs().max(end.abs()).max(f32::MIN_POSITIVE);
+            for step in 0..=STEPS {
+                let value = start + (end - start) * f32::from(step) / f32::from(STEPS);
+                let normalized = normalize_numeric(value, valid_range, *scaling);
+                assert_normalized(normalized);
+                assert_approx_eq!(
+                    convert_numeric(normalized, valid_range, *scaling),
+                    value,
+                    tolerance
+                );
+            }
+        }
+        TypeSpecificInfo::Enum { values, .. } => {
+            let count = u32::try_from(values.len()).unwrap();
+            for index in 0..count {
+                let normalized = normalize_enum(index, count);
+                assert_normalized(normalized);
+                assert_eq!(convert_enum(normalized, count), index);
+            }
+        }
+        TypeSpecificInfo::Switch { .. } => {
+            for value in [false, true] {
+                let normalized = normalize_switch(value);
+                assert_normalized(normalized);
+                assert_eq!(convert_switch(normalized), value);
+            }
+        }
+    }
+}
+
+fn check_convert_in_range(info: &TypeSpecificInfo) {
+    for step in 0..=STEPS {
+        let normalized = f64::from(step) / f64::from(STEPS);
+        match info {
+            TypeSpecificInfo::Numeric {
+                valid_range,
+                options: NumericOptions { scaling, .. },
+                ..
+            } => {
+                assert!(valid_range.contains(&convert_numeric(normalized, valid_range, *scaling)));
+            }
+            TypeSpecificInfo::Enum { values, .. } => {
+                let count = u32::try_from(values.len()).unwrap();
+                assert!(convert_enum(normalized, count) < count);
+            }
+            TypeSpecificInfo::Switch { .. } => {}
+        }
+    }
+}
+
+#[test]
+fn round_trips() {
+    for info in infos() {
+        check_round_trip(&info);
+    }
+}
+
+#[test]
+fn convert_stays_in_range() {
+    for info in infos() {
+        check_convert_in_range(&info);
+    }
+}
+
+#[test]
+fn normalize_clamps_out_of_range_numeric() {
+    for info in infos() {
+        if let TypeSpecificInfo::Numeric {
+            valid_range,
+            options: NumericOptions { scaling, .. },
+            ..
+        } = info
+        {
+            let below = normalize_numeric(valid_range.start() - 1.0, &valid_range, scaling);
+            let above = normalize_numeric(valid_range.end() + 1.0, &valid_range, scaling);
+            assert_normalized(below);
+            assert_normalized(above);
+            assert_approx_eq!(
+                convert_numeric(below, &valid_range, scaling),
+                *valid_range.start(),
+                1e-6
+            );
+            assert_approx_eq!(
+                convert_numeric(above, &valid_range, scaling),
+                *valid_range.end(),
+                1e-6
+            );
+        }
+    }
+}
+
+#[test]
+fn zero_width_range_normalizes_to_zero() {
+    let valid_range = 3.0..=3.0;
+    for scaling in [Scaling::Linear, Scaling::Logarithmic, Scaling::Power(2.0)] {
+        assert_approx_eq!(normalize_numeric(3.0, &valid_range, scaling), 0.0);
+        assert_approx_eq!(normalize_numeric(2.0, &valid_range, scaling), 0.0);
+        assert_approx_eq!(convert_numeric(0.0, &valid_range, scaling), 3.0);
+        assert_approx_eq!(convert_numeric(1.0, &valid_range, scaling), 3.0);
+    }
+}
+
+#[test]
+fn bipolar_range_centers_zero() {
+    let valid_range = -1.0..=1.0;
+    assert_approx_eq!(normalize_numeric(0.0, &valid_range, Scaling::Linear), 0.5);
+    assert_approx_eq!(convert_numeric(0.5, &valid_range, Scaling::Linear), 0.0);
+}
+
+#[test]
+fn logarithmic_scaling_spreads_ratios_evenly() {
+    let valid_range = 20.0..=20000.0;
+    let scaling = Scaling::Logarithmic;
+    assert_approx_eq!(normalize_numeric(200.0, &valid_range, scaling), 1.0 / 3.0);
+    assert_approx_eq!(normalize_numeric(2000.0, &valid_range, scaling), 2.0 / 3.0);
+    assert_approx_eq!(
+        convert_numeric(1.0 / 3.0, &valid_range, scaling),
+        200.0,
+        1e-3
+    );
+    assert_approx_eq!(
+        convert_numeric(2.0 / 3.0, &valid_range, scaling),
+        2000.0,
+        1e-2
+    );
+}
+
+#[test]
+fn power_scaling_raises_normalized_value() {
+    let valid_range = 0.0..=1.0;
+    let scaling = Scaling::Power(2.0);
+    assert_approx_eq!(normalize_numeric(0.25, &valid_range, scaling), 0.5);
+    assert_approx_eq!(convert_numeric(0.5, &valid_range, scaling), 0.25);
+
+    let valid_range = 10.0..=20.0;
+    assert_approx_eq!(normalize_numeric(12.5, &valid_range, scaling), 0.5);
+    assert_approx_eq!(convert_numeric(0.5, &valid_range, scaling), 12.5);
+}
+
+#[test]
+fn invalid_scaling_falls_back_to_linear() {
+    for (valid_range, scaling) in [
+        (0.0..=1.0, Scaling::Logarithmic),
+        (-1.0..=1.0, Scaling::Logarithmic),
+        (0.0..=1.0, Scaling::Power(0.0)),
+        (0.0..=1.0, Scaling::Power(-1.0)),
+        (0.0..=1.0, Scaling::Power(f32::NAN)),
+    ] {
+        for step in 0..=STEPS {
+            let normalized = f64::from(step) / f64::from(STEPS);
+            let value = convert_numeric(normalized, &valid_range, Scaling::Linear);
+            assert_approx_eq!(convert_numeric(normalized, &valid_range, scaling), value);
+            assert_approx_eq!(
+                normalize_numeric(value, &valid_range, scaling),
+                normalize_numeric(value, &valid_range, Scaling::Linear)
+            );
+        }
+    }
+}
+
+#[test]
+fn convert_clamps_out_of_range_normalized() {
+    let valid_range = -1.0..=1.0;
+    assert_approx_eq!(convert_numeric(-0.5, &valid_range, Scaling::Linear), -1.0);
+    assert_approx_eq!(convert_numeric(1.5, &valid_range, Scaling::Linear), 1.0);
+    assert_eq!(convert_enum(-0.5, 3), 0);
+    assert_eq!(convert_enum(1.5, 3), 2);
+}
+
+fn info(type_specific: TypeSpecificInfo) -> Info {
+    Info {
+        unique_id: "test".to_string(),
+        title: "Test".to_string(),
+        short_title: "Test".to_string(),
+        flags: Flags::default(),
+        type_specific,
+    }
+}
+
+fn values(info: &TypeSpecificInfo) -> Vec<Value> {
+    match info {
+        TypeSpecificInfo::Numeric { valid_range, .. } => (0..=STEPS)
+            .map(|step| {
+                Value::Numeric(
+                    valid_range.start()
+                        + (valid_range.end() - valid_range.start()) * f32::from(step)
+                            / f32::from(STEPS),
+                )
+            })
+            .collect(),
+        TypeSpecificInfo::Enum { values, .. } => values.iter().cloned().map(Value::Enum).collect(),
+        TypeSpecificInfo::Switch { .. } => vec![Value::Switch(false), Value::Switch(true)],
+    }
+}
+
+#[test]
+fn values_round_trip() {
+    for type_specific in infos() {
+        let info = info(type_specific);
+        for value in values(&info.type_specific) {
+            let normalized = to_normalized(&value, &info).unwrap();
+            assert_normalized(normalized);
+            match (&value, from_normalized(normalized, &info)) {
+                (Value::Numeric(expected), Value::Numeric(actual)) => {
+                    assert_approx_eq!(actual, expected, 1e-2);
+                }
+                (expected, actual) => assert_eq!(&actual, expected),
+            }
+        }
+    }
+}
+
+#[test]
+fn values_match_type_specific_conversions() {
+    for type_specific in infos() {
+        let info = info(type_specific);
+        for step in 0..=STEPS {
+            let normalized = f64::from(step) / f64::from(STEPS);
+            match (&info.type_specific, from_normalized(normalized, &info)) {
+                (
+                    TypeSpecificInfo::Numeric {
+                        valid_range,
+                        options: NumericOptions { scaling, .. },
+                        ..
+                    },
+                    Value::Numeric(value),
+                ) => {
+                    assert_approx_eq!(value, convert_numeric(normalized, valid_range, *scaling));
+                    assert_approx_eq!(
+                        to_normalized(&Value::Numeric(value), &info).unwrap(),
+                        normalize_numeric(value, valid_range, *scaling)
+                    );
+                }
+                (TypeSpecificInfo::Enum { values, .. }, Value::Enum(value)) => {
+                    let count = u32::try_from(values.len()).unwrap();
+                    let index = convert_enum(normalized, count);
+                    assert_eq!(value, values[index as usize]);
+                    assert_approx_eq!(
+                        to_normalized(&Value::Enum(value), &info).unwrap(),
+                        normalize_enum(index, count)
+                    );
+                }
+                (TypeSpecificInfo::Switch { .. }, Value::Switch(value)) => {
+                    assert_eq!(value, convert_switch(normalized));
+                    assert_approx_eq!(
+                        to_normalized(&Value::Switch(value), &info).unwrap(),
+                        normalize_switch(value)
+                    );
+                }
+                (_, value) => panic!("{value:?} has the wrong type"),
+            }
+        }
+    }
+}
+
+#[test]
+fn to_normalized_rejects_invalid_values() {
+    let numeric = info(numeric(0.0..=1.0));
+    let enumeration = info(enumeration(3));
+    assert_eq!(to_normalized(&Value::Switch(true), &numeric), None);
+    assert_eq!(to_normalized(&Value::Numeric(0.5), &enumeration), None);
+    assert_eq!(
+        to_normalized(&Value::Enum("not a value".to_string()), &enumeration),
+        None
+    );
+}
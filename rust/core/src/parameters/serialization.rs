@@ -20,7 +20,12 @@
 //! - Increasing the allowed range of a numeric parameter.
 //! - Adding a new enum values to the end of the list.
 //!
-//! Other changes will need explicit migrations (to be supported after #19).
+//! Other changes will need explicit migrations. To support these, each saved snapshot
+//! is associated with a schema version chosen by the component. When loading a snapshot
+//! from an older schema version, the component gets a chance to upgrade the saved
+//! values, for example to rename a parameter or convert its units (see
+//! [`Snapshot::into_snapshot_with_version`]). Snapshots from newer schema versions
+//! are never loaded.
 //!
 //! ## Automatable parameter restrictions
 //!
@@ -162,6 +167,10 @@ pub enum SnapshotCorruptionError {
     /// Changing the type of a parameter requires a migration, so it's an error
     /// if we try to load a snapshot that has a different type for a parameter.
     IncompatibleType(String),
+
+    /// Upgrading a snapshot from an older schema version produced values that
+    /// are not valid for the current parameters.
+    InvalidUpgrade(),
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -237,6 +246,74 @@ impl Snapshot {
         Ok(super::Snapshot { values })
     }
 
+    /// Convert a serialized snapshot saved with an explicit schema version to a snapshot.
+    ///
+    /// `saved_version` is the schema version that the snapshot was saved with, and
+    /// `current_version` is the schema version of the component loading it.
+    ///
+    ///  - If the snapshot is from a newer schema version, this fails with
+    ///    `DeserializationError::VersionTooNew`, even if all the values would be valid.
+    ///  - If the snapshot is from an older schema version, `upgrade` is first called with
+    ///    the saved version and the values exactly as they were saved, so it can adjust them
+    ///    for the current schema. Since this happens before the values are checked against
+    ///    the current parameters, `upgrade` sees values of parameters that no longer exist
+    ///    and values that are no longer valid, so it can move a value to a renamed parameter
+    ///    or convert its units. The upgraded values are then decoded as with
+    ///    [`Self::into_snapshot`].
+    ///
+    /// # Errors
+    ///
+    /// Returns any error that [`Self::into_snapshot`] would for a snapshot from the current
+    /// version, `DeserializationError::VersionTooNew` if `saved_version` is newer than
+    /// `current_version`, or `DeserializationError::Corrupted` if the values of a snapshot
+    /// from an older version are invalid for the current parameters after `upgrade`.
+    pub fn into_snapshot_with_version<'a, I: IntoIterator<Item = &'a str> + Clone>(
+        self,
+        saved_version: u32,
+        current_version: u32,
+        all_params: impl IntoIterator<Item = (&'a str, ReadInfoRef<I>)>,
+        upgrade: impl FnOnce(u32, &mut HashMap<String, ParameterValue>),
+    ) -> Result<super::Snapshot, DeserializationError> {
+        if saved_version > current_version {
+            return Err(DeserializationError::VersionTooNew());
+        }
+        if saved_version == current_version {
+            return self.into_snapshot(all_params);
+        }
+        let mut values = self
+            .values
+            .into_iter()
+            .map(|(id, value)| {
+                (
+                    id,
+                    match value {
+                        Value::Numeric(value) => ParameterValue::Numeric(value),
+                        Value::Enum(value) => ParameterValue::Enum(value),
+                        Value::Switch(value) => ParameterValue::Switch(value),
+                    },
+                )
+            })
+            .collect();
+        upgrade(saved_version, &mut values);
+        Snapshot {
+            values: values
+                .into_iter()
+                .map(|(id, value)| {
+                    (
+                        id,
+                        match value {
+                            ParameterValue::Numeric(value) => Value::Numeric(value),
+                            ParameterValue::Enum(value) => Value::Enum(value),
+                            ParameterValue::Switch(value) => Value::Switch(value),
+                        },
+                    )
+                })
+                .collect(),
+        }
+        .into_snapshot(all_params)
+        .map_err(|_| DeserializationError::Corrupted(SnapshotCorruptionError::InvalidUpgrade()))
+    }
+
     #[cfg(test)]
     fn into_snapshot_no_enums<'a>(
         self,
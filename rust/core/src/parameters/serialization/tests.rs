@@ -261,3 +261,154 @@ fn removing_enum_value_causes_too_new() {
         Err(super::DeserializationError::VersionTooNew())
     );
 }
+
+#[test]
+fn roundtrip_same_version_does_not_upgrade() {
+    let snapshot = Snapshot {
+        values: to_hash([("numeric", Value::Numeric(0.5))]),
+    };
+    let lookup = |_: &_| Some(super::WriteInfoRef::Numeric {});
+    let serialized = snapshot.clone().into_serialize_no_enum(lookup).unwrap();
+
+    let deserialized = serialized.into_snapshot_with_version(
+        2,
+        2,
+        [(
+            "numeric",
+            super::ReadInfoRef::Numeric::<std::iter::Empty<&str>> {
+                default: 0.0,
+                valid_range: 0.0..=1.0,
+            },
+        )],
+        |_, _| panic!("Should not upgrade a snapshot from the current version"),
+    );
+    assert_eq!(deserialized, Ok(snapshot));
+}
+
+#[test]
+fn older_version_is_upgraded() {
+    // In version 1, "gain" was a fraction from 0 to 1.
+    let snapshot = Snapshot {
+        values: to_hash([("gain", Value::Numeric(0.5))]),
+    };
+    let lookup = |_: &_| Some(super::WriteInfoRef::Numeric {});
+    let serialized = snapshot.into_serialize_no_enum(lookup).unwrap();
+
+    // In version 2, "gain" is a percentage from 0 to 100.
+    let mut upgraded_from = None;
+    let deserialized = serialized.into_snapshot_with_version(
+        1,
+        2,
+        [(
+            "gain",
+            super::ReadInfoRef::Numeric::<std::iter::Empty<&str>> {
+                default: 100.0,
+                valid_range: 0.0..=100.0,
+            },
+        )],
+        |version, values| {
+            upgraded_from = Some(version);
+            if let Some(Value::Numeric(gain)) = values.get_mut("gain") {
+                *gain *= 100.0;
+            }
+        },
+    );
+    assert_eq!(upgraded_from, Some(1));
+    assert_eq!(
+        deserialized,
+        Ok(Snapshot {
+            values: to_hash([("gain", Value::Numeric(50.0))])
+        })
+    );
+}
+
+#[test]
+fn newer_version_causes_too_new() {
+    let snapshot = Snapshot {
+        values: to_hash([("numeric", Value::Numeric(0.5))]),
+    };
+    let lookup = |_: &_| Some(super::WriteInfoRef::Numeric {});
+    let serialized = snapshot.into_serialize_no_enum(lookup).unwrap();
+
+    let deserialized = serialized.into_snapshot_with_version(
+        3,
+        2,
+        [(
+            "numeric",
+            super::ReadInfoRef::Numeric::<std::iter::Empty<&str>> {
+                default: 0.0,
+                valid_range: 0.0..=1.0,
+            },
+        )],
+        |_, _| panic!("Should not upgrade a snapshot from a newer version"),
+    );
+    assert_eq!(
+        deserialized,
+        Err(super::DeserializationError::VersionTooNew())
+    );
+}
+
+#[test]
+fn invalid_upgrade_causes_corrupted() {
+    let snapshot = Snapshot {
+        values: to_hash([("numeric", Value::Numeric(0.5))]),
+    };
+    let lookup = |_: &_| Some(super::WriteInfoRef::Numeric {});
+    let serialized = snapshot.into_serialize_no_enum(lookup).unwrap();
+
+    let deserialized = serialized.into_snapshot_with_version(
+        1,
+        2,
+        [(
+            "numeric",
+            super::ReadInfoRef::Numeric::<std::iter::Empty<&str>> {
+                default: 0.0,
+                valid_range: 0.0..=1.0,
+            },
+        )],
+        |_, values| {
+            values.insert("numeric".to_string(), Value::Switch(true));
+        },
+    );
+    assert_eq!(
+        deserialized,
+        Err(super::DeserializationError::Corrupted(
+            super::SnapshotCorruptionError::InvalidUpgrade()
+        ))
+    );
+}
+
+#[test]
+fn older_version_is_upgraded_before_decoding() {
+    // In version 1, "gain" was a fraction from 0 to 1 called "volume".
+    let snapshot = Snapshot {
+        values: to_hash([("volume", Value::Numeric(0.5))]),
+    };
+    let lookup = |_: &_| Some(super::WriteInfoRef::Numeric {});
+    let serialized = snapshot.into_serialize_no_enum(lookup).unwrap();
+
+    // In version 2, "gain" is a percentage from 1 to 100, so the saved
+    // value would be out of range if it weren't upgraded first.
+    let deserialized = serialized.into_snapshot_with_version(
+        1,
+        2,
+        [(
+            "gain",
+            super::ReadInfoRef::Numeric::<std::iter::Empty<&str>> {
+                default: 100.0,
+                valid_range: 1.0..=100.0,
+            },
+        )],
+        |_, values| {
+            if let Some(Value::Numeric(volume)) = values.remove("volume") {
+                values.insert("gain".to_string(), Value::Numeric(volume * 100.0));
+            }
+        },
+    );
+    assert_eq!(
+        deserialized,
+        Ok(Snapshot {
+            values: to_hash([("gain", Value::Numeric(50.0))])
+        })
+    );
+}
@@ -1,9 +1,13 @@
 use std::rc;
 
-use conformal_component::parameters::{Info, Value};
+use conformal_component::parameters::{default_value, Info, Value};
 
 pub trait Listener {
     fn parameter_changed(&self, unique_id: &str, value: &Value);
+
+    /// Called when the UI state is replaced from outside the UI, for example when
+    /// the host restores a saved state.
+    fn ui_state_changed(&self, state: &[u8]);
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -43,6 +47,29 @@ pub trait Store {
     ///  - Returns `InternalError` if the store is unable to set the value due to a bad internal state
     fn set_grabbed(&mut self, unique_id: &str, grabbed: bool) -> Result<(), SetGrabbedError>;
 
+    /// Set a parameter back to the default value from its info.
+    ///
+    /// This goes through the same path as `set`, so the host will see the change.
+    ///
+    /// # Errors
+    ///
+    ///  - Returns `NotFound` if the no parameter with the given `unique_id` is in the store.
+    ///  - Returns `InternalError` if the store is unable to set the value due to a bad internal state
+    fn reset_to_default(&mut self, unique_id: &str) -> Result<(), SetError> {
+        let info = self.get_info(unique_id).ok_or(SetError::NotFound)?;
+        self.set(unique_id, default_value(&info))
+    }
+
+    /// Get the UI state.
+    ///
+    /// This is an opaque blob of bytes that the UI can use to store state that
+    /// doesn't belong in parameters. It is saved and restored along with the rest
+    /// of the plug-in's state, and is empty if nothing has been stored.
+    fn get_ui_state(&self) -> Vec<u8>;
+
+    /// Replace the UI state.
+    fn set_ui_state(&mut self, state: &[u8]);
+
     /// Note that there can only be one listener at a time!
     fn set_listener(&mut self, listener: rc::Weak<dyn Listener>);
 }
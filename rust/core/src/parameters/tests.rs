@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+
+use super::{diff, Snapshot};
+use conformal_component::parameters::Value;
+
+fn snapshot<'a, I: IntoIterator<Item = (&'a str, Value)>>(i: I) -> Snapshot {
+    Snapshot {
+        values: i.into_iter().map(|(k, v)| (k.to_string(), v)).collect(),
+    }
+}
+
+#[test]
+fn diff_of_identical_snapshots_is_empty() {
+    let a = snapshot([
+        ("numeric", Value::Numeric(0.5)),
+        ("enum", Value::Enum("a".to_string())),
+        ("switch", Value::Switch(false)),
+    ]);
+    assert_eq!(diff(&a, &a.clone()), vec![]);
+}
+
+#[test]
+fn diff_of_empty_snapshots_is_empty() {
+    let a = Snapshot {
+        values: HashMap::new(),
+    };
+    assert_eq!(diff(&a, &a.clone()), vec![]);
+}
+
+#[test]
+fn diff_reports_changed_values() {
+    let before = snapshot([
+        ("numeric", Value::Numeric(0.5)),
+        ("enum", Value::Enum("a".to_string())),
+        ("switch", Value::Switch(false)),
+        ("unchanged", Value::Numeric(1.0)),
+    ]);
+    let after = snapshot([
+        ("numeric", Value::Numeric(0.75)),
+        ("enum", Value::Enum("b".to_string())),
+        ("switch", Value::Switch(true)),
+        ("unchanged", Value::Numeric(1.0)),
+    ]);
+    assert_eq!(
+        diff(&before, &after),
+        vec![
+            (
+                "enum".to_string(),
+                Value::Enum("a".to_string()),
+                Value::Enum("b".to_string())
+            ),
+            (
+                "numeric".to_string(),
+                Value::Numeric(0.5),
+                Value::Numeric(0.75)
+            ),
+            (
+                "switch".to_string(),
+                Value::Switch(false),
+                Value::Switch(true)
+            ),
+        ]
+    );
+}
+
+#[test]
+fn diff_is_reversible() {
+    let before = snapshot([("numeric", Value::Numeric(0.5))]);
+    let after = snapshot([("numeric", Value::Numeric(0.75))]);
+    assert_eq!(
+        diff(&after, &before),
+        vec![(
+            "numeric".to_string(),
+            Value::Numeric(0.75),
+            Value::Numeric(0.5)
+        )]
+    );
+}
+
+#[test]
+fn diff_ignores_parameters_in_only_one_snapshot() {
+    let before = snapshot([
+        ("removed", Value::Numeric(0.5)),
+        ("switch", Value::Switch(false)),
+    ]);
+    let after = snapshot([
+        ("added", Value::Numeric(0.5)),
+        ("switch", Value::Switch(true)),
+    ]);
+    assert_eq!(
+        diff(&before, &after),
+        vec![(
+            "switch".to_string(),
+            Value::Switch(false),
+            Value::Switch(true)
+        )]
+    );
+}
@@ -0,0 +1,119 @@
+use conformal_component::audio::LinearRamp;
+
+/// The default duration of the fades applied by [`ClickGuard`], in seconds.
+pub const DEFAULT_CLICK_GUARD_SECONDS: f32 = 0.002;
+
+/// A short fade applied at the start and end of notes to avoid clicks.
+///
+/// Voices that start notes abruptly (for example, by resetting an oscillator's
+/// phase and jumping to full gain) produce an audible click. This applies a
+/// linear fade in over a few milliseconds when a note starts, and a linear fade
+/// out when it stops.
+///
+/// To use this, call [`Self::note_on`] and [`Self::note_off`] at the sample where
+/// the note starts or stops, and pass the voice's output through
+/// [`Self::process`] (or multiply each sample by [`Self::next_gain`]).
+///
+/// This is independent of any envelope the voice has, and its gain should be
+/// multiplied with the envelope's. Since the fades are very short, they only
+/// affect the very start of the envelope's attack. Note that calling
+/// [`Self::note_off`] will silence the voice after the fade, cutting off any
+/// release stage. Voices with a release envelope should call it only when the
+/// release has finished or the note must stop immediately (for example, when
+/// the voice is stolen), and can otherwise rely on the envelope to fade out.
+///
+/// # Examples
+///
+/// ```
+/// # use conformal_poly::ClickGuard;
+/// let mut guard = ClickGuard::new_with_ramp_samples(4);
+/// let mut output = [1.0; 6];
+/// guard.note_on();
+/// guard.process(&mut output);
+/// assert_eq!(output, [0.25, 0.5, 0.75, 1.0, 1.0, 1.0]);
+///
+/// let mut output = [1.0; 6];
+/// guard.note_off();
+/// guard.process(&mut output);
+/// assert_eq!(output, [0.75, 0.5, 0.25, 0.0, 0.0, 0.0]);
+/// assert!(guard.silent());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClickGuard {
+    ramp: LinearRamp,
+    rising: bool,
+}
+
+impl ClickGuard {
+    /// Creates a new click guard that fades over [`DEFAULT_CLICK_GUARD_SECONDS`].
+    ///
+    /// The guard starts out silent.
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn new(sampling_rate: f32) -> Self {
+        Self::new_with_ramp_samples((sampling_rate * DEFAULT_CLICK_GUARD_SECONDS).round() as usize)
+    }
+
+    /// Creates a new click guard whose fades last `ramp_samples` samples.
+    ///
+    /// A `ramp_samples` of 0 is treated as 1, that is, no fade.
+    ///
+    /// The guard starts out silent.
+    #[must_use]
+    pub fn new_with_ramp_samples(ramp_samples: usize) -> Self {
+        Self {
+            ramp: LinearRamp::new(ramp_samples),
+            rising: false,
+        }
+    }
+
+    /// Starts fading in, for example when a new note starts.
+    ///
+    /// The fade always starts from silence.
+    pub fn note_on(&mut self) {
+        self.ramp.jump(false);
+        self.rising = true;
+    }
+
+    /// Jumps straight to full gain without fading in.
+    pub(crate) fn open(&mut self) {
+        self.ramp.jump(true);
+        self.rising = true;
+    }
+
+    /// Starts fading out from the current gain, for example when a note stops.
+    pub fn note_off(&mut self) {
+        self.rising = false;
+    }
+
+    /// Advances the fade by one sample and returns the gain for that sample.
+    pub fn next_gain(&mut self) -> f32 {
+        self.ramp.step(self.rising)
+    }
+
+    /// Applies the fade to a buffer of the voice's output, in place.
+    pub fn process(&mut self, output: &mut [f32]) {
+        for sample in output {
+            *sample *= self.next_gain();
+        }
+    }
+
+    /// Returns whether the guard has finished fading out.
+    ///
+    /// This can be used to help implement [`crate::Voice::quiescent`].
+    #[must_use]
+    pub fn silent(&self) -> bool {
+        !self.rising && self.ramp.is_zero()
+    }
+
+    /// Resets the guard to its initial, silent state.
+    ///
+    /// This can be used to implement [`crate::Voice::reset`].
+    pub fn reset(&mut self) {
+        self.ramp.reset();
+        self.rising = false;
+    }
+}
+
+#[cfg(test)]
+mod tests;
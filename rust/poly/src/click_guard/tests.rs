@@ -0,0 +1,60 @@
+use super::ClickGuard;
+use conformal_component::audio::approx_eq;
+
+const EPSILON: f32 = 1e-6;
+
+fn max_step(signal: &[f32]) -> f32 {
+    signal
+        .windows(2)
+        .map(|pair| (pair[1] - pair[0]).abs())
+        .fold(0.0, f32::max)
+}
+
+#[test]
+fn note_on_and_off_ramp_rather_than_jump() {
+    // 2ms at 48kHz is 96 samples.
+    let mut guard = ClickGuard::new(48000.0);
+    let mut output = vec![0.0; 256];
+    output[128..].fill(1.0);
+
+    // Starting from silence, the note starts partway through the buffer.
+    let (before, after) = output.split_at_mut(128);
+    guard.process(before);
+    guard.note_on();
+    guard.process(after);
+    assert!(output[128] > 0.0);
+    assert!(output[128] < 0.02);
+    assert!(approx_eq(output[128 + 95], 1.0, EPSILON));
+    assert!(max_step(&output) <= 1.0 / 96.0 + EPSILON);
+
+    let mut output = vec![1.0; 256];
+    guard.note_off();
+    guard.process(&mut output);
+    assert!(output[0] < 1.0);
+    assert!(output[0] > 0.98);
+    assert!(approx_eq(output[95], 0.0, EPSILON));
+    assert!(max_step(&output) <= 1.0 / 96.0 + EPSILON);
+    assert!(guard.silent());
+}
+
+#[test]
+fn note_off_during_fade_in_fades_from_current_gain() {
+    let mut guard = ClickGuard::new_with_ramp_samples(4);
+    guard.note_on();
+    assert!(approx_eq(guard.next_gain(), 0.25, EPSILON));
+    assert!(approx_eq(guard.next_gain(), 0.5, EPSILON));
+    guard.note_off();
+    assert!(approx_eq(guard.next_gain(), 0.25, EPSILON));
+    assert!(approx_eq(guard.next_gain(), 0.0, EPSILON));
+    assert!(guard.silent());
+}
+
+#[test]
+fn reset_silences() {
+    let mut guard = ClickGuard::new_with_ramp_samples(4);
+    guard.note_on();
+    guard.next_gain();
+    guard.reset();
+    assert!(guard.silent());
+    assert!(approx_eq(guard.next_gain(), 0.0, EPSILON));
+}
@@ -0,0 +1,232 @@
+use crate::EventData;
+
+/// The shape of the pitch trajectory when gliding from one note to another.
+///
+/// All curves start exactly at the note being glided from and end exactly at
+/// the note being glided to - they differ only in how they get there.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GlideCurve {
+    /// The pitch moves at a constant rate in semitones.
+    ///
+    /// This sounds like an even slide, and is the default.
+    #[default]
+    LinearPitch,
+
+    /// The frequency moves at a constant rate in Hz.
+    ///
+    /// Since pitch is logarithmic in frequency, upward glides will seem to start
+    /// fast and slow down at the end, and downward glides will do the opposite.
+    LinearFrequency,
+
+    /// The pitch approaches the new note exponentially.
+    ///
+    /// This moves quickly at first and settles gently into the new note,
+    /// similar to the portamento of many analog synthesizers.
+    Exponential,
+}
+
+/// How sharply [`GlideCurve::Exponential`] bends.
+///
+/// This is the number of time constants the curve covers over the whole glide.
+const EXPONENTIAL_TIME_CONSTANTS: f32 = 5.0;
+
+impl GlideCurve {
+    /// Returns the pitch at a point during a glide.
+    ///
+    /// `from` and `to` are pitches in semitones (for example, MIDI note numbers),
+    /// and `progress` is how far through the glide we are, from 0 (the start) to
+    /// 1 (the end). `progress` values outside this range are clamped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use conformal_poly::GlideCurve;
+    /// assert_eq!(GlideCurve::LinearPitch.pitch(60.0, 72.0, 0.5), 66.0);
+    /// for curve in [GlideCurve::LinearPitch, GlideCurve::LinearFrequency, GlideCurve::Exponential] {
+    ///     assert_eq!(curve.pitch(60.0, 72.0, 0.0), 60.0);
+    ///     assert_eq!(curve.pitch(60.0, 72.0, 1.0), 72.0);
+    /// }
+    /// ```
+    #[must_use]
+    pub fn pitch(self, from: f32, to: f32, progress: f32) -> f32 {
+        let progress = progress.clamp(0.0, 1.0);
+        if progress >= 1.0 {
+            return to;
+        }
+        match self {
+            GlideCurve::LinearPitch => from + (to - from) * progress,
+            GlideCurve::LinearFrequency => {
+                let ratio = ((to - from) / 12.0).exp2();
+                from + 12.0 * (1.0 + (ratio - 1.0) * progress).log2()
+            }
+            GlideCurve::Exponential => {
+                let shape = (1.0 - (-EXPONENTIAL_TIME_CONSTANTS * progress).exp())
+                    / (1.0 - (-EXPONENTIAL_TIME_CONSTANTS).exp());
+                from + (to - from) * shape
+            }
+        }
+    }
+}
+
+/// Decides which new notes glide from the previous note.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GlideMode {
+    /// Every note glides from the previous note played by the same voice. This is the default.
+    #[default]
+    Always,
+
+    /// Only [`EventData::Legato`] events glide, so notes played after all other
+    /// notes have been released start right at their pitch.
+    ///
+    /// This is only useful with [`crate::VoiceMode::Legato`].
+    LegatoOnly,
+}
+
+/// Tracks the pitch of a single voice as it glides from note to note.
+///
+/// Each voice should own one of these. Pass it the voice's events with
+/// [`Self::handle_event`], and call [`Self::next_pitch`] once per sample to get the
+/// pitch to play. When a note starts, the pitch ramps from wherever the voice was
+/// to the new note over the glide time, following a [`GlideCurve`]. Since this
+/// starts from the current pitch, a glide that interrupts another glide is smooth.
+///
+/// The first note after creation or [`Self::reset`] never glides, since there is
+/// no previous note to glide from.
+///
+/// Pitches are in semitones, as MIDI note numbers with the note's
+/// [`tuning`](`conformal_component::events::NoteData::tuning`) applied.
+///
+/// # Examples
+///
+/// ```
+/// # use conformal_poly::{EventData, Glide};
+/// # use conformal_component::events::{NoteData, NoteID};
+/// let note_on = |pitch| EventData::NoteOn {
+///     data: NoteData {
+///         id: NoteID::from_pitch(pitch),
+///         pitch,
+///         velocity: 1.0,
+///         tuning: 0.0,
+///         channel: 0,
+///     },
+/// };
+/// let mut glide = Glide::new(100.0);
+/// glide.set_time(0.04);
+///
+/// glide.handle_event(&note_on(60));
+/// assert_eq!(glide.next_pitch(), Some(60.0));
+///
+/// // The next note glides up over 4 samples.
+/// glide.handle_event(&note_on(64));
+/// let pitches: Vec<_> = std::iter::from_fn(|| glide.next_pitch()).take(6).collect();
+/// assert_eq!(pitches, [60.0, 61.0, 62.0, 63.0, 64.0, 64.0]);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Glide {
+    sampling_rate: f32,
+    time: f32,
+    curve: GlideCurve,
+    mode: GlideMode,
+
+    from: f32,
+    progress: f32,
+
+    /// The pitch of the current note, or `None` if no note has started yet.
+    to: Option<f32>,
+}
+
+impl Glide {
+    /// Creates a new glide with a glide time of 0, so notes don't glide until
+    /// [`Self::set_time`] is called.
+    #[must_use]
+    pub fn new(sampling_rate: f32) -> Self {
+        Self {
+            sampling_rate,
+            time: 0.0,
+            curve: GlideCurve::default(),
+            mode: GlideMode::default(),
+            from: 0.0,
+            progress: 1.0,
+            to: None,
+        }
+    }
+
+    /// Sets how long each glide takes, in seconds.
+    ///
+    /// This may be changed at any time, for example from a parameter once per buffer.
+    /// Changing the time during a glide changes the speed of the rest of that glide.
+    pub fn set_time(&mut self, seconds: f32) {
+        self.time = seconds.max(0.0);
+    }
+
+    /// Sets the shape of future glides.
+    pub fn set_curve(&mut self, curve: GlideCurve) {
+        self.curve = curve;
+    }
+
+    /// Sets which notes glide.
+    pub fn set_mode(&mut self, mode: GlideMode) {
+        self.mode = mode;
+    }
+
+    /// Handles an event sent to the voice.
+    ///
+    /// [`EventData::NoteOn`] and [`EventData::Legato`] events start a glide to the new note,
+    /// depending on the [`GlideMode`]. Note offs don't affect the pitch, so that the
+    /// note keeps its pitch while it is released.
+    pub fn handle_event(&mut self, event: &EventData) {
+        let (data, legato) = match event {
+            EventData::NoteOn { data } => (data, false),
+            EventData::Legato { data } => (data, true),
+            EventData::NoteOff { .. } => return,
+        };
+        let target = f32::from(data.pitch) + data.tuning / 100.0;
+        let glides = self.time > 0.0 && (legato || self.mode == GlideMode::Always);
+        match (self.current(), glides) {
+            (Some(current), true) => {
+                self.from = current;
+                self.progress = 0.0;
+            }
+            _ => self.progress = 1.0,
+        }
+        self.to = Some(target);
+    }
+
+    fn current(&self) -> Option<f32> {
+        self.to
+            .map(|to| self.curve.pitch(self.from, to, self.progress))
+    }
+
+    /// Returns the pitch for the current sample, and advances to the next sample.
+    ///
+    /// Returns `None` if no note has started since creation or the last [`Self::reset`].
+    pub fn next_pitch(&mut self) -> Option<f32> {
+        let current = self.current();
+        if self.progress < 1.0 {
+            let samples = self.time * self.sampling_rate;
+            self.progress = if samples > 1.0 {
+                (self.progress + 1.0 / samples).min(1.0)
+            } else {
+                1.0
+            };
+        }
+        current
+    }
+
+    /// Returns whether a glide is in progress.
+    #[must_use]
+    pub fn gliding(&self) -> bool {
+        self.to.is_some() && self.progress < 1.0
+    }
+
+    /// Forgets the previous note, so the next note starts without gliding.
+    ///
+    /// This can be used to implement [`crate::Voice::reset`].
+    pub fn reset(&mut self) {
+        self.progress = 1.0;
+        self.to = None;
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,213 @@
+use super::{Glide, GlideCurve, GlideMode};
+use crate::EventData;
+use conformal_component::{
+    audio::{all_approx_eq, approx_eq},
+    events::{NoteData, NoteID},
+};
+
+const CURVES: [GlideCurve; 3] = [
+    GlideCurve::LinearPitch,
+    GlideCurve::LinearFrequency,
+    GlideCurve::Exponential,
+];
+
+fn trajectory(curve: GlideCurve, from: f32, to: f32) -> Vec<f32> {
+    (0..=8u8)
+        .map(|i| curve.pitch(from, to, f32::from(i) / 8.0))
+        .collect()
+}
+
+#[test]
+fn curves_hit_endpoints() {
+    for curve in CURVES {
+        for (from, to) in [(48.0, 67.0), (67.0, 48.0)] {
+            let pitches = trajectory(curve, from, to);
+            assert!(approx_eq(pitches[0], from, 1e-4), "{curve:?}");
+            assert!(approx_eq(pitches[8], to, 1e-4), "{curve:?}");
+        }
+    }
+}
+
+#[test]
+fn curves_are_monotonic() {
+    for curve in CURVES {
+        let pitches = trajectory(curve, 48.0, 67.0);
+        assert!(pitches.windows(2).all(|w| w[0] < w[1]), "{curve:?}");
+        let pitches = trajectory(curve, 67.0, 48.0);
+        assert!(pitches.windows(2).all(|w| w[0] > w[1]), "{curve:?}");
+    }
+}
+
+#[test]
+fn linear_pitch_moves_evenly() {
+    let pitches = trajectory(GlideCurve::LinearPitch, 48.0, 64.0);
+    assert!(all_approx_eq(
+        pitches,
+        [48.0, 50.0, 52.0, 54.0, 56.0, 58.0, 60.0, 62.0, 64.0],
+        1e-4
+    ));
+}
+
+#[test]
+fn linear_frequency_moves_evenly_in_hz() {
+    let hz = |pitch: f32| 440.0 * ((pitch - 69.0) / 12.0).exp2();
+    let frequencies = trajectory(GlideCurve::LinearFrequency, 45.0, 69.0)
+        .into_iter()
+        .map(hz)
+        .collect::<Vec<_>>();
+    assert!(all_approx_eq(
+        frequencies,
+        [110.0, 151.25, 192.5, 233.75, 275.0, 316.25, 357.5, 398.75, 440.0],
+        1e-2
+    ));
+}
+
+#[test]
+fn exponential_moves_fastest_at_start() {
+    let pitches = trajectory(GlideCurve::Exponential, 48.0, 64.0);
+    let steps = pitches.windows(2).map(|w| w[1] - w[0]).collect::<Vec<_>>();
+    assert!(steps.windows(2).all(|w| w[0] > w[1]));
+
+    // Halfway through, we should be well past the halfway pitch.
+    assert!(pitches[4] > 60.0);
+}
+
+#[test]
+fn default_is_linear_pitch() {
+    assert_eq!(GlideCurve::default(), GlideCurve::LinearPitch);
+}
+
+fn note_data(pitch: u8) -> NoteData {
+    NoteData {
+        id: NoteID::from_pitch(pitch),
+        pitch,
+        velocity: 1.0,
+        tuning: 0.0,
+        channel: 0,
+    }
+}
+
+fn note_on(pitch: u8) -> EventData {
+    EventData::NoteOn {
+        data: note_data(pitch),
+    }
+}
+
+fn legato(pitch: u8) -> EventData {
+    EventData::Legato {
+        data: note_data(pitch),
+    }
+}
+
+fn note_off(pitch: u8) -> EventData {
+    EventData::NoteOff {
+        data: note_data(pitch),
+    }
+}
+
+/// Makes a glide that takes 4 samples.
+fn example_glide() -> Glide {
+    let mut glide = Glide::new(1000.0);
+    glide.set_time(0.004);
+    glide
+}
+
+fn pitches(glide: &mut Glide, count: usize) -> Vec<f32> {
+    (0..count).map(|_| glide.next_pitch().unwrap()).collect()
+}
+
+#[test]
+fn no_pitch_before_first_note() {
+    let mut glide = example_glide();
+    assert_eq!(glide.next_pitch(), None);
+}
+
+#[test]
+fn first_note_does_not_glide() {
+    let mut glide = example_glide();
+    glide.handle_event(&note_on(60));
+    assert!(!glide.gliding());
+    assert!(all_approx_eq(pitches(&mut glide, 2), [60.0, 60.0], 1e-4));
+}
+
+#[test]
+fn glides_between_notes() {
+    let mut glide = example_glide();
+    glide.handle_event(&note_on(60));
+    glide.handle_event(&note_on(64));
+    assert!(all_approx_eq(
+        pitches(&mut glide, 6),
+        [60.0, 61.0, 62.0, 63.0, 64.0, 64.0],
+        1e-4
+    ));
+}
+
+#[test]
+fn interrupted_glide_starts_from_current_pitch() {
+    let mut glide = example_glide();
+    glide.handle_event(&note_on(60));
+    glide.handle_event(&note_on(64));
+    pitches(&mut glide, 2);
+    glide.handle_event(&note_on(58));
+    assert!(all_approx_eq(
+        pitches(&mut glide, 5),
+        [62.0, 61.0, 60.0, 59.0, 58.0],
+        1e-4
+    ));
+}
+
+#[test]
+fn note_off_keeps_pitch() {
+    let mut glide = example_glide();
+    glide.handle_event(&note_on(60));
+    glide.handle_event(&note_off(60));
+    assert!(all_approx_eq(pitches(&mut glide, 1), [60.0], 1e-4));
+}
+
+#[test]
+fn tuning_is_applied() {
+    let mut glide = example_glide();
+    glide.handle_event(&EventData::NoteOn {
+        data: NoteData {
+            tuning: 50.0,
+            ..note_data(60)
+        },
+    });
+    assert!(all_approx_eq(pitches(&mut glide, 1), [60.5], 1e-4));
+}
+
+#[test]
+fn legato_only_mode_skips_fresh_notes() {
+    let mut glide = example_glide();
+    glide.set_mode(GlideMode::LegatoOnly);
+    glide.handle_event(&note_on(60));
+    glide.handle_event(&note_on(64));
+    assert!(all_approx_eq(pitches(&mut glide, 1), [64.0], 1e-4));
+
+    glide.handle_event(&legato(68));
+    assert!(all_approx_eq(
+        pitches(&mut glide, 5),
+        [64.0, 65.0, 66.0, 67.0, 68.0],
+        1e-4
+    ));
+}
+
+#[test]
+fn zero_time_does_not_glide() {
+    let mut glide = example_glide();
+    glide.set_time(0.0);
+    glide.handle_event(&note_on(60));
+    glide.handle_event(&note_on(64));
+    assert!(all_approx_eq(pitches(&mut glide, 1), [64.0], 1e-4));
+}
+
+#[test]
+fn reset_forgets_previous_note() {
+    let mut glide = example_glide();
+    glide.handle_event(&note_on(60));
+    glide.reset();
+    assert_eq!(glide.next_pitch(), None);
+    glide.handle_event(&note_on(64));
+    assert!(!glide.gliding());
+    assert!(all_approx_eq(pitches(&mut glide, 1), [64.0], 1e-4));
+}
@@ -0,0 +1,146 @@
+use conformal_component::events::{NoteData, NoteID};
+
+/// Decides which of several held notes should sound when only one can.
+///
+/// This is used by monophonic synths to pick the note to play, and to pick
+/// which note to return to when the sounding note is released.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NotePriority {
+    /// The most recently pressed note sounds.
+    ///
+    /// When it is released, the most recently pressed note that is still held
+    /// sounds instead. This is the default.
+    #[default]
+    Last,
+
+    /// The lowest held note sounds.
+    Lowest,
+
+    /// The highest held note sounds.
+    Highest,
+}
+
+/// Keeps track of the notes that are currently held down, in the order they were pressed.
+///
+/// To use this, call [`Self::note_on`] and [`Self::note_off`] as the corresponding
+/// events arrive, and use [`Self::current`] to find which note should be sounding
+/// under a given [`NotePriority`]. After a note off, [`Self::current`] returns the
+/// note that should sound next, or `None` if no notes are held.
+///
+/// When several held notes share the same pitch, the most recently pressed one
+/// is preferred.
+///
+/// Storage for all held notes is allocated up-front, so this never allocates
+/// after it is created. If more notes are held than the capacity, the oldest
+/// held note is forgotten.
+///
+/// # Examples
+///
+/// ```
+/// # use conformal_poly::{HeldNotes, NotePriority};
+/// # use conformal_component::events::{NoteData, NoteID};
+/// let note = |id, pitch| NoteData {
+///     id: NoteID::from_id(id),
+///     pitch,
+///     velocity: 1.0,
+///     tuning: 0.0,
+///     channel: 0,
+/// };
+/// let mut held = HeldNotes::new(8);
+/// held.note_on(note(0, 60));
+/// held.note_on(note(1, 64));
+/// held.note_on(note(2, 62));
+/// assert_eq!(held.current(NotePriority::Last).map(|n| n.pitch), Some(62));
+/// assert_eq!(held.current(NotePriority::Lowest).map(|n| n.pitch), Some(60));
+/// assert_eq!(held.current(NotePriority::Highest).map(|n| n.pitch), Some(64));
+///
+/// // After releasing the sounding note, we return to the previous one.
+/// held.note_off(NoteID::from_id(2));
+/// assert_eq!(held.current(NotePriority::Last).map(|n| n.pitch), Some(64));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeldNotes {
+    /// Held notes, from oldest to newest.
+    notes: Vec<NoteData>,
+    capacity: usize,
+}
+
+impl HeldNotes {
+    /// Creates a new, empty set of held notes that can remember up to `capacity` notes.
+    ///
+    /// A `capacity` of 0 is treated as 1.
+    #[must_use]
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            notes: Vec::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Records that a note has been pressed.
+    ///
+    /// If a note with the same ID is already held, it is replaced and counts as
+    /// the most recently pressed note.
+    pub fn note_on(&mut self, data: NoteData) {
+        self.remove(data.id);
+        if self.notes.len() == self.capacity {
+            self.notes.remove(0);
+        }
+        self.notes.push(data);
+    }
+
+    /// Records that a note has been released.
+    ///
+    /// Returns `true` if the note was held.
+    pub fn note_off(&mut self, id: NoteID) -> bool {
+        self.remove(id)
+    }
+
+    /// Returns the note that should sound under `priority`, or `None` if no notes are held.
+    #[must_use]
+    pub fn current(&self, priority: NotePriority) -> Option<&NoteData> {
+        match priority {
+            NotePriority::Last => self.notes.last(),
+            // `min_by_key` picks the first of equal notes, so search newest to oldest.
+            NotePriority::Lowest => self.notes.iter().rev().min_by_key(|note| note.pitch),
+            // `max_by_key` picks the last of equal notes, so search oldest to newest.
+            NotePriority::Highest => self.notes.iter().max_by_key(|note| note.pitch),
+        }
+    }
+
+    /// Returns whether a note with the given ID is held.
+    #[must_use]
+    pub fn contains(&self, id: NoteID) -> bool {
+        self.notes.iter().any(|note| note.id == id)
+    }
+
+    /// Returns the number of held notes.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.notes.len()
+    }
+
+    /// Returns `true` if no notes are held.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.notes.is_empty()
+    }
+
+    /// Forgets all held notes, for example when processing is reset.
+    pub fn clear(&mut self) {
+        self.notes.clear();
+    }
+
+    fn remove(&mut self, id: NoteID) -> bool {
+        if let Some(index) = self.notes.iter().position(|note| note.id == id) {
+            self.notes.remove(index);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,179 @@
+use super::{HeldNotes, NotePriority};
+use conformal_component::events::{NoteData, NoteID};
+
+fn note(id: i32, pitch: u8) -> NoteData {
+    NoteData {
+        id: NoteID::from_id(id),
+        pitch,
+        velocity: 1.0,
+        tuning: 0.0,
+        channel: 0,
+    }
+}
+
+fn current_pitch(held: &HeldNotes, priority: NotePriority) -> Option<u8> {
+    held.current(priority).map(|note| note.pitch)
+}
+
+/// Plays an overlapping sequence of notes, returning the sounding pitch after each event.
+fn sounding_pitches(priority: NotePriority) -> Vec<Option<u8>> {
+    let mut held = HeldNotes::new(16);
+    let mut ret = vec![];
+    held.note_on(note(0, 60));
+    ret.push(current_pitch(&held, priority));
+    held.note_on(note(1, 67));
+    ret.push(current_pitch(&held, priority));
+    held.note_on(note(2, 55));
+    ret.push(current_pitch(&held, priority));
+    held.note_on(note(3, 64));
+    ret.push(current_pitch(&held, priority));
+    held.note_off(NoteID::from_id(3));
+    ret.push(current_pitch(&held, priority));
+    held.note_off(NoteID::from_id(2));
+    ret.push(current_pitch(&held, priority));
+    held.note_off(NoteID::from_id(1));
+    ret.push(current_pitch(&held, priority));
+    held.note_off(NoteID::from_id(0));
+    ret.push(current_pitch(&held, priority));
+    ret
+}
+
+#[test]
+fn defaults_to_last() {
+    assert_eq!(NotePriority::default(), NotePriority::Last);
+}
+
+#[test]
+fn last_priority_with_overlapping_notes() {
+    assert_eq!(
+        sounding_pitches(NotePriority::Last),
+        vec![
+            Some(60),
+            Some(67),
+            Some(55),
+            Some(64),
+            Some(55),
+            Some(67),
+            Some(60),
+            None
+        ]
+    );
+}
+
+#[test]
+fn lowest_priority_with_overlapping_notes() {
+    assert_eq!(
+        sounding_pitches(NotePriority::Lowest),
+        vec![
+            Some(60),
+            Some(60),
+            Some(55),
+            Some(55),
+            Some(55),
+            Some(60),
+            Some(60),
+            None
+        ]
+    );
+}
+
+#[test]
+fn highest_priority_with_overlapping_notes() {
+    assert_eq!(
+        sounding_pitches(NotePriority::Highest),
+        vec![
+            Some(60),
+            Some(67),
+            Some(67),
+            Some(67),
+            Some(67),
+            Some(67),
+            Some(60),
+            None
+        ]
+    );
+}
+
+#[test]
+fn last_priority_returns_to_most_recent_remaining_note() {
+    let mut held = HeldNotes::new(16);
+    held.note_on(note(0, 60));
+    held.note_on(note(1, 62));
+    held.note_on(note(2, 64));
+    // Releasing a note that isn't sounding doesn't change the sounding note
+    assert!(held.note_off(NoteID::from_id(1)));
+    assert_eq!(current_pitch(&held, NotePriority::Last), Some(64));
+    // But we skip over it when the sounding note is released.
+    assert!(held.note_off(NoteID::from_id(2)));
+    assert_eq!(current_pitch(&held, NotePriority::Last), Some(60));
+}
+
+#[test]
+fn equal_pitches_prefer_most_recent() {
+    let mut held = HeldNotes::new(16);
+    held.note_on(note(0, 60));
+    held.note_on(note(1, 60));
+    for priority in [
+        NotePriority::Last,
+        NotePriority::Lowest,
+        NotePriority::Highest,
+    ] {
+        assert_eq!(
+            held.current(priority).map(|n| n.id),
+            Some(NoteID::from_id(1))
+        );
+    }
+    held.note_off(NoteID::from_id(1));
+    for priority in [
+        NotePriority::Last,
+        NotePriority::Lowest,
+        NotePriority::Highest,
+    ] {
+        assert_eq!(
+            held.current(priority).map(|n| n.id),
+            Some(NoteID::from_id(0))
+        );
+    }
+}
+
+#[test]
+fn repeated_note_on_moves_note_to_most_recent() {
+    let mut held = HeldNotes::new(16);
+    held.note_on(note(0, 60));
+    held.note_on(note(1, 64));
+    held.note_on(note(0, 60));
+    assert_eq!(held.len(), 2);
+    assert_eq!(current_pitch(&held, NotePriority::Last), Some(60));
+    held.note_off(NoteID::from_id(0));
+    assert_eq!(current_pitch(&held, NotePriority::Last), Some(64));
+}
+
+#[test]
+fn unknown_note_off_is_ignored() {
+    let mut held = HeldNotes::new(16);
+    held.note_on(note(0, 60));
+    assert!(!held.note_off(NoteID::from_id(1)));
+    assert!(held.contains(NoteID::from_id(0)));
+    assert_eq!(held.len(), 1);
+}
+
+#[test]
+fn over_capacity_forgets_oldest() {
+    let mut held = HeldNotes::new(2);
+    held.note_on(note(0, 48));
+    held.note_on(note(1, 60));
+    held.note_on(note(2, 64));
+    assert_eq!(held.len(), 2);
+    assert!(!held.contains(NoteID::from_id(0)));
+    assert_eq!(current_pitch(&held, NotePriority::Lowest), Some(60));
+}
+
+#[test]
+fn clear_forgets_all_notes() {
+    let mut held = HeldNotes::new(16);
+    held.note_on(note(0, 60));
+    held.note_on(note(1, 64));
+    held.clear();
+    assert!(held.is_empty());
+    assert_eq!(current_pitch(&held, NotePriority::Last), None);
+}
@@ -17,25 +17,18 @@
 #![doc = include_str!("../docs_boilerplate.md")]
 #![doc = include_str!("../README.md")]
 
+use std::borrow::Borrow;
+
 use self::state::State;
 use conformal_component::{
-    audio::{channels_mut, BufferMut},
+    audio::{
+        add_in_place, channels_mut, scale_in_place, slice_buffer, slice_buffer_mut, Buffer,
+        BufferData, BufferMut, ChannelLayout,
+    },
     events::{Data, Event as CEvent, NoteData},
-    parameters, ProcessingEnvironment,
+    parameters, ProcessingEnvironment, Transport,
 };
 
-fn add_in_place(x: &[f32], y: &mut [f32]) {
-    for (x, y) in x.iter().zip(y.iter_mut()) {
-        *y += *x;
-    }
-}
-
-fn mul_constant_in_place(x: f32, y: &mut [f32]) {
-    for y in y.iter_mut() {
-        *y *= x;
-    }
-}
-
 /// The data associated with an event.
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum EventData {
@@ -49,6 +42,16 @@ pub enum EventData {
         /// The data associated with the note.
         data: NoteData,
     },
+    /// This event is sent in [`VoiceMode::Legato`] when the note a voice is playing
+    /// changes without the voice being released.
+    ///
+    /// This happens when a new note starts while another is held, or when the sounding
+    /// note is released while an earlier note is still held. Voices should move to the
+    /// new note (for example, by changing pitch or gliding) without retriggering envelopes.
+    Legato {
+        /// The data associated with the new note.
+        data: NoteData,
+    },
 }
 
 /// An event sent to a voice at a particular time.
@@ -177,9 +180,48 @@ impl<I: Iterator<Item = NoteExpressionPoint> + Clone> NoteExpressionCurve<I> {
     }
 }
 
-// Optimization opportunity - allow `Voice` to indicate that not all output
-// was filled. This will let us skip rendering until a voice is playing
-// and also skip mixing silence.
+impl NoteExpressionCurve<std::vec::IntoIter<NoteExpressionPoint>> {
+    /// Creates a note expression curve from points that may be unsorted or may not start at 0.
+    ///
+    /// The curve starts at `initial_state`, followed by `events` sorted by time. Events at
+    /// the same time keep their relative order, so later events take precedence. This
+    /// includes events at time 0, which take precedence over `initial_state`.
+    ///
+    /// This is useful for building curves for tests or generative control, without having
+    /// to uphold the invariants required by [`Self::new`]. Note that this allocates, so
+    /// it should not be used during processing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use conformal_poly::{NoteExpressionCurve, NoteExpressionPoint, NoteExpressionState};
+    /// let bent = NoteExpressionState { pitch_bend: 2.0, ..Default::default() };
+    /// let curve = NoteExpressionCurve::from_events(
+    ///     [NoteExpressionPoint { sample_offset: 2, state: bent }],
+    ///     Default::default(),
+    /// );
+    /// assert!(curve
+    ///     .iter_by_sample()
+    ///     .take(4)
+    ///     .map(|state| state.pitch_bend)
+    ///     .eq([0.0, 0.0, 2.0, 2.0]));
+    /// ```
+    #[must_use]
+    #[allow(clippy::missing_panics_doc)] // We always insert a point at 0, so this can't panic.
+    pub fn from_events(
+        events: impl IntoIterator<Item = NoteExpressionPoint>,
+        initial_state: NoteExpressionState,
+    ) -> Self {
+        let mut points: Vec<_> = std::iter::once(NoteExpressionPoint {
+            sample_offset: 0,
+            state: initial_state,
+        })
+        .chain(events)
+        .collect();
+        points.sort_by_key(|point| point.sample_offset);
+        Self::new(points.into_iter()).unwrap()
+    }
+}
 
 /// A single voice in a polyphonic synth.
 pub trait Voice {
@@ -199,6 +241,11 @@ pub trait Voice {
     ///
     /// Audio for the voice will be written into the `output` buffer, which will
     /// start out filled with silence.
+    ///
+    /// Returns the number of leading samples of `output` that were rendered. The
+    /// rest of the buffer is treated as silence, and [`Poly`] skips mixing it. This lets
+    /// voices that stop partway through a buffer save work. Voices that always fill
+    /// the whole buffer should return `output.len()`.
     fn process(
         &mut self,
         events: impl IntoIterator<Item = Event>,
@@ -206,13 +253,15 @@ pub trait Voice {
         note_expressions: NoteExpressionCurve<impl Iterator<Item = NoteExpressionPoint> + Clone>,
         data: Self::SharedData<'_>,
         output: &mut [f32],
-    );
+    ) -> usize;
 
     /// Returns whether this voice is currently outputng audio.
     ///
     /// When this returns `true`, [`process`](`Voice::process`) will not be called for this
     /// voice again until a new note is started. This can improve performance by
     /// allowing voices to skip processing.
+    ///
+    /// [`QuiescenceTracker`] can help implement this for voices whose output decays slowly.
     #[must_use]
     fn quiescent(&self) -> bool;
 
@@ -223,8 +272,33 @@ pub trait Voice {
     /// of audio.
     fn skip_samples(&mut self, _num_samples: usize) {}
 
+    /// Returns an estimate of how loud this voice currently is, as a linear amplitude.
+    ///
+    /// This is used to pick a voice to steal under [`StealStrategy::Quietest`]. [`Poly`]
+    /// reads it once at the start of each buffer, so it only needs to be accurate to
+    /// within a buffer. [`QuiescenceTracker::recent_peak`] can be used to implement this.
+    ///
+    /// The default implementation returns 0, so all voices are equally quiet and
+    /// the oldest note is stolen.
+    #[must_use]
+    fn output_level(&self) -> f32 {
+        0.0
+    }
+
     /// Resets the voice to its initial state.
     fn reset(&mut self);
+
+    /// Sets the seed this voice should use for any random variation.
+    ///
+    /// [`Poly`] calls this exactly once for each voice, right after [`new`](`Voice::new`).
+    /// Each voice gets a different seed, derived deterministically from its index and
+    /// the seed passed to [`Poly::new_with_seed`]. Voices that emulate analog-style
+    /// voice-to-voice variation (for example, small offsets to tuning, filter cutoff, or
+    /// level) can derive that variation from this seed. The variation should be kept
+    /// across calls to [`reset`](`Voice::reset`), so that output is reproducible.
+    ///
+    /// The default implementation does nothing.
+    fn set_seed(&mut self, _seed: u64) {}
 }
 
 /// A helper struct for implementing polyphonic synths.
@@ -234,10 +308,34 @@ pub trait Voice {
 ///
 /// To use it, you must implement the [`Voice`] trait for your synth. Then, use the methods
 /// on this struct to implement the required [`conformal_component::synth::Synth`] trait methods.
+///
+/// Note expression state is tracked per voice, so at most [`Self::max_expressive_notes`]
+/// notes have their expressions tracked at once. When more notes are playing than there
+/// are voices, a note is stolen according to the [`StealStrategy`] (by default, the oldest
+/// note), and its expression state is dropped along with it.
+/// Any further expressions for the stolen note are ignored, and the note that replaced it
+/// starts from neutral expression.
 pub struct Poly<V> {
     voices: Vec<V>,
     state: State,
-    voice_scratch_buffer: Vec<f32>,
+    voice_scratch_buffer: BufferData,
+
+    /// The sum of all voices, before it is copied to each output channel.
+    mix_buffer: BufferData,
+
+    /// Fades out the mix while we're flushing.
+    flush_guard: ClickGuard,
+    flushing: bool,
+
+    /// Whether the host's transport was playing, as of the last [`Poly::set_transport`].
+    transport_playing: bool,
+
+    summing_mode: VoiceSummingMode,
+
+    pedals: Option<Pedals>,
+
+    /// Scratch space for the events after the pedals have been applied.
+    pedal_events: Vec<CEvent>,
 }
 
 impl<V: std::fmt::Debug> std::fmt::Debug for Poly<V> {
@@ -251,63 +349,402 @@ impl<V: std::fmt::Debug> std::fmt::Debug for Poly<V> {
 
 mod state;
 
+/// Decides which playing note is stolen when a new note starts and all voices are busy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StealStrategy {
+    /// The note that started longest ago is stolen. This is the default.
+    #[default]
+    Oldest,
+
+    /// The note on the voice with the lowest [`Voice::output_level`] is stolen.
+    ///
+    /// This steals notes that have mostly decayed before louder ones, which is less
+    /// noticeable. If several voices are equally quiet, the oldest of them is stolen.
+    Quietest,
+}
+
+/// The maximum number of events in each buffer that [`Poly`] applies the pedals to.
+///
+/// So that applying the pedals never allocates, any events past this many in a single
+/// buffer are dropped. This is far more events than hosts send in a buffer in practice.
+pub const PEDAL_EVENTS_CAPACITY: usize = 1024;
+
+/// Decides how the outputs of the voices are scaled when they are mixed together.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum VoiceSummingMode {
+    /// Each voice is scaled by one over the total number of voices. This is the default.
+    ///
+    /// This guarantees the mix can't be louder than a single voice at full level, but
+    /// makes synths with many voices quiet when only a few notes are playing.
+    #[default]
+    DivideByVoices,
+
+    /// Voices are summed at unity gain.
+    ///
+    /// The mix may be much louder than a single voice, so synths using this
+    /// will usually want to apply their own gain staging or limiting.
+    NoScaling,
+
+    /// Each voice is scaled by a fixed linear gain.
+    FixedScale(f32),
+}
+
+/// Decides how notes are assigned to voices.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VoiceMode {
+    /// Each note is played by its own voice. This is the default.
+    #[default]
+    Poly,
+
+    /// Only the first voice is used, and each new note retriggers it.
+    ///
+    /// Held notes form a last-note-priority stack: when the sounding note is released
+    /// while earlier notes are still held, the voice is retriggered with the most
+    /// recently pressed of those notes. Releasing a note that isn't sounding has no effect.
+    ///
+    /// Retriggering sends the voice an [`EventData::NoteOff`] for the old note followed by an
+    /// [`EventData::NoteOn`] for the new one, at the same time.
+    Mono,
+
+    /// Like [`Self::Mono`], but moving between overlapping notes sends the voice an
+    /// [`EventData::Legato`] rather than retriggering it.
+    ///
+    /// A note that starts while no other notes are held still sends [`EventData::NoteOn`].
+    Legato,
+}
+
+/// The number of held notes [`Poly`] remembers in [`VoiceMode::Mono`] and [`VoiceMode::Legato`].
+///
+/// If more notes are held than this, the oldest held note is forgotten, so it
+/// won't sound again when later notes are released.
+pub const MONO_NOTE_STACK_SIZE: usize = 32;
+
+/// Derives the seed for a single voice from the seed of the whole [`Poly`].
+///
+/// This uses the `SplitMix64` finalizer so that nearby voice indices get unrelated seeds.
+fn voice_seed(seed: u64, index: usize) -> u64 {
+    let mut z = seed.wrapping_add((index as u64 + 1).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+mod click_guard;
+pub use click_guard::{ClickGuard, DEFAULT_CLICK_GUARD_SECONDS};
+
+mod glide;
+pub use glide::{Glide, GlideCurve, GlideMode};
+
+mod held_notes;
+pub use held_notes::{HeldNotes, NotePriority};
+
+mod mod_matrix;
+pub use mod_matrix::{ModMatrix, ModRoute};
+
+mod pedals;
+pub use pedals::{Pedals, DEFAULT_SOFT_PEDAL_VELOCITY_SCALE};
+
+mod quiescence;
+pub use quiescence::{QuiescenceTracker, DEFAULT_QUIESCENCE_THRESHOLD_DB};
+
+mod wavetable;
+pub use wavetable::{Interpolation, Wavetable, WavetableOscillator};
+
 impl<V: Voice> Poly<V> {
     /// Creates a new [`Poly`] struct.
+    ///
+    /// This is the same as [`Self::new_with_seed`] with a seed of `0`.
     #[must_use]
     pub fn new(environment: &ProcessingEnvironment, max_voices: usize) -> Self {
-        let voices = std::iter::repeat_with(|| {
-            V::new(
-                environment.max_samples_per_process_call,
-                environment.sampling_rate,
-            )
-        })
-        .take(max_voices)
-        .collect();
-        let state = State::new(max_voices);
+        Self::new_with_seed(environment, max_voices, 0)
+    }
+
+    /// Creates a new [`Poly`] struct, giving each voice a seed derived from `seed`.
+    ///
+    /// Each voice receives its own seed through [`Voice::set_seed`], derived
+    /// deterministically from `seed` and the voice's index. This means that two
+    /// [`Poly`]s created with the same `seed` will have the same voice-to-voice variation.
+    ///
+    /// When rendering offline (see [`conformal_component::ProcessingMode`]), you should
+    /// pin `seed` to a fixed value so that the output is identical between renders.
+    /// In realtime, you may choose to randomize it instead.
+    #[must_use]
+    pub fn new_with_seed(
+        environment: &ProcessingEnvironment,
+        max_voices: usize,
+        seed: u64,
+    ) -> Self {
+        Self::new_with_state(environment, max_voices, State::new(max_voices), seed)
+    }
+
+    /// Creates a new [`Poly`] struct whose voices are partitioned into pools by MIDI channel.
+    ///
+    /// This is useful for multitimbral or drum synths, where notes on each channel
+    /// should be played by a different part of the synth. `voices_per_channel[i]` is the
+    /// number of voices reserved for notes on channel `i` (see
+    /// [`conformal_component::events::NoteData::channel`]). Notes are only ever routed to
+    /// voices in the pool for their channel, and voices are only stolen from within that
+    /// pool. Notes on channels past the end of `voices_per_channel`, or with no voices
+    /// reserved, are ignored.
+    ///
+    /// Wrappers only deliver note channels to synths that opt in to them; otherwise,
+    /// every note arrives on channel 0.
+    ///
+    /// Voices are ordered by channel, so voices `0..voices_per_channel[0]` play channel 0,
+    /// the next `voices_per_channel[1]` voices play channel 1, and so on.
+    /// Voices are seeded the same way as in [`Self::new`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are no voices in total, or if `voices_per_channel` has more
+    /// than 16 entries, one for each MIDI channel.
+    #[must_use]
+    pub fn new_partitioned_by_channel(
+        environment: &ProcessingEnvironment,
+        voices_per_channel: &[usize],
+    ) -> Self {
+        Self::new_with_state(
+            environment,
+            voices_per_channel.iter().sum(),
+            State::new_partitioned_by_channel(voices_per_channel),
+            0,
+        )
+    }
+
+    fn new_with_state(
+        environment: &ProcessingEnvironment,
+        max_voices: usize,
+        state: State,
+        seed: u64,
+    ) -> Self {
+        let voices = (0..max_voices)
+            .map(|index| {
+                let mut voice = V::new(
+                    environment.max_samples_per_process_call,
+                    environment.sampling_rate,
+                );
+                voice.set_seed(voice_seed(seed, index));
+                voice
+            })
+            .collect();
 
         Self {
             voices,
             state,
-            voice_scratch_buffer: vec![0f32; environment.max_samples_per_process_call],
+            voice_scratch_buffer: BufferData::new(
+                ChannelLayout::Mono,
+                environment.max_samples_per_process_call,
+            ),
+            mix_buffer: BufferData::new(
+                ChannelLayout::Mono,
+                environment.max_samples_per_process_call,
+            ),
+            flush_guard: ClickGuard::new(environment.sampling_rate),
+            flushing: false,
+            transport_playing: false,
+            summing_mode: VoiceSummingMode::default(),
+            pedals: None,
+            pedal_events: Vec::new(),
         }
     }
 
+    /// Sets how notes are stolen when a new note starts and all voices are busy.
+    ///
+    /// By default, [`StealStrategy::Oldest`] is used.
+    pub fn set_steal_strategy(&mut self, strategy: StealStrategy) {
+        self.state.set_steal_strategy(strategy);
+    }
+
+    /// Sets how voices are scaled when they are mixed together.
+    ///
+    /// By default, [`VoiceSummingMode::DivideByVoices`] is used. This is usually set once,
+    /// right after creating the [`Poly`]. The scale only depends on the mode and the
+    /// number of voices, so output stays deterministic regardless of when it is changed.
+    pub fn set_summing_mode(&mut self, mode: VoiceSummingMode) {
+        self.summing_mode = mode;
+    }
+
+    /// Sets how notes are assigned to voices.
+    ///
+    /// By default, [`VoiceMode::Poly`] is used. In [`VoiceMode::Mono`] and [`VoiceMode::Legato`],
+    /// only the first voice plays notes, and notes on channels it can't play (see
+    /// [`Self::new_partitioned_by_channel`]) are ignored.
+    ///
+    /// Changing the mode doesn't stop notes that are already playing, but they won't
+    /// be part of the held note stack of the new mode. To start the new mode cleanly,
+    /// call [`Self::flush`] or [`Self::reset`] as well.
+    pub fn set_voice_mode(&mut self, mode: VoiceMode) {
+        self.state.set_voice_mode(mode);
+    }
+
+    /// Sets the pedal model applied to incoming notes, or `None` to ignore the pedals.
+    ///
+    /// By default, pedals are ignored, and voices receive note events exactly as the host
+    /// sent them. With a [`Pedals`] model, note offs are deferred while the sustain or
+    /// sostenuto pedals hold them, and velocities are scaled down while the soft pedal is
+    /// held. See [`Pedals`] for details.
+    ///
+    /// While pedals are set, only the first [`PEDAL_EVENTS_CAPACITY`] events of each
+    /// buffer are played, and any further events are dropped.
+    ///
+    /// This allocates, so it should be called outside of audio processing.
+    pub fn set_pedals(&mut self, pedals: Option<Pedals>) {
+        // Applying the pedals can add a note off for each note they were holding.
+        self.pedal_events = match &pedals {
+            Some(pedals) => Vec::with_capacity(PEDAL_EVENTS_CAPACITY + pedals.capacity()),
+            None => Vec::new(),
+        };
+        self.pedals = pedals;
+    }
+
+    /// Returns the number of voices that are currently sounding.
+    ///
+    /// A voice is sounding if it is playing a note, or if it is still producing audio
+    /// after its note was released (that is, it isn't [`Voice::quiescent`]).
+    ///
+    /// This doesn't allocate, so it can be called from the audio thread, for example
+    /// to report voice activity to a user interface.
+    #[must_use]
+    pub fn active_voice_count(&self) -> usize {
+        self.voice_notes()
+            .zip(self.voices.iter())
+            .filter(|((_, note), voice)| note.is_some() || !voice.quiescent())
+            .count()
+    }
+
+    /// Returns the note each voice is playing, as `(voice_index, note)` pairs in order
+    /// of voice index.
+    ///
+    /// `note` is `None` for voices that aren't playing a note, including voices whose
+    /// note has been released but that are still sounding.
+    ///
+    /// As with [`Self::active_voice_count`], this doesn't allocate.
+    pub fn voice_notes(&self) -> impl Iterator<Item = (usize, Option<NoteData>)> + '_ {
+        self.state.notes().enumerate()
+    }
+
+    /// Returns the maximum number of notes whose note expressions are tracked at once.
+    ///
+    /// This is always the number of voices, since each voice tracks the expression
+    /// state of the note it is playing.
+    #[must_use]
+    pub fn max_expressive_notes(&self) -> usize {
+        self.voices.len()
+    }
+
     /// Handles a set of events without rendering audio.
     ///
     /// This can be used to implement [`conformal_component::synth::Synth::handle_events`].
     pub fn handle_events(&mut self, events: impl IntoIterator<Item = Data> + Clone) {
-        for (v, ev) in self
-            .state
-            .clone()
-            .dispatch_events(events.clone().into_iter().map(|data| CEvent {
-                sample_offset: 0,
-                data,
-            }))
-        {
+        if let Some(pedals) = &mut self.pedals {
+            let mut pedal_events = std::mem::take(&mut self.pedal_events);
+            pedal_events.clear();
+            for data in events.into_iter().take(PEDAL_EVENTS_CAPACITY) {
+                pedals.handle_event(
+                    CEvent {
+                        sample_offset: 0,
+                        data,
+                    },
+                    &mut pedal_events,
+                );
+            }
+            self.handle_events_inner(|| pedal_events.iter());
+            self.pedal_events = pedal_events;
+        } else {
+            self.handle_events_inner(|| {
+                events.clone().into_iter().map(|data| CEvent {
+                    sample_offset: 0,
+                    data,
+                })
+            });
+        }
+    }
+
+    /// `events` is called once for each pass over the events.
+    fn handle_events_inner<I: IntoIterator<Item = impl Borrow<CEvent>>>(
+        &mut self,
+        events: impl Fn() -> I,
+    ) {
+        self.state
+            .set_output_levels(self.voices.iter().map(Voice::output_level));
+        for (v, ev) in self.state.clone().dispatch_events(events()) {
             self.voices[v].handle_event(&ev.data);
         }
 
-        self.state.update(events.into_iter().map(|data| CEvent {
-            sample_offset: 0,
-            data,
-        }));
+        self.state.update(events());
     }
 
     /// Renders the audio for the synth.
     ///
     /// This can be used to implement [`conformal_component::synth::Synth::process`].
     /// For any voices with active notes, [`Voice::process`] will be called.
+    ///
+    /// Each voice will receive a clone of `shared_data`. If the shared data needs
+    /// to be advanced every buffer, consider [`Self::process_with_shared_data`] instead.
+    ///
+    /// The output is mixed by starting from silence and adding the output of each
+    /// active voice in order of voice index, and then scaling the mix according to
+    /// the [`VoiceSummingMode`]. This order is stable, so the same events always produce bit-identical
+    /// output. Which voice plays a note depends on voice allocation, so the same notes
+    /// landing in different voices may change the output by floating-point rounding
+    /// error, but no more than that.
     pub fn process(
         &mut self,
         events: impl Iterator<Item = CEvent> + Clone,
         params: &impl parameters::BufferStates,
         shared_data: &V::SharedData<'_>,
         output: &mut impl BufferMut,
+    ) {
+        self.process_with_shared_data(events, params, |_| shared_data.clone(), output);
+    }
+
+    /// Renders the audio for the synth, producing the shared data once for this buffer.
+    ///
+    /// This behaves like [`Self::process`], except that the shared data is produced by
+    /// calling `shared_data` with the number of frames in the buffer. This is called
+    /// exactly once per call, before any voice is processed, and even if no voices
+    /// are currently active. This makes it a good place to advance global modulation
+    /// sources such as LFOs, so that they stay phase-consistent across voices and are
+    /// not recomputed for each voice.
+    pub fn process_with_shared_data<'a>(
+        &mut self,
+        events: impl Iterator<Item = CEvent> + Clone,
+        params: &impl parameters::BufferStates,
+        shared_data: impl FnOnce(usize) -> V::SharedData<'a>,
+        output: &mut impl BufferMut,
+    ) {
+        if let Some(pedals) = &mut self.pedals {
+            let mut pedal_events = std::mem::take(&mut self.pedal_events);
+            pedals.process(
+                events.take(PEDAL_EVENTS_CAPACITY),
+                params,
+                &mut pedal_events,
+            );
+            self.render(pedal_events.iter(), params, shared_data, output);
+            self.pedal_events = pedal_events;
+        } else {
+            self.render(events, params, shared_data, output);
+        }
+    }
+
+    fn render<'a>(
+        &mut self,
+        events: impl Iterator<Item = impl Borrow<CEvent>> + Clone,
+        params: &impl parameters::BufferStates,
+        shared_data: impl FnOnce(usize) -> V::SharedData<'a>,
+        output: &mut impl BufferMut,
     ) {
         let buffer_size = output.num_frames();
+        let shared_data = shared_data(buffer_size);
+        self.state
+            .set_output_levels(self.voices.iter().map(Voice::output_level));
         #[allow(clippy::cast_precision_loss)]
-        let voice_scale = 1f32 / self.voices.len() as f32;
-        let mut cleared = false;
+        let voice_scale = match self.summing_mode {
+            VoiceSummingMode::DivideByVoices => 1f32 / self.voices.len() as f32,
+            VoiceSummingMode::NoScaling => 1f32,
+            VoiceSummingMode::FixedScale(scale) => scale,
+        };
+        self.mix_buffer.channel_mut(0)[..buffer_size].fill(0f32);
         for (index, voice) in self.voices.iter_mut().enumerate() {
             let voice_events = || {
                 self.state
@@ -320,33 +757,79 @@ impl<V: Voice> Poly<V> {
                 voice.skip_samples(buffer_size);
                 continue;
             }
-            voice.process(
-                voice_events(),
-                params,
-                self.state
-                    .clone()
-                    .note_expressions_for_voice(index, events.clone()),
-                shared_data.clone(),
-                &mut self.voice_scratch_buffer[0..output.num_frames()],
+            let voice_output = &mut self.voice_scratch_buffer.channel_mut(0)[..buffer_size];
+            voice_output.fill(0f32);
+            let rendered = voice
+                .process(
+                    voice_events(),
+                    params,
+                    self.state
+                        .clone()
+                        .note_expressions_for_voice(index, events.clone()),
+                    shared_data.clone(),
+                    voice_output,
+                )
+                .min(buffer_size);
+            if rendered == 0 {
+                continue;
+            }
+            add_in_place(
+                &slice_buffer(&self.voice_scratch_buffer, ..rendered),
+                &mut slice_buffer_mut(&mut self.mix_buffer, ..rendered),
             );
-            mul_constant_in_place(voice_scale, &mut self.voice_scratch_buffer);
-            if cleared {
-                for channel_mut in channels_mut(output) {
-                    add_in_place(&self.voice_scratch_buffer[0..buffer_size], channel_mut);
-                }
-            } else {
-                for channel_mut in channels_mut(output) {
-                    channel_mut.copy_from_slice(&self.voice_scratch_buffer[0..buffer_size]);
+        }
+        let mix = &self.mix_buffer.channel(0)[..buffer_size];
+        for channel_mut in channels_mut(output) {
+            channel_mut.copy_from_slice(mix);
+        }
+        scale_in_place(voice_scale, output);
+        self.state.update(events);
+        if self.flushing {
+            let gains = &mut self.voice_scratch_buffer.channel_mut(0)[..buffer_size];
+            gains.fill(1f32);
+            self.flush_guard.process(gains);
+            for channel_mut in channels_mut(output) {
+                for (sample, gain) in channel_mut.iter_mut().zip(gains.iter()) {
+                    *sample *= *gain;
                 }
-                cleared = true;
+            }
+            if self.flush_guard.silent() {
+                self.reset();
             }
         }
-        if !cleared {
-            for channel_mut in channels_mut(output) {
-                channel_mut.fill(0f32);
+    }
+
+    /// Quickly fades out all voices, for example when the host's transport stops.
+    ///
+    /// Over the next few milliseconds (see [`DEFAULT_CLICK_GUARD_SECONDS`]), the output
+    /// of [`Self::process`] fades to silence. Once the fade is done, all voices are
+    /// [`reset`](`Voice::reset`) and all notes are forgotten, so the synth becomes
+    /// quiescent. Notes started before the fade is done are cut off as well.
+    ///
+    /// Unlike [`Self::reset`], which is instantaneous, this avoids a click when
+    /// stopping voices that are still sounding. Calling this while a flush is already
+    /// in progress does not restart the fade.
+    pub fn flush(&mut self) {
+        if !self.flushing {
+            self.flush_guard.open();
+            self.flush_guard.note_off();
+            self.flushing = true;
+        }
+    }
+
+    /// Tells the synth about the host's transport, flushing all voices when it stops.
+    ///
+    /// This can be used to implement [`conformal_component::Processor::set_transport`].
+    /// When the transport goes from playing to stopped, this calls [`Self::flush`], so
+    /// notes left ringing at the end of the song fade out quickly. Transports where the
+    /// host doesn't say whether it is playing are ignored.
+    pub fn set_transport(&mut self, transport: &Transport) {
+        if let Some(playing) = transport.playing {
+            if self.transport_playing && !playing {
+                self.flush();
             }
+            self.transport_playing = playing;
         }
-        self.state.update(events);
     }
 
     /// Resets the state of the polyphonic synth.
@@ -357,5 +840,12 @@ impl<V: Voice> Poly<V> {
             voice.reset();
         }
         self.state.reset();
+        if let Some(pedals) = &mut self.pedals {
+            pedals.reset();
+        }
+        self.flushing = false;
     }
 }
+
+#[cfg(test)]
+mod tests;
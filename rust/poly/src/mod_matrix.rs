@@ -0,0 +1,142 @@
+/// A single connection in a [`ModMatrix`] from a modulation source to a destination.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModRoute {
+    /// The index of the modulation source, from 0 to the number of sources of the matrix.
+    pub source: usize,
+
+    /// The index of the destination, from 0 to the number of destinations of the matrix.
+    pub destination: usize,
+
+    /// How much the source affects the destination.
+    ///
+    /// The source's value is multiplied by this before being added to the destination.
+    /// Negative depths invert the source.
+    pub depth: f32,
+}
+
+/// Routes several modulation sources to several destinations for a single voice.
+///
+/// Voices declare their sources (for example, LFOs, envelopes, or velocity) and
+/// destinations (for example, pitch or filter cutoff) as indices, and connect them
+/// with [`ModRoute`]s. Then, for each sample, the voice passes the current value of
+/// every source to [`Self::process`], which returns the total modulation amount
+/// for every destination.
+///
+/// Storage for routes is allocated up-front, so this never allocates after it is
+/// created, and the output depends only on the routes and the source values.
+///
+/// # Examples
+///
+/// ```
+/// # use conformal_poly::{ModMatrix, ModRoute};
+/// enum Source { Lfo, Envelope, Velocity }
+/// enum Destination { Pitch, Cutoff }
+///
+/// let mut matrix = ModMatrix::new(3, 2, 4);
+/// matrix.add_route(ModRoute {
+///     source: Source::Lfo as usize,
+///     destination: Destination::Pitch as usize,
+///     depth: 0.5,
+/// });
+/// matrix.add_route(ModRoute {
+///     source: Source::Envelope as usize,
+///     destination: Destination::Cutoff as usize,
+///     depth: 2.0,
+/// });
+/// matrix.add_route(ModRoute {
+///     source: Source::Velocity as usize,
+///     destination: Destination::Cutoff as usize,
+///     depth: 1.0,
+/// });
+///
+/// let mut destinations = [0.0; 2];
+/// matrix.process(&[1.0, 0.25, 0.5], &mut destinations);
+/// assert_eq!(destinations, [0.5, 1.0]);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModMatrix {
+    num_sources: usize,
+    num_destinations: usize,
+    max_routes: usize,
+    routes: Vec<ModRoute>,
+}
+
+impl ModMatrix {
+    /// Creates a new matrix with no routes.
+    ///
+    /// The matrix has `num_sources` sources and `num_destinations` destinations,
+    /// and can hold up to `max_routes` routes.
+    #[must_use]
+    pub fn new(num_sources: usize, num_destinations: usize, max_routes: usize) -> Self {
+        Self {
+            num_sources,
+            num_destinations,
+            max_routes,
+            routes: Vec::with_capacity(max_routes),
+        }
+    }
+
+    /// The number of sources this matrix was created with.
+    #[must_use]
+    pub fn num_sources(&self) -> usize {
+        self.num_sources
+    }
+
+    /// The number of destinations this matrix was created with.
+    #[must_use]
+    pub fn num_destinations(&self) -> usize {
+        self.num_destinations
+    }
+
+    /// Adds a route to the matrix.
+    ///
+    /// Routes are summed, so adding several routes between the same source and
+    /// destination is the same as adding a single route with the total depth.
+    ///
+    /// Returns `false` without adding the route if the matrix already holds
+    /// the maximum number of routes, or if the route's source or destination
+    /// is out of range.
+    pub fn add_route(&mut self, route: ModRoute) -> bool {
+        if self.routes.len() >= self.max_routes
+            || route.source >= self.num_sources
+            || route.destination >= self.num_destinations
+        {
+            return false;
+        }
+        self.routes.push(route);
+        true
+    }
+
+    /// Removes all routes from the matrix.
+    pub fn clear_routes(&mut self) {
+        self.routes.clear();
+    }
+
+    /// Returns the routes in the matrix, in the order they were added.
+    #[must_use]
+    pub fn routes(&self) -> &[ModRoute] {
+        &self.routes
+    }
+
+    /// Computes the modulation amount of every destination for a single sample.
+    ///
+    /// `sources` contains the current value of each source, and `destinations` is
+    /// overwritten with the sum of every route's source value scaled by its depth.
+    /// Destinations with no routes are set to 0.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `sources` doesn't have exactly [`Self::num_sources`] elements, or
+    /// `destinations` doesn't have exactly [`Self::num_destinations`] elements.
+    pub fn process(&self, sources: &[f32], destinations: &mut [f32]) {
+        assert_eq!(sources.len(), self.num_sources);
+        assert_eq!(destinations.len(), self.num_destinations);
+        destinations.fill(0.0);
+        for route in &self.routes {
+            destinations[route.destination] += sources[route.source] * route.depth;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
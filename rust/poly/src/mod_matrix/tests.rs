@@ -0,0 +1,128 @@
+use super::{ModMatrix, ModRoute};
+use conformal_component::audio::all_approx_eq;
+
+const EPSILON: f32 = 1e-6;
+
+#[test]
+fn source_routed_to_destination_is_scaled_by_depth() {
+    let mut matrix = ModMatrix::new(2, 3, 4);
+    assert!(matrix.add_route(ModRoute {
+        source: 1,
+        destination: 2,
+        depth: 0.25,
+    }));
+    let mut destinations = [1.0; 3];
+    matrix.process(&[0.5, 2.0], &mut destinations);
+    assert!(all_approx_eq(destinations, [0.0, 0.0, 0.5], EPSILON));
+}
+
+#[test]
+fn negative_depth_inverts_source() {
+    let mut matrix = ModMatrix::new(1, 1, 1);
+    assert!(matrix.add_route(ModRoute {
+        source: 0,
+        destination: 0,
+        depth: -2.0,
+    }));
+    let mut destinations = [0.0];
+    matrix.process(&[0.5], &mut destinations);
+    assert!(all_approx_eq(destinations, [-1.0], EPSILON));
+}
+
+#[test]
+fn routes_to_the_same_destination_are_summed() {
+    let mut matrix = ModMatrix::new(2, 1, 4);
+    for (source, depth) in [(0, 1.0), (1, 0.5), (1, 0.5)] {
+        assert!(matrix.add_route(ModRoute {
+            source,
+            destination: 0,
+            depth,
+        }));
+    }
+    let mut destinations = [0.0];
+    matrix.process(&[0.25, 0.5], &mut destinations);
+    assert!(all_approx_eq(destinations, [0.75], EPSILON));
+}
+
+#[test]
+fn per_sample_modulation_follows_source() {
+    let mut matrix = ModMatrix::new(1, 2, 2);
+    matrix.add_route(ModRoute {
+        source: 0,
+        destination: 0,
+        depth: 2.0,
+    });
+    matrix.add_route(ModRoute {
+        source: 0,
+        destination: 1,
+        depth: -1.0,
+    });
+    let lfo = [0.0, 0.5, 1.0, 0.5, 0.0, -0.5];
+    let mut pitch = vec![];
+    let mut cutoff = vec![];
+    let mut destinations = [0.0; 2];
+    for sample in lfo {
+        matrix.process(&[sample], &mut destinations);
+        pitch.push(destinations[0]);
+        cutoff.push(destinations[1]);
+    }
+    assert!(all_approx_eq(
+        pitch,
+        [0.0, 1.0, 2.0, 1.0, 0.0, -1.0],
+        EPSILON
+    ));
+    assert!(all_approx_eq(
+        cutoff,
+        [0.0, -0.5, -1.0, -0.5, 0.0, 0.5],
+        EPSILON
+    ));
+}
+
+#[test]
+fn rejects_routes_out_of_range() {
+    let mut matrix = ModMatrix::new(2, 2, 4);
+    assert!(!matrix.add_route(ModRoute {
+        source: 2,
+        destination: 0,
+        depth: 1.0,
+    }));
+    assert!(!matrix.add_route(ModRoute {
+        source: 0,
+        destination: 2,
+        depth: 1.0,
+    }));
+    assert!(matrix.routes().is_empty());
+}
+
+#[test]
+fn rejects_routes_beyond_capacity() {
+    let route = ModRoute {
+        source: 0,
+        destination: 0,
+        depth: 1.0,
+    };
+    let mut matrix = ModMatrix::new(1, 1, 2);
+    assert!(matrix.add_route(route));
+    assert!(matrix.add_route(route));
+    assert!(!matrix.add_route(route));
+    assert_eq!(matrix.routes().len(), 2);
+
+    matrix.clear_routes();
+    assert!(matrix.routes().is_empty());
+    assert!(matrix.add_route(route));
+}
+
+#[test]
+fn no_routes_produces_no_modulation() {
+    let matrix = ModMatrix::new(2, 2, 4);
+    let mut destinations = [1.0; 2];
+    matrix.process(&[1.0, 1.0], &mut destinations);
+    assert!(all_approx_eq(destinations, [0.0, 0.0], EPSILON));
+}
+
+#[test]
+#[should_panic(expected = "assertion `left == right` failed")]
+fn wrong_number_of_sources_panics() {
+    let matrix = ModMatrix::new(2, 1, 1);
+    matrix.process(&[1.0], &mut [0.0]);
+}
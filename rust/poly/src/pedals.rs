@@ -0,0 +1,307 @@
+use conformal_component::{
+    events::{Data, Event, NoteData, NoteID},
+    parameters::{BufferStates, SwitchBufferState, TimedValue},
+    synth::{SOFT_PEDAL_PARAMETER, SOSTENUTO_PARAMETER, SUSTAIN_PARAMETER},
+};
+
+/// The default amount that [`Pedals`] scales the velocity of notes played while
+/// the soft pedal is held.
+pub const DEFAULT_SOFT_PEDAL_VELOCITY_SCALE: f32 = 0.6;
+
+/// Tracks the sustain, sostenuto, and soft pedals, and applies them to notes.
+///
+/// - While the sustain pedal is held, releasing a key doesn't stop its note. Instead,
+///   the note stops when the sustain pedal is released.
+/// - When the sostenuto pedal is pressed, it catches the notes whose keys are held
+///   down at that moment. Releasing those keys doesn't stop their notes until the
+///   sostenuto pedal is released. Notes started after the pedal is pressed are not affected.
+/// - Notes started while the soft pedal is held have their velocity scaled down
+///   (by [`DEFAULT_SOFT_PEDAL_VELOCITY_SCALE`] by default).
+///
+/// When both the sustain and sostenuto pedals are held, a released note keeps
+/// sounding until _every_ pedal that is holding it has been released. So, a note
+/// caught by the sostenuto pedal keeps sounding after the sustain pedal is released,
+/// and a note held only by the sustain pedal stops when the sustain pedal is released,
+/// regardless of the sostenuto pedal.
+///
+/// This can be used directly by synths that manage their own notes, or passed
+/// to [`crate::Poly::set_pedals`] to have [`crate::Poly`] apply it automatically.
+///
+/// Storage is allocated up-front, so this never allocates after it is created.
+/// If more than `capacity` released notes are being held by pedals at once, further
+/// notes are stopped immediately when released.
+///
+/// # Examples
+///
+/// ```
+/// # use conformal_poly::Pedals;
+/// # use conformal_component::events::{NoteData, NoteID};
+/// let note = |id, pitch| NoteData {
+///     id: NoteID::from_id(id),
+///     pitch,
+///     velocity: 1.0,
+///     tuning: 0.0,
+///     channel: 0,
+/// };
+/// let mut pedals = Pedals::new(16);
+/// let mut released = vec![];
+///
+/// pedals.note_on(note(0, 60));
+/// pedals.set_sustain(true, |n| released.push(n.pitch));
+///
+/// // With the pedal held, releasing the key doesn't stop the note...
+/// assert_eq!(pedals.note_off(note(0, 60)), None);
+///
+/// // ... until the pedal is released.
+/// pedals.set_sustain(false, |n| released.push(n.pitch));
+/// assert_eq!(released, vec![60]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Pedals {
+    capacity: usize,
+    soft_velocity_scale: f32,
+    sustain: bool,
+    sostenuto: bool,
+    soft: bool,
+
+    /// Notes whose keys are currently held down, from oldest to newest.
+    down: Vec<NoteID>,
+
+    /// Notes caught by the sostenuto pedal.
+    caught: Vec<NoteID>,
+
+    /// The note offs of notes whose keys have been released, but are still held by a pedal.
+    deferred: Vec<NoteData>,
+}
+
+fn holds(sustain: bool, sostenuto: bool, caught: &[NoteID], id: NoteID) -> bool {
+    sustain || (sostenuto && caught.contains(&id))
+}
+
+fn switch_changes<I: IntoIterator<Item = TimedValue<bool>>>(
+    state: Option<SwitchBufferState<I>>,
+) -> impl Iterator<Item = TimedValue<bool>> {
+    let (constant, varying) = match state {
+        Some(SwitchBufferState::Constant(value)) => (
+            Some(TimedValue {
+                sample_offset: 0,
+                value,
+            }),
+            None,
+        ),
+        Some(SwitchBufferState::Varying(values)) => (None, Some(values)),
+        None => (None, None),
+    };
+    constant.into_iter().chain(varying.into_iter().flatten())
+}
+
+impl Pedals {
+    /// Creates a new pedal model that can track up to `capacity` notes, using
+    /// [`DEFAULT_SOFT_PEDAL_VELOCITY_SCALE`].
+    ///
+    /// A `capacity` of 0 is treated as 1. All pedals start released.
+    #[must_use]
+    pub fn new(capacity: usize) -> Self {
+        Self::new_with_soft_pedal_velocity_scale(capacity, DEFAULT_SOFT_PEDAL_VELOCITY_SCALE)
+    }
+
+    /// Creates a new pedal model where the soft pedal scales velocities by `soft_velocity_scale`.
+    ///
+    /// A `capacity` of 0 is treated as 1. All pedals start released.
+    #[must_use]
+    pub fn new_with_soft_pedal_velocity_scale(capacity: usize, soft_velocity_scale: f32) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            capacity,
+            soft_velocity_scale,
+            sustain: false,
+            sostenuto: false,
+            soft: false,
+            down: Vec::with_capacity(capacity),
+            caught: Vec::with_capacity(capacity),
+            deferred: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Records that a key has been pressed, and returns the note that should start.
+    ///
+    /// If the soft pedal is held, the returned note's velocity is scaled down.
+    #[must_use]
+    pub fn note_on(&mut self, data: NoteData) -> NoteData {
+        // If this note was being held by a pedal, it's been re-struck, so it no
+        // longer needs to be stopped when the pedal is released.
+        self.deferred.retain(|note| note.id != data.id);
+        self.down.retain(|id| *id != data.id);
+        if self.down.len() == self.capacity {
+            self.down.remove(0);
+        }
+        self.down.push(data.id);
+        if self.soft {
+            NoteData {
+                velocity: data.velocity * self.soft_velocity_scale,
+                ..data
+            }
+        } else {
+            data
+        }
+    }
+
+    /// Records that a key has been released.
+    ///
+    /// Returns the note off that should be sent now, or `None` if the note is
+    /// held by a pedal. In that case, the note off will be passed to the
+    /// `released` callback of [`Self::set_sustain`] or [`Self::set_sostenuto`]
+    /// once the pedals holding it are released.
+    #[must_use]
+    pub fn note_off(&mut self, data: NoteData) -> Option<NoteData> {
+        self.down.retain(|id| *id != data.id);
+        if holds(self.sustain, self.sostenuto, &self.caught, data.id)
+            && self.deferred.len() < self.capacity
+        {
+            self.deferred.retain(|note| note.id != data.id);
+            self.deferred.push(data);
+            None
+        } else {
+            Some(data)
+        }
+    }
+
+    /// Sets whether the sustain pedal is held.
+    ///
+    /// `released` is called with the note off of each note that should stop
+    /// because the pedal was released.
+    pub fn set_sustain(&mut self, down: bool, released: impl FnMut(NoteData)) {
+        self.sustain = down;
+        self.release_deferred(released);
+    }
+
+    /// Sets whether the sostenuto pedal is held.
+    ///
+    /// Pressing the pedal catches all notes whose keys are currently down.
+    /// `released` is called with the note off of each note that should stop
+    /// because the pedal was released.
+    pub fn set_sostenuto(&mut self, down: bool, released: impl FnMut(NoteData)) {
+        if down && !self.sostenuto {
+            self.caught.clear();
+            self.caught.extend_from_slice(&self.down);
+        } else if !down {
+            self.caught.clear();
+        }
+        self.sostenuto = down;
+        self.release_deferred(released);
+    }
+
+    /// Sets whether the soft pedal is held.
+    ///
+    /// This only affects notes started after the change.
+    pub fn set_soft(&mut self, down: bool) {
+        self.soft = down;
+    }
+
+    fn release_deferred(&mut self, mut released: impl FnMut(NoteData)) {
+        let (sustain, sostenuto, caught) = (self.sustain, self.sostenuto, &self.caught);
+        self.deferred.retain(|note| {
+            if holds(sustain, sostenuto, caught, note.id) {
+                true
+            } else {
+                released(*note);
+                false
+            }
+        });
+    }
+
+    /// Applies the pedals to a buffer of events.
+    ///
+    /// The pedal states are read from the [`SUSTAIN_PARAMETER`], [`SOSTENUTO_PARAMETER`],
+    /// and [`SOFT_PEDAL_PARAMETER`] controller parameters in `params`, and pedal changes
+    /// take effect before any events at the same sample offset. `output` is
+    /// cleared and filled with the events that should be played, including any note offs
+    /// caused by releasing pedals.
+    ///
+    /// This pushes at most `capacity` more events than it is given, so reserving that
+    /// much room in `output` up-front avoids allocating.
+    pub fn process(
+        &mut self,
+        events: impl IntoIterator<Item = Event>,
+        params: &impl BufferStates,
+        output: &mut Vec<Event>,
+    ) {
+        output.clear();
+        let mut sustain = switch_changes(params.get_switch(SUSTAIN_PARAMETER)).peekable();
+        let mut sostenuto = switch_changes(params.get_switch(SOSTENUTO_PARAMETER)).peekable();
+        let mut soft = switch_changes(params.get_switch(SOFT_PEDAL_PARAMETER)).peekable();
+        let mut events = events.into_iter().peekable();
+        loop {
+            let next_pedal = [sustain.peek(), sostenuto.peek(), soft.peek()]
+                .into_iter()
+                .flatten()
+                .map(|change| change.sample_offset)
+                .min();
+            let next_event = events.peek().map(|event| event.sample_offset);
+            match (next_pedal, next_event) {
+                (Some(offset), next_event) if next_event.map_or(true, |e| offset <= e) => {
+                    let mut released = |data| {
+                        output.push(Event {
+                            sample_offset: offset,
+                            data: Data::NoteOff { data },
+                        });
+                    };
+                    if let Some(change) = soft.next_if(|c| c.sample_offset == offset) {
+                        self.set_soft(change.value);
+                    }
+                    if let Some(change) = sostenuto.next_if(|c| c.sample_offset == offset) {
+                        self.set_sostenuto(change.value, &mut released);
+                    }
+                    if let Some(change) = sustain.next_if(|c| c.sample_offset == offset) {
+                        self.set_sustain(change.value, &mut released);
+                    }
+                }
+                (None, None) => break,
+                _ => {
+                    if let Some(event) = events.next() {
+                        self.handle_event(event, output);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Applies the current state of the pedals to a single event.
+    pub(crate) fn handle_event(&mut self, event: Event, output: &mut Vec<Event>) {
+        match event.data {
+            Data::NoteOn { data } => output.push(Event {
+                sample_offset: event.sample_offset,
+                data: Data::NoteOn {
+                    data: self.note_on(data),
+                },
+            }),
+            Data::NoteOff { data } => {
+                if let Some(data) = self.note_off(data) {
+                    output.push(Event {
+                        sample_offset: event.sample_offset,
+                        data: Data::NoteOff { data },
+                    });
+                }
+            }
+            _ => output.push(event),
+        }
+    }
+
+    /// The number of notes this can track, as passed to [`Self::new`].
+    pub(crate) fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Releases all pedals and forgets all notes, for example when processing is reset.
+    pub fn reset(&mut self) {
+        self.sustain = false;
+        self.sostenuto = false;
+        self.soft = false;
+        self.down.clear();
+        self.caught.clear();
+        self.deferred.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,203 @@
+use std::collections::HashMap;
+
+use super::Pedals;
+use conformal_component::{
+    events::{Data, Event, NoteData, NoteID},
+    parameters::{InternalValue, RampedStatesMap, StaticInfoRef},
+    synth::{SOFT_PEDAL_PARAMETER, SUSTAIN_PARAMETER},
+};
+
+fn note(id: i32, pitch: u8) -> NoteData {
+    NoteData {
+        id: NoteID::from_id(id),
+        pitch,
+        velocity: 1.0,
+        tuning: 0.0,
+        channel: 0,
+    }
+}
+
+fn press(pedals: &mut Pedals, id: i32) {
+    let _ = pedals.note_on(note(id, 60));
+}
+
+fn release(pedals: &mut Pedals, id: i32) -> bool {
+    pedals.note_off(note(id, 60)).is_some()
+}
+
+#[test]
+fn notes_stop_immediately_without_pedals() {
+    let mut pedals = Pedals::new(16);
+    press(&mut pedals, 0);
+    assert!(release(&mut pedals, 0));
+}
+
+#[test]
+fn sustain_defers_note_offs_until_released() {
+    let mut pedals = Pedals::new(16);
+    press(&mut pedals, 0);
+    pedals.set_sustain(true, |_| panic!("No notes should be released"));
+    press(&mut pedals, 1);
+    assert!(!release(&mut pedals, 0));
+    assert!(!release(&mut pedals, 1));
+
+    let mut released = vec![];
+    pedals.set_sustain(false, |n| released.push(n.id));
+    assert_eq!(released, vec![NoteID::from_id(0), NoteID::from_id(1)]);
+}
+
+#[test]
+fn sustain_does_not_release_held_keys() {
+    let mut pedals = Pedals::new(16);
+    pedals.set_sustain(true, |_| {});
+    press(&mut pedals, 0);
+    pedals.set_sustain(false, |_| panic!("Key is still held"));
+    assert!(release(&mut pedals, 0));
+}
+
+#[test]
+fn restruck_notes_are_not_released_by_pedal() {
+    let mut pedals = Pedals::new(16);
+    pedals.set_sustain(true, |_| {});
+    press(&mut pedals, 0);
+    assert!(!release(&mut pedals, 0));
+    press(&mut pedals, 0);
+    pedals.set_sustain(false, |_| panic!("Note was re-struck"));
+}
+
+#[test]
+fn sostenuto_only_catches_held_notes() {
+    let mut pedals = Pedals::new(16);
+    press(&mut pedals, 0);
+    pedals.set_sostenuto(true, |_| {});
+    press(&mut pedals, 1);
+
+    // Note 1 was pressed after the pedal, so it isn't caught
+    assert!(release(&mut pedals, 1));
+    assert!(!release(&mut pedals, 0));
+
+    let mut released = vec![];
+    pedals.set_sostenuto(false, |n| released.push(n.id));
+    assert_eq!(released, vec![NoteID::from_id(0)]);
+}
+
+#[test]
+fn sostenuto_caught_notes_survive_sustain_release() {
+    let mut pedals = Pedals::new(16);
+    press(&mut pedals, 0);
+    pedals.set_sostenuto(true, |_| {});
+    pedals.set_sustain(true, |_| {});
+    press(&mut pedals, 1);
+    assert!(!release(&mut pedals, 0));
+    assert!(!release(&mut pedals, 1));
+
+    let mut released = vec![];
+    pedals.set_sustain(false, |n| released.push(n.id));
+    assert_eq!(released, vec![NoteID::from_id(1)]);
+
+    released.clear();
+    pedals.set_sostenuto(false, |n| released.push(n.id));
+    assert_eq!(released, vec![NoteID::from_id(0)]);
+}
+
+#[test]
+fn sustain_holds_notes_after_sostenuto_release() {
+    let mut pedals = Pedals::new(16);
+    press(&mut pedals, 0);
+    pedals.set_sostenuto(true, |_| {});
+    pedals.set_sustain(true, |_| {});
+    assert!(!release(&mut pedals, 0));
+
+    pedals.set_sostenuto(false, |_| panic!("Sustain is still held"));
+
+    let mut released = vec![];
+    pedals.set_sustain(false, |n| released.push(n.id));
+    assert_eq!(released, vec![NoteID::from_id(0)]);
+}
+
+#[test]
+#[allow(clippy::float_cmp)]
+fn soft_pedal_scales_new_notes() {
+    let mut pedals = Pedals::new_with_soft_pedal_velocity_scale(16, 0.5);
+    pedals.set_soft(true);
+    assert_eq!(pedals.note_on(note(0, 60)).velocity, 0.5);
+    pedals.set_soft(false);
+    assert_eq!(pedals.note_on(note(1, 60)).velocity, 1.0);
+}
+
+#[test]
+fn notes_beyond_capacity_stop_immediately() {
+    let mut pedals = Pedals::new(1);
+    pedals.set_sustain(true, |_| {});
+    press(&mut pedals, 0);
+    press(&mut pedals, 1);
+    assert!(!release(&mut pedals, 0));
+    assert!(release(&mut pedals, 1));
+}
+
+#[test]
+fn reset_releases_pedals() {
+    let mut pedals = Pedals::new(16);
+    pedals.set_sustain(true, |_| {});
+    press(&mut pedals, 0);
+    pedals.reset();
+    assert!(release(&mut pedals, 0));
+}
+
+#[test]
+#[allow(clippy::float_cmp)]
+fn process_applies_pedal_parameters_at_their_offsets() {
+    let mut pedals = Pedals::new(16);
+    let start_overrides: HashMap<_, _> = [
+        (SUSTAIN_PARAMETER, InternalValue::Switch(true)),
+        (SOFT_PEDAL_PARAMETER, InternalValue::Switch(true)),
+    ]
+    .into_iter()
+    .collect();
+    let end_overrides: HashMap<_, _> = [
+        (SUSTAIN_PARAMETER, InternalValue::Switch(false)),
+        (SOFT_PEDAL_PARAMETER, InternalValue::Switch(false)),
+    ]
+    .into_iter()
+    .collect();
+    // With a buffer size of 10, the pedals are released at sample 5.
+    let params = RampedStatesMap::new_synth(
+        std::iter::empty::<StaticInfoRef>(),
+        &start_overrides,
+        &end_overrides,
+        10,
+    );
+    let events = [
+        Event {
+            sample_offset: 1,
+            data: Data::NoteOn { data: note(0, 60) },
+        },
+        Event {
+            sample_offset: 2,
+            data: Data::NoteOff { data: note(0, 60) },
+        },
+        Event {
+            sample_offset: 5,
+            data: Data::NoteOn { data: note(1, 62) },
+        },
+    ];
+    let mut output = vec![];
+    pedals.process(events, &params, &mut output);
+
+    assert_eq!(output.len(), 3);
+    assert_eq!(output[0].sample_offset, 1);
+    match &output[0].data {
+        Data::NoteOn { data } => assert_eq!(data.velocity, 0.6),
+        _ => panic!("Expected a note on"),
+    }
+
+    // The note off is deferred until the sustain pedal is released,
+    // and the pedal change comes before the note on at the same time.
+    assert_eq!(output[1].sample_offset, 5);
+    assert!(matches!(&output[1].data, Data::NoteOff { data } if data.id == NoteID::from_id(0)));
+    assert_eq!(output[2].sample_offset, 5);
+    match &output[2].data {
+        Data::NoteOn { data } => assert_eq!(data.velocity, 1.0),
+        _ => panic!("Expected a note on"),
+    }
+}
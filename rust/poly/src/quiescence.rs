@@ -0,0 +1,138 @@
+/// The default threshold used by [`QuiescenceTracker`], in decibels relative to full scale.
+pub const DEFAULT_QUIESCENCE_THRESHOLD_DB: f32 = -90.0;
+
+fn db_to_amplitude(db: f32) -> f32 {
+    10f32.powf(db / 20.0)
+}
+
+/// A helper for implementing [`crate::Voice::quiescent`].
+///
+/// This tracks the output of a voice, and reports that the voice is quiescent once
+/// the output has stayed below a threshold for a given number of samples. This lets
+/// voices with slowly decaying releases stop processing once they become inaudible.
+///
+/// By default, the threshold is [`DEFAULT_QUIESCENCE_THRESHOLD_DB`] (-90 dB).
+///
+/// To use this, call [`Self::trigger`] whenever the voice starts a note, and call
+/// [`Self::update`] with the output of each call to [`crate::Voice::process`].
+///
+/// The tracker also keeps the peak level of the most recent buffer, which can be used
+/// to implement [`crate::Voice::output_level`].
+///
+/// # Examples
+///
+/// ```
+/// # use conformal_poly::QuiescenceTracker;
+/// let mut tracker = QuiescenceTracker::new(4);
+/// assert!(tracker.quiescent());
+///
+/// tracker.trigger();
+/// assert!(!tracker.quiescent());
+///
+/// tracker.update(&[0.5, 0.25, 0.0, 0.0]);
+/// assert!(!tracker.quiescent());
+///
+/// tracker.update(&[0.0, 0.0, 0.0, 0.0]);
+/// assert!(tracker.quiescent());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuiescenceTracker {
+    threshold: f32,
+    hold_samples: usize,
+    quiet_samples: usize,
+    recent_peak: f32,
+}
+
+impl QuiescenceTracker {
+    /// Creates a new tracker using the default threshold.
+    ///
+    /// The voice will be reported quiescent once its output has stayed below
+    /// the threshold for at least `hold_samples` samples.
+    ///
+    /// The tracker starts out quiescent.
+    #[must_use]
+    pub fn new(hold_samples: usize) -> Self {
+        Self::new_with_threshold(hold_samples, DEFAULT_QUIESCENCE_THRESHOLD_DB)
+    }
+
+    /// Creates a new tracker with a custom threshold, in decibels relative to full scale.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use conformal_poly::QuiescenceTracker;
+    /// let mut tracker = QuiescenceTracker::new_with_threshold(2, -20.0);
+    /// tracker.trigger();
+    /// tracker.update(&[0.05, 0.05]);
+    /// assert!(tracker.quiescent());
+    /// ```
+    #[must_use]
+    pub fn new_with_threshold(hold_samples: usize, threshold_db: f32) -> Self {
+        Self {
+            threshold: db_to_amplitude(threshold_db),
+            hold_samples,
+            quiet_samples: hold_samples,
+            recent_peak: 0.0,
+        }
+    }
+
+    /// Marks the voice as active, for example when a new note starts.
+    ///
+    /// The voice will not be reported quiescent again until its output has
+    /// stayed below the threshold for the full hold time.
+    pub fn trigger(&mut self) {
+        self.quiet_samples = 0;
+    }
+
+    /// Updates the tracker with a buffer of output from the voice.
+    pub fn update(&mut self, output: &[f32]) {
+        self.recent_peak = output.iter().fold(0.0, |peak, x| x.abs().max(peak));
+        match output.iter().rposition(|x| x.abs() > self.threshold) {
+            Some(index) => self.quiet_samples = output.len() - 1 - index,
+            None => self.quiet_samples = self.quiet_samples.saturating_add(output.len()),
+        }
+    }
+
+    /// Returns whether the output has been below the threshold for at least the hold time.
+    ///
+    /// This can be used to implement [`crate::Voice::quiescent`].
+    #[must_use]
+    pub fn quiescent(&self) -> bool {
+        self.quiet_samples >= self.hold_samples
+    }
+
+    /// Returns the peak absolute value of the most recent buffer passed to [`Self::update`].
+    ///
+    /// This is only updated once per buffer, so it describes the level of the voice as of the
+    /// end of the last buffer it processed, not its current level. Before the first call to
+    /// [`Self::update`], and after [`Self::reset`], this is 0.
+    ///
+    /// This can be used to implement [`crate::Voice::output_level`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use conformal_poly::QuiescenceTracker;
+    /// let mut tracker = QuiescenceTracker::new(4);
+    /// tracker.update(&[0.5, -0.75, 0.25]);
+    /// assert_eq!(tracker.recent_peak(), 0.75);
+    ///
+    /// tracker.update(&[0.125, 0.0]);
+    /// assert_eq!(tracker.recent_peak(), 0.125);
+    /// ```
+    #[must_use]
+    pub fn recent_peak(&self) -> f32 {
+        self.recent_peak
+    }
+
+    /// Resets the tracker to its initial, quiescent state.
+    ///
+    /// This can be used to implement [`crate::Voice::reset`].
+    pub fn reset(&mut self) {
+        self.quiet_samples = self.hold_samples;
+        self.recent_peak = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,49 @@
+use super::QuiescenceTracker;
+
+#[test]
+fn decaying_signal_becomes_quiescent_after_hold() {
+    let hold_samples = 64;
+    let mut tracker = QuiescenceTracker::new(hold_samples);
+    tracker.trigger();
+
+    // An exponentially decaying signal, losing 1 dB per sample.
+    let decay = 10f32.powf(-1.0 / 20.0);
+    let signal: Vec<f32> = std::iter::successors(Some(1f32), |x| Some(x * decay))
+        .take(256)
+        .collect();
+
+    // We should become quiescent exactly `hold_samples` after the signal
+    // drops below -90 dB, which happens around sample 90.
+    let threshold = 10f32.powf(-90.0 / 20.0);
+    let crossing = signal.iter().position(|x| *x <= threshold).unwrap();
+    assert!((89..=91).contains(&crossing));
+    for (index, chunk) in signal.chunks(1).enumerate() {
+        tracker.update(chunk);
+        assert_eq!(
+            tracker.quiescent(),
+            index + 1 >= crossing + hold_samples,
+            "Unexpected quiescence at sample {index}"
+        );
+    }
+}
+
+#[test]
+fn loud_output_resets_hold() {
+    let mut tracker = QuiescenceTracker::new(4);
+    tracker.trigger();
+    tracker.update(&[0.0, 0.0, 0.0]);
+    assert!(!tracker.quiescent());
+    tracker.update(&[1.0, 0.0, 0.0]);
+    assert!(!tracker.quiescent());
+    tracker.update(&[0.0, 0.0]);
+    assert!(tracker.quiescent());
+}
+
+#[test]
+fn reset_restores_quiescence() {
+    let mut tracker = QuiescenceTracker::new(4);
+    tracker.trigger();
+    assert!(!tracker.quiescent());
+    tracker.reset();
+    assert!(tracker.quiescent());
+}
@@ -1,15 +1,32 @@
+use std::borrow::Borrow;
+
 use super::{Event, EventData, NoteExpressionCurve};
 use conformal_component::{
     audio::approx_eq,
     events::{self as events, NoteData, NoteExpressionData, NoteID},
 };
 
-use crate::{NoteExpressionPoint, NoteExpressionState};
+use crate::{
+    HeldNotes, NoteExpressionPoint, NoteExpressionState, NotePriority, StealStrategy, VoiceMode,
+    MONO_NOTE_STACK_SIZE,
+};
+
+/// The number of MIDI channels a note can be played on, see [`NoteData::channel`].
+const MIDI_CHANNELS: usize = 16;
 
 #[derive(Clone, Debug, PartialEq)]
 enum VoicePlayingState {
-    Idle { order: usize },
-    Note { order: usize, id: NoteID, pitch: u8 },
+    Idle {
+        order: usize,
+    },
+    Note {
+        order: usize,
+        id: NoteID,
+        pitch: u8,
+        channel: u8,
+        velocity: f32,
+        tuning: f32,
+    },
 }
 
 impl NoteExpressionState {
@@ -50,11 +67,22 @@ impl NoteExpressionState {
 pub struct Voice {
     playing: VoicePlayingState,
     expression: NoteExpressionState,
+
+    /// If set, this voice will only play notes on this MIDI channel.
+    channel: Option<u8>,
+
+    /// The most recent [`crate::Voice::output_level`] of this voice.
+    output_level: f32,
 }
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct State {
     voices: Vec<Voice>,
+    steal_strategy: StealStrategy,
+    voice_mode: VoiceMode,
+
+    /// The notes held in the mono voice modes, used to pick which note to return to.
+    held_notes: HeldNotes,
 
     voices_compress_order_scratch: Vec<(usize, usize)>,
 }
@@ -151,62 +179,158 @@ impl EventStreamStep {
     }
 }
 
-fn synthetic_note_off(id: NoteID, pitch: u8) -> EventData {
-    EventData::NoteOff {
-        data: NoteData {
-            id,
-            pitch,
-            velocity: 1.0,
-            tuning: 0.0,
-        },
+impl Voice {
+    /// Make synthetic note data for the note playing on this voice, used when stopping it.
+    fn synthetic_note_off_data(&self) -> NoteData {
+        if let VoicePlayingState::Note {
+            id, pitch, channel, ..
+        } = self.playing
+        {
+            NoteData {
+                id,
+                pitch,
+                velocity: 1.0,
+                tuning: 0.0,
+                channel,
+            }
+        } else {
+            panic!("Internal error");
+        }
+    }
+
+    /// Make a synthetic note off event for the note playing on this voice, used when stealing it.
+    fn synthetic_note_off(&self) -> EventData {
+        EventData::NoteOff {
+            data: self.synthetic_note_off_data(),
+        }
+    }
+}
+
+/// Voices are compared by this key when stealing, and the voice with the lowest key is stolen.
+fn steal_key(strategy: StealStrategy, output_level: f32, order: usize) -> (f32, usize) {
+    match strategy {
+        StealStrategy::Oldest => (0.0, order),
+        StealStrategy::Quietest => (output_level, order),
     }
 }
 
+fn steals_before(a: (f32, usize), b: (f32, usize)) -> bool {
+    a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)).is_lt()
+}
+
 impl State {
     pub fn new(max_voices: usize) -> Self {
-        assert!(max_voices > 0);
+        Self::new_with_channels(std::iter::repeat(None).take(max_voices))
+    }
+
+    /// Creates a state where voices are partitioned into pools by MIDI channel.
+    ///
+    /// Voices in the pool for channel `i` will only play notes on channel `i`, and
+    /// notes on channels without a pool are ignored.
+    pub fn new_partitioned_by_channel(voices_per_channel: &[usize]) -> Self {
+        assert!(
+            voices_per_channel.len() <= MIDI_CHANNELS,
+            "Can't partition voices over more than {MIDI_CHANNELS} MIDI channels"
+        );
+        Self::new_with_channels(voices_per_channel.iter().enumerate().flat_map(
+            |(channel, &num_voices)| {
+                std::iter::repeat(Some(u8::try_from(channel).unwrap())).take(num_voices)
+            },
+        ))
+    }
+
+    fn new_with_channels(channels: impl IntoIterator<Item = Option<u8>>) -> Self {
+        let voices: Vec<_> = channels
+            .into_iter()
+            .enumerate()
+            .map(|(i, channel)| Voice {
+                playing: VoicePlayingState::Idle { order: i },
+                expression: NoteExpressionState::default(),
+                channel,
+                output_level: 0.0,
+            })
+            .collect();
+        assert!(!voices.is_empty());
         Self {
-            voices: (0..max_voices)
-                .map(|i| Voice {
-                    playing: VoicePlayingState::Idle { order: i },
-                    expression: NoteExpressionState::default(),
-                })
-                .collect(),
-            voices_compress_order_scratch: Vec::with_capacity(max_voices),
+            voices_compress_order_scratch: Vec::with_capacity(voices.len()),
+            voices,
+            steal_strategy: StealStrategy::default(),
+            voice_mode: VoiceMode::default(),
+            held_notes: HeldNotes::new(MONO_NOTE_STACK_SIZE),
+        }
+    }
+
+    pub fn set_steal_strategy(&mut self, strategy: StealStrategy) {
+        self.steal_strategy = strategy;
+    }
+
+    pub fn set_voice_mode(&mut self, mode: VoiceMode) {
+        if mode != self.voice_mode {
+            self.voice_mode = mode;
+            self.held_notes.clear();
+        }
+    }
+
+    /// Records the output level of each voice, used by [`StealStrategy::Quietest`].
+    pub fn set_output_levels(&mut self, levels: impl IntoIterator<Item = f32>) {
+        for (voice, level) in self.voices.iter_mut().zip(levels) {
+            voice.output_level = level;
         }
     }
 
     pub fn reset(&mut self) {
-        let num_voices = self.voices.len();
-        self.voices.clear();
-        self.voices.extend((0..num_voices).map(|i| Voice {
-            playing: VoicePlayingState::Idle { order: i },
-            expression: NoteExpressionState::default(),
-        }));
+        for (i, voice) in self.voices.iter_mut().enumerate() {
+            voice.playing = VoicePlayingState::Idle { order: i };
+            voice.expression = NoteExpressionState::default();
+            voice.output_level = 0.0;
+        }
+        self.held_notes.clear();
+    }
+
+    /// Returns the note each voice is playing, in order of voice index.
+    pub fn notes(&self) -> impl Iterator<Item = Option<NoteData>> + '_ {
+        self.voices.iter().map(|voice| match voice.playing {
+            VoicePlayingState::Note {
+                id,
+                pitch,
+                channel,
+                velocity,
+                tuning,
+                ..
+            } => Some(NoteData {
+                id,
+                pitch,
+                velocity,
+                tuning,
+                channel,
+            }),
+            VoicePlayingState::Idle { .. } => None,
+        })
     }
 
     /// Note that the events must be sorted by time!
     pub fn dispatch_events(
         mut self,
-        events: impl IntoIterator<Item = events::Event>,
+        events: impl IntoIterator<Item = impl Borrow<events::Event>>,
     ) -> impl IntoIterator<Item = (usize, Event)> {
         events
             .into_iter()
-            .flat_map(move |event| self.update_state_and_dispatch_for_event(&event))
+            .flat_map(move |event| self.update_state_and_dispatch_for_event(event.borrow()))
     }
 
     pub fn note_expressions_for_voice(
         mut self,
         voice: usize,
-        events: impl Iterator<Item = events::Event> + Clone,
+        events: impl Iterator<Item = impl Borrow<events::Event>> + Clone,
     ) -> NoteExpressionCurve<impl Iterator<Item = NoteExpressionPoint> + Clone> {
         let raw = std::iter::once(NoteExpressionPoint {
             sample_offset: 0,
             state: self.voices[voice].expression,
         })
         .chain(events.filter_map(move |event| {
+            let event = event.borrow();
             let time = event.sample_offset;
-            let dispatched = self.update_state_and_dispatch_for_event(&event);
+            let dispatched = self.update_state_and_dispatch_for_event(event);
             if dispatched.voice == voice {
                 dispatched.expression.map(|state| NoteExpressionPoint {
                     sample_offset: time,
@@ -222,15 +346,26 @@ impl State {
 
     fn update_state_and_dispatch_for_event(&mut self, event: &events::Event) -> EventStreamStep {
         match &event.data {
-            events::Data::NoteOn { data } => {
-                self.update_state_and_dispatch_for_note_on(event.sample_offset, data)
-            }
-            events::Data::NoteOff { data } => {
-                self.update_state_and_dispatch_for_note_off(event.sample_offset, data)
-            }
+            events::Data::NoteOn { data } => match self.voice_mode {
+                VoiceMode::Poly => {
+                    self.update_state_and_dispatch_for_note_on(event.sample_offset, data)
+                }
+                VoiceMode::Mono | VoiceMode::Legato => {
+                    self.update_state_and_dispatch_for_mono_note_on(event.sample_offset, data)
+                }
+            },
+            events::Data::NoteOff { data } => match self.voice_mode {
+                VoiceMode::Poly => {
+                    self.update_state_and_dispatch_for_note_off(event.sample_offset, data)
+                }
+                VoiceMode::Mono | VoiceMode::Legato => {
+                    self.update_state_and_dispatch_for_mono_note_off(event.sample_offset, data)
+                }
+            },
             events::Data::NoteExpression { data } => {
                 self.update_state_and_dispatch_for_note_expression(event.sample_offset, data)
             }
+            events::Data::ParameterChange { .. } => EventStreamStep::new0(),
         }
     }
 
@@ -241,17 +376,23 @@ impl State {
     ) -> EventStreamStep {
         let mut open_index = None;
         let mut open_index_order = None;
-        let mut old_voice_index = None;
-        let mut old_voice_order = None;
+        let mut steal_index = None;
+        let mut steal_index_key = None;
         let mut new_voice_order = None;
+        let steal_strategy = self.steal_strategy;
         for (
             index,
             Voice {
                 playing,
                 expression,
+                channel,
+                output_level,
             },
         ) in self.voices.iter_mut().enumerate()
         {
+            if channel.is_some_and(|channel| channel != data.channel) {
+                continue;
+            }
             match (playing, open_index_order) {
                 (VoicePlayingState::Idle { order }, None) => {
                     open_index = Some(index);
@@ -274,14 +415,10 @@ impl State {
                     );
                 }
                 (VoicePlayingState::Note { order, .. }, _) => {
-                    if let Some(old_voice_order_) = old_voice_order {
-                        if *order < old_voice_order_ {
-                            old_voice_index = Some(index);
-                            old_voice_order = Some(*order);
-                        }
-                    } else {
-                        old_voice_index = Some(index);
-                        old_voice_order = Some(*order);
+                    let key = steal_key(steal_strategy, *output_level, *order);
+                    if steal_index_key.map_or(true, |current| steals_before(key, current)) {
+                        steal_index = Some(index);
+                        steal_index_key = Some(key);
                     }
                     if let Some(new_voice_order_) = new_voice_order {
                         if *order > new_voice_order_ {
@@ -296,25 +433,24 @@ impl State {
         }
 
         let mut extra_off = None;
-        let open_index = open_index.unwrap_or_else(|| {
-            // If we got here, no notes are open - we have to steal one!
-            // We always steal the oldest note.
-
-            // Make a synthetic note off event for the oldest note
-            if let VoicePlayingState::Note { id, pitch, .. } =
-                self.voices[old_voice_index.unwrap()].playing
-            {
-                extra_off = Some(synthetic_note_off(id, pitch));
-            } else {
-                panic!("Internal error");
+        let open_index = match (open_index, steal_index) {
+            (Some(open_index), _) => open_index,
+            (None, Some(steal_index)) => {
+                // If we got here, no notes are open - we have to steal one!
+                extra_off = Some(self.voices[steal_index].synthetic_note_off());
+                steal_index
             }
-            old_voice_index.unwrap()
-        });
+            // No voices can play notes on this channel, so we ignore the note.
+            (None, None) => return EventStreamStep::new0(),
+        };
 
         self.voices[open_index].playing = VoicePlayingState::Note {
             id: data.id,
             order: new_voice_order.map_or(0, |x| x + 1),
             pitch: data.pitch,
+            channel: data.channel,
+            velocity: data.velocity,
+            tuning: data.tuning,
         };
         let expression_point = self.voices[open_index]
             .expression
@@ -342,6 +478,105 @@ impl State {
         }
     }
 
+    /// Moves the mono voice from the note it's playing to `data`.
+    ///
+    /// In [`VoiceMode::Mono`], this retriggers the voice, and in [`VoiceMode::Legato`]
+    /// it sends a legato event.
+    fn move_mono_voice(
+        &mut self,
+        sample_offset: usize,
+        from: NoteData,
+        to: NoteData,
+    ) -> EventStreamStep {
+        let voice = &mut self.voices[0];
+        voice.playing = VoicePlayingState::Note {
+            order: 0,
+            id: to.id,
+            pitch: to.pitch,
+            channel: to.channel,
+            velocity: to.velocity,
+            tuning: to.tuning,
+        };
+        let expression_point = voice.expression.update_note_expression(Default::default());
+        if self.voice_mode == VoiceMode::Legato {
+            EventStreamStep::new1(
+                0,
+                Event {
+                    sample_offset,
+                    data: EventData::Legato { data: to },
+                },
+                expression_point,
+            )
+        } else {
+            EventStreamStep::new2(
+                0,
+                Event {
+                    sample_offset,
+                    data: EventData::NoteOff { data: from },
+                },
+                EventData::NoteOn { data: to },
+                expression_point,
+            )
+        }
+    }
+
+    fn update_state_and_dispatch_for_mono_note_on(
+        &mut self,
+        sample_offset: usize,
+        data: &NoteData,
+    ) -> EventStreamStep {
+        let voice = &self.voices[0];
+        if voice.channel.is_some_and(|channel| channel != data.channel) {
+            return EventStreamStep::new0();
+        }
+        self.held_notes.note_on(*data);
+        match voice.playing {
+            VoicePlayingState::Note { id, .. } if id != data.id => {
+                let from = voice.synthetic_note_off_data();
+                self.move_mono_voice(sample_offset, from, *data)
+            }
+            _ => {
+                let voice = &mut self.voices[0];
+                voice.playing = VoicePlayingState::Note {
+                    order: 0,
+                    id: data.id,
+                    pitch: data.pitch,
+                    channel: data.channel,
+                    velocity: data.velocity,
+                    tuning: data.tuning,
+                };
+                EventStreamStep::new1(
+                    0,
+                    Event {
+                        sample_offset,
+                        data: EventData::NoteOn { data: *data },
+                    },
+                    voice.expression.update_note_expression(Default::default()),
+                )
+            }
+        }
+    }
+
+    fn update_state_and_dispatch_for_mono_note_off(
+        &mut self,
+        sample_offset: usize,
+        data: &NoteData,
+    ) -> EventStreamStep {
+        self.held_notes.note_off(data.id);
+        if let VoicePlayingState::Note { id, .. } = self.voices[0].playing {
+            if id == data.id {
+                if let Some(next) = self.held_notes.current(NotePriority::Last).copied() {
+                    return self.move_mono_voice(sample_offset, *data, next);
+                }
+            }
+        }
+
+        // Either no other notes are held, or this note isn't sounding on the mono
+        // voice. In the latter case, it may still be playing on another voice
+        // from before the mode changed.
+        self.update_state_and_dispatch_for_note_off(sample_offset, data)
+    }
+
     fn update_state_and_dispatch_for_note_off(
         &mut self,
         sample_offset: usize,
@@ -388,6 +623,7 @@ impl State {
             Voice {
                 playing,
                 expression,
+                ..
             },
         ) in self.voices.iter_mut().enumerate()
         {
@@ -474,9 +710,9 @@ impl State {
     }
 
     /// Note that the events must be sorted by time!
-    pub fn update(&mut self, events: impl IntoIterator<Item = events::Event>) {
+    pub fn update(&mut self, events: impl IntoIterator<Item = impl Borrow<events::Event>>) {
         for event in events {
-            self.update_state_and_dispatch_for_event(&event);
+            self.update_state_and_dispatch_for_event(event.borrow());
         }
 
         // compress orders - this keeps the `order` member bounded between buffers.
@@ -1,5 +1,6 @@
 use super::State;
 use crate::{Event, EventData};
+use crate::{VoiceMode, MONO_NOTE_STACK_SIZE};
 use conformal_component::events::{self as events, NoteData, NoteID};
 
 fn example_note_data(pitch: u8) -> NoteData {
@@ -8,6 +9,7 @@ fn example_note_data(pitch: u8) -> NoteData {
         pitch,
         velocity: 1.0,
         tuning: 0.0,
+        channel: 0,
     }
 }
 
@@ -245,3 +247,268 @@ fn reset_restors_state() {
         ),
     );
 }
+
+fn channel_note_data(channel: u8, pitch: u8) -> NoteData {
+    NoteData {
+        id: NoteID::from_id(i32::from(channel) * 128 + i32::from(pitch)),
+        channel,
+        ..example_note_data(pitch)
+    }
+}
+
+fn channel_note_on(time: usize, channel: u8, pitch: u8) -> events::Event {
+    events::Event {
+        sample_offset: time,
+        data: events::Data::NoteOn {
+            data: channel_note_data(channel, pitch),
+        },
+    }
+}
+
+fn channel_note_off(time: usize, channel: u8, pitch: u8) -> events::Event {
+    events::Event {
+        sample_offset: time,
+        data: events::Data::NoteOff {
+            data: channel_note_data(channel, pitch),
+        },
+    }
+}
+
+fn expected_channel_note_on(time: usize, channel: u8, pitch: u8) -> Event {
+    Event {
+        sample_offset: time,
+        data: EventData::NoteOn {
+            data: channel_note_data(channel, pitch),
+        },
+    }
+}
+
+fn expected_channel_note_off(time: usize, channel: u8, pitch: u8) -> Event {
+    Event {
+        sample_offset: time,
+        data: EventData::NoteOff {
+            data: channel_note_data(channel, pitch),
+        },
+    }
+}
+
+#[test]
+fn partitioned_channels_route_to_distinct_pools() {
+    // Voice 0 plays channel 0, voices 1 and 2 play channel 1.
+    let state = State::new_partitioned_by_channel(&[1, 2]);
+    let events = gather_events(
+        &state,
+        3,
+        vec![
+            channel_note_on(0, 1, 60),
+            channel_note_on(1, 0, 60),
+            channel_note_on(2, 1, 64),
+            channel_note_off(3, 0, 60),
+            channel_note_off(4, 1, 60),
+        ],
+    );
+    assert_eq!(
+        events[0],
+        vec![
+            expected_channel_note_on(1, 0, 60),
+            expected_channel_note_off(3, 0, 60),
+        ]
+    );
+    assert_events_match(
+        vec![
+            vec![
+                expected_channel_note_on(0, 1, 60),
+                expected_channel_note_off(4, 1, 60),
+            ],
+            vec![expected_channel_note_on(2, 1, 64)],
+        ],
+        events[1..].to_vec(),
+    );
+}
+
+#[test]
+fn partitioned_channels_steal_within_pool() {
+    let state = State::new_partitioned_by_channel(&[1, 1]);
+    let events = gather_events(
+        &state,
+        2,
+        vec![
+            channel_note_on(0, 1, 60),
+            channel_note_on(1, 0, 60),
+            channel_note_on(2, 0, 62),
+        ],
+    );
+    assert_eq!(
+        events,
+        vec![
+            vec![
+                expected_channel_note_on(1, 0, 60),
+                // The stolen note is turned off on its own channel.
+                expected_channel_note_off(2, 0, 60),
+                expected_channel_note_on(2, 0, 62),
+            ],
+            vec![expected_channel_note_on(0, 1, 60)],
+        ]
+    );
+}
+
+#[test]
+fn partitioned_channels_ignore_notes_without_pool() {
+    let state = State::new_partitioned_by_channel(&[1, 0]);
+    assert_eq!(
+        gather_events(
+            &state,
+            1,
+            vec![channel_note_on(0, 1, 60), channel_note_on(1, 2, 60)],
+        ),
+        vec![Vec::<Event>::new()]
+    );
+}
+
+#[test]
+#[should_panic(expected = "MIDI channels")]
+fn partitioning_over_too_many_channels_panics() {
+    let _ = State::new_partitioned_by_channel(&[1; 17]);
+}
+
+fn expected_legato(time: usize, pitch: u8) -> Event {
+    Event {
+        sample_offset: time,
+        data: EventData::Legato {
+            data: example_note_data(pitch),
+        },
+    }
+}
+
+fn mono_state(mode: VoiceMode) -> State {
+    let mut state = State::new(2);
+    state.set_voice_mode(mode);
+    state
+}
+
+#[test]
+fn legato_reuses_first_voice() {
+    assert_eq!(
+        gather_events(
+            &mono_state(VoiceMode::Legato),
+            2,
+            vec![
+                example_note_on(0, 60),
+                example_note_on(1, 62),
+                example_note_off(2, 62),
+                example_note_off(3, 60),
+            ],
+        ),
+        vec![
+            vec![
+                expected_note_on(0, 60),
+                expected_legato(1, 62),
+                // Releasing the top note falls back to the previous held note.
+                expected_legato(2, 60),
+                expected_note_off(3, 60),
+            ],
+            vec![],
+        ]
+    );
+}
+
+#[test]
+fn legato_releasing_non_top_note_does_nothing() {
+    assert_eq!(
+        gather_events(
+            &mono_state(VoiceMode::Legato),
+            1,
+            vec![
+                example_note_on(0, 60),
+                example_note_on(1, 62),
+                example_note_off(2, 60),
+                example_note_off(3, 62),
+            ],
+        ),
+        vec![vec![
+            expected_note_on(0, 60),
+            expected_legato(1, 62),
+            expected_note_off(3, 62),
+        ]]
+    );
+}
+
+#[test]
+fn legato_falls_back_through_note_stack() {
+    assert_eq!(
+        gather_events(
+            &mono_state(VoiceMode::Legato),
+            1,
+            vec![
+                example_note_on(0, 60),
+                example_note_on(1, 62),
+                example_note_on(2, 64),
+                example_note_off(3, 64),
+                example_note_off(4, 62),
+                example_note_off(5, 60),
+            ],
+        ),
+        vec![vec![
+            expected_note_on(0, 60),
+            expected_legato(1, 62),
+            expected_legato(2, 64),
+            expected_legato(3, 62),
+            expected_legato(4, 60),
+            expected_note_off(5, 60),
+        ]]
+    );
+}
+
+#[test]
+fn mono_retriggers_voice() {
+    assert_eq!(
+        gather_events(
+            &mono_state(VoiceMode::Mono),
+            1,
+            vec![
+                example_note_on(0, 60),
+                example_note_on(1, 62),
+                example_note_off(2, 62),
+                example_note_off(3, 60),
+            ],
+        ),
+        vec![vec![
+            expected_note_on(0, 60),
+            expected_note_off(1, 60),
+            expected_note_on(1, 62),
+            expected_note_off(2, 62),
+            expected_note_on(2, 60),
+            expected_note_off(3, 60),
+        ]]
+    );
+}
+
+#[test]
+fn legato_note_stack_is_bounded() {
+    let mut state = mono_state(VoiceMode::Legato);
+    let pitches = (0..).take(MONO_NOTE_STACK_SIZE + 1).collect::<Vec<u8>>();
+    state.update(pitches.iter().map(|pitch| example_note_on(0, *pitch)));
+    state.update(
+        pitches
+            .iter()
+            .skip(2)
+            .rev()
+            .map(|pitch| example_note_off(0, *pitch)),
+    );
+
+    // The oldest note was forgotten, so releasing the second note ends the phrase.
+    assert_eq!(
+        gather_events(&state, 1, vec![example_note_off(1, 1)]),
+        vec![vec![expected_note_off(1, 1)]]
+    );
+}
+
+#[test]
+fn legato_state_carries_across_buffers() {
+    let mut state = mono_state(VoiceMode::Legato);
+    state.update(vec![example_note_on(0, 60), example_note_on(1, 62)]);
+    assert_eq!(
+        gather_events(&state, 1, vec![example_note_off(0, 62)]),
+        vec![vec![expected_legato(0, 60)]]
+    );
+}
@@ -0,0 +1,797 @@
+use super::{
+    Event, EventData, NoteExpressionCurve, NoteExpressionPoint, NoteExpressionState, Pedals, Poly,
+    QuiescenceTracker, StealStrategy, Voice, VoiceSummingMode, PEDAL_EVENTS_CAPACITY,
+};
+use conformal_component::{
+    audio::{Buffer, BufferData, ChannelLayout},
+    events::{self as events, NoteData, NoteExpression, NoteExpressionData, NoteID},
+    parameters::{self, ConstantBufferStates, StaticInfoRef},
+    ProcessingEnvironment, ProcessingMode, Transport,
+};
+
+#[derive(Debug, Default)]
+struct SharedDataVoice {
+    playing: bool,
+}
+
+impl Voice for SharedDataVoice {
+    type SharedData<'a> = f32;
+
+    fn new(_max_samples_per_process_call: usize, _sampling_rate: f32) -> Self {
+        Default::default()
+    }
+
+    fn handle_event(&mut self, event: &EventData) {
+        self.playing = matches!(event, EventData::NoteOn { .. });
+    }
+
+    fn process(
+        &mut self,
+        events: impl IntoIterator<Item = Event>,
+        _params: &impl parameters::BufferStates,
+        _note_expressions: NoteExpressionCurve<impl Iterator<Item = NoteExpressionPoint> + Clone>,
+        data: f32,
+        output: &mut [f32],
+    ) -> usize {
+        for event in events {
+            self.handle_event(&event.data);
+        }
+        output.fill(data);
+        output.len()
+    }
+
+    fn quiescent(&self) -> bool {
+        !self.playing
+    }
+
+    fn reset(&mut self) {
+        self.playing = false;
+    }
+}
+
+fn example_environment() -> ProcessingEnvironment {
+    ProcessingEnvironment::new(48000.0, 16, ChannelLayout::Mono, ProcessingMode::Realtime)
+}
+
+fn example_note_on(pitch: u8) -> events::Event {
+    events::Event {
+        sample_offset: 0,
+        data: events::Data::NoteOn {
+            data: NoteData {
+                id: NoteID::from_pitch(pitch),
+                pitch,
+                velocity: 1.0,
+                tuning: 0.0,
+                channel: 0,
+            },
+        },
+    }
+}
+
+fn example_note_off(pitch: u8) -> events::Event {
+    events::Event {
+        sample_offset: 0,
+        data: events::Data::NoteOff {
+            data: NoteData {
+                id: NoteID::from_pitch(pitch),
+                pitch,
+                velocity: 1.0,
+                tuning: 0.0,
+                channel: 0,
+            },
+        },
+    }
+}
+
+#[test]
+fn shared_data_advances_once_per_buffer() {
+    let mut poly = Poly::<SharedDataVoice>::new(&example_environment(), 2);
+    let params = ConstantBufferStates::new_defaults(std::iter::empty::<StaticInfoRef>());
+    let mut advances: u16 = 0;
+    let mut output = BufferData::new(ChannelLayout::Mono, 16);
+
+    // Shared data should advance even when no voices are playing.
+    poly.process_with_shared_data(
+        std::iter::empty(),
+        &params,
+        |num_frames| {
+            assert_eq!(num_frames, 16);
+            advances += 1;
+            f32::from(advances)
+        },
+        &mut output,
+    );
+    assert_eq!(advances, 1);
+    assert_eq!(output.channel(0), [0.0; 16]);
+
+    for _ in 0..2 {
+        poly.process_with_shared_data(
+            [example_note_on(60), example_note_on(64)].into_iter(),
+            &params,
+            |_| {
+                advances += 1;
+                f32::from(advances)
+            },
+            &mut output,
+        );
+    }
+    assert_eq!(advances, 3);
+
+    // Both voices saw the same shared data, so their mix is exactly that value.
+    assert_eq!(output.channel(0), [3.0; 16]);
+}
+
+/// A voice that outputs a constant level offset derived from its seed.
+#[derive(Debug, Default)]
+struct DriftVoice {
+    playing: bool,
+    drift: f32,
+}
+
+impl Voice for DriftVoice {
+    type SharedData<'a> = ();
+
+    fn new(_max_samples_per_process_call: usize, _sampling_rate: f32) -> Self {
+        Default::default()
+    }
+
+    fn handle_event(&mut self, event: &EventData) {
+        self.playing = matches!(event, EventData::NoteOn { .. });
+    }
+
+    fn process(
+        &mut self,
+        events: impl IntoIterator<Item = Event>,
+        _params: &impl parameters::BufferStates,
+        _note_expressions: NoteExpressionCurve<impl Iterator<Item = NoteExpressionPoint> + Clone>,
+        (): (),
+        output: &mut [f32],
+    ) -> usize {
+        for event in events {
+            self.handle_event(&event.data);
+        }
+        output.fill(1.0 + self.drift);
+        output.len()
+    }
+
+    fn quiescent(&self) -> bool {
+        !self.playing
+    }
+
+    fn reset(&mut self) {
+        self.playing = false;
+    }
+
+    fn set_seed(&mut self, seed: u64) {
+        self.drift = f32::from(u16::try_from(seed % 1000).unwrap()) / 10000.0;
+    }
+}
+
+fn render_drift(seed: u64) -> Vec<f32> {
+    let mut poly = Poly::<DriftVoice>::new_with_seed(&example_environment(), 2, seed);
+    let params = ConstantBufferStates::new_defaults(std::iter::empty::<StaticInfoRef>());
+    let mut output = BufferData::new(ChannelLayout::Mono, 16);
+    poly.process([example_note_on(60)].into_iter(), &params, &(), &mut output);
+    output.channel(0).to_vec()
+}
+
+#[test]
+fn same_seed_gives_identical_output() {
+    assert_eq!(render_drift(42), render_drift(42));
+    assert_ne!(render_drift(42), render_drift(43));
+}
+
+#[test]
+fn voices_get_distinct_seeds() {
+    let seeds: Vec<_> = (0..8).map(|index| super::voice_seed(42, index)).collect();
+    for (index, seed) in seeds.iter().enumerate() {
+        assert!(!seeds[index + 1..].contains(seed));
+    }
+}
+
+#[test]
+fn flush_fades_voices_to_quiescent() {
+    let mut poly = Poly::<SharedDataVoice>::new(&example_environment(), 2);
+    let params = ConstantBufferStates::new_defaults(std::iter::empty::<StaticInfoRef>());
+    let mut output = BufferData::new(ChannelLayout::Mono, 16);
+    poly.process(
+        [example_note_on(60)].into_iter(),
+        &params,
+        &1.0,
+        &mut output,
+    );
+    assert_eq!(output.channel(0), [0.5; 16]);
+
+    poly.flush();
+    let mut flushed = Vec::new();
+    // The fade lasts 2ms, which is 96 samples at 48kHz.
+    for _ in 0..7 {
+        poly.process(std::iter::empty(), &params, &1.0, &mut output);
+        flushed.extend_from_slice(output.channel(0));
+    }
+    assert!(flushed[0] < 0.5);
+    assert!(flushed[0] > 0.45);
+    assert!(flushed.windows(2).all(|pair| pair[1] <= pair[0]));
+    assert!(flushed[96..].iter().all(|x| *x == 0.0));
+    assert!(poly.voices.iter().all(Voice::quiescent));
+
+    // New notes play normally after the flush.
+    poly.process(
+        [example_note_on(62)].into_iter(),
+        &params,
+        &1.0,
+        &mut output,
+    );
+    assert_eq!(output.channel(0), [0.5; 16]);
+}
+
+#[test]
+fn transport_stop_flushes_voices() {
+    let mut poly = Poly::<SharedDataVoice>::new(&example_environment(), 2);
+    let params = ConstantBufferStates::new_defaults(std::iter::empty::<StaticInfoRef>());
+    let mut output = BufferData::new(ChannelLayout::Mono, 16);
+    let transport = |playing| Transport {
+        playing: Some(playing),
+        ..Default::default()
+    };
+
+    // Starting the transport, or not knowing whether it's playing, doesn't flush.
+    poly.set_transport(&transport(true));
+    poly.set_transport(&Transport::default());
+    poly.process(
+        [example_note_on(60)].into_iter(),
+        &params,
+        &1.0,
+        &mut output,
+    );
+    assert_eq!(output.channel(0), [0.5; 16]);
+
+    poly.set_transport(&transport(false));
+    let mut flushed = Vec::new();
+    for _ in 0..7 {
+        poly.process(std::iter::empty(), &params, &1.0, &mut output);
+        flushed.extend_from_slice(output.channel(0));
+    }
+    assert!(flushed[0] < 0.5);
+    assert!(flushed[96..].iter().all(|x| *x == 0.0));
+    assert!(poly.voices.iter().all(Voice::quiescent));
+
+    // Staying stopped doesn't flush notes started afterwards.
+    poly.set_transport(&transport(false));
+    poly.process(
+        [example_note_on(62)].into_iter(),
+        &params,
+        &1.0,
+        &mut output,
+    );
+    assert_eq!(output.channel(0), [0.5; 16]);
+}
+
+/// A voice whose output decays exponentially from the start of each note, at a rate set by its pitch.
+#[derive(Debug)]
+struct DecayingVoice {
+    pitch: Option<u8>,
+    level: f32,
+    decay: f32,
+    tracker: QuiescenceTracker,
+}
+
+impl DecayingVoice {
+    fn handle_event_internal(&mut self, event: &EventData) {
+        match event {
+            EventData::NoteOn { data } => {
+                self.pitch = Some(data.pitch);
+                self.level = 1.0;
+                self.decay = match data.pitch {
+                    60 => 0.999,
+                    62 => 0.8,
+                    _ => 0.95,
+                };
+                self.tracker.trigger();
+            }
+            EventData::Legato { data } => self.pitch = Some(data.pitch),
+            EventData::NoteOff { .. } => self.pitch = None,
+        }
+    }
+}
+
+impl Voice for DecayingVoice {
+    type SharedData<'a> = ();
+
+    fn new(_max_samples_per_process_call: usize, _sampling_rate: f32) -> Self {
+        Self {
+            pitch: None,
+            level: 0.0,
+            decay: 1.0,
+            tracker: QuiescenceTracker::new(16),
+        }
+    }
+
+    fn handle_event(&mut self, event: &EventData) {
+        self.handle_event_internal(event);
+    }
+
+    fn process(
+        &mut self,
+        events: impl IntoIterator<Item = Event>,
+        _params: &impl parameters::BufferStates,
+        _note_expressions: NoteExpressionCurve<impl Iterator<Item = NoteExpressionPoint> + Clone>,
+        (): (),
+        output: &mut [f32],
+    ) -> usize {
+        for event in events {
+            self.handle_event_internal(&event.data);
+        }
+        for sample in output.iter_mut() {
+            *sample = self.level;
+            self.level *= self.decay;
+        }
+        self.tracker.update(output);
+        output.len()
+    }
+
+    fn quiescent(&self) -> bool {
+        self.tracker.quiescent()
+    }
+
+    fn output_level(&self) -> f32 {
+        self.tracker.recent_peak()
+    }
+
+    fn reset(&mut self) {
+        self.pitch = None;
+        self.level = 0.0;
+        self.tracker.reset();
+    }
+}
+
+fn render_with_steal_strategy(strategy: StealStrategy) -> Vec<Option<u8>> {
+    let mut poly = Poly::<DecayingVoice>::new(&example_environment(), 3);
+    poly.set_steal_strategy(strategy);
+    let params = ConstantBufferStates::new_defaults(std::iter::empty::<StaticInfoRef>());
+    let mut output = BufferData::new(ChannelLayout::Mono, 16);
+
+    // Start three notes that decay at different rates, oldest first.
+    for pitch in [60, 62, 64] {
+        poly.process(
+            [example_note_on(pitch)].into_iter(),
+            &params,
+            &(),
+            &mut output,
+        );
+    }
+    for _ in 0..4 {
+        poly.process(std::iter::empty(), &params, &(), &mut output);
+    }
+
+    // All voices are busy, so this note must steal one.
+    poly.process([example_note_on(67)].into_iter(), &params, &(), &mut output);
+    poly.voices.iter().map(|voice| voice.pitch).collect()
+}
+
+#[test]
+fn oldest_steal_strategy_steals_oldest_note() {
+    let pitches = render_with_steal_strategy(StealStrategy::Oldest);
+    assert!(!pitches.contains(&Some(60)));
+    assert!(pitches.contains(&Some(62)));
+    assert!(pitches.contains(&Some(64)));
+    assert!(pitches.contains(&Some(67)));
+}
+
+#[test]
+fn quietest_steal_strategy_steals_quietest_note() {
+    let pitches = render_with_steal_strategy(StealStrategy::Quietest);
+    assert!(pitches.contains(&Some(60)));
+    assert!(!pitches.contains(&Some(62)));
+    assert!(pitches.contains(&Some(64)));
+    assert!(pitches.contains(&Some(67)));
+}
+
+fn expression_point(sample_offset: usize, pitch_bend: f32) -> NoteExpressionPoint {
+    NoteExpressionPoint {
+        sample_offset,
+        state: NoteExpressionState {
+            pitch_bend,
+            ..Default::default()
+        },
+    }
+}
+
+fn pitch_bends<I: Iterator<Item = NoteExpressionPoint> + Clone>(
+    curve: NoteExpressionCurve<I>,
+    num_samples: usize,
+) -> Vec<f32> {
+    curve
+        .iter_by_sample()
+        .take(num_samples)
+        .map(|state| state.pitch_bend)
+        .collect()
+}
+
+#[test]
+fn from_events_sorts_out_of_order_events() {
+    let curve = NoteExpressionCurve::from_events(
+        [expression_point(4, 2.0), expression_point(2, 1.0)],
+        Default::default(),
+    );
+    assert_eq!(pitch_bends(curve, 6), [0.0, 0.0, 1.0, 1.0, 2.0, 2.0]);
+}
+
+#[test]
+fn from_events_inserts_initial_state_when_missing_zero() {
+    let curve = NoteExpressionCurve::from_events(
+        [expression_point(3, 1.0)],
+        NoteExpressionState {
+            pitch_bend: -1.0,
+            ..Default::default()
+        },
+    );
+    assert_eq!(pitch_bends(curve, 4), [-1.0, -1.0, -1.0, 1.0]);
+
+    let curve = NoteExpressionCurve::from_events([], Default::default());
+    assert_eq!(pitch_bends(curve, 2), [0.0, 0.0]);
+}
+
+#[test]
+fn from_events_prefers_later_events_at_the_same_time() {
+    let curve = NoteExpressionCurve::from_events(
+        [
+            expression_point(0, 1.0),
+            expression_point(2, 2.0),
+            expression_point(2, 3.0),
+        ],
+        Default::default(),
+    );
+    assert_eq!(pitch_bends(curve, 3), [1.0, 1.0, 3.0]);
+}
+
+/// A voice that outputs the pitch bend of the note it's playing.
+#[derive(Debug, Default)]
+struct PitchBendVoice {
+    playing: bool,
+}
+
+impl Voice for PitchBendVoice {
+    type SharedData<'a> = ();
+
+    fn new(_max_samples_per_process_call: usize, _sampling_rate: f32) -> Self {
+        Default::default()
+    }
+
+    fn handle_event(&mut self, event: &EventData) {
+        self.playing = matches!(event, EventData::NoteOn { .. });
+    }
+
+    fn process(
+        &mut self,
+        events: impl IntoIterator<Item = Event>,
+        _params: &impl parameters::BufferStates,
+        note_expressions: NoteExpressionCurve<impl Iterator<Item = NoteExpressionPoint> + Clone>,
+        (): (),
+        output: &mut [f32],
+    ) -> usize {
+        for event in events {
+            self.handle_event(&event.data);
+        }
+        for (sample, state) in output.iter_mut().zip(note_expressions.iter_by_sample()) {
+            *sample = state.pitch_bend;
+        }
+        output.len()
+    }
+
+    fn quiescent(&self) -> bool {
+        !self.playing
+    }
+
+    fn reset(&mut self) {
+        self.playing = false;
+    }
+}
+
+fn example_pitch_bend(sample_offset: usize, pitch: u8, pitch_bend: f32) -> events::Event {
+    events::Event {
+        sample_offset,
+        data: events::Data::NoteExpression {
+            data: NoteExpressionData {
+                id: NoteID::from_pitch(pitch),
+                expression: NoteExpression::PitchBend(pitch_bend),
+            },
+        },
+    }
+}
+
+#[test]
+fn expressions_beyond_voice_count_are_routed_to_playing_notes() {
+    let mut poly = Poly::<PitchBendVoice>::new(&example_environment(), 2);
+    assert_eq!(poly.max_expressive_notes(), 2);
+    let params = ConstantBufferStates::new_defaults(std::iter::empty::<StaticInfoRef>());
+    let mut output = BufferData::new(ChannelLayout::Mono, 16);
+
+    // The third note steals the voice of the first, so the first note's
+    // expression must be dropped rather than sent to another voice.
+    poly.process(
+        [
+            example_note_on(60),
+            example_note_on(62),
+            example_note_on(64),
+            example_pitch_bend(1, 62, 2.0),
+            example_pitch_bend(1, 64, 3.0),
+            example_pitch_bend(1, 60, 1.0),
+        ]
+        .into_iter(),
+        &params,
+        &(),
+        &mut output,
+    );
+    assert_eq!(output.channel(0)[..2], [0.0, 2.5]);
+    assert_eq!(output.channel(0)[1..], [2.5; 15]);
+
+    // Expressions keep being routed correctly in later buffers.
+    poly.process(
+        [
+            example_pitch_bend(0, 60, -1.0),
+            example_pitch_bend(0, 62, 0.0),
+        ]
+        .into_iter(),
+        &params,
+        &(),
+        &mut output,
+    );
+    assert_eq!(output.channel(0), [1.5; 16]);
+}
+
+/// A voice that outputs a sine wave whose frequency is set by its pitch.
+#[derive(Debug, Default)]
+struct SineVoice {
+    pitch: Option<u8>,
+    phase: f32,
+}
+
+impl Voice for SineVoice {
+    type SharedData<'a> = ();
+
+    fn new(_max_samples_per_process_call: usize, _sampling_rate: f32) -> Self {
+        Default::default()
+    }
+
+    fn handle_event(&mut self, event: &EventData) {
+        match event {
+            EventData::NoteOn { data } => {
+                self.pitch = Some(data.pitch);
+                self.phase = 0.0;
+            }
+            EventData::Legato { data } => self.pitch = Some(data.pitch),
+            EventData::NoteOff { .. } => self.pitch = None,
+        }
+    }
+
+    fn process(
+        &mut self,
+        events: impl IntoIterator<Item = Event>,
+        _params: &impl parameters::BufferStates,
+        _note_expressions: NoteExpressionCurve<impl Iterator<Item = NoteExpressionPoint> + Clone>,
+        (): (),
+        output: &mut [f32],
+    ) -> usize {
+        for event in events {
+            self.handle_event(&event.data);
+        }
+        let increment = f32::from(self.pitch.unwrap_or_default()) * 0.013;
+        for sample in output.iter_mut() {
+            *sample = self.phase.sin();
+            self.phase += increment;
+        }
+        output.len()
+    }
+
+    fn quiescent(&self) -> bool {
+        self.pitch.is_none()
+    }
+
+    fn reset(&mut self) {
+        self.pitch = None;
+        self.phase = 0.0;
+    }
+}
+
+fn render_chord(pitches: &[u8]) -> (Vec<f32>, Vec<Option<u8>>) {
+    let mut poly = Poly::<SineVoice>::new(&example_environment(), 4);
+    let params = ConstantBufferStates::new_defaults(std::iter::empty::<StaticInfoRef>());
+    let mut output = BufferData::new(ChannelLayout::Mono, 16);
+    let mut rendered = Vec::new();
+    poly.process(
+        pitches.iter().map(|pitch| example_note_on(*pitch)),
+        &params,
+        &(),
+        &mut output,
+    );
+    rendered.extend_from_slice(output.channel(0));
+    for _ in 0..4 {
+        poly.process(std::iter::empty(), &params, &(), &mut output);
+        rendered.extend_from_slice(output.channel(0));
+    }
+    (
+        rendered,
+        poly.voices.iter().map(|voice| voice.pitch).collect(),
+    )
+}
+
+#[test]
+fn same_events_give_identical_mix() {
+    assert_eq!(render_chord(&[60, 64, 67]), render_chord(&[60, 64, 67]));
+}
+
+#[test]
+fn voice_assignment_does_not_change_mix() {
+    let (forward, forward_voices) = render_chord(&[60, 64, 67]);
+    let (reversed, reversed_voices) = render_chord(&[67, 64, 60]);
+
+    // The same notes are playing, but in different voices.
+    assert_ne!(forward_voices, reversed_voices);
+    assert!(forward.iter().any(|x| x.abs() > 0.1));
+    assert!(forward
+        .iter()
+        .zip(&reversed)
+        .all(|(a, b)| (a - b).abs() < 1e-6));
+}
+
+/// A voice that fills its whole buffer, but only reports rendering some of it.
+#[derive(Debug, Default)]
+struct PartialVoice {
+    playing: bool,
+}
+
+impl Voice for PartialVoice {
+    type SharedData<'a> = usize;
+
+    fn new(_max_samples_per_process_call: usize, _sampling_rate: f32) -> Self {
+        Default::default()
+    }
+
+    fn handle_event(&mut self, event: &EventData) {
+        self.playing = matches!(event, EventData::NoteOn { .. });
+    }
+
+    #[allow(clippy::float_cmp)]
+    fn process(
+        &mut self,
+        events: impl IntoIterator<Item = Event>,
+        _params: &impl parameters::BufferStates,
+        _note_expressions: NoteExpressionCurve<impl Iterator<Item = NoteExpressionPoint> + Clone>,
+        rendered: usize,
+        output: &mut [f32],
+    ) -> usize {
+        for event in events {
+            self.handle_event(&event.data);
+        }
+        assert!(output.iter().all(|sample| *sample == 0.0));
+        output.fill(1.0);
+        rendered
+    }
+
+    fn quiescent(&self) -> bool {
+        !self.playing
+    }
+
+    fn reset(&mut self) {
+        self.playing = false;
+    }
+}
+
+#[test]
+fn samples_past_rendered_length_are_silent() {
+    let mut poly = Poly::<PartialVoice>::new(&example_environment(), 2);
+    let params = ConstantBufferStates::new_defaults(std::iter::empty::<StaticInfoRef>());
+    let mut output = BufferData::new(ChannelLayout::Mono, 16);
+
+    poly.process(
+        [example_note_on(60), example_note_on(64)].into_iter(),
+        &params,
+        &4,
+        &mut output,
+    );
+    assert_eq!(output.channel(0)[..4], [1.0; 4]);
+    assert_eq!(output.channel(0)[4..], [0.0; 12]);
+
+    poly.process(std::iter::empty(), &params, &0, &mut output);
+    assert_eq!(output.channel(0), [0.0; 16]);
+}
+
+#[test]
+fn reports_active_voices_and_their_notes() {
+    let mut poly = Poly::<DecayingVoice>::new(&example_environment(), 3);
+    let params = ConstantBufferStates::new_defaults(std::iter::empty::<StaticInfoRef>());
+    let mut output = BufferData::new(ChannelLayout::Mono, 16);
+    assert_eq!(poly.active_voice_count(), 0);
+    assert!(poly.voice_notes().all(|(_, note)| note.is_none()));
+
+    poly.process(
+        [example_note_on(60), example_note_on(64)].into_iter(),
+        &params,
+        &(),
+        &mut output,
+    );
+    assert_eq!(poly.active_voice_count(), 2);
+    let mut pitches = poly
+        .voice_notes()
+        .filter_map(|(_, note)| note.map(|note| note.pitch))
+        .collect::<Vec<_>>();
+    pitches.sort_unstable();
+    assert_eq!(pitches, vec![60, 64]);
+    assert_eq!(
+        poly.voice_notes()
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>(),
+        vec![0, 1, 2]
+    );
+
+    // Released voices still count as active until they fall silent.
+    poly.process(
+        [example_note_off(60), example_note_off(64)].into_iter(),
+        &params,
+        &(),
+        &mut output,
+    );
+    assert!(poly.voice_notes().all(|(_, note)| note.is_none()));
+    assert_eq!(poly.active_voice_count(), 2);
+
+    poly.reset();
+    assert_eq!(poly.active_voice_count(), 0);
+}
+
+#[test]
+fn events_beyond_pedal_capacity_are_dropped() {
+    let mut poly = Poly::<DecayingVoice>::new(&example_environment(), 3);
+    poly.set_pedals(Some(Pedals::new(16)));
+    let params = ConstantBufferStates::new_defaults(std::iter::empty::<StaticInfoRef>());
+    let mut output = BufferData::new(ChannelLayout::Mono, 16);
+
+    let filler = std::iter::repeat([example_note_on(60), example_note_off(60)])
+        .take(PEDAL_EVENTS_CAPACITY / 2)
+        .flatten();
+    poly.process(
+        filler.chain([example_note_on(64)]),
+        &params,
+        &(),
+        &mut output,
+    );
+    assert!(poly.voice_notes().all(|(_, note)| note.is_none()));
+
+    poly.process([example_note_on(64)].into_iter(), &params, &(), &mut output);
+    assert!(poly
+        .voice_notes()
+        .any(|(_, note)| note.is_some_and(|note| note.pitch == 64)));
+}
+
+#[test]
+#[allow(clippy::float_cmp)]
+fn summing_mode_sets_voice_scale() {
+    let params = ConstantBufferStates::new_defaults(std::iter::empty::<StaticInfoRef>());
+    for (mode, expected) in [
+        (VoiceSummingMode::DivideByVoices, 0.25),
+        (VoiceSummingMode::NoScaling, 1.0),
+        (VoiceSummingMode::FixedScale(0.5), 0.5),
+    ] {
+        let mut poly = Poly::<SharedDataVoice>::new(&example_environment(), 4);
+        poly.set_summing_mode(mode);
+        let mut output = BufferData::new(ChannelLayout::Mono, 16);
+        poly.process(
+            [example_note_on(60)].into_iter(),
+            &params,
+            &1.0,
+            &mut output,
+        );
+        assert_eq!(output.channel(0), [expected; 16], "{mode:?}");
+
+        // Resetting and replaying gives identical output.
+        poly.reset();
+        let mut replayed = BufferData::new(ChannelLayout::Mono, 16);
+        poly.process(
+            [example_note_on(60)].into_iter(),
+            &params,
+            &1.0,
+            &mut replayed,
+        );
+        assert_eq!(output.channel(0), replayed.channel(0), "{mode:?}");
+    }
+}
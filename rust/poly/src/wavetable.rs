@@ -0,0 +1,275 @@
+use std::f64::consts::TAU;
+
+/// The number of samples in each mip-map level of a [`Wavetable`].
+const TABLE_SIZE: usize = 2048;
+
+/// The number of mip-map levels in a [`Wavetable`].
+const NUM_TABLES: usize = 10;
+
+/// The number of harmonics kept in the first (brightest) mip-map level.
+///
+/// Each subsequent level keeps half as many, down to a single harmonic in the last level.
+/// Keeping this well below `TABLE_SIZE / 2` leaves headroom for interpolation.
+const MAX_HARMONICS: usize = TABLE_SIZE / 4;
+
+/// How a [`WavetableOscillator`] interpolates between the samples of a table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Interpolation {
+    /// Linear interpolation between the two nearest samples.
+    ///
+    /// This is the cheapest option, but adds slightly more noise.
+    Linear,
+
+    /// Cubic (Catmull-Rom) interpolation between the four nearest samples.
+    ///
+    /// This is the default.
+    #[default]
+    Cubic,
+}
+
+/// A set of band-limited mip-maps of a single-cycle waveform, for use with a
+/// [`WavetableOscillator`].
+///
+/// The waveform is split into harmonics once at construction time, and then
+/// re-synthesized into 10 levels of 2048 samples each. The first level contains the first 512 harmonics of the waveform,
+/// and each subsequent level contains half as many harmonics as the one before,
+/// down to a pure sine in the last level.
+///
+/// Construction allocates and is fairly expensive, so it should be done outside
+/// of audio processing, for example when the component is created. After that,
+/// the table is immutable and may be shared between any number of oscillators,
+/// for example by passing a reference through [`crate::Voice::SharedData`].
+///
+/// # Memory usage
+///
+/// Each wavetable holds 10 levels of 2048 `f32` samples, or 80 KiB, regardless of
+/// the length of the waveform it was built from.
+///
+/// # Table selection
+///
+/// For each sample, the oscillator picks the levels based on the current frequency,
+/// such that no harmonic is ever at or above the Nyquist frequency. Rather than
+/// switching abruptly between levels, it crossfades between two adjacent levels so that
+/// the highest octave of harmonics fades out smoothly as it approaches Nyquist.
+///
+/// Concretely, all 512 harmonics are used at frequencies up to `sampling_rate / 2048`
+/// (about 23 Hz at 48 kHz). Each time the frequency doubles, the oscillator moves
+/// one level down, losing half of its harmonics. Above `sampling_rate / 4`, only the
+/// fundamental remains.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Wavetable {
+    tables: Vec<f32>,
+}
+
+impl Wavetable {
+    /// Creates a new wavetable from a single cycle of a waveform.
+    ///
+    /// `waveform` can be any length, but only harmonics that can be represented
+    /// with that many samples will be included, so it should usually be at least
+    /// 1024 samples long to get the full bandwidth of the table.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `waveform` is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use conformal_poly::Wavetable;
+    /// let saw: Vec<f32> = (0..2048).map(|i| i as f32 / 1024.0 - 1.0).collect();
+    /// let table = Wavetable::new(&saw);
+    /// ```
+    #[must_use]
+    #[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation)]
+    pub fn new(waveform: &[f32]) -> Self {
+        assert!(!waveform.is_empty(), "waveform must not be empty");
+
+        let len = waveform.len();
+        let num_harmonics = MAX_HARMONICS.min((len - 1) / 2);
+        let cos_table = (0..len)
+            .map(|n| (TAU * n as f64 / len as f64).cos())
+            .collect::<Vec<_>>();
+        let sin_table = (0..len)
+            .map(|n| (TAU * n as f64 / len as f64).sin())
+            .collect::<Vec<_>>();
+        let dc = waveform.iter().map(|x| f64::from(*x)).sum::<f64>() / len as f64;
+        let harmonics = (1..=num_harmonics)
+            .map(|h| {
+                let (cos, sin) =
+                    waveform
+                        .iter()
+                        .enumerate()
+                        .fold((0.0, 0.0), |(cos, sin), (n, x)| {
+                            let index = (h * n) % len;
+                            let x = f64::from(*x);
+                            (cos + x * cos_table[index], sin + x * sin_table[index])
+                        });
+                (2.0 * cos / len as f64, 2.0 * sin / len as f64)
+            })
+            .collect::<Vec<_>>();
+
+        let cos_table = (0..TABLE_SIZE)
+            .map(|n| (TAU * n as f64 / TABLE_SIZE as f64).cos())
+            .collect::<Vec<_>>();
+        let sin_table = (0..TABLE_SIZE)
+            .map(|n| (TAU * n as f64 / TABLE_SIZE as f64).sin())
+            .collect::<Vec<_>>();
+
+        // We build the levels from the darkest to the brightest, adding in the
+        // extra harmonics for each level as we go.
+        let mut level = vec![dc; TABLE_SIZE];
+        let mut tables = vec![0.0; NUM_TABLES * TABLE_SIZE];
+        let mut included = 0;
+        for index in (0..NUM_TABLES).rev() {
+            let level_harmonics = num_harmonics.min(MAX_HARMONICS >> index);
+            for (h, (cos, sin)) in harmonics
+                .iter()
+                .enumerate()
+                .take(level_harmonics)
+                .skip(included)
+            {
+                let h = h + 1;
+                for (n, sample) in level.iter_mut().enumerate() {
+                    let index = (h * n) % TABLE_SIZE;
+                    *sample += cos * cos_table[index] + sin * sin_table[index];
+                }
+            }
+            included = included.max(level_harmonics);
+            for (dest, src) in tables[index * TABLE_SIZE..(index + 1) * TABLE_SIZE]
+                .iter_mut()
+                .zip(level.iter())
+            {
+                *dest = *src as f32;
+            }
+        }
+
+        Self { tables }
+    }
+
+    fn table(&self, index: usize) -> &[f32] {
+        &self.tables[index * TABLE_SIZE..(index + 1) * TABLE_SIZE]
+    }
+
+    /// Looks up the value at `phase` (from 0 to 1) for a given phase increment per sample.
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_precision_loss,
+        clippy::cast_sign_loss
+    )]
+    fn lookup(&self, phase: f64, increment: f32, interpolation: Interpolation) -> f32 {
+        // Level `l` is safe to use while `increment * (MAX_HARMONICS >> l) <= 0.5`.
+        // We fade the highest harmonics of the safe level out as they approach Nyquist.
+        let position = ((2.0 * MAX_HARMONICS as f32 * increment.abs()).log2() + 1.0)
+            .clamp(0.0, (NUM_TABLES - 1) as f32);
+        let index = position as usize;
+        let fade = position - index as f32;
+
+        let table_position = phase * TABLE_SIZE as f64;
+        let sample = table_position as usize;
+        let fraction = (table_position - sample as f64) as f32;
+
+        let value = interpolate(self.table(index), sample, fraction, interpolation);
+        if fade > 0.0 && index + 1 < NUM_TABLES {
+            let next = interpolate(self.table(index + 1), sample, fraction, interpolation);
+            value + (next - value) * fade
+        } else {
+            value
+        }
+    }
+}
+
+fn interpolate(table: &[f32], sample: usize, fraction: f32, interpolation: Interpolation) -> f32 {
+    let at = |offset: usize| table[(sample + offset) & (TABLE_SIZE - 1)];
+    match interpolation {
+        Interpolation::Linear => {
+            let (x0, x1) = (at(0), at(1));
+            x0 + (x1 - x0) * fraction
+        }
+        Interpolation::Cubic => {
+            let (xm1, x0, x1, x2) = (at(TABLE_SIZE - 1), at(0), at(1), at(2));
+            let c1 = 0.5 * (x1 - xm1);
+            let c2 = xm1 - 2.5 * x0 + 2.0 * x1 - 0.5 * x2;
+            let c3 = 0.5 * (x2 - xm1) + 1.5 * (x0 - x1);
+            ((c3 * fraction + c2) * fraction + c1) * fraction + x0
+        }
+    }
+}
+
+/// An oscillator that plays back a [`Wavetable`] without aliasing.
+///
+/// The oscillator itself only holds the current phase, so each voice can have
+/// its own oscillator while sharing a single [`Wavetable`]. Processing never allocates.
+///
+/// See [`Wavetable`] for details on how the band-limited tables are chosen.
+///
+/// # Examples
+///
+/// ```
+/// # use conformal_poly::{Interpolation, Wavetable, WavetableOscillator};
+/// let sine: Vec<f32> = (0..2048)
+///     .map(|i| (std::f32::consts::TAU * i as f32 / 2048.0).sin())
+///     .collect();
+/// let table = Wavetable::new(&sine);
+/// let mut oscillator = WavetableOscillator::new(48000.0, Interpolation::Cubic);
+///
+/// let mut output = [0.0; 4];
+/// oscillator.process(&table, std::iter::repeat(12000.0), &mut output);
+/// for (actual, expected) in output.iter().zip([0.0, 1.0, 0.0, -1.0]) {
+///     assert!((actual - expected).abs() < 1e-4);
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct WavetableOscillator {
+    sampling_rate: f32,
+    interpolation: Interpolation,
+    phase: f64,
+}
+
+impl WavetableOscillator {
+    /// Creates a new oscillator, starting at phase 0.
+    #[must_use]
+    pub fn new(sampling_rate: f32, interpolation: Interpolation) -> Self {
+        Self {
+            sampling_rate,
+            interpolation,
+            phase: 0.0,
+        }
+    }
+
+    /// Returns the next sample of `table` at `frequency`, in Hz, and advances the phase.
+    ///
+    /// Negative frequencies play the waveform backwards. Frequencies at or above
+    /// the Nyquist frequency will alias.
+    pub fn next_sample(&mut self, table: &Wavetable, frequency: f32) -> f32 {
+        let increment = frequency / self.sampling_rate;
+        let value = table.lookup(self.phase, increment, self.interpolation);
+        self.phase += f64::from(increment);
+        self.phase -= self.phase.floor();
+        value
+    }
+
+    /// Renders `table` into `output`, with one frequency, in Hz, per sample.
+    ///
+    /// If `frequencies` runs out before `output` is full, the rest of `output` is
+    /// left unchanged.
+    pub fn process(
+        &mut self,
+        table: &Wavetable,
+        frequencies: impl IntoIterator<Item = f32>,
+        output: &mut [f32],
+    ) {
+        for (sample, frequency) in output.iter_mut().zip(frequencies) {
+            *sample = self.next_sample(table, frequency);
+        }
+    }
+
+    /// Resets the oscillator's phase to 0.
+    ///
+    /// This can be used to implement [`crate::Voice::reset`].
+    pub fn reset(&mut self) {
+        self.phase = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests;
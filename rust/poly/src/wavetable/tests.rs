@@ -0,0 +1,126 @@
+use std::f64::consts::TAU;
+
+use super::{Interpolation, Wavetable, WavetableOscillator};
+
+/// We use a sampling rate equal to the analysis length, so that integer frequencies
+/// land exactly on analysis bins.
+const ANALYSIS_SIZE: usize = 4096;
+
+#[allow(clippy::cast_precision_loss)]
+fn saw() -> Vec<f32> {
+    (0..2048).map(|i| i as f32 / 1024.0 - 1.0).collect()
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn render(table: &Wavetable, interpolation: Interpolation, frequency: usize) -> Vec<f32> {
+    let mut oscillator = WavetableOscillator::new(ANALYSIS_SIZE as f32, interpolation);
+    let mut output = vec![0.0; ANALYSIS_SIZE];
+    oscillator.process(table, std::iter::repeat(frequency as f32), &mut output);
+    output
+}
+
+/// Returns the energy in the given bin of the signal.
+#[allow(clippy::cast_precision_loss)]
+fn bin_energy(signal: &[f32], bin: usize) -> f64 {
+    let (re, im) = signal
+        .iter()
+        .enumerate()
+        .fold((0.0, 0.0), |(re, im), (n, x)| {
+            let angle = TAU * ((bin * n) % signal.len()) as f64 / signal.len() as f64;
+            let x = f64::from(*x);
+            (re + x * angle.cos(), im - x * angle.sin())
+        });
+    let scale = if bin == 0 { 1.0 } else { 2.0 };
+    scale * (re * re + im * im) / signal.len() as f64
+}
+
+/// Returns the ratio of the energy outside of harmonics of `frequency` to the total energy.
+///
+/// Since `frequency` is odd and the analysis size is a power of two, any
+/// aliased harmonic will land outside of the harmonic bins.
+fn aliased_ratio(signal: &[f32], frequency: usize) -> f64 {
+    let total = signal.iter().map(|x| f64::from(*x).powi(2)).sum::<f64>();
+    let harmonic = (0..ANALYSIS_SIZE / 2)
+        .step_by(frequency)
+        .map(|bin| bin_energy(signal, bin))
+        .sum::<f64>();
+    (total - harmonic).max(0.0) / total
+}
+
+#[test]
+#[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+fn sine_table_plays_sine() {
+    let sine = (0..2048)
+        .map(|i| (TAU * f64::from(i) / 2048.0).sin() as f32)
+        .collect::<Vec<_>>();
+    let table = Wavetable::new(&sine);
+    for interpolation in [Interpolation::Linear, Interpolation::Cubic] {
+        let output = render(&table, interpolation, 101);
+        for (n, actual) in output.iter().enumerate() {
+            let expected = (TAU * 101.0 * n as f64 / ANALYSIS_SIZE as f64).sin();
+            assert!(
+                (f64::from(*actual) - expected).abs() < 1e-3,
+                "Mismatch at sample {n} with {interpolation:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn saw_does_not_alias_across_pitch_range() {
+    let table = Wavetable::new(&saw());
+    // Linear interpolation adds a little more noise at low frequencies,
+    // so we hold it to a looser (-50 dB) standard than cubic (-60 dB).
+    for (interpolation, limit) in [(Interpolation::Linear, 1e-5), (Interpolation::Cubic, 1e-6)] {
+        for frequency in [3, 11, 41, 101, 301, 701, 1001, 1501, 1901] {
+            let output = render(&table, interpolation, frequency);
+            let ratio = aliased_ratio(&output, frequency);
+            assert!(
+                ratio < limit,
+                "Aliasing of {ratio} at {frequency} with {interpolation:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn low_notes_keep_high_harmonics() {
+    let table = Wavetable::new(&saw());
+    let output = render(&table, Interpolation::Cubic, 3);
+
+    // A saw's harmonics fall off at 6 dB per octave, so the 100th harmonic
+    // should have 1/10000th the energy of the fundamental.
+    let fundamental = bin_energy(&output, 3);
+    let high = bin_energy(&output, 300);
+    assert!((high / fundamental - 1e-4).abs() < 1e-5);
+}
+
+#[test]
+fn high_notes_lose_harmonics_gradually() {
+    let table = Wavetable::new(&saw());
+    let second_harmonic = |frequency| {
+        let output = render(&table, Interpolation::Cubic, frequency);
+        bin_energy(&output, 2 * frequency) / bin_energy(&output, frequency)
+    };
+
+    // The second harmonic fades out as it approaches Nyquist.
+    let low = second_harmonic(401);
+    let mid = second_harmonic(701);
+    let high = second_harmonic(901);
+    assert!((low - 0.25).abs() < 1e-3);
+    assert!(mid < low && mid > 0.0);
+    assert!(high < mid);
+}
+
+#[test]
+#[allow(clippy::float_cmp)]
+fn reset_restarts_phase() {
+    let table = Wavetable::new(&saw());
+    let mut oscillator = WavetableOscillator::new(48000.0, Interpolation::default());
+    let mut first = [0.0; 64];
+    oscillator.process(&table, std::iter::repeat(440.0), &mut first);
+    oscillator.reset();
+    let mut second = [0.0; 64];
+    oscillator.process(&table, std::iter::repeat(440.0), &mut second);
+    assert_eq!(first, second);
+}
@@ -20,8 +20,15 @@ use conformal_core::parameters::store;
 mod preferences_convert;
 mod protocol;
 mod server;
+mod ui_state;
 mod web_ui;
 
+/// The parameters that can be accessed by the user interface.
+///
+/// For synths, this includes the controller parameters described in
+/// [`conformal_component::synth::CONTROLLER_PARAMETERS`]. These can be
+/// read and set like any other parameter, but are never saved in the
+/// component state.
 pub trait ParameterStore {
     fn get(&self, unique_id: &str) -> Option<parameters::Value>;
 
@@ -29,6 +36,10 @@ pub trait ParameterStore {
 
     /// Sets a value on the parameter store.
     ///
+    /// Values outside the parameter's valid range are rejected rather than clamped.
+    /// To clamp instead, pass the value through
+    /// [`conformal_component::parameters::clamp_to_info`] first.
+    ///
     /// # Errors
     ///
     /// - `SetError::NotFound` if there is no parameter with the given unique ID.
@@ -43,8 +54,31 @@ pub trait ParameterStore {
     /// - `SetError::NotFound` if there is no parameter with the given unique ID.
     fn set_grabbed(&mut self, unique_id: &str, grabbed: bool)
         -> Result<(), store::SetGrabbedError>;
+
+    /// Sets the parameter back to its default value.
+    ///
+    /// This is set through the same path as `set`, so the change is visible to the host.
+    ///
+    /// # Errors
+    ///
+    /// - `SetError::NotFound` if there is no parameter with the given unique ID.
+    fn reset_to_default(&mut self, unique_id: &str) -> Result<(), store::SetError> {
+        let info = self.get_info(unique_id).ok_or(store::SetError::NotFound)?;
+        self.set(unique_id, parameters::default_value(&info))
+    }
+
+    /// Gets the UI state, an opaque blob of bytes saved along with the rest of
+    /// the plug-in's state.
+    ///
+    /// This is empty if no UI state has been stored. See [`serialize_ui_state`] for
+    /// a way to store structured state here.
+    fn get_ui_state(&self) -> Vec<u8>;
+
+    /// Replaces the UI state.
+    fn set_ui_state(&mut self, state: &[u8]);
 }
 
+pub use ui_state::{deserialize_ui_state, serialize_ui_state, ui_state_version, UiStateError};
 pub use web_ui::Size;
 pub use web_ui::Ui;
 pub use wry::raw_window_handle;
@@ -132,6 +132,7 @@ pub mod parameter_info {
                     default,
                     valid_range,
                     units,
+                    ..
                 } => Self::Numeric {
                     default,
                     valid_range: (*valid_range.start(), *valid_range.end()),
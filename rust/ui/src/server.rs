@@ -5,6 +5,11 @@ use super::protocol;
 use conformal_component::parameters;
 use conformal_preferences::Store as PreferenceStore;
 
+/// The path the UI uses to read and write the UI state blob.
+///
+/// See [`crate::serialize_ui_state`] for the format UIs are expected to store here.
+const UI_STATE_PATH: &str = "ui-state";
+
 /// It is the job of the server to connect the UI to the state of the plug-in.
 pub struct Server<S, R> {
     param_store: S,
@@ -68,6 +73,12 @@ impl<S: super::ParameterStore, R: ResponseSender> Server<S, R> {
                         });
                         return;
                     }
+                } else if path == UI_STATE_PATH {
+                    self.subscriptions.insert(path.clone());
+                    self.response_sender.send(protocol::Response::Values {
+                        values: [(path.clone(), self.param_store.get_ui_state().into())].into(),
+                    });
+                    return;
                 }
                 self.response_sender
                     .send(protocol::Response::SubscribeValueError { path: path.clone() });
@@ -101,6 +112,10 @@ impl<S: super::ParameterStore, R: ResponseSender> Server<S, R> {
                             }
                         }
                     }
+                } else if path == UI_STATE_PATH {
+                    if let protocol::Value::Bytes(state) = value {
+                        self.param_store.set_ui_state(state);
+                    }
                 }
             }
         }
@@ -117,6 +132,17 @@ impl<S: super::ParameterStore, R: ResponseSender> Server<S, R> {
         }
     }
 
+    /// Handle a change to the UI state that didn't come from the UI, for example
+    /// when the host restores a saved state.
+    /// Note that this _may_ call `send` on the `response_sender` passed to `new`.
+    pub fn update_ui_state(&mut self, state: &[u8]) {
+        if self.subscriptions.contains(UI_STATE_PATH) {
+            self.response_sender.send(protocol::Response::Values {
+                values: [(UI_STATE_PATH.to_string(), state.to_vec().into())].into(),
+            });
+        }
+    }
+
     pub fn update_preference(&mut self, unique_id: &str, value: &conformal_preferences::Value) {
         let path = format!("prefs/{unique_id}");
         if self.subscriptions.contains(&path) {
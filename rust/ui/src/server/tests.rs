@@ -34,6 +34,7 @@ impl<'a> ResponseSender for ResponseSenderSpy<'a> {
 struct StubStoreData {
     values: HashMap<String, conformal_component::parameters::Value>,
     grabbed: HashSet<String>,
+    ui_state: Vec<u8>,
 }
 
 impl<I: IntoIterator<Item = (String, conformal_component::parameters::Value)>> From<I>
@@ -43,6 +44,7 @@ impl<I: IntoIterator<Item = (String, conformal_component::parameters::Value)>> F
         StubStoreData {
             values: values.into_iter().collect(),
             grabbed: Default::default(),
+            ui_state: Default::default(),
         }
     }
 }
@@ -77,17 +79,29 @@ impl crate::ParameterStore for StubStore {
         Ok(())
     }
 
+    fn get_ui_state(&self) -> Vec<u8> {
+        self.values.borrow().ui_state.clone()
+    }
+
+    fn set_ui_state(&mut self, state: &[u8]) {
+        self.values.borrow_mut().ui_state = state.to_vec();
+    }
+
     fn get_info(&self, unique_id: &str) -> Option<conformal_component::parameters::Info> {
         if unique_id == "a" {
             Some(conformal_component::parameters::Info {
                 title: "Test Title".to_string(),
                 short_title: "Test Short Title".to_string(),
                 unique_id: "a".to_string(),
-                flags: conformal_component::parameters::Flags { automatable: true },
+                flags: conformal_component::parameters::Flags {
+                    automatable: true,
+                    curve_hint: conformal_component::parameters::CurveHint::Auto,
+                },
                 type_specific: conformal_component::parameters::TypeSpecificInfo::Numeric {
                     default: 1.0,
                     valid_range: 0.0..=10.0,
                     units: Some("Hz".to_string()),
+                    options: Default::default(),
                 },
             })
         } else {
@@ -418,3 +432,51 @@ fn get_set_preferences() {
         }
     }));
 }
+
+#[test]
+fn get_set_ui_state() {
+    let sent = RefCell::new(Vec::new());
+    let sender = ResponseSenderSpy {
+        sent: &sent,
+        pref_updates: &RefCell::new(Default::default()),
+    };
+    let store = StubStore {
+        values: Rc::new(RefCell::new([("a".to_string(), 1.0.into())].into())),
+    };
+    let mut server = Server::new(
+        store.clone(),
+        Box::new(RefCell::new(
+            conformal_preferences::create_with_fake_os_store(Default::default()),
+        )),
+        sender,
+    );
+    server.handle_request(&Request::Subscribe {
+        path: "ui-state".to_string(),
+    });
+    assert!(sent.borrow().iter().any(|m| {
+        match m {
+            Response::Values { values } => values
+                .iter()
+                .any(|(p, v)| p == "ui-state" && v == &protocol::Value::Bytes(vec![])),
+            _ => false,
+        }
+    }));
+    sent.borrow_mut().clear();
+
+    server.handle_request(&Request::Set {
+        path: "ui-state".to_string(),
+        value: protocol::Value::Bytes(vec![1, 2, 3]),
+    });
+    assert_eq!(store.values.borrow().ui_state, vec![1, 2, 3]);
+    assert!(sent.borrow().is_empty());
+
+    server.update_ui_state(&[4, 5]);
+    assert!(sent.borrow().iter().any(|m| {
+        match m {
+            Response::Values { values } => values
+                .iter()
+                .any(|(p, v)| p == "ui-state" && v == &protocol::Value::Bytes(vec![4, 5])),
+            _ => false,
+        }
+    }));
+}
@@ -0,0 +1,90 @@
+//! Helpers for storing structured, UI-only state.
+//!
+//! Some state belongs to the user interface rather than the component, for
+//! example which tab is open or a scroll position. This shouldn't be stored in
+//! parameters, since it shouldn't be automatable or affect the sound. Instead,
+//! it can be stored as an opaque blob of bytes alongside the parameters.
+//!
+//! Plug-in wrappers save this blob along with the rest of the plug-in's state
+//! (see [`crate::ParameterStore::get_ui_state`]), and web UIs can read and write it
+//! as bytes at the `ui-state` path.
+//!
+//! These helpers convert a UI-defined struct to and from such a blob. Each blob
+//! records a version number, so that UIs can change the shape of their state
+//! and still load blobs saved by older versions.
+
+use serde::{de::DeserializeOwned, de::IgnoredAny, Deserialize, Serialize};
+
+use crate::protocol::serialize_as_bytes;
+
+#[derive(Serialize, Deserialize)]
+struct Envelope<T> {
+    version: u32,
+    state: T,
+}
+
+/// An error that occurs when reading UI state.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UiStateError {
+    /// The bytes couldn't be read as UI state of the requested type.
+    ///
+    /// This includes the case where no UI state was ever saved.
+    Corrupted,
+
+    /// The UI state was saved with a different version than the one requested.
+    WrongVersion {
+        /// The version the UI state was saved with.
+        saved_version: u32,
+    },
+}
+
+/// Serializes `state` into a blob of UI state bytes, recording `version`.
+///
+/// Any struct that implements [`Serialize`] can be stored. Fields are stored by
+/// name, so fields can be added to the struct without changing `version`
+/// as long as they implement [`Default`] and are marked `#[serde(default)]`.
+/// You should increase `version` when making other changes.
+#[must_use]
+pub fn serialize_ui_state<T: Serialize>(version: u32, state: &T) -> Vec<u8> {
+    serialize_as_bytes(&Envelope { version, state })
+}
+
+/// Reads the version that a blob of UI state bytes was saved with.
+///
+/// This can be used to decide which type to pass to [`deserialize_ui_state`]
+/// when loading state saved by older versions of the UI.
+///
+/// # Errors
+///
+/// Returns [`UiStateError::Corrupted`] if `bytes` is not a valid UI state blob.
+pub fn ui_state_version(bytes: &[u8]) -> Result<u32, UiStateError> {
+    rmp_serde::from_slice::<Envelope<IgnoredAny>>(bytes)
+        .map(|envelope| envelope.version)
+        .map_err(|_| UiStateError::Corrupted)
+}
+
+/// Deserializes a blob of UI state bytes saved by [`serialize_ui_state`].
+///
+/// `version` must match the version the state was saved with. To load state
+/// saved with an older version, check [`ui_state_version`] first and deserialize
+/// into the type used for that version.
+///
+/// # Errors
+///
+/// - [`UiStateError::WrongVersion`] if the state was saved with a different version.
+/// - [`UiStateError::Corrupted`] if `bytes` can't be read as state of type `T`.
+pub fn deserialize_ui_state<T: DeserializeOwned>(
+    bytes: &[u8],
+    version: u32,
+) -> Result<T, UiStateError> {
+    let saved_version = ui_state_version(bytes)?;
+    if saved_version != version {
+        return Err(UiStateError::WrongVersion { saved_version });
+    }
+    rmp_serde::from_slice::<Envelope<T>>(bytes)
+        .map(|envelope| envelope.state)
+        .map_err(|_| UiStateError::Corrupted)
+}
+
+#[cfg(test)]
+mod tests;
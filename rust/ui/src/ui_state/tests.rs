@@ -0,0 +1,62 @@
+use serde::{Deserialize, Serialize};
+
+use super::{deserialize_ui_state, serialize_ui_state, ui_state_version, UiStateError};
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Default)]
+struct ExampleUiState {
+    open_tab: String,
+    scroll_position: f32,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Default)]
+struct ExampleUiStateV2 {
+    open_tab: String,
+    scroll_position: f32,
+    #[serde(default)]
+    zoom: f32,
+}
+
+fn example_state() -> ExampleUiState {
+    ExampleUiState {
+        open_tab: "envelopes".to_string(),
+        scroll_position: 0.25,
+    }
+}
+
+#[test]
+fn round_trip() {
+    let bytes = serialize_ui_state(1, &example_state());
+    assert_eq!(ui_state_version(&bytes), Ok(1));
+    assert_eq!(deserialize_ui_state(&bytes, 1), Ok(example_state()));
+}
+
+#[test]
+fn wrong_version_rejected() {
+    let bytes = serialize_ui_state(1, &example_state());
+    assert_eq!(
+        deserialize_ui_state::<ExampleUiState>(&bytes, 2),
+        Err(UiStateError::WrongVersion { saved_version: 1 })
+    );
+}
+
+#[test]
+fn added_default_field_loads_older_state() {
+    let bytes = serialize_ui_state(1, &example_state());
+    assert_eq!(
+        deserialize_ui_state(&bytes, 1),
+        Ok(ExampleUiStateV2 {
+            open_tab: "envelopes".to_string(),
+            scroll_position: 0.25,
+            zoom: 0.0,
+        })
+    );
+}
+
+#[test]
+fn empty_bytes_are_corrupted() {
+    assert_eq!(ui_state_version(&[]), Err(UiStateError::Corrupted));
+    assert_eq!(
+        deserialize_ui_state::<ExampleUiState>(&[], 1),
+        Err(UiStateError::Corrupted)
+    );
+}
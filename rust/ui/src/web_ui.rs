@@ -145,6 +145,16 @@ pub struct Size {
     pub height: i32,
 }
 
+fn bounds(size: Size) -> wry::Rect {
+    wry::Rect {
+        position: wry::dpi::Position::Logical(wry::dpi::LogicalPosition { x: 0f64, y: 0f64 }),
+        size: wry::dpi::Size::Logical(wry::dpi::LogicalSize {
+            width: f64::from(size.width),
+            height: f64::from(size.height),
+        }),
+    }
+}
+
 impl<S: super::ParameterStore + 'static> Ui<S> {
     /// # Errors
     ///
@@ -197,18 +207,23 @@ impl<S: super::ParameterStore + 'static> Ui<S> {
         );
 
         server_web_view.replace(Rc::downgrade(&web_view));
-        let _ = web_view.set_bounds(wry::Rect {
-            position: wry::dpi::Position::Logical(wry::dpi::LogicalPosition { x: 0f64, y: 0f64 }),
-            size: wry::dpi::Size::Logical(wry::dpi::LogicalSize {
-                width: f64::from(size.width),
-                height: f64::from(size.height),
-            }),
-        });
+        let _ = web_view.set_bounds(bounds(size));
         Ok(Self { web_view, server })
     }
 
+    /// Resize the UI, for example when the host resizes the window containing it.
+    pub fn set_size(&mut self, size: Size) {
+        let _ = self.web_view.set_bounds(bounds(size));
+    }
+
     /// Any time any parameter changes, this must be called with the new value.
     pub fn update_parameter(&mut self, unique_id: &str, value: &parameters::Value) {
         self.server.borrow_mut().update_parameter(unique_id, value);
     }
+
+    /// Any time the UI state is replaced from outside the UI, this must be called
+    /// with the new state.
+    pub fn update_ui_state(&mut self, state: &[u8]) {
+        self.server.borrow_mut().update_ui_state(state);
+    }
 }
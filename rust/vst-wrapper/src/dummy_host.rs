@@ -21,7 +21,8 @@ impl IHostApplicationTrait for Host {
         _iid: *mut vst3::Steinberg::TUID,
         _obj: *mut *mut std::ffi::c_void,
     ) -> vst3::Steinberg::tresult {
-        unimplemented!()
+        // The wrapper never asks the host to create objects.
+        vst3::Steinberg::kNotImplemented
     }
 }
 
@@ -5,70 +5,58 @@ use std::{
 };
 
 use conformal_component::{
-    parameters::{self, InfoRef, TypeSpecificInfo, TypeSpecificInfoRef},
+    parameters::{self, CurveHint, InfoRef, NumericOptions, TypeSpecificInfo, TypeSpecificInfoRef},
     synth::{
-        AFTERTOUCH_PARAMETER, CONTROLLER_PARAMETERS, EXPRESSION_PARAMETER, MOD_WHEEL_PARAMETER,
-        PITCH_BEND_PARAMETER, SUSTAIN_PARAMETER, TIMBRE_PARAMETER,
+        NoteExpressionKind, NoteExpressionRanges, AFTERTOUCH_PARAMETER, CONTROLLER_PARAMETERS,
+        EXPRESSION_PARAMETER, MOD_WHEEL_PARAMETER, PITCH_BEND_PARAMETER, SOFT_PEDAL_PARAMETER,
+        SOSTENUTO_PARAMETER, SUSTAIN_PARAMETER, TIMBRE_PARAMETER,
     },
+    Preset,
 };
-use conformal_core::parameters::serialization::{DeserializationError, ReadInfoRef};
+use conformal_core::parameters::normalization::{
+    convert_enum, convert_numeric, convert_switch, normalize_enum, normalize_numeric,
+    normalize_switch,
+};
+use conformal_core::parameters::serialization::DeserializationError;
 use conformal_core::parameters::store;
 
 #[cfg(target_os = "macos")]
 use conformal_macos_bundle::get_current_bundle_info;
 
 use conformal_ui::Size;
+use serde::Serialize;
 use vst3::{
     Class, ComPtr, ComRef,
     Steinberg::{
         IPluginBase, IPluginBaseTrait,
         Vst::{
-            IComponentHandler, IComponentHandlerTrait, IConnectionPoint, IConnectionPointTrait,
-            IEditController, IEditControllerTrait, IHostApplication, IMidiMapping,
-            IMidiMappingTrait, INoteExpressionController, INoteExpressionControllerTrait,
-            INoteExpressionPhysicalUIMapping, INoteExpressionPhysicalUIMappingTrait,
-            NoteExpressionTypeID, NoteExpressionTypeInfo, NoteExpressionValue,
+            IComponentHandler, IComponentHandler2, IComponentHandler2Trait, IComponentHandlerTrait,
+            IConnectionPoint, IConnectionPointTrait, IEditController, IEditControllerTrait,
+            IHostApplication, IMidiMapping, IMidiMappingTrait, INoteExpressionController,
+            INoteExpressionControllerTrait, INoteExpressionPhysicalUIMapping,
+            INoteExpressionPhysicalUIMappingTrait, IUnitInfo, IUnitInfoTrait, NoteExpressionTypeID,
+            NoteExpressionTypeInfo, NoteExpressionValue,
         },
     },
 };
 
 use crate::{
     mpe_quirks::{self, aftertouch_param_id, pitch_param_id, timbre_param_id, Support},
-    HostInfo, ParameterModel,
+    programs, HostParameterModel, ParameterModel, SynthOptions, UiSizeConstraints,
 };
 
 use super::{
     from_utf16_ptr, host_info,
-    io::StreamRead,
-    parameters::{
-        convert_enum, convert_numeric, convert_switch, normalize_enum, normalize_numeric,
-        normalize_switch,
-    },
-    processor::state,
-    to_utf16, view,
+    io::{StreamRead, StreamWrite},
+    parameters::{as_deserialization, step_count},
+    processor, to_utf16, view,
 };
 
+mod state;
+
 #[cfg(test)]
 mod tests;
 
-fn as_deserialization(info: &parameters::Info) -> ReadInfoRef<impl Iterator<Item = &str> + Clone> {
-    match &info.type_specific {
-        TypeSpecificInfo::Enum { default, values } => ReadInfoRef::Enum {
-            default: *default,
-            values: values.iter().map(String::as_str),
-        },
-        TypeSpecificInfo::Numeric {
-            default,
-            valid_range,
-            ..
-        } => ReadInfoRef::Numeric {
-            default: *default,
-            valid_range: valid_range.clone(),
-        },
-        TypeSpecificInfo::Switch { default } => ReadInfoRef::Switch { default: *default },
-    }
-}
-
 struct ParameterStore {
     unhash: HashMap<parameters::IdHash, String>,
 
@@ -85,10 +73,16 @@ struct ParameterStore {
     values: HashMap<String, parameters::InternalValue>,
     order: Vec<String>,
 
+    /// Factory presets the host can select with the program change parameter (see [`programs`]).
+    presets: Vec<Preset>,
+
     component_handler: Option<ComPtr<IComponentHandler>>,
 
     // Note that unsized weak types can't dangle, so we use Option here to allow dangling.
     listener: Option<rc::Weak<dyn store::Listener>>,
+
+    /// Opaque state saved by the UI, see [`store::Store::get_ui_state`].
+    ui_state: Vec<u8>,
 }
 
 #[derive(Clone)]
@@ -97,8 +91,11 @@ struct SharedStore {
 }
 
 struct Initialized {
-    host_info: HostInfo,
+    support_mpe_quirks: Support,
     store: SharedStore,
+    note_expression_ranges: NoteExpressionRanges,
+    state_version: u32,
+    upgrade_parameters: Box<dyn Fn(u32, &mut HashMap<String, parameters::Value>)>,
     parameter_model: ParameterModel,
     pref_domain: String,
 }
@@ -116,16 +113,31 @@ enum State {
     Initialized(Initialized),
 }
 
+impl State {
+    /// The note expression ranges of the component, or `None` if we aren't initialized yet.
+    fn note_expression_ranges(&self) -> Option<NoteExpressionRanges> {
+        match self {
+            State::Initialized(Initialized {
+                note_expression_ranges,
+                ..
+            }) => Some(*note_expression_ranges),
+            State::ReadyForInitialization(..) => None,
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum Kind {
-    Synth(),
+    Synth(SynthOptions),
     Effect { bypass_id: &'static str },
+    Analyzer(),
 }
 
 struct EditController {
     s: RefCell<Option<State>>,
     host: RefCell<Option<ComPtr<IHostApplication>>>,
     ui_initial_size: Size,
+    ui_size_constraints: Option<UiSizeConstraints>,
     kind: Kind,
 }
 
@@ -140,6 +152,7 @@ fn create_internal(
         s: Some(State::ReadyForInitialization(parameter_model, pref_domain)).into(),
         host: Default::default(),
         ui_initial_size,
+        ui_size_constraints: None,
         kind,
     }
 }
@@ -147,6 +160,7 @@ fn create_internal(
 pub fn create(
     parameter_model: ParameterModel,
     ui_initial_size: Size,
+    ui_size_constraints: Option<UiSizeConstraints>,
     kind: Kind,
 ) -> impl Class<
     Interfaces = (
@@ -156,23 +170,72 @@ pub fn create(
         IConnectionPoint,
         INoteExpressionController,
         INoteExpressionPhysicalUIMapping,
+        IUnitInfo,
     ),
 > + IEditControllerTrait
        + IMidiMappingTrait
        + IConnectionPointTrait
        + INoteExpressionControllerTrait
        + INoteExpressionPhysicalUIMappingTrait
+       + IUnitInfoTrait
        + 'static {
-    create_internal(
+    create_with_pref_domain(
         parameter_model,
         get_current_bundle_info()
             .expect("Could not find bundle info")
             .identifier,
         ui_initial_size,
+        ui_size_constraints,
         kind,
     )
 }
 
+/// Like [`create`], but stores preferences in `pref_domain` rather than the bundle's domain.
+pub fn create_with_pref_domain(
+    parameter_model: ParameterModel,
+    pref_domain: String,
+    ui_initial_size: Size,
+    ui_size_constraints: Option<UiSizeConstraints>,
+    kind: Kind,
+) -> impl Class<
+    Interfaces = (
+        IPluginBase,
+        IEditController,
+        IMidiMapping,
+        IConnectionPoint,
+        INoteExpressionController,
+        INoteExpressionPhysicalUIMapping,
+        IUnitInfo,
+    ),
+> + IEditControllerTrait
+       + IMidiMappingTrait
+       + IConnectionPointTrait
+       + INoteExpressionControllerTrait
+       + INoteExpressionPhysicalUIMappingTrait
+       + IUnitInfoTrait
+       + 'static {
+    EditController {
+        ui_size_constraints,
+        ..create_internal(parameter_model, pref_domain, ui_initial_size, kind)
+    }
+}
+
+/// The number of decimal places shown for numeric parameters that don't set `display_precision`.
+const DEFAULT_DISPLAY_PRECISION: u8 = 2;
+
+/// The VST3 tuning note expression maps the normalized range to +/- 120 semitones.
+const TUNING_SEMITONES_PER_NORMALIZED: f64 = 240.0;
+
+fn tuning_to_semitones(value_normalized: f64, ranges: &NoteExpressionRanges) -> f64 {
+    let range = f64::from(ranges.pitch_bend);
+    ((value_normalized - 0.5) * TUNING_SEMITONES_PER_NORMALIZED).clamp(-range, range)
+}
+
+fn semitones_to_tuning(semitones: f64, ranges: &NoteExpressionRanges) -> f64 {
+    let range = f64::from(ranges.pitch_bend);
+    semitones.clamp(-range, range) / TUNING_SEMITONES_PER_NORMALIZED + 0.5
+}
+
 fn get_default(info: &TypeSpecificInfo) -> parameters::InternalValue {
     match info {
         TypeSpecificInfo::Enum { default, .. } => parameters::InternalValue::Enum(*default),
@@ -252,8 +315,11 @@ impl store::Store for SharedStore {
     fn set(&mut self, unique_id: &str, value: parameters::Value) -> Result<(), store::SetError> {
         let maybe_set = if let ParameterStore {
             component_handler: Some(component_handler),
+            component_parameter_infos,
             host_parameter_infos,
             values,
+            presets,
+            listener,
             ..
         } = &mut (*self.store.borrow_mut())
         {
@@ -261,12 +327,17 @@ impl store::Store for SharedStore {
                 (
                     parameters::Value::Numeric(value),
                     Some(parameters::Info {
-                        type_specific: TypeSpecificInfo::Numeric { valid_range, .. },
+                        type_specific:
+                            TypeSpecificInfo::Numeric {
+                                valid_range,
+                                options: NumericOptions { scaling, .. },
+                                ..
+                            },
                         ..
                     }),
                 ) => {
                     if valid_range.contains(value) {
-                        Ok(normalize_numeric(*value, valid_range))
+                        Ok(normalize_numeric(*value, valid_range, *scaling))
                     } else {
                         Err(store::SetError::InvalidValue)
                     }
@@ -295,19 +366,33 @@ impl store::Store for SharedStore {
                 (_, None) => Err(store::SetError::NotFound),
             })
             .map(|v| {
-                values.insert(
-                    unique_id.to_string(),
-                    to_internal(unique_id, &value, host_parameter_infos),
+                let internal_value = to_internal(unique_id, &value, host_parameter_infos);
+                values.insert(unique_id.to_string(), internal_value);
+                let changed = select_program(
+                    unique_id,
+                    internal_value,
+                    presets,
+                    component_parameter_infos,
+                    values,
+                    listener.as_ref(),
                 );
-                (component_handler.clone(), parameters::hash_id(unique_id), v)
+                (
+                    component_handler.clone(),
+                    parameters::hash_id(unique_id),
+                    v,
+                    changed,
+                )
             })
         } else {
             Err(store::SetError::InternalError)
         };
-        maybe_set.map(|(component_handler, hash, v)| {
-            unsafe {
-                component_handler.performEdit(hash.internal_hash(), v);
-            };
+        maybe_set.map(|(component_handler, hash, v, changed)| unsafe {
+            component_handler.performEdit(hash.internal_hash(), v);
+            if changed {
+                component_handler.restartComponent(
+                    vst3::Steinberg::Vst::RestartFlags_::kParamValuesChanged as i32,
+                );
+            }
         })
     }
 
@@ -350,6 +435,28 @@ impl store::Store for SharedStore {
             .get(unique_id)
             .cloned()
     }
+
+    fn get_ui_state(&self) -> Vec<u8> {
+        self.store.borrow().ui_state.clone()
+    }
+
+    fn set_ui_state(&mut self, state: &[u8]) {
+        let component_handler = {
+            let mut store = self.store.borrow_mut();
+            state.clone_into(&mut store.ui_state);
+            store.component_handler.clone()
+        };
+        // The UI state is saved with the project, so let the host know it needs saving.
+        // Note that we must do this only after we're done borrowing the store, since
+        // the host may call back into us.
+        if let Some(handler) =
+            component_handler.and_then(|handler| handler.cast::<IComponentHandler2>())
+        {
+            unsafe {
+                handler.setDirty(1);
+            }
+        }
+    }
 }
 
 /// For testing only.
@@ -390,16 +497,32 @@ impl IPluginBaseTrait for EditController {
             host_info::get(&self.host.borrow().clone().unwrap()),
         ) {
             (State::ReadyForInitialization(parameter_model, pref_domain), Some(host_info)) => {
-                let parameter_infos = {
-                    let mut infos = (parameter_model.parameter_infos)(&host_info);
-                    if Kind::Synth() == self.kind {
-                        infos.extend(CONTROLLER_PARAMETERS.iter().map(parameters::Info::from));
-                        if mpe_quirks::should_support(&host_info) == Support::SupportQuirks {
-                            infos.extend(mpe_quirks::parameters());
-                        }
+                let HostParameterModel {
+                    mut parameter_infos,
+                    note_expression_ranges,
+                    state_version,
+                    factory_presets,
+                    upgrade_parameters,
+                } = (parameter_model.for_host)(&host_info);
+                let presets = programs::exposed_presets(factory_presets);
+                let support_mpe_quirks = mpe_quirks::should_support(
+                    &host_info,
+                    matches!(
+                        self.kind,
+                        Kind::Synth(SynthOptions {
+                            uses_midi_channels: true,
+                            ..
+                        })
+                    ),
+                );
+                if let Kind::Synth(_) = self.kind {
+                    parameter_infos
+                        .extend(CONTROLLER_PARAMETERS.iter().map(parameters::Info::from));
+                    if support_mpe_quirks == Support::SupportQuirks {
+                        parameter_infos.extend(mpe_quirks::parameters());
                     }
-                    infos
-                };
+                }
+                parameter_infos.extend(programs::parameter(&presets));
                 let parameters: HashMap<String, parameters::Info> = parameter_infos
                     .iter()
                     .map(|info| {
@@ -434,7 +557,10 @@ impl IPluginBaseTrait for EditController {
                     .map(|(id, info)| (id.clone(), info.clone()))
                     .collect();
                 let s = State::Initialized(Initialized {
-                    host_info,
+                    support_mpe_quirks,
+                    note_expression_ranges,
+                    state_version,
+                    upgrade_parameters,
                     store: SharedStore {store: rc::Rc::new(RefCell::new(ParameterStore {
                         unhash: hash_parameter_ids(parameter_infos.iter().map(Into::into)).expect("Duplicate parameter ID hash! This could be caused by duplicate parameter IDs or a hash collision."),
                         host_parameter_infos: parameters,
@@ -451,8 +577,10 @@ impl IPluginBaseTrait for EditController {
                         .iter()
                         .map(|info| info.unique_id.clone())
                         .collect(),
+                        presets,
                         component_handler: Default::default(),
                         listener: Default::default(),
+                        ui_state: Default::default(),
                     }))},
                     parameter_model,
                     pref_domain,
@@ -501,38 +629,106 @@ fn hash_parameter_ids<'a, S: AsRef<str> + 'a, I: IntoIterator<Item = InfoRef<'a,
 }
 
 /// Note this assumes that `new_values` contains every key in `parameter_values`.
+///
+/// Returns whether any of the values changed.
 fn apply_values<'a>(
     new_values: impl IntoIterator<Item = (&'a str, parameters::InternalValue)>,
     parameter_values: &mut HashMap<String, parameters::InternalValue>,
-) {
+) -> bool {
+    let mut changed = false;
     for (id, value) in new_values {
-        parameter_values.insert(id.to_string(), value);
+        changed |= parameter_values.insert(id.to_string(), value) != Some(value);
     }
+    changed
 }
 
-impl IEditControllerTrait for EditController {
-    unsafe fn setComponentState(
+/// If `id` is the program change parameter, sets every component parameter to the
+/// values of the newly selected factory preset and notifies the listener.
+///
+/// Parameters that aren't in the preset are reset to their defaults, as are
+/// parameters whose values in the preset are invalid.
+///
+/// Returns whether any of the values changed.
+fn select_program(
+    id: &str,
+    value: parameters::InternalValue,
+    presets: &[Preset],
+    component_parameter_infos: &HashMap<String, parameters::Info>,
+    parameter_values: &mut HashMap<String, parameters::InternalValue>,
+    listener: Option<&rc::Weak<dyn store::Listener>>,
+) -> bool {
+    let preset = match value {
+        parameters::InternalValue::Enum(index) if id == programs::PARAMETER_ID => {
+            presets.get(index as usize)
+        }
+        _ => None,
+    };
+    let Some(preset) = preset else {
+        return false;
+    };
+    let changed = apply_values(
+        component_parameter_infos.iter().map(|(id, info)| {
+            (
+                id.as_str(),
+                preset
+                    .values
+                    .get(id)
+                    .filter(|value| parameters::clamp_to_info(value, info) == **value)
+                    .map_or_else(
+                        || get_default(&info.type_specific),
+                        |value| to_internal(id, value, component_parameter_infos),
+                    ),
+            )
+        }),
+        parameter_values,
+    );
+    if let Some(listener) = listener.and_then(rc::Weak::upgrade) {
+        for (id, value) in parameter_values.iter() {
+            if component_parameter_infos.contains_key(id) {
+                listener
+                    .parameter_changed(id, &from_internal(id, *value, component_parameter_infos));
+            }
+        }
+    }
+    changed
+}
+
+impl EditController {
+    /// Applies a component state saved by the processor.
+    ///
+    /// If this changed any parameter values, also returns the component handler that
+    /// should be notified of the change.
+    unsafe fn apply_component_state(
         &self,
         stream: *mut vst3::Steinberg::IBStream,
-    ) -> vst3::Steinberg::tresult {
-        if let State::Initialized(Initialized { store, .. }) = self.s.borrow_mut().as_mut().unwrap()
+    ) -> (vst3::Steinberg::tresult, Option<ComPtr<IComponentHandler>>) {
+        if let State::Initialized(Initialized {
+            store,
+            state_version,
+            upgrade_parameters,
+            ..
+        }) = self.s.borrow_mut().as_mut().unwrap()
         {
             let ParameterStore {
                 component_parameter_infos: ref infos,
                 ref mut values,
                 ref listener,
+                ref component_handler,
                 ..
             } = &mut *store.store.borrow_mut();
             if let Some(com_stream) = ComRef::from_raw(stream) {
                 let read = StreamRead::new(com_stream);
-                if let Ok(state) = rmp_serde::from_read::<_, state::State>(read) {
-                    return match state.params.into_snapshot(
+                if let Ok(state) = rmp_serde::from_read::<_, processor::state::State>(read) {
+                    return match state.params.into_snapshot_with_version(
+                        state.version,
+                        *state_version,
                         infos
                             .iter()
                             .map(|(id, info)| (id.as_str(), as_deserialization(info))),
+                        upgrade_parameters,
                     ) {
                         Ok(snapshot) => {
-                            apply_values(
+                            let changed = apply_values(
                                 snapshot
                                     .values
                                     .iter()
@@ -547,36 +743,172 @@ impl IEditControllerTrait for EditController {
                                     }
                                 }
                             }
-                            vst3::Steinberg::kResultOk
+                            (
+                                vst3::Steinberg::kResultOk,
+                                component_handler.clone().filter(|_| changed),
+                            )
                         }
                         Err(DeserializationError::Corrupted(_)) => {
-                            vst3::Steinberg::kInvalidArgument
+                            (vst3::Steinberg::kInvalidArgument, None)
                         }
                         Err(DeserializationError::VersionTooNew()) => {
-                            apply_values(
+                            let changed = apply_values(
                                 infos.iter().map(|(id, info)| {
                                     (id.as_str(), get_default(&info.type_specific))
                                 }),
                                 values,
                             );
 
-                            vst3::Steinberg::kResultOk
+                            (
+                                vst3::Steinberg::kResultOk,
+                                component_handler.clone().filter(|_| changed),
+                            )
                         }
                     };
                 }
             }
         }
-        vst3::Steinberg::kInvalidArgument
+        (vst3::Steinberg::kInvalidArgument, None)
     }
 
-    unsafe fn setState(&self, _state: *mut vst3::Steinberg::IBStream) -> vst3::Steinberg::tresult {
-        // Note that we don't support any state on the edit controller side at the moment
-        vst3::Steinberg::kResultOk
+    /// Sets a parameter from the host.
+    ///
+    /// If this selected a program that changed other parameter values, also returns the
+    /// component handler that should be notified of the change.
+    unsafe fn set_param_normalized(
+        &self,
+        id: parameters::IdHash,
+        value: vst3::Steinberg::Vst::ParamValue,
+    ) -> (vst3::Steinberg::tresult, Option<ComPtr<IComponentHandler>>) {
+        if let State::Initialized(Initialized { store, .. }) = self.s.borrow_mut().as_mut().unwrap()
+        {
+            let ParameterStore {
+                unhash,
+                host_parameter_infos: infos,
+                component_parameter_infos,
+                values,
+                presets,
+                listener,
+                component_handler,
+                ..
+            } = &mut *store.store.borrow_mut();
+            if let Some(id) = unhash.get(&id) {
+                if let Some(value) = match infos.get(id) {
+                    Some(parameters::Info {
+                        type_specific:
+                            TypeSpecificInfo::Numeric {
+                                valid_range,
+                                options: NumericOptions { scaling, .. },
+                                ..
+                            },
+                        ..
+                    }) => Some(parameters::InternalValue::Numeric(convert_numeric(
+                        value,
+                        valid_range,
+                        *scaling,
+                    ))),
+                    Some(parameters::Info {
+                        type_specific: TypeSpecificInfo::Enum { values, .. },
+                        ..
+                    }) => Some(parameters::InternalValue::Enum(convert_enum(
+                        value,
+                        values.len().try_into().unwrap(),
+                    ))),
+                    Some(parameters::Info {
+                        type_specific: TypeSpecificInfo::Switch { .. },
+                        ..
+                    }) => Some(parameters::InternalValue::Switch(convert_switch(value))),
+                    _ => None,
+                } {
+                    values.insert(id.to_string(), value);
+                    if let Some(listener) = listener {
+                        if let Some(listener) = listener.upgrade() {
+                            (*listener).parameter_changed(id, &from_internal(id, value, infos));
+                        }
+                    }
+                    let changed = select_program(
+                        id,
+                        value,
+                        presets,
+                        component_parameter_infos,
+                        values,
+                        listener.as_ref(),
+                    );
+                    return (
+                        vst3::Steinberg::kResultOk,
+                        component_handler.clone().filter(|_| changed),
+                    );
+                }
+            }
+        }
+        (vst3::Steinberg::kInvalidArgument, None)
     }
 
-    unsafe fn getState(&self, _state: *mut vst3::Steinberg::IBStream) -> vst3::Steinberg::tresult {
-        // Note that we don't support any state on the edit controller side at the moment
-        vst3::Steinberg::kResultOk
+    /// Calls `f` with our factory presets, or returns `None` if we're not initialized.
+    fn with_presets<T>(&self, f: impl FnOnce(&[Preset]) -> T) -> Option<T> {
+        if let State::Initialized(Initialized { store, .. }) = self.s.borrow().as_ref().unwrap() {
+            Some(f(&store.store.borrow().presets))
+        } else {
+            None
+        }
+    }
+}
+
+impl IEditControllerTrait for EditController {
+    unsafe fn setComponentState(
+        &self,
+        stream: *mut vst3::Steinberg::IBStream,
+    ) -> vst3::Steinberg::tresult {
+        let (result, changed_handler) = self.apply_component_state(stream);
+
+        // Ask the host to refresh any parameter displays it draws itself. Note that we must
+        // do this only after we're done borrowing our state, since the host may call
+        // back into us to read the new values.
+        if let Some(handler) = changed_handler {
+            handler
+                .restartComponent(vst3::Steinberg::Vst::RestartFlags_::kParamValuesChanged as i32);
+        }
+        result
+    }
+
+    unsafe fn setState(&self, state: *mut vst3::Steinberg::IBStream) -> vst3::Steinberg::tresult {
+        if let State::Initialized(Initialized { store, .. }) = self.s.borrow().as_ref().unwrap() {
+            if let Some(com_state) = ComRef::from_raw(state) {
+                let read = StreamRead::new(com_state);
+                if let Ok(state) = rmp_serde::from_read::<_, state::State>(read) {
+                    let listener = {
+                        let mut store = store.store.borrow_mut();
+                        store.ui_state.clone_from(&state.ui_state);
+                        store.listener.clone()
+                    };
+                    // Note that we must notify the listener only after we're done borrowing
+                    // the store, since it may read the store.
+                    if let Some(listener) = listener.as_ref().and_then(rc::Weak::upgrade) {
+                        listener.ui_state_changed(&state.ui_state);
+                    }
+                    return vst3::Steinberg::kResultOk;
+                }
+            }
+        }
+        vst3::Steinberg::kInvalidArgument
+    }
+
+    unsafe fn getState(&self, state: *mut vst3::Steinberg::IBStream) -> vst3::Steinberg::tresult {
+        if let State::Initialized(Initialized { store, .. }) = self.s.borrow().as_ref().unwrap() {
+            if let Some(com_state) = ComRef::from_raw(state) {
+                let writer = StreamWrite::new(com_state);
+                if (state::State {
+                    ui_state: store.store.borrow().ui_state.clone(),
+                })
+                .serialize(&mut rmp_serde::Serializer::new(writer))
+                .is_ok()
+                {
+                    return vst3::Steinberg::kResultOk;
+                }
+                return vst3::Steinberg::kInternalError;
+            }
+        }
+        vst3::Steinberg::kInvalidArgument
     }
 
     unsafe fn getParameterCount(&self) -> vst3::Steinberg::int32 {
@@ -626,21 +958,27 @@ impl IEditControllerTrait for EditController {
                 }
             } else {
                 0
+            } | if param_id == programs::PARAMETER_ID {
+                vst3::Steinberg::Vst::ParameterInfo_::ParameterFlags_::kIsProgramChange as i32
+            } else {
+                0
             };
+            info_out.stepCount = step_count(&info.type_specific, info.flags.curve_hint);
 
             match &info.type_specific {
                 TypeSpecificInfo::Enum {
                     default,
                     ref values,
                 } => {
-                    info_out.flags |=
-                        vst3::Steinberg::Vst::ParameterInfo_::ParameterFlags_::kIsList as i32;
+                    if info.flags.curve_hint == CurveHint::Auto {
+                        info_out.flags |=
+                            vst3::Steinberg::Vst::ParameterInfo_::ParameterFlags_::kIsList as i32;
+                    }
 
                     assert!(
                         values.len() >= 2,
                         "Enum parameters must contain at least 2 values."
                     );
-                    info_out.stepCount = i32::try_from(values.len()).unwrap() - 1;
                     info_out.defaultNormalizedValue =
                         // Note we checked that the number of values fit in an i32 on construction.
                         f64::from(*default) / f64::from(i32::try_from(values.len()).unwrap() - 1);
@@ -650,19 +988,17 @@ impl IEditControllerTrait for EditController {
                     default,
                     valid_range,
                     ref units,
+                    options: NumericOptions { scaling, .. },
                     ..
                 } => {
-                    info_out.stepCount = 0;
-                    info_out.defaultNormalizedValue = f64::from(
-                        (default - valid_range.start()) / (valid_range.end() - valid_range.start()),
-                    );
+                    info_out.defaultNormalizedValue =
+                        normalize_numeric(*default, valid_range, *scaling);
                     to_utf16(
                         units.as_ref().map_or("", |x| x.as_str()),
                         &mut info_out.units,
                     );
                 }
                 TypeSpecificInfo::Switch { default } => {
-                    info_out.stepCount = 1;
                     info_out.defaultNormalizedValue = if *default { 1.0 } else { 0.0 };
                     info_out.units[0] = 0;
                 }
@@ -688,13 +1024,23 @@ impl IEditControllerTrait for EditController {
             } = &*store.store.borrow();
             match lookup_by_hash(parameters::id_hash_from_internal_hash(id), unhash, infos) {
                 Some(parameters::Info {
-                    type_specific: TypeSpecificInfo::Numeric { valid_range, .. },
+                    type_specific:
+                        TypeSpecificInfo::Numeric {
+                            valid_range,
+                            options:
+                                NumericOptions {
+                                    display_precision,
+                                    scaling,
+                                    ..
+                                },
+                            ..
+                        },
                     ..
                 }) => {
-                    let value = value_normalized
-                        * f64::from(valid_range.end() - valid_range.start())
-                        + f64::from(*valid_range.start());
-                    let serialized = format!("{value:.2}");
+                    let value = convert_numeric(value_normalized, valid_range, *scaling);
+                    let precision =
+                        usize::from(display_precision.unwrap_or(DEFAULT_DISPLAY_PRECISION));
+                    let serialized = format!("{value:.precision$}");
                     to_utf16(serialized.as_str(), &mut *string);
                     vst3::Steinberg::kResultOk
                 }
@@ -744,13 +1090,17 @@ impl IEditControllerTrait for EditController {
             if let Some(string) = from_utf16_ptr(string, MAX_STRING_SIZE) {
                 match lookup_by_hash(parameters::id_hash_from_internal_hash(id), unhash, infos) {
                     Some(parameters::Info {
-                        type_specific: TypeSpecificInfo::Numeric { valid_range, .. },
+                        type_specific:
+                            TypeSpecificInfo::Numeric {
+                                valid_range,
+                                options: NumericOptions { scaling, .. },
+                                ..
+                            },
                         ..
                     }) => {
-                        let value = string.parse::<f64>();
+                        let value = string.parse::<f32>();
                         if let Ok(value) = value {
-                            *value_normalized = (value - f64::from(*valid_range.start()))
-                                / f64::from(valid_range.end() - valid_range.start());
+                            *value_normalized = normalize_numeric(value, valid_range, *scaling);
                             vst3::Steinberg::kResultOk
                         } else {
                             vst3::Steinberg::kInvalidArgument
@@ -830,11 +1180,16 @@ impl IEditControllerTrait for EditController {
             ) {
                 (
                     Some(parameters::Info {
-                        type_specific: TypeSpecificInfo::Numeric { valid_range, .. },
+                        type_specific:
+                            TypeSpecificInfo::Numeric {
+                                valid_range,
+                                options: NumericOptions { scaling, .. },
+                                ..
+                            },
                         ..
                     }),
                     Some(parameters::InternalValue::Numeric(value)),
-                ) => normalize_numeric(*value, valid_range),
+                ) => normalize_numeric(*value, valid_range, *scaling),
                 (
                     Some(parameters::Info {
                         type_specific: TypeSpecificInfo::Enum { values, .. },
@@ -872,48 +1227,16 @@ impl IEditControllerTrait for EditController {
         if !(0.0..=1.0).contains(&value) {
             return vst3::Steinberg::kInvalidArgument;
         }
-        if let State::Initialized(Initialized { store, .. }) = self.s.borrow_mut().as_mut().unwrap()
-        {
-            let ParameterStore {
-                unhash,
-                host_parameter_infos: infos,
-                values,
-                listener,
-                ..
-            } = &mut *store.store.borrow_mut();
-            if let Some(id) = unhash.get(&id) {
-                if let Some(value) = match infos.get(id) {
-                    Some(parameters::Info {
-                        type_specific: TypeSpecificInfo::Numeric { valid_range, .. },
-                        ..
-                    }) => Some(parameters::InternalValue::Numeric(convert_numeric(
-                        value,
-                        valid_range,
-                    ))),
-                    Some(parameters::Info {
-                        type_specific: TypeSpecificInfo::Enum { values, .. },
-                        ..
-                    }) => Some(parameters::InternalValue::Enum(convert_enum(
-                        value,
-                        values.len().try_into().unwrap(),
-                    ))),
-                    Some(parameters::Info {
-                        type_specific: TypeSpecificInfo::Switch { .. },
-                        ..
-                    }) => Some(parameters::InternalValue::Switch(convert_switch(value))),
-                    _ => None,
-                } {
-                    values.insert(id.to_string(), value);
-                    if let Some(listener) = listener {
-                        if let Some(listener) = listener.upgrade() {
-                            (*listener).parameter_changed(id, &from_internal(id, value, infos));
-                        }
-                    }
-                    return vst3::Steinberg::kResultOk;
-                }
-            }
+        let (result, changed_handler) = self.set_param_normalized(id, value);
+
+        // Ask the host to refresh its parameter displays if selecting a program changed
+        // other parameters. As in `setComponentState`, we must do this only after we're
+        // done borrowing our state.
+        if let Some(handler) = changed_handler {
+            handler
+                .restartComponent(vst3::Steinberg::Vst::RestartFlags_::kParamValuesChanged as i32);
         }
-        vst3::Steinberg::kInvalidArgument
+        result
     }
 
     unsafe fn setComponentHandler(
@@ -944,6 +1267,7 @@ impl IEditControllerTrait for EditController {
                         .identifier
                         .clone(),
                     self.ui_initial_size,
+                    self.ui_size_constraints,
                 )
                 .into_raw();
             }
@@ -960,17 +1284,19 @@ impl IMidiMappingTrait for EditController {
         midi_controller_number: vst3::Steinberg::Vst::CtrlNumber,
         id: *mut vst3::Steinberg::Vst::ParamID,
     ) -> vst3::Steinberg::tresult {
-        if let State::Initialized(Initialized { host_info, .. }) = self.s.borrow().as_ref().unwrap()
+        if let State::Initialized(Initialized {
+            support_mpe_quirks, ..
+        }) = self.s.borrow().as_ref().unwrap()
         {
-            // Effects don't have midi mappings
-            if let Kind::Effect { .. } = self.kind {
+            // Effects and analyzers don't have midi mappings
+            if let Kind::Effect { .. } | Kind::Analyzer() = self.kind {
                 return vst3::Steinberg::kResultFalse;
             }
             if bus_index != 0 {
                 return vst3::Steinberg::kResultFalse;
             }
             if channel_index != 0 {
-                if mpe_quirks::should_support(host_info) == Support::SupportQuirks {
+                if *support_mpe_quirks == Support::SupportQuirks {
                     (match midi_controller_number.try_into() {
                         Ok(vst3::Steinberg::Vst::ControllerNumbers_::kPitchBend) => {
                             Some(pitch_param_id(channel_index))
@@ -1004,6 +1330,12 @@ impl IMidiMappingTrait for EditController {
                     Ok(vst3::Steinberg::Vst::ControllerNumbers_::kCtrlSustainOnOff) => {
                         Some(SUSTAIN_PARAMETER)
                     }
+                    Ok(vst3::Steinberg::Vst::ControllerNumbers_::kCtrlSustenutoOnOff) => {
+                        Some(SOSTENUTO_PARAMETER)
+                    }
+                    Ok(vst3::Steinberg::Vst::ControllerNumbers_::kCtrlSoftPedalOnOff) => {
+                        Some(SOFT_PEDAL_PARAMETER)
+                    }
                     Ok(vst3::Steinberg::Vst::ControllerNumbers_::kAfterTouch) => {
                         Some(AFTERTOUCH_PARAMETER)
                     }
@@ -1060,9 +1392,11 @@ impl INoteExpressionControllerTrait for EditController {
         note_expression_index: i32,
         info_out: *mut NoteExpressionTypeInfo,
     ) -> vst3::Steinberg::tresult {
-        if !matches!(self.s.borrow().as_ref().unwrap(), State::Initialized(_)) {
+        let Some(note_expression_ranges) =
+            self.s.borrow().as_ref().unwrap().note_expression_ranges()
+        else {
             return vst3::Steinberg::kInvalidArgument;
-        }
+        };
         if bus_index != 0 {
             return vst3::Steinberg::kInvalidArgument;
         }
@@ -1081,8 +1415,14 @@ impl INoteExpressionControllerTrait for EditController {
                 // It's not clear from docs if this is necessary for a pre-defined tuning type.
                 info_out.valueDesc = vst3::Steinberg::Vst::NoteExpressionValueDescription {
                     defaultValue: 0.5,
-                    minimum: 0.0,
-                    maximum: 1.0,
+                    minimum: semitones_to_tuning(
+                        -f64::from(note_expression_ranges.pitch_bend),
+                        &note_expression_ranges,
+                    ),
+                    maximum: semitones_to_tuning(
+                        f64::from(note_expression_ranges.pitch_bend),
+                        &note_expression_ranges,
+                    ),
                     stepCount: 0, // Continuous
                 };
                 info_out.flags = vst3::Steinberg::Vst::NoteExpressionTypeInfo_::NoteExpressionTypeFlags_::kIsBipolar as i32;
@@ -1132,9 +1472,11 @@ impl INoteExpressionControllerTrait for EditController {
         value_normalized: NoteExpressionValue,
         string: *mut vst3::Steinberg::Vst::String128,
     ) -> vst3::Steinberg::tresult {
-        if !matches!(self.s.borrow().as_ref().unwrap(), State::Initialized(_)) {
+        let Some(note_expression_ranges) =
+            self.s.borrow().as_ref().unwrap().note_expression_ranges()
+        else {
             return vst3::Steinberg::kInvalidArgument;
-        }
+        };
         if bus_index != 0 {
             return vst3::Steinberg::kInvalidArgument;
         }
@@ -1143,7 +1485,7 @@ impl INoteExpressionControllerTrait for EditController {
         }
         match id {
             vst3::Steinberg::Vst::NoteExpressionTypeIDs_::kTuningTypeID => {
-                let value = (value_normalized - 0.5) * 240.0;
+                let value = tuning_to_semitones(value_normalized, &note_expression_ranges);
                 to_utf16(&format!("{value:.2}"), &mut *string);
                 vst3::Steinberg::kResultOk
             }
@@ -1168,9 +1510,11 @@ impl INoteExpressionControllerTrait for EditController {
         // so we make a reasonable size up.
         const MAX_STRING_SIZE: usize = 2049;
 
-        if !matches!(self.s.borrow().as_ref().unwrap(), State::Initialized(_)) {
+        let Some(note_expression_ranges) =
+            self.s.borrow().as_ref().unwrap().note_expression_ranges()
+        else {
             return vst3::Steinberg::kInvalidArgument;
-        }
+        };
         if bus_index != 0 {
             return vst3::Steinberg::kInvalidArgument;
         }
@@ -1182,7 +1526,7 @@ impl INoteExpressionControllerTrait for EditController {
             let value = string.parse::<f64>().ok()?;
             match id {
                 vst3::Steinberg::Vst::NoteExpressionTypeIDs_::kTuningTypeID => {
-                    Some((value / 240.0) + 0.5)
+                    Some(semitones_to_tuning(value, &note_expression_ranges))
                 }
                 crate::processor::NOTE_EXPRESSION_AFTERTOUCH_TYPE_ID
                 | crate::processor::NOTE_EXPRESSION_TIMBRE_TYPE_ID => Some(value),
@@ -1204,9 +1548,12 @@ impl INoteExpressionPhysicalUIMappingTrait for EditController {
         channel: i16,
         list: *mut vst3::Steinberg::Vst::PhysicalUIMapList,
     ) -> vst3::Steinberg::tresult {
-        if !matches!(self.s.borrow().as_ref().unwrap(), State::Initialized(_)) {
+        let Some(note_expression_ranges) =
+            self.s.borrow().as_ref().unwrap().note_expression_ranges()
+        else {
             return vst3::Steinberg::kInvalidArgument;
-        }
+        };
+        let mapping = note_expression_ranges.physical_ui_mapping;
         if bus_index != 0 {
             return vst3::Steinberg::kInvalidArgument;
         }
@@ -1217,23 +1564,172 @@ impl INoteExpressionPhysicalUIMappingTrait for EditController {
         let list = &mut *list;
         for idx in 0..list.count {
             let item = &mut (*list.map.offset(idx as isize));
-            match item.physicalUITypeID {
-                vst3::Steinberg::Vst::PhysicalUITypeIDs_::kPUIXMovement => {
-                    item.noteExpressionTypeID =
-                        vst3::Steinberg::Vst::NoteExpressionTypeIDs_::kTuningTypeID;
+            let kind = match item.physicalUITypeID {
+                vst3::Steinberg::Vst::PhysicalUITypeIDs_::kPUIXMovement => mapping.x,
+                vst3::Steinberg::Vst::PhysicalUITypeIDs_::kPUIYMovement => mapping.y,
+                vst3::Steinberg::Vst::PhysicalUITypeIDs_::kPUIPressure => mapping.pressure,
+                _ => continue,
+            };
+            item.noteExpressionTypeID = match kind {
+                Some(NoteExpressionKind::PitchBend) => {
+                    vst3::Steinberg::Vst::NoteExpressionTypeIDs_::kTuningTypeID
                 }
-                vst3::Steinberg::Vst::PhysicalUITypeIDs_::kPUIYMovement => {
-                    item.noteExpressionTypeID = crate::processor::NOTE_EXPRESSION_TIMBRE_TYPE_ID;
+                Some(NoteExpressionKind::Timbre) => {
+                    crate::processor::NOTE_EXPRESSION_TIMBRE_TYPE_ID
                 }
-                vst3::Steinberg::Vst::PhysicalUITypeIDs_::kPUIPressure => {
-                    item.noteExpressionTypeID =
-                        crate::processor::NOTE_EXPRESSION_AFTERTOUCH_TYPE_ID;
+                Some(NoteExpressionKind::Aftertouch) => {
+                    crate::processor::NOTE_EXPRESSION_AFTERTOUCH_TYPE_ID
                 }
-                _ => {}
+                None => vst3::Steinberg::Vst::NoteExpressionTypeIDs_::kInvalidTypeID,
+            };
+        }
+        vst3::Steinberg::kResultOk
+    }
+}
+
+// Note that we only use `IUnitInfo` to list our factory presets, so we
+// always have just a single root unit containing all parameters.
+impl IUnitInfoTrait for EditController {
+    unsafe fn getUnitCount(&self) -> vst3::Steinberg::int32 {
+        1
+    }
+
+    unsafe fn getUnitInfo(
+        &self,
+        unit_index: vst3::Steinberg::int32,
+        info: *mut vst3::Steinberg::Vst::UnitInfo,
+    ) -> vst3::Steinberg::tresult {
+        if unit_index != 0 || info.is_null() {
+            return vst3::Steinberg::kInvalidArgument;
+        }
+        let Some(has_programs) = self.with_presets(|presets| !presets.is_empty()) else {
+            return vst3::Steinberg::kInvalidArgument;
+        };
+        let info = &mut *info;
+        info.id = vst3::Steinberg::Vst::kRootUnitId;
+        info.parentUnitId = vst3::Steinberg::Vst::kNoParentUnitId;
+        to_utf16("Root", &mut info.name);
+        info.programListId = if has_programs {
+            programs::PROGRAM_LIST_ID
+        } else {
+            vst3::Steinberg::Vst::kNoProgramListId
+        };
+        vst3::Steinberg::kResultOk
+    }
+
+    unsafe fn getProgramListCount(&self) -> vst3::Steinberg::int32 {
+        self.with_presets(|presets| i32::from(!presets.is_empty()))
+            .unwrap_or(0)
+    }
+
+    unsafe fn getProgramListInfo(
+        &self,
+        list_index: vst3::Steinberg::int32,
+        info: *mut vst3::Steinberg::Vst::ProgramListInfo,
+    ) -> vst3::Steinberg::tresult {
+        if list_index != 0 || info.is_null() {
+            return vst3::Steinberg::kInvalidArgument;
+        }
+        match self.with_presets(<[Preset]>::len) {
+            Some(count) if count > 0 => {
+                let info = &mut *info;
+                info.id = programs::PROGRAM_LIST_ID;
+                to_utf16("Factory Presets", &mut info.name);
+                info.programCount = i32::try_from(count).unwrap();
+                vst3::Steinberg::kResultOk
             }
+            _ => vst3::Steinberg::kInvalidArgument,
+        }
+    }
+
+    unsafe fn getProgramName(
+        &self,
+        list_id: vst3::Steinberg::Vst::ProgramListID,
+        program_index: vst3::Steinberg::int32,
+        name: *mut vst3::Steinberg::Vst::String128,
+    ) -> vst3::Steinberg::tresult {
+        if list_id != programs::PROGRAM_LIST_ID || name.is_null() {
+            return vst3::Steinberg::kInvalidArgument;
+        }
+        let Ok(program_index) = usize::try_from(program_index) else {
+            return vst3::Steinberg::kInvalidArgument;
+        };
+        let named = self.with_presets(|presets| {
+            presets
+                .get(program_index)
+                .map(|preset| to_utf16(&preset.name, &mut *name))
+                .is_some()
+        });
+        if named == Some(true) {
+            vst3::Steinberg::kResultOk
+        } else {
+            vst3::Steinberg::kInvalidArgument
         }
+    }
+
+    unsafe fn getProgramInfo(
+        &self,
+        _list_id: vst3::Steinberg::Vst::ProgramListID,
+        _program_index: vst3::Steinberg::int32,
+        _attribute_id: vst3::Steinberg::Vst::CString,
+        _attribute_value: *mut vst3::Steinberg::Vst::String128,
+    ) -> vst3::Steinberg::tresult {
+        vst3::Steinberg::kResultFalse
+    }
+
+    unsafe fn hasProgramPitchNames(
+        &self,
+        _list_id: vst3::Steinberg::Vst::ProgramListID,
+        _program_index: vst3::Steinberg::int32,
+    ) -> vst3::Steinberg::tresult {
+        vst3::Steinberg::kResultFalse
+    }
+
+    unsafe fn getProgramPitchName(
+        &self,
+        _list_id: vst3::Steinberg::Vst::ProgramListID,
+        _program_index: vst3::Steinberg::int32,
+        _midi_pitch: vst3::Steinberg::int16,
+        _name: *mut vst3::Steinberg::Vst::String128,
+    ) -> vst3::Steinberg::tresult {
+        vst3::Steinberg::kResultFalse
+    }
+
+    unsafe fn getSelectedUnit(&self) -> vst3::Steinberg::Vst::UnitID {
+        vst3::Steinberg::Vst::kRootUnitId
+    }
+
+    unsafe fn selectUnit(&self, unit_id: vst3::Steinberg::Vst::UnitID) -> vst3::Steinberg::tresult {
+        if unit_id == vst3::Steinberg::Vst::kRootUnitId {
+            vst3::Steinberg::kResultOk
+        } else {
+            vst3::Steinberg::kInvalidArgument
+        }
+    }
+
+    unsafe fn getUnitByBus(
+        &self,
+        _media_type: vst3::Steinberg::Vst::MediaType,
+        _dir: vst3::Steinberg::Vst::BusDirection,
+        _bus_index: vst3::Steinberg::int32,
+        _channel: vst3::Steinberg::int32,
+        unit_id: *mut vst3::Steinberg::Vst::UnitID,
+    ) -> vst3::Steinberg::tresult {
+        if unit_id.is_null() {
+            return vst3::Steinberg::kInvalidArgument;
+        }
+        *unit_id = vst3::Steinberg::Vst::kRootUnitId;
         vst3::Steinberg::kResultOk
     }
+
+    unsafe fn setUnitProgramData(
+        &self,
+        _list_or_unit_id: vst3::Steinberg::int32,
+        _program_index: vst3::Steinberg::int32,
+        _data: *mut vst3::Steinberg::IBStream,
+    ) -> vst3::Steinberg::tresult {
+        vst3::Steinberg::kNotImplemented
+    }
 }
 
 impl Class for EditController {
@@ -1244,5 +1740,6 @@ impl Class for EditController {
         IConnectionPoint,
         INoteExpressionController,
         INoteExpressionPhysicalUIMapping,
+        IUnitInfo,
     );
 }
@@ -0,0 +1,12 @@
+use serde::{Deserialize, Serialize};
+
+/// The state saved by the edit controller.
+///
+/// Parameter values are saved by the processor, so this only holds state that
+/// belongs to the user interface.
+#[derive(Serialize, Deserialize, Default)]
+pub struct State {
+    /// The UI state blob, see [`conformal_core::parameters::store::Store::get_ui_state`].
+    #[serde(with = "serde_bytes")]
+    pub ui_state: Vec<u8>,
+}
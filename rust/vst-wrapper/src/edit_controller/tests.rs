@@ -1,11 +1,12 @@
 use std::cell::RefCell;
+use std::collections::HashMap;
 use std::rc;
 
 use vst3::Class;
 use vst3::Steinberg::Vst::{
     IAudioProcessorTrait, IComponentHandler, IComponentHandlerTrait, IComponentTrait,
     IHostApplication, IMidiMappingTrait, INoteExpressionControllerTrait,
-    INoteExpressionPhysicalUIMappingTrait, PhysicalUIMap,
+    INoteExpressionPhysicalUIMappingTrait, IUnitInfoTrait, PhysicalUIMap,
 };
 use vst3::Steinberg::{IBStreamTrait, IPluginBaseTrait};
 use vst3::{ComWrapper, Steinberg::Vst::IEditControllerTrait};
@@ -15,20 +16,23 @@ use crate::fake_ibstream::Stream;
 use crate::processor::test_utils::{
     mock_no_audio_process_data, setup_proc, ParameterValueQueueImpl, ParameterValueQueuePoint,
 };
-use crate::HostInfo;
 use crate::{dummy_host, from_utf16_buffer, to_utf16};
-use crate::{processor, ParameterModel};
+use crate::{processor, programs, HostParameterModel, ParameterModel};
+use crate::{HostInfo, SynthOptions};
 use assert_approx_eq::assert_approx_eq;
 use conformal_component::audio::BufferMut;
 use conformal_component::events::{Data, Event, Events};
-use conformal_component::parameters::{self, hash_id, BufferStates, Flags, States, StaticInfoRef};
+use conformal_component::parameters::{
+    self, hash_id, BufferStates, CurveHint, Flags, NumericOptions, Scaling, States, StaticInfoRef,
+};
 use conformal_component::{
     parameters::{InfoRef, TypeSpecificInfoRef},
     synth::Synth,
-    Component, ProcessingEnvironment, Processor,
+    Component, Preset, ProcessingEnvironment, Processor,
 };
 use conformal_core::parameters::store;
 use conformal_core::parameters::store::Store;
+use serde::{Deserialize, Serialize};
 
 #[derive(Default)]
 struct DummyComponent {}
@@ -84,18 +88,25 @@ static PARAMETERS: [InfoRef<'static, &'static str>; 3] = [
         title: "Test Numeric",
         short_title: "Num",
         unique_id: NUMERIC_ID,
-        flags: Flags { automatable: true },
+        flags: Flags {
+            automatable: true,
+            curve_hint: CurveHint::Auto,
+        },
         type_specific: TypeSpecificInfoRef::Numeric {
             default: DEFAULT_NUMERIC,
             valid_range: MIN_NUMERIC..=MAX_NUMERIC,
             units: Some("Hz"),
+            options: NumericOptions::DEFAULT,
         },
     },
     InfoRef {
         title: "Test Enum",
         short_title: "Enum",
         unique_id: ENUM_ID,
-        flags: Flags { automatable: false },
+        flags: Flags {
+            automatable: false,
+            curve_hint: CurveHint::Auto,
+        },
         type_specific: TypeSpecificInfoRef::Enum {
             default: 0,
             values: &["A", "B", "C"],
@@ -105,7 +116,10 @@ static PARAMETERS: [InfoRef<'static, &'static str>; 3] = [
         title: "Test Switch",
         short_title: "Switch",
         unique_id: SWITCH_ID,
-        flags: Flags { automatable: true },
+        flags: Flags {
+            automatable: true,
+            curve_hint: CurveHint::Auto,
+        },
         type_specific: TypeSpecificInfoRef::Switch { default: false },
     },
 ];
@@ -126,11 +140,15 @@ static INCOMPATIBLE_PARAMETERS: [StaticInfoRef; 1] = [InfoRef {
     title: "Test Numeric",
     short_title: "Num",
     unique_id: SWITCH_ID, // This is incompatible since the previous version used this ID for a switch
-    flags: Flags { automatable: true },
+    flags: Flags {
+        automatable: true,
+        curve_hint: CurveHint::Auto,
+    },
     type_specific: TypeSpecificInfoRef::Numeric {
         default: DEFAULT_NUMERIC,
         valid_range: MIN_NUMERIC..=MAX_NUMERIC,
         units: Some("Hz"),
+        options: NumericOptions::DEFAULT,
     },
 }];
 
@@ -154,18 +172,25 @@ static NEWER_PARAMETERS: [StaticInfoRef; 3] = [
         title: "Test Numeric",
         short_title: "Num",
         unique_id: NUMERIC_ID,
-        flags: Flags { automatable: true },
+        flags: Flags {
+            automatable: true,
+            curve_hint: CurveHint::Auto,
+        },
         type_specific: TypeSpecificInfoRef::Numeric {
             default: DEFAULT_NUMERIC,
             valid_range: MIN_NUMERIC..=20.0,
             units: Some("Hz"),
+            options: NumericOptions::DEFAULT,
         },
     },
     InfoRef {
         title: "Test Enum",
         short_title: "Enum",
         unique_id: ENUM_ID,
-        flags: Flags { automatable: false },
+        flags: Flags {
+            automatable: false,
+            curve_hint: CurveHint::Auto,
+        },
         type_specific: TypeSpecificInfoRef::Enum {
             default: 0,
             values: &["A", "B", "C"],
@@ -175,7 +200,10 @@ static NEWER_PARAMETERS: [StaticInfoRef; 3] = [
         title: "Test Switch",
         short_title: "Switch",
         unique_id: SWITCH_ID,
-        flags: Flags { automatable: true },
+        flags: Flags {
+            automatable: true,
+            curve_hint: CurveHint::Auto,
+        },
         type_specific: TypeSpecificInfoRef::Switch { default: false },
     },
 ];
@@ -200,31 +228,49 @@ static DUPLICATE_PARAMETERS: [StaticInfoRef; 2] = [
         title: "Test Numeric",
         short_title: "Num",
         unique_id: NUMERIC_ID,
-        flags: Flags { automatable: true },
+        flags: Flags {
+            automatable: true,
+            curve_hint: CurveHint::Auto,
+        },
         type_specific: TypeSpecificInfoRef::Numeric {
             default: DEFAULT_NUMERIC,
             valid_range: MIN_NUMERIC..=20.0,
             units: Some("Hz"),
+            options: NumericOptions::DEFAULT,
         },
     },
     InfoRef {
         title: "Test Numeric",
         short_title: "Num",
         unique_id: NUMERIC_ID,
-        flags: Flags { automatable: true },
+        flags: Flags {
+            automatable: true,
+            curve_hint: CurveHint::Auto,
+        },
         type_specific: TypeSpecificInfoRef::Numeric {
             default: DEFAULT_NUMERIC,
             valid_range: MIN_NUMERIC..=20.0,
             units: Some("Hz"),
+            options: NumericOptions::DEFAULT,
         },
     },
 ];
 
+fn host_parameter_model(parameter_infos: Vec<parameters::Info>) -> HostParameterModel {
+    HostParameterModel {
+        parameter_infos,
+        note_expression_ranges: Default::default(),
+        state_version: 0,
+        factory_presets: vec![],
+        upgrade_parameters: Box::new(|_, _| {}),
+    }
+}
+
 fn create_parameter_model<F: Fn(&HostInfo) -> Vec<parameters::Info> + 'static>(
     f: F,
 ) -> ParameterModel {
     ParameterModel {
-        parameter_infos: Box::new(f),
+        for_host: Box::new(move |host_info| host_parameter_model(f(host_info))),
     }
 }
 
@@ -246,6 +292,7 @@ fn dummy_processor() -> impl IComponentTrait + IAudioProcessorTrait {
     processor::create_synth(
         |_: &HostInfo| -> DummyComponent { Default::default() },
         [4; 16],
+        SynthOptions::DEFAULT,
     )
 }
 
@@ -395,6 +442,84 @@ fn parameter_basics() {
     assert_eq!(param_info.defaultNormalizedValue, 0.0);
 }
 
+#[test]
+fn curve_hints_set_parameter_flags() {
+    let ec = super::create_internal(
+        create_parameter_model(|_: &HostInfo| {
+            let mut infos = parameters::to_infos(&PARAMETERS);
+            infos[0].flags.curve_hint = CurveHint::Stepped;
+            infos[1].flags.curve_hint = CurveHint::Stepped;
+            infos[2].flags.curve_hint = CurveHint::Continuous;
+            infos
+        }),
+        "dummy_domain".to_string(),
+        conformal_ui::Size {
+            width: 0,
+            height: 0,
+        },
+        super::Kind::Synth(SynthOptions::DEFAULT),
+    );
+    let host = ComWrapper::new(dummy_host::Host::default())
+        .to_com_ptr::<IHostApplication>()
+        .unwrap();
+    unsafe {
+        assert_eq!(
+            ec.initialize(host.cast().unwrap().as_ptr()),
+            vst3::Steinberg::kResultOk
+        );
+    }
+
+    let mut param_info = vst3::Steinberg::Vst::ParameterInfo {
+        id: 0,
+        title: [0; 128],
+        shortTitle: [0; 128],
+        units: [0; 128],
+        stepCount: 0,
+        defaultNormalizedValue: 0f64,
+        unitId: 0,
+        flags: 0,
+    };
+
+    // A stepped numeric parameter has one step per whole number in its range.
+    unsafe {
+        assert_eq!(
+            ec.getParameterInfo(0, &mut param_info),
+            vst3::Steinberg::kResultOk
+        );
+    }
+    assert_eq!(param_info.id, numeric_hash());
+    assert_eq!(
+        param_info.flags,
+        vst3::Steinberg::Vst::ParameterInfo_::ParameterFlags_::kCanAutomate as i32
+    );
+    assert_eq!(param_info.stepCount, 9);
+
+    // A stepped enum parameter keeps its steps, but isn't presented as a list.
+    unsafe {
+        assert_eq!(
+            ec.getParameterInfo(1, &mut param_info),
+            vst3::Steinberg::kResultOk
+        );
+    }
+    assert_eq!(param_info.id, enum_hash());
+    assert_eq!(param_info.flags, 0);
+    assert_eq!(param_info.stepCount, 2);
+
+    // A continuous switch parameter has no steps.
+    unsafe {
+        assert_eq!(
+            ec.getParameterInfo(2, &mut param_info),
+            vst3::Steinberg::kResultOk
+        );
+    }
+    assert_eq!(param_info.id, switch_hash());
+    assert_eq!(
+        param_info.flags,
+        vst3::Steinberg::Vst::ParameterInfo_::ParameterFlags_::kCanAutomate as i32
+    );
+    assert_eq!(param_info.stepCount, 0);
+}
+
 #[test]
 fn defends_against_count_without_initialize() {
     let ec = dummy_edit_controller();
@@ -572,6 +697,92 @@ fn value_to_string_basics() {
     assert_eq!(from_utf16_buffer(&string), Some("On".to_string()));
 }
 
+static PRECISION_PARAMETERS: [StaticInfoRef; 2] = [
+    InfoRef {
+        title: "Detune",
+        short_title: "Detune",
+        unique_id: "detune",
+        flags: Flags {
+            automatable: true,
+            curve_hint: CurveHint::Auto,
+        },
+        type_specific: TypeSpecificInfoRef::Numeric {
+            default: 0.0,
+            valid_range: -100.0..=100.0,
+            units: Some("cents"),
+            options: NumericOptions {
+                display_precision: Some(0),
+                ..NumericOptions::DEFAULT
+            },
+        },
+    },
+    InfoRef {
+        title: "Gain",
+        short_title: "Gain",
+        unique_id: "gain",
+        flags: Flags {
+            automatable: true,
+            curve_hint: CurveHint::Auto,
+        },
+        type_specific: TypeSpecificInfoRef::Numeric {
+            default: 1.0,
+            valid_range: 0.0..=1.0,
+            units: None,
+            options: NumericOptions {
+                display_precision: Some(3),
+                ..NumericOptions::DEFAULT
+            },
+        },
+    },
+];
+
+#[test]
+fn value_to_string_uses_display_precision() {
+    let ec = super::create_internal(
+        create_parameter_model(|_: &HostInfo| parameters::to_infos(&PRECISION_PARAMETERS)),
+        "dummy_domain".to_string(),
+        conformal_ui::Size {
+            width: 0,
+            height: 0,
+        },
+        super::Kind::Synth(SynthOptions::DEFAULT),
+    );
+    let host = ComWrapper::new(dummy_host::Host::default())
+        .to_com_ptr::<IHostApplication>()
+        .unwrap();
+    unsafe {
+        assert_eq!(
+            ec.initialize(host.cast().unwrap().as_ptr()),
+            vst3::Steinberg::kResultOk
+        );
+    }
+    let mut string = [0; 128];
+
+    unsafe {
+        assert_eq!(
+            ec.getParamStringByValue(
+                parameters::hash_id("detune").internal_hash(),
+                0.7512,
+                string.as_mut_ptr() as *mut vst3::Steinberg::Vst::String128,
+            ),
+            vst3::Steinberg::kResultOk
+        );
+    }
+    assert_eq!(from_utf16_buffer(&string), Some("50".to_string()));
+
+    unsafe {
+        assert_eq!(
+            ec.getParamStringByValue(
+                parameters::hash_id("gain").internal_hash(),
+                0.12345,
+                string.as_mut_ptr() as *mut vst3::Steinberg::Vst::String128,
+            ),
+            vst3::Steinberg::kResultOk
+        );
+    }
+    assert_eq!(from_utf16_buffer(&string), Some("0.123".to_string()));
+}
+
 #[test]
 fn defends_against_value_to_string_without_initialize() {
     let ec = dummy_edit_controller();
@@ -640,6 +851,91 @@ fn string_to_value_basics() {
     }
 }
 
+static SCALED_PARAMETERS: [StaticInfoRef; 1] = [InfoRef {
+    title: "Cutoff",
+    short_title: "Cutoff",
+    unique_id: "cutoff",
+    flags: Flags {
+        automatable: true,
+        curve_hint: CurveHint::Auto,
+    },
+    type_specific: TypeSpecificInfoRef::Numeric {
+        default: 200.0,
+        valid_range: 20.0..=20000.0,
+        units: Some("Hz"),
+        options: NumericOptions {
+            display_precision: Some(0),
+            scaling: Scaling::Logarithmic,
+            ..NumericOptions::DEFAULT
+        },
+    },
+}];
+
+#[test]
+fn conversions_respect_scaling() {
+    let ec = super::create_internal(
+        create_parameter_model(|_: &HostInfo| parameters::to_infos(&SCALED_PARAMETERS)),
+        "dummy_domain".to_string(),
+        conformal_ui::Size {
+            width: 0,
+            height: 0,
+        },
+        super::Kind::Synth(SynthOptions::DEFAULT),
+    );
+    let host = ComWrapper::new(dummy_host::Host::default())
+        .to_com_ptr::<IHostApplication>()
+        .unwrap();
+    unsafe {
+        assert_eq!(
+            ec.initialize(host.cast().unwrap().as_ptr()),
+            vst3::Steinberg::kResultOk
+        );
+    }
+    let id = parameters::hash_id("cutoff").internal_hash();
+
+    let mut param_info = vst3::Steinberg::Vst::ParameterInfo {
+        id: 0,
+        title: [0; 128],
+        shortTitle: [0; 128],
+        units: [0; 128],
+        stepCount: 0,
+        defaultNormalizedValue: 0.0,
+        unitId: 0,
+        flags: 0,
+    };
+    unsafe {
+        assert_eq!(
+            ec.getParameterInfo(0, &mut param_info),
+            vst3::Steinberg::kResultOk
+        );
+    }
+    assert_approx_eq!(param_info.defaultNormalizedValue, 1.0 / 3.0);
+    assert_approx_eq!(unsafe { ec.getParamNormalized(id) }, 1.0 / 3.0);
+
+    let mut string = [0; 128];
+    unsafe {
+        assert_eq!(
+            ec.getParamStringByValue(
+                id,
+                2.0 / 3.0,
+                string.as_mut_ptr() as *mut vst3::Steinberg::Vst::String128,
+            ),
+            vst3::Steinberg::kResultOk
+        );
+    }
+    assert_eq!(from_utf16_buffer(&string), Some("2000".to_string()));
+
+    to_utf16("2000", &mut string);
+    let mut value = 0.0;
+    unsafe {
+        assert_eq!(
+            ec.getParamValueByString(id, string.as_mut_ptr(), &mut value),
+            vst3::Steinberg::kResultOk
+        );
+    }
+    assert_approx_eq!(value, 2.0 / 3.0);
+}
+
 #[test]
 fn defends_against_get_param_normalized_called_too_early() {
     let ec = dummy_edit_controller();
@@ -890,6 +1186,7 @@ fn set_component_incompatible_error() {
     let proc = processor::create_synth(
         |_: &HostInfo| -> IncompatibleComponent { Default::default() },
         [5; 16],
+        SynthOptions::DEFAULT,
     );
     let ec = dummy_edit_controller();
 
@@ -933,6 +1230,7 @@ fn set_component_newer_loads_defaults() {
     let proc = processor::create_synth(
         |_: &HostInfo| -> NewerComponent { Default::default() },
         [5; 16],
+        SynthOptions::DEFAULT,
     );
     let ec = dummy_edit_controller();
 
@@ -1114,7 +1412,7 @@ fn panic_on_duplicate_ids() {
             width: 0,
             height: 0,
         },
-        super::Kind::Synth(),
+        super::Kind::Synth(SynthOptions::DEFAULT),
     );
     let host = ComWrapper::new(dummy_host::Host::default());
     unsafe {
@@ -1147,8 +1445,10 @@ fn get_parameters_from_store() {
     assert_eq!(store.get("Invalid"), None);
 }
 
+#[derive(Default)]
 struct SpyListener {
     param_changes: RefCell<Vec<(String, parameters::Value)>>,
+    ui_state_changes: RefCell<Vec<Vec<u8>>>,
 }
 
 impl store::Listener for SpyListener {
@@ -1157,6 +1457,10 @@ impl store::Listener for SpyListener {
             .borrow_mut()
             .push((id.to_string(), value.clone()));
     }
+
+    fn ui_state_changed(&self, state: &[u8]) {
+        self.ui_state_changes.borrow_mut().push(state.to_vec());
+    }
 }
 
 #[test]
@@ -1171,9 +1475,7 @@ fn changing_parameters_in_store() {
         let store = ec.get_store();
         assert!(store.is_some());
         let mut store = store.unwrap();
-        let listener = rc::Rc::new(SpyListener {
-            param_changes: RefCell::new(vec![]),
-        });
+        let listener = rc::Rc::new(SpyListener::default());
         store.set_listener(rc::Rc::downgrade(
             &(listener.clone() as rc::Rc<dyn store::Listener>),
         ));
@@ -1195,6 +1497,70 @@ fn changing_parameters_in_store() {
     }
 }
 
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct TestUiState {
+    open_tab: String,
+    scroll_position: f32,
+}
+
+#[test]
+fn ui_state_saved_in_controller_state() {
+    let ec = dummy_edit_controller();
+    let host = ComWrapper::new(dummy_host::Host::default());
+    let ui_state = TestUiState {
+        open_tab: "envelopes".to_string(),
+        scroll_position: 0.25,
+    };
+    unsafe {
+        assert_eq!(
+            ec.initialize(host.as_com_ref().unwrap().as_ptr()),
+            vst3::Steinberg::kResultOk
+        );
+        let mut store = ec.get_store().unwrap();
+        assert!(store.get_ui_state().is_empty());
+        store.set_ui_state(&conformal_ui::serialize_ui_state(1, &ui_state));
+
+        let stream = ComWrapper::new(Stream::new([]));
+        assert_eq!(
+            ec.getState(stream.as_com_ref().unwrap().as_ptr()),
+            vst3::Steinberg::kResultOk
+        );
+        assert_eq!(
+            stream.seek(
+                0,
+                vst3::Steinberg::IBStream_::IStreamSeekMode_::kIBSeekSet as i32,
+                std::ptr::null_mut(),
+            ),
+            vst3::Steinberg::kResultOk
+        );
+
+        let ec2 = dummy_edit_controller();
+        assert_eq!(
+            ec2.initialize(host.as_com_ref().unwrap().as_ptr()),
+            vst3::Steinberg::kResultOk
+        );
+        let mut store2 = ec2.get_store().unwrap();
+        let listener = rc::Rc::new(SpyListener::default());
+        store2.set_listener(rc::Rc::downgrade(
+            &(listener.clone() as rc::Rc<dyn store::Listener>),
+        ));
+        assert_eq!(
+            ec2.setState(stream.as_com_ref().unwrap().as_ptr()),
+            vst3::Steinberg::kResultOk
+        );
+
+        // The restored state should be passed on to the UI.
+        assert_eq!(
+            listener.ui_state_changes.borrow().as_slice(),
+            &[store2.get_ui_state()]
+        );
+        assert_eq!(
+            conformal_ui::deserialize_ui_state::<TestUiState>(&store2.get_ui_state(), 1),
+            Ok(ui_state)
+        );
+    }
+}
+
 #[test]
 fn set_component_state_sets_params() {
     let proc = dummy_processor();
@@ -1210,9 +1576,7 @@ fn set_component_state_sets_params() {
         let store = ec.get_store();
         assert!(store.is_some());
         let mut store = store.unwrap();
-        let listener = rc::Rc::new(SpyListener {
-            param_changes: RefCell::new(vec![]),
-        });
+        let listener = rc::Rc::new(SpyListener::default());
         store.set_listener(rc::Rc::downgrade(
             &(listener.clone() as rc::Rc<dyn store::Listener>),
         ));
@@ -1270,38 +1634,175 @@ fn set_component_state_sets_params() {
 }
 
 #[test]
-fn set_from_store_forwarded_to_component_handler() {
+fn set_component_state_requests_restart_when_values_change() {
+    let proc = dummy_processor();
     let ec = dummy_edit_controller();
 
     let host = ComWrapper::new(dummy_host::Host::default());
     let spy = ComWrapper::new(ComponentHandlerSpy::default());
+    let param_values_changed = ComponentHandlerCalls::RestartComponent(
+        vst3::Steinberg::Vst::RestartFlags_::kParamValuesChanged as i32,
+    );
+
     unsafe {
         assert_eq!(
             ec.initialize(host.as_com_ref().unwrap().as_ptr()),
             vst3::Steinberg::kResultOk
         );
-        let mut store = ec.get_store().unwrap();
         assert_eq!(
             ec.setComponentHandler(spy.as_com_ref().unwrap().as_ptr()),
             vst3::Steinberg::kResultOk
         );
-        assert_eq!(
-            store.set(ENUM_ID, parameters::Value::Enum("C".to_string())),
-            Ok(())
-        );
-        assert!(spy.calls.borrow().iter().any(|call| call
-            == &ComponentHandlerCalls::PerformEdit(
-                parameters::hash_id(ENUM_ID).internal_hash(),
-                1.0
-            )));
-    }
-}
 
-#[test]
-fn invalid_id_fails_set() {
-    let ec = dummy_edit_controller();
+        setup_proc(&proc, &host);
 
-    let host = ComWrapper::new(dummy_host::Host::default());
+        assert_eq!(
+            proc.process(
+                &mut mock_no_audio_process_data(
+                    vec![],
+                    vec![ParameterValueQueueImpl {
+                        param_id: ENUM_ID.to_string(),
+                        points: vec![ParameterValueQueuePoint {
+                            sample_offset: 0,
+                            value: 1.0,
+                        }],
+                    },],
+                )
+                .process_data
+            ),
+            vst3::Steinberg::kResultOk
+        );
+        let stream = ComWrapper::new(Stream::new([]));
+        assert_eq!(
+            proc.getState(
+                stream
+                    .as_com_ref::<vst3::Steinberg::IBStream>()
+                    .unwrap()
+                    .as_ptr()
+            ),
+            vst3::Steinberg::kResultOk
+        );
+        assert_eq!(
+            stream.seek(
+                0,
+                vst3::Steinberg::IBStream_::IStreamSeekMode_::kIBSeekSet as i32,
+                std::ptr::null_mut(),
+            ),
+            vst3::Steinberg::kResultOk
+        );
+
+        assert_eq!(
+            ec.setComponentState(stream.as_com_ref().unwrap().as_ptr()),
+            vst3::Steinberg::kResultOk
+        );
+        assert!(spy.calls.borrow().contains(&param_values_changed));
+
+        // Loading the same state again doesn't change any values, so we shouldn't restart.
+        spy.calls.borrow_mut().clear();
+        assert_eq!(
+            stream.seek(
+                0,
+                vst3::Steinberg::IBStream_::IStreamSeekMode_::kIBSeekSet as i32,
+                std::ptr::null_mut(),
+            ),
+            vst3::Steinberg::kResultOk
+        );
+        assert_eq!(
+            ec.setComponentState(stream.as_com_ref().unwrap().as_ptr()),
+            vst3::Steinberg::kResultOk
+        );
+        assert!(!spy.calls.borrow().contains(&param_values_changed));
+    }
+}
+
+#[test]
+fn set_from_store_forwarded_to_component_handler() {
+    let ec = dummy_edit_controller();
+
+    let host = ComWrapper::new(dummy_host::Host::default());
+    let spy = ComWrapper::new(ComponentHandlerSpy::default());
+    unsafe {
+        assert_eq!(
+            ec.initialize(host.as_com_ref().unwrap().as_ptr()),
+            vst3::Steinberg::kResultOk
+        );
+        let mut store = ec.get_store().unwrap();
+        assert_eq!(
+            ec.setComponentHandler(spy.as_com_ref().unwrap().as_ptr()),
+            vst3::Steinberg::kResultOk
+        );
+        assert_eq!(
+            store.set(ENUM_ID, parameters::Value::Enum("C".to_string())),
+            Ok(())
+        );
+        assert!(spy.calls.borrow().iter().any(|call| call
+            == &ComponentHandlerCalls::PerformEdit(
+                parameters::hash_id(ENUM_ID).internal_hash(),
+                1.0
+            )));
+    }
+}
+
+#[test]
+fn reset_to_default_from_store_forwarded_to_component_handler() {
+    let ec = dummy_edit_controller();
+
+    let host = ComWrapper::new(dummy_host::Host::default());
+    let spy = ComWrapper::new(ComponentHandlerSpy::default());
+    unsafe {
+        assert_eq!(
+            ec.initialize(host.as_com_ref().unwrap().as_ptr()),
+            vst3::Steinberg::kResultOk
+        );
+        let mut store = ec.get_store().unwrap();
+        assert_eq!(
+            ec.setComponentHandler(spy.as_com_ref().unwrap().as_ptr()),
+            vst3::Steinberg::kResultOk
+        );
+        assert_eq!(
+            store.set(NUMERIC_ID, parameters::Value::Numeric(MAX_NUMERIC)),
+            Ok(())
+        );
+        assert_eq!(store.reset_to_default(NUMERIC_ID), Ok(()));
+        assert_eq!(
+            store.get(NUMERIC_ID),
+            Some(parameters::Value::Numeric(DEFAULT_NUMERIC))
+        );
+        assert!(matches!(
+            spy.calls.borrow().last(),
+            Some(ComponentHandlerCalls::PerformEdit(id, value))
+                if *id == numeric_hash()
+                    && (value
+                        - f64::from((DEFAULT_NUMERIC - MIN_NUMERIC) / (MAX_NUMERIC - MIN_NUMERIC)))
+                    .abs()
+                        < NUMERIC_EPSILON
+        ));
+    }
+}
+
+#[test]
+fn invalid_id_fails_reset_to_default() {
+    let ec = dummy_edit_controller();
+
+    let host = ComWrapper::new(dummy_host::Host::default());
+    unsafe {
+        assert_eq!(
+            ec.initialize(host.as_com_ref().unwrap().as_ptr()),
+            vst3::Steinberg::kResultOk
+        );
+        let mut store = ec.get_store().unwrap();
+        assert_eq!(
+            store.reset_to_default("Not a real ID"),
+            Err(store::SetError::NotFound)
+        );
+    }
+}
+
+#[test]
+fn invalid_id_fails_set() {
+    let ec = dummy_edit_controller();
+
+    let host = ComWrapper::new(dummy_host::Host::default());
     let spy = ComWrapper::new(ComponentHandlerSpy::default());
     unsafe {
         assert_eq!(
@@ -1512,7 +2013,10 @@ fn defends_against_default_on_bypass_param() {
                 title: "Test Switch",
                 short_title: "Switch",
                 unique_id: SWITCH_ID,
-                flags: Flags { automatable: true },
+                flags: Flags {
+                    automatable: true,
+                    curve_hint: CurveHint::Auto,
+                },
                 type_specific: TypeSpecificInfoRef::Switch { default: true },
             }])
         }),
@@ -1539,7 +2043,10 @@ fn bypass_parameter_exposed() {
                 title: "Test Switch",
                 short_title: "Switch",
                 unique_id: SWITCH_ID,
-                flags: Flags { automatable: true },
+                flags: Flags {
+                    automatable: true,
+                    curve_hint: CurveHint::Auto,
+                },
                 type_specific: TypeSpecificInfoRef::Switch { default: false },
             }])
         }),
@@ -1599,10 +2106,130 @@ fn dummy_synth_edit_controller() -> impl IPluginBaseTrait
             width: 0,
             height: 0,
         },
-        super::Kind::Synth(),
+        super::Kind::Synth(SynthOptions::DEFAULT),
+    )
+}
+
+fn dummy_synth_edit_controller_with_parameters(
+) -> impl IPluginBaseTrait + IEditControllerTrait + GetStore {
+    super::create_internal(
+        create_parameter_model(|_: &HostInfo| parameters::to_infos(&PARAMETERS)),
+        "dummy_domain".to_string(),
+        conformal_ui::Size {
+            width: 0,
+            height: 0,
+        },
+        super::Kind::Synth(SynthOptions::DEFAULT),
     )
 }
 
+#[test]
+fn controller_parameters_settable_from_store_but_not_saved() {
+    let proc = dummy_processor();
+    let ec = dummy_synth_edit_controller_with_parameters();
+    let host = ComWrapper::new(dummy_host::Host::default());
+    let spy = ComWrapper::new(ComponentHandlerSpy::default());
+    unsafe {
+        assert_eq!(
+            ec.initialize(host.as_com_ref().unwrap().as_ptr()),
+            vst3::Steinberg::kResultOk
+        );
+        assert_eq!(
+            ec.setComponentHandler(spy.as_com_ref().unwrap().as_ptr()),
+            vst3::Steinberg::kResultOk
+        );
+        let mut store = ec.get_store().unwrap();
+        assert_eq!(
+            store.get(conformal_component::synth::MOD_WHEEL_PARAMETER),
+            Some(parameters::Value::Numeric(0.0))
+        );
+        assert_eq!(
+            store.set(
+                conformal_component::synth::MOD_WHEEL_PARAMETER,
+                parameters::Value::Numeric(0.5)
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            store.get(conformal_component::synth::MOD_WHEEL_PARAMETER),
+            Some(parameters::Value::Numeric(0.5))
+        );
+        assert!(spy.calls.borrow().iter().any(|call| call
+            == &ComponentHandlerCalls::PerformEdit(
+                parameters::hash_id(conformal_component::synth::MOD_WHEEL_PARAMETER)
+                    .internal_hash(),
+                0.5
+            )));
+
+        // Now, forward the edits to the processor and save its state.
+        setup_proc(&proc, &host);
+        assert_eq!(
+            proc.process(
+                &mut mock_no_audio_process_data(
+                    vec![],
+                    vec![
+                        ParameterValueQueueImpl {
+                            param_id: ENUM_ID.to_string(),
+                            points: vec![ParameterValueQueuePoint {
+                                sample_offset: 0,
+                                value: 1.0,
+                            }],
+                        },
+                        ParameterValueQueueImpl {
+                            param_id: conformal_component::synth::MOD_WHEEL_PARAMETER.to_string(),
+                            points: vec![ParameterValueQueuePoint {
+                                sample_offset: 0,
+                                value: 0.5,
+                            }],
+                        },
+                    ],
+                )
+                .process_data
+            ),
+            vst3::Steinberg::kResultOk
+        );
+        let stream = ComWrapper::new(Stream::new([]));
+        assert_eq!(
+            proc.getState(
+                stream
+                    .as_com_ref::<vst3::Steinberg::IBStream>()
+                    .unwrap()
+                    .as_ptr()
+            ),
+            vst3::Steinberg::kResultOk
+        );
+        assert_eq!(
+            stream.seek(
+                0,
+                vst3::Steinberg::IBStream_::IStreamSeekMode_::kIBSeekSet as i32,
+                std::ptr::null_mut(),
+            ),
+            vst3::Steinberg::kResultOk
+        );
+
+        // Loading the state into a fresh controller should restore the component
+        // parameters, but leave the controller parameters at their defaults.
+        let ec2 = dummy_synth_edit_controller_with_parameters();
+        assert_eq!(
+            ec2.initialize(host.as_com_ref().unwrap().as_ptr()),
+            vst3::Steinberg::kResultOk
+        );
+        assert_eq!(
+            ec2.setComponentState(stream.as_com_ref().unwrap().as_ptr()),
+            vst3::Steinberg::kResultOk
+        );
+        let store2 = ec2.get_store().unwrap();
+        assert_eq!(
+            store2.get(ENUM_ID),
+            Some(parameters::Value::Enum("C".to_string()))
+        );
+        assert_eq!(
+            store2.get(conformal_component::synth::MOD_WHEEL_PARAMETER),
+            Some(parameters::Value::Numeric(0.0))
+        );
+    }
+}
+
 #[test]
 fn synth_control_parameters_exposed() {
     let ec = dummy_synth_edit_controller();
@@ -1635,6 +2262,14 @@ fn synth_control_parameters_exposed() {
             vst3::Steinberg::Vst::ControllerNumbers_::kCtrlSustainOnOff,
             conformal_component::synth::SUSTAIN_PARAMETER,
         );
+        check_assignment(
+            vst3::Steinberg::Vst::ControllerNumbers_::kCtrlSustenutoOnOff,
+            conformal_component::synth::SOSTENUTO_PARAMETER,
+        );
+        check_assignment(
+            vst3::Steinberg::Vst::ControllerNumbers_::kCtrlSoftPedalOnOff,
+            conformal_component::synth::SOFT_PEDAL_PARAMETER,
+        );
         check_assignment(
             vst3::Steinberg::Vst::ControllerNumbers_::kAfterTouch,
             conformal_component::synth::AFTERTOUCH_PARAMETER,
@@ -1661,6 +2296,43 @@ fn synth_control_parameters_exposed() {
     }
 }
 
+#[test]
+fn synths_that_use_midi_channels_skip_mpe_quirks() {
+    let ec = super::create_internal(
+        create_parameter_model(|_: &HostInfo| parameters::to_infos(&[])),
+        "dummy_domain".to_string(),
+        conformal_ui::Size {
+            width: 0,
+            height: 0,
+        },
+        super::Kind::Synth(SynthOptions {
+            uses_midi_channels: true,
+            ..SynthOptions::DEFAULT
+        }),
+    );
+    let host = ComWrapper::new(dummy_host::Host::default());
+    unsafe {
+        assert_eq!(ec.initialize(host.as_com_ref().unwrap().as_ptr()), 0);
+
+        // Non-zero channels carry real notes, so they aren't mapped to MPE quirks parameters.
+        let mut id: vst3::Steinberg::Vst::ParamID = 0;
+        assert_eq!(
+            ec.getMidiControllerAssignment(
+                0,
+                1,
+                vst3::Steinberg::Vst::ControllerNumbers_::kAfterTouch as i16,
+                &mut id
+            ),
+            vst3::Steinberg::kResultFalse
+        );
+        assert!(ec
+            .get_store()
+            .unwrap()
+            .get(&crate::mpe_quirks::aftertouch_param_id(1))
+            .is_none());
+    }
+}
+
 #[test]
 fn midi_mapping_bad_context_false() {
     let ec = dummy_synth_edit_controller();
@@ -1903,6 +2575,97 @@ fn get_note_expression_string_by_value() {
     }
 }
 
+#[test]
+fn note_expression_conversions_respect_declared_ranges() {
+    let ec = super::create_internal(
+        ParameterModel {
+            for_host: Box::new(|_: &HostInfo| HostParameterModel {
+                note_expression_ranges: conformal_component::synth::NoteExpressionRanges {
+                    pitch_bend: 48.0,
+                    ..Default::default()
+                },
+                ..host_parameter_model(parameters::to_infos(&[]))
+            }),
+        },
+        "dummy_domain".to_string(),
+        conformal_ui::Size {
+            width: 0,
+            height: 0,
+        },
+        super::Kind::Synth(SynthOptions::DEFAULT),
+    );
+    let host = ComWrapper::new(dummy_host::Host::default());
+    unsafe {
+        assert_eq!(
+            ec.initialize(host.as_com_ref().unwrap().as_ptr()),
+            vst3::Steinberg::kResultOk
+        );
+
+        let mut info = vst3::Steinberg::Vst::NoteExpressionTypeInfo {
+            typeId: 0,
+            title: [0; 128],
+            shortTitle: [0; 128],
+            units: [0; 128],
+            unitId: 0,
+            valueDesc: vst3::Steinberg::Vst::NoteExpressionValueDescription {
+                defaultValue: 0.0,
+                minimum: 0.0,
+                maximum: 0.0,
+                stepCount: 0,
+            },
+            associatedParameterId: 0,
+            flags: 0,
+        };
+        assert_eq!(
+            ec.getNoteExpressionInfo(0, 0, 0, &mut info),
+            vst3::Steinberg::kResultOk
+        );
+        assert_approx_eq!(info.valueDesc.minimum, 0.3);
+        assert_approx_eq!(info.valueDesc.maximum, 0.7);
+        assert_eq!(info.valueDesc.defaultValue, 0.5);
+
+        let mut string = [0i16; 128];
+        assert_eq!(
+            ec.getNoteExpressionStringByValue(
+                0,
+                0,
+                vst3::Steinberg::Vst::NoteExpressionTypeIDs_::kTuningTypeID,
+                0.7,
+                string.as_mut_ptr().cast::<[i16; 128]>()
+            ),
+            vst3::Steinberg::kResultOk
+        );
+        assert_eq!(from_utf16_buffer(&string).unwrap(), "48.00");
+
+        // Values outside the declared range are clamped
+        assert_eq!(
+            ec.getNoteExpressionStringByValue(
+                0,
+                0,
+                vst3::Steinberg::Vst::NoteExpressionTypeIDs_::kTuningTypeID,
+                1.0,
+                string.as_mut_ptr().cast::<[i16; 128]>()
+            ),
+            vst3::Steinberg::kResultOk
+        );
+        assert_eq!(from_utf16_buffer(&string).unwrap(), "48.00");
+
+        let mut value = 0.0;
+        to_utf16("-60", &mut string);
+        assert_eq!(
+            ec.getNoteExpressionValueByString(
+                0,
+                0,
+                vst3::Steinberg::Vst::NoteExpressionTypeIDs_::kTuningTypeID,
+                string.as_ptr(),
+                &mut value
+            ),
+            vst3::Steinberg::kResultOk
+        );
+        assert_approx_eq!(value, 0.3);
+    }
+}
+
 #[test]
 fn get_note_expression_value_by_string() {
     let ec = dummy_synth_edit_controller();
@@ -2050,6 +2813,66 @@ fn get_physical_ui_mapping() {
     }
 }
 
+#[test]
+fn get_physical_ui_mapping_respects_declared_mapping() {
+    let ec = super::create_internal(
+        ParameterModel {
+            for_host: Box::new(|_: &HostInfo| HostParameterModel {
+                note_expression_ranges: conformal_component::synth::NoteExpressionRanges {
+                    physical_ui_mapping: conformal_component::synth::PhysicalUIMapping {
+                        x: Some(conformal_component::synth::NoteExpressionKind::PitchBend),
+                        y: Some(conformal_component::synth::NoteExpressionKind::Aftertouch),
+                        pressure: None,
+                    },
+                    ..Default::default()
+                },
+                ..host_parameter_model(parameters::to_infos(&[]))
+            }),
+        },
+        "dummy_domain".to_string(),
+        conformal_ui::Size {
+            width: 0,
+            height: 0,
+        },
+        super::Kind::Synth(SynthOptions::DEFAULT),
+    );
+    let host = ComWrapper::new(dummy_host::Host::default());
+    unsafe {
+        assert_eq!(
+            ec.initialize(host.as_com_ref().unwrap().as_ptr()),
+            vst3::Steinberg::kResultOk
+        );
+
+        let mut map: [PhysicalUIMap; 3] = [PhysicalUIMap {
+            physicalUITypeID: 0,
+            noteExpressionTypeID: 0,
+        }; 3];
+        map[0].physicalUITypeID = vst3::Steinberg::Vst::PhysicalUITypeIDs_::kPUIYMovement;
+        map[1].physicalUITypeID = vst3::Steinberg::Vst::PhysicalUITypeIDs_::kPUIXMovement;
+        map[2].physicalUITypeID = vst3::Steinberg::Vst::PhysicalUITypeIDs_::kPUIPressure;
+        let mut physical_ui_mapping = vst3::Steinberg::Vst::PhysicalUIMapList {
+            count: 3,
+            map: map.as_mut_ptr(),
+        };
+        assert_eq!(
+            ec.getPhysicalUIMapping(0, 0, &mut physical_ui_mapping),
+            vst3::Steinberg::kResultOk
+        );
+        assert_eq!(
+            map[0].noteExpressionTypeID,
+            processor::NOTE_EXPRESSION_AFTERTOUCH_TYPE_ID
+        );
+        assert_eq!(
+            map[1].noteExpressionTypeID,
+            vst3::Steinberg::Vst::NoteExpressionTypeIDs_::kTuningTypeID
+        );
+        assert_eq!(
+            map[2].noteExpressionTypeID,
+            vst3::Steinberg::Vst::NoteExpressionTypeIDs_::kInvalidTypeID
+        );
+    }
+}
+
 #[test]
 fn get_midi_controller_assignment_effect() {
     let ec = super::create_internal(
@@ -2058,7 +2881,10 @@ fn get_midi_controller_assignment_effect() {
                 title: "Test Switch",
                 short_title: "Switch",
                 unique_id: SWITCH_ID,
-                flags: Flags { automatable: true },
+                flags: Flags {
+                    automatable: true,
+                    curve_hint: CurveHint::Auto,
+                },
                 type_specific: TypeSpecificInfoRef::Switch { default: false },
             }])
         }),
@@ -2091,3 +2917,249 @@ fn get_midi_controller_assignment_effect() {
         );
     }
 }
+
+fn factory_presets() -> Vec<Preset> {
+    vec![
+        Preset {
+            name: "Init".to_string(),
+            values: HashMap::new(),
+        },
+        Preset {
+            name: "Bright".to_string(),
+            values: [
+                (NUMERIC_ID.to_string(), parameters::Value::Numeric(5.0)),
+                (
+                    ENUM_ID.to_string(),
+                    parameters::Value::Enum("C".to_string()),
+                ),
+            ]
+            .into_iter()
+            .collect(),
+        },
+    ]
+}
+
+fn preset_edit_controller() -> impl IEditControllerTrait + IUnitInfoTrait {
+    super::create_internal(
+        ParameterModel {
+            for_host: Box::new(|_: &HostInfo| HostParameterModel {
+                factory_presets: factory_presets(),
+                ..host_parameter_model(parameters::to_infos(&PARAMETERS))
+            }),
+        },
+        "dummy_domain".to_string(),
+        conformal_ui::Size {
+            width: 0,
+            height: 0,
+        },
+        super::Kind::Effect {
+            bypass_id: SWITCH_ID,
+        },
+    )
+}
+
+fn program_hash() -> u32 {
+    parameters::hash_id(programs::PARAMETER_ID).internal_hash()
+}
+
+#[test]
+fn factory_presets_add_program_change_parameter() {
+    let ec = preset_edit_controller();
+    let host = ComWrapper::new(dummy_host::Host::default());
+    unsafe {
+        assert_eq!(
+            ec.initialize(host.as_com_ref().unwrap().as_ptr()),
+            vst3::Steinberg::kResultOk
+        );
+        assert_eq!(ec.getParameterCount(), 4);
+    }
+
+    let mut param_info = vst3::Steinberg::Vst::ParameterInfo {
+        id: 0,
+        title: [0; 128],
+        shortTitle: [0; 128],
+        units: [0; 128],
+        stepCount: 0,
+        defaultNormalizedValue: 0f64,
+        unitId: 0,
+        flags: 0,
+    };
+    unsafe {
+        assert_eq!(
+            ec.getParameterInfo(3, &mut param_info),
+            vst3::Steinberg::kResultOk
+        );
+    }
+    assert_eq!(param_info.id, program_hash());
+    assert_eq!(
+        param_info.flags,
+        vst3::Steinberg::Vst::ParameterInfo_::ParameterFlags_::kIsList as i32
+            | vst3::Steinberg::Vst::ParameterInfo_::ParameterFlags_::kIsProgramChange as i32
+    );
+    assert_eq!(param_info.stepCount, 1);
+    assert_eq!(param_info.unitId, vst3::Steinberg::Vst::kRootUnitId);
+}
+
+#[test]
+fn factory_presets_listed_as_programs() {
+    let ec = preset_edit_controller();
+    let host = ComWrapper::new(dummy_host::Host::default());
+    unsafe {
+        assert_eq!(
+            ec.initialize(host.as_com_ref().unwrap().as_ptr()),
+            vst3::Steinberg::kResultOk
+        );
+        assert_eq!(ec.getUnitCount(), 1);
+        let mut unit_info = vst3::Steinberg::Vst::UnitInfo {
+            id: 0,
+            parentUnitId: 0,
+            name: [0; 128],
+            programListId: 0,
+        };
+        assert_eq!(
+            ec.getUnitInfo(0, &mut unit_info),
+            vst3::Steinberg::kResultOk
+        );
+        assert_eq!(unit_info.id, vst3::Steinberg::Vst::kRootUnitId);
+        assert_eq!(
+            unit_info.parentUnitId,
+            vst3::Steinberg::Vst::kNoParentUnitId
+        );
+        assert_eq!(unit_info.programListId, programs::PROGRAM_LIST_ID);
+
+        assert_eq!(ec.getProgramListCount(), 1);
+        let mut list_info = vst3::Steinberg::Vst::ProgramListInfo {
+            id: 0,
+            name: [0; 128],
+            programCount: 0,
+        };
+        assert_eq!(
+            ec.getProgramListInfo(0, &mut list_info),
+            vst3::Steinberg::kResultOk
+        );
+        assert_eq!(list_info.id, programs::PROGRAM_LIST_ID);
+        assert_eq!(list_info.programCount, 2);
+
+        let mut name = [0; 128];
+        assert_eq!(
+            ec.getProgramName(programs::PROGRAM_LIST_ID, 1, &mut name),
+            vst3::Steinberg::kResultOk
+        );
+        assert_eq!(from_utf16_buffer(&name), Some("Bright".to_string()));
+        assert_eq!(
+            ec.getProgramName(programs::PROGRAM_LIST_ID, 2, &mut name),
+            vst3::Steinberg::kInvalidArgument
+        );
+    }
+}
+
+#[test]
+fn no_program_list_without_factory_presets() {
+    let ec = super::create_internal(
+        create_parameter_model(|_: &HostInfo| parameters::to_infos(&PARAMETERS)),
+        "dummy_domain".to_string(),
+        conformal_ui::Size {
+            width: 0,
+            height: 0,
+        },
+        super::Kind::Effect {
+            bypass_id: SWITCH_ID,
+        },
+    );
+    let host = ComWrapper::new(dummy_host::Host::default());
+    unsafe {
+        assert_eq!(
+            ec.initialize(host.as_com_ref().unwrap().as_ptr()),
+            vst3::Steinberg::kResultOk
+        );
+        assert_eq!(ec.getParameterCount(), 3);
+        assert_eq!(ec.getProgramListCount(), 0);
+        let mut unit_info = vst3::Steinberg::Vst::UnitInfo {
+            id: 0,
+            parentUnitId: 0,
+            name: [0; 128],
+            programListId: 0,
+        };
+        assert_eq!(
+            ec.getUnitInfo(0, &mut unit_info),
+            vst3::Steinberg::kResultOk
+        );
+        assert_eq!(
+            unit_info.programListId,
+            vst3::Steinberg::Vst::kNoProgramListId
+        );
+    }
+}
+
+#[test]
+fn no_program_list_with_single_factory_preset() {
+    let ec = super::create_internal(
+        ParameterModel {
+            for_host: Box::new(|_: &HostInfo| HostParameterModel {
+                factory_presets: factory_presets()[..1].to_vec(),
+                ..host_parameter_model(parameters::to_infos(&PARAMETERS))
+            }),
+        },
+        "dummy_domain".to_string(),
+        conformal_ui::Size {
+            width: 0,
+            height: 0,
+        },
+        super::Kind::Effect {
+            bypass_id: SWITCH_ID,
+        },
+    );
+    let host = ComWrapper::new(dummy_host::Host::default());
+    unsafe {
+        assert_eq!(
+            ec.initialize(host.as_com_ref().unwrap().as_ptr()),
+            vst3::Steinberg::kResultOk
+        );
+        assert_eq!(ec.getParameterCount(), 3);
+        assert_eq!(ec.getProgramListCount(), 0);
+    }
+}
+
+#[test]
+fn selecting_program_applies_preset() {
+    let ec = preset_edit_controller();
+    let host = ComWrapper::new(dummy_host::Host::default());
+    let spy = ComWrapper::new(ComponentHandlerSpy::default());
+    let param_values_changed = ComponentHandlerCalls::RestartComponent(
+        vst3::Steinberg::Vst::RestartFlags_::kParamValuesChanged as i32,
+    );
+    unsafe {
+        assert_eq!(
+            ec.initialize(host.as_com_ref().unwrap().as_ptr()),
+            vst3::Steinberg::kResultOk
+        );
+        assert_eq!(
+            ec.setComponentHandler(spy.as_com_ref().unwrap().as_ptr()),
+            vst3::Steinberg::kResultOk
+        );
+        assert_eq!(
+            ec.setParamNormalized(switch_hash(), 1.0),
+            vst3::Steinberg::kResultOk
+        );
+
+        assert_eq!(
+            ec.setParamNormalized(program_hash(), 1.0),
+            vst3::Steinberg::kResultOk
+        );
+        assert_approx_eq!(ec.getParamNormalized(numeric_hash()), 4.0 / 9.0);
+        assert_approx_eq!(ec.getParamNormalized(enum_hash()), 1.0);
+
+        // Parameters that aren't in the preset are reset to their defaults
+        assert_approx_eq!(ec.getParamNormalized(switch_hash()), 0.0);
+        assert!(spy.calls.borrow().contains(&param_values_changed));
+
+        spy.calls.borrow_mut().clear();
+        assert_eq!(
+            ec.setParamNormalized(program_hash(), 0.0),
+            vst3::Steinberg::kResultOk
+        );
+        assert_approx_eq!(ec.getParamNormalized(numeric_hash()), 1.0 / 9.0);
+        assert_approx_eq!(ec.getParamNormalized(enum_hash()), 0.0);
+        assert!(spy.calls.borrow().contains(&param_values_changed));
+    }
+}
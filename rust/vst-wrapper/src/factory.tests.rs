@@ -1,6 +1,6 @@
 use super::Factory;
 use crate::{ClassInfo, Info};
-use crate::{HostInfo, SynthClass};
+use crate::{HostInfo, SynthClass, SynthOptions};
 use conformal_component::audio::BufferMut;
 use conformal_component::events::{Data, Event, Events};
 use conformal_component::parameters::{BufferStates, States};
@@ -103,8 +103,10 @@ fn test_too_long_class_name() {
                     width: 800,
                     height: 400,
                 },
+                ui_size_constraints: None,
             },
             factory: |_: &HostInfo| DummyComponent {},
+            options: SynthOptions::DEFAULT,
         }],
         Info {
             vendor: "test",
@@ -171,8 +173,10 @@ fn basic_class_info_test() {
                     width: 800,
                     height: 400,
                 },
+                ui_size_constraints: None,
             },
             factory: &|_: &HostInfo| DummyComponent {},
+            options: SynthOptions::DEFAULT,
         }],
         Info {
             vendor: "test",
@@ -254,8 +258,10 @@ fn bad_class_id_gives_error() {
                     width: 800,
                     height: 400,
                 },
+                ui_size_constraints: None,
             },
             factory: &|_: &HostInfo| DummyComponent {},
+            options: SynthOptions::DEFAULT,
         }],
         Info {
             vendor: "test",
@@ -285,8 +291,10 @@ fn get_edit_controller_class_id() {
                     width: 800,
                     height: 400,
                 },
+                ui_size_constraints: None,
             },
             factory: &|_: &HostInfo| DummyComponent {},
+            options: SynthOptions::DEFAULT,
         }],
         Info {
             vendor: "test",
@@ -363,8 +371,10 @@ fn basic_class_info_2_test() {
                     width: 800,
                     height: 400,
                 },
+                ui_size_constraints: None,
             },
             factory: &|_: &HostInfo| DummyComponent {},
+            options: SynthOptions::DEFAULT,
         }],
         Info {
             vendor: VENDOR,
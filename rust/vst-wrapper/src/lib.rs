@@ -24,7 +24,13 @@ use core::slice;
 /// Contains information about the host.
 ///
 /// You can use this to customize the comonent based on the host.
-#[derive(Clone, Debug, PartialEq, Eq)]
+///
+/// The [`Default`] value, with an empty `name`, doesn't match any real host.
+/// It's used as a representative host when we need information about a
+/// component outside of any host, for example to generate documentation for its
+/// parameters. Components whose parameters depend on the host should treat it like
+/// any unknown host.
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
 pub struct HostInfo {
     /// The name of the host.
     pub name: String,
@@ -70,11 +76,117 @@ pub struct ClassInfo<'a> {
 
     /// Initial size of the UI in logical pixels
     pub ui_initial_size: UiSize,
+
+    /// Constraints on the size of the UI, if any.
+    ///
+    /// Hosts can only resize the UI if this is set, and will ask the UI to
+    /// check any sizes they choose against these constraints.
+    pub ui_size_constraints: Option<UiSizeConstraints>,
+}
+
+impl<'a> ClassInfo<'a> {
+    /// Create a `ClassInfo` with only the required settings.
+    ///
+    /// Optional settings can be added with the `with_` methods, such as
+    /// [`Self::with_ui_size_constraints`]. Unlike a struct literal, this won't
+    /// have to change when new optional settings are added.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use conformal_vst_wrapper::{ClassInfo, UiSize, UiSizeConstraints};
+    ///
+    /// const INFO: ClassInfo<'static> = ClassInfo::new(
+    ///     "My effect",
+    ///     [1; 16],
+    ///     [2; 16],
+    ///     UiSize {
+    ///         width: 400,
+    ///         height: 400,
+    ///     },
+    /// )
+    /// .with_ui_size_constraints(UiSizeConstraints {
+    ///     min: Some(UiSize {
+    ///         width: 200,
+    ///         height: 200,
+    ///     }),
+    ///     max: None,
+    ///     keep_aspect_ratio: true,
+    /// });
+    /// assert!(INFO.ui_size_constraints.is_some());
+    /// ```
+    #[must_use]
+    pub const fn new(
+        name: &'a str,
+        cid: ClassID,
+        edit_controller_cid: ClassID,
+        ui_initial_size: UiSize,
+    ) -> Self {
+        Self {
+            name,
+            cid,
+            edit_controller_cid,
+            ui_initial_size,
+            ui_size_constraints: None,
+        }
+    }
+
+    /// Let the host resize the UI, within `constraints`.
+    #[must_use]
+    pub const fn with_ui_size_constraints(self, constraints: UiSizeConstraints) -> Self {
+        Self {
+            ui_size_constraints: Some(constraints),
+            ..self
+        }
+    }
+}
+
+/// Constraints on the size of a component's UI, in logical pixels.
+///
+/// Any constraint can be left out. The [`Default`] value doesn't constrain the size at all.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UiSizeConstraints {
+    /// The smallest size of the UI.
+    pub min: Option<UiSize>,
+
+    /// The largest size of the UI.
+    pub max: Option<UiSize>,
+
+    /// If `true`, the UI always keeps the same aspect ratio as [`ClassInfo::ui_initial_size`].
+    ///
+    /// When the host chooses a size with a different aspect ratio, the width is kept
+    /// (within `min` and `max`) and the height is adjusted to match.
+    pub keep_aspect_ratio: bool,
+}
+
+/// Everything the edit controller needs to know about a component running in a given host.
+#[doc(hidden)]
+pub struct HostParameterModel {
+    pub parameter_infos: Vec<conformal_component::parameters::Info>,
+    pub note_expression_ranges: conformal_component::synth::NoteExpressionRanges,
+    pub state_version: u32,
+    pub factory_presets: Vec<conformal_component::Preset>,
+    pub upgrade_parameters: Box<
+        dyn Fn(u32, &mut std::collections::HashMap<String, conformal_component::parameters::Value>),
+    >,
 }
 
 #[doc(hidden)]
 pub struct ParameterModel {
-    pub parameter_infos: Box<dyn Fn(&HostInfo) -> Vec<conformal_component::parameters::Info>>,
+    /// Creates the component for a host, and describes it.
+    pub for_host: Box<dyn Fn(&HostInfo) -> HostParameterModel>,
+}
+
+impl ParameterModel {
+    /// Get the parameter infos for a representative, host-agnostic host.
+    ///
+    /// This doesn't require a processor or a connection to a real host, so tooling
+    /// can use it to enumerate the parameters of a component, for example to
+    /// generate documentation or to validate them in a test. See [`HostInfo`]'s
+    /// [`Default`] implementation for what "host-agnostic" means here.
+    pub fn host_agnostic_parameter_infos(&self) -> Vec<conformal_component::parameters::Info> {
+        (self.for_host)(&HostInfo::default()).parameter_infos
+    }
 }
 
 #[doc(hidden)]
@@ -97,16 +209,115 @@ pub struct SynthClass<CF> {
 
     /// Information about the component
     pub info: ClassInfo<'static>,
+
+    /// Optional settings for the synth.
+    pub options: SynthOptions,
+}
+
+/// Optional settings for a [`SynthClass`].
+///
+/// In `const` and `static` items, where `Default::default()` can't be called,
+/// use `..SynthOptions::DEFAULT`.
+///
+/// # Examples
+///
+/// ```
+/// use conformal_vst_wrapper::SynthOptions;
+///
+/// const DRUM_MACHINE_OPTIONS: SynthOptions = SynthOptions {
+///     aux_outputs: &["Kick", "Snare", "Hat"],
+///     ..SynthOptions::DEFAULT
+/// };
+/// assert_eq!(DRUM_MACHINE_OPTIONS.aux_outputs.len(), 3);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SynthOptions {
+    /// The names of the synth's auxiliary audio outputs.
+    ///
+    /// Every synth has a main output. Each name here adds another output after
+    /// the main one, for example to send each drum of a drum machine to its own
+    /// mixer channel. All outputs are rendered in [`Synth::process_buses`].
+    ///
+    /// Auxiliary outputs start out in stereo, and each can use any layout in
+    /// [`Component::supported_channel_layouts`]. Hosts may leave auxiliary outputs
+    /// inactive until the user routes them somewhere.
+    ///
+    /// At most [`conformal_component::synth::MAX_AUX_OUTPUTS`] auxiliary outputs
+    /// are supported, and creating a synth with more will panic.
+    ///
+    /// The default is no auxiliary outputs.
+    pub aux_outputs: &'static [&'static str],
+
+    /// Whether the synth plays notes differently depending on their MIDI channel.
+    ///
+    /// If this is `true`, the channel each note was played on is delivered in
+    /// [`conformal_component::events::NoteData::channel`], so that, for example,
+    /// a multitimbral or drum synth can route each channel to a different part.
+    /// Otherwise, every note arrives on channel 0.
+    ///
+    /// Some hosts send per-note expressions MPE-style, by playing each note on its
+    /// own channel. We can only interpret channels this way for synths that don't
+    /// use them, so synths that set this only receive per-note expressions from
+    /// hosts that send them natively.
+    ///
+    /// The default is `false`.
+    pub uses_midi_channels: bool,
+
+    /// Whether the synth receives [`conformal_component::events::Data::ParameterChange`] events.
+    ///
+    /// If this is `true`, each parameter change sent by the host will be interleaved
+    /// into the events at its sample offset. This is useful for synths that handle events
+    /// sample-by-sample and need to see parameter changes at the right time relative to
+    /// other events, such as a note on at the same sample.
+    ///
+    /// The default is `false`.
+    pub parameter_change_events: bool,
+}
+
+impl SynthOptions {
+    /// The default options, usable in `const` and `static` items.
+    pub const DEFAULT: Self = Self {
+        aux_outputs: &[],
+        uses_midi_channels: false,
+        parameter_change_events: false,
+    };
+}
+
+impl Default for SynthOptions {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Panics if the component that `factory` creates for a host-agnostic host
+/// fails [`conformal_component::validate_component`].
+///
+/// Classes check this whenever they're constructed, so that invalid components
+/// fail as soon as a host loads them.
+fn assert_valid_component<CF: ComponentFactory<Component: Component>>(factory: &CF) {
+    if let Err(errors) =
+        conformal_component::validate_component(&factory.create(&HostInfo::default()))
+    {
+        panic!("Invalid component: {errors:?}");
+    }
 }
 
 fn create_parameter_model_internal<CF: ComponentFactory + 'static>(factory: CF) -> ParameterModel
 where
-    CF::Component: Component,
+    CF::Component: Component + 'static,
 {
     ParameterModel {
-        parameter_infos: Box::new(move |host_info| {
+        for_host: Box::new(move |host_info| {
             let component = factory.create(host_info);
-            component.parameter_infos()
+            HostParameterModel {
+                parameter_infos: component.parameter_infos(),
+                note_expression_ranges: component.note_expression_ranges(),
+                state_version: component.state_version(),
+                factory_presets: component.factory_presets(),
+                upgrade_parameters: Box::new(move |saved_version, values| {
+                    component.upgrade_parameters(saved_version, values);
+                }),
+            }
         }),
     }
 }
@@ -116,15 +327,18 @@ where
     CF::Component: Component<Processor: Synth> + 'static,
 {
     fn create_processor(&self, controller_cid: ClassID) -> vst3::ComPtr<IPluginBase> {
+        assert_valid_component(&self.factory);
         vst3::ComWrapper::new(processor::create_synth(
             self.factory.clone(),
             controller_cid,
+            self.options,
         ))
         .to_com_ptr::<IPluginBase>()
         .unwrap()
     }
 
     fn create_parameter_model(&self) -> ParameterModel {
+        assert_valid_component(&self.factory);
         create_parameter_model_internal(self.factory.clone())
     }
 
@@ -137,7 +351,7 @@ where
     }
 
     fn get_kind(&self) -> edit_controller::Kind {
-        edit_controller::Kind::Synth()
+        edit_controller::Kind::Synth(self.options)
     }
 }
 
@@ -156,15 +370,63 @@ pub struct EffectClass<CF> {
 
     /// All effects must have a bypass parameter. This is the unique ID for that parameter.
     pub bypass_id: &'static str,
+
+    /// Optional settings for the effect.
+    pub options: EffectOptions,
+}
+
+/// Optional settings for an [`EffectClass`].
+///
+/// In `const` and `static` items, where `Default::default()` can't be called,
+/// use `..EffectOptions::DEFAULT`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EffectOptions {
+    /// Whether the effect has an event input.
+    ///
+    /// Effects normally only receive audio and parameters. If this is `true`, the
+    /// effect also receives events, such as notes and note expressions, through
+    /// [`Effect::handle_events`] and [`Effect::process_with_events`].
+    ///
+    /// We declare an event input bus for the effect, which hosts may deactivate,
+    /// in which case the effect receives no events.
+    ///
+    /// The default is `false`.
+    pub event_input: bool,
+
+    /// Whether the effect receives [`conformal_component::events::Data::ParameterChange`] events.
+    ///
+    /// Like [`SynthOptions::parameter_change_events`], this interleaves each parameter
+    /// change sent by the host into the events at its sample offset. This only has an
+    /// effect if [`Self::event_input`] is also `true`.
+    ///
+    /// The default is `false`.
+    pub parameter_change_events: bool,
+}
+
+impl EffectOptions {
+    /// The default options, usable in `const` and `static` items.
+    pub const DEFAULT: Self = Self {
+        event_input: false,
+        parameter_change_events: false,
+    };
+}
+
+impl Default for EffectOptions {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
 }
 
 impl<CF: ComponentFactory<Component: Component<Processor: Effect> + 'static> + 'static>
     ClassCategory for EffectClass<CF>
 {
     fn create_processor(&self, controller_cid: ClassID) -> vst3::ComPtr<IPluginBase> {
+        assert_valid_component(&self.factory);
         vst3::ComWrapper::new(processor::create_effect(
             self.factory.clone(),
             controller_cid,
+            self.bypass_id,
+            self.options,
         ))
         .to_com_ptr::<IPluginBase>()
         .unwrap()
@@ -179,6 +441,7 @@ impl<CF: ComponentFactory<Component: Component<Processor: Effect> + 'static> + '
     }
 
     fn create_parameter_model(&self) -> ParameterModel {
+        assert_valid_component(&self.factory);
         create_parameter_model_internal(self.factory.clone())
     }
 
@@ -189,6 +452,49 @@ impl<CF: ComponentFactory<Component: Component<Processor: Effect> + 'static> + '
     }
 }
 
+/// Information about an analyzer component
+///
+/// Analyzers are effects that never change their audio, such as tuners or meters.
+/// See [`conformal_component::analyzer::Analyzer`] for more details.
+pub struct AnalyzerClass<CF> {
+    /// The actual factory.
+    pub factory: CF,
+
+    /// Information about the component
+    pub info: ClassInfo<'static>,
+}
+
+impl<CF: ComponentFactory<Component: Component<Processor: Analyzer> + 'static> + 'static>
+    ClassCategory for AnalyzerClass<CF>
+{
+    fn create_processor(&self, controller_cid: ClassID) -> vst3::ComPtr<IPluginBase> {
+        assert_valid_component(&self.factory);
+        vst3::ComWrapper::new(processor::create_analyzer(
+            self.factory.clone(),
+            controller_cid,
+        ))
+        .to_com_ptr::<IPluginBase>()
+        .unwrap()
+    }
+
+    fn category_str(&self) -> &'static str {
+        "Fx|Analyzer"
+    }
+
+    fn info(&self) -> &ClassInfo<'static> {
+        &self.info
+    }
+
+    fn create_parameter_model(&self) -> ParameterModel {
+        assert_valid_component(&self.factory);
+        create_parameter_model_internal(self.factory.clone())
+    }
+
+    fn get_kind(&self) -> edit_controller::Kind {
+        edit_controller::Kind::Analyzer()
+    }
+}
+
 /// General global infor about a vst plug-in
 #[derive(Debug, Clone, Copy)]
 pub struct Info<'a> {
@@ -207,6 +513,7 @@ pub struct Info<'a> {
     pub version: &'a str,
 }
 
+use conformal_component::analyzer::Analyzer;
 use conformal_component::effect::Effect;
 use conformal_component::synth::Synth;
 use conformal_component::Component;
@@ -221,14 +528,25 @@ mod io;
 mod mpe_quirks;
 mod parameters;
 mod processor;
+mod programs;
+mod shared_data;
 mod view;
 
-#[cfg(test)]
+pub use processor::state::{deserialize_state, serialize_state, StateError};
+pub use shared_data::{SharedData, SharedDataFactory};
+
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_utils;
+
+#[cfg(any(test, feature = "test-utils"))]
 mod dummy_host;
 
-#[cfg(test)]
+#[cfg(any(test, feature = "test-utils"))]
 mod fake_ibstream;
 
+#[cfg(test)]
+mod tests;
+
 #[doc(hidden)]
 pub fn _wrap_factory(
     classes: &'static [&'static dyn ClassCategory],
@@ -282,7 +600,7 @@ fn should_include_parameter_in_snapshot(id: &str) -> bool {
 /// be invoked exactly once in each VST3 plug-in binary.
 ///
 /// Note that each VST3 plug-in binary can contain _multiple_ components,
-/// so this takes a slice of `EffectClass` and `SynthClass` instances.
+/// so this takes a slice of `EffectClass`, `SynthClass`, and `AnalyzerClass` instances.
 ///
 /// Note that to create a loadable plug-in, you must add this to your
 /// `Cargo.toml`:
@@ -299,10 +617,10 @@ fn should_include_parameter_in_snapshot(id: &str) -> bool {
 /// # Example
 ///
 /// ```
-/// use conformal_vst_wrapper::{ClassID, ClassInfo, EffectClass, HostInfo, Info};
+/// use conformal_vst_wrapper::{ClassID, ClassInfo, EffectClass, EffectOptions, HostInfo, Info};
 /// use conformal_component::audio::{channels, channels_mut, Buffer, BufferMut};
 /// use conformal_component::effect::Effect as EffectTrait;
-/// use conformal_component::parameters::{self, BufferStates, Flags, InfoRef, TypeSpecificInfoRef};
+/// use conformal_component::parameters::{self, BufferStates, CurveHint, Flags, InfoRef, TypeSpecificInfoRef, NumericOptions};
 /// use conformal_component::pzip;
 /// use conformal_component::{Component as ComponentTrait, ProcessingEnvironment, Processor};
 ///
@@ -311,18 +629,25 @@ fn should_include_parameter_in_snapshot(id: &str) -> bool {
 ///         title: "Bypass",
 ///         short_title: "Bypass",
 ///         unique_id: "bypass",
-///         flags: Flags { automatable: true },
+///         flags: Flags {
+///             automatable: true,
+///             curve_hint: CurveHint::Auto,
+///         },
 ///         type_specific: TypeSpecificInfoRef::Switch { default: false },
 ///     },
 ///     InfoRef {
 ///         title: "Gain",
 ///         short_title: "Gain",
 ///         unique_id: "gain",
-///         flags: Flags { automatable: true },
+///         flags: Flags {
+///             automatable: true,
+///             curve_hint: CurveHint::Auto,
+///         },
 ///         type_specific: TypeSpecificInfoRef::Numeric {
 ///             default: 100.,
 ///             valid_range: 0f32..=100.,
 ///             units: Some("%"),
+///             options: NumericOptions::DEFAULT,
 ///         },
 ///     },
 /// ];
@@ -382,18 +707,19 @@ fn should_include_parameter_in_snapshot(id: &str) -> bool {
 /// conformal_vst_wrapper::wrap_factory!(
 ///     &const {
 ///         [&EffectClass {
-///             info: ClassInfo {
-///                 name: "My effect",
-///                 cid: CID,
-///                 edit_controller_cid: EDIT_CONTROLLER_CID,
-///                 ui_initial_size: conformal_vst_wrapper::UiSize {
+///             info: ClassInfo::new(
+///                 "My effect",
+///                 CID,
+///                 EDIT_CONTROLLER_CID,
+///                 conformal_vst_wrapper::UiSize {
 ///                     width: 400,
 ///                     height: 400,
 ///                 },
-///             },
+///             ),
 ///             factory: |_: &HostInfo| -> Component { Default::default() },
 ///             category: "Fx",
 ///             bypass_id: "bypass",
+///             options: EffectOptions::DEFAULT,
 ///         }]
 ///     },
 ///     Info {
@@ -6,7 +6,7 @@ use conformal_component::{
         self, to_vst_note_channel_for_mpe_quirks, Events, NoteExpression, NoteExpressionData,
         NoteID,
     },
-    parameters::{self, hash_id, BufferStates, Flags, IdHash, States, TypeSpecificInfo},
+    parameters::{self, hash_id, BufferStates, CurveHint, Flags, IdHash, States, TypeSpecificInfo},
 };
 
 use crate::HostInfo;
@@ -38,11 +38,16 @@ pub enum Support {
 //
 // We begrudgingly support this, since we want our plug-ins to work with Ableton, even though
 // it means adding _several_ completely unnecessary dummy parameters, and a bunch of extra code.
-pub fn should_support(_: &HostInfo) -> Support {
-    // Currently support "mpe quirks" in all hosts. If this implementation of note expression
+pub fn should_support(_: &HostInfo, uses_midi_channels: bool) -> Support {
+    // Components that use midi channels need every channel for real notes, so we can't
+    // reinterpret non-zero channels as MPE per-note channels for them.
+    if uses_midi_channels {
+        return Support::DoNotSupportQuirks;
+    }
+    // Otherwise, support "mpe quirks" in all hosts. If this implementation of note expression
     // becomes less common, we might want to use only a list of hosts known to use this quirky
-    // implementation. There isn't much of a downside to supporting the quirks, since we
-    // don't support multi-channel synths anyways. When and if we do, we'll have to reconsider this.
+    // implementation. There isn't much of a downside to supporting the quirks for components
+    // that only listen to a single channel.
     Support::SupportQuirks
 }
 
@@ -53,33 +58,45 @@ pub fn parameters() -> impl Iterator<Item = parameters::Info> + Clone + 'static
                 unique_id: aftertouch_param_id(idx),
                 title: format!("MPE Quirks Aftertouch {idx}"),
                 short_title: format!("MPE After {idx}"),
-                flags: Flags { automatable: false },
+                flags: Flags {
+                    automatable: false,
+                    curve_hint: CurveHint::Auto,
+                },
                 type_specific: TypeSpecificInfo::Numeric {
                     default: 0.0,
                     valid_range: 0.0..=1.0,
                     units: None,
+                    options: Default::default(),
                 },
             },
             parameters::Info {
                 unique_id: pitch_param_id(idx),
                 title: format!("MPE Quirks Pitch {idx}"),
                 short_title: format!("MPE Pitch {idx}"),
-                flags: Flags { automatable: false },
+                flags: Flags {
+                    automatable: false,
+                    curve_hint: CurveHint::Auto,
+                },
                 type_specific: TypeSpecificInfo::Numeric {
                     default: 0.0,
                     valid_range: -48.0..=48.0,
                     units: None,
+                    options: Default::default(),
                 },
             },
             parameters::Info {
                 unique_id: timbre_param_id(idx),
                 title: format!("MPE Quirks Timbre {idx}"),
                 short_title: format!("MPE Timbre {idx}"),
-                flags: Flags { automatable: false },
+                flags: Flags {
+                    automatable: false,
+                    curve_hint: CurveHint::Auto,
+                },
                 type_specific: TypeSpecificInfo::Numeric {
                     default: 0.0,
                     valid_range: 0.0..=1.0,
                     units: None,
+                    options: Default::default(),
                 },
             },
         ]
@@ -190,7 +207,9 @@ fn update_state_for_event(ev: &events::Data, quirks_state: &mut State) {
                 quirks_state.channels[(c - 1) as usize] = Default::default();
             }
         }
-        events::Data::NoteOff { .. } | events::Data::NoteExpression { .. } => {}
+        events::Data::NoteOff { .. }
+        | events::Data::NoteExpression { .. }
+        | events::Data::ParameterChange { .. } => {}
     }
 }
 
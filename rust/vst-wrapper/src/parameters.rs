@@ -1,33 +1,37 @@
-// Generally we _expect_ truncation here, so allow it.
-#[allow(clippy::cast_possible_truncation)]
-pub fn convert_numeric(value: f64, valid_range: &std::ops::RangeInclusive<f32>) -> f32 {
-    (value as f32).clamp(0.0, 1.0) * (valid_range.end() - valid_range.start()) + valid_range.start()
-}
+use conformal_component::parameters::{CurveHint, Info, TypeSpecificInfo};
+use conformal_core::parameters::serialization::ReadInfoRef;
 
-pub fn normalize_numeric(value: f32, valid_range: &std::ops::RangeInclusive<f32>) -> f64 {
-    ((value.clamp(*valid_range.start(), *valid_range.end()) - valid_range.start())
-        / (valid_range.end() - valid_range.start()))
-    .into()
-}
+#[cfg(test)]
+mod tests;
 
+/// The number of discrete steps the host should present for a parameter, or 0 if it is continuous.
 // Generally we _expect_ truncation here, so allow it.
 #[allow(clippy::cast_possible_truncation)]
-pub fn convert_enum(value: f64, count: u32) -> u32 {
-    ((value.clamp(0.0, 1.0) * (f64::from(count))).floor() as u32).min(count - 1)
-}
-
-pub fn normalize_enum(value: u32, count: u32) -> f64 {
-    (f64::from(value.clamp(0, count - 1))) / (f64::from(count - 1))
-}
-
-pub fn convert_switch(value: f64) -> bool {
-    value > 0.5
+pub fn step_count(info: &TypeSpecificInfo, curve_hint: CurveHint) -> i32 {
+    match (info, curve_hint) {
+        (_, CurveHint::Continuous) | (TypeSpecificInfo::Numeric { .. }, CurveHint::Auto) => 0,
+        (_, CurveHint::Toggle) | (TypeSpecificInfo::Switch { .. }, _) => 1,
+        (TypeSpecificInfo::Enum { values, .. }, _) => i32::try_from(values.len()).unwrap() - 1,
+        (TypeSpecificInfo::Numeric { valid_range, .. }, CurveHint::Stepped) => {
+            ((valid_range.end() - valid_range.start()).round() as i32).max(1)
+        }
+    }
 }
 
-pub fn normalize_switch(value: bool) -> f64 {
-    if value {
-        1.0
-    } else {
-        0.0
+pub fn as_deserialization(info: &Info) -> ReadInfoRef<impl Iterator<Item = &str> + Clone> {
+    match &info.type_specific {
+        TypeSpecificInfo::Enum { default, values } => ReadInfoRef::Enum {
+            default: *default,
+            values: values.iter().map(String::as_str),
+        },
+        TypeSpecificInfo::Numeric {
+            default,
+            valid_range,
+            ..
+        } => ReadInfoRef::Numeric {
+            default: *default,
+            valid_range: valid_range.clone(),
+        },
+        TypeSpecificInfo::Switch { default } => ReadInfoRef::Switch { default: *default },
     }
 }
@@ -0,0 +1,80 @@
+use conformal_component::parameters::{CurveHint, TypeSpecificInfo};
+
+use super::step_count;
+
+fn numeric(valid_range: std::ops::RangeInclusive<f32>) -> TypeSpecificInfo {
+    TypeSpecificInfo::Numeric {
+        default: *valid_range.start(),
+        valid_range,
+        units: None,
+        options: Default::default(),
+    }
+}
+
+fn enumeration(count: usize) -> TypeSpecificInfo {
+    TypeSpecificInfo::Enum {
+        default: 0,
+        values: (0..count).map(|i| i.to_string()).collect(),
+    }
+}
+
+fn infos() -> Vec<TypeSpecificInfo> {
+    vec![
+        numeric(0.0..=1.0),
+        numeric(-1.0..=1.0),
+        numeric(-10.0..=-2.0),
+        numeric(0.5..=10.0),
+        numeric(20.0..=20000.0),
+        numeric(1.0..=1.0001),
+        numeric(-1e-4..=1e-4),
+        numeric(3.0..=3.0),
+        enumeration(2),
+        enumeration(3),
+        enumeration(7),
+        enumeration(128),
+        TypeSpecificInfo::Switch { default: false },
+    ]
+}
+
+#[test]
+fn auto_curve_hint_steps_by_type() {
+    assert_eq!(step_count(&numeric(0.0..=10.0), CurveHint::Auto), 0);
+    assert_eq!(step_count(&enumeration(3), CurveHint::Auto), 2);
+    assert_eq!(
+        step_count(
+            &TypeSpecificInfo::Switch { default: false },
+            CurveHint::Auto
+        ),
+        1
+    );
+}
+
+#[test]
+fn continuous_curve_hint_has_no_steps() {
+    for info in infos() {
+        assert_eq!(step_count(&info, CurveHint::Continuous), 0);
+    }
+}
+
+#[test]
+fn toggle_curve_hint_has_one_step() {
+    for info in infos() {
+        assert_eq!(step_count(&info, CurveHint::Toggle), 1);
+    }
+}
+
+#[test]
+fn stepped_curve_hint_steps_by_whole_numbers() {
+    assert_eq!(step_count(&numeric(-12.0..=12.0), CurveHint::Stepped), 24);
+    assert_eq!(step_count(&numeric(1.0..=16.0), CurveHint::Stepped), 15);
+    // Narrow ranges still get at least one step.
+    assert_eq!(step_count(&numeric(0.0..=0.25), CurveHint::Stepped), 1);
+    assert_eq!(step_count(&enumeration(7), CurveHint::Stepped), 6);
+    assert_eq!(
+        step_count(
+            &TypeSpecificInfo::Switch { default: false },
+            CurveHint::Stepped
+        ),
+        1
+    );
+}
@@ -1,3 +1,4 @@
+use itertools::Itertools;
 use vst3::{
     ComRef,
     Steinberg::Vst::{IEventList, IEventListTrait},
@@ -37,6 +38,42 @@ unsafe fn get_event(
     Some(event)
 }
 
+/// Work out the note id and channel of a note on or off event.
+///
+/// When we support MPE quirks, non-zero channels each carry a single note, so
+/// they get a per-channel note id and all notes arrive on channel 0. Otherwise,
+/// notes keep their midi channel.
+fn note_id_and_channel(
+    channel: i16,
+    pitch: u8,
+    note_id: i32,
+    support_mpe_quirks: Support,
+) -> Option<(NoteID, u8)> {
+    match support_mpe_quirks {
+        Support::SupportQuirks => Some((
+            if channel != 0 {
+                NoteID::from_channel_for_mpe_quirks(channel)
+            } else if note_id == -1 {
+                NoteID::from_pitch(pitch)
+            } else {
+                NoteID::from_id(note_id)
+            },
+            0,
+        )),
+        Support::DoNotSupportQuirks => {
+            let channel = u8::try_from(channel).ok().filter(|channel| *channel < 16)?;
+            Some((
+                if note_id == -1 {
+                    NoteID::from_channel_and_pitch(channel, pitch)
+                } else {
+                    NoteID::from_id(note_id)
+                },
+                channel,
+            ))
+        }
+    }
+}
+
 unsafe fn convert_event(
     event: &vst3::Steinberg::Vst::Event,
     support_mpe_quirks: Support,
@@ -47,10 +84,12 @@ unsafe fn convert_event(
     match u32::from(event.r#type) {
         vst3::Steinberg::Vst::Event_::EventTypes_::kNoteOnEvent => {
             let pitch = u8::try_from(event.__field0.noteOn.pitch).ok()?;
-            let channel = event.__field0.noteOn.channel;
-            if support_mpe_quirks == Support::DoNotSupportQuirks && channel != 0 {
-                return None;
-            }
+            let (id, channel) = note_id_and_channel(
+                event.__field0.noteOn.channel,
+                pitch,
+                event.__field0.noteOn.noteId,
+                support_mpe_quirks,
+            )?;
             Some(Event {
                 sample_offset: event.sampleOffset as usize,
                 data: Data::NoteOn {
@@ -58,24 +97,20 @@ unsafe fn convert_event(
                         pitch,
                         tuning: event.__field0.noteOn.tuning,
                         velocity: event.__field0.noteOn.velocity,
-                        id: if channel != 0 {
-                            NoteID::from_channel_for_mpe_quirks(channel)
-                        } else if event.__field0.noteOn.noteId == -1 {
-                            NoteID::from_pitch(pitch)
-                        } else {
-                            NoteID::from_id(event.__field0.noteOn.noteId)
-                        },
+                        channel,
+                        id,
                     },
                 },
             })
         }
         vst3::Steinberg::Vst::Event_::EventTypes_::kNoteOffEvent => {
             let pitch = u8::try_from(event.__field0.noteOff.pitch).ok()?;
-            let channel = event.__field0.noteOff.channel;
-            if support_mpe_quirks == Support::DoNotSupportQuirks && channel != 0 {
-                return None;
-            }
-
+            let (id, channel) = note_id_and_channel(
+                event.__field0.noteOff.channel,
+                pitch,
+                event.__field0.noteOff.noteId,
+                support_mpe_quirks,
+            )?;
             Some(Event {
                 sample_offset: event.sampleOffset as usize,
                 data: Data::NoteOff {
@@ -83,13 +118,8 @@ unsafe fn convert_event(
                         pitch,
                         tuning: event.__field0.noteOff.tuning,
                         velocity: event.__field0.noteOff.velocity,
-                        id: if channel != 0 {
-                            NoteID::from_channel_for_mpe_quirks(channel)
-                        } else if event.__field0.noteOff.noteId == -1 {
-                            NoteID::from_pitch(pitch)
-                        } else {
-                            NoteID::from_id(event.__field0.noteOff.noteId)
-                        },
+                        channel,
+                        id,
                     },
                 },
             })
@@ -147,3 +177,15 @@ pub unsafe fn all_zero_event_iterator(
         Some(i.map(|x| x.data))
     }
 }
+
+/// Interleaves parameter change events into `events`, keeping them sorted by sample offset.
+///
+/// Parameter changes come before other events at the same sample offset.
+pub fn with_parameter_changes(
+    events: impl Iterator<Item = Event> + Clone,
+    changes: impl Iterator<Item = Event> + Clone,
+) -> impl Iterator<Item = Event> + Clone {
+    changes.merge_by(events, |change: &Event, event: &Event| {
+        change.sample_offset <= event.sample_offset
+    })
+}
@@ -6,14 +6,16 @@ use crate::mpe_quirks::{
     self, add_mpe_quirk_events_buffer, add_mpe_quirk_events_no_audio,
     update_mpe_quirk_events_buffer, update_mpe_quirk_events_no_audio, Support,
 };
-use crate::{ClassID, ComponentFactory, HostInfo};
+use crate::{programs, ClassID, ComponentFactory, EffectOptions, HostInfo, SynthOptions};
+use conformal_component::analyzer::Analyzer;
 use conformal_component::audio::{Buffer, BufferMut, ChannelLayout};
-use conformal_component::effect::Effect;
+use conformal_component::effect::{Effect, WithBypassAlias};
 use conformal_component::events::{Event, Events};
 use conformal_component::parameters::BufferStates;
 use conformal_component::synth::{Synth, CONTROLLER_PARAMETERS};
 use conformal_component::{
-    Component, ProcessingEnvironment, ProcessingMode, Processor as ProcessorT,
+    Component, ProcessingEnvironment, ProcessingMode, Processor as ProcessorT, TimeSignature,
+    Transport, INFINITE_TAIL_SAMPLES,
 };
 use serde::Serialize;
 use vst3::Steinberg::Vst::{
@@ -44,6 +46,7 @@ mod parameters;
 
 struct InitializedData<C, CF> {
     conformal_component: C,
+    host_info: HostInfo,
     params_main: parameters::MainStore,
     processing_environment: Option<PartialProcessingEnvironment>,
 
@@ -62,6 +65,12 @@ struct InitializedData<C, CF> {
     // sequence rules without having to use locks.
     process_context_active: bool,
 
+    /// The latency reported by the most recently created processor.
+    latency_samples: vst3::Steinberg::uint32,
+
+    /// The tail length reported by the most recently created processor.
+    tail_samples: vst3::Steinberg::uint32,
+
     factory: CF,
 }
 
@@ -74,6 +83,64 @@ struct ActiveProcessContext<P, A> {
 
     /// If we support hosts with MPE Quirks, the current state for MPE quirks.
     mpe_quirks: Option<mpe_quirks::State>,
+
+    /// Whether to interleave parameter changes into the events sent to the processor.
+    /// See [`ProcessorCategory::parameter_change_events`].
+    parameter_change_events: bool,
+
+    /// Whether the host may process in 64-bit.
+    /// See [`ProcessorT::SUPPORTS_DOUBLE_PRECISION`].
+    double_precision: bool,
+}
+
+impl<P, A: ActiveProcessorCategory<P>> ActiveProcessContext<P, A> {
+    /// Processes a buffer of audio whose samples are of type `S`.
+    unsafe fn process_audio<S: Sample>(
+        &mut self,
+        data: *mut vst3::Steinberg::Vst::ProcessData,
+        num_frames: usize,
+    ) -> vst3::Steinberg::tresult {
+        if let Some(process_buffer) = self
+            .category
+            .make_process_buffer::<S>(&mut self.processor, data)
+        {
+            if let Some(input_events) = ComRef::from_raw((*data).inputEvents) {
+                if let Some(events) = Events::new(
+                    events::event_iterator(
+                        input_events,
+                        if self.mpe_quirks.is_some() {
+                            Support::SupportQuirks
+                        } else {
+                            Support::DoNotSupportQuirks
+                        },
+                    ),
+                    num_frames,
+                ) {
+                    return events.do_process(
+                        process_buffer,
+                        &mut self.params,
+                        data,
+                        self.mpe_quirks.as_mut(),
+                        self.parameter_change_events,
+                        num_frames,
+                    );
+                }
+            } else {
+                return Events::new(std::iter::empty(), num_frames)
+                    .unwrap()
+                    .do_process(
+                        process_buffer,
+                        &mut self.params,
+                        data,
+                        self.mpe_quirks.as_mut(),
+                        self.parameter_change_events,
+                        num_frames,
+                    );
+            }
+        }
+        // If we got here, some invariant was not met by the host (i.e., Events was misformated.)
+        vst3::Steinberg::kInvalidArgument
+    }
 }
 
 #[derive(Default)]
@@ -109,35 +176,74 @@ struct SynthBusActivationState {
     audio_output_active: bool,
 }
 
+/// The most audio outputs a synth can have, including the main output.
+///
+/// [`create_synth`] panics for synths that declare more auxiliary outputs.
+const MAX_SYNTH_OUTPUTS: usize = 1 + conformal_component::synth::MAX_AUX_OUTPUTS;
+
+/// An auxiliary output, see [`SynthOptions::aux_outputs`].
+#[derive(Clone, Debug)]
+struct SynthAuxOutput {
+    name: &'static str,
+    channel_layout: ChannelLayout,
+    active: bool,
+}
+
 #[derive(Clone, Debug)]
 struct SynthProcessorCategory {
     channel_layout: ChannelLayout,
     bus_activation_state: SynthBusActivationState,
+    aux_outputs: Vec<SynthAuxOutput>,
+    uses_midi_channels: bool,
+    parameter_change_events: bool,
 }
 
 struct ActiveSynthProcessorCategory {
     channel_layout: ChannelLayout,
+
+    /// The layout of each auxiliary output, and whether the host activated it.
+    aux_outputs: Vec<(ChannelLayout, bool)>,
 }
 
-impl Default for SynthProcessorCategory {
-    fn default() -> Self {
+impl SynthProcessorCategory {
+    fn new(options: SynthOptions) -> Self {
         SynthProcessorCategory {
             channel_layout: ChannelLayout::Stereo,
             bus_activation_state: Default::default(),
+            aux_outputs: options
+                .aux_outputs
+                .iter()
+                .map(|&name| SynthAuxOutput {
+                    name,
+                    channel_layout: ChannelLayout::Stereo,
+                    active: false,
+                })
+                .collect(),
+            uses_midi_channels: options.uses_midi_channels,
+            parameter_change_events: options.parameter_change_events,
         }
     }
+
+    /// Auxiliary outputs come after the main output, so output bus `index` is
+    /// auxiliary output `index - 1`.
+    fn aux_output_index(&self, index: vst3::Steinberg::int32) -> Option<usize> {
+        usize::try_from(index)
+            .ok()?
+            .checked_sub(1)
+            .filter(|index| *index < self.aux_outputs.len())
+    }
 }
 
 trait ActiveProcessorCategory<P> {
-    type ProcessBuffer<'a>: ProcessBuffer
+    type ProcessBuffer<'a, S: Sample>: ProcessBuffer
     where
         P: 'a,
         Self: 'a;
-    unsafe fn make_process_buffer<'a>(
+    unsafe fn make_process_buffer<'a, S: Sample>(
         &self,
         processor: &'a mut P,
         data: *mut vst3::Steinberg::Vst::ProcessData,
-    ) -> Option<Self::ProcessBuffer<'a>>;
+    ) -> Option<Self::ProcessBuffer<'a, S>>;
 
     fn handle_events<
         E: Iterator<Item = conformal_component::events::Data> + Clone,
@@ -153,12 +259,20 @@ trait ActiveProcessorCategory<P> {
 trait ProcessorCategory {
     type Active;
 
+    /// See [`SynthOptions::uses_midi_channels`].
+    fn uses_midi_channels(&self) -> bool;
+
+    /// See [`SynthOptions::parameter_change_events`] and
+    /// [`EffectOptions::parameter_change_events`].
+    fn parameter_change_events(&self) -> bool;
+
     fn activate(&self) -> Option<Self::Active>;
 
     fn create_processor<C: Component>(
         &self,
         conformal_component: &C,
         env: &PartialProcessingEnvironment,
+        host_info: &HostInfo,
     ) -> C::Processor;
 
     unsafe fn get_bus_count(
@@ -190,12 +304,14 @@ trait ProcessorCategory {
         state: vst3::Steinberg::TBool,
     ) -> vst3::Steinberg::tresult;
 
+    /// Note that only layouts in `supported_layouts` may be accepted.
     unsafe fn set_bus_arrangements(
         &mut self,
         inputs: *mut vst3::Steinberg::Vst::SpeakerArrangement,
         num_ins: vst3::Steinberg::int32,
         outputs: *mut vst3::Steinberg::Vst::SpeakerArrangement,
         num_outs: vst3::Steinberg::int32,
+        supported_layouts: &[ChannelLayout],
     ) -> vst3::Steinberg::tresult;
 
     fn get_extra_parameters(
@@ -207,13 +323,27 @@ trait ProcessorCategory {
 impl ProcessorCategory for SynthProcessorCategory {
     type Active = ActiveSynthProcessorCategory;
 
+    fn uses_midi_channels(&self) -> bool {
+        self.uses_midi_channels
+    }
+
+    fn parameter_change_events(&self) -> bool {
+        self.parameter_change_events
+    }
+
     fn activate(&self) -> Option<Self::Active> {
-        // We can only be activated if all our buses are active.
+        // We can only be activated if our main buses are active. Auxiliary
+        // outputs are optional - if the host deactivates them, we just don't render them.
         if self.bus_activation_state.event_input_active
             && self.bus_activation_state.audio_output_active
         {
             Some(ActiveSynthProcessorCategory {
                 channel_layout: self.channel_layout,
+                aux_outputs: self
+                    .aux_outputs
+                    .iter()
+                    .map(|aux| (aux.channel_layout, aux.active))
+                    .collect(),
             })
         } else {
             None
@@ -232,8 +362,8 @@ impl ProcessorCategory for SynthProcessorCategory {
             (
                 vst3::Steinberg::Vst::MediaTypes_::kAudio,
                 vst3::Steinberg::Vst::BusDirections_::kOutput,
-            )
-            | (
+            ) => 1 + vst3::Steinberg::int32::try_from(self.aux_outputs.len()).unwrap_or(0),
+            (
                 vst3::Steinberg::Vst::MediaTypes_::kEvent,
                 vst3::Steinberg::Vst::BusDirections_::kInput,
             ) => 1,
@@ -260,10 +390,7 @@ impl ProcessorCategory for SynthProcessorCategory {
             ) => {
                 (*bus).mediaType = rtype;
                 (*bus).direction = dir;
-                (*bus).channelCount = match self.channel_layout {
-                    ChannelLayout::Mono => 1,
-                    ChannelLayout::Stereo => 2,
-                };
+                (*bus).channelCount = channel_count(self.channel_layout);
                 (*bus).busType = vst3::Steinberg::Vst::BusTypes_::kMain as i32;
                 (*bus).flags = vst3::Steinberg::Vst::BusInfo_::BusFlags_::kDefaultActive;
 
@@ -272,6 +399,30 @@ impl ProcessorCategory for SynthProcessorCategory {
 
                 vst3::Steinberg::kResultOk
             }
+            (
+                vst3::Steinberg::Vst::MediaTypes_::kAudio,
+                vst3::Steinberg::Vst::BusDirections_::kOutput,
+                _,
+            ) => {
+                let Some(aux) = self
+                    .aux_output_index(index)
+                    .map(|index| &self.aux_outputs[index])
+                else {
+                    return vst3::Steinberg::kInvalidArgument;
+                };
+                (*bus).mediaType = rtype;
+                (*bus).direction = dir;
+                (*bus).channelCount = channel_count(aux.channel_layout);
+                (*bus).busType = vst3::Steinberg::Vst::BusTypes_::kAux as i32;
+
+                // Auxiliary outputs are only active once the host routes them somewhere.
+                (*bus).flags = 0;
+
+                // fill name
+                to_utf16(aux.name, &mut (*bus).name);
+
+                vst3::Steinberg::kResultOk
+            }
             (
                 vst3::Steinberg::Vst::MediaTypes_::kEvent,
                 vst3::Steinberg::Vst::BusDirections_::kInput,
@@ -298,18 +449,18 @@ impl ProcessorCategory for SynthProcessorCategory {
         index: vst3::Steinberg::int32,
         arr: *mut vst3::Steinberg::Vst::SpeakerArrangement,
     ) -> vst3::Steinberg::tresult {
-        if index != 0 || dir != vst3::Steinberg::Vst::BusDirections_::kOutput as i32 {
+        if dir != vst3::Steinberg::Vst::BusDirections_::kOutput as i32 {
             return vst3::Steinberg::kInvalidArgument;
         }
 
-        match self.channel_layout {
-            ChannelLayout::Mono => {
-                *arr = vst3::Steinberg::Vst::SpeakerArr::kMono;
-            }
-            ChannelLayout::Stereo => {
-                *arr = vst3::Steinberg::Vst::SpeakerArr::kStereo;
-            }
-        }
+        let channel_layout = if index == 0 {
+            self.channel_layout
+        } else if let Some(index) = self.aux_output_index(index) {
+            self.aux_outputs[index].channel_layout
+        } else {
+            return vst3::Steinberg::kInvalidArgument;
+        };
+        *arr = arrangement_from_channel_layout(channel_layout);
         vst3::Steinberg::kResultOk
     }
 
@@ -333,6 +484,18 @@ impl ProcessorCategory for SynthProcessorCategory {
                 self.bus_activation_state.audio_output_active = state != 0;
                 vst3::Steinberg::kResultOk
             }
+            (
+                vst3::Steinberg::Vst::MediaTypes_::kAudio,
+                vst3::Steinberg::Vst::BusDirections_::kOutput,
+                _,
+            ) => {
+                if let Some(index) = self.aux_output_index(index) {
+                    self.aux_outputs[index].active = state != 0;
+                    vst3::Steinberg::kResultOk
+                } else {
+                    vst3::Steinberg::kInvalidArgument
+                }
+            }
             (
                 vst3::Steinberg::Vst::MediaTypes_::kEvent,
                 vst3::Steinberg::Vst::BusDirections_::kInput,
@@ -349,8 +512,9 @@ impl ProcessorCategory for SynthProcessorCategory {
         &self,
         conformal_component: &C,
         env: &PartialProcessingEnvironment,
+        host_info: &HostInfo,
     ) -> C::Processor {
-        conformal_component.create_processor(&make_env(env, self.channel_layout))
+        conformal_component.create_processor(&make_env(env, self.channel_layout, host_info))
     }
 
     unsafe fn set_bus_arrangements(
@@ -359,20 +523,28 @@ impl ProcessorCategory for SynthProcessorCategory {
         num_ins: vst3::Steinberg::int32,
         outputs: *mut vst3::Steinberg::Vst::SpeakerArrangement,
         num_outs: vst3::Steinberg::int32,
+        supported_layouts: &[ChannelLayout],
     ) -> vst3::Steinberg::tresult {
-        if num_ins != 0 || num_outs != 1 {
+        let num_buses = 1 + self.aux_outputs.len();
+        if num_ins != 0 || usize::try_from(num_outs) != Ok(num_buses) {
             return vst3::Steinberg::kInvalidArgument;
         }
-        match *outputs {
-            vst3::Steinberg::Vst::SpeakerArr::kMono => {
-                self.channel_layout = ChannelLayout::Mono;
-                vst3::Steinberg::kResultTrue
-            }
-            vst3::Steinberg::Vst::SpeakerArr::kStereo => {
-                self.channel_layout = ChannelLayout::Stereo;
+        let layouts = std::slice::from_raw_parts(outputs, num_buses)
+            .iter()
+            .map(|arrangement| {
+                channel_layout_from_arrangement(*arrangement)
+                    .filter(|layout| supported_layouts.contains(layout))
+            })
+            .collect::<Option<Vec<_>>>();
+        match layouts {
+            Some(layouts) => {
+                self.channel_layout = layouts[0];
+                for (aux, layout) in self.aux_outputs.iter_mut().zip(&layouts[1..]) {
+                    aux.channel_layout = *layout;
+                }
                 vst3::Steinberg::kResultTrue
             }
-            _ => vst3::Steinberg::kResultFalse,
+            None => vst3::Steinberg::kResultFalse,
         }
     }
 
@@ -380,9 +552,9 @@ impl ProcessorCategory for SynthProcessorCategory {
         &self,
         host_info: &HostInfo,
     ) -> impl Iterator<Item = conformal_component::parameters::Info> + Clone {
+        let support_mpe_quirks = mpe_quirks::should_support(host_info, self.uses_midi_channels);
         CONTROLLER_PARAMETERS.iter().map(Into::into).chain(
-            mpe_quirks::parameters()
-                .filter(|_| mpe_quirks::should_support(host_info) == Support::SupportQuirks),
+            mpe_quirks::parameters().filter(move |_| support_mpe_quirks == Support::SupportQuirks),
         )
     }
 }
@@ -391,39 +563,113 @@ impl ProcessorCategory for SynthProcessorCategory {
 struct EffectBusActivationState {
     audio_input_active: bool,
     audio_output_active: bool,
+    event_input_active: bool,
+}
+
+/// Effects and analyzers have the same busses - a single audio input
+/// and an output with a matching layout, and optionally an event input
+/// (see [`EffectOptions::event_input`]). They differ only in how they
+/// process audio once active, which is captured by this trait.
+trait EffectKind {
+    type Active;
+
+    /// `events` is true if the effect has an event input and the host activated it.
+    fn activate(&self, channel_layout: ChannelLayout, events: bool) -> Self::Active;
 }
 
 #[derive(Debug)]
-struct EffectProcessorCategory {
+struct EffectProcessorCategory<K> {
     channel_layout: ChannelLayout,
     bus_activation_state: EffectBusActivationState,
+    kind: K,
+
+    /// Whether we declare an event input bus.
+    event_input: bool,
+
+    /// Whether to interleave parameter changes into the events.
+    parameter_change_events: bool,
 }
 
-impl Default for EffectProcessorCategory {
-    fn default() -> Self {
+impl<K> EffectProcessorCategory<K> {
+    fn new(kind: K, options: EffectOptions) -> Self {
         EffectProcessorCategory {
             channel_layout: ChannelLayout::Stereo,
-            bus_activation_state: Default::default(),
+            bus_activation_state: EffectBusActivationState {
+                // Our event input is default-active, so hosts that don't care about
+                // it may not bother activating it.
+                event_input_active: true,
+                ..Default::default()
+            },
+            kind,
+            event_input: options.event_input,
+            parameter_change_events: options.parameter_change_events,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct ProcessingEffectKind {
+    bypass_id: &'static str,
+}
+
+impl EffectKind for ProcessingEffectKind {
+    type Active = ActiveEffectProcessorCategory;
+
+    fn activate(&self, channel_layout: ChannelLayout, events: bool) -> Self::Active {
+        ActiveEffectProcessorCategory {
+            channel_layout,
+            bypass_id: self.bypass_id,
+            events,
         }
     }
 }
 
+#[derive(Debug)]
+struct AnalyzerEffectKind;
+
+impl EffectKind for AnalyzerEffectKind {
+    type Active = ActiveAnalyzerProcessorCategory;
+
+    fn activate(&self, channel_layout: ChannelLayout, _events: bool) -> Self::Active {
+        ActiveAnalyzerProcessorCategory { channel_layout }
+    }
+}
+
 #[derive(Debug)]
 struct ActiveEffectProcessorCategory {
     channel_layout: ChannelLayout,
+    bypass_id: &'static str,
+
+    /// Whether to deliver events to the effect.
+    events: bool,
 }
 
-impl ProcessorCategory for EffectProcessorCategory {
-    type Active = ActiveEffectProcessorCategory;
+#[derive(Debug)]
+struct ActiveAnalyzerProcessorCategory {
+    channel_layout: ChannelLayout,
+}
+
+impl<K: EffectKind> ProcessorCategory for EffectProcessorCategory<K> {
+    type Active = K::Active;
+
+    fn uses_midi_channels(&self) -> bool {
+        false
+    }
+
+    fn parameter_change_events(&self) -> bool {
+        self.parameter_change_events
+    }
 
     fn activate(&self) -> Option<Self::Active> {
-        // We can only be activated if all our buses are active.
+        // We can only be activated if all our audio buses are active. Our event
+        // input is optional - if the host deactivates it, we just don't deliver events.
         if self.bus_activation_state.audio_input_active
             && self.bus_activation_state.audio_output_active
         {
-            Some(ActiveEffectProcessorCategory {
-                channel_layout: self.channel_layout,
-            })
+            Some(self.kind.activate(
+                self.channel_layout,
+                self.event_input && self.bus_activation_state.event_input_active,
+            ))
         } else {
             None
         }
@@ -433,8 +679,9 @@ impl ProcessorCategory for EffectProcessorCategory {
         &self,
         conformal_component: &C,
         env: &PartialProcessingEnvironment,
+        host_info: &HostInfo,
     ) -> C::Processor {
-        conformal_component.create_processor(&make_env(env, self.channel_layout))
+        conformal_component.create_processor(&make_env(env, self.channel_layout, host_info))
     }
 
     unsafe fn get_bus_count(
@@ -451,6 +698,10 @@ impl ProcessorCategory for EffectProcessorCategory {
                 vst3::Steinberg::Vst::BusDirections_::kOutput
                 | vst3::Steinberg::Vst::BusDirections_::kInput,
             ) => 1,
+            (
+                vst3::Steinberg::Vst::MediaTypes_::kEvent,
+                vst3::Steinberg::Vst::BusDirections_::kInput,
+            ) if self.event_input => 1,
             _ => 0,
         }
     }
@@ -474,10 +725,7 @@ impl ProcessorCategory for EffectProcessorCategory {
             ) => {
                 (*bus).mediaType = rtype;
                 (*bus).direction = dir;
-                (*bus).channelCount = match self.channel_layout {
-                    ChannelLayout::Mono => 1,
-                    ChannelLayout::Stereo => 2,
-                };
+                (*bus).channelCount = channel_count(self.channel_layout);
                 (*bus).busType = vst3::Steinberg::Vst::BusTypes_::kMain as i32;
                 (*bus).flags = vst3::Steinberg::Vst::BusInfo_::BusFlags_::kDefaultActive;
 
@@ -493,10 +741,7 @@ impl ProcessorCategory for EffectProcessorCategory {
             ) => {
                 (*bus).mediaType = rtype;
                 (*bus).direction = dir;
-                (*bus).channelCount = match self.channel_layout {
-                    ChannelLayout::Mono => 1,
-                    ChannelLayout::Stereo => 2,
-                };
+                (*bus).channelCount = channel_count(self.channel_layout);
                 (*bus).busType = vst3::Steinberg::Vst::BusTypes_::kMain as i32;
                 (*bus).flags = vst3::Steinberg::Vst::BusInfo_::BusFlags_::kDefaultActive;
 
@@ -505,6 +750,22 @@ impl ProcessorCategory for EffectProcessorCategory {
 
                 vst3::Steinberg::kResultOk
             }
+            (
+                vst3::Steinberg::Vst::MediaTypes_::kEvent,
+                vst3::Steinberg::Vst::BusDirections_::kInput,
+                0,
+            ) if self.event_input => {
+                (*bus).mediaType = rtype;
+                (*bus).direction = dir;
+                (*bus).channelCount = 1;
+                (*bus).busType = vst3::Steinberg::Vst::BusTypes_::kMain as i32;
+                (*bus).flags = vst3::Steinberg::Vst::BusInfo_::BusFlags_::kDefaultActive;
+
+                // Fill name
+                to_utf16("Event In", &mut (*bus).name);
+
+                vst3::Steinberg::kResultOk
+            }
             _ => vst3::Steinberg::kInvalidArgument,
         }
     }
@@ -519,14 +780,7 @@ impl ProcessorCategory for EffectProcessorCategory {
             return vst3::Steinberg::kInvalidArgument;
         }
 
-        match self.channel_layout {
-            ChannelLayout::Mono => {
-                *arr = vst3::Steinberg::Vst::SpeakerArr::kMono;
-            }
-            ChannelLayout::Stereo => {
-                *arr = vst3::Steinberg::Vst::SpeakerArr::kStereo;
-            }
-        }
+        *arr = arrangement_from_channel_layout(self.channel_layout);
         vst3::Steinberg::kResultOk
     }
 
@@ -558,6 +812,14 @@ impl ProcessorCategory for EffectProcessorCategory {
                 self.bus_activation_state.audio_input_active = state != 0;
                 vst3::Steinberg::kResultOk
             }
+            (
+                vst3::Steinberg::Vst::MediaTypes_::kEvent,
+                vst3::Steinberg::Vst::BusDirections_::kInput,
+                0,
+            ) if self.event_input => {
+                self.bus_activation_state.event_input_active = state != 0;
+                vst3::Steinberg::kResultOk
+            }
             _ => vst3::Steinberg::kInvalidArgument,
         }
     }
@@ -568,21 +830,14 @@ impl ProcessorCategory for EffectProcessorCategory {
         num_ins: vst3::Steinberg::int32,
         outputs: *mut vst3::Steinberg::Vst::SpeakerArrangement,
         num_outs: vst3::Steinberg::int32,
+        supported_layouts: &[ChannelLayout],
     ) -> vst3::Steinberg::tresult {
         if num_ins != 1 || num_outs != 1 {
             return vst3::Steinberg::kInvalidArgument;
         }
-        match *inputs {
-            vst3::Steinberg::Vst::SpeakerArr::kMono => {
-                self.channel_layout = ChannelLayout::Mono;
-                if *inputs == *outputs {
-                    vst3::Steinberg::kResultTrue
-                } else {
-                    vst3::Steinberg::kResultFalse
-                }
-            }
-            vst3::Steinberg::Vst::SpeakerArr::kStereo => {
-                self.channel_layout = ChannelLayout::Stereo;
+        match channel_layout_from_arrangement(*inputs) {
+            Some(layout) if supported_layouts.contains(&layout) => {
+                self.channel_layout = layout;
                 if *inputs == *outputs {
                     vst3::Steinberg::kResultTrue
                 } else {
@@ -601,30 +856,39 @@ impl ProcessorCategory for EffectProcessorCategory {
     }
 }
 
-struct EffectProcessBuffer<'a, P> {
+struct EffectProcessBuffer<'a, P, S> {
     processor: &'a mut P,
-    input: UnsafeBufferFromRaw,
-    output: UnsafeMutBufferFromRaw,
+    input: UnsafeBufferFromRaw<S>,
+    output: UnsafeMutBufferFromRaw<S>,
+    bypass_id: &'static str,
+    events: bool,
 }
 
-impl<'a, P: Effect> ProcessBuffer for EffectProcessBuffer<'a, P> {
-    fn process<E: IntoIterator<Item = Event> + Clone, Parameters: BufferStates>(
+impl<'a, P: Effect, S: Sample> ProcessBuffer for EffectProcessBuffer<'a, P, S> {
+    fn process<E: Iterator<Item = Event> + Clone, Parameters: BufferStates>(
         &mut self,
-        _e: Events<E>,
+        e: Events<E>,
         p: Parameters,
     ) {
-        self.processor.process(p, &self.input, &mut self.output);
+        let p = WithBypassAlias::new(p, self.bypass_id);
+        S::process_effect(
+            self.processor,
+            self.events.then_some(e),
+            p,
+            &self.input,
+            &mut self.output,
+        );
     }
 }
 
 impl<P: Effect> ActiveProcessorCategory<P> for ActiveEffectProcessorCategory {
-    type ProcessBuffer<'a> = EffectProcessBuffer<'a, P> where P: 'a;
+    type ProcessBuffer<'a, S: Sample> = EffectProcessBuffer<'a, P, S> where P: 'a;
 
-    unsafe fn make_process_buffer<'a>(
+    unsafe fn make_process_buffer<'a, S: Sample>(
         &self,
         processor: &'a mut P,
         data: *mut vst3::Steinberg::Vst::ProcessData,
-    ) -> Option<Self::ProcessBuffer<'a>> {
+    ) -> Option<Self::ProcessBuffer<'a, S>> {
         if (*data).numOutputs != 1 {
             return None;
         }
@@ -634,12 +898,86 @@ impl<P: Effect> ActiveProcessorCategory<P> for ActiveEffectProcessorCategory {
         Some(EffectProcessBuffer {
             processor,
             input: UnsafeBufferFromRaw {
-                ptr: (*(*data).inputs).__field0.channelBuffers32,
+                ptr: S::channel_buffers((*data).inputs),
                 channel_layout: self.channel_layout,
                 num_frames: (*data).numSamples as usize,
             },
             output: UnsafeMutBufferFromRaw {
-                ptr: (*(*data).outputs).__field0.channelBuffers32,
+                ptr: S::channel_buffers((*data).outputs),
+                channel_layout: self.channel_layout,
+                num_frames: (*data).numSamples as usize,
+            },
+            bypass_id: self.bypass_id,
+            events: self.events,
+        })
+    }
+
+    fn handle_events<
+        E: Iterator<Item = conformal_component::events::Data> + Clone,
+        Parameters: conformal_component::parameters::States,
+    >(
+        &self,
+        processor: &mut P,
+        e: E,
+        p: Parameters,
+    ) {
+        let p = WithBypassAlias::new(p, self.bypass_id);
+        if self.events {
+            processor.handle_events(e, p);
+        } else {
+            processor.handle_parameters(p);
+        }
+    }
+}
+
+struct AnalyzerProcessBuffer<'a, P, S> {
+    processor: &'a mut P,
+    input: UnsafeBufferFromRaw<S>,
+    output: UnsafeMutBufferFromRaw<S>,
+}
+
+impl<'a, P: Analyzer, S: Sample> ProcessBuffer for AnalyzerProcessBuffer<'a, P, S> {
+    fn process<E: IntoIterator<Item = Event> + Clone, Parameters: BufferStates>(
+        &mut self,
+        _e: Events<E>,
+        p: Parameters,
+    ) {
+        S::analyze(self.processor, p, &self.input);
+
+        // Hosts may process in-place, in which case there's nothing to copy.
+        for channel in 0..self.input.num_channels() {
+            let input = self.input.channel(channel);
+            let output = self.output.channel_mut(channel);
+            if input.as_ptr() != output.as_ptr() {
+                output.copy_from_slice(input);
+            }
+        }
+    }
+}
+
+impl<P: Analyzer> ActiveProcessorCategory<P> for ActiveAnalyzerProcessorCategory {
+    type ProcessBuffer<'a, S: Sample> = AnalyzerProcessBuffer<'a, P, S> where P: 'a;
+
+    unsafe fn make_process_buffer<'a, S: Sample>(
+        &self,
+        processor: &'a mut P,
+        data: *mut vst3::Steinberg::Vst::ProcessData,
+    ) -> Option<Self::ProcessBuffer<'a, S>> {
+        if (*data).numOutputs != 1 {
+            return None;
+        }
+        if (*data).numInputs != 1 {
+            return None;
+        }
+        Some(AnalyzerProcessBuffer {
+            processor,
+            input: UnsafeBufferFromRaw {
+                ptr: S::channel_buffers((*data).inputs),
+                channel_layout: self.channel_layout,
+                num_frames: (*data).numSamples as usize,
+            },
+            output: UnsafeMutBufferFromRaw {
+                ptr: S::channel_buffers((*data).outputs),
                 channel_layout: self.channel_layout,
                 num_frames: (*data).numSamples as usize,
             },
@@ -665,16 +1003,95 @@ struct PartialProcessingEnvironment {
     processing_mode: ProcessingMode,
 }
 
+fn channel_layout_from_arrangement(
+    arrangement: vst3::Steinberg::Vst::SpeakerArrangement,
+) -> Option<ChannelLayout> {
+    match arrangement {
+        vst3::Steinberg::Vst::SpeakerArr::kMono => Some(ChannelLayout::Mono),
+        vst3::Steinberg::Vst::SpeakerArr::kStereo => Some(ChannelLayout::Stereo),
+        vst3::Steinberg::Vst::SpeakerArr::k40Music => Some(ChannelLayout::Quad),
+        vst3::Steinberg::Vst::SpeakerArr::k51 => Some(ChannelLayout::FivePointOne),
+        vst3::Steinberg::Vst::SpeakerArr::k71Music => Some(ChannelLayout::SevenPointOne),
+        _ => None,
+    }
+}
+
+fn arrangement_from_channel_layout(
+    layout: ChannelLayout,
+) -> vst3::Steinberg::Vst::SpeakerArrangement {
+    match layout {
+        ChannelLayout::Mono => vst3::Steinberg::Vst::SpeakerArr::kMono,
+        ChannelLayout::Stereo => vst3::Steinberg::Vst::SpeakerArr::kStereo,
+        ChannelLayout::Quad => vst3::Steinberg::Vst::SpeakerArr::k40Music,
+        ChannelLayout::FivePointOne => vst3::Steinberg::Vst::SpeakerArr::k51,
+        ChannelLayout::SevenPointOne => vst3::Steinberg::Vst::SpeakerArr::k71Music,
+    }
+}
+
+#[allow(clippy::cast_possible_truncation)]
+fn channel_count(layout: ChannelLayout) -> vst3::Steinberg::int32 {
+    layout.num_channels() as vst3::Steinberg::int32
+}
+
+/// Converts a processor's tail length into the form VST3 hosts expect.
+///
+/// Tails too long to represent are treated as infinite.
+fn to_vst_tail_samples(tail_samples: usize) -> vst3::Steinberg::uint32 {
+    if tail_samples == INFINITE_TAIL_SAMPLES {
+        vst3::Steinberg::Vst::kInfiniteTail
+    } else {
+        tail_samples
+            .try_into()
+            .unwrap_or(vst3::Steinberg::Vst::kInfiniteTail)
+    }
+}
+
+/// Converts the host's process context into a [`Transport`].
+///
+/// # Safety
+///
+/// `context` must be null or point to a valid `ProcessContext`.
+unsafe fn transport_from_context(
+    context: *const vst3::Steinberg::Vst::ProcessContext,
+) -> Transport {
+    use vst3::Steinberg::Vst::ProcessContext_::{StatesAndFlags, StatesAndFlags_};
+
+    let Some(context) = context.as_ref() else {
+        return Transport::default();
+    };
+    let has = |flag: StatesAndFlags| context.state & (flag as u32) != 0;
+    Transport {
+        tempo: has(StatesAndFlags_::kTempoValid).then_some(context.tempo),
+        time_signature: if has(StatesAndFlags_::kTimeSigValid) {
+            u32::try_from(context.timeSigNumerator)
+                .ok()
+                .zip(u32::try_from(context.timeSigDenominator).ok())
+                .map(|(numerator, denominator)| TimeSignature {
+                    numerator,
+                    denominator,
+                })
+        } else {
+            None
+        },
+        position: has(StatesAndFlags_::kProjectTimeMusicValid).then_some(context.projectTimeMusic),
+        bar_start: has(StatesAndFlags_::kBarPositionValid).then_some(context.barPositionMusic),
+        playing: Some(has(StatesAndFlags_::kPlaying)),
+        recording: Some(has(StatesAndFlags_::kRecording)),
+    }
+}
+
 fn make_env(
     partial: &PartialProcessingEnvironment,
     layout: ChannelLayout,
+    host_info: &HostInfo,
 ) -> ProcessingEnvironment {
-    ProcessingEnvironment {
-        sampling_rate: partial.sampling_rate,
-        max_samples_per_process_call: partial.max_samples_per_process_call,
-        processing_mode: partial.processing_mode,
-        channel_layout: layout,
-    }
+    ProcessingEnvironment::new(
+        partial.sampling_rate,
+        partial.max_samples_per_process_call,
+        layout,
+        partial.processing_mode,
+    )
+    .with_host_name(host_info.name.clone())
 }
 
 /// Note that according to the VST3 spec, almost all functions must be called
@@ -774,14 +1191,27 @@ impl<P, C, CF, PC, APC> Processor<P, C, CF, PC, APC> {
 
 impl<P, C, CF, PC, APC> IProcessContextRequirementsTrait for Processor<P, C, CF, PC, APC> {
     unsafe fn getProcessContextRequirements(&self) -> vst3::Steinberg::uint32 {
-        // We don't need any special processing context requirements, for now!
-        0
+        use vst3::Steinberg::Vst::IProcessContextRequirements_::Flags_;
+
+        // We ask for everything we surface in `Transport`.
+        (Flags_::kNeedTempo
+            | Flags_::kNeedTimeSignature
+            | Flags_::kNeedProjectTimeMusic
+            | Flags_::kNeedBarPositionMusic
+            | Flags_::kNeedTransportState) as vst3::Steinberg::uint32
     }
 }
 
+/// Creates a synth processor.
+///
+/// # Panics
+///
+/// Panics if `options` declares more than [`conformal_component::synth::MAX_AUX_OUTPUTS`]
+/// auxiliary outputs.
 pub fn create_synth<'a, CF: ComponentFactory<Component: Component<Processor: Synth>> + 'a>(
     factory: CF,
     controller_cid: ClassID,
+    options: SynthOptions,
 ) -> impl Class<
     Interfaces = (
         IPluginBase,
@@ -795,18 +1225,53 @@ pub fn create_synth<'a, CF: ComponentFactory<Component: Component<Processor: Syn
        + IProcessContextRequirementsTrait
        + IConnectionPointTrait
        + 'a {
+    assert!(
+        options.aux_outputs.len() < MAX_SYNTH_OUTPUTS,
+        "Synths can have at most {} auxiliary outputs",
+        MAX_SYNTH_OUTPUTS - 1
+    );
     Processor {
         controller_cid,
         s: Some(State::ReadyForInitialization(factory)).into(),
         host: Default::default(),
         process_context: Default::default(),
-        category: <RefCell<SynthProcessorCategory> as Default>::default(),
+        category: RefCell::new(SynthProcessorCategory::new(options)),
     }
 }
 
 pub fn create_effect<'a, CF: ComponentFactory<Component: Component<Processor: Effect>> + 'a>(
     factory: CF,
     controller_cid: ClassID,
+    bypass_id: &'static str,
+    options: EffectOptions,
+) -> impl Class<
+    Interfaces = (
+        IPluginBase,
+        IComponent,
+        IAudioProcessor,
+        IProcessContextRequirements,
+        IConnectionPoint,
+    ),
+> + IComponentTrait
+       + IAudioProcessorTrait
+       + IProcessContextRequirementsTrait
+       + IConnectionPointTrait
+       + 'a {
+    Processor {
+        controller_cid,
+        s: Some(State::ReadyForInitialization(factory)).into(),
+        host: Default::default(),
+        process_context: Default::default(),
+        category: RefCell::new(EffectProcessorCategory::new(
+            ProcessingEffectKind { bypass_id },
+            options,
+        )),
+    }
+}
+
+pub fn create_analyzer<'a, CF: ComponentFactory<Component: Component<Processor: Analyzer>> + 'a>(
+    factory: CF,
+    controller_cid: ClassID,
 ) -> impl Class<
     Interfaces = (
         IPluginBase,
@@ -825,7 +1290,10 @@ pub fn create_effect<'a, CF: ComponentFactory<Component: Component<Processor: Ef
         s: Some(State::ReadyForInitialization(factory)).into(),
         host: Default::default(),
         process_context: Default::default(),
-        category: <RefCell<EffectProcessorCategory> as Default>::default(),
+        category: RefCell::new(EffectProcessorCategory::new(
+            AnalyzerEffectKind,
+            EffectOptions::DEFAULT,
+        )),
     }
 }
 
@@ -854,6 +1322,10 @@ where
         ) {
             (State::ReadyForInitialization(factory), Some(host_info)) => {
                 let conformal_component = factory.create(&host_info);
+                let support_mpe_quirks = mpe_quirks::should_support(
+                    &host_info,
+                    self.category.borrow().uses_midi_channels(),
+                );
                 let (params_main, params_processing) = parameters::create_stores(
                     {
                         let mut infos = conformal_component.parameter_infos();
@@ -862,12 +1334,16 @@ where
                     }
                     .iter()
                     .map(Into::into),
+                    &programs::exposed_presets(conformal_component.factory_presets()),
                 );
                 let s = State::Initialized(InitializedData {
                     conformal_component,
+                    host_info: host_info.clone(),
                     params_main,
                     processing_environment: None,
                     process_context_active: false,
+                    latency_samples: 0,
+                    tail_samples: vst3::Steinberg::Vst::kNoTail,
                     factory,
                 });
 
@@ -882,7 +1358,7 @@ where
                 self.process_context.replace(ProcessContext::Inactive {
                     processing: false,
                     params: params_processing,
-                    support_mpe_quirks: mpe_quirks::should_support(&host_info),
+                    support_mpe_quirks,
                 });
                 (s, vst3::Steinberg::kResultOk)
             }
@@ -906,7 +1382,11 @@ where
                 self.process_context.replace(ProcessContext::Uninitialized);
                 vst3::Steinberg::kResultOk
             }
-            _ => vst3::Steinberg::kInvalidArgument,
+            s => {
+                // We weren't initialized, so put back whatever state we had.
+                self.s.replace(s);
+                vst3::Steinberg::kInvalidArgument
+            }
         }
     }
 }
@@ -982,8 +1462,11 @@ impl<CF: ComponentFactory<Component: Component<Processor: ProcessorT>>, PC: Proc
     unsafe fn setActive(&self, state: vst3::Steinberg::TBool) -> vst3::Steinberg::tresult {
         if let Some(State::Initialized(InitializedData {
             conformal_component,
+            host_info,
             processing_environment: Some(env),
             process_context_active,
+            latency_samples,
+            tail_samples,
             ..
         })) = self.s.borrow_mut().as_mut()
         {
@@ -1023,17 +1506,26 @@ impl<CF: ComponentFactory<Component: Component<Processor: ProcessorT>>, PC: Proc
                 }
                 (
                     ProcessContext::Inactive {
-                        params,
+                        mut params,
                         processing,
                         support_mpe_quirks,
                     },
                     true,
                 ) => {
                     if let Some(category) = self.category.borrow().activate() {
-                        let mut processor = self
-                            .category
-                            .borrow()
-                            .create_processor(conformal_component, env);
+                        params
+                            .prepare_smoothing(env.sampling_rate, env.max_samples_per_process_call);
+                        let mut processor = self.category.borrow().create_processor(
+                            conformal_component,
+                            env,
+                            host_info,
+                        );
+                        processor.prepare();
+                        *latency_samples = processor
+                            .latency_samples()
+                            .try_into()
+                            .unwrap_or(vst3::Steinberg::uint32::MAX);
+                        *tail_samples = to_vst_tail_samples(processor.tail_samples());
                         if processing {
                             processor.set_processing(true);
                         }
@@ -1048,6 +1540,11 @@ impl<CF: ComponentFactory<Component: Component<Processor: ProcessorT>>, PC: Proc
                                 } else {
                                     None
                                 },
+                                parameter_change_events: self
+                                    .category
+                                    .borrow()
+                                    .parameter_change_events(),
+                                double_precision: supports_double_precision::<CF::Component>(),
                             },
                         ));
                         *process_context_active = true;
@@ -1073,13 +1570,21 @@ impl<CF: ComponentFactory<Component: Component<Processor: ProcessorT>>, PC: Proc
     unsafe fn setState(&self, state: *mut vst3::Steinberg::IBStream) -> vst3::Steinberg::tresult {
         if let Some(State::Initialized(InitializedData {
             params_main: main_context_store,
+            conformal_component,
             ..
         })) = self.s.borrow_mut().as_mut()
         {
             if let Some(com_state) = ComRef::from_raw(state) {
                 let read = StreamRead::new(com_state);
                 if let Ok(state) = rmp_serde::from_read::<_, state::State>(read) {
-                    return match main_context_store.apply_snapshot(&state.params) {
+                    return match main_context_store.apply_snapshot(
+                        &state.params,
+                        state.version,
+                        conformal_component.state_version(),
+                        |saved_version, values| {
+                            conformal_component.upgrade_parameters(saved_version, values);
+                        },
+                    ) {
                         Ok(()) => vst3::Steinberg::kResultOk,
                         Err(parameters::SnapshotError::QueueTooFull) => {
                             // Note that right now, if we can't apply the snapshot due to the
@@ -1102,6 +1607,7 @@ impl<CF: ComponentFactory<Component: Component<Processor: ProcessorT>>, PC: Proc
     unsafe fn getState(&self, state: *mut vst3::Steinberg::IBStream) -> vst3::Steinberg::tresult {
         if let Some(State::Initialized(InitializedData {
             params_main: main_context_store,
+            conformal_component,
             ..
         })) = self.s.borrow().as_ref()
         {
@@ -1109,6 +1615,7 @@ impl<CF: ComponentFactory<Component: Component<Processor: ProcessorT>>, PC: Proc
                 let writer = StreamWrite::new(com_state);
                 if (state::State {
                     params: main_context_store.snapshot_with_tearing(),
+                    version: conformal_component.state_version(),
                 })
                 .serialize(&mut rmp_serde::Serializer::new(writer))
                 .is_ok()
@@ -1122,13 +1629,154 @@ impl<CF: ComponentFactory<Component: Component<Processor: ProcessorT>>, PC: Proc
     }
 }
 
-struct UnsafeBufferFromRaw {
-    ptr: *mut *mut f32,
+/// Whether hosts may process audio in `f64` for components of type `C`.
+fn supports_double_precision<C: Component<Processor: ProcessorT>>() -> bool {
+    <C::Processor as ProcessorT>::SUPPORTS_DOUBLE_PRECISION
+}
+
+/// A sample type that hosts may process audio in.
+///
+/// Hosts only process in `f64` if the component opted in with
+/// [`ProcessorT::SUPPORTS_DOUBLE_PRECISION`].
+trait Sample: Copy + 'static {
+    unsafe fn channel_buffers(bus: *mut vst3::Steinberg::Vst::AudioBusBuffers) -> *mut *mut Self;
+
+    fn process_effect<
+        P: Effect,
+        E: Iterator<Item = Event> + Clone,
+        Parameters: BufferStates,
+        I: Buffer<Self>,
+        O: BufferMut<Self>,
+    >(
+        processor: &mut P,
+        events: Option<Events<E>>,
+        parameters: Parameters,
+        input: &I,
+        output: &mut O,
+    );
+
+    fn process_synth<
+        P: Synth,
+        E: Iterator<Item = Event> + Clone,
+        Parameters: BufferStates,
+        O: BufferMut<Self>,
+    >(
+        processor: &mut P,
+        events: Events<E>,
+        parameters: Parameters,
+        outputs: &mut [O],
+    );
+
+    fn analyze<P: Analyzer, Parameters: BufferStates, I: Buffer<Self>>(
+        processor: &mut P,
+        parameters: Parameters,
+        input: &I,
+    );
+}
+
+impl Sample for f32 {
+    unsafe fn channel_buffers(bus: *mut vst3::Steinberg::Vst::AudioBusBuffers) -> *mut *mut Self {
+        (*bus).__field0.channelBuffers32
+    }
+
+    fn process_effect<
+        P: Effect,
+        E: Iterator<Item = Event> + Clone,
+        Parameters: BufferStates,
+        I: Buffer,
+        O: BufferMut,
+    >(
+        processor: &mut P,
+        events: Option<Events<E>>,
+        parameters: Parameters,
+        input: &I,
+        output: &mut O,
+    ) {
+        if let Some(events) = events {
+            processor.process_with_events(events, parameters, input, output);
+        } else {
+            processor.process(parameters, input, output);
+        }
+    }
+
+    fn process_synth<
+        P: Synth,
+        E: Iterator<Item = Event> + Clone,
+        Parameters: BufferStates,
+        O: BufferMut,
+    >(
+        processor: &mut P,
+        events: Events<E>,
+        parameters: Parameters,
+        outputs: &mut [O],
+    ) {
+        processor.process_buses(events, parameters, outputs);
+    }
+
+    fn analyze<P: Analyzer, Parameters: BufferStates, I: Buffer>(
+        processor: &mut P,
+        parameters: Parameters,
+        input: &I,
+    ) {
+        processor.analyze(parameters, input);
+    }
+}
+
+impl Sample for f64 {
+    unsafe fn channel_buffers(bus: *mut vst3::Steinberg::Vst::AudioBusBuffers) -> *mut *mut Self {
+        (*bus).__field0.channelBuffers64
+    }
+
+    fn process_effect<
+        P: Effect,
+        E: Iterator<Item = Event> + Clone,
+        Parameters: BufferStates,
+        I: Buffer<f64>,
+        O: BufferMut<f64>,
+    >(
+        processor: &mut P,
+        events: Option<Events<E>>,
+        parameters: Parameters,
+        input: &I,
+        output: &mut O,
+    ) {
+        if let Some(events) = events {
+            processor.process_with_events_f64(events, parameters, input, output);
+        } else {
+            processor.process_f64(parameters, input, output);
+        }
+    }
+
+    fn process_synth<
+        P: Synth,
+        E: Iterator<Item = Event> + Clone,
+        Parameters: BufferStates,
+        O: BufferMut<f64>,
+    >(
+        processor: &mut P,
+        events: Events<E>,
+        parameters: Parameters,
+        outputs: &mut [O],
+    ) {
+        processor.process_buses_f64(events, parameters, outputs);
+    }
+
+    fn analyze<P: Analyzer, Parameters: BufferStates, I: Buffer<f64>>(
+        processor: &mut P,
+        parameters: Parameters,
+        input: &I,
+    ) {
+        processor.analyze_f64(parameters, input);
+    }
+}
+
+struct UnsafeBufferFromRaw<S> {
+    ptr: *mut *mut S,
     channel_layout: ChannelLayout,
     num_frames: usize,
 }
 
-impl Buffer for UnsafeBufferFromRaw {
+impl<S> Buffer<S> for UnsafeBufferFromRaw<S> {
     fn channel_layout(&self) -> ChannelLayout {
         self.channel_layout
     }
@@ -1137,18 +1785,19 @@ impl Buffer for UnsafeBufferFromRaw {
         self.num_frames
     }
 
-    fn channel(&self, channel: usize) -> &[f32] {
+    fn channel(&self, channel: usize) -> &[S] {
         unsafe { std::slice::from_raw_parts(*self.ptr.add(channel), self.num_frames) }
     }
 }
 
-struct UnsafeMutBufferFromRaw {
-    ptr: *mut *mut f32,
+/// Note that when `num_frames` is zero, `ptr` is never read, so it may be null.
+struct UnsafeMutBufferFromRaw<S> {
+    ptr: *mut *mut S,
     channel_layout: ChannelLayout,
     num_frames: usize,
 }
 
-impl Buffer for UnsafeMutBufferFromRaw {
+impl<S> Buffer<S> for UnsafeMutBufferFromRaw<S> {
     fn channel_layout(&self) -> ChannelLayout {
         self.channel_layout
     }
@@ -1157,13 +1806,19 @@ impl Buffer for UnsafeMutBufferFromRaw {
         self.num_frames
     }
 
-    fn channel(&self, channel: usize) -> &[f32] {
+    fn channel(&self, channel: usize) -> &[S] {
+        if self.num_frames == 0 {
+            return &[];
+        }
         unsafe { std::slice::from_raw_parts(*self.ptr.add(channel), self.num_frames) }
     }
 }
 
-impl BufferMut for UnsafeMutBufferFromRaw {
-    fn channel_mut(&mut self, channel: usize) -> &mut [f32] {
+impl<S> BufferMut<S> for UnsafeMutBufferFromRaw<S> {
+    fn channel_mut(&mut self, channel: usize) -> &mut [S] {
+        if self.num_frames == 0 {
+            return &mut [];
+        }
         unsafe { std::slice::from_raw_parts_mut(*self.ptr.add(channel), self.num_frames) }
     }
 }
@@ -1175,22 +1830,26 @@ pub const NOTE_EXPRESSION_AFTERTOUCH_TYPE_ID: vst3::Steinberg::Vst::NoteExpressi
 
 mod events;
 
-struct SynthProcessBuffer<'a, P> {
+struct SynthProcessBuffer<'a, P, S> {
     synth: &'a mut P,
-    output: UnsafeMutBufferFromRaw,
+
+    /// The main output followed by the auxiliary outputs. Only the first
+    /// `num_outputs` of these are used.
+    outputs: [UnsafeMutBufferFromRaw<S>; MAX_SYNTH_OUTPUTS],
+    num_outputs: usize,
 }
 
 trait ProcessBuffer {
     fn process<E: Iterator<Item = Event> + Clone, P: BufferStates>(&mut self, e: Events<E>, p: P);
 }
 
-impl<'a, P: Synth> ProcessBuffer for SynthProcessBuffer<'a, P> {
+impl<'a, P: Synth, S: Sample> ProcessBuffer for SynthProcessBuffer<'a, P, S> {
     fn process<E: Iterator<Item = Event> + Clone, Parameters: BufferStates>(
         &mut self,
         e: Events<E>,
         p: Parameters,
     ) {
-        self.synth.process(e, p, &mut self.output);
+        S::process_synth(self.synth, e, p, &mut self.outputs[..self.num_outputs]);
     }
 }
 
@@ -1201,6 +1860,7 @@ trait InternalProcessHelper<H> {
         params: &mut parameters::ProcessingStore,
         data: *mut vst3::Steinberg::Vst::ProcessData,
         mpe_quirks: Option<&mut mpe_quirks::State>,
+        parameter_change_events: bool,
         num_frames: usize,
     ) -> vst3::Steinberg::tresult;
 }
@@ -1214,31 +1874,41 @@ impl<H: ProcessBuffer, I: Iterator<Item = conformal_component::events::Event> +
         params: &mut parameters::ProcessingStore,
         data: *mut vst3::Steinberg::Vst::ProcessData,
         mpe_quirks: Option<&mut mpe_quirks::State>,
+        parameter_change_events: bool,
         num_frames: usize,
     ) -> vst3::Steinberg::tresult {
         if let Some(com_changes) = vst3::ComRef::from_raw((*data).inputParameterChanges) {
-            if let Some(buffer_states) =
-                parameters::param_changes_from_vst3(com_changes, params, num_frames)
-            {
+            if let Some((buffer_states, change_events)) = parameters::param_changes_from_vst3(
+                com_changes,
+                params,
+                num_frames,
+                parameter_change_events,
+            ) {
+                let Some(events) = Events::new(
+                    events::with_parameter_changes(self.into_iter(), change_events),
+                    num_frames,
+                ) else {
+                    return vst3::Steinberg::kInvalidArgument;
+                };
                 if let Some(mpe_quirks) = mpe_quirks {
                     let buffer_states_clone = buffer_states.clone();
-                    let events = add_mpe_quirk_events_buffer(
-                        self.clone().into_iter(),
+                    let quirk_events = add_mpe_quirk_events_buffer(
+                        events.clone().into_iter(),
                         mpe_quirks.clone(),
                         &buffer_states_clone,
                         num_frames,
                     );
-                    helper.process(events, buffer_states.clone());
-                    update_mpe_quirk_events_buffer(self.into_iter(), mpe_quirks, &buffer_states);
+                    helper.process(quirk_events, buffer_states.clone());
+                    update_mpe_quirk_events_buffer(events.into_iter(), mpe_quirks, &buffer_states);
                 } else {
-                    helper.process(self, buffer_states);
+                    helper.process(events, buffer_states);
                 }
                 vst3::Steinberg::kResultOk
             } else {
                 vst3::Steinberg::kInvalidArgument
             }
         } else {
-            let buffer_states = parameters::ExistingBufferStates::new(params);
+            let buffer_states = parameters::ExistingBufferStates::new(params, num_frames);
             helper.process(self.clone(), buffer_states.clone());
             if let Some(mpe_quirks) = mpe_quirks {
                 update_mpe_quirk_events_buffer(self.into_iter(), mpe_quirks, &buffer_states);
@@ -1267,30 +1937,36 @@ impl<
         params: &mut parameters::ProcessingStore,
         data: *mut vst3::Steinberg::Vst::ProcessData,
         mpe_quirks: Option<&mut mpe_quirks::State>,
+        parameter_change_events: bool,
         _: usize,
     ) -> vst3::Steinberg::tresult {
         if let Some(param_changes) = ComRef::from_raw((*data).inputParameterChanges) {
-            if let Some((change_status, param_states)) =
-                parameters::no_audio_param_changes_from_vst3(param_changes, params)
+            if let Some((change_status, param_states, change_events)) =
+                parameters::no_audio_param_changes_from_vst3(
+                    param_changes,
+                    params,
+                    parameter_change_events,
+                )
             {
+                let events = change_events.map(|event| event.data).chain(self);
                 if change_status == parameters::ChangesStatus::Changes || !helper.events_empty {
                     if let Some(mpe_quirks) = mpe_quirks {
                         let param_states_clone = param_states.clone();
-                        let events = add_mpe_quirk_events_no_audio(
-                            self.clone(),
+                        let quirk_events = add_mpe_quirk_events_no_audio(
+                            events.clone(),
                             mpe_quirks.clone(),
                             &param_states_clone,
                         );
                         helper.category.handle_events(
                             helper.processor,
-                            events,
+                            quirk_events,
                             param_states.clone(),
                         );
-                        update_mpe_quirk_events_no_audio(self, mpe_quirks, &param_states);
+                        update_mpe_quirk_events_no_audio(events, mpe_quirks, &param_states);
                     } else {
                         helper
                             .category
-                            .handle_events(helper.processor, self, param_states);
+                            .handle_events(helper.processor, events, param_states);
                     }
                 }
                 return vst3::Steinberg::kResultOk;
@@ -1307,24 +1983,47 @@ impl<
 }
 
 impl<P: Synth> ActiveProcessorCategory<P> for ActiveSynthProcessorCategory {
-    type ProcessBuffer<'a> = SynthProcessBuffer<'a, P> where P: 'a;
+    type ProcessBuffer<'a, S: Sample> = SynthProcessBuffer<'a, P, S> where P: 'a;
 
-    unsafe fn make_process_buffer<'a>(
+    unsafe fn make_process_buffer<'a, S: Sample>(
         &self,
         processor: &'a mut P,
         data: *mut vst3::Steinberg::Vst::ProcessData,
-    ) -> Option<Self::ProcessBuffer<'a>> {
-        if (*data).numOutputs != 1 {
+    ) -> Option<Self::ProcessBuffer<'a, S>> {
+        let num_outputs = 1 + self.aux_outputs.len();
+        if usize::try_from((*data).numOutputs) != Ok(num_outputs) {
             return None;
         }
 
+        let num_frames = (*data).numSamples as usize;
         Some(SynthProcessBuffer {
             synth: processor,
-            output: UnsafeMutBufferFromRaw {
-                ptr: (*(*data).outputs).__field0.channelBuffers32,
-                channel_layout: self.channel_layout,
-                num_frames: (*data).numSamples as usize,
-            },
+            outputs: std::array::from_fn(|index| {
+                let (channel_layout, active) = if index == 0 {
+                    (self.channel_layout, true)
+                } else {
+                    self.aux_outputs
+                        .get(index - 1)
+                        .copied()
+                        .unwrap_or((ChannelLayout::Stereo, false))
+                };
+                if active {
+                    UnsafeMutBufferFromRaw {
+                        ptr: S::channel_buffers((*data).outputs.add(index)),
+                        channel_layout,
+                        num_frames,
+                    }
+                } else {
+                    // Inactive outputs get a buffer with no frames, so we never
+                    // touch whatever the host passed for them.
+                    UnsafeMutBufferFromRaw {
+                        ptr: std::ptr::null_mut(),
+                        channel_layout,
+                        num_frames: 0,
+                    }
+                }
+            }),
+            num_outputs,
         })
     }
 
@@ -1356,6 +2055,7 @@ impl<
     ) -> vst3::Steinberg::tresult {
         if let Some(State::Initialized(InitializedData {
             process_context_active,
+            conformal_component,
             ..
         })) = self.s.borrow().as_ref()
         {
@@ -1363,9 +2063,14 @@ impl<
             if *process_context_active {
                 return vst3::Steinberg::kInvalidArgument;
             }
-            self.category
-                .borrow_mut()
-                .set_bus_arrangements(inputs, num_ins, outputs, num_outs)
+
+            self.category.borrow_mut().set_bus_arrangements(
+                inputs,
+                num_ins,
+                outputs,
+                num_outs,
+                &conformal_component.supported_channel_layouts(),
+            )
         } else {
             vst3::Steinberg::kInvalidArgument
         }
@@ -1388,15 +2093,28 @@ impl<
         &self,
         symbolic_sample_size: vst3::Steinberg::int32,
     ) -> vst3::Steinberg::tresult {
-        if symbolic_sample_size as u32 == vst3::Steinberg::Vst::SymbolicSampleSizes_::kSample32 {
-            vst3::Steinberg::kResultTrue
-        } else {
-            vst3::Steinberg::kResultFalse
+        match symbolic_sample_size as u32 {
+            vst3::Steinberg::Vst::SymbolicSampleSizes_::kSample32 => vst3::Steinberg::kResultTrue,
+            vst3::Steinberg::Vst::SymbolicSampleSizes_::kSample64 => {
+                if supports_double_precision::<CF::Component>() {
+                    vst3::Steinberg::kResultTrue
+                } else {
+                    vst3::Steinberg::kResultFalse
+                }
+            }
+            _ => vst3::Steinberg::kResultFalse,
         }
     }
 
     unsafe fn getLatencySamples(&self) -> vst3::Steinberg::uint32 {
-        0
+        if let Some(State::Initialized(InitializedData {
+            latency_samples, ..
+        })) = self.s.borrow().as_ref()
+        {
+            *latency_samples
+        } else {
+            0
+        }
     }
 
     unsafe fn setupProcessing(
@@ -1460,6 +2178,8 @@ impl<
             }
 
             pd.params.sync_from_main_thread();
+            pd.processor
+                .set_transport(&transport_from_context((*data).processContext));
             let num_frames = (*data).numSamples as usize;
 
             if num_frames == 0 {
@@ -1482,6 +2202,7 @@ impl<
                             &mut pd.params,
                             data,
                             pd.mpe_quirks.as_mut(),
+                            pd.parameter_change_events,
                             0,
                         );
                     }
@@ -1496,50 +2217,21 @@ impl<
                         &mut pd.params,
                         data,
                         pd.mpe_quirks.as_mut(),
+                        pd.parameter_change_events,
                         0,
                     );
                 }
                 // If we got here, some pre-condition of the parameters was not met by the host
                 return vst3::Steinberg::kInvalidArgument;
             }
-            if (*data).symbolicSampleSize
-                != vst3::Steinberg::Vst::SymbolicSampleSizes_::kSample32 as i32
-            {
-                return vst3::Steinberg::kInvalidArgument;
-            }
-
-            if let Some(process_buffer) = pd.category.make_process_buffer(&mut pd.processor, data) {
-                if let Some(input_events) = ComRef::from_raw((*data).inputEvents) {
-                    if let Some(events) = Events::new(
-                        events::event_iterator(
-                            input_events,
-                            if pd.mpe_quirks.is_some() {
-                                Support::SupportQuirks
-                            } else {
-                                Support::DoNotSupportQuirks
-                            },
-                        ),
-                        num_frames,
-                    ) {
-                        return events.do_process(
-                            process_buffer,
-                            &mut pd.params,
-                            data,
-                            pd.mpe_quirks.as_mut(),
-                            num_frames,
-                        );
-                    }
-                } else {
-                    return Events::new(std::iter::empty(), num_frames)
-                        .unwrap()
-                        .do_process(
-                            process_buffer,
-                            &mut pd.params,
-                            data,
-                            pd.mpe_quirks.as_mut(),
-                            num_frames,
-                        );
+            match (*data).symbolicSampleSize as u32 {
+                vst3::Steinberg::Vst::SymbolicSampleSizes_::kSample32 => {
+                    return pd.process_audio::<f32>(data, num_frames);
+                }
+                vst3::Steinberg::Vst::SymbolicSampleSizes_::kSample64 if pd.double_precision => {
+                    return pd.process_audio::<f64>(data, num_frames);
                 }
+                _ => {}
             }
         }
         // If we got here, some invariant was not met by the host (i.e., Events was misformated, or wrong audio format.)
@@ -1547,7 +2239,13 @@ impl<
     }
 
     unsafe fn getTailSamples(&self) -> vst3::Steinberg::uint32 {
-        0
+        if let Some(State::Initialized(InitializedData { tail_samples, .. })) =
+            self.s.borrow().as_ref()
+        {
+            *tail_samples
+        } else {
+            vst3::Steinberg::Vst::kNoTail
+        }
     }
 }
 
@@ -14,19 +14,26 @@ use vst3::{
     },
 };
 
-use crate::parameters::{convert_enum, convert_numeric, convert_switch};
+use conformal_component::events::{Data, Event, ParameterChangeData};
 use conformal_component::parameters as cp;
+use conformal_component::Preset;
 use conformal_core::parameters as cc;
+use conformal_core::parameters::normalization::{convert_enum, convert_numeric, convert_switch};
+use smoothing::Smoothers;
 
 use conformal_component::parameters::{
-    BufferState, BufferStates, EnumBufferState, NumericBufferState, PiecewiseLinearCurve,
-    PiecewiseLinearCurvePoint, States as ParameterStates, SwitchBufferState, TimedEnumValues,
-    TimedSwitchValues, TimedValue, TypeSpecificInfoRef,
+    BufferState, BufferStates, EnumBufferState, NumericBufferState, NumericOptions,
+    PiecewiseLinearCurve, PiecewiseLinearCurvePoint, Scaling, States as ParameterStates,
+    SwitchBufferState, TimedEnumValues, TimedSwitchValues, TimedValue, TypeSpecificInfoRef,
 };
 
+mod smoothing;
+
 struct NumericParamMetadatum {
     valid_range: RangeInclusive<f32>,
     default: f32,
+    smoothing_ms: Option<f32>,
+    scaling: Scaling,
 }
 
 struct EnumParamMetadatum {
@@ -89,6 +96,12 @@ struct ProcessingStoreCore {
 
     garbage_tx: mpsc::SyncSender<Arc<cc::Snapshot>>,
     snapshot_rx: mpsc::Receiver<SnapshotMessage>,
+
+    /// The snapshot of each factory preset, indexed by program (see [`crate::programs`]).
+    presets: Vec<cc::Snapshot>,
+
+    /// The hash of [`crate::programs::PARAMETER_ID`].
+    program_id: cp::IdHash,
 }
 
 /// This represents the processing side of the store (see `create_stores`).
@@ -97,6 +110,11 @@ pub struct ProcessingStore {
     /// This is a pre-allocated scratch space used to implement
     /// our API without allocating in the processing context.
     scratch: Scratch,
+
+    /// Pre-allocated space for the parameter change events, see [`ChangeEvents`].
+    change_events: ChangeEventsScratch,
+
+    smoothers: Smoothers,
 }
 
 /// Get a snapshot of the current value of an atomic value.
@@ -169,12 +187,16 @@ fn make_unhash<'a, S: AsRef<str> + 'a, Iter: IntoIterator<Item = cp::InfoRef<'a,
 /// the vst3 `process` call, while the `MainStore` is designed to support
 /// the operations needed by the vst3 `setState` and `getState` calls.
 /// These calls can happen concurrently, which is why we return two different objects.
+///
+/// `presets` are the factory presets the host can select with the program change
+/// parameter (see [`crate::programs`]).
 pub fn create_stores<
     'a,
     S: AsRef<str> + 'a,
     Iter: IntoIterator<Item = cp::InfoRef<'a, S>> + Clone,
 >(
     iter: Iter,
+    presets: &[Preset],
 ) -> (MainStore, ProcessingStore) {
     let data = Arc::<HashMap<cp::IdHash, AtomicValue>>::new(
         iter.clone()
@@ -203,22 +225,35 @@ pub fn create_stores<
     );
     let metadata = Arc::new(Metadata::new(iter));
     let scratch = Scratch::new(&metadata);
+    let change_events = ChangeEventsScratch::new(&metadata);
+    let smoothers = Smoothers::new(metadata.data.iter().filter_map(|(id, metadatum)| {
+        if let Metadatum::Numeric { datum } = metadatum {
+            Some((*id, datum.smoothing_ms?, datum.valid_range.clone()))
+        } else {
+            None
+        }
+    }));
     let (garbage_tx, garbage_rx) = mpsc::sync_channel(CHANNEL_BOUNDS);
     let (snapshot_tx, snapshot_rx) = mpsc::sync_channel(CHANNEL_BOUNDS);
     let read_generation = Arc::new(AtomicU64::new(0));
+    let main = MainStore {
+        unhash_for_snapshot,
+
+        data: data.clone(),
+        cached_write_snapshot: None,
+        write_generation: 0,
+        read_generation: read_generation.clone(),
+        metadata: metadata.clone(),
+
+        garbage_rx,
+        snapshot_tx,
+    };
+    let presets = presets
+        .iter()
+        .map(|preset| main.preset_snapshot(preset))
+        .collect();
     (
-        MainStore {
-            unhash_for_snapshot,
-
-            data: data.clone(),
-            cached_write_snapshot: None,
-            write_generation: 0,
-            read_generation: read_generation.clone(),
-            metadata: metadata.clone(),
-
-            garbage_rx,
-            snapshot_tx,
-        },
+        main,
         ProcessingStore {
             core: ProcessingStoreCore {
                 data,
@@ -228,8 +263,13 @@ pub fn create_stores<
 
                 garbage_tx,
                 snapshot_rx,
+
+                presets,
+                program_id: cp::hash_id(crate::programs::PARAMETER_ID),
             },
             scratch,
+            change_events,
+            smoothers,
         },
     )
 }
@@ -289,9 +329,7 @@ impl ProcessingStoreCore {
         };
 
         if let Some(msg) = most_recent_data {
-            for (k, v) in &msg.snapshot.as_ref().values {
-                self.set(cp::hash_id(k), to_internal(k, v, &self.metadata));
-            }
+            self.apply_snapshot_values(msg.snapshot.as_ref());
 
             self.read_generation
                 .store(msg.generation, std::sync::atomic::Ordering::Release);
@@ -300,6 +338,33 @@ impl ProcessingStoreCore {
         }
     }
 
+    /// Sets every parameter in `snapshot`.
+    fn apply_snapshot_values(&self, snapshot: &cc::Snapshot) {
+        for (k, v) in &snapshot.values {
+            self.set(cp::hash_id(k), to_internal(k, v, &self.metadata));
+        }
+    }
+
+    /// Applies the factory preset selected by a program change queue.
+    ///
+    /// Note that the preset applies to the whole buffer, even if the program changed partway
+    /// through. Returns `None` if the queue is invalid.
+    unsafe fn select_program(&self, queue: ComRef<'_, IParamValueQueue>) -> Option<ChangesStatus> {
+        let Some(RawQueuePoint {
+            sample_offset,
+            value,
+        }) = raw_iterator_from_queue(queue).last()
+        else {
+            return Some(ChangesStatus::NoChanges);
+        };
+        if sample_offset < 0 || !(0.0..=1.0).contains(&value) {
+            return None;
+        }
+        let program = convert_enum(value, u32::try_from(self.presets.len()).ok()?);
+        self.apply_snapshot_values(&self.presets[program as usize]);
+        Some(ChangesStatus::Changes)
+    }
+
     fn set(&self, id: cp::IdHash, new_value: cp::InternalValue) -> bool {
         self.data
             .get(&id)
@@ -327,6 +392,14 @@ impl ProcessingStore {
     pub fn sync_from_main_thread(&mut self) {
         self.core.sync_from_main_thread();
     }
+
+    /// Prepare to smooth parameters for buffers of up to `max_buffer_size` samples.
+    ///
+    /// This resets any smoothing in progress. Note that this allocates, so it must
+    /// be called outside of processing.
+    pub fn prepare_smoothing(&mut self, sampling_rate: f32, max_buffer_size: usize) {
+        self.smoothers.prepare(sampling_rate, max_buffer_size);
+    }
 }
 
 pub enum SnapshotError {
@@ -399,6 +472,32 @@ impl MainStore {
         .unwrap()
     }
 
+    /// Get a snapshot of every parameter with the values of a factory preset.
+    ///
+    /// Parameters that aren't in the preset get their default values, as do
+    /// parameters whose values in the preset are invalid (see
+    /// [`cp::validate_presets`]).
+    fn preset_snapshot(&self, preset: &Preset) -> cc::Snapshot {
+        let mut snapshot = self.get_default_snapshot();
+        for (id, value) in &preset.values {
+            let valid = snapshot.values.contains_key(id)
+                && match (self.metadata.data.get(&cp::hash_id(id)), value) {
+                    (Some(Metadatum::Numeric { datum }), cp::Value::Numeric(value)) => {
+                        datum.valid_range.contains(value)
+                    }
+                    (Some(Metadatum::Enum { datum }), cp::Value::Enum(value)) => {
+                        datum.values.contains(value)
+                    }
+                    (Some(Metadatum::Switch { .. }), cp::Value::Switch(_)) => true,
+                    _ => false,
+                };
+            if valid {
+                snapshot.values.insert(id.clone(), value.clone());
+            }
+        }
+        snapshot
+    }
+
     fn get_default_snapshot(&self) -> cc::Snapshot {
         cc::Snapshot {
             values: self
@@ -431,45 +530,51 @@ impl MainStore {
     /// `conformal_component::parameters::serialization`, a corrupt snapshot, or a
     /// snapshot from a newer version of the plug-in), we will reset to default
     /// state.
+    ///
+    /// If the snapshot was saved with an older schema version than `current_version`,
+    /// `upgrade` will be called to adjust the saved values before they are decoded.
     pub fn apply_snapshot(
         &mut self,
         snapshot: &cc::serialization::Snapshot,
+        saved_version: u32,
+        current_version: u32,
+        upgrade: impl FnOnce(u32, &mut HashMap<String, cp::Value>),
     ) -> Result<(), SnapshotError> {
         self.drop_garbage();
 
-        let decoded =
-            Arc::new(match snapshot
-                .clone()
-                .into_snapshot(self.metadata.data.iter().filter_map(|(id, metadatum)| {
-                    let unhashed = self.unhash_for_snapshot.get(id)?;
-                    Some((
-                        unhashed.as_str(),
-                        match metadatum {
-                            Metadatum::Numeric { datum } => {
-                                cc::serialization::ReadInfoRef::Numeric {
-                                    default: datum.default,
-                                    valid_range: datum.valid_range.clone(),
-                                }
-                            }
-                            Metadatum::Enum { datum } => cc::serialization::ReadInfoRef::Enum {
-                                default: datum.default,
-                                values: datum.values.iter().map(String::as_str),
-                            },
-                            Metadatum::Switch { datum } => cc::serialization::ReadInfoRef::Switch {
-                                default: datum.default,
-                            },
+        let decoded = Arc::new(match snapshot.clone().into_snapshot_with_version(
+            saved_version,
+            current_version,
+            self.metadata.data.iter().filter_map(|(id, metadatum)| {
+                let unhashed = self.unhash_for_snapshot.get(id)?;
+                Some((
+                    unhashed.as_str(),
+                    match metadatum {
+                        Metadatum::Numeric { datum } => cc::serialization::ReadInfoRef::Numeric {
+                            default: datum.default,
+                            valid_range: datum.valid_range.clone(),
                         },
-                    ))
-                })) {
-                Ok(decoded) => Ok(decoded),
-                Err(cc::serialization::DeserializationError::Corrupted(_)) => {
-                    Err(SnapshotError::SnapshotCorrupted)
-                }
-                Err(cc::serialization::DeserializationError::VersionTooNew()) => {
-                    // If the version was too new, we just use the default state
-                    Ok(self.get_default_snapshot())
-                }
-            }?);
+                        Metadatum::Enum { datum } => cc::serialization::ReadInfoRef::Enum {
+                            default: datum.default,
+                            values: datum.values.iter().map(String::as_str),
+                        },
+                        Metadatum::Switch { datum } => cc::serialization::ReadInfoRef::Switch {
+                            default: datum.default,
+                        },
+                    },
+                ))
+            }),
+            upgrade,
+        ) {
+            Ok(decoded) => Ok(decoded),
+            Err(cc::serialization::DeserializationError::Corrupted(_)) => {
+                Err(SnapshotError::SnapshotCorrupted)
+            }
+            Err(cc::serialization::DeserializationError::VersionTooNew()) => {
+                // If the version was too new, we just use the default state
+                Ok(self.get_default_snapshot())
+            }
+        }?);
         self.cached_write_snapshot = Some(decoded.clone());
         self.write_generation = self.write_generation.wrapping_add(1);
         self.snapshot_tx
@@ -524,11 +629,19 @@ impl Metadata {
                         TypeSpecificInfoRef::Numeric {
                             valid_range,
                             default,
+                            options:
+                                NumericOptions {
+                                    smoothing_ms,
+                                    scaling,
+                                    ..
+                                },
                             ..
                         } => Metadatum::Numeric {
                             datum: NumericParamMetadatum {
                                 default: *default,
                                 valid_range: valid_range.clone(),
+                                smoothing_ms: *smoothing_ms,
+                                scaling: *scaling,
                             },
                         },
                         TypeSpecificInfoRef::Switch { default } => Metadatum::Switch {
@@ -549,7 +662,7 @@ fn convert_value(value: f64, metadatum: &Metadatum) -> cp::InternalValue {
 
     match metadatum {
         Metadatum::Numeric { datum } => {
-            cp::InternalValue::Numeric(convert_numeric(value, &datum.valid_range))
+            cp::InternalValue::Numeric(convert_numeric(value, &datum.valid_range, datum.scaling))
         }
         Metadatum::Enum { datum } => {
             cp::InternalValue::Enum(convert_enum(value, datum.values.len().try_into().unwrap()))
@@ -632,6 +745,9 @@ struct InitializedScratch<'a> {
     metadata: &'a Metadata,
     data: &'a HashMap<cp::IdHash, Option<ValueOrQueue>>,
     buffer_size: usize,
+
+    /// If this is `None`, parameters will not be smoothed.
+    smoothers: Option<&'a Smoothers>,
 }
 
 #[derive(Debug, Clone)]
@@ -679,7 +795,9 @@ impl CurveIteratorMetadatum for NumericParamMetadatum {
     ) -> Self::CurvePoint {
         PiecewiseLinearCurvePoint {
             sample_offset: sample_offset.max(0) as usize,
-            value: convert_numeric(value, &self.valid_range),
+            // Note that with non-linear scaling, this interpolates linearly between
+            // the converted points rather than following the curve between them.
+            value: convert_numeric(value, &self.valid_range, self.scaling),
         }
     }
 
@@ -768,6 +886,18 @@ impl<'a> BufferStates for InitializedScratch<'a> {
             impl Iterator<Item = TimedValue<bool>> + Clone,
         >,
     > {
+        if let Some(curve) = self.smoothers.and_then(|s| s.curve(param_id)) {
+            let Metadatum::Numeric { datum } = self.metadata.data.get(&param_id)? else {
+                return None;
+            };
+            return Some(BufferState::Numeric(NumericBufferState::PiecewiseLinear(
+                PiecewiseLinearCurve::new(
+                    itertools::Either::Right(curve),
+                    self.buffer_size,
+                    datum.valid_range.clone(),
+                )?,
+            )));
+        }
         match self.data.get(&param_id)? {
             Some(ValueOrQueue::Value(cp::InternalValue::Numeric(v))) => {
                 Some(BufferState::Numeric(NumericBufferState::Constant(*v)))
@@ -782,7 +912,7 @@ impl<'a> BufferStates for InitializedScratch<'a> {
                 };
                 Some(BufferState::Numeric(NumericBufferState::PiecewiseLinear(
                     PiecewiseLinearCurve::new(
-                        curve_iterator_from_queue(*v, queue, datum),
+                        itertools::Either::Left(curve_iterator_from_queue(*v, queue, datum)),
                         self.buffer_size,
                         datum.valid_range.clone(),
                     )?,
@@ -833,11 +963,26 @@ impl<'a> BufferStates for InitializedScratch<'a> {
 #[derive(Clone)]
 pub struct ExistingBufferStates<'a> {
     store: &'a ProcessingStoreCore,
+    buffer_size: usize,
+
+    /// If this is `None`, parameters will not be smoothed.
+    smoothers: Option<&'a Smoothers>,
 }
 
 impl<'a> ExistingBufferStates<'a> {
-    pub fn new(store: &'a ProcessingStore) -> Self {
-        Self { store: &store.core }
+    /// Note that this advances any parameter smoothing by `buffer_size` samples.
+    pub fn new(store: &'a mut ProcessingStore, buffer_size: usize) -> Self {
+        let unsmoothed = Self {
+            store: &store.core,
+            buffer_size,
+            smoothers: None,
+        };
+        store.smoothers.update(&unsmoothed, buffer_size);
+        Self {
+            store: &store.core,
+            buffer_size,
+            smoothers: Some(&store.smoothers),
+        }
     }
 }
 
@@ -853,9 +998,23 @@ impl<'a> BufferStates for ExistingBufferStates<'a> {
         >,
     > {
         match self.store.get_by_hash(param_id)? {
-            cp::InternalValue::Numeric(v) => Some(BufferState::Numeric(NumericBufferState::<
-                std::iter::Empty<PiecewiseLinearCurvePoint>,
-            >::Constant(v))),
+            cp::InternalValue::Numeric(v) => {
+                if let Some(curve) = self.smoothers.and_then(|s| s.curve(param_id)) {
+                    let Metadatum::Numeric { datum } = self.store.metadata.data.get(&param_id)?
+                    else {
+                        return None;
+                    };
+                    Some(BufferState::Numeric(NumericBufferState::PiecewiseLinear(
+                        PiecewiseLinearCurve::new(
+                            curve,
+                            self.buffer_size,
+                            datum.valid_range.clone(),
+                        )?,
+                    )))
+                } else {
+                    Some(BufferState::Numeric(NumericBufferState::Constant(v)))
+                }
+            }
             cp::InternalValue::Enum(v) => Some(BufferState::Enum(EnumBufferState::<
                 std::iter::Empty<TimedValue<u32>>,
             >::Constant(v))),
@@ -904,6 +1063,20 @@ unsafe fn check_changes_and_update_scratch_and_store<'a>(
     if param_count < 0 {
         return None;
     }
+
+    // Note that we apply program changes first, so that any other changes in
+    // this buffer apply on top of the newly selected preset.
+    let programs = !store.presets.is_empty();
+    if programs {
+        if let Some(queue) = (0..param_count)
+            .filter_map(|idx| ComRef::from_raw(changes.getParameterData(idx)))
+            .find(|q| cp::id_hash_from_internal_hash(q.getParameterId()) == store.program_id)
+        {
+            if store.select_program(queue)? == ChangesStatus::Changes {
+                change_status = ChangesStatus::Changes;
+            }
+        }
+    }
     // Clear all the checker flags
     for v in scratch.data.values_mut() {
         *v = None;
@@ -916,6 +1089,10 @@ unsafe fn check_changes_and_update_scratch_and_store<'a>(
             if point_count < 0 {
                 return false;
             }
+            if programs && parameter_id == store.program_id {
+                // We already handled the program change above.
+                return true;
+            }
             match (
                 scratch.data.get_mut(&parameter_id),
                 store.metadata.data.get(&parameter_id),
@@ -986,28 +1163,216 @@ unsafe fn check_changes_and_update_scratch_and_store<'a>(
             data: &scratch.data,
             metadata: &store.metadata,
             buffer_size,
+            smoothers: None,
         },
     ))
 }
 
+/// Pre-allocated space used to merge the host's parameter queues into a single
+/// stream of events (see [`ChangeEvents`]).
+struct ChangeEventsScratch {
+    cursors: Vec<QueueCursor>,
+
+    /// The merged events. Note that this is re-used between calls, so it
+    /// will only grow (and thus allocate) if the host sends more points than
+    /// in any previous call.
+    events: Vec<Event>,
+}
+
+/// Our position within a single parameter queue while merging.
+struct QueueCursor {
+    com_ptr: *mut IParamValueQueue,
+    id: cp::IdHash,
+    point_count: i32,
+    next_point: i32,
+
+    /// The earliest point we haven't merged yet, as (sample offset, value).
+    head: Option<(usize, f64)>,
+}
+
+impl QueueCursor {
+    /// Move `head` to the next point in the queue.
+    ///
+    /// Any points that can't be read or are out of order within the queue are skipped.
+    unsafe fn advance(&mut self) {
+        let Some(queue) = ComRef::from_raw(self.com_ptr) else {
+            self.head = None;
+            return;
+        };
+        let min_offset = self
+            .head
+            .take()
+            .map_or(0, |(sample_offset, _)| sample_offset);
+        while self.next_point < self.point_count {
+            let point_index = self.next_point;
+            self.next_point += 1;
+            let mut sample_offset = 0;
+            let mut value = 0.0;
+            if queue.getPoint(point_index, &mut sample_offset, &mut value)
+                != vst3::Steinberg::kResultOk
+            {
+                continue;
+            }
+            let sample_offset = sample_offset.max(0) as usize;
+            if sample_offset >= min_offset {
+                self.head = Some((sample_offset, value));
+                return;
+            }
+        }
+    }
+}
+
+impl ChangeEventsScratch {
+    fn new(metadata: &Metadata) -> Self {
+        Self {
+            // Note that hosts may only send one queue per parameter
+            // (see `check_changes_and_update_scratch_and_store`), so this
+            // is enough to never re-allocate.
+            cursors: Vec::with_capacity(metadata.data.len()),
+            events: Vec::with_capacity(metadata.data.len()),
+        }
+    }
+
+    /// Merge all queues of `changes` into `self.events`, in order of sample offset.
+    ///
+    /// This keeps a cursor into each queue, relying on the points
+    /// within each queue being sorted by sample offset. Points at the same
+    /// sample offset are ordered by queue index.
+    ///
+    /// Queues for parameters that are not part of the component (for example,
+    /// the program change parameter) are skipped.
+    ///
+    /// Note that `changes` must already have been checked by
+    /// `check_changes_and_update_scratch_and_store`.
+    unsafe fn fill(&mut self, changes: ComRef<'_, IParameterChanges>, metadata: &Metadata) {
+        self.cursors.clear();
+        self.events.clear();
+        for queue_index in 0..changes.getParameterCount() {
+            let com_ptr = changes.getParameterData(queue_index);
+            let Some(queue) = ComRef::from_raw(com_ptr) else {
+                continue;
+            };
+            let id = cp::id_hash_from_internal_hash(queue.getParameterId());
+            if !metadata.data.contains_key(&id) {
+                continue;
+            }
+            let mut cursor = QueueCursor {
+                com_ptr,
+                id,
+                point_count: queue.getPointCount(),
+                next_point: 0,
+                head: None,
+            };
+            cursor.advance();
+            self.cursors.push(cursor);
+        }
+        // Note that `min_by_key` returns the first of any equal elements,
+        // so ties go to the earliest queue.
+        while let Some(cursor) = self
+            .cursors
+            .iter_mut()
+            .filter(|cursor| cursor.head.is_some())
+            .min_by_key(|cursor| cursor.head.map(|(sample_offset, _)| sample_offset))
+        {
+            let Some((sample_offset, value)) = cursor.head else {
+                break;
+            };
+            if let Some(metadatum) = metadata.data.get(&cursor.id) {
+                self.events.push(Event {
+                    sample_offset,
+                    data: Data::ParameterChange {
+                        data: ParameterChangeData {
+                            id: cursor.id,
+                            value: convert_value(value, metadatum),
+                        },
+                    },
+                });
+            }
+            cursor.advance();
+        }
+    }
+}
+
+/// An iterator over every point of the host's parameter changes as events,
+/// in order of sample offset.
+///
+/// To avoid allocating, the events are merged ahead of time into pre-allocated
+/// scratch space owned by the [`ProcessingStore`].
+#[derive(Clone)]
+pub struct ChangeEvents<'a> {
+    events: std::slice::Iter<'a, Event>,
+}
+
+impl<'a> ChangeEvents<'a> {
+    /// Merge the changes into `scratch`, if `enabled` (otherwise, this is empty).
+    unsafe fn new(
+        changes: ComRef<'_, IParameterChanges>,
+        metadata: &Metadata,
+        scratch: &'a mut ChangeEventsScratch,
+        enabled: bool,
+    ) -> Self {
+        if !enabled {
+            return Self::empty();
+        }
+        scratch.fill(changes, metadata);
+        Self {
+            events: scratch.events.iter(),
+        }
+    }
+
+    fn empty() -> Self {
+        Self { events: [].iter() }
+    }
+}
+
+impl Iterator for ChangeEvents<'_> {
+    type Item = Event;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.events.next().cloned()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.events.size_hint()
+    }
+}
+
 pub unsafe fn param_changes_from_vst3<'a>(
     com_changes: ComRef<'a, IParameterChanges>,
     store: &'a mut ProcessingStore,
     buffer_size: usize,
-) -> Option<impl BufferStates + Clone + 'a> {
-    let (_, states) = check_changes_and_update_scratch_and_store(
+    parameter_change_events: bool,
+) -> Option<(impl BufferStates + Clone + 'a, ChangeEvents<'a>)> {
+    let (_, unsmoothed) = check_changes_and_update_scratch_and_store(
         com_changes,
         &mut store.scratch,
         &store.core,
         buffer_size,
     )?;
-    Some(states)
+    store.smoothers.update(&unsmoothed, buffer_size);
+    Some((
+        InitializedScratch {
+            smoothers: Some(&store.smoothers),
+            ..unsmoothed
+        },
+        ChangeEvents::new(
+            com_changes,
+            unsmoothed.metadata,
+            &mut store.change_events,
+            parameter_change_events,
+        ),
+    ))
 }
 
 pub unsafe fn no_audio_param_changes_from_vst3<'a>(
     com_changes: ComRef<'a, IParameterChanges>,
     store: &'a mut ProcessingStore,
-) -> Option<(ChangesStatus, impl cp::States + Clone + 'a)> {
+    parameter_change_events: bool,
+) -> Option<(
+    ChangesStatus,
+    impl cp::States + Clone + 'a,
+    ChangeEvents<'a>,
+)> {
     let (status, scratch) = check_changes_and_update_scratch_and_store(
         com_changes,
         &mut store.scratch,
@@ -1019,5 +1384,14 @@ pub unsafe fn no_audio_param_changes_from_vst3<'a>(
             return None;
         }
     }
-    Some((status, &store.core))
+    Some((
+        status,
+        &store.core,
+        ChangeEvents::new(
+            com_changes,
+            &store.core.metadata,
+            &mut store.change_events,
+            parameter_change_events,
+        ),
+    ))
 }
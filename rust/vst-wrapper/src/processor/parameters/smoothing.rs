@@ -0,0 +1,145 @@
+//! Smoothing for numeric parameters that declare a `smoothing_ms`.
+//!
+//! Smoothing is a one-pole low-pass filter applied to the per-sample values of
+//! the parameter, including any ramps from host automation. While a parameter
+//! is being smoothed, we pass the processor a curve with a point on every sample.
+
+use std::{collections::HashMap, ops::RangeInclusive};
+
+use conformal_component::parameters::{
+    numeric_per_sample, BufferState, BufferStates, IdHash, NumericBufferState,
+    PiecewiseLinearCurvePoint,
+};
+
+/// Once the smoothed value is within this fraction of the parameter's range of
+/// its target, we jump to the target and stop smoothing.
+const SETTLED_FRACTION: f32 = 1e-6;
+
+struct Smoother {
+    smoothing_ms: f32,
+    valid_range: RangeInclusive<f32>,
+
+    /// The amount we move towards the target each sample.
+    coefficient: f32,
+
+    /// The smoothed value as of the end of the last buffer, or `None`
+    /// if we haven't processed any buffers yet.
+    value: Option<f32>,
+
+    /// The smoothed curve for the current buffer.
+    ///
+    /// This is empty if the parameter is not currently being smoothed.
+    curve: Vec<PiecewiseLinearCurvePoint>,
+}
+
+impl Smoother {
+    fn is_settled(&self, target: f32) -> bool {
+        let threshold = (self.valid_range.end() - self.valid_range.start()) * SETTLED_FRACTION;
+        self.value
+            .map_or(true, |value| (value - target).abs() <= threshold)
+    }
+
+    fn process<I: Iterator<Item = PiecewiseLinearCurvePoint> + Clone>(
+        &mut self,
+        state: NumericBufferState<I>,
+        buffer_size: usize,
+    ) {
+        self.curve.clear();
+        if let NumericBufferState::Constant(target) = state {
+            if self.is_settled(target) {
+                self.value = Some(target);
+                return;
+            }
+        }
+        for (sample_offset, target) in numeric_per_sample(state).take(buffer_size).enumerate() {
+            let value = self
+                .value
+                .map_or(target, |value| value + self.coefficient * (target - value))
+                .clamp(*self.valid_range.start(), *self.valid_range.end());
+            self.value = Some(value);
+            self.curve.push(PiecewiseLinearCurvePoint {
+                sample_offset,
+                value,
+            });
+        }
+    }
+}
+
+/// Holds the smoothing state of all smoothed parameters.
+pub struct Smoothers {
+    data: HashMap<IdHash, Smoother>,
+}
+
+impl Smoothers {
+    /// Create smoothers for parameters, given their ID, `smoothing_ms`, and valid range.
+    ///
+    /// Note that until `prepare` is called, parameters will not be smoothed.
+    pub fn new<I: IntoIterator<Item = (IdHash, f32, RangeInclusive<f32>)>>(params: I) -> Self {
+        Self {
+            data: params
+                .into_iter()
+                .map(|(id, smoothing_ms, valid_range)| {
+                    (
+                        id,
+                        Smoother {
+                            smoothing_ms,
+                            valid_range,
+                            coefficient: 1.0,
+                            value: None,
+                            curve: Vec::new(),
+                        },
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    /// Prepare to smooth buffers of up to `max_buffer_size` samples at `sampling_rate`.
+    ///
+    /// This resets any smoothing in progress. Note that this allocates!
+    pub fn prepare(&mut self, sampling_rate: f32, max_buffer_size: usize) {
+        for smoother in self.data.values_mut() {
+            let time_constant_samples = smoother.smoothing_ms * 0.001 * sampling_rate;
+            smoother.coefficient = if time_constant_samples > 1.0 {
+                1.0 - (-1.0 / time_constant_samples).exp()
+            } else {
+                1.0
+            };
+            smoother.value = None;
+            smoother.curve = Vec::with_capacity(max_buffer_size);
+        }
+    }
+
+    /// Advance the smoothers by one buffer, given the unsmoothed parameter states.
+    ///
+    /// This must not allocate as long as `buffer_size` is no larger than
+    /// the `max_buffer_size` passed to `prepare`.
+    pub fn update<B: BufferStates>(&mut self, unsmoothed: &B, buffer_size: usize) {
+        for (id, smoother) in &mut self.data {
+            if let Some(BufferState::Numeric(state)) = unsmoothed.get_by_hash(*id) {
+                smoother.process(state, buffer_size);
+            } else {
+                smoother.curve.clear();
+            }
+        }
+    }
+
+    /// Get the smoothed curve of a parameter for the current buffer.
+    ///
+    /// This returns `None` if the parameter isn't currently being smoothed,
+    /// in which case the unsmoothed value should be used.
+    pub fn curve(
+        &self,
+        id: IdHash,
+    ) -> Option<impl Iterator<Item = PiecewiseLinearCurvePoint> + Clone + '_> {
+        let smoother = self.data.get(&id)?;
+        if smoother.curve.is_empty() {
+            None
+        } else {
+            Some(smoother.curve.iter().cloned())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
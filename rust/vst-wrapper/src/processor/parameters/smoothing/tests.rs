@@ -0,0 +1,72 @@
+use std::collections::HashMap;
+
+use conformal_component::audio::approx_eq;
+use conformal_component::parameters::{
+    hash_id, ConstantBufferStates, InternalValue, PiecewiseLinearCurvePoint, StatesMap,
+};
+
+use super::Smoothers;
+
+const SAMPLING_RATE: f32 = 10_000.0;
+const BUFFER_SIZE: usize = 64;
+
+fn gain_smoothers() -> Smoothers {
+    // At our sampling rate, this is a time constant of 10 samples.
+    let mut smoothers = Smoothers::new([(hash_id("gain"), 1.0, 0.0..=1.0)]);
+    smoothers.prepare(SAMPLING_RATE, BUFFER_SIZE);
+    smoothers
+}
+
+fn constant_gain(gain: f32) -> ConstantBufferStates<StatesMap> {
+    ConstantBufferStates::new(StatesMap::from(HashMap::from([
+        ("gain", InternalValue::Numeric(gain)),
+        ("other", InternalValue::Numeric(gain)),
+    ])))
+}
+
+fn curve(smoothers: &Smoothers, id: &str) -> Option<Vec<PiecewiseLinearCurvePoint>> {
+    smoothers.curve(hash_id(id)).map(Iterator::collect)
+}
+
+#[test]
+fn first_buffer_is_not_smoothed() {
+    let mut smoothers = gain_smoothers();
+    smoothers.update(&constant_gain(0.5), BUFFER_SIZE);
+    assert!(curve(&smoothers, "gain").is_none());
+}
+
+#[test]
+fn step_change_is_smoothed_over_time_constant() {
+    let mut smoothers = gain_smoothers();
+    smoothers.update(&constant_gain(0.0), BUFFER_SIZE);
+    smoothers.update(&constant_gain(1.0), BUFFER_SIZE);
+    let points = curve(&smoothers, "gain").unwrap();
+    assert_eq!(points.len(), BUFFER_SIZE);
+    assert!(points
+        .iter()
+        .enumerate()
+        .all(|(index, point)| point.sample_offset == index));
+    assert!(points.windows(2).all(|w| w[0].value < w[1].value));
+
+    // After one time constant, we should be about 63% of the way there.
+    assert!(approx_eq(points[9].value, 1.0 - (-1.0f32).exp(), 1e-3));
+    assert!(points[BUFFER_SIZE - 1].value < 1.0);
+}
+
+#[test]
+fn smoothing_settles() {
+    let mut smoothers = gain_smoothers();
+    smoothers.update(&constant_gain(0.0), BUFFER_SIZE);
+    for _ in 0..10 {
+        smoothers.update(&constant_gain(1.0), BUFFER_SIZE);
+    }
+    assert!(curve(&smoothers, "gain").is_none());
+}
+
+#[test]
+fn parameters_without_smoothing_are_ignored() {
+    let mut smoothers = gain_smoothers();
+    smoothers.update(&constant_gain(0.0), BUFFER_SIZE);
+    smoothers.update(&constant_gain(1.0), BUFFER_SIZE);
+    assert!(curve(&smoothers, "other").is_none());
+}
@@ -1,8 +1,215 @@
+use std::{collections::HashMap, hash::BuildHasher};
+
 use serde::{Deserialize, Serialize};
 
-use conformal_core::parameters::serialization;
+use conformal_component::{
+    parameters::{Info, TypeSpecificInfo, Value},
+    Component,
+};
+use conformal_core::parameters::{self as cp, serialization};
+
+use crate::parameters::as_deserialization;
 
 #[derive(Serialize, Deserialize)]
 pub struct State {
     pub params: serialization::Snapshot,
+
+    /// The schema version of the component that saved this state.
+    ///
+    /// States saved before we recorded versions are treated as version 0.
+    #[serde(default)]
+    pub version: u32,
+}
+
+/// An error that can occur in [`serialize_state`] or [`deserialize_state`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StateError {
+    /// The data is not a state in the format written by [`serialize_state`].
+    Malformed,
+
+    /// The values don't match the component's parameters.
+    ///
+    /// This happens if a value is for a parameter the component doesn't have,
+    /// has the wrong type for its parameter, or is out of its parameter's range.
+    InvalidValues,
+
+    /// The state was saved by a component with a newer [`Component::state_version`].
+    VersionTooNew,
+}
+
+fn is_valid(info: &TypeSpecificInfo, value: &Value) -> bool {
+    match (info, value) {
+        (TypeSpecificInfo::Numeric { valid_range, .. }, Value::Numeric(value)) => {
+            valid_range.contains(value)
+        }
+        (TypeSpecificInfo::Enum { values, .. }, Value::Enum(value)) => values.contains(value),
+        (TypeSpecificInfo::Switch { .. }, Value::Switch(_)) => true,
+        _ => false,
+    }
+}
+
+/// Serializes parameter values into the same format the wrapper uses to save its state.
+///
+/// Hosts save this state when saving a project, and often when copying and pasting
+/// the plug-in's settings. The result can be passed to [`deserialize_state`] or loaded
+/// by the plug-in's component in a host.
+///
+/// The format is a [MessagePack](https://msgpack.org) array with two elements:
+///
+///  - The parameter values, as an array with a single element: a map from each
+///    parameter's unique ID to its value. Each value is a single-entry map from the
+///    type of the parameter (`Numeric`, `Enum`, or `Switch`) to its value. Enum values
+///    are saved by name rather than by index.
+///  - The [`Component::state_version`] of `component`.
+///
+/// This format is stable - states will continue to load in future versions of the
+/// wrapper. See [`Component::upgrade_parameters`] for how to change the parameters of a
+/// component while still loading states saved with older versions.
+///
+/// `values` doesn't need to contain every parameter of `component` - any parameters
+/// missing from `values` will be set to their defaults when the state is loaded.
+///
+/// # Errors
+///
+/// Returns [`StateError::InvalidValues`] if any of `values` doesn't match the
+/// corresponding parameter returned by [`Component::parameter_infos`].
+///
+/// # Examples
+///
+/// ```
+/// # use std::{collections::HashMap, hash::BuildHasher};
+/// # use conformal_component::parameters::{self, CurveHint, Flags, InfoRef, TypeSpecificInfoRef, Value};
+/// # use conformal_component::{Component, ProcessingEnvironment};
+/// # use conformal_vst_wrapper::{deserialize_state, serialize_state};
+/// struct MyComponent;
+///
+/// impl Component for MyComponent {
+///     type Processor = ();
+///
+///     fn parameter_infos(&self) -> Vec<parameters::Info> {
+///         parameters::to_infos(&[InfoRef {
+///             title: "Gain",
+///             short_title: "Gain",
+///             unique_id: "gain",
+///             flags: Flags {
+///                 automatable: true,
+///                 curve_hint: CurveHint::Auto,
+///             },
+///             type_specific: TypeSpecificInfoRef::Numeric {
+///                 default: 100.0,
+///                 valid_range: 0f32..=100.,
+///                 units: Some("%"),
+///                 options: Default::default(),
+///             },
+///         }])
+///     }
+///
+///     fn create_processor(&self, _env: &ProcessingEnvironment) -> Self::Processor {}
+/// }
+///
+/// let values = HashMap::from([("gain".to_string(), Value::Numeric(50.0))]);
+/// let state = serialize_state(&MyComponent, &values).unwrap();
+/// assert_eq!(deserialize_state(&MyComponent, &state).unwrap(), values);
+/// ```
+pub fn serialize_state<C: Component, H: BuildHasher>(
+    component: &C,
+    values: &HashMap<String, Value, H>,
+) -> Result<Vec<u8>, StateError> {
+    serialize_values(
+        component.parameter_infos(),
+        component.state_version(),
+        values,
+    )
+}
+
+/// Like [`serialize_state`], but for a component with the given parameters and state version.
+pub fn serialize_values<H: BuildHasher>(
+    infos: Vec<Info>,
+    version: u32,
+    values: &HashMap<String, Value, H>,
+) -> Result<Vec<u8>, StateError> {
+    let infos: HashMap<_, _> = infos
+        .into_iter()
+        .map(|info| (info.unique_id, info.type_specific))
+        .collect();
+    if !values
+        .iter()
+        .all(|(id, value)| infos.get(id).is_some_and(|info| is_valid(info, value)))
+    {
+        return Err(StateError::InvalidValues);
+    }
+    let params = cp::Snapshot {
+        values: values
+            .iter()
+            .map(|(id, value)| (id.clone(), value.clone()))
+            .collect(),
+    }
+    .into_serialize(|id| match infos.get(id)? {
+        TypeSpecificInfo::Numeric { .. } => Some(serialization::WriteInfoRef::Numeric {}),
+        TypeSpecificInfo::Enum { values, .. } => Some(serialization::WriteInfoRef::Enum {
+            values: values.iter().map(String::as_str),
+        }),
+        TypeSpecificInfo::Switch { .. } => Some(serialization::WriteInfoRef::Switch {}),
+    })
+    .ok_or(StateError::InvalidValues)?;
+    let mut ret = Vec::new();
+    State { params, version }
+        .serialize(&mut rmp_serde::Serializer::new(&mut ret))
+        .map_err(|_| StateError::InvalidValues)?;
+    Ok(ret)
+}
+
+/// Deserializes parameter values from a state saved by the wrapper or by [`serialize_state`].
+///
+/// The result contains a value for every parameter returned by
+/// [`Component::parameter_infos`], with any parameters missing from the state
+/// set to their defaults. Values for parameters that `component` doesn't have are
+/// ignored. If the state was saved with an older [`Component::state_version`], the
+/// values are upgraded with [`Component::upgrade_parameters`].
+///
+/// # Errors
+///
+///  - Returns [`StateError::Malformed`] if `state` isn't in the format written
+///    by [`serialize_state`].
+///  - Returns [`StateError::InvalidValues`] if a value has the wrong type for its
+///    parameter, or if upgrading the values produced invalid values.
+///  - Returns [`StateError::VersionTooNew`] if the state was saved with a newer
+///    [`Component::state_version`], or has values outside of the range of the
+///    current parameters, which can only happen if it was saved by a newer version
+///    of the component.
+pub fn deserialize_state<C: Component>(
+    component: &C,
+    state: &[u8],
+) -> Result<HashMap<String, Value>, StateError> {
+    deserialize_values(
+        &component.parameter_infos(),
+        component.state_version(),
+        |saved_version, values| component.upgrade_parameters(saved_version, values),
+        state,
+    )
+}
+
+/// Like [`deserialize_state`], but for a component with the given parameters and state version.
+pub fn deserialize_values(
+    infos: &[Info],
+    version: u32,
+    upgrade_parameters: impl FnOnce(u32, &mut HashMap<String, Value>),
+    state: &[u8],
+) -> Result<HashMap<String, Value>, StateError> {
+    let state: State = rmp_serde::from_slice(state).map_err(|_| StateError::Malformed)?;
+    state
+        .params
+        .into_snapshot_with_version(
+            state.version,
+            version,
+            infos
+                .iter()
+                .map(|info| (info.unique_id.as_str(), as_deserialization(info))),
+            upgrade_parameters,
+        )
+        .map(|snapshot| snapshot.values)
+        .map_err(|e| match e {
+            serialization::DeserializationError::VersionTooNew() => StateError::VersionTooNew,
+            serialization::DeserializationError::Corrupted(_) => StateError::InvalidValues,
+        })
 }
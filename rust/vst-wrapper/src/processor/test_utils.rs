@@ -7,7 +7,10 @@ use vst3::{
 };
 
 use conformal_component::{
-    events::{to_vst_note_channel_for_mpe_quirks, to_vst_note_id, Data, Event, NoteExpressionData},
+    events::{
+        to_vst_note_channel_for_mpe_quirks, to_vst_note_id, Data, Event, NoteData,
+        NoteExpressionData,
+    },
     parameters::hash_id,
     ProcessingMode,
 };
@@ -226,6 +229,13 @@ struct EventList {
     events: Vec<Event>,
 }
 
+fn to_vst_note_channel(data: &NoteData) -> i16 {
+    match to_vst_note_channel_for_mpe_quirks(data.id) {
+        0 => i16::from(data.channel),
+        channel => channel,
+    }
+}
+
 fn event_to_vst3_event(event: &Event) -> vst3::Steinberg::Vst::Event {
     match &event.data {
         Data::NoteOn { data } => vst3::Steinberg::Vst::Event {
@@ -236,7 +246,7 @@ fn event_to_vst3_event(event: &Event) -> vst3::Steinberg::Vst::Event {
             r#type: vst3::Steinberg::Vst::Event_::EventTypes_::kNoteOnEvent as u16,
             __field0: vst3::Steinberg::Vst::Event__type0 {
                 noteOn: vst3::Steinberg::Vst::NoteOnEvent {
-                    channel: to_vst_note_channel_for_mpe_quirks(data.id),
+                    channel: to_vst_note_channel(data),
                     pitch: data.pitch as i16,
                     tuning: data.tuning,
                     velocity: data.velocity,
@@ -253,7 +263,7 @@ fn event_to_vst3_event(event: &Event) -> vst3::Steinberg::Vst::Event {
             r#type: vst3::Steinberg::Vst::Event_::EventTypes_::kNoteOffEvent as u16,
             __field0: vst3::Steinberg::Vst::Event__type0 {
                 noteOff: vst3::Steinberg::Vst::NoteOffEvent {
-                    channel: to_vst_note_channel_for_mpe_quirks(data.id),
+                    channel: to_vst_note_channel(data),
                     pitch: data.pitch as i16,
                     tuning: data.tuning,
                     velocity: data.velocity,
@@ -293,6 +303,9 @@ fn event_to_vst3_event(event: &Event) -> vst3::Steinberg::Vst::Event {
                 },
             },
         },
+        Data::ParameterChange { .. } => {
+            panic!("Parameter changes must be sent as parameter queues, not events")
+        }
     }
 }
 
@@ -390,10 +403,33 @@ pub unsafe fn mock_process_effect<D: IAudioProcessorTrait>(
     inputs: Vec<Vec<f32>>,
     params: Vec<ParameterValueQueueImpl>,
     processor: &D,
+) -> Option<Vec<Vec<f32>>> {
+    mock_process_effect_impl(inputs, None, params, processor)
+}
+
+pub unsafe fn mock_process_effect_with_events<D: IAudioProcessorTrait>(
+    inputs: Vec<Vec<f32>>,
+    events: Vec<Event>,
+    params: Vec<ParameterValueQueueImpl>,
+    processor: &D,
+) -> Option<Vec<Vec<f32>>> {
+    mock_process_effect_impl(inputs, Some(events), params, processor)
+}
+
+unsafe fn mock_process_effect_impl<D: IAudioProcessorTrait>(
+    inputs: Vec<Vec<f32>>,
+    events: Option<Vec<Event>>,
+    params: Vec<ParameterValueQueueImpl>,
+    processor: &D,
 ) -> Option<Vec<Vec<f32>>> {
     let input_parameter_changes = ComWrapper::new(ParameterChangesImpl::new(params))
         .to_com_ptr::<IParameterChanges>()
         .unwrap();
+    let input_events = events.map(|events| {
+        ComWrapper::new(EventList { events })
+            .to_com_ptr::<IEventList>()
+            .unwrap()
+    });
 
     let mut input_audio_channels_ptr = inputs
         .iter()
@@ -430,6 +466,63 @@ pub unsafe fn mock_process_effect<D: IAudioProcessorTrait>(
         outputs: output_audio_buffer_struct.as_mut(),
         inputParameterChanges: input_parameter_changes.as_ptr(),
         outputParameterChanges: std::ptr::null_mut(),
+        inputEvents: input_events
+            .as_ref()
+            .map_or(std::ptr::null_mut(), |events| events.as_ptr()),
+        outputEvents: std::ptr::null_mut(),
+        processContext: std::ptr::null_mut(),
+    };
+    if vst3::Steinberg::kResultOk == processor.process(&mut process_data) {
+        Some(output_audio_channels)
+    } else {
+        None
+    }
+}
+
+pub unsafe fn mock_process_effect_f64<D: IAudioProcessorTrait>(
+    inputs: Vec<Vec<f64>>,
+    params: Vec<ParameterValueQueueImpl>,
+    processor: &D,
+) -> Option<Vec<Vec<f64>>> {
+    let input_parameter_changes = ComWrapper::new(ParameterChangesImpl::new(params))
+        .to_com_ptr::<IParameterChanges>()
+        .unwrap();
+
+    let mut input_audio_channels_ptr = inputs
+        .iter()
+        .map(|x| x.as_ptr() as *mut f64)
+        .collect::<Vec<_>>();
+
+    let mut output_audio_channels = vec![vec![0f64; inputs[0].len()]; inputs.len()];
+    let mut output_audio_channels_ptr = output_audio_channels
+        .iter_mut()
+        .map(|x| x.as_mut_ptr())
+        .collect::<Vec<_>>();
+    let mut input_audio_buffer_struct = Box::new(vst3::Steinberg::Vst::AudioBusBuffers {
+        numChannels: inputs.len() as i32,
+        silenceFlags: 0,
+        __field0: AudioBusBuffers__type0 {
+            channelBuffers64: input_audio_channels_ptr.as_mut_ptr(),
+        },
+    });
+
+    let mut output_audio_buffer_struct = Box::new(vst3::Steinberg::Vst::AudioBusBuffers {
+        numChannels: inputs.len() as i32,
+        silenceFlags: 0,
+        __field0: AudioBusBuffers__type0 {
+            channelBuffers64: output_audio_channels_ptr.as_mut_ptr(),
+        },
+    });
+    let mut process_data = vst3::Steinberg::Vst::ProcessData {
+        processMode: vst3::Steinberg::Vst::ProcessModes_::kRealtime as i32,
+        symbolicSampleSize: vst3::Steinberg::Vst::SymbolicSampleSizes_::kSample64 as i32,
+        numSamples: inputs[0].len() as i32,
+        numInputs: 1,
+        numOutputs: 1,
+        inputs: input_audio_buffer_struct.as_mut(),
+        outputs: output_audio_buffer_struct.as_mut(),
+        inputParameterChanges: input_parameter_changes.as_ptr(),
+        outputParameterChanges: std::ptr::null_mut(),
         inputEvents: std::ptr::null_mut(),
         outputEvents: std::ptr::null_mut(),
         processContext: std::ptr::null_mut(),
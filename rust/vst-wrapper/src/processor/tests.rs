@@ -1,47 +1,51 @@
 use std::cell::RefCell;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
-use conformal_component::effect::Effect;
-use conformal_component::synth::PITCH_BEND_PARAMETER;
+use conformal_component::analyzer::Analyzer;
+use conformal_component::effect::{Effect, SoftBypass, BYPASS_PARAMETER};
+use conformal_component::synth::{MAX_AUX_OUTPUTS, PITCH_BEND_PARAMETER};
 use vst3::ComWrapper;
 use vst3::Steinberg::{
     IBStreamTrait, IPluginBaseTrait,
-    Vst::{IAudioProcessorTrait, IComponentTrait, IHostApplication},
+    Vst::{
+        IAudioProcessorTrait, IComponentTrait, IHostApplication, IProcessContextRequirementsTrait,
+    },
 };
 
+use super::state::serialize_values;
 use super::test_utils::{activate_busses, process_setup, setup_proc, DEFAULT_ENV};
-use super::{create_effect, create_synth, PartialProcessingEnvironment};
+use super::{create_analyzer, create_effect, create_synth, PartialProcessingEnvironment};
 use crate::fake_ibstream::Stream;
 use crate::mpe_quirks::aftertouch_param_id;
 use crate::processor::test_utils::{
     activate_effect_busses, mock_no_audio_process_data, mock_process, mock_process_effect,
-    mock_process_mod, setup_proc_effect, ParameterValueQueueImpl, ParameterValueQueuePoint,
-    SAMPLE_COUNT,
+    mock_process_effect_f64, mock_process_effect_with_events, mock_process_mod, setup_proc_effect,
+    ParameterValueQueueImpl, ParameterValueQueuePoint, SAMPLE_COUNT,
 };
-use crate::HostInfo;
+use crate::programs;
+use crate::{deserialize_state, serialize_state, StateError};
 use crate::{dummy_host, from_utf16_buffer};
+use crate::{EffectOptions, HostInfo, SynthOptions};
 use assert_approx_eq::assert_approx_eq;
 use conformal_component;
-use conformal_component::audio::{channels, channels_mut, BufferMut};
+use conformal_component::audio::{channels, channels_mut, BufferMut, ChannelLayout};
 use conformal_component::events::{
-    Data, Event, Events, NoteData, NoteExpression, NoteExpressionData, NoteID,
+    Data, Event, Events, NoteData, NoteExpression, NoteExpressionData, NoteID, ParameterChangeData,
 };
 use conformal_component::parameters::{enum_per_sample, numeric_per_sample, switch_per_sample};
 use conformal_component::parameters::{
-    BufferStates, Flags, InfoRef, States, StaticInfoRef, TypeSpecificInfoRef,
+    hash_id, BufferStates, CurveHint, Flags, InfoRef, InternalValue, NumericOptions, States,
+    StaticInfoRef, TypeSpecificInfo, TypeSpecificInfoRef, Value,
 };
 use conformal_component::{
-    synth::Synth, Component, ProcessingEnvironment, ProcessingMode, Processor,
+    synth::Synth, Component, Preset, ProcessingEnvironment, ProcessingMode, Processor,
+    TimeSignature, Transport, INFINITE_TAIL_SAMPLES,
 };
-
-#[derive(Default)]
-struct FakeSynthComponent<'a> {
-    last_process_env: Option<&'a RefCell<Option<ProcessingEnvironment>>>,
-    processing: Option<&'a RefCell<bool>>,
-}
+use conformal_poly::{EventData, NoteExpressionCurve, NoteExpressionPoint, Poly, Voice};
 
 struct FakeSynth<'a> {
     processing: Option<&'a RefCell<bool>>,
+    lifecycle: Option<&'a RefCell<Vec<&'static str>>>,
     notes: HashSet<NoteID>,
     pitchbend: f32,
     timbre: f32,
@@ -70,10 +74,11 @@ impl<'a> FakeSynth<'a> {
     }
 }
 
-struct FakeEffect {}
-
 #[derive(Default)]
-struct FakeEffectComponent {}
+struct FakeEffect {
+    latency_samples: usize,
+    tail_samples: usize,
+}
 
 static DEFAULT_NUMERIC: f32 = 1.0;
 static MIN_NUMERIC: f32 = 0.5;
@@ -90,18 +95,25 @@ static PARAMETERS: [StaticInfoRef; 3] = [
         title: "Multiplier",
         short_title: "Mult",
         unique_id: NUMERIC_ID,
-        flags: Flags { automatable: true },
+        flags: Flags {
+            automatable: true,
+            curve_hint: CurveHint::Auto,
+        },
         type_specific: TypeSpecificInfoRef::Numeric {
             default: DEFAULT_NUMERIC,
             valid_range: MIN_NUMERIC..=MAX_NUMERIC,
             units: Some("Hz"),
+            options: NumericOptions::DEFAULT,
         },
     },
     InfoRef {
         title: "Enum Multiplier",
         short_title: "Enum",
         unique_id: ENUM_ID,
-        flags: Flags { automatable: true },
+        flags: Flags {
+            automatable: true,
+            curve_hint: CurveHint::Auto,
+        },
         type_specific: TypeSpecificInfoRef::Enum {
             default: DEFAULT_ENUM,
             values: &["1", "2", "3"],
@@ -111,18 +123,109 @@ static PARAMETERS: [StaticInfoRef; 3] = [
         title: "Switch Multipler",
         short_title: "Switch",
         unique_id: SWITCH_ID,
-        flags: Flags { automatable: true },
+        flags: Flags {
+            automatable: true,
+            curve_hint: CurveHint::Auto,
+        },
         type_specific: TypeSpecificInfoRef::Switch {
             default: DEFAULT_SWITCH,
         },
     },
 ];
 
+/// A component that creates its processor with `create_processor`, and reports
+/// everything else from its fields.
+///
+/// Use `FakeComponent::new` for a component with `PARAMETERS` and the default
+/// behavior, and struct update syntax to change anything a test depends on.
+struct FakeComponent<'a, P> {
+    create_processor: Box<dyn Fn(&ProcessingEnvironment) -> P + 'a>,
+    parameter_infos: Vec<conformal_component::parameters::Info>,
+    factory_presets: Vec<Preset>,
+    mono_compatible: bool,
+    /// If `None`, we derive the layouts from `mono_compatible` like the default
+    /// implementation does.
+    supported_channel_layouts: Option<Vec<ChannelLayout>>,
+    state_version: u32,
+    upgrade_parameters: fn(u32, &mut HashMap<String, Value>),
+}
+
+impl<'a, P> FakeComponent<'a, P> {
+    fn new(create_processor: impl Fn(&ProcessingEnvironment) -> P + 'a) -> Self {
+        Self {
+            create_processor: Box::new(create_processor),
+            parameter_infos: conformal_component::parameters::to_infos(&PARAMETERS),
+            factory_presets: vec![],
+            mono_compatible: true,
+            supported_channel_layouts: None,
+            state_version: 0,
+            upgrade_parameters: |_, _| {},
+        }
+    }
+}
+
+impl<P> Component for FakeComponent<'_, P> {
+    type Processor = P;
+
+    fn create_processor(&self, env: &ProcessingEnvironment) -> Self::Processor {
+        (self.create_processor)(env)
+    }
+
+    fn parameter_infos(&self) -> Vec<conformal_component::parameters::Info> {
+        self.parameter_infos.clone()
+    }
+
+    fn mono_compatible(&self) -> bool {
+        self.mono_compatible
+    }
+
+    fn supported_channel_layouts(&self) -> Vec<ChannelLayout> {
+        match &self.supported_channel_layouts {
+            Some(layouts) => layouts.clone(),
+            None if self.mono_compatible => vec![ChannelLayout::Mono, ChannelLayout::Stereo],
+            None => vec![ChannelLayout::Stereo],
+        }
+    }
+
+    fn state_version(&self) -> u32 {
+        self.state_version
+    }
+
+    fn upgrade_parameters(&self, saved_version: u32, values: &mut HashMap<String, Value>) {
+        (self.upgrade_parameters)(saved_version, values);
+    }
+
+    fn factory_presets(&self) -> Vec<Preset> {
+        self.factory_presets.clone()
+    }
+}
+
+fn fake_synth_component<'a>() -> FakeComponent<'a, FakeSynth<'a>> {
+    FakeComponent::new(|_| FakeSynth::default())
+}
+
+fn fake_effect_component<'a>() -> FakeComponent<'a, FakeEffect> {
+    FakeComponent::new(|_| FakeEffect::default())
+}
+
 impl<'a> Processor for FakeSynth<'a> {
     fn set_processing(&mut self, processing: bool) {
         if let Some(processing_) = self.processing {
             processing_.replace(processing);
         }
+        if let Some(lifecycle) = self.lifecycle {
+            lifecycle.borrow_mut().push(if processing {
+                "set_processing(true)"
+            } else {
+                "set_processing(false)"
+            });
+        }
+    }
+
+    fn prepare(&mut self) {
+        if let Some(lifecycle) = self.lifecycle {
+            lifecycle.borrow_mut().push("prepare");
+        }
     }
 }
 
@@ -143,6 +246,7 @@ impl<'a> Synth for FakeSynth<'a> {
                 Data::NoteExpression { data } => {
                     self.handle_note_expression(data);
                 }
+                Data::ParameterChange { .. } => {}
             }
         }
     }
@@ -181,6 +285,7 @@ impl<'a> Synth for FakeSynth<'a> {
                         Data::NoteExpression { data } => {
                             self.handle_note_expression(data);
                         }
+                        Data::ParameterChange { .. } => {}
                     }
                     next_event = events_iter.next();
                 } else {
@@ -201,33 +306,26 @@ impl<'a> Synth for FakeSynth<'a> {
     }
 }
 
-impl<'a> Component for FakeSynthComponent<'a> {
-    type Processor = FakeSynth<'a>;
-
-    fn create_processor(&self, env: &ProcessingEnvironment) -> Self::Processor {
-        if let Some(proc_env) = self.last_process_env {
-            proc_env.replace(Some(env.clone()));
-        }
+impl Default for FakeSynth<'_> {
+    fn default() -> Self {
         let mut notes = HashSet::new();
         notes.reserve(1024);
         FakeSynth {
-            processing: self.processing,
+            processing: None,
+            lifecycle: None,
             notes,
             pitchbend: 0f32,
             timbre: 0f32,
             aftertouch: 0f32,
         }
     }
-
-    fn parameter_infos(&self) -> Vec<conformal_component::parameters::Info> {
-        conformal_component::parameters::to_infos(&PARAMETERS)
-    }
 }
 
 fn dummy_synth() -> impl IComponentTrait + IAudioProcessorTrait {
     create_synth(
-        |_: &HostInfo| -> FakeSynthComponent<'static> { Default::default() },
+        |_: &HostInfo| fake_synth_component(),
         [4; 16],
+        SynthOptions::DEFAULT,
     )
 }
 
@@ -235,11 +333,14 @@ fn dummy_synth_with_processing_environment<'a>(
     env: &'a RefCell<Option<ProcessingEnvironment>>,
 ) -> impl IAudioProcessorTrait + IComponentTrait + 'a {
     create_synth(
-        |_: &HostInfo| FakeSynthComponent {
-            last_process_env: Some(env),
-            processing: None,
+        |_: &HostInfo| {
+            FakeComponent::new(|proc_env| {
+                env.replace(Some(proc_env.clone()));
+                FakeSynth::default()
+            })
         },
         [4; 16],
+        SynthOptions::DEFAULT,
     )
 }
 
@@ -249,12 +350,10 @@ fn dummy_synth_with_host_info<'a>(
     create_synth(
         |real_host_info: &HostInfo| {
             host_info.replace(Some((*real_host_info).clone()));
-            FakeSynthComponent {
-                last_process_env: None,
-                processing: None,
-            }
+            fake_synth_component()
         },
         [4; 16],
+        SynthOptions::DEFAULT,
     )
 }
 
@@ -262,16 +361,42 @@ fn dummy_synth_with_processing<'a>(
     env: &'a RefCell<bool>,
 ) -> impl IAudioProcessorTrait + IComponentTrait + 'a {
     create_synth(
-        |_: &HostInfo| FakeSynthComponent {
-            last_process_env: None,
-            processing: Some(env),
+        |_: &HostInfo| {
+            FakeComponent::new(|_| FakeSynth {
+                processing: Some(env),
+                ..Default::default()
+            })
+        },
+        [4; 16],
+        SynthOptions::DEFAULT,
+    )
+}
+
+fn dummy_synth_with_lifecycle<'a>(
+    lifecycle: &'a RefCell<Vec<&'static str>>,
+) -> impl IAudioProcessorTrait + IComponentTrait + 'a {
+    create_synth(
+        |_: &HostInfo| {
+            FakeComponent::new(|_| FakeSynth {
+                lifecycle: Some(lifecycle),
+                ..Default::default()
+            })
         },
         [4; 16],
+        SynthOptions::DEFAULT,
     )
 }
 
 impl Processor for FakeEffect {
     fn set_processing(&mut self, _processing: bool) {}
+
+    fn latency_samples(&self) -> usize {
+        self.latency_samples
+    }
+
+    fn tail_samples(&self) -> usize {
+        self.tail_samples
+    }
 }
 
 impl Effect for FakeEffect {
@@ -306,1682 +431,3957 @@ impl Effect for FakeEffect {
     }
 }
 
-impl Component for FakeEffectComponent {
-    type Processor = FakeEffect;
-
-    fn create_processor(&self, _env: &ProcessingEnvironment) -> Self::Processor {
-        FakeEffect {}
-    }
-
-    fn parameter_infos(&self) -> Vec<conformal_component::parameters::Info> {
-        conformal_component::parameters::to_infos(&PARAMETERS)
-    }
-}
-
 fn dummy_effect() -> impl IComponentTrait + IAudioProcessorTrait {
     create_effect(
-        |_: &HostInfo| -> FakeEffectComponent { Default::default() },
+        |_: &HostInfo| fake_effect_component(),
         [4; 16],
+        SWITCH_ID,
+        EffectOptions::DEFAULT,
     )
 }
 
-#[test]
-fn can_process_f32() {
-    let proc = dummy_synth();
+/// An effect that outputs 1.0 while bypassed, and 0.0 otherwise.
+struct BypassReportingEffect {}
 
-    unsafe {
-        assert_eq!(
-            proc.canProcessSampleSize(vst3::Steinberg::Vst::SymbolicSampleSizes_::kSample32 as i32),
-            vst3::Steinberg::kResultTrue
-        );
+impl Processor for BypassReportingEffect {
+    fn set_processing(&mut self, _processing: bool) {}
+}
+
+impl Effect for BypassReportingEffect {
+    fn handle_parameters<P: conformal_component::parameters::States>(&mut self, _parameters: P) {}
+
+    fn process<
+        P: conformal_component::parameters::BufferStates,
+        I: conformal_component::audio::Buffer,
+        O: conformal_component::audio::BufferMut,
+    >(
+        &mut self,
+        parameters: P,
+        _input: &I,
+        output: &mut O,
+    ) {
+        let bypass_iter = switch_per_sample(parameters.get_switch(BYPASS_PARAMETER).unwrap());
+        for (frame_index, bypass) in (0..output.num_frames()).zip(bypass_iter) {
+            for ochannel in channels_mut(output) {
+                ochannel[frame_index] = if bypass { 1.0 } else { 0.0 };
+            }
+        }
     }
 }
 
-#[test]
-fn cannot_process_f64() {
-    let proc = dummy_synth();
+static SMOOTHING_MS: f32 = 10.0;
 
-    unsafe {
-        assert_eq!(
-            proc.canProcessSampleSize(vst3::Steinberg::Vst::SymbolicSampleSizes_::kSample64 as i32),
-            vst3::Steinberg::kResultFalse
-        );
+/// `PARAMETERS`, but the numeric parameter is smoothed.
+fn smoothed_parameters() -> Vec<conformal_component::parameters::Info> {
+    let mut infos = conformal_component::parameters::to_infos(&PARAMETERS);
+    for info in &mut infos {
+        if let TypeSpecificInfo::Numeric { options, .. } = &mut info.type_specific {
+            options.smoothing_ms = Some(SMOOTHING_MS);
+        }
     }
+    infos
 }
 
-fn matches(partial: &PartialProcessingEnvironment, full: &ProcessingEnvironment) -> bool {
-    partial.sampling_rate == full.sampling_rate
-        && partial.max_samples_per_process_call == full.max_samples_per_process_call
-        && partial.processing_mode == full.processing_mode
+const PARAMETER_CHANGE_EVENTS_SYNTH_OPTIONS: SynthOptions = SynthOptions {
+    parameter_change_events: true,
+    ..SynthOptions::DEFAULT
+};
+
+/// A synth that records all events it processes, including parameter changes.
+struct EventRecordingSynth<'a> {
+    events: &'a RefCell<Vec<Event>>,
 }
 
-#[test]
-fn defends_against_setup_processing_before_initialize() {
-    let proc = dummy_synth();
-    unsafe {
-        assert_ne!(
-            proc.setupProcessing(&mut process_setup(&DEFAULT_ENV)),
-            vst3::Steinberg::kResultOk
-        );
+impl Processor for EventRecordingSynth<'_> {
+    fn set_processing(&mut self, _processing: bool) {}
+}
+
+impl Synth for EventRecordingSynth<'_> {
+    fn handle_events<E: IntoIterator<Item = Data>, P: States>(
+        &mut self,
+        _events: E,
+        _parameters: P,
+    ) {
+    }
+
+    fn process<E: Iterator<Item = Event>, P: BufferStates, O: BufferMut>(
+        &mut self,
+        events: Events<E>,
+        _parameters: P,
+        _output: &mut O,
+    ) {
+        self.events.borrow_mut().extend(events);
     }
 }
 
-#[test]
-fn correct_bus_count() {
-    let proc = dummy_synth();
-    let host = ComWrapper::new(dummy_host::Host::default())
-        .to_com_ptr::<IHostApplication>()
-        .unwrap();
+/// A synth that records the transport it was given before each processing call.
+struct TransportRecordingSynth<'a> {
+    transports: &'a RefCell<Vec<Transport>>,
+}
 
-    unsafe {
-        assert_eq!(
-            proc.initialize(host.cast().unwrap().as_ptr()),
-            vst3::Steinberg::kResultOk
-        );
+impl Processor for TransportRecordingSynth<'_> {
+    fn set_processing(&mut self, _processing: bool) {}
 
-        assert_eq!(
-            proc.setupProcessing(&mut process_setup(&DEFAULT_ENV)),
-            vst3::Steinberg::kResultOk
-        );
-        assert_eq!(
-            proc.getBusCount(
-                vst3::Steinberg::Vst::MediaTypes_::kAudio as i32,
-                vst3::Steinberg::Vst::BusDirections_::kInput as i32
-            ),
-            0
-        );
-        assert_eq!(
-            proc.getBusCount(
-                vst3::Steinberg::Vst::MediaTypes_::kAudio as i32,
-                vst3::Steinberg::Vst::BusDirections_::kOutput as i32
-            ),
-            1
-        );
-        assert_eq!(
-            proc.getBusCount(
-                vst3::Steinberg::Vst::MediaTypes_::kEvent as i32,
-                vst3::Steinberg::Vst::BusDirections_::kInput as i32
-            ),
-            1
-        );
-        assert_eq!(
-            proc.getBusCount(
-                vst3::Steinberg::Vst::MediaTypes_::kEvent as i32,
-                vst3::Steinberg::Vst::BusDirections_::kOutput as i32
-            ),
-            0
-        );
+    fn set_transport(&mut self, transport: &Transport) {
+        self.transports.borrow_mut().push(transport.clone());
     }
 }
 
-#[test]
-fn bus_info_invalid_bus() {
-    let proc = dummy_synth();
+impl Synth for TransportRecordingSynth<'_> {
+    fn handle_events<E: IntoIterator<Item = Data>, P: States>(
+        &mut self,
+        _events: E,
+        _parameters: P,
+    ) {
+    }
 
-    for &(type_, dir, ind) in &[
-        (
-            vst3::Steinberg::Vst::MediaTypes_::kAudio,
-            vst3::Steinberg::Vst::BusDirections_::kInput,
-            0,
-        ),
-        (
-            vst3::Steinberg::Vst::MediaTypes_::kEvent,
-            vst3::Steinberg::Vst::BusDirections_::kOutput,
-            0,
-        ),
-        (
-            vst3::Steinberg::Vst::MediaTypes_::kEvent,
-            vst3::Steinberg::Vst::BusDirections_::kInput,
-            1,
-        ),
-        (
-            vst3::Steinberg::Vst::MediaTypes_::kAudio,
-            vst3::Steinberg::Vst::BusDirections_::kOutput,
-            1,
-        ),
-    ] {
-        unsafe {
-            let mut bus = vst3::Steinberg::Vst::BusInfo {
-                mediaType: 0,
-                direction: 0,
-                channelCount: 0,
-                name: [0; 128],
-                busType: 0,
-                flags: 0,
-            };
-            assert_eq!(
-                proc.getBusInfo(type_ as i32, dir as i32, ind, &mut bus),
-                vst3::Steinberg::kInvalidArgument
-            );
-        }
+    fn process<E: Iterator<Item = Event>, P: BufferStates, O: BufferMut>(
+        &mut self,
+        _events: Events<E>,
+        _parameters: P,
+        _output: &mut O,
+    ) {
     }
 }
 
-#[test]
-fn bus_info() {
-    let proc = dummy_synth();
+/// A voice that does nothing, so we can watch how `Poly` assigns notes to voices.
+struct SilentVoice {}
 
-    unsafe {
-        assert_eq!(
-            proc.getBusCount(
-                vst3::Steinberg::Vst::MediaTypes_::kAudio as i32,
-                vst3::Steinberg::Vst::BusDirections_::kOutput as i32
-            ),
-            1
-        );
-        assert_eq!(
-            proc.getBusCount(
-                vst3::Steinberg::Vst::MediaTypes_::kEvent as i32,
-                vst3::Steinberg::Vst::BusDirections_::kOutput as i32
-            ),
-            0
-        );
-        assert_eq!(
-            proc.getBusCount(
-                vst3::Steinberg::Vst::MediaTypes_::kAudio as i32,
-                vst3::Steinberg::Vst::BusDirections_::kInput as i32
-            ),
-            0
-        );
-        assert_eq!(
-            proc.getBusCount(
-                vst3::Steinberg::Vst::MediaTypes_::kEvent as i32,
-                vst3::Steinberg::Vst::BusDirections_::kInput as i32
-            ),
-            1
-        );
+impl Voice for SilentVoice {
+    type SharedData<'a> = ();
 
-        let mut bus = vst3::Steinberg::Vst::BusInfo {
-            mediaType: 0,
-            direction: 0,
-            channelCount: 0,
-            name: [0; 128],
-            busType: 0,
-            flags: 0,
-        };
-        assert_eq!(
-            proc.getBusInfo(
-                vst3::Steinberg::Vst::MediaTypes_::kAudio as i32,
-                vst3::Steinberg::Vst::BusDirections_::kOutput as i32,
-                0,
-                &mut bus
-            ),
-            vst3::Steinberg::kResultOk
-        );
-        assert_eq!(
-            bus.mediaType,
-            vst3::Steinberg::Vst::MediaTypes_::kAudio as i32
-        );
-        assert_eq!(
-            bus.direction,
-            vst3::Steinberg::Vst::BusDirections_::kOutput as i32
-        );
-        // Note we are locked into stereo for now
-        assert_eq!(bus.channelCount, 2);
-        assert_eq!(
-            from_utf16_buffer(&bus.name)
-                .as_ref()
-                .map(|x: &String| x.as_str()),
-            Some("Output")
-        );
-        assert_eq!(
-            bus.flags,
-            vst3::Steinberg::Vst::BusInfo_::BusFlags_::kDefaultActive
-        );
+    fn new(_max_samples_per_process_call: usize, _sampling_rate: f32) -> Self {
+        SilentVoice {}
+    }
+
+    fn handle_event(&mut self, _event: &EventData) {}
+
+    fn process(
+        &mut self,
+        _events: impl IntoIterator<Item = conformal_poly::Event>,
+        _params: &impl BufferStates,
+        _note_expressions: NoteExpressionCurve<impl Iterator<Item = NoteExpressionPoint> + Clone>,
+        _data: Self::SharedData<'_>,
+        _output: &mut [f32],
+    ) -> usize {
+        0
+    }
+
+    fn quiescent(&self) -> bool {
+        true
+    }
+
+    fn reset(&mut self) {}
+}
+
+/// A synth with one voice per midi channel, that records which note each voice
+/// is playing after each processing call.
+struct ChannelPoolSynth<'a> {
+    poly: Poly<SilentVoice>,
+    voice_notes: &'a RefCell<Vec<Option<NoteData>>>,
+}
+
+impl Processor for ChannelPoolSynth<'_> {
+    fn set_processing(&mut self, _processing: bool) {}
+}
+
+impl Synth for ChannelPoolSynth<'_> {
+    fn handle_events<E: Iterator<Item = Data> + Clone, P: States>(
+        &mut self,
+        events: E,
+        _parameters: P,
+    ) {
+        self.poly.handle_events(events);
+    }
+
+    fn process<E: Iterator<Item = Event> + Clone, P: BufferStates, O: BufferMut>(
+        &mut self,
+        events: Events<E>,
+        parameters: P,
+        output: &mut O,
+    ) {
+        self.poly
+            .process(events.into_iter(), &parameters, &(), output);
+        self.voice_notes
+            .replace(self.poly.voice_notes().map(|(_, note)| note).collect());
+    }
+}
+
+/// A synth that outputs a constant that depends on which host it's running in.
+struct HostAwareSynth {
+    level: f32,
+}
+
+impl Processor for HostAwareSynth {
+    fn set_processing(&mut self, _processing: bool) {}
+}
+
+impl Synth for HostAwareSynth {
+    fn handle_events<E: IntoIterator<Item = Data>, P: States>(
+        &mut self,
+        _events: E,
+        _parameters: P,
+    ) {
+    }
+
+    fn process<E: Iterator<Item = Event>, P: BufferStates, O: BufferMut>(
+        &mut self,
+        _events: Events<E>,
+        _parameters: P,
+        output: &mut O,
+    ) {
+        for channel in channels_mut(output) {
+            channel.fill(self.level);
+        }
+    }
+}
+
+/// An analyzer that records the peak level of its input.
+struct PeakAnalyzer<'a> {
+    peak: &'a RefCell<f32>,
+}
+
+impl Processor for PeakAnalyzer<'_> {
+    fn set_processing(&mut self, _processing: bool) {}
+}
+
+impl Analyzer for PeakAnalyzer<'_> {
+    fn handle_parameters<P: conformal_component::parameters::States>(&mut self, _parameters: P) {}
+
+    fn analyze<P: BufferStates, I: conformal_component::audio::Buffer>(
+        &mut self,
+        _parameters: P,
+        input: &I,
+    ) {
+        let mut peak = self.peak.borrow_mut();
+        for channel in channels(input) {
+            for sample in channel {
+                *peak = peak.max(sample.abs());
+            }
+        }
+    }
+}
+
+/// An effect with an event input that records all events it processes.
+struct EventRecordingEffect<'a> {
+    events: &'a RefCell<Vec<Event>>,
+}
+
+impl Processor for EventRecordingEffect<'_> {
+    fn set_processing(&mut self, _processing: bool) {}
+}
+
+impl Effect for EventRecordingEffect<'_> {
+    fn handle_parameters<P: States>(&mut self, _parameters: P) {}
+
+    fn process<
+        P: BufferStates,
+        I: conformal_component::audio::Buffer,
+        O: conformal_component::audio::BufferMut,
+    >(
+        &mut self,
+        _parameters: P,
+        _input: &I,
+        _output: &mut O,
+    ) {
+    }
+
+    fn process_with_events<
+        E: Iterator<Item = Event> + Clone,
+        P: BufferStates,
+        I: conformal_component::audio::Buffer,
+        O: conformal_component::audio::BufferMut,
+    >(
+        &mut self,
+        events: Events<E>,
+        _parameters: P,
+        _input: &I,
+        _output: &mut O,
+    ) {
+        self.events.borrow_mut().extend(events);
+    }
+}
+
+const EVENT_INPUT_EFFECT_OPTIONS: EffectOptions = EffectOptions {
+    event_input: true,
+    ..EffectOptions::DEFAULT
+};
+
+#[test]
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn reports_processor_latency() {
+    // The effect reports a millisecond of latency.
+    let proc = create_effect(
+        |_: &HostInfo| {
+            FakeComponent::new(|env| FakeEffect {
+                latency_samples: (env.sampling_rate / 1000.0).round() as usize,
+                ..Default::default()
+            })
+        },
+        [4; 16],
+        SWITCH_ID,
+        EffectOptions::DEFAULT,
+    );
+    let host = ComWrapper::new(dummy_host::Host::default());
+
+    unsafe {
+        assert_eq!(proc.getLatencySamples(), 0);
+
+        setup_proc_effect(&proc, &host);
+        assert_eq!(proc.getLatencySamples(), 44);
+
+        // Latency can change with the processing environment.
+        assert_eq!(proc.setProcessing(0u8), vst3::Steinberg::kResultOk);
+        assert_eq!(proc.setActive(0u8), vst3::Steinberg::kResultOk);
         assert_eq!(
-            proc.getBusInfo(
-                vst3::Steinberg::Vst::MediaTypes_::kEvent as i32,
-                vst3::Steinberg::Vst::BusDirections_::kInput as i32,
-                0,
-                &mut bus
-            ),
+            proc.setupProcessing(&mut process_setup(&PartialProcessingEnvironment {
+                sampling_rate: 96000.0,
+                ..DEFAULT_ENV
+            })),
             vst3::Steinberg::kResultOk
         );
-        assert_eq!(
-            bus.mediaType,
-            vst3::Steinberg::Vst::MediaTypes_::kEvent as i32
-        );
-        assert_eq!(
-            bus.direction,
-            vst3::Steinberg::Vst::BusDirections_::kInput as i32
-        );
-        assert_eq!(
-            from_utf16_buffer(&bus.name)
-                .as_ref()
-                .map(|x: &String| x.as_str()),
-            Some("Event In")
-        );
-        assert_eq!(
-            bus.flags,
-            vst3::Steinberg::Vst::BusInfo_::BusFlags_::kDefaultActive
-        );
+        assert_eq!(proc.setActive(1u8), vst3::Steinberg::kResultOk);
+        assert_eq!(proc.getLatencySamples(), 96);
+    }
+}
+
+fn tail_samples_for(tail_samples: usize) -> vst3::Steinberg::uint32 {
+    let proc = create_effect(
+        move |_: &HostInfo| {
+            FakeComponent::new(move |_| FakeEffect {
+                tail_samples,
+                ..Default::default()
+            })
+        },
+        [4; 16],
+        SWITCH_ID,
+        EffectOptions::DEFAULT,
+    );
+    let host = ComWrapper::new(dummy_host::Host::default());
+
+    unsafe {
+        assert_eq!(proc.getTailSamples(), vst3::Steinberg::Vst::kNoTail);
+        setup_proc_effect(&proc, &host);
+        proc.getTailSamples()
     }
 }
 
 #[test]
-fn bus_info_effect() {
-    let proc = dummy_effect();
+fn reports_processor_tail() {
+    assert_eq!(tail_samples_for(0), vst3::Steinberg::Vst::kNoTail);
+    assert_eq!(tail_samples_for(48000), 48000);
+}
+
+#[test]
+fn reports_infinite_tail() {
+    assert_eq!(
+        tail_samples_for(INFINITE_TAIL_SAMPLES),
+        vst3::Steinberg::Vst::kInfiniteTail
+    );
+}
+
+/// A stereo delay that outputs only its echo.
+struct DelayEffect {
+    lines: [Vec<f32>; 2],
+    position: usize,
+}
+
+impl Processor for DelayEffect {
+    fn set_processing(&mut self, _processing: bool) {
+        for line in &mut self.lines {
+            line.fill(0.0);
+        }
+        self.position = 0;
+    }
+
+    fn tail_samples(&self) -> usize {
+        self.lines[0].len()
+    }
+}
+
+impl Effect for DelayEffect {
+    fn handle_parameters<P: conformal_component::parameters::States>(&mut self, _: P) {}
+
+    fn process<
+        P: conformal_component::parameters::BufferStates,
+        I: conformal_component::audio::Buffer,
+        O: conformal_component::audio::BufferMut,
+    >(
+        &mut self,
+        _: P,
+        input: &I,
+        output: &mut O,
+    ) {
+        let start = self.position;
+        for ((input, output), line) in channels(input)
+            .zip(channels_mut(output))
+            .zip(self.lines.iter_mut())
+        {
+            let mut position = start;
+            for (input, output) in input.iter().zip(output.iter_mut()) {
+                *output = line[position];
+                line[position] = *input;
+                position = (position + 1) % line.len();
+            }
+            self.position = position;
+        }
+    }
+}
+
+const ECHO_DELAY_SAMPLES: usize = 1000;
+
+impl Default for DelayEffect {
+    fn default() -> Self {
+        DelayEffect {
+            lines: [vec![0.0; ECHO_DELAY_SAMPLES], vec![0.0; ECHO_DELAY_SAMPLES]],
+            position: 0,
+        }
+    }
+}
+
+#[test]
+fn offline_render_includes_tail_after_input_ends() {
+    let proc = create_effect(
+        |_: &HostInfo| FakeComponent::new(|_| DelayEffect::default()),
+        [4; 16],
+        SWITCH_ID,
+        EffectOptions::DEFAULT,
+    );
+    let host = ComWrapper::new(dummy_host::Host::default());
 
     unsafe {
-        let mut bus = vst3::Steinberg::Vst::BusInfo {
-            mediaType: 0,
-            direction: 0,
-            channelCount: 0,
-            name: [0; 128],
-            busType: 0,
-            flags: 0,
-        };
-        assert_eq!(
-            proc.getBusCount(
-                vst3::Steinberg::Vst::MediaTypes_::kAudio as i32,
-                vst3::Steinberg::Vst::BusDirections_::kOutput as i32
-            ),
-            1
-        );
+        setup_proc_effect(&proc, &host);
+        assert_eq!(proc.setProcessing(0u8), vst3::Steinberg::kResultOk);
+        assert_eq!(proc.setActive(0u8), vst3::Steinberg::kResultOk);
         assert_eq!(
-            proc.getBusCount(
-                vst3::Steinberg::Vst::MediaTypes_::kEvent as i32,
-                vst3::Steinberg::Vst::BusDirections_::kOutput as i32
-            ),
-            0
-        );
-        assert_eq!(
-            proc.getBusCount(
-                vst3::Steinberg::Vst::MediaTypes_::kAudio as i32,
-                vst3::Steinberg::Vst::BusDirections_::kInput as i32
-            ),
-            1
+            proc.setupProcessing(&mut process_setup(&PartialProcessingEnvironment {
+                processing_mode: ProcessingMode::Offline,
+                ..DEFAULT_ENV
+            })),
+            vst3::Steinberg::kResultOk
         );
+        assert_eq!(proc.setActive(1u8), vst3::Steinberg::kResultOk);
+        assert_eq!(proc.setProcessing(1u8), vst3::Steinberg::kResultOk);
+
+        let tail_samples = proc.getTailSamples() as usize;
+        assert_eq!(tail_samples, ECHO_DELAY_SAMPLES);
+
+        // The input is a single impulse, much shorter than the delay time.
+        let mut input = vec![0.0; SAMPLE_COUNT];
+        input[0] = 1.0;
+        let mut rendered = mock_process_effect(vec![input.clone(), input], vec![], &proc)
+            .unwrap()
+            .remove(0);
+
+        // Once the input ends, the host keeps processing silence for the reported tail.
+        let silence = vec![0.0; SAMPLE_COUNT];
+        while rendered.len() < SAMPLE_COUNT + tail_samples {
+            rendered.extend(
+                mock_process_effect(vec![silence.clone(), silence.clone()], vec![], &proc)
+                    .unwrap()
+                    .remove(0),
+            );
+        }
+
+        for (index, sample) in rendered.iter().enumerate() {
+            let expected = if index == ECHO_DELAY_SAMPLES {
+                1.0
+            } else {
+                0.0
+            };
+            assert_approx_eq!(*sample, expected);
+        }
+    }
+}
+
+/// A delay with a soft bypass, so its echoes keep ringing while it's bypassed.
+struct SoftBypassDelayEffect {
+    delay: DelayEffect,
+    bypass: SoftBypass,
+}
+
+impl Processor for SoftBypassDelayEffect {
+    fn set_processing(&mut self, processing: bool) {
+        self.delay.set_processing(processing);
+        self.bypass.reset();
+    }
+
+    fn tail_samples(&self) -> usize {
+        self.delay.tail_samples()
+    }
+}
+
+impl Effect for SoftBypassDelayEffect {
+    fn handle_parameters<P: conformal_component::parameters::States>(&mut self, _: P) {}
+
+    fn process<
+        P: conformal_component::parameters::BufferStates,
+        I: conformal_component::audio::Buffer,
+        O: conformal_component::audio::BufferMut,
+    >(
+        &mut self,
+        parameters: P,
+        input: &I,
+        output: &mut O,
+    ) {
+        let bypass: Vec<_> =
+            switch_per_sample(parameters.get_switch(BYPASS_PARAMETER).unwrap()).collect();
+        self.delay.process(parameters, input, output);
+        self.bypass.process(bypass, input, output);
+    }
+}
+
+fn bypass_queue(bypassed: bool) -> ParameterValueQueueImpl {
+    ParameterValueQueueImpl {
+        param_id: SWITCH_ID.to_string(),
+        points: vec![ParameterValueQueuePoint {
+            sample_offset: 0,
+            value: if bypassed { 1.0 } else { 0.0 },
+        }],
+    }
+}
+
+#[test]
+fn soft_bypass_preserves_tail() {
+    let proc = create_effect(
+        |_: &HostInfo| {
+            FakeComponent::new(|_| SoftBypassDelayEffect {
+                delay: DelayEffect::default(),
+                bypass: SoftBypass::new_with_ramp_samples(1),
+            })
+        },
+        [4; 16],
+        SWITCH_ID,
+        EffectOptions::DEFAULT,
+    );
+    let host = ComWrapper::new(dummy_host::Host::default());
+
+    unsafe {
+        setup_proc_effect(&proc, &host);
+
+        // An impulse at the end of the first buffer echoes in the third buffer.
+        let mut input = vec![0.0; SAMPLE_COUNT];
+        input[SAMPLE_COUNT - 1] = 1.0;
+        let silence = vec![0.0; SAMPLE_COUNT];
+        let output =
+            mock_process_effect(vec![input.clone(), input], vec![bypass_queue(false)], &proc)
+                .unwrap();
+        assert!(output.iter().flatten().all(|x| *x == 0.0));
+
+        // While bypassed, the output is dry but we still report the tail.
+        let output = mock_process_effect(
+            vec![silence.clone(), silence.clone()],
+            vec![bypass_queue(true)],
+            &proc,
+        )
+        .unwrap();
+        assert!(output.iter().flatten().all(|x| *x == 0.0));
+        assert_eq!(proc.getTailSamples() as usize, ECHO_DELAY_SAMPLES);
+
+        // Once un-bypassed, the echo that rang out during the bypass is still there.
+        let output = mock_process_effect(
+            vec![silence.clone(), silence],
+            vec![bypass_queue(false)],
+            &proc,
+        )
+        .unwrap();
+        assert_eq!(proc.getTailSamples() as usize, ECHO_DELAY_SAMPLES);
+        let echo_index = ECHO_DELAY_SAMPLES + SAMPLE_COUNT - 1 - 2 * SAMPLE_COUNT;
+        for channel in output {
+            for (index, sample) in channel.iter().enumerate() {
+                let expected = if index == echo_index { 1.0 } else { 0.0 };
+                assert_approx_eq!(*sample, expected);
+            }
+        }
+    }
+}
+
+#[test]
+fn can_process_f32() {
+    let proc = dummy_synth();
+
+    unsafe {
         assert_eq!(
-            proc.getBusCount(
-                vst3::Steinberg::Vst::MediaTypes_::kEvent as i32,
-                vst3::Steinberg::Vst::BusDirections_::kInput as i32
-            ),
-            0
+            proc.canProcessSampleSize(vst3::Steinberg::Vst::SymbolicSampleSizes_::kSample32 as i32),
+            vst3::Steinberg::kResultTrue
         );
+    }
+}
+
+#[test]
+fn cannot_process_f64() {
+    let proc = dummy_synth();
 
+    unsafe {
         assert_eq!(
-            proc.getBusInfo(
-                vst3::Steinberg::Vst::MediaTypes_::kAudio as i32,
-                vst3::Steinberg::Vst::BusDirections_::kOutput as i32,
-                0,
-                &mut bus
-            ),
-            vst3::Steinberg::kResultOk
+            proc.canProcessSampleSize(vst3::Steinberg::Vst::SymbolicSampleSizes_::kSample64 as i32),
+            vst3::Steinberg::kResultFalse
         );
+    }
+}
+
+struct DoublingEffect {}
+
+impl Processor for DoublingEffect {
+    const SUPPORTS_DOUBLE_PRECISION: bool = true;
+
+    fn set_processing(&mut self, _processing: bool) {}
+}
+
+impl Effect for DoublingEffect {
+    fn handle_parameters<P: conformal_component::parameters::States>(&mut self, _: P) {}
+
+    fn process<
+        P: conformal_component::parameters::BufferStates,
+        I: conformal_component::audio::Buffer,
+        O: conformal_component::audio::BufferMut,
+    >(
+        &mut self,
+        _: P,
+        input: &I,
+        output: &mut O,
+    ) {
+        for (input, output) in channels(input).zip(channels_mut(output)) {
+            for (input, output) in input.iter().zip(output.iter_mut()) {
+                *output = *input * 2.0;
+            }
+        }
+    }
+
+    fn process_f64<
+        P: conformal_component::parameters::BufferStates,
+        I: conformal_component::audio::Buffer<f64>,
+        O: conformal_component::audio::BufferMut<f64>,
+    >(
+        &mut self,
+        _: P,
+        input: &I,
+        output: &mut O,
+    ) {
+        for (input, output) in channels(input).zip(channels_mut(output)) {
+            for (input, output) in input.iter().zip(output.iter_mut()) {
+                *output = *input * 2.0;
+            }
+        }
+    }
+}
+
+#[test]
+fn double_precision_effect_processes_f64() {
+    let proc = create_effect(
+        |_: &HostInfo| FakeComponent::new(|_| DoublingEffect {}),
+        [4; 16],
+        SWITCH_ID,
+        EffectOptions::DEFAULT,
+    );
+    let host = ComWrapper::new(dummy_host::Host::default());
+
+    unsafe {
+        setup_proc_effect(&proc, &host);
         assert_eq!(
-            bus.mediaType,
-            vst3::Steinberg::Vst::MediaTypes_::kAudio as i32
+            proc.canProcessSampleSize(vst3::Steinberg::Vst::SymbolicSampleSizes_::kSample64 as i32),
+            vst3::Steinberg::kResultTrue
         );
+
+        // This value can't be represented in 32-bit, so we check that no precision is lost.
+        let input = 1.0 + 1e-12;
+        let audio = mock_process_effect_f64(vec![vec![input; 512]; 2], vec![], &proc).unwrap();
+        assert!(audio.iter().flatten().all(|x| *x == input * 2.0));
+
+        // Hosts may still process in 32-bit.
+        let audio = mock_process_effect(vec![vec![1f32; 512]; 2], vec![], &proc).unwrap();
+        assert!(audio.iter().flatten().all(|x| *x == 2.0));
+    }
+}
+
+#[test]
+fn effect_without_double_precision_rejects_f64() {
+    let proc = dummy_effect();
+    let host = ComWrapper::new(dummy_host::Host::default());
+
+    unsafe {
+        setup_proc_effect(&proc, &host);
         assert_eq!(
-            bus.direction,
-            vst3::Steinberg::Vst::BusDirections_::kOutput as i32
+            proc.canProcessSampleSize(vst3::Steinberg::Vst::SymbolicSampleSizes_::kSample64 as i32),
+            vst3::Steinberg::kResultFalse
         );
-        // Note we are locked into stereo for now
-        assert_eq!(bus.channelCount, 2);
+        assert!(mock_process_effect_f64(vec![vec![1f64; 512]; 2], vec![], &proc).is_none());
+    }
+}
+
+fn matches(partial: &PartialProcessingEnvironment, full: &ProcessingEnvironment) -> bool {
+    partial.sampling_rate == full.sampling_rate
+        && partial.max_samples_per_process_call == full.max_samples_per_process_call
+        && partial.processing_mode == full.processing_mode
+}
+
+#[test]
+fn defends_against_setup_processing_before_initialize() {
+    let proc = dummy_synth();
+    unsafe {
+        assert_ne!(
+            proc.setupProcessing(&mut process_setup(&DEFAULT_ENV)),
+            vst3::Steinberg::kResultOk
+        );
+    }
+}
+
+#[test]
+fn correct_bus_count() {
+    let proc = dummy_synth();
+    let host = ComWrapper::new(dummy_host::Host::default())
+        .to_com_ptr::<IHostApplication>()
+        .unwrap();
+
+    unsafe {
         assert_eq!(
-            from_utf16_buffer(&bus.name)
-                .as_ref()
-                .map(|x: &String| x.as_str()),
-            Some("Output")
+            proc.initialize(host.cast().unwrap().as_ptr()),
+            vst3::Steinberg::kResultOk
         );
+
         assert_eq!(
-            bus.flags,
-            vst3::Steinberg::Vst::BusInfo_::BusFlags_::kDefaultActive
+            proc.setupProcessing(&mut process_setup(&DEFAULT_ENV)),
+            vst3::Steinberg::kResultOk
         );
         assert_eq!(
-            proc.getBusInfo(
+            proc.getBusCount(
                 vst3::Steinberg::Vst::MediaTypes_::kAudio as i32,
-                vst3::Steinberg::Vst::BusDirections_::kInput as i32,
-                0,
-                &mut bus
+                vst3::Steinberg::Vst::BusDirections_::kInput as i32
             ),
-            vst3::Steinberg::kResultOk
+            0
         );
         assert_eq!(
-            bus.mediaType,
-            vst3::Steinberg::Vst::MediaTypes_::kAudio as i32
+            proc.getBusCount(
+                vst3::Steinberg::Vst::MediaTypes_::kAudio as i32,
+                vst3::Steinberg::Vst::BusDirections_::kOutput as i32
+            ),
+            1
         );
         assert_eq!(
-            bus.direction,
-            vst3::Steinberg::Vst::BusDirections_::kInput as i32
+            proc.getBusCount(
+                vst3::Steinberg::Vst::MediaTypes_::kEvent as i32,
+                vst3::Steinberg::Vst::BusDirections_::kInput as i32
+            ),
+            1
         );
         assert_eq!(
-            from_utf16_buffer(&bus.name)
-                .as_ref()
-                .map(|x: &String| x.as_str()),
-            Some("Input")
+            proc.getBusCount(
+                vst3::Steinberg::Vst::MediaTypes_::kEvent as i32,
+                vst3::Steinberg::Vst::BusDirections_::kOutput as i32
+            ),
+            0
         );
-        assert_eq!(
-            bus.flags,
-            vst3::Steinberg::Vst::BusInfo_::BusFlags_::kDefaultActive
+    }
+}
+
+#[test]
+fn bus_info_invalid_bus() {
+    let proc = dummy_synth();
+
+    for &(type_, dir, ind) in &[
+        (
+            vst3::Steinberg::Vst::MediaTypes_::kAudio,
+            vst3::Steinberg::Vst::BusDirections_::kInput,
+            0,
+        ),
+        (
+            vst3::Steinberg::Vst::MediaTypes_::kEvent,
+            vst3::Steinberg::Vst::BusDirections_::kOutput,
+            0,
+        ),
+        (
+            vst3::Steinberg::Vst::MediaTypes_::kEvent,
+            vst3::Steinberg::Vst::BusDirections_::kInput,
+            1,
+        ),
+        (
+            vst3::Steinberg::Vst::MediaTypes_::kAudio,
+            vst3::Steinberg::Vst::BusDirections_::kOutput,
+            1,
+        ),
+    ] {
+        unsafe {
+            let mut bus = vst3::Steinberg::Vst::BusInfo {
+                mediaType: 0,
+                direction: 0,
+                channelCount: 0,
+                name: [0; 128],
+                busType: 0,
+                flags: 0,
+            };
+            assert_eq!(
+                proc.getBusInfo(type_ as i32, dir as i32, ind, &mut bus),
+                vst3::Steinberg::kInvalidArgument
+            );
+        }
+    }
+}
+
+#[test]
+fn bus_info() {
+    let proc = dummy_synth();
+
+    unsafe {
+        assert_eq!(
+            proc.getBusCount(
+                vst3::Steinberg::Vst::MediaTypes_::kAudio as i32,
+                vst3::Steinberg::Vst::BusDirections_::kOutput as i32
+            ),
+            1
+        );
+        assert_eq!(
+            proc.getBusCount(
+                vst3::Steinberg::Vst::MediaTypes_::kEvent as i32,
+                vst3::Steinberg::Vst::BusDirections_::kOutput as i32
+            ),
+            0
+        );
+        assert_eq!(
+            proc.getBusCount(
+                vst3::Steinberg::Vst::MediaTypes_::kAudio as i32,
+                vst3::Steinberg::Vst::BusDirections_::kInput as i32
+            ),
+            0
+        );
+        assert_eq!(
+            proc.getBusCount(
+                vst3::Steinberg::Vst::MediaTypes_::kEvent as i32,
+                vst3::Steinberg::Vst::BusDirections_::kInput as i32
+            ),
+            1
+        );
+
+        let mut bus = vst3::Steinberg::Vst::BusInfo {
+            mediaType: 0,
+            direction: 0,
+            channelCount: 0,
+            name: [0; 128],
+            busType: 0,
+            flags: 0,
+        };
+        assert_eq!(
+            proc.getBusInfo(
+                vst3::Steinberg::Vst::MediaTypes_::kAudio as i32,
+                vst3::Steinberg::Vst::BusDirections_::kOutput as i32,
+                0,
+                &mut bus
+            ),
+            vst3::Steinberg::kResultOk
+        );
+        assert_eq!(
+            bus.mediaType,
+            vst3::Steinberg::Vst::MediaTypes_::kAudio as i32
+        );
+        assert_eq!(
+            bus.direction,
+            vst3::Steinberg::Vst::BusDirections_::kOutput as i32
+        );
+        // Note we are locked into stereo for now
+        assert_eq!(bus.channelCount, 2);
+        assert_eq!(
+            from_utf16_buffer(&bus.name)
+                .as_ref()
+                .map(|x: &String| x.as_str()),
+            Some("Output")
+        );
+        assert_eq!(
+            bus.flags,
+            vst3::Steinberg::Vst::BusInfo_::BusFlags_::kDefaultActive
+        );
+        assert_eq!(
+            proc.getBusInfo(
+                vst3::Steinberg::Vst::MediaTypes_::kEvent as i32,
+                vst3::Steinberg::Vst::BusDirections_::kInput as i32,
+                0,
+                &mut bus
+            ),
+            vst3::Steinberg::kResultOk
+        );
+        assert_eq!(
+            bus.mediaType,
+            vst3::Steinberg::Vst::MediaTypes_::kEvent as i32
+        );
+        assert_eq!(
+            bus.direction,
+            vst3::Steinberg::Vst::BusDirections_::kInput as i32
+        );
+        assert_eq!(
+            from_utf16_buffer(&bus.name)
+                .as_ref()
+                .map(|x: &String| x.as_str()),
+            Some("Event In")
+        );
+        assert_eq!(
+            bus.flags,
+            vst3::Steinberg::Vst::BusInfo_::BusFlags_::kDefaultActive
+        );
+    }
+}
+
+#[test]
+fn bus_info_effect() {
+    let proc = dummy_effect();
+
+    unsafe {
+        let mut bus = vst3::Steinberg::Vst::BusInfo {
+            mediaType: 0,
+            direction: 0,
+            channelCount: 0,
+            name: [0; 128],
+            busType: 0,
+            flags: 0,
+        };
+        assert_eq!(
+            proc.getBusCount(
+                vst3::Steinberg::Vst::MediaTypes_::kAudio as i32,
+                vst3::Steinberg::Vst::BusDirections_::kOutput as i32
+            ),
+            1
+        );
+        assert_eq!(
+            proc.getBusCount(
+                vst3::Steinberg::Vst::MediaTypes_::kEvent as i32,
+                vst3::Steinberg::Vst::BusDirections_::kOutput as i32
+            ),
+            0
+        );
+        assert_eq!(
+            proc.getBusCount(
+                vst3::Steinberg::Vst::MediaTypes_::kAudio as i32,
+                vst3::Steinberg::Vst::BusDirections_::kInput as i32
+            ),
+            1
+        );
+        assert_eq!(
+            proc.getBusCount(
+                vst3::Steinberg::Vst::MediaTypes_::kEvent as i32,
+                vst3::Steinberg::Vst::BusDirections_::kInput as i32
+            ),
+            0
+        );
+
+        assert_eq!(
+            proc.getBusInfo(
+                vst3::Steinberg::Vst::MediaTypes_::kAudio as i32,
+                vst3::Steinberg::Vst::BusDirections_::kOutput as i32,
+                0,
+                &mut bus
+            ),
+            vst3::Steinberg::kResultOk
+        );
+        assert_eq!(
+            bus.mediaType,
+            vst3::Steinberg::Vst::MediaTypes_::kAudio as i32
+        );
+        assert_eq!(
+            bus.direction,
+            vst3::Steinberg::Vst::BusDirections_::kOutput as i32
+        );
+        // Note we are locked into stereo for now
+        assert_eq!(bus.channelCount, 2);
+        assert_eq!(
+            from_utf16_buffer(&bus.name)
+                .as_ref()
+                .map(|x: &String| x.as_str()),
+            Some("Output")
+        );
+        assert_eq!(
+            bus.flags,
+            vst3::Steinberg::Vst::BusInfo_::BusFlags_::kDefaultActive
+        );
+        assert_eq!(
+            proc.getBusInfo(
+                vst3::Steinberg::Vst::MediaTypes_::kAudio as i32,
+                vst3::Steinberg::Vst::BusDirections_::kInput as i32,
+                0,
+                &mut bus
+            ),
+            vst3::Steinberg::kResultOk
+        );
+        assert_eq!(
+            bus.mediaType,
+            vst3::Steinberg::Vst::MediaTypes_::kAudio as i32
+        );
+        assert_eq!(
+            bus.direction,
+            vst3::Steinberg::Vst::BusDirections_::kInput as i32
+        );
+        assert_eq!(
+            from_utf16_buffer(&bus.name)
+                .as_ref()
+                .map(|x: &String| x.as_str()),
+            Some("Input")
+        );
+        assert_eq!(
+            bus.flags,
+            vst3::Steinberg::Vst::BusInfo_::BusFlags_::kDefaultActive
+        );
+    }
+}
+
+#[test]
+fn initialize_gets_host_info() {
+    let host_info = Default::default();
+    let host = ComWrapper::new(dummy_host::Host::default())
+        .to_com_ptr::<IHostApplication>()
+        .unwrap();
+    let proc = dummy_synth_with_host_info(&host_info);
+    unsafe {
+        assert_eq!(
+            proc.initialize(host.cast().unwrap().as_ptr()),
+            vst3::Steinberg::kResultOk
+        )
+    }
+    assert_eq!(
+        host_info.borrow().as_ref(),
+        Some(&HostInfo {
+            name: "Dummy Host".to_string()
+        })
+    );
+    unsafe { assert_eq!(proc.terminate(), vst3::Steinberg::kResultOk) }
+}
+
+#[test]
+fn defends_against_termination_before_initialization() {
+    let proc = dummy_synth();
+    unsafe { assert_ne!(proc.terminate(), vst3::Steinberg::kResultOk) }
+}
+
+#[test]
+fn allow_initialize_after_termination_before_initialization() {
+    let proc = dummy_synth();
+    let host = ComWrapper::new(dummy_host::Host::default())
+        .to_com_ptr::<IHostApplication>()
+        .unwrap();
+    unsafe { assert_ne!(proc.terminate(), vst3::Steinberg::kResultOk) }
+    unsafe {
+        assert_eq!(
+            proc.initialize(host.cast().unwrap().as_ptr()),
+            vst3::Steinberg::kResultOk
+        )
+    }
+}
+
+#[test]
+fn defends_against_initialize_twice() {
+    let proc = dummy_synth();
+    let host = ComWrapper::new(dummy_host::Host::default())
+        .to_com_ptr::<IHostApplication>()
+        .unwrap();
+    unsafe {
+        assert_eq!(
+            proc.initialize(host.cast().unwrap().as_ptr()),
+            vst3::Steinberg::kResultOk
+        )
+    }
+    unsafe {
+        assert_ne!(
+            proc.initialize(host.cast().unwrap().as_ptr()),
+            vst3::Steinberg::kResultOk
+        )
+    }
+}
+
+#[test]
+fn allow_initialize_twice_with_terminate() {
+    let proc = dummy_synth();
+    let host = ComWrapper::new(dummy_host::Host::default())
+        .to_com_ptr::<IHostApplication>()
+        .unwrap();
+    unsafe {
+        assert_eq!(
+            proc.initialize(host.cast().unwrap().as_ptr()),
+            vst3::Steinberg::kResultOk
+        )
+    }
+    unsafe { assert_eq!(proc.terminate(), vst3::Steinberg::kResultOk) }
+    unsafe {
+        assert_eq!(
+            proc.initialize(host.cast().unwrap().as_ptr()),
+            vst3::Steinberg::kResultOk
+        )
+    }
+}
+
+#[test]
+fn defends_against_activating_wrong_bus() {
+    let proc = dummy_synth();
+    let host = ComWrapper::new(dummy_host::Host::default())
+        .to_com_ptr::<IHostApplication>()
+        .unwrap();
+
+    unsafe {
+        assert_eq!(
+            proc.initialize(host.cast().unwrap().as_ptr()),
+            vst3::Steinberg::kResultOk
+        );
+        assert_ne!(
+            proc.activateBus(
+                vst3::Steinberg::Vst::MediaTypes_::kAudio as i32,
+                vst3::Steinberg::Vst::BusDirections_::kInput as i32,
+                0,
+                1
+            ),
+            vst3::Steinberg::kResultOk
+        );
+    }
+}
+
+#[test]
+fn defends_against_effect_activating_wrong_bus() {
+    let proc = dummy_effect();
+    let host = ComWrapper::new(dummy_host::Host::default())
+        .to_com_ptr::<IHostApplication>()
+        .unwrap();
+
+    unsafe {
+        assert_eq!(
+            proc.initialize(host.cast().unwrap().as_ptr()),
+            vst3::Steinberg::kResultOk
+        );
+        assert_ne!(
+            proc.activateBus(
+                vst3::Steinberg::Vst::MediaTypes_::kEvent as i32,
+                vst3::Steinberg::Vst::BusDirections_::kOutput as i32,
+                0,
+                1
+            ),
+            vst3::Steinberg::kResultOk
+        );
+    }
+}
+
+#[test]
+fn captures_process_environment() {
+    let env = Default::default();
+    let proc = dummy_synth_with_processing_environment(&env);
+
+    let host = ComWrapper::new(dummy_host::Host::default())
+        .to_com_ptr::<IHostApplication>()
+        .unwrap();
+
+    unsafe {
+        assert_eq!(
+            proc.initialize(host.cast().unwrap().as_ptr()),
+            vst3::Steinberg::kResultOk
+        );
+
+        activate_busses(&proc);
+    }
+
+    for test_env in [
+        PartialProcessingEnvironment {
+            sampling_rate: 48000.0,
+            max_samples_per_process_call: 512,
+            processing_mode: ProcessingMode::Realtime,
+        },
+        PartialProcessingEnvironment {
+            sampling_rate: 44100.0,
+            max_samples_per_process_call: 8192,
+            processing_mode: ProcessingMode::Offline,
+        },
+        PartialProcessingEnvironment {
+            sampling_rate: 96000.0,
+            max_samples_per_process_call: 2048,
+            processing_mode: ProcessingMode::Prefetch,
+        },
+    ] {
+        unsafe {
+            assert_eq!(
+                proc.setupProcessing(&mut process_setup(&test_env)),
+                vst3::Steinberg::kResultOk
+            );
+            assert_eq!(proc.setActive(1u8), vst3::Steinberg::kResultOk);
+            assert!(matches(&test_env, env.borrow().as_ref().unwrap()));
+            assert_eq!(
+                env.borrow().as_ref().unwrap().host_name.as_deref(),
+                Some("Dummy Host")
+            );
+            assert_eq!(proc.setActive(0u8), vst3::Steinberg::kResultOk);
+        }
+    }
+}
+
+#[test]
+fn defends_against_activating_without_environment() {
+    let proc = dummy_synth();
+
+    let host = ComWrapper::new(dummy_host::Host::default())
+        .to_com_ptr::<IHostApplication>()
+        .unwrap();
+
+    unsafe {
+        assert_eq!(
+            proc.initialize(host.cast().unwrap().as_ptr()),
+            vst3::Steinberg::kResultOk
+        );
+        activate_busses(&proc);
+        assert_ne!(proc.setActive(1u8), vst3::Steinberg::kResultOk);
+    }
+}
+
+#[test]
+fn defends_against_activating_without_activating_busses() {
+    let proc = dummy_synth();
+
+    let host = ComWrapper::new(dummy_host::Host::default())
+        .to_com_ptr::<IHostApplication>()
+        .unwrap();
+
+    unsafe {
+        assert_eq!(
+            proc.initialize(host.cast().unwrap().as_ptr()),
+            vst3::Steinberg::kResultOk
+        );
+
+        assert_eq!(
+            proc.setupProcessing(&mut process_setup(&PartialProcessingEnvironment {
+                sampling_rate: 48000.0,
+                max_samples_per_process_call: 512,
+                processing_mode: ProcessingMode::Realtime,
+            })),
+            vst3::Steinberg::kResultOk
+        );
+
+        assert_ne!(proc.setActive(1u8), vst3::Steinberg::kResultOk);
+    }
+}
+
+#[test]
+fn defends_agsinst_effect_activating_without_activating_busses() {
+    let proc = dummy_effect();
+    let host = ComWrapper::new(dummy_host::Host::default())
+        .to_com_ptr::<IHostApplication>()
+        .unwrap();
+
+    unsafe {
+        assert_eq!(
+            proc.initialize(host.cast().unwrap().as_ptr()),
+            vst3::Steinberg::kResultOk
+        );
+
+        assert_eq!(
+            proc.setupProcessing(&mut process_setup(&PartialProcessingEnvironment {
+                sampling_rate: 48000.0,
+                max_samples_per_process_call: 512,
+                processing_mode: ProcessingMode::Realtime,
+            })),
+            vst3::Steinberg::kResultOk
+        );
+
+        assert_ne!(proc.setActive(1u8), vst3::Steinberg::kResultOk);
+    }
+}
+
+#[test]
+fn defends_against_changing_processing_environment_while_active() {
+    let proc = dummy_synth();
+
+    let host = ComWrapper::new(dummy_host::Host::default())
+        .to_com_ptr::<IHostApplication>()
+        .unwrap();
+
+    unsafe {
+        assert_eq!(
+            proc.initialize(host.cast().unwrap().as_ptr()),
+            vst3::Steinberg::kResultOk
+        );
+
+        assert_eq!(
+            proc.setupProcessing(&mut process_setup(&PartialProcessingEnvironment {
+                sampling_rate: 48000.0,
+                max_samples_per_process_call: 512,
+                processing_mode: ProcessingMode::Realtime,
+            })),
+            vst3::Steinberg::kResultOk
+        );
+        activate_busses(&proc);
+        assert_eq!(proc.setActive(1u8), vst3::Steinberg::kResultOk);
+        assert_ne!(
+            proc.setupProcessing(&mut process_setup(&PartialProcessingEnvironment {
+                sampling_rate: 44100.0,
+                max_samples_per_process_call: 512,
+                processing_mode: ProcessingMode::Realtime,
+            })),
+            vst3::Steinberg::kResultOk
+        );
+    }
+}
+
+#[test]
+fn defends_against_activating_without_initialization() {
+    let proc = dummy_synth();
+
+    unsafe {
+        assert_ne!(proc.setActive(1u8), vst3::Steinberg::kResultOk);
+    }
+}
+
+#[test]
+fn defends_against_activating_bus_while_active() {
+    let proc = dummy_synth();
+
+    let host = ComWrapper::new(dummy_host::Host::default())
+        .to_com_ptr::<IHostApplication>()
+        .unwrap();
+
+    unsafe {
+        assert_eq!(
+            proc.initialize(host.cast().unwrap().as_ptr()),
+            vst3::Steinberg::kResultOk
+        );
+
+        assert_eq!(
+            proc.setupProcessing(&mut process_setup(&PartialProcessingEnvironment {
+                sampling_rate: 48000.0,
+                max_samples_per_process_call: 512,
+                processing_mode: ProcessingMode::Realtime,
+            })),
+            vst3::Steinberg::kResultOk
+        );
+        activate_busses(&proc);
+        assert_eq!(proc.setActive(1u8), vst3::Steinberg::kResultOk);
+        assert_ne!(
+            proc.activateBus(
+                vst3::Steinberg::Vst::MediaTypes_::kAudio as i32,
+                vst3::Steinberg::Vst::BusDirections_::kOutput as i32,
+                0,
+                0
+            ),
+            vst3::Steinberg::kResultOk
+        );
+    }
+}
+
+#[test]
+fn defends_against_bad_input_count_for_bus_arrangement() {
+    let proc = dummy_synth();
+
+    let host = ComWrapper::new(dummy_host::Host::default())
+        .to_com_ptr::<IHostApplication>()
+        .unwrap();
+
+    unsafe {
+        assert_eq!(
+            proc.initialize(host.cast().unwrap().as_ptr()),
+            vst3::Steinberg::kResultOk
+        );
+
+        let mut in_arrangement = vst3::Steinberg::Vst::SpeakerArr::kMono;
+        let mut out_arrangement = vst3::Steinberg::Vst::SpeakerArr::kMono;
+        assert_ne!(
+            proc.setBusArrangements(&mut in_arrangement, 1, &mut out_arrangement, 1),
+            vst3::Steinberg::kResultOk
+        );
+
+        assert_ne!(
+            proc.getBusArrangement(
+                vst3::Steinberg::Vst::BusDirections_::kOutput as i32,
+                1,
+                &mut out_arrangement
+            ),
+            vst3::Steinberg::kResultOk
+        );
+
+        assert_ne!(
+            proc.getBusArrangement(
+                vst3::Steinberg::Vst::BusDirections_::kInput as i32,
+                0,
+                &mut out_arrangement
+            ),
+            vst3::Steinberg::kResultOk
+        );
+    }
+}
+
+#[test]
+fn defends_against_get_set_bus_before_init() {
+    let proc = dummy_synth();
+    unsafe {
+        let mut out_arrangement = vst3::Steinberg::Vst::SpeakerArr::kMono;
+        assert_ne!(
+            proc.setBusArrangements(std::ptr::null_mut(), 0, &mut out_arrangement, 1),
+            vst3::Steinberg::kResultOk
+        );
+        assert_ne!(
+            proc.getBusArrangement(
+                vst3::Steinberg::Vst::BusDirections_::kOutput as i32,
+                0,
+                &mut out_arrangement
+            ),
+            vst3::Steinberg::kResultOk
+        );
+    }
+}
+
+#[test]
+fn defends_against_set_bus_after_active() {
+    let proc = dummy_synth();
+
+    let host = ComWrapper::new(dummy_host::Host::default())
+        .to_com_ptr::<IHostApplication>()
+        .unwrap();
+
+    unsafe {
+        assert_eq!(
+            proc.initialize(host.cast().unwrap().as_ptr()),
+            vst3::Steinberg::kResultOk
+        );
+
+        let mut out_arrangement = vst3::Steinberg::Vst::SpeakerArr::kMono;
+        assert_eq!(
+            proc.setupProcessing(&mut process_setup(&PartialProcessingEnvironment {
+                sampling_rate: 48000.0,
+                max_samples_per_process_call: 512,
+                processing_mode: ProcessingMode::Realtime,
+            })),
+            vst3::Steinberg::kResultOk
+        );
+        activate_busses(&proc);
+        assert_eq!(proc.setActive(1u8), vst3::Steinberg::kResultOk);
+
+        assert_ne!(
+            proc.setBusArrangements(std::ptr::null_mut(), 0, &mut out_arrangement, 1),
+            vst3::Steinberg::kResultOk
+        );
+        // getBusArrangement should still work after setActive.
+        assert_eq!(
+            proc.getBusArrangement(
+                vst3::Steinberg::Vst::BusDirections_::kOutput as i32,
+                0,
+                &mut out_arrangement
+            ),
+            vst3::Steinberg::kResultOk
+        );
+    }
+}
+
+#[test]
+fn set_get_bus() {
+    let proc = dummy_synth();
+
+    let host = ComWrapper::new(dummy_host::Host::default())
+        .to_com_ptr::<IHostApplication>()
+        .unwrap();
+
+    unsafe {
+        assert_eq!(
+            proc.initialize(host.cast().unwrap().as_ptr()),
+            vst3::Steinberg::kResultOk
+        );
+
+        let mut out_arrangement = vst3::Steinberg::Vst::SpeakerArr::kMono;
+        assert_eq!(
+            proc.setupProcessing(&mut process_setup(&PartialProcessingEnvironment {
+                sampling_rate: 48000.0,
+                max_samples_per_process_call: 512,
+                processing_mode: ProcessingMode::Realtime,
+            })),
+            vst3::Steinberg::kResultOk
+        );
+        activate_busses(&proc);
+
+        assert_eq!(
+            proc.setBusArrangements(std::ptr::null_mut(), 0, &mut out_arrangement, 1),
+            vst3::Steinberg::kResultOk
+        );
+
+        out_arrangement = vst3::Steinberg::Vst::SpeakerArr::kStereo;
+        assert_eq!(
+            proc.getBusArrangement(
+                vst3::Steinberg::Vst::BusDirections_::kOutput as i32,
+                0,
+                &mut out_arrangement
+            ),
+            vst3::Steinberg::kResultOk
+        );
+        assert_eq!(out_arrangement, vst3::Steinberg::Vst::SpeakerArr::kMono);
+
+        out_arrangement = vst3::Steinberg::Vst::SpeakerArr::kStereo;
+        assert_eq!(
+            proc.setBusArrangements(std::ptr::null_mut(), 0, &mut out_arrangement, 1),
+            vst3::Steinberg::kResultOk
+        );
+        out_arrangement = vst3::Steinberg::Vst::SpeakerArr::kStereo;
+        assert_eq!(
+            proc.getBusArrangement(
+                vst3::Steinberg::Vst::BusDirections_::kOutput as i32,
+                0,
+                &mut out_arrangement
+            ),
+            vst3::Steinberg::kResultOk
+        );
+        assert_eq!(out_arrangement, vst3::Steinberg::Vst::SpeakerArr::kStereo);
+    }
+}
+
+#[test]
+fn set_get_bus_effect() {
+    let proc = dummy_effect();
+
+    let host = ComWrapper::new(dummy_host::Host::default())
+        .to_com_ptr::<IHostApplication>()
+        .unwrap();
+
+    unsafe {
+        assert_eq!(
+            proc.initialize(host.cast().unwrap().as_ptr()),
+            vst3::Steinberg::kResultOk
+        );
+        let mut in_arrangement = vst3::Steinberg::Vst::SpeakerArr::kMono;
+        let mut out_arrangement = vst3::Steinberg::Vst::SpeakerArr::kMono;
+
+        assert_eq!(
+            proc.setupProcessing(&mut process_setup(&PartialProcessingEnvironment {
+                sampling_rate: 48000.0,
+                max_samples_per_process_call: 512,
+                processing_mode: ProcessingMode::Realtime,
+            })),
+            vst3::Steinberg::kResultOk
+        );
+        activate_effect_busses(&proc);
+
+        assert_ne!(
+            proc.setBusArrangements(std::ptr::null_mut(), 0, &mut out_arrangement, 1),
+            vst3::Steinberg::kResultOk
+        );
+        assert_eq!(
+            proc.setBusArrangements(&mut in_arrangement, 1, &mut out_arrangement, 1),
+            vst3::Steinberg::kResultTrue
+        );
+
+        out_arrangement = vst3::Steinberg::Vst::SpeakerArr::kStereo;
+        assert_eq!(
+            proc.getBusArrangement(
+                vst3::Steinberg::Vst::BusDirections_::kOutput as i32,
+                0,
+                &mut out_arrangement
+            ),
+            vst3::Steinberg::kResultOk
+        );
+        assert_eq!(out_arrangement, vst3::Steinberg::Vst::SpeakerArr::kMono);
+
+        in_arrangement = vst3::Steinberg::Vst::SpeakerArr::kStereo;
+        assert_ne!(
+            proc.setBusArrangements(&mut in_arrangement, 1, &mut out_arrangement, 1),
+            vst3::Steinberg::kResultTrue
+        );
+
+        out_arrangement = vst3::Steinberg::Vst::SpeakerArr::kStereo;
+        assert_eq!(
+            proc.setBusArrangements(&mut in_arrangement, 1, &mut out_arrangement, 1),
+            vst3::Steinberg::kResultOk
+        );
+        out_arrangement = vst3::Steinberg::Vst::SpeakerArr::kStereo;
+        assert_eq!(
+            proc.getBusArrangement(
+                vst3::Steinberg::Vst::BusDirections_::kOutput as i32,
+                0,
+                &mut out_arrangement
+            ),
+            vst3::Steinberg::kResultOk
+        );
+        assert_eq!(out_arrangement, vst3::Steinberg::Vst::SpeakerArr::kStereo);
+    }
+}
+
+#[test]
+fn mono_incompatible_effect_refuses_mono_busses() {
+    let proc = create_effect(
+        |_: &HostInfo| FakeComponent {
+            mono_compatible: false,
+            ..fake_effect_component()
+        },
+        [4; 16],
+        SWITCH_ID,
+        EffectOptions::DEFAULT,
+    );
+
+    let host = ComWrapper::new(dummy_host::Host::default())
+        .to_com_ptr::<IHostApplication>()
+        .unwrap();
+
+    unsafe {
+        assert_eq!(
+            proc.initialize(host.cast().unwrap().as_ptr()),
+            vst3::Steinberg::kResultOk
+        );
+        let mut in_arrangement = vst3::Steinberg::Vst::SpeakerArr::kMono;
+        let mut out_arrangement = vst3::Steinberg::Vst::SpeakerArr::kMono;
+        assert_eq!(
+            proc.setBusArrangements(&mut in_arrangement, 1, &mut out_arrangement, 1),
+            vst3::Steinberg::kResultFalse
+        );
+
+        // We should still be in stereo
+        assert_eq!(
+            proc.getBusArrangement(
+                vst3::Steinberg::Vst::BusDirections_::kOutput as i32,
+                0,
+                &mut out_arrangement
+            ),
+            vst3::Steinberg::kResultOk
+        );
+        assert_eq!(out_arrangement, vst3::Steinberg::Vst::SpeakerArr::kStereo);
+
+        in_arrangement = vst3::Steinberg::Vst::SpeakerArr::kStereo;
+        out_arrangement = vst3::Steinberg::Vst::SpeakerArr::kStereo;
+        assert_eq!(
+            proc.setBusArrangements(&mut in_arrangement, 1, &mut out_arrangement, 1),
+            vst3::Steinberg::kResultTrue
+        );
+    }
+}
+
+#[test]
+fn surround_effect_accepts_supported_surround_busses() {
+    let proc = create_effect(
+        |_: &HostInfo| FakeComponent {
+            supported_channel_layouts: Some(vec![
+                ChannelLayout::Stereo,
+                ChannelLayout::FivePointOne,
+            ]),
+            ..fake_effect_component()
+        },
+        [4; 16],
+        SWITCH_ID,
+        EffectOptions::DEFAULT,
+    );
+    let host = ComWrapper::new(dummy_host::Host::default());
+
+    unsafe {
+        let host_ref = host.as_com_ref::<IHostApplication>().unwrap();
+        assert_eq!(
+            proc.initialize(host_ref.cast().unwrap().as_ptr()),
+            vst3::Steinberg::kResultOk
+        );
+        assert_eq!(
+            proc.setupProcessing(&mut process_setup(&DEFAULT_ENV)),
+            vst3::Steinberg::kResultOk
+        );
+
+        let mut in_arrangement = vst3::Steinberg::Vst::SpeakerArr::k71Music;
+        let mut out_arrangement = vst3::Steinberg::Vst::SpeakerArr::k71Music;
+        assert_eq!(
+            proc.setBusArrangements(&mut in_arrangement, 1, &mut out_arrangement, 1),
+            vst3::Steinberg::kResultFalse
+        );
+
+        in_arrangement = vst3::Steinberg::Vst::SpeakerArr::k51;
+        out_arrangement = vst3::Steinberg::Vst::SpeakerArr::k51;
+        assert_eq!(
+            proc.setBusArrangements(&mut in_arrangement, 1, &mut out_arrangement, 1),
+            vst3::Steinberg::kResultTrue
+        );
+        out_arrangement = vst3::Steinberg::Vst::SpeakerArr::kStereo;
+        assert_eq!(
+            proc.getBusArrangement(
+                vst3::Steinberg::Vst::BusDirections_::kOutput as i32,
+                0,
+                &mut out_arrangement
+            ),
+            vst3::Steinberg::kResultOk
+        );
+        assert_eq!(out_arrangement, vst3::Steinberg::Vst::SpeakerArr::k51);
+
+        let mut bus = vst3::Steinberg::Vst::BusInfo {
+            mediaType: 0,
+            direction: 0,
+            channelCount: 0,
+            name: [0; 128],
+            busType: 0,
+            flags: 0,
+        };
+        assert_eq!(
+            proc.getBusInfo(
+                vst3::Steinberg::Vst::MediaTypes_::kAudio as i32,
+                vst3::Steinberg::Vst::BusDirections_::kOutput as i32,
+                0,
+                &mut bus
+            ),
+            vst3::Steinberg::kResultOk
+        );
+        assert_eq!(bus.channelCount, 6);
+
+        activate_effect_busses(&proc);
+        assert_eq!(proc.setActive(1u8), vst3::Steinberg::kResultOk);
+        assert_eq!(proc.setProcessing(1u8), vst3::Steinberg::kResultOk);
+
+        let audio = mock_process_effect(vec![vec![1f32; 512]; 6], vec![], &proc).unwrap();
+        assert_eq!(audio.len(), 6);
+        assert!(audio.iter().all(|channel| *channel == audio[0]));
+    }
+}
+
+#[test]
+fn stereo_only_synth_refuses_mono_output() {
+    let proc = create_synth(
+        |_: &HostInfo| FakeComponent {
+            supported_channel_layouts: Some(vec![ChannelLayout::Stereo]),
+            ..fake_synth_component()
+        },
+        [4; 16],
+        SynthOptions::DEFAULT,
+    );
+
+    let host = ComWrapper::new(dummy_host::Host::default())
+        .to_com_ptr::<IHostApplication>()
+        .unwrap();
+
+    unsafe {
+        assert_eq!(
+            proc.initialize(host.cast().unwrap().as_ptr()),
+            vst3::Steinberg::kResultOk
+        );
+        let mut out_arrangement = vst3::Steinberg::Vst::SpeakerArr::kMono;
+        assert_eq!(
+            proc.setBusArrangements(std::ptr::null_mut(), 0, &mut out_arrangement, 1),
+            vst3::Steinberg::kResultFalse
+        );
+
+        // We should still be in stereo
+        assert_eq!(
+            proc.getBusArrangement(
+                vst3::Steinberg::Vst::BusDirections_::kOutput as i32,
+                0,
+                &mut out_arrangement
+            ),
+            vst3::Steinberg::kResultOk
+        );
+        assert_eq!(out_arrangement, vst3::Steinberg::Vst::SpeakerArr::kStereo);
+    }
+}
+
+#[test]
+fn defends_against_set_processing_before_init() {
+    let proc = dummy_synth();
+    unsafe {
+        assert_ne!(proc.setProcessing(1u8), vst3::Steinberg::kResultOk);
+    }
+}
+
+#[test]
+fn defends_against_set_processing_before_setup_processing() {
+    let proc = dummy_synth();
+    let host = ComWrapper::new(dummy_host::Host::default())
+        .to_com_ptr::<IHostApplication>()
+        .unwrap();
+
+    unsafe {
+        assert_eq!(
+            proc.initialize(host.cast().unwrap().as_ptr()),
+            vst3::Steinberg::kResultOk
+        );
+        assert_ne!(proc.setProcessing(1u8), vst3::Steinberg::kResultOk);
+    }
+}
+
+#[test]
+fn defends_against_set_processing_before_activating_busses() {
+    let proc = dummy_synth();
+    let host = ComWrapper::new(dummy_host::Host::default())
+        .to_com_ptr::<IHostApplication>()
+        .unwrap();
+
+    unsafe {
+        assert_eq!(
+            proc.initialize(host.cast().unwrap().as_ptr()),
+            vst3::Steinberg::kResultOk
+        );
+        assert_eq!(
+            proc.setupProcessing(&mut process_setup(&DEFAULT_ENV)),
+            vst3::Steinberg::kResultOk
+        );
+
+        assert_ne!(proc.setProcessing(1u8), vst3::Steinberg::kResultOk);
+    }
+}
+
+#[test]
+fn defends_against_set_processing_before_activating() {
+    let proc = dummy_synth();
+    let host = ComWrapper::new(dummy_host::Host::default())
+        .to_com_ptr::<IHostApplication>()
+        .unwrap();
+
+    unsafe {
+        assert_eq!(
+            proc.initialize(host.cast().unwrap().as_ptr()),
+            vst3::Steinberg::kResultOk
+        );
+        assert_eq!(
+            proc.setupProcessing(&mut process_setup(&DEFAULT_ENV)),
+            vst3::Steinberg::kResultOk
+        );
+        activate_busses(&proc);
+
+        assert_ne!(proc.setProcessing(1u8), vst3::Steinberg::kResultOk);
+    }
+}
+
+#[test]
+fn can_set_processing() {
+    let processing: RefCell<bool> = Default::default();
+    let proc = dummy_synth_with_processing(&processing);
+    let host = ComWrapper::new(dummy_host::Host::default())
+        .to_com_ptr::<IHostApplication>()
+        .unwrap();
+
+    unsafe {
+        assert_eq!(
+            proc.initialize(host.cast().unwrap().as_ptr()),
+            vst3::Steinberg::kResultOk
+        );
+        assert_eq!(
+            proc.setupProcessing(&mut process_setup(&DEFAULT_ENV)),
+            vst3::Steinberg::kResultOk
+        );
+        activate_busses(&proc);
+        assert_eq!(proc.setActive(1u8), vst3::Steinberg::kResultOk);
+        assert_eq!(*processing.borrow(), false);
+        assert_eq!(proc.setProcessing(1u8), vst3::Steinberg::kResultOk);
+        assert_eq!(*processing.borrow(), true);
+        assert_eq!(proc.setProcessing(0u8), vst3::Steinberg::kResultOk);
+        assert_eq!(*processing.borrow(), false);
+    }
+}
+
+#[test]
+fn prepare_called_once_before_processing() {
+    let lifecycle: RefCell<Vec<&'static str>> = Default::default();
+    let proc = dummy_synth_with_lifecycle(&lifecycle);
+    let host = ComWrapper::new(dummy_host::Host::default())
+        .to_com_ptr::<IHostApplication>()
+        .unwrap();
+
+    unsafe {
+        assert_eq!(
+            proc.initialize(host.cast().unwrap().as_ptr()),
+            vst3::Steinberg::kResultOk
+        );
+        assert_eq!(
+            proc.setupProcessing(&mut process_setup(&DEFAULT_ENV)),
+            vst3::Steinberg::kResultOk
+        );
+        activate_busses(&proc);
+        assert_eq!(proc.setActive(1u8), vst3::Steinberg::kResultOk);
+        assert_eq!(*lifecycle.borrow(), ["prepare"]);
+        assert_eq!(proc.setProcessing(1u8), vst3::Steinberg::kResultOk);
+        assert_eq!(proc.setProcessing(0u8), vst3::Steinberg::kResultOk);
+        assert_eq!(proc.setProcessing(1u8), vst3::Steinberg::kResultOk);
+        assert_eq!(
+            *lifecycle.borrow(),
+            [
+                "prepare",
+                "set_processing(true)",
+                "set_processing(false)",
+                "set_processing(true)"
+            ]
+        );
+    }
+}
+
+#[test]
+fn defends_against_set_processing_while_inactive() {
+    let proc = dummy_synth();
+    let host = ComWrapper::new(dummy_host::Host::default())
+        .to_com_ptr::<IHostApplication>()
+        .unwrap();
+
+    unsafe {
+        assert_eq!(
+            proc.initialize(host.cast().unwrap().as_ptr()),
+            vst3::Steinberg::kResultOk
+        );
+        assert_eq!(
+            proc.setupProcessing(&mut process_setup(&DEFAULT_ENV)),
+            vst3::Steinberg::kResultOk
+        );
+        activate_busses(&proc);
+        assert_eq!(proc.setActive(1u8), vst3::Steinberg::kResultOk);
+        assert_eq!(proc.setActive(0u8), vst3::Steinberg::kResultOk);
+
+        assert_ne!(proc.setProcessing(1u8), vst3::Steinberg::kResultOk);
+    }
+}
+
+#[test]
+fn defends_against_processing_without_set() {
+    let proc = dummy_synth();
+    let host = ComWrapper::new(dummy_host::Host::default())
+        .to_com_ptr::<IHostApplication>()
+        .unwrap();
+
+    unsafe {
+        assert_eq!(
+            proc.initialize(host.cast().unwrap().as_ptr()),
+            vst3::Steinberg::kResultOk
+        );
+        assert_eq!(
+            proc.setupProcessing(&mut process_setup(&DEFAULT_ENV)),
+            vst3::Steinberg::kResultOk
+        );
+        activate_busses(&proc);
+        assert_eq!(proc.setActive(1u8), vst3::Steinberg::kResultOk);
+        assert_ne!(
+            proc.process(std::ptr::null_mut()),
+            vst3::Steinberg::kResultOk
+        );
+    }
+}
+
+#[test]
+fn can_process() {
+    let proc = dummy_synth();
+    let host = ComWrapper::new(dummy_host::Host::default());
+
+    unsafe {
+        setup_proc(&proc, &host);
+
+        let audio = mock_process(
+            2,
+            vec![
+                Event {
+                    sample_offset: 0,
+                    data: Data::NoteOn {
+                        data: NoteData {
+                            id: NoteID::from_id(0),
+                            pitch: 64,
+                            velocity: 0.5,
+                            tuning: 0f32,
+                            channel: 0,
+                        },
+                    },
+                },
+                Event {
+                    sample_offset: 100,
+                    data: Data::NoteOff {
+                        data: NoteData {
+                            id: NoteID::from_id(0),
+                            pitch: 64,
+                            velocity: 0.5,
+                            tuning: 0f32,
+                            channel: 0,
+                        },
+                    },
+                },
+                Event {
+                    sample_offset: 200,
+                    data: Data::NoteOn {
+                        data: NoteData {
+                            id: NoteID::from_id(0),
+                            pitch: 64,
+                            velocity: 0.5,
+                            tuning: 0f32,
+                            channel: 0,
+                        },
+                    },
+                },
+                Event {
+                    sample_offset: 200,
+                    data: Data::NoteOn {
+                        data: NoteData {
+                            id: NoteID::from_id(1),
+                            pitch: 65,
+                            velocity: 0.5,
+                            tuning: 0f32,
+                            channel: 0,
+                        },
+                    },
+                },
+            ],
+            vec![],
+            &proc,
+        );
+        assert!(audio.is_some());
+        assert_eq!(audio.as_ref().unwrap()[0][0], 1.0);
+        assert_eq!(audio.as_ref().unwrap()[1][100], 0.0);
+        assert_eq!(audio.as_ref().unwrap()[1][200], 2.0);
+    }
+}
+
+#[test]
+fn can_process_mpe() {
+    let proc = dummy_synth();
+    let host = ComWrapper::new(dummy_host::Host::default());
+
+    unsafe {
+        setup_proc(&proc, &host);
+
+        let audio = mock_process(
+            2,
+            vec![
+                Event {
+                    sample_offset: 0,
+                    data: Data::NoteOn {
+                        data: NoteData {
+                            id: NoteID::from_id(0),
+                            pitch: 64,
+                            velocity: 0.5,
+                            tuning: 0f32,
+                            channel: 0,
+                        },
+                    },
+                },
+                Event {
+                    sample_offset: 10,
+                    data: Data::NoteExpression {
+                        data: NoteExpressionData {
+                            id: NoteID::from_id(0),
+                            expression: NoteExpression::PitchBend(12.0),
+                        },
+                    },
+                },
+                // Should ignore wrong-id events
+                Event {
+                    sample_offset: 11,
+                    data: Data::NoteExpression {
+                        data: NoteExpressionData {
+                            id: NoteID::from_id(4),
+                            expression: NoteExpression::PitchBend(24.0),
+                        },
+                    },
+                },
+                Event {
+                    sample_offset: 16,
+                    data: Data::NoteExpression {
+                        data: NoteExpressionData {
+                            id: NoteID::from_id(0),
+                            expression: NoteExpression::Timbre(0.6),
+                        },
+                    },
+                },
+                Event {
+                    sample_offset: 20,
+                    data: Data::NoteExpression {
+                        data: NoteExpressionData {
+                            id: NoteID::from_id(0),
+                            expression: NoteExpression::Aftertouch(0.7),
+                        },
+                    },
+                },
+                Event {
+                    sample_offset: 90,
+                    data: Data::NoteExpression {
+                        data: NoteExpressionData {
+                            id: NoteID::from_id(0),
+                            expression: NoteExpression::PitchBend(0.0),
+                        },
+                    },
+                },
+                Event {
+                    sample_offset: 90,
+                    data: Data::NoteExpression {
+                        data: NoteExpressionData {
+                            id: NoteID::from_id(0),
+                            expression: NoteExpression::Timbre(0.0),
+                        },
+                    },
+                },
+                Event {
+                    sample_offset: 90,
+                    data: Data::NoteExpression {
+                        data: NoteExpressionData {
+                            id: NoteID::from_id(0),
+                            expression: NoteExpression::Aftertouch(0.0),
+                        },
+                    },
+                },
+                Event {
+                    sample_offset: 100,
+                    data: Data::NoteOff {
+                        data: NoteData {
+                            id: NoteID::from_id(0),
+                            pitch: 64,
+                            velocity: 0.5,
+                            tuning: 0f32,
+                            channel: 0,
+                        },
+                    },
+                },
+            ],
+            vec![],
+            &proc,
+        );
+        assert!(audio.is_some());
+        assert_eq!(audio.as_ref().unwrap()[0][0], 1.0);
+        assert_approx_eq!(audio.as_ref().unwrap()[0][10], 13.0, 1e-5);
+        assert_approx_eq!(audio.as_ref().unwrap()[0][15], 13.0, 1e-5);
+        assert_approx_eq!(audio.as_ref().unwrap()[0][16], 13.6, 1e-5);
+        assert_approx_eq!(audio.as_ref().unwrap()[0][20], 14.3, 1e-5);
+        assert_approx_eq!(audio.as_ref().unwrap()[0][90], 1.0);
+        assert_eq!(audio.as_ref().unwrap()[1][100], 0.0);
+    }
+}
+
+#[test]
+fn can_process_effect() {
+    let proc = dummy_effect();
+    let host = ComWrapper::new(dummy_host::Host::default());
+
+    unsafe {
+        setup_proc_effect(&proc, &host);
+
+        let audio = mock_process_effect(
+            vec![vec![1f32; 512]; 2],
+            vec![
+                ParameterValueQueueImpl {
+                    param_id: NUMERIC_ID.to_string(),
+                    points: vec![
+                        ParameterValueQueuePoint {
+                            sample_offset: 99,
+                            value: ((1.0 - MIN_NUMERIC) / (MAX_NUMERIC - MIN_NUMERIC)) as f64,
+                        },
+                        ParameterValueQueuePoint {
+                            sample_offset: 100,
+                            value: 1.0,
+                        },
+                    ],
+                },
+                ParameterValueQueueImpl {
+                    param_id: SWITCH_ID.to_string(),
+                    points: vec![
+                        ParameterValueQueuePoint {
+                            sample_offset: 500,
+                            value: 1.0,
+                        },
+                        ParameterValueQueuePoint {
+                            sample_offset: 501,
+                            value: 0.0,
+                        },
+                    ],
+                },
+                ParameterValueQueueImpl {
+                    param_id: ENUM_ID.to_string(),
+                    points: vec![
+                        ParameterValueQueuePoint {
+                            sample_offset: 300,
+                            value: 0.33,
+                        },
+                        ParameterValueQueuePoint {
+                            sample_offset: 301,
+                            value: 0.34,
+                        },
+                    ],
+                },
+            ],
+            &proc,
         );
+        assert!(audio.is_some());
+        assert_approx_eq!(audio.as_ref().unwrap()[1][100], 10.0);
+        assert_approx_eq!(audio.as_ref().unwrap()[1][500], 20.0);
     }
 }
 
 #[test]
-fn initialize_gets_host_info() {
-    let host_info = Default::default();
-    let host = ComWrapper::new(dummy_host::Host::default())
-        .to_com_ptr::<IHostApplication>()
-        .unwrap();
-    let proc = dummy_synth_with_host_info(&host_info);
+fn effect_can_read_bypass_by_alias() {
+    let proc = create_effect(
+        |_: &HostInfo| FakeComponent::new(|_| BypassReportingEffect {}),
+        [4; 16],
+        SWITCH_ID,
+        EffectOptions::DEFAULT,
+    );
+    let host = ComWrapper::new(dummy_host::Host::default());
+
     unsafe {
-        assert_eq!(
-            proc.initialize(host.cast().unwrap().as_ptr()),
-            vst3::Steinberg::kResultOk
-        )
+        setup_proc_effect(&proc, &host);
+
+        let audio = mock_process_effect(
+            vec![vec![1f32; 512]; 2],
+            vec![ParameterValueQueueImpl {
+                param_id: SWITCH_ID.to_string(),
+                points: vec![ParameterValueQueuePoint {
+                    sample_offset: 100,
+                    value: 0.0,
+                }],
+            }],
+            &proc,
+        );
+        assert!(audio.is_some());
+        // The switch parameter defaults to on, and is then turned off during the buffer.
+        assert_approx_eq!(audio.as_ref().unwrap()[0][0], 1.0);
+        assert_approx_eq!(audio.as_ref().unwrap()[0][200], 0.0);
     }
-    assert_eq!(
-        host_info.borrow().as_ref(),
-        Some(&HostInfo {
-            name: "Dummy Host".to_string()
-        })
-    );
-    unsafe { assert_eq!(proc.terminate(), vst3::Steinberg::kResultOk) }
 }
 
 #[test]
-fn defends_against_termination_before_initialization() {
+fn defends_against_events_past_buffer() {
     let proc = dummy_synth();
-    unsafe { assert_ne!(proc.terminate(), vst3::Steinberg::kResultOk) }
+    let host = ComWrapper::new(dummy_host::Host::default());
+
+    unsafe {
+        setup_proc(&proc, &host);
+        assert!(mock_process(
+            2,
+            vec![Event {
+                sample_offset: SAMPLE_COUNT + 1000,
+                data: Data::NoteOn {
+                    data: NoteData {
+                        id: NoteID::from_id(0),
+                        pitch: 64,
+                        velocity: 0.5,
+                        tuning: 0f32,
+                        channel: 0,
+                    },
+                },
+            }],
+            vec![],
+            &proc
+        )
+        .is_none());
+    }
 }
 
 #[test]
-fn defends_against_initialize_twice() {
+fn defends_against_shuffled_events() {
     let proc = dummy_synth();
-    let host = ComWrapper::new(dummy_host::Host::default())
-        .to_com_ptr::<IHostApplication>()
-        .unwrap();
+    let host = ComWrapper::new(dummy_host::Host::default());
+
     unsafe {
-        assert_eq!(
-            proc.initialize(host.cast().unwrap().as_ptr()),
-            vst3::Steinberg::kResultOk
+        setup_proc(&proc, &host);
+
+        assert!(mock_process(
+            2,
+            vec![
+                Event {
+                    sample_offset: 200,
+                    data: Data::NoteOn {
+                        data: NoteData {
+                            id: NoteID::from_id(0),
+                            pitch: 64,
+                            velocity: 0.5,
+                            tuning: 0f32,
+                            channel: 0,
+                        },
+                    },
+                },
+                Event {
+                    sample_offset: 100,
+                    data: Data::NoteOff {
+                        data: NoteData {
+                            id: NoteID::from_id(0),
+                            pitch: 64,
+                            velocity: 0.5,
+                            tuning: 0f32,
+                            channel: 0,
+                        },
+                    },
+                },
+            ],
+            vec![],
+            &proc
         )
+        .is_none());
     }
+}
+
+#[test]
+fn can_handle_null_events() {
+    let proc = dummy_synth();
+    let host = ComWrapper::new(dummy_host::Host::default());
+
     unsafe {
-        assert_ne!(
-            proc.initialize(host.cast().unwrap().as_ptr()),
-            vst3::Steinberg::kResultOk
-        )
+        setup_proc(&proc, &host);
+
+        let audio = mock_process_mod(2, vec![], vec![], &proc, |x| {
+            x.inputEvents = std::ptr::null_mut();
+        });
+        assert!(audio.is_some());
+        assert_eq!(audio.as_ref().unwrap()[0][0], 0.0);
     }
 }
 
 #[test]
-fn allow_initialize_twice_with_terminate() {
+fn defends_against_timed_events_without_audio() {
     let proc = dummy_synth();
-    let host = ComWrapper::new(dummy_host::Host::default())
-        .to_com_ptr::<IHostApplication>()
-        .unwrap();
+    let host = ComWrapper::new(dummy_host::Host::default());
+
     unsafe {
-        assert_eq!(
-            proc.initialize(host.cast().unwrap().as_ptr()),
+        setup_proc(&proc, &host);
+
+        let mut data = mock_no_audio_process_data(
+            vec![Event {
+                sample_offset: 100,
+                data: Data::NoteOn {
+                    data: NoteData {
+                        id: NoteID::from_id(0),
+                        pitch: 64,
+                        velocity: 0.5,
+                        tuning: 0f32,
+                        channel: 0,
+                    },
+                },
+            }],
+            vec![],
+        );
+        assert_ne!(
+            proc.process(&mut data.process_data),
             vst3::Steinberg::kResultOk
-        )
+        );
     }
-    unsafe { assert_eq!(proc.terminate(), vst3::Steinberg::kResultOk) }
+}
+
+#[test]
+fn can_handle_events_without_audio() {
+    let proc = dummy_synth();
+    let host = ComWrapper::new(dummy_host::Host::default());
+
     unsafe {
+        setup_proc(&proc, &host);
+
+        let audio = mock_process(
+            2,
+            vec![
+                Event {
+                    sample_offset: 0,
+                    data: Data::NoteOn {
+                        data: NoteData {
+                            id: NoteID::from_id(0),
+                            pitch: 64,
+                            velocity: 0.5,
+                            tuning: 0f32,
+                            channel: 0,
+                        },
+                    },
+                },
+                Event {
+                    sample_offset: 0,
+                    data: Data::NoteOn {
+                        data: NoteData {
+                            id: NoteID::from_id(1),
+                            pitch: 64,
+                            velocity: 0.5,
+                            tuning: 0f32,
+                            channel: 0,
+                        },
+                    },
+                },
+            ],
+            vec![],
+            &proc,
+        );
+
+        assert!(audio.is_some());
+
+        let mut data2 = mock_no_audio_process_data(
+            vec![Event {
+                sample_offset: 0,
+                data: Data::NoteOff {
+                    data: NoteData {
+                        id: NoteID::from_id(0),
+                        pitch: 64,
+                        velocity: 0.5,
+                        tuning: 0f32,
+                        channel: 0,
+                    },
+                },
+            }],
+            vec![],
+        );
+
         assert_eq!(
-            proc.initialize(host.cast().unwrap().as_ptr()),
+            proc.process(&mut data2.process_data),
             vst3::Steinberg::kResultOk
-        )
+        );
+
+        let audio3 = mock_process(
+            2,
+            vec![Event {
+                sample_offset: 100,
+                data: Data::NoteOff {
+                    data: NoteData {
+                        id: NoteID::from_id(1),
+                        pitch: 64,
+                        velocity: 0.5,
+                        tuning: 0f32,
+                        channel: 0,
+                    },
+                },
+            }],
+            vec![],
+            &proc,
+        );
+        assert!(audio3.is_some());
+        assert_eq!(audio3.as_ref().unwrap()[0][0], 1.0);
+        assert_eq!(audio3.as_ref().unwrap()[1][100], 0.0);
     }
 }
 
 #[test]
-fn defends_against_activating_wrong_bus() {
-    let proc = dummy_synth();
-    let host = ComWrapper::new(dummy_host::Host::default())
-        .to_com_ptr::<IHostApplication>()
-        .unwrap();
+fn smoothed_parameters_are_smoothed() {
+    let proc = create_effect(
+        |_: &HostInfo| FakeComponent {
+            parameter_infos: smoothed_parameters(),
+            ..fake_effect_component()
+        },
+        [4; 16],
+        SWITCH_ID,
+        EffectOptions::DEFAULT,
+    );
+    let host = ComWrapper::new(dummy_host::Host::default());
 
     unsafe {
-        assert_eq!(
-            proc.initialize(host.cast().unwrap().as_ptr()),
-            vst3::Steinberg::kResultOk
+        setup_proc_effect(&proc, &host);
+
+        // The first buffer after activation starts at the unsmoothed value.
+        let audio = mock_process_effect(vec![vec![1f32; 512]; 2], vec![], &proc);
+        assert!(audio.is_some());
+        assert_approx_eq!(audio.as_ref().unwrap()[0][0], DEFAULT_NUMERIC);
+        assert_approx_eq!(audio.as_ref().unwrap()[0][511], DEFAULT_NUMERIC);
+
+        let audio = mock_process_effect(
+            vec![vec![1f32; 512]; 2],
+            vec![ParameterValueQueueImpl {
+                param_id: NUMERIC_ID.to_string(),
+                points: vec![ParameterValueQueuePoint {
+                    sample_offset: 0,
+                    value: 1.0,
+                }],
+            }],
+            &proc,
         );
-        assert_ne!(
-            proc.activateBus(
-                vst3::Steinberg::Vst::MediaTypes_::kAudio as i32,
-                vst3::Steinberg::Vst::BusDirections_::kInput as i32,
-                0,
-                1
-            ),
-            vst3::Steinberg::kResultOk
+        assert!(audio.is_some());
+        let audio = audio.as_ref().unwrap();
+        assert!(audio[0].windows(2).all(|w| w[0] < w[1]));
+        assert!(audio[0][0] > DEFAULT_NUMERIC);
+
+        // After one time constant, we should be about 63% of the way to the target.
+        // `SMOOTHING_MS` at the default sampling rate of 44.1kHz
+        let time_constant_samples = 441;
+        assert_approx_eq!(
+            audio[1][time_constant_samples - 1],
+            DEFAULT_NUMERIC + (MAX_NUMERIC - DEFAULT_NUMERIC) * (1.0 - (-1.0f32).exp()),
+            1e-3
         );
     }
 }
 
 #[test]
-fn defends_against_effect_activating_wrong_bus() {
-    let proc = dummy_effect();
-    let host = ComWrapper::new(dummy_host::Host::default())
-        .to_com_ptr::<IHostApplication>()
-        .unwrap();
+fn analyzer_passes_audio_through() {
+    let peak = RefCell::new(0f32);
+    let proc = create_analyzer(
+        |_: &HostInfo| FakeComponent::new(|_| PeakAnalyzer { peak: &peak }),
+        [4; 16],
+    );
+    let host = ComWrapper::new(dummy_host::Host::default());
 
     unsafe {
-        assert_eq!(
-            proc.initialize(host.cast().unwrap().as_ptr()),
-            vst3::Steinberg::kResultOk
-        );
-        assert_ne!(
-            proc.activateBus(
-                vst3::Steinberg::Vst::MediaTypes_::kEvent as i32,
-                vst3::Steinberg::Vst::BusDirections_::kOutput as i32,
-                0,
-                1
-            ),
-            vst3::Steinberg::kResultOk
-        );
+        setup_proc_effect(&proc, &host);
+
+        let left = (0..512u16)
+            .map(|i| f32::from(i) / 512.0)
+            .collect::<Vec<_>>();
+        let right = left.iter().map(|x| -x).collect::<Vec<_>>();
+        let audio = mock_process_effect(vec![left.clone(), right.clone()], vec![], &proc);
+        assert_eq!(audio, Some(vec![left, right]));
+        assert_approx_eq!(*peak.borrow(), 511.0 / 512.0);
     }
 }
 
 #[test]
-fn captures_process_environment() {
-    let env = Default::default();
-    let proc = dummy_synth_with_processing_environment(&env);
-
-    let host = ComWrapper::new(dummy_host::Host::default())
-        .to_com_ptr::<IHostApplication>()
-        .unwrap();
+fn can_handle_parameter_changes() {
+    let proc = dummy_synth();
+    let host = ComWrapper::new(dummy_host::Host::default());
 
     unsafe {
-        assert_eq!(
-            proc.initialize(host.cast().unwrap().as_ptr()),
-            vst3::Steinberg::kResultOk
+        setup_proc(&proc, &host);
+        let audio = mock_process(
+            2,
+            vec![Event {
+                sample_offset: 0,
+                data: Data::NoteOn {
+                    data: NoteData {
+                        id: NoteID::from_id(0),
+                        pitch: 64,
+                        velocity: 0.5,
+                        tuning: 0f32,
+                        channel: 0,
+                    },
+                },
+            }],
+            vec![
+                ParameterValueQueueImpl {
+                    param_id: NUMERIC_ID.to_string(),
+                    points: vec![
+                        ParameterValueQueuePoint {
+                            sample_offset: 99,
+                            value: ((1.0 - MIN_NUMERIC) / (MAX_NUMERIC - MIN_NUMERIC)) as f64,
+                        },
+                        ParameterValueQueuePoint {
+                            sample_offset: 100,
+                            value: 1.0,
+                        },
+                    ],
+                },
+                ParameterValueQueueImpl {
+                    param_id: SWITCH_ID.to_string(),
+                    points: vec![
+                        ParameterValueQueuePoint {
+                            sample_offset: 500,
+                            value: 1.0,
+                        },
+                        ParameterValueQueuePoint {
+                            sample_offset: 501,
+                            value: 0.0,
+                        },
+                    ],
+                },
+                ParameterValueQueueImpl {
+                    param_id: ENUM_ID.to_string(),
+                    points: vec![
+                        ParameterValueQueuePoint {
+                            sample_offset: 300,
+                            value: 0.33,
+                        },
+                        ParameterValueQueuePoint {
+                            sample_offset: 301,
+                            value: 0.34,
+                        },
+                    ],
+                },
+            ],
+            &proc,
         );
 
-        activate_busses(&proc);
-    }
-
-    for test_env in [
-        PartialProcessingEnvironment {
-            sampling_rate: 48000.0,
-            max_samples_per_process_call: 512,
-            processing_mode: ProcessingMode::Realtime,
-        },
-        PartialProcessingEnvironment {
-            sampling_rate: 44100.0,
-            max_samples_per_process_call: 8192,
-            processing_mode: ProcessingMode::Offline,
-        },
-        PartialProcessingEnvironment {
-            sampling_rate: 96000.0,
-            max_samples_per_process_call: 2048,
-            processing_mode: ProcessingMode::Prefetch,
-        },
-    ] {
-        unsafe {
-            assert_eq!(
-                proc.setupProcessing(&mut process_setup(&test_env)),
-                vst3::Steinberg::kResultOk
-            );
-            assert_eq!(proc.setActive(1u8), vst3::Steinberg::kResultOk);
-            assert!(matches(&test_env, env.borrow().as_ref().unwrap()));
-            assert_eq!(proc.setActive(0u8), vst3::Steinberg::kResultOk);
-        }
+        assert!(audio.is_some());
+        let audio = audio.as_ref().unwrap();
+        assert_eq!(audio[0][0], 1.0);
+        assert_eq!(audio[1][99], 1.0);
+        assert_eq!(audio[0][100], MAX_NUMERIC as f32);
+        assert_eq!(audio[0][200], MAX_NUMERIC as f32);
+        assert_eq!(audio[0][300], MAX_NUMERIC as f32);
+        assert_eq!(audio[0][301], (MAX_NUMERIC * 2.0) as f32);
+        assert_eq!(audio[0][500], (MAX_NUMERIC * 2.0) as f32);
+        assert_eq!(audio[0][501], 0.0);
     }
 }
 
 #[test]
-fn defends_against_activating_without_environment() {
+fn parameter_changes_at_start_of_buffer() {
     let proc = dummy_synth();
-
-    let host = ComWrapper::new(dummy_host::Host::default())
-        .to_com_ptr::<IHostApplication>()
-        .unwrap();
+    let host = ComWrapper::new(dummy_host::Host::default());
 
     unsafe {
-        assert_eq!(
-            proc.initialize(host.cast().unwrap().as_ptr()),
-            vst3::Steinberg::kResultOk
+        setup_proc(&proc, &host);
+        let audio = mock_process(
+            2,
+            vec![Event {
+                sample_offset: 0,
+                data: Data::NoteOn {
+                    data: NoteData {
+                        id: NoteID::from_id(0),
+                        pitch: 64,
+                        velocity: 0.5,
+                        tuning: 0f32,
+                        channel: 0,
+                    },
+                },
+            }],
+            vec![ParameterValueQueueImpl {
+                param_id: NUMERIC_ID.to_string(),
+                points: vec![
+                    ParameterValueQueuePoint {
+                        sample_offset: 0,
+                        value: ((0.6 - MIN_NUMERIC) / (MAX_NUMERIC - MIN_NUMERIC)) as f64,
+                    },
+                    ParameterValueQueuePoint {
+                        sample_offset: 100,
+                        value: 1.0,
+                    },
+                ],
+            }],
+            &proc,
         );
-        activate_busses(&proc);
-        assert_ne!(proc.setActive(1u8), vst3::Steinberg::kResultOk);
+
+        assert!(audio.is_some());
+        let audio = audio.as_ref().unwrap();
+        assert_eq!(audio[0][0], 0.6);
+        assert_eq!(audio[0][100], MAX_NUMERIC as f32);
+        assert_eq!(audio[0][500], MAX_NUMERIC as f32);
     }
 }
 
 #[test]
-fn defends_against_activating_without_activating_busses() {
+fn defends_against_wild_parameter_ids() {
     let proc = dummy_synth();
-
-    let host = ComWrapper::new(dummy_host::Host::default())
-        .to_com_ptr::<IHostApplication>()
-        .unwrap();
+    let host = ComWrapper::new(dummy_host::Host::default());
 
     unsafe {
-        assert_eq!(
-            proc.initialize(host.cast().unwrap().as_ptr()),
-            vst3::Steinberg::kResultOk
-        );
-
-        assert_eq!(
-            proc.setupProcessing(&mut process_setup(&PartialProcessingEnvironment {
-                sampling_rate: 48000.0,
-                max_samples_per_process_call: 512,
-                processing_mode: ProcessingMode::Realtime,
-            })),
-            vst3::Steinberg::kResultOk
+        setup_proc(&proc, &host);
+        let audio = mock_process(
+            2,
+            vec![Event {
+                sample_offset: 0,
+                data: Data::NoteOn {
+                    data: NoteData {
+                        id: NoteID::from_id(0),
+                        pitch: 64,
+                        velocity: 0.5,
+                        tuning: 0f32,
+                        channel: 0,
+                    },
+                },
+            }],
+            vec![ParameterValueQueueImpl {
+                param_id: "some garbage parameter".to_string(),
+                points: vec![ParameterValueQueuePoint {
+                    sample_offset: 0,
+                    value: MAX_NUMERIC as f64,
+                }],
+            }],
+            &proc,
         );
 
-        assert_ne!(proc.setActive(1u8), vst3::Steinberg::kResultOk);
+        assert!(audio.is_none());
     }
 }
 
 #[test]
-fn defends_agsinst_effect_activating_without_activating_busses() {
-    let proc = dummy_effect();
-    let host = ComWrapper::new(dummy_host::Host::default())
-        .to_com_ptr::<IHostApplication>()
-        .unwrap();
+fn empty_queues_are_ignored() {
+    let proc = dummy_synth();
+    let host = ComWrapper::new(dummy_host::Host::default());
 
     unsafe {
-        assert_eq!(
-            proc.initialize(host.cast().unwrap().as_ptr()),
-            vst3::Steinberg::kResultOk
-        );
-
-        assert_eq!(
-            proc.setupProcessing(&mut process_setup(&PartialProcessingEnvironment {
-                sampling_rate: 48000.0,
-                max_samples_per_process_call: 512,
-                processing_mode: ProcessingMode::Realtime,
-            })),
-            vst3::Steinberg::kResultOk
+        setup_proc(&proc, &host);
+        let audio = mock_process(
+            2,
+            vec![Event {
+                sample_offset: 0,
+                data: Data::NoteOn {
+                    data: NoteData {
+                        id: NoteID::from_id(0),
+                        pitch: 64,
+                        velocity: 0.5,
+                        tuning: 0f32,
+                        channel: 0,
+                    },
+                },
+            }],
+            vec![ParameterValueQueueImpl {
+                param_id: NUMERIC_ID.to_string(),
+                points: vec![],
+            }],
+            &proc,
         );
 
-        assert_ne!(proc.setActive(1u8), vst3::Steinberg::kResultOk);
+        assert!(audio.is_some());
+        assert_eq!(audio.as_ref().unwrap()[0][100], 1.0);
     }
 }
 
 #[test]
-fn defends_against_changing_processing_environment_while_active() {
+fn defends_against_unsorted_param_queues() {
     let proc = dummy_synth();
-
-    let host = ComWrapper::new(dummy_host::Host::default())
-        .to_com_ptr::<IHostApplication>()
-        .unwrap();
+    let host = ComWrapper::new(dummy_host::Host::default());
 
     unsafe {
-        assert_eq!(
-            proc.initialize(host.cast().unwrap().as_ptr()),
-            vst3::Steinberg::kResultOk
+        setup_proc(&proc, &host);
+        let audio = mock_process(
+            2,
+            vec![Event {
+                sample_offset: 0,
+                data: Data::NoteOn {
+                    data: NoteData {
+                        id: NoteID::from_id(0),
+                        pitch: 64,
+                        velocity: 0.5,
+                        tuning: 0f32,
+                        channel: 0,
+                    },
+                },
+            }],
+            vec![ParameterValueQueueImpl {
+                param_id: NUMERIC_ID.to_string(),
+                points: vec![
+                    ParameterValueQueuePoint {
+                        sample_offset: 100,
+                        value: (1.0 - MIN_NUMERIC as f64) / ((MAX_NUMERIC - MIN_NUMERIC) as f64),
+                    },
+                    ParameterValueQueuePoint {
+                        sample_offset: 99,
+                        value: 1.0,
+                    },
+                ],
+            }],
+            &proc,
         );
 
-        assert_eq!(
-            proc.setupProcessing(&mut process_setup(&PartialProcessingEnvironment {
-                sampling_rate: 48000.0,
-                max_samples_per_process_call: 512,
-                processing_mode: ProcessingMode::Realtime,
-            })),
-            vst3::Steinberg::kResultOk
-        );
-        activate_busses(&proc);
-        assert_eq!(proc.setActive(1u8), vst3::Steinberg::kResultOk);
-        assert_ne!(
-            proc.setupProcessing(&mut process_setup(&PartialProcessingEnvironment {
-                sampling_rate: 44100.0,
-                max_samples_per_process_call: 512,
-                processing_mode: ProcessingMode::Realtime,
-            })),
-            vst3::Steinberg::kResultOk
-        );
+        assert!(audio.is_none());
     }
 }
 
 #[test]
-fn defends_against_activating_without_initialization() {
+fn defends_against_doubled_curve_points() {
     let proc = dummy_synth();
+    let host = ComWrapper::new(dummy_host::Host::default());
 
     unsafe {
-        assert_ne!(proc.setActive(1u8), vst3::Steinberg::kResultOk);
+        setup_proc(&proc, &host);
+        let audio = mock_process(
+            2,
+            vec![Event {
+                sample_offset: 0,
+                data: Data::NoteOn {
+                    data: NoteData {
+                        id: NoteID::from_id(0),
+                        pitch: 64,
+                        velocity: 0.5,
+                        tuning: 0f32,
+                        channel: 0,
+                    },
+                },
+            }],
+            vec![ParameterValueQueueImpl {
+                param_id: NUMERIC_ID.to_string(),
+                points: vec![
+                    ParameterValueQueuePoint {
+                        sample_offset: 99,
+                        value: ((1.0 - MIN_NUMERIC) / (MAX_NUMERIC - MIN_NUMERIC)) as f64,
+                    },
+                    ParameterValueQueuePoint {
+                        sample_offset: 99,
+                        value: 1.0,
+                    },
+                ],
+            }],
+            &proc,
+        );
+
+        assert!(audio.is_none());
     }
 }
 
 #[test]
-fn defends_against_activating_bus_while_active() {
+fn defends_against_points_outside_buffer() {
     let proc = dummy_synth();
-
-    let host = ComWrapper::new(dummy_host::Host::default())
-        .to_com_ptr::<IHostApplication>()
-        .unwrap();
+    let host = ComWrapper::new(dummy_host::Host::default());
 
     unsafe {
-        assert_eq!(
-            proc.initialize(host.cast().unwrap().as_ptr()),
-            vst3::Steinberg::kResultOk
+        setup_proc(&proc, &host);
+        let audio = mock_process(
+            2,
+            vec![Event {
+                sample_offset: 0,
+                data: Data::NoteOn {
+                    data: NoteData {
+                        id: NoteID::from_id(0),
+                        pitch: 64,
+                        velocity: 0.5,
+                        tuning: 0f32,
+                        channel: 0,
+                    },
+                },
+            }],
+            vec![ParameterValueQueueImpl {
+                param_id: NUMERIC_ID.to_string(),
+                points: vec![ParameterValueQueuePoint {
+                    sample_offset: 700,
+                    value: 1.0,
+                }],
+            }],
+            &proc,
         );
 
-        assert_eq!(
-            proc.setupProcessing(&mut process_setup(&PartialProcessingEnvironment {
-                sampling_rate: 48000.0,
-                max_samples_per_process_call: 512,
-                processing_mode: ProcessingMode::Realtime,
-            })),
-            vst3::Steinberg::kResultOk
-        );
-        activate_busses(&proc);
-        assert_eq!(proc.setActive(1u8), vst3::Steinberg::kResultOk);
-        assert_ne!(
-            proc.activateBus(
-                vst3::Steinberg::Vst::MediaTypes_::kAudio as i32,
-                vst3::Steinberg::Vst::BusDirections_::kOutput as i32,
-                0,
-                0
-            ),
-            vst3::Steinberg::kResultOk
-        );
+        assert!(audio.is_none());
     }
 }
 
 #[test]
-fn defends_against_bad_input_count_for_bus_arrangement() {
+fn defends_against_multiple_queues_for_same_param() {
     let proc = dummy_synth();
-
-    let host = ComWrapper::new(dummy_host::Host::default())
-        .to_com_ptr::<IHostApplication>()
-        .unwrap();
+    let host = ComWrapper::new(dummy_host::Host::default());
 
     unsafe {
-        assert_eq!(
-            proc.initialize(host.cast().unwrap().as_ptr()),
-            vst3::Steinberg::kResultOk
-        );
-
-        let mut in_arrangement = vst3::Steinberg::Vst::SpeakerArr::kMono;
-        let mut out_arrangement = vst3::Steinberg::Vst::SpeakerArr::kMono;
-        assert_ne!(
-            proc.setBusArrangements(&mut in_arrangement, 1, &mut out_arrangement, 1),
-            vst3::Steinberg::kResultOk
-        );
-
-        assert_ne!(
-            proc.getBusArrangement(
-                vst3::Steinberg::Vst::BusDirections_::kOutput as i32,
-                1,
-                &mut out_arrangement
-            ),
-            vst3::Steinberg::kResultOk
-        );
-
-        assert_ne!(
-            proc.getBusArrangement(
-                vst3::Steinberg::Vst::BusDirections_::kInput as i32,
-                0,
-                &mut out_arrangement
-            ),
-            vst3::Steinberg::kResultOk
+        setup_proc(&proc, &host);
+        let data = mock_process(
+            2,
+            vec![Event {
+                sample_offset: 0,
+                data: Data::NoteOn {
+                    data: NoteData {
+                        id: NoteID::from_id(0),
+                        pitch: 64,
+                        velocity: 0.5,
+                        tuning: 0f32,
+                        channel: 0,
+                    },
+                },
+            }],
+            vec![
+                ParameterValueQueueImpl {
+                    param_id: NUMERIC_ID.to_string(),
+                    points: vec![ParameterValueQueuePoint {
+                        sample_offset: 99,
+                        value: ((1.0 - MIN_NUMERIC) / (MAX_NUMERIC - MIN_NUMERIC)) as f64,
+                    }],
+                },
+                ParameterValueQueueImpl {
+                    param_id: NUMERIC_ID.to_string(),
+                    points: vec![ParameterValueQueuePoint {
+                        sample_offset: 100,
+                        value: 1.0,
+                    }],
+                },
+            ],
+            &proc,
         );
+
+        assert!(data.is_none());
     }
 }
 
 #[test]
-fn defends_against_get_set_bus_before_init() {
+fn defends_against_non_zero_time_parameter_change() {
     let proc = dummy_synth();
+    let host = ComWrapper::new(dummy_host::Host::default());
+
     unsafe {
-        let mut out_arrangement = vst3::Steinberg::Vst::SpeakerArr::kMono;
-        assert_ne!(
-            proc.setBusArrangements(std::ptr::null_mut(), 0, &mut out_arrangement, 1),
-            vst3::Steinberg::kResultOk
+        setup_proc(&proc, &host);
+        let mut data = mock_no_audio_process_data(
+            vec![],
+            vec![ParameterValueQueueImpl {
+                param_id: NUMERIC_ID.to_string(),
+                points: vec![ParameterValueQueuePoint {
+                    sample_offset: 1,
+                    value: 1.0,
+                }],
+            }],
         );
+
         assert_ne!(
-            proc.getBusArrangement(
-                vst3::Steinberg::Vst::BusDirections_::kOutput as i32,
-                0,
-                &mut out_arrangement
-            ),
+            proc.process(&mut data.process_data),
             vst3::Steinberg::kResultOk
         );
     }
 }
 
 #[test]
-fn defends_against_set_bus_after_active() {
+fn defends_against_out_of_range_parameter_values() {
     let proc = dummy_synth();
-
-    let host = ComWrapper::new(dummy_host::Host::default())
-        .to_com_ptr::<IHostApplication>()
-        .unwrap();
+    let host = ComWrapper::new(dummy_host::Host::default());
 
     unsafe {
-        assert_eq!(
-            proc.initialize(host.cast().unwrap().as_ptr()),
-            vst3::Steinberg::kResultOk
-        );
-
-        let mut out_arrangement = vst3::Steinberg::Vst::SpeakerArr::kMono;
-        assert_eq!(
-            proc.setupProcessing(&mut process_setup(&PartialProcessingEnvironment {
-                sampling_rate: 48000.0,
-                max_samples_per_process_call: 512,
-                processing_mode: ProcessingMode::Realtime,
-            })),
-            vst3::Steinberg::kResultOk
+        setup_proc(&proc, &host);
+        let mut data = mock_no_audio_process_data(
+            vec![],
+            vec![ParameterValueQueueImpl {
+                param_id: NUMERIC_ID.to_string(),
+                points: vec![ParameterValueQueuePoint {
+                    sample_offset: 0,
+                    value: -5.0,
+                }],
+            }],
         );
-        activate_busses(&proc);
-        assert_eq!(proc.setActive(1u8), vst3::Steinberg::kResultOk);
 
         assert_ne!(
-            proc.setBusArrangements(std::ptr::null_mut(), 0, &mut out_arrangement, 1),
-            vst3::Steinberg::kResultOk
-        );
-        // getBusArrangement should still work after setActive.
-        assert_eq!(
-            proc.getBusArrangement(
-                vst3::Steinberg::Vst::BusDirections_::kOutput as i32,
-                0,
-                &mut out_arrangement
-            ),
+            proc.process(&mut data.process_data),
             vst3::Steinberg::kResultOk
         );
     }
 }
 
 #[test]
-fn set_get_bus() {
+fn can_change_parameters_in_handle_events() {
     let proc = dummy_synth();
-
-    let host = ComWrapper::new(dummy_host::Host::default())
-        .to_com_ptr::<IHostApplication>()
-        .unwrap();
+    let host = ComWrapper::new(dummy_host::Host::default());
 
     unsafe {
+        setup_proc(&proc, &host);
         assert_eq!(
-            proc.initialize(host.cast().unwrap().as_ptr()),
-            vst3::Steinberg::kResultOk
-        );
-
-        let mut out_arrangement = vst3::Steinberg::Vst::SpeakerArr::kMono;
-        assert_eq!(
-            proc.setupProcessing(&mut process_setup(&PartialProcessingEnvironment {
-                sampling_rate: 48000.0,
-                max_samples_per_process_call: 512,
-                processing_mode: ProcessingMode::Realtime,
-            })),
-            vst3::Steinberg::kResultOk
-        );
-        activate_busses(&proc);
-
-        assert_eq!(
-            proc.setBusArrangements(std::ptr::null_mut(), 0, &mut out_arrangement, 1),
-            vst3::Steinberg::kResultOk
-        );
-
-        out_arrangement = vst3::Steinberg::Vst::SpeakerArr::kStereo;
-        assert_eq!(
-            proc.getBusArrangement(
-                vst3::Steinberg::Vst::BusDirections_::kOutput as i32,
-                0,
-                &mut out_arrangement
+            proc.process(
+                &mut mock_no_audio_process_data(
+                    vec![],
+                    vec![
+                        ParameterValueQueueImpl {
+                            param_id: NUMERIC_ID.to_string(),
+                            points: vec![ParameterValueQueuePoint {
+                                sample_offset: 0,
+                                value: 1.0,
+                            }],
+                        },
+                        ParameterValueQueueImpl {
+                            param_id: ENUM_ID.to_string(),
+                            points: vec![ParameterValueQueuePoint {
+                                sample_offset: 0,
+                                value: 0.5,
+                            }],
+                        },
+                        ParameterValueQueueImpl {
+                            param_id: SWITCH_ID.to_string(),
+                            points: vec![ParameterValueQueuePoint {
+                                sample_offset: 0,
+                                value: 0.0,
+                            }],
+                        }
+                    ],
+                )
+                .process_data
             ),
             vst3::Steinberg::kResultOk
         );
-        assert_eq!(out_arrangement, vst3::Steinberg::Vst::SpeakerArr::kMono);
 
-        out_arrangement = vst3::Steinberg::Vst::SpeakerArr::kStereo;
-        assert_eq!(
-            proc.setBusArrangements(std::ptr::null_mut(), 0, &mut out_arrangement, 1),
-            vst3::Steinberg::kResultOk
-        );
-        out_arrangement = vst3::Steinberg::Vst::SpeakerArr::kStereo;
-        assert_eq!(
-            proc.getBusArrangement(
-                vst3::Steinberg::Vst::BusDirections_::kOutput as i32,
-                0,
-                &mut out_arrangement
-            ),
-            vst3::Steinberg::kResultOk
+        let audio = mock_process(
+            2,
+            vec![Event {
+                sample_offset: 0,
+                data: Data::NoteOn {
+                    data: NoteData {
+                        id: NoteID::from_id(0),
+                        pitch: 64,
+                        velocity: 0.5,
+                        tuning: 0f32,
+                        channel: 0,
+                    },
+                },
+            }],
+            vec![ParameterValueQueueImpl {
+                param_id: SWITCH_ID.to_string(),
+                points: vec![ParameterValueQueuePoint {
+                    sample_offset: 100,
+                    value: 1.0,
+                }],
+            }],
+            &proc,
         );
-        assert_eq!(out_arrangement, vst3::Steinberg::Vst::SpeakerArr::kStereo);
+
+        assert!(audio.is_some());
+        assert_eq!(audio.as_ref().unwrap()[0][0], 0.0 as f32);
+        assert_eq!(audio.as_ref().unwrap()[0][100], 2.0 * MAX_NUMERIC as f32);
     }
 }
 
 #[test]
-fn set_get_bus_effect() {
-    let proc = dummy_effect();
-
-    let host = ComWrapper::new(dummy_host::Host::default())
-        .to_com_ptr::<IHostApplication>()
+fn defends_against_get_state_before_initialized() {
+    let proc = dummy_synth();
+    let stream = ComWrapper::new(Stream::new([]))
+        .to_com_ptr::<vst3::Steinberg::IBStream>()
         .unwrap();
-
-    unsafe {
-        assert_eq!(
-            proc.initialize(host.cast().unwrap().as_ptr()),
-            vst3::Steinberg::kResultOk
-        );
-        let mut in_arrangement = vst3::Steinberg::Vst::SpeakerArr::kMono;
-        let mut out_arrangement = vst3::Steinberg::Vst::SpeakerArr::kMono;
-
-        assert_eq!(
-            proc.setupProcessing(&mut process_setup(&PartialProcessingEnvironment {
-                sampling_rate: 48000.0,
-                max_samples_per_process_call: 512,
-                processing_mode: ProcessingMode::Realtime,
-            })),
-            vst3::Steinberg::kResultOk
-        );
-        activate_effect_busses(&proc);
-
-        assert_ne!(
-            proc.setBusArrangements(std::ptr::null_mut(), 0, &mut out_arrangement, 1),
-            vst3::Steinberg::kResultOk
-        );
-        assert_eq!(
-            proc.setBusArrangements(&mut in_arrangement, 1, &mut out_arrangement, 1),
-            vst3::Steinberg::kResultTrue
-        );
-
-        out_arrangement = vst3::Steinberg::Vst::SpeakerArr::kStereo;
-        assert_eq!(
-            proc.getBusArrangement(
-                vst3::Steinberg::Vst::BusDirections_::kOutput as i32,
-                0,
-                &mut out_arrangement
-            ),
-            vst3::Steinberg::kResultOk
-        );
-        assert_eq!(out_arrangement, vst3::Steinberg::Vst::SpeakerArr::kMono);
-
-        in_arrangement = vst3::Steinberg::Vst::SpeakerArr::kStereo;
-        assert_ne!(
-            proc.setBusArrangements(&mut in_arrangement, 1, &mut out_arrangement, 1),
-            vst3::Steinberg::kResultTrue
-        );
-
-        out_arrangement = vst3::Steinberg::Vst::SpeakerArr::kStereo;
-        assert_eq!(
-            proc.setBusArrangements(&mut in_arrangement, 1, &mut out_arrangement, 1),
-            vst3::Steinberg::kResultOk
-        );
-        out_arrangement = vst3::Steinberg::Vst::SpeakerArr::kStereo;
-        assert_eq!(
-            proc.getBusArrangement(
-                vst3::Steinberg::Vst::BusDirections_::kOutput as i32,
-                0,
-                &mut out_arrangement
-            ),
-            vst3::Steinberg::kResultOk
-        );
-        assert_eq!(out_arrangement, vst3::Steinberg::Vst::SpeakerArr::kStereo);
-    }
+    let result = unsafe { proc.getState(stream.as_ptr()) };
+    assert_ne!(result, vst3::Steinberg::kResultOk);
 }
 
 #[test]
-fn defends_against_set_processing_before_init() {
+fn defends_against_get_state_with_null_stream() {
     let proc = dummy_synth();
+    let host = ComWrapper::new(dummy_host::Host::default());
     unsafe {
-        assert_ne!(proc.setProcessing(1u8), vst3::Steinberg::kResultOk);
+        setup_proc(&proc, &host);
+        assert_ne!(
+            proc.getState(std::ptr::null_mut()),
+            vst3::Steinberg::kResultOk
+        );
     }
 }
 
 #[test]
-fn defends_against_set_processing_before_setup_processing() {
+fn get_state_saves_data() {
     let proc = dummy_synth();
-    let host = ComWrapper::new(dummy_host::Host::default())
-        .to_com_ptr::<IHostApplication>()
-        .unwrap();
-
+    let host = ComWrapper::new(dummy_host::Host::default());
     unsafe {
+        setup_proc(&proc, &host);
+        let stream = ComWrapper::new(Stream::new([]));
         assert_eq!(
-            proc.initialize(host.cast().unwrap().as_ptr()),
+            proc.getState(
+                stream
+                    .as_com_ref::<vst3::Steinberg::IBStream>()
+                    .unwrap()
+                    .as_ptr()
+            ),
             vst3::Steinberg::kResultOk
         );
-        assert_ne!(proc.setProcessing(1u8), vst3::Steinberg::kResultOk);
+        assert!(stream.data().len() > 0);
     }
 }
 
 #[test]
-fn defends_against_set_processing_before_activating_busses() {
+fn defends_against_set_state_before_initialized() {
     let proc = dummy_synth();
-    let host = ComWrapper::new(dummy_host::Host::default())
-        .to_com_ptr::<IHostApplication>()
+    let stream = ComWrapper::new(Stream::new([]))
+        .to_com_ptr::<vst3::Steinberg::IBStream>()
         .unwrap();
+    let result = unsafe { proc.setState(stream.as_ptr()) };
+    assert_ne!(result, vst3::Steinberg::kResultOk);
+}
 
+#[test]
+fn defends_against_set_state_with_null_stream() {
+    let proc = dummy_synth();
+    let host = ComWrapper::new(dummy_host::Host::default());
     unsafe {
-        assert_eq!(
-            proc.initialize(host.cast().unwrap().as_ptr()),
-            vst3::Steinberg::kResultOk
-        );
-        assert_eq!(
-            proc.setupProcessing(&mut process_setup(&DEFAULT_ENV)),
+        setup_proc(&proc, &host);
+        assert_ne!(
+            proc.setState(std::ptr::null_mut()),
             vst3::Steinberg::kResultOk
         );
-
-        assert_ne!(proc.setProcessing(1u8), vst3::Steinberg::kResultOk);
     }
 }
 
 #[test]
-fn defends_against_set_processing_before_activating() {
-    let proc = dummy_synth();
-    let host = ComWrapper::new(dummy_host::Host::default())
-        .to_com_ptr::<IHostApplication>()
-        .unwrap();
-
+fn set_state_sets_parameters() {
+    let proc1 = dummy_synth();
+    let proc2 = dummy_synth();
+    let proc3 = dummy_synth();
+    let host = ComWrapper::new(dummy_host::Host::default());
     unsafe {
+        setup_proc(&proc1, &host);
+        setup_proc(&proc2, &host);
+        setup_proc(&proc3, &host);
         assert_eq!(
-            proc.initialize(host.cast().unwrap().as_ptr()),
-            vst3::Steinberg::kResultOk
-        );
-        assert_eq!(
-            proc.setupProcessing(&mut process_setup(&DEFAULT_ENV)),
+            proc1.process(
+                &mut mock_no_audio_process_data(
+                    vec![],
+                    vec![
+                        ParameterValueQueueImpl {
+                            param_id: NUMERIC_ID.to_string(),
+                            points: vec![ParameterValueQueuePoint {
+                                sample_offset: 0,
+                                value: 1.0,
+                            }],
+                        },
+                        // Test that pitch bend parameter is _not_ saved
+                        ParameterValueQueueImpl {
+                            param_id: PITCH_BEND_PARAMETER.to_string(),
+                            points: vec![ParameterValueQueuePoint {
+                                sample_offset: 0,
+                                value: 1.0,
+                            }],
+                        }
+                    ],
+                )
+                .process_data
+            ),
             vst3::Steinberg::kResultOk
         );
-        activate_busses(&proc);
-
-        assert_ne!(proc.setProcessing(1u8), vst3::Steinberg::kResultOk);
-    }
-}
-
-#[test]
-fn can_set_processing() {
-    let processing: RefCell<bool> = Default::default();
-    let proc = dummy_synth_with_processing(&processing);
-    let host = ComWrapper::new(dummy_host::Host::default())
-        .to_com_ptr::<IHostApplication>()
-        .unwrap();
 
-    unsafe {
+        let stream = ComWrapper::new(Stream::new([]));
         assert_eq!(
-            proc.initialize(host.cast().unwrap().as_ptr()),
+            proc1.getState(
+                stream
+                    .as_com_ref::<vst3::Steinberg::IBStream>()
+                    .unwrap()
+                    .as_ptr()
+            ),
             vst3::Steinberg::kResultOk
         );
         assert_eq!(
-            proc.setupProcessing(&mut process_setup(&DEFAULT_ENV)),
+            stream.seek(
+                0,
+                vst3::Steinberg::IBStream_::IStreamSeekMode_::kIBSeekSet as i32,
+                std::ptr::null_mut(),
+            ),
             vst3::Steinberg::kResultOk
         );
-        activate_busses(&proc);
-        assert_eq!(proc.setActive(1u8), vst3::Steinberg::kResultOk);
-        assert_eq!(*processing.borrow(), false);
-        assert_eq!(proc.setProcessing(1u8), vst3::Steinberg::kResultOk);
-        assert_eq!(*processing.borrow(), true);
-        assert_eq!(proc.setProcessing(0u8), vst3::Steinberg::kResultOk);
-        assert_eq!(*processing.borrow(), false);
-    }
-}
-
-#[test]
-fn defends_against_set_processing_while_inactive() {
-    let proc = dummy_synth();
-    let host = ComWrapper::new(dummy_host::Host::default())
-        .to_com_ptr::<IHostApplication>()
-        .unwrap();
 
-    unsafe {
+        // Here we test an instant-readback to make sure we don't have a bug where we
+        // only read the correct state after the next process call.
         assert_eq!(
-            proc.initialize(host.cast().unwrap().as_ptr()),
+            proc2.setState(
+                stream
+                    .as_com_ref::<vst3::Steinberg::IBStream>()
+                    .unwrap()
+                    .as_ptr()
+            ),
             vst3::Steinberg::kResultOk
         );
+        let stream = ComWrapper::new(Stream::new([]));
         assert_eq!(
-            proc.setupProcessing(&mut process_setup(&DEFAULT_ENV)),
+            proc2.getState(
+                stream
+                    .as_com_ref::<vst3::Steinberg::IBStream>()
+                    .unwrap()
+                    .as_ptr()
+            ),
             vst3::Steinberg::kResultOk
         );
-        activate_busses(&proc);
-        assert_eq!(proc.setActive(1u8), vst3::Steinberg::kResultOk);
-        assert_eq!(proc.setActive(0u8), vst3::Steinberg::kResultOk);
-
-        assert_ne!(proc.setProcessing(1u8), vst3::Steinberg::kResultOk);
-    }
-}
-
-#[test]
-fn defends_against_processing_without_set() {
-    let proc = dummy_synth();
-    let host = ComWrapper::new(dummy_host::Host::default())
-        .to_com_ptr::<IHostApplication>()
-        .unwrap();
-
-    unsafe {
         assert_eq!(
-            proc.initialize(host.cast().unwrap().as_ptr()),
+            stream.seek(
+                0,
+                vst3::Steinberg::IBStream_::IStreamSeekMode_::kIBSeekSet as i32,
+                std::ptr::null_mut(),
+            ),
             vst3::Steinberg::kResultOk
         );
         assert_eq!(
-            proc.setupProcessing(&mut process_setup(&DEFAULT_ENV)),
+            proc3.setState(
+                stream
+                    .as_com_ref::<vst3::Steinberg::IBStream>()
+                    .unwrap()
+                    .as_ptr()
+            ),
             vst3::Steinberg::kResultOk
         );
-        activate_busses(&proc);
-        assert_eq!(proc.setActive(1u8), vst3::Steinberg::kResultOk);
-        assert_ne!(
-            proc.process(std::ptr::null_mut()),
-            vst3::Steinberg::kResultOk
+
+        let audio = mock_process(
+            2,
+            vec![Event {
+                sample_offset: 10,
+                data: Data::NoteOn {
+                    data: NoteData {
+                        id: NoteID::from_id(0),
+                        pitch: 64,
+                        velocity: 0.5,
+                        tuning: 0f32,
+                        channel: 0,
+                    },
+                },
+            }],
+            vec![],
+            &proc3,
         );
+
+        assert!(audio.is_some());
+
+        assert_eq!(audio.as_ref().unwrap()[0][0], 0.0);
+        assert_eq!(audio.as_ref().unwrap()[0][10], MAX_NUMERIC);
     }
 }
 
 #[test]
-fn can_process() {
+fn serialized_state_loads_via_set_state() {
     let proc = dummy_synth();
     let host = ComWrapper::new(dummy_host::Host::default());
-
+    let values = HashMap::from([
+        (NUMERIC_ID.to_string(), Value::Numeric(2.0)),
+        (ENUM_ID.to_string(), Value::Enum("3".to_string())),
+    ]);
+    let state = serialize_state(&fake_synth_component(), &values).unwrap();
     unsafe {
         setup_proc(&proc, &host);
+        let stream = ComWrapper::new(Stream::new(state));
+        assert_eq!(
+            proc.setState(
+                stream
+                    .as_com_ref::<vst3::Steinberg::IBStream>()
+                    .unwrap()
+                    .as_ptr()
+            ),
+            vst3::Steinberg::kResultOk
+        );
 
         let audio = mock_process(
             2,
-            vec![
-                Event {
-                    sample_offset: 0,
-                    data: Data::NoteOn {
-                        data: NoteData {
-                            id: NoteID::from_id(0),
-                            pitch: 64,
-                            velocity: 0.5,
-                            tuning: 0f32,
-                        },
-                    },
-                },
-                Event {
-                    sample_offset: 100,
-                    data: Data::NoteOff {
-                        data: NoteData {
-                            id: NoteID::from_id(0),
-                            pitch: 64,
-                            velocity: 0.5,
-                            tuning: 0f32,
-                        },
-                    },
-                },
-                Event {
-                    sample_offset: 200,
-                    data: Data::NoteOn {
-                        data: NoteData {
-                            id: NoteID::from_id(0),
-                            pitch: 64,
-                            velocity: 0.5,
-                            tuning: 0f32,
-                        },
-                    },
-                },
-                Event {
-                    sample_offset: 200,
-                    data: Data::NoteOn {
-                        data: NoteData {
-                            id: NoteID::from_id(1),
-                            pitch: 65,
-                            velocity: 0.5,
-                            tuning: 0f32,
-                        },
+            vec![Event {
+                sample_offset: 10,
+                data: Data::NoteOn {
+                    data: NoteData {
+                        id: NoteID::from_id(0),
+                        pitch: 64,
+                        velocity: 0.5,
+                        tuning: 0f32,
+                        channel: 0,
                     },
                 },
-            ],
+            }],
             vec![],
             &proc,
         );
+
         assert!(audio.is_some());
-        assert_eq!(audio.as_ref().unwrap()[0][0], 1.0);
-        assert_eq!(audio.as_ref().unwrap()[1][100], 0.0);
-        assert_eq!(audio.as_ref().unwrap()[1][200], 2.0);
+        assert_eq!(audio.as_ref().unwrap()[0][0], 0.0);
+        // The switch parameter was missing from the state, so it uses its default (on).
+        assert_eq!(audio.as_ref().unwrap()[0][10], 6.0);
     }
 }
 
 #[test]
-fn can_process_mpe() {
+fn get_state_can_be_deserialized() {
     let proc = dummy_synth();
     let host = ComWrapper::new(dummy_host::Host::default());
-
     unsafe {
         setup_proc(&proc, &host);
+        assert_eq!(
+            proc.process(
+                &mut mock_no_audio_process_data(
+                    vec![],
+                    vec![ParameterValueQueueImpl {
+                        param_id: NUMERIC_ID.to_string(),
+                        points: vec![ParameterValueQueuePoint {
+                            sample_offset: 0,
+                            value: 1.0,
+                        }],
+                    }],
+                )
+                .process_data
+            ),
+            vst3::Steinberg::kResultOk
+        );
 
-        let audio = mock_process(
-            2,
-            vec![
-                Event {
-                    sample_offset: 0,
-                    data: Data::NoteOn {
-                        data: NoteData {
-                            id: NoteID::from_id(0),
-                            pitch: 64,
-                            velocity: 0.5,
-                            tuning: 0f32,
-                        },
-                    },
-                },
-                Event {
-                    sample_offset: 10,
-                    data: Data::NoteExpression {
-                        data: NoteExpressionData {
-                            id: NoteID::from_id(0),
-                            expression: NoteExpression::PitchBend(12.0),
-                        },
-                    },
-                },
-                // Should ignore wrong-id events
-                Event {
-                    sample_offset: 11,
-                    data: Data::NoteExpression {
-                        data: NoteExpressionData {
-                            id: NoteID::from_id(4),
-                            expression: NoteExpression::PitchBend(24.0),
-                        },
-                    },
-                },
-                Event {
-                    sample_offset: 16,
-                    data: Data::NoteExpression {
-                        data: NoteExpressionData {
-                            id: NoteID::from_id(0),
-                            expression: NoteExpression::Timbre(0.6),
-                        },
-                    },
-                },
-                Event {
-                    sample_offset: 20,
-                    data: Data::NoteExpression {
-                        data: NoteExpressionData {
-                            id: NoteID::from_id(0),
-                            expression: NoteExpression::Aftertouch(0.7),
-                        },
-                    },
-                },
-                Event {
-                    sample_offset: 90,
-                    data: Data::NoteExpression {
-                        data: NoteExpressionData {
-                            id: NoteID::from_id(0),
-                            expression: NoteExpression::PitchBend(0.0),
-                        },
-                    },
-                },
-                Event {
-                    sample_offset: 90,
-                    data: Data::NoteExpression {
-                        data: NoteExpressionData {
-                            id: NoteID::from_id(0),
-                            expression: NoteExpression::Timbre(0.0),
-                        },
-                    },
-                },
-                Event {
-                    sample_offset: 90,
-                    data: Data::NoteExpression {
-                        data: NoteExpressionData {
-                            id: NoteID::from_id(0),
-                            expression: NoteExpression::Aftertouch(0.0),
-                        },
-                    },
-                },
-                Event {
-                    sample_offset: 100,
-                    data: Data::NoteOff {
-                        data: NoteData {
-                            id: NoteID::from_id(0),
-                            pitch: 64,
-                            velocity: 0.5,
-                            tuning: 0f32,
-                        },
-                    },
-                },
-            ],
-            vec![],
-            &proc,
+        let stream = ComWrapper::new(Stream::new([]));
+        assert_eq!(
+            proc.getState(
+                stream
+                    .as_com_ref::<vst3::Steinberg::IBStream>()
+                    .unwrap()
+                    .as_ptr()
+            ),
+            vst3::Steinberg::kResultOk
+        );
+        assert_eq!(
+            deserialize_state(&fake_synth_component(), &stream.data()),
+            Ok(HashMap::from([
+                (NUMERIC_ID.to_string(), Value::Numeric(MAX_NUMERIC)),
+                (ENUM_ID.to_string(), Value::Enum("1".to_string())),
+                (SWITCH_ID.to_string(), Value::Switch(DEFAULT_SWITCH)),
+            ]))
         );
-        assert!(audio.is_some());
-        assert_eq!(audio.as_ref().unwrap()[0][0], 1.0);
-        assert_approx_eq!(audio.as_ref().unwrap()[0][10], 13.0, 1e-5);
-        assert_approx_eq!(audio.as_ref().unwrap()[0][15], 13.0, 1e-5);
-        assert_approx_eq!(audio.as_ref().unwrap()[0][16], 13.6, 1e-5);
-        assert_approx_eq!(audio.as_ref().unwrap()[0][20], 14.3, 1e-5);
-        assert_approx_eq!(audio.as_ref().unwrap()[0][90], 1.0);
-        assert_eq!(audio.as_ref().unwrap()[1][100], 0.0);
     }
 }
 
 #[test]
-fn can_process_effect() {
-    let proc = dummy_effect();
-    let host = ComWrapper::new(dummy_host::Host::default());
+fn serialize_state_rejects_invalid_values() {
+    let component = fake_synth_component();
+    for (id, value) in [
+        (NUMERIC_ID, Value::Numeric(MAX_NUMERIC + 1.0)),
+        (NUMERIC_ID, Value::Switch(true)),
+        (ENUM_ID, Value::Enum("4".to_string())),
+        ("not_a_parameter", Value::Switch(true)),
+    ] {
+        assert_eq!(
+            serialize_state(&component, &HashMap::from([(id.to_string(), value)])),
+            Err(StateError::InvalidValues)
+        );
+    }
+}
 
-    unsafe {
-        setup_proc_effect(&proc, &host);
+#[test]
+fn deserialize_state_rejects_malformed_data() {
+    assert_eq!(
+        deserialize_state(&fake_synth_component(), &[0xc1, 0x00]),
+        Err(StateError::Malformed)
+    );
+}
 
-        let audio = mock_process_effect(
-            vec![vec![1f32; 512]; 2],
-            vec![
-                ParameterValueQueueImpl {
-                    param_id: NUMERIC_ID.to_string(),
-                    points: vec![
-                        ParameterValueQueuePoint {
-                            sample_offset: 99,
-                            value: ((1.0 - MIN_NUMERIC) / (MAX_NUMERIC - MIN_NUMERIC)) as f64,
-                        },
-                        ParameterValueQueuePoint {
-                            sample_offset: 100,
-                            value: 1.0,
-                        },
-                    ],
-                },
-                ParameterValueQueueImpl {
-                    param_id: SWITCH_ID.to_string(),
-                    points: vec![
-                        ParameterValueQueuePoint {
-                            sample_offset: 500,
+#[test]
+fn get_state_sees_automation() {
+    let proc1 = dummy_synth();
+    let proc2 = dummy_synth();
+    let proc3 = dummy_synth();
+    let host = ComWrapper::new(dummy_host::Host::default());
+    unsafe {
+        setup_proc(&proc1, &host);
+        setup_proc(&proc2, &host);
+        setup_proc(&proc3, &host);
+        assert_eq!(
+            proc1.process(
+                &mut mock_no_audio_process_data(
+                    vec![],
+                    vec![ParameterValueQueueImpl {
+                        param_id: NUMERIC_ID.to_string(),
+                        points: vec![ParameterValueQueuePoint {
+                            sample_offset: 0,
                             value: 1.0,
-                        },
-                        ParameterValueQueuePoint {
-                            sample_offset: 501,
-                            value: 0.0,
-                        },
-                    ],
-                },
-                ParameterValueQueueImpl {
-                    param_id: ENUM_ID.to_string(),
-                    points: vec![
-                        ParameterValueQueuePoint {
-                            sample_offset: 300,
-                            value: 0.33,
-                        },
-                        ParameterValueQueuePoint {
-                            sample_offset: 301,
-                            value: 0.34,
-                        },
-                    ],
-                },
-            ],
-            &proc,
+                        }],
+                    },],
+                )
+                .process_data
+            ),
+            vst3::Steinberg::kResultOk
+        );
+
+        let stream = ComWrapper::new(Stream::new([]));
+        assert_eq!(
+            proc1.getState(
+                stream
+                    .as_com_ref::<vst3::Steinberg::IBStream>()
+                    .unwrap()
+                    .as_ptr()
+            ),
+            vst3::Steinberg::kResultOk
+        );
+        assert_eq!(
+            stream.seek(
+                0,
+                vst3::Steinberg::IBStream_::IStreamSeekMode_::kIBSeekSet as i32,
+                std::ptr::null_mut(),
+            ),
+            vst3::Steinberg::kResultOk
+        );
+
+        assert_eq!(
+            proc2.setState(
+                stream
+                    .as_com_ref::<vst3::Steinberg::IBStream>()
+                    .unwrap()
+                    .as_ptr()
+            ),
+            vst3::Steinberg::kResultOk
+        );
+
+        assert_eq!(
+            proc2.process(
+                &mut mock_no_audio_process_data(
+                    vec![],
+                    vec![ParameterValueQueueImpl {
+                        param_id: ENUM_ID.to_string(),
+                        points: vec![ParameterValueQueuePoint {
+                            sample_offset: 0,
+                            value: 0.5,
+                        }],
+                    },],
+                )
+                .process_data
+            ),
+            vst3::Steinberg::kResultOk
         );
-        assert!(audio.is_some());
-        assert_approx_eq!(audio.as_ref().unwrap()[1][100], 10.0);
-        assert_approx_eq!(audio.as_ref().unwrap()[1][500], 20.0);
-    }
-}
 
-#[test]
-fn defends_against_events_past_buffer() {
-    let proc = dummy_synth();
-    let host = ComWrapper::new(dummy_host::Host::default());
+        let stream = ComWrapper::new(Stream::new([]));
+        assert_eq!(
+            proc2.getState(
+                stream
+                    .as_com_ref::<vst3::Steinberg::IBStream>()
+                    .unwrap()
+                    .as_ptr()
+            ),
+            vst3::Steinberg::kResultOk
+        );
+        assert_eq!(
+            stream.seek(
+                0,
+                vst3::Steinberg::IBStream_::IStreamSeekMode_::kIBSeekSet as i32,
+                std::ptr::null_mut(),
+            ),
+            vst3::Steinberg::kResultOk
+        );
+        assert_eq!(
+            proc3.setState(
+                stream
+                    .as_com_ref::<vst3::Steinberg::IBStream>()
+                    .unwrap()
+                    .as_ptr()
+            ),
+            vst3::Steinberg::kResultOk
+        );
 
-    unsafe {
-        setup_proc(&proc, &host);
-        assert!(mock_process(
+        let audio = mock_process(
             2,
             vec![Event {
-                sample_offset: SAMPLE_COUNT + 1000,
+                sample_offset: 10,
                 data: Data::NoteOn {
                     data: NoteData {
                         id: NoteID::from_id(0),
                         pitch: 64,
                         velocity: 0.5,
                         tuning: 0f32,
+                        channel: 0,
                     },
                 },
             }],
             vec![],
-            &proc
-        )
-        .is_none());
+            &proc3,
+        );
+        assert!(audio.is_some());
+
+        assert_eq!(audio.as_ref().unwrap()[0][0], 0.0);
+        assert_eq!(audio.as_ref().unwrap()[0][10], 2.0 * MAX_NUMERIC);
     }
 }
 
 #[test]
-fn defends_against_shuffled_events() {
+fn defends_against_invalid_state_data() {
     let proc = dummy_synth();
     let host = ComWrapper::new(dummy_host::Host::default());
-
     unsafe {
         setup_proc(&proc, &host);
 
-        assert!(mock_process(
-            2,
-            vec![
-                Event {
-                    sample_offset: 200,
-                    data: Data::NoteOn {
-                        data: NoteData {
-                            id: NoteID::from_id(0),
-                            pitch: 64,
-                            velocity: 0.5,
-                            tuning: 0f32,
-                        },
-                    },
-                },
-                Event {
-                    sample_offset: 100,
-                    data: Data::NoteOff {
-                        data: NoteData {
-                            id: NoteID::from_id(0),
-                            pitch: 64,
-                            velocity: 0.5,
-                            tuning: 0f32,
-                        },
-                    },
-                },
-            ],
-            vec![],
-            &proc
-        )
-        .is_none());
+        // Note - this test makes a (currently valid) assumption that an empty state buffer
+        // is not a valid state. If the implementation of processor's serialization changes
+        // and this assumption becomes invalid, please replace the empty buffer with an
+        // invalid state buffer.
+        let invalid_stream = ComWrapper::new(Stream::new([]));
+        assert_ne!(
+            proc.setState(
+                invalid_stream
+                    .as_com_ref::<vst3::Steinberg::IBStream>()
+                    .unwrap()
+                    .as_ptr()
+            ),
+            vst3::Steinberg::kResultOk
+        );
+    }
+}
+
+static INCOMPATIBLE_PARAMETERS: [StaticInfoRef; 1] = [InfoRef {
+    title: "Multiplier",
+    short_title: "Mult",
+    // This is incompatible since the previous version had a
+    // parameter of a different type with this ID
+    unique_id: ENUM_ID,
+    flags: Flags {
+        automatable: true,
+        curve_hint: CurveHint::Auto,
+    },
+    type_specific: TypeSpecificInfoRef::Numeric {
+        default: DEFAULT_NUMERIC,
+        valid_range: MIN_NUMERIC..=MAX_NUMERIC,
+        units: Some("Hz"),
+        options: NumericOptions::DEFAULT,
+    },
+}];
+
+#[derive(Default)]
+struct IncompatibleSynth {}
+
+impl Processor for IncompatibleSynth {
+    fn set_processing(&mut self, _processing: bool) {}
+}
+
+impl Synth for IncompatibleSynth {
+    fn handle_events<E: IntoIterator<Item = Data>, P: States>(
+        &mut self,
+        _events: E,
+        _parameters: P,
+    ) {
+    }
+
+    fn process<E: IntoIterator<Item = Event>, P: BufferStates, O: BufferMut>(
+        &mut self,
+        _events: Events<E>,
+        _parameters: P,
+        _output: &mut O,
+    ) {
     }
 }
 
 #[test]
-fn can_handle_null_events() {
-    let proc = dummy_synth();
-    let host = ComWrapper::new(dummy_host::Host::default());
+fn defends_against_load_state_with_incompatible_parameters() {
+    let proc1 = dummy_synth();
+    let proc2 = create_synth(
+        |_: &HostInfo| FakeComponent {
+            parameter_infos: conformal_component::parameters::to_infos(&INCOMPATIBLE_PARAMETERS),
+            ..FakeComponent::new(|_| IncompatibleSynth::default())
+        },
+        [5; 16],
+        SynthOptions::DEFAULT,
+    );
 
+    let host = ComWrapper::new(dummy_host::Host::default());
     unsafe {
-        setup_proc(&proc, &host);
+        setup_proc(&proc1, &host);
+        setup_proc(&proc2, &host);
+        let stream = ComWrapper::new(Stream::new([]));
+        assert_eq!(
+            proc1.getState(
+                stream
+                    .as_com_ref::<vst3::Steinberg::IBStream>()
+                    .unwrap()
+                    .as_ptr()
+            ),
+            vst3::Steinberg::kResultOk
+        );
+        assert_eq!(
+            stream.seek(
+                0,
+                vst3::Steinberg::IBStream_::IStreamSeekMode_::kIBSeekSet as i32,
+                std::ptr::null_mut(),
+            ),
+            vst3::Steinberg::kResultOk
+        );
+        assert_ne!(
+            proc2.setState(
+                stream
+                    .as_com_ref::<vst3::Steinberg::IBStream>()
+                    .unwrap()
+                    .as_ptr()
+            ),
+            vst3::Steinberg::kResultOk
+        );
+    }
+}
 
-        let audio = mock_process_mod(2, vec![], vec![], &proc, |x| {
-            x.inputEvents = std::ptr::null_mut();
-        });
-        assert!(audio.is_some());
-        assert_eq!(audio.as_ref().unwrap()[0][0], 0.0);
+#[derive(Default)]
+struct NewerSynth {}
+
+impl Processor for NewerSynth {
+    fn set_processing(&mut self, _processing: bool) {}
+}
+
+impl Synth for NewerSynth {
+    fn handle_events<E: IntoIterator<Item = Data>, P: States>(
+        &mut self,
+        _events: E,
+        _parameters: P,
+    ) {
+    }
+
+    fn process<E: IntoIterator<Item = Event>, P: BufferStates, O: BufferMut>(
+        &mut self,
+        _events: Events<E>,
+        _parameters: P,
+        _output: &mut O,
+    ) {
     }
 }
 
+static NEWER_PARAMETERS: [StaticInfoRef; 3] = [
+    InfoRef {
+        title: "Multiplier",
+        short_title: "Mult",
+        unique_id: "mult",
+        flags: Flags {
+            automatable: true,
+            curve_hint: CurveHint::Auto,
+        },
+        type_specific: TypeSpecificInfoRef::Numeric {
+            default: DEFAULT_NUMERIC,
+            valid_range: MIN_NUMERIC..=20.0,
+            units: Some("Hz"),
+            options: NumericOptions::DEFAULT,
+        },
+    },
+    InfoRef {
+        title: "Enum Multiplier",
+        short_title: "Enum",
+        unique_id: "enum_mult",
+        flags: Flags {
+            automatable: true,
+            curve_hint: CurveHint::Auto,
+        },
+        type_specific: TypeSpecificInfoRef::Enum {
+            default: DEFAULT_ENUM,
+            values: &["1", "2", "3"],
+        },
+    },
+    InfoRef {
+        title: "Switch Multipler",
+        short_title: "Switch",
+        unique_id: "switch",
+        flags: Flags {
+            automatable: true,
+            curve_hint: CurveHint::Auto,
+        },
+        type_specific: TypeSpecificInfoRef::Switch {
+            default: DEFAULT_SWITCH,
+        },
+    },
+];
+
 #[test]
-fn defends_against_timed_events_without_audio() {
-    let proc = dummy_synth();
+fn loading_too_new_parameters_loads_default_state() {
+    let proc1 = dummy_synth();
+    let proc2 = create_synth(
+        |_: &HostInfo| FakeComponent {
+            parameter_infos: conformal_component::parameters::to_infos(&NEWER_PARAMETERS),
+            ..FakeComponent::new(|_| NewerSynth::default())
+        },
+        [5; 16],
+        SynthOptions::DEFAULT,
+    );
     let host = ComWrapper::new(dummy_host::Host::default());
-
     unsafe {
-        setup_proc(&proc, &host);
-
-        let mut data = mock_no_audio_process_data(
-            vec![Event {
-                sample_offset: 100,
-                data: Data::NoteOn {
-                    data: NoteData {
-                        id: NoteID::from_id(0),
-                        pitch: 64,
-                        velocity: 0.5,
-                        tuning: 0f32,
-                    },
-                },
-            }],
-            vec![],
+        setup_proc(&proc1, &host);
+        setup_proc(&proc2, &host);
+        assert_eq!(
+            proc1.process(
+                &mut mock_no_audio_process_data(
+                    vec![],
+                    vec![ParameterValueQueueImpl {
+                        param_id: NUMERIC_ID.to_string(),
+                        points: vec![ParameterValueQueuePoint {
+                            sample_offset: 0,
+                            value: 1.0, // Set it to the old max of 10!
+                        }],
+                    },],
+                )
+                .process_data
+            ),
+            vst3::Steinberg::kResultOk
         );
-        assert_ne!(
-            proc.process(&mut data.process_data),
+        assert_eq!(
+            proc2.process(
+                &mut mock_no_audio_process_data(
+                    vec![],
+                    vec![ParameterValueQueueImpl {
+                        param_id: NUMERIC_ID.to_string(),
+                        points: vec![ParameterValueQueuePoint {
+                            sample_offset: 0,
+                            value: 1.0, // Set it to the new max of 20!
+                        }],
+                    },],
+                )
+                .process_data
+            ),
             vst3::Steinberg::kResultOk
         );
-    }
-}
-
-#[test]
-fn can_handle_events_without_audio() {
-    let proc = dummy_synth();
-    let host = ComWrapper::new(dummy_host::Host::default());
 
-    unsafe {
-        setup_proc(&proc, &host);
-
-        let audio = mock_process(
-            2,
-            vec![
-                Event {
-                    sample_offset: 0,
-                    data: Data::NoteOn {
-                        data: NoteData {
-                            id: NoteID::from_id(0),
-                            pitch: 64,
-                            velocity: 0.5,
-                            tuning: 0f32,
-                        },
-                    },
-                },
-                Event {
-                    sample_offset: 0,
-                    data: Data::NoteOn {
-                        data: NoteData {
-                            id: NoteID::from_id(1),
-                            pitch: 64,
-                            velocity: 0.5,
-                            tuning: 0f32,
-                        },
-                    },
-                },
-            ],
-            vec![],
-            &proc,
+        let stream = ComWrapper::new(Stream::new([]));
+        assert_eq!(
+            proc2.getState(
+                stream
+                    .as_com_ref::<vst3::Steinberg::IBStream>()
+                    .unwrap()
+                    .as_ptr()
+            ),
+            vst3::Steinberg::kResultOk
         );
-
-        assert!(audio.is_some());
-
-        let mut data2 = mock_no_audio_process_data(
-            vec![Event {
-                sample_offset: 0,
-                data: Data::NoteOff {
-                    data: NoteData {
-                        id: NoteID::from_id(0),
-                        pitch: 64,
-                        velocity: 0.5,
-                        tuning: 0f32,
-                    },
-                },
-            }],
-            vec![],
+        assert_eq!(
+            stream.seek(
+                0,
+                vst3::Steinberg::IBStream_::IStreamSeekMode_::kIBSeekSet as i32,
+                std::ptr::null_mut(),
+            ),
+            vst3::Steinberg::kResultOk
         );
-
         assert_eq!(
-            proc.process(&mut data2.process_data),
+            proc1.setState(
+                stream
+                    .as_com_ref::<vst3::Steinberg::IBStream>()
+                    .unwrap()
+                    .as_ptr()
+            ),
             vst3::Steinberg::kResultOk
         );
 
-        let audio3 = mock_process(
+        // Now, since proc2 was a newer version than proc1, we expect proc1 to be re-set
+        // to its default state.
+        let audio = mock_process(
             2,
             vec![Event {
-                sample_offset: 100,
-                data: Data::NoteOff {
+                sample_offset: 10,
+                data: Data::NoteOn {
                     data: NoteData {
-                        id: NoteID::from_id(1),
+                        id: NoteID::from_id(0),
                         pitch: 64,
                         velocity: 0.5,
                         tuning: 0f32,
+                        channel: 0,
                     },
                 },
             }],
             vec![],
-            &proc,
+            &proc1,
         );
-        assert!(audio3.is_some());
-        assert_eq!(audio3.as_ref().unwrap()[0][0], 1.0);
-        assert_eq!(audio3.as_ref().unwrap()[1][100], 0.0);
+        assert!(audio.is_some());
+
+        assert_eq!(audio.as_ref().unwrap()[0][10], 1.0);
     }
 }
 
+/// A synth where the meaning of the "mult" parameter changed in schema version 2 -
+/// states saved from version 1 must have their "mult" doubled.
+fn versioned_synth(version: u32) -> impl IAudioProcessorTrait + IComponentTrait {
+    create_synth(
+        move |_: &HostInfo| FakeComponent {
+            state_version: version,
+            upgrade_parameters: |saved_version, values| {
+                if saved_version < 2 {
+                    if let Some(Value::Numeric(mult)) = values.get_mut(NUMERIC_ID) {
+                        *mult *= 2.0;
+                    }
+                }
+            },
+            ..fake_synth_component()
+        },
+        [4; 16],
+        SynthOptions::DEFAULT,
+    )
+}
+
 #[test]
-fn can_handle_parameter_changes() {
-    let proc = dummy_synth();
+fn loading_older_state_version_upgrades_parameters() {
+    let proc_v1 = versioned_synth(1);
+    let proc_v1_reload = versioned_synth(1);
+    let proc_v2 = versioned_synth(2);
     let host = ComWrapper::new(dummy_host::Host::default());
-
     unsafe {
-        setup_proc(&proc, &host);
-        let audio = mock_process(
-            2,
+        setup_proc(&proc_v1, &host);
+        setup_proc(&proc_v1_reload, &host);
+        setup_proc(&proc_v2, &host);
+        assert_eq!(
+            proc_v1.process(
+                &mut mock_no_audio_process_data(
+                    vec![],
+                    vec![ParameterValueQueueImpl {
+                        param_id: NUMERIC_ID.to_string(),
+                        points: vec![ParameterValueQueuePoint {
+                            sample_offset: 0,
+                            value: f64::from((2.0 - MIN_NUMERIC) / (MAX_NUMERIC - MIN_NUMERIC)),
+                        }],
+                    },],
+                )
+                .process_data
+            ),
+            vst3::Steinberg::kResultOk
+        );
+
+        let stream = ComWrapper::new(Stream::new([]));
+        assert_eq!(
+            proc_v1.getState(
+                stream
+                    .as_com_ref::<vst3::Steinberg::IBStream>()
+                    .unwrap()
+                    .as_ptr()
+            ),
+            vst3::Steinberg::kResultOk
+        );
+
+        let note_on = || {
             vec![Event {
-                sample_offset: 0,
+                sample_offset: 10,
                 data: Data::NoteOn {
                     data: NoteData {
                         id: NoteID::from_id(0),
                         pitch: 64,
                         velocity: 0.5,
                         tuning: 0f32,
+                        channel: 0,
                     },
                 },
-            }],
-            vec![
-                ParameterValueQueueImpl {
-                    param_id: NUMERIC_ID.to_string(),
-                    points: vec![
-                        ParameterValueQueuePoint {
-                            sample_offset: 99,
-                            value: ((1.0 - MIN_NUMERIC) / (MAX_NUMERIC - MIN_NUMERIC)) as f64,
-                        },
-                        ParameterValueQueuePoint {
-                            sample_offset: 100,
-                            value: 1.0,
-                        },
-                    ],
-                },
-                ParameterValueQueueImpl {
-                    param_id: SWITCH_ID.to_string(),
-                    points: vec![
-                        ParameterValueQueuePoint {
-                            sample_offset: 500,
-                            value: 1.0,
-                        },
-                        ParameterValueQueuePoint {
-                            sample_offset: 501,
-                            value: 0.0,
-                        },
-                    ],
-                },
-                ParameterValueQueueImpl {
-                    param_id: ENUM_ID.to_string(),
-                    points: vec![
-                        ParameterValueQueuePoint {
-                            sample_offset: 300,
-                            value: 0.33,
-                        },
-                        ParameterValueQueuePoint {
-                            sample_offset: 301,
-                            value: 0.34,
-                        },
-                    ],
-                },
-            ],
-            &proc,
-        );
+            }]
+        };
 
-        assert!(audio.is_some());
-        let audio = audio.as_ref().unwrap();
-        assert_eq!(audio[0][0], 1.0);
-        assert_eq!(audio[1][99], 1.0);
-        assert_eq!(audio[0][100], MAX_NUMERIC as f32);
-        assert_eq!(audio[0][200], MAX_NUMERIC as f32);
-        assert_eq!(audio[0][300], MAX_NUMERIC as f32);
-        assert_eq!(audio[0][301], (MAX_NUMERIC * 2.0) as f32);
-        assert_eq!(audio[0][500], (MAX_NUMERIC * 2.0) as f32);
-        assert_eq!(audio[0][501], 0.0);
+        for (proc, expected_mult) in [(&proc_v1_reload, 2.0), (&proc_v2, 4.0)] {
+            assert_eq!(
+                stream.seek(
+                    0,
+                    vst3::Steinberg::IBStream_::IStreamSeekMode_::kIBSeekSet as i32,
+                    std::ptr::null_mut(),
+                ),
+                vst3::Steinberg::kResultOk
+            );
+            assert_eq!(
+                proc.setState(
+                    stream
+                        .as_com_ref::<vst3::Steinberg::IBStream>()
+                        .unwrap()
+                        .as_ptr()
+                ),
+                vst3::Steinberg::kResultOk
+            );
+
+            // Loading into the same version should not upgrade, but loading into
+            // the newer version should.
+            let audio = mock_process(2, note_on(), vec![], proc);
+            assert!(audio.is_some());
+            assert_approx_eq!(audio.as_ref().unwrap()[0][10], expected_mult, 1e-5);
+        }
+    }
+}
+
+/// A component where version 1 had a "volume" parameter from 0 to 100, which was
+/// renamed to "mult" and rescaled to 0 to 10 in version 2.
+fn renaming_component() -> FakeComponent<'static, FakeSynth<'static>> {
+    FakeComponent {
+        state_version: 2,
+        upgrade_parameters: |saved_version, values| {
+            if saved_version < 2 {
+                match values.remove("volume") {
+                    Some(Value::Numeric(volume)) => {
+                        values.insert(NUMERIC_ID.to_string(), Value::Numeric(volume / 10.0));
+                    }
+                    // Leave invalid values in place so the state fails to load.
+                    Some(volume) => {
+                        values.insert(NUMERIC_ID.to_string(), volume);
+                    }
+                    None => {}
+                }
+            }
+        },
+        ..fake_synth_component()
     }
 }
 
+fn version_1_state(volume: Value) -> Vec<u8> {
+    serialize_values(
+        vec![conformal_component::parameters::Info {
+            unique_id: "volume".to_string(),
+            title: "Volume".to_string(),
+            short_title: "Volume".to_string(),
+            flags: Flags::default(),
+            type_specific: match volume {
+                Value::Switch(_) => TypeSpecificInfo::Switch { default: false },
+                _ => TypeSpecificInfo::Numeric {
+                    default: 50.0,
+                    valid_range: 0.0..=100.0,
+                    units: None,
+                    options: Default::default(),
+                },
+            },
+        }],
+        1,
+        &HashMap::from([("volume".to_string(), volume)]),
+    )
+    .unwrap()
+}
+
 #[test]
-fn parameter_changes_at_start_of_buffer() {
-    let proc = dummy_synth();
-    let host = ComWrapper::new(dummy_host::Host::default());
+fn loading_older_state_version_upgrades_raw_values() {
+    let state = version_1_state(Value::Numeric(40.0));
+    let expected = HashMap::from([
+        (NUMERIC_ID.to_string(), Value::Numeric(4.0)),
+        (ENUM_ID.to_string(), Value::Enum("1".to_string())),
+        (SWITCH_ID.to_string(), Value::Switch(DEFAULT_SWITCH)),
+    ]);
+    assert_eq!(
+        deserialize_state(&renaming_component(), &state),
+        Ok(expected.clone())
+    );
 
+    let proc = create_synth(
+        |_: &HostInfo| renaming_component(),
+        [4; 16],
+        SynthOptions::DEFAULT,
+    );
+    let host = ComWrapper::new(dummy_host::Host::default());
     unsafe {
         setup_proc(&proc, &host);
-        let audio = mock_process(
-            2,
-            vec![Event {
-                sample_offset: 0,
-                data: Data::NoteOn {
-                    data: NoteData {
-                        id: NoteID::from_id(0),
-                        pitch: 64,
-                        velocity: 0.5,
-                        tuning: 0f32,
-                    },
-                },
-            }],
-            vec![ParameterValueQueueImpl {
-                param_id: NUMERIC_ID.to_string(),
-                points: vec![
-                    ParameterValueQueuePoint {
-                        sample_offset: 0,
-                        value: ((0.6 - MIN_NUMERIC) / (MAX_NUMERIC - MIN_NUMERIC)) as f64,
-                    },
-                    ParameterValueQueuePoint {
-                        sample_offset: 100,
-                        value: 1.0,
-                    },
-                ],
-            }],
-            &proc,
+        let stream = ComWrapper::new(Stream::new(state));
+        assert_eq!(
+            proc.setState(
+                stream
+                    .as_com_ref::<vst3::Steinberg::IBStream>()
+                    .unwrap()
+                    .as_ptr()
+            ),
+            vst3::Steinberg::kResultOk
         );
 
-        assert!(audio.is_some());
-        let audio = audio.as_ref().unwrap();
-        assert_eq!(audio[0][0], 0.6);
-        assert_eq!(audio[0][100], MAX_NUMERIC as f32);
-        assert_eq!(audio[0][500], MAX_NUMERIC as f32);
+        let saved = ComWrapper::new(Stream::new([]));
+        assert_eq!(
+            proc.getState(
+                saved
+                    .as_com_ref::<vst3::Steinberg::IBStream>()
+                    .unwrap()
+                    .as_ptr()
+            ),
+            vst3::Steinberg::kResultOk
+        );
+        assert_eq!(
+            deserialize_state(&renaming_component(), &saved.data()),
+            Ok(expected)
+        );
     }
 }
 
 #[test]
-fn defends_against_wild_parameter_ids() {
-    let proc = dummy_synth();
-    let host = ComWrapper::new(dummy_host::Host::default());
+fn defends_against_invalid_upgraded_state() {
+    let state = version_1_state(Value::Switch(true));
+    assert_eq!(
+        deserialize_state(&renaming_component(), &state),
+        Err(StateError::InvalidValues)
+    );
 
+    let proc = create_synth(
+        |_: &HostInfo| renaming_component(),
+        [4; 16],
+        SynthOptions::DEFAULT,
+    );
+    let host = ComWrapper::new(dummy_host::Host::default());
     unsafe {
         setup_proc(&proc, &host);
-        let audio = mock_process(
-            2,
-            vec![Event {
-                sample_offset: 0,
-                data: Data::NoteOn {
-                    data: NoteData {
-                        id: NoteID::from_id(0),
-                        pitch: 64,
-                        velocity: 0.5,
-                        tuning: 0f32,
-                    },
-                },
-            }],
-            vec![ParameterValueQueueImpl {
-                param_id: "some garbage parameter".to_string(),
-                points: vec![ParameterValueQueuePoint {
-                    sample_offset: 0,
-                    value: MAX_NUMERIC as f64,
-                }],
-            }],
-            &proc,
+        let stream = ComWrapper::new(Stream::new(state));
+        assert_eq!(
+            proc.setState(
+                stream
+                    .as_com_ref::<vst3::Steinberg::IBStream>()
+                    .unwrap()
+                    .as_ptr()
+            ),
+            vst3::Steinberg::kInvalidArgument
         );
+    }
+}
 
-        assert!(audio.is_none());
+static DUPLICATE_PARAMETERS: [StaticInfoRef; 2] = [
+    InfoRef {
+        title: "Multiplier",
+        short_title: "Mult",
+        unique_id: "mult",
+        flags: Flags {
+            automatable: true,
+            curve_hint: CurveHint::Auto,
+        },
+        type_specific: TypeSpecificInfoRef::Numeric {
+            default: DEFAULT_NUMERIC,
+            valid_range: MIN_NUMERIC..=20.0,
+            units: Some("Hz"),
+            options: NumericOptions::DEFAULT,
+        },
+    },
+    InfoRef {
+        title: "Multiplier",
+        short_title: "Mult",
+        unique_id: "mult",
+        flags: Flags {
+            automatable: true,
+            curve_hint: CurveHint::Auto,
+        },
+        type_specific: TypeSpecificInfoRef::Numeric {
+            default: DEFAULT_NUMERIC,
+            valid_range: MIN_NUMERIC..=20.0,
+            units: Some("Hz"),
+            options: NumericOptions::DEFAULT,
+        },
+    },
+];
+
+#[test]
+#[should_panic]
+fn panic_on_duplicate_ids() {
+    let processor = create_synth(
+        |_: &HostInfo| FakeComponent {
+            parameter_infos: conformal_component::parameters::to_infos(&DUPLICATE_PARAMETERS),
+            ..FakeComponent::new(|_| IncompatibleSynth::default())
+        },
+        [5; 16],
+        SynthOptions::DEFAULT,
+    );
+    let host = ComWrapper::new(dummy_host::Host::default());
+    unsafe {
+        processor.initialize(host.as_com_ref().unwrap().as_ptr());
     }
 }
 
 #[test]
-fn empty_queues_are_ignored() {
+fn supports_toggling_active_while_processing() {
+    // Note that this call pattern is _explicitly disallowed_ by the spec,
+    // but we support it anyway since some DAWs do it (tested Ableton 11.3.20).
     let proc = dummy_synth();
     let host = ComWrapper::new(dummy_host::Host::default());
 
     unsafe {
         setup_proc(&proc, &host);
+
+        proc.setActive(0);
+        proc.setActive(1);
+
         let audio = mock_process(
             2,
             vec![Event {
-                sample_offset: 0,
+                sample_offset: 100,
                 data: Data::NoteOn {
                     data: NoteData {
                         id: NoteID::from_id(0),
                         pitch: 64,
                         velocity: 0.5,
                         tuning: 0f32,
+                        channel: 0,
                     },
                 },
             }],
-            vec![ParameterValueQueueImpl {
-                param_id: NUMERIC_ID.to_string(),
-                points: vec![],
-            }],
+            vec![],
             &proc,
         );
-
         assert!(audio.is_some());
-        assert_eq!(audio.as_ref().unwrap()[0][100], 1.0);
+        assert_eq!(audio.as_ref().unwrap()[0][0], 0.0);
+        assert_eq!(audio.as_ref().unwrap()[1][100], 1.0);
     }
 }
 
 #[test]
-fn defends_against_unsorted_param_queues() {
+fn supports_mpe_quirks() {
     let proc = dummy_synth();
     let host = ComWrapper::new(dummy_host::Host::default());
 
     unsafe {
         setup_proc(&proc, &host);
+
         let audio = mock_process(
             2,
             vec![Event {
-                sample_offset: 0,
+                sample_offset: 10,
                 data: Data::NoteOn {
                     data: NoteData {
-                        id: NoteID::from_id(0),
+                        id: NoteID::from_channel_for_mpe_quirks(1),
                         pitch: 64,
                         velocity: 0.5,
                         tuning: 0f32,
@@ -1989,125 +4389,268 @@ fn defends_against_unsorted_param_queues() {
                 },
             }],
             vec![ParameterValueQueueImpl {
-                param_id: NUMERIC_ID.to_string(),
-                points: vec![
-                    ParameterValueQueuePoint {
-                        sample_offset: 100,
-                        value: (1.0 - MIN_NUMERIC as f64) / ((MAX_NUMERIC - MIN_NUMERIC) as f64),
-                    },
-                    ParameterValueQueuePoint {
-                        sample_offset: 99,
-                        value: 1.0,
-                    },
-                ],
+                param_id: aftertouch_param_id(1).to_string(),
+                points: vec![ParameterValueQueuePoint {
+                    sample_offset: 10,
+                    value: 1.0,
+                }],
             }],
             &proc,
         );
 
-        assert!(audio.is_none());
+        assert_approx_eq!(audio.as_ref().unwrap()[0][10], 2.0);
     }
 }
 
 #[test]
-fn defends_against_doubled_curve_points() {
+fn supports_mpe_quirks_no_audio() {
     let proc = dummy_synth();
     let host = ComWrapper::new(dummy_host::Host::default());
 
     unsafe {
         setup_proc(&proc, &host);
-        let audio = mock_process(
-            2,
-            vec![Event {
-                sample_offset: 0,
-                data: Data::NoteOn {
-                    data: NoteData {
-                        id: NoteID::from_id(0),
-                        pitch: 64,
-                        velocity: 0.5,
-                        tuning: 0f32,
-                    },
-                },
-            }],
-            vec![ParameterValueQueueImpl {
-                param_id: NUMERIC_ID.to_string(),
-                points: vec![
-                    ParameterValueQueuePoint {
-                        sample_offset: 99,
-                        value: ((1.0 - MIN_NUMERIC) / (MAX_NUMERIC - MIN_NUMERIC)) as f64,
-                    },
-                    ParameterValueQueuePoint {
-                        sample_offset: 99,
-                        value: 1.0,
-                    },
-                ],
-            }],
-            &proc,
+        assert_eq!(
+            proc.process(
+                &mut mock_no_audio_process_data(
+                    vec![Event {
+                        sample_offset: 0,
+                        data: Data::NoteOn {
+                            data: NoteData {
+                                id: NoteID::from_channel_for_mpe_quirks(1),
+                                pitch: 64,
+                                velocity: 0.5,
+                                tuning: 0f32,
+                            },
+                        },
+                    }],
+                    vec![],
+                )
+                .process_data
+            ),
+            vst3::Steinberg::kResultOk
+        );
+        assert_eq!(
+            proc.process(
+                &mut mock_no_audio_process_data(
+                    vec![],
+                    vec![ParameterValueQueueImpl {
+                        param_id: aftertouch_param_id(1).to_string(),
+                        points: vec![ParameterValueQueuePoint {
+                            sample_offset: 0,
+                            value: 1.0, // Set it to the old max of 10!
+                        }],
+                    },],
+                )
+                .process_data
+            ),
+            vst3::Steinberg::kResultOk
         );
 
-        assert!(audio.is_none());
+        let audio = mock_process(2, vec![], vec![], &proc);
+
+        assert_approx_eq!(audio.as_ref().unwrap()[0][10], 2.0);
+    }
+}
+
+/// A tiny deterministic pseudo-random number generator, so that failures are reproducible.
+struct XorShift(u64);
+
+impl XorShift {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
     }
 }
 
-#[test]
-fn defends_against_points_outside_buffer() {
-    let proc = dummy_synth();
-    let host = ComWrapper::new(dummy_host::Host::default());
+#[derive(Debug, Clone, Copy)]
+enum HostCall {
+    Initialize,
+    Terminate,
+    SetupProcessing,
+    ActivateBusses(bool),
+    SetActive(bool),
+    SetProcessing(bool),
+    Process,
+}
 
-    unsafe {
-        setup_proc(&proc, &host);
-        let audio = mock_process(
-            2,
-            vec![Event {
-                sample_offset: 0,
-                data: Data::NoteOn {
-                    data: NoteData {
-                        id: NoteID::from_id(0),
-                        pitch: 64,
-                        velocity: 0.5,
-                        tuning: 0f32,
-                    },
-                },
-            }],
-            vec![ParameterValueQueueImpl {
-                param_id: NUMERIC_ID.to_string(),
-                points: vec![ParameterValueQueuePoint {
-                    sample_offset: 700,
-                    value: 1.0,
-                }],
-            }],
-            &proc,
-        );
+static HOST_CALLS: [HostCall; 10] = [
+    HostCall::Initialize,
+    HostCall::Terminate,
+    HostCall::SetupProcessing,
+    HostCall::ActivateBusses(true),
+    HostCall::ActivateBusses(false),
+    HostCall::SetActive(true),
+    HostCall::SetActive(false),
+    HostCall::SetProcessing(true),
+    HostCall::SetProcessing(false),
+    HostCall::Process,
+];
 
-        assert!(audio.is_none());
+/// A model of which host calls the processor should accept.
+#[derive(Default)]
+struct ExpectedLifecycle {
+    initialized: bool,
+    setup: bool,
+    busses_active: bool,
+    active: bool,
+    processing: bool,
+}
+
+impl ExpectedLifecycle {
+    /// Updates the model with a host call, and returns whether it should succeed.
+    fn apply(&mut self, call: HostCall) -> bool {
+        match call {
+            HostCall::Initialize => {
+                if self.initialized {
+                    return false;
+                }
+                *self = Self {
+                    initialized: true,
+                    busses_active: self.busses_active,
+                    ..Default::default()
+                };
+                true
+            }
+            HostCall::Terminate => {
+                if !self.initialized {
+                    return false;
+                }
+                *self = Self {
+                    busses_active: self.busses_active,
+                    ..Default::default()
+                };
+                true
+            }
+            HostCall::SetupProcessing => {
+                if !self.initialized || self.active {
+                    return false;
+                }
+                self.setup = true;
+                true
+            }
+            HostCall::ActivateBusses(state) => {
+                if self.active {
+                    return false;
+                }
+                self.busses_active = state;
+                true
+            }
+            HostCall::SetActive(state) => {
+                if !self.initialized
+                    || !self.setup
+                    || (state && !self.active && !self.busses_active)
+                {
+                    return false;
+                }
+                self.active = state;
+                true
+            }
+            HostCall::SetProcessing(state) => {
+                if !self.active {
+                    return false;
+                }
+                self.processing = state;
+                true
+            }
+            HostCall::Process => self.active && self.processing,
+        }
+    }
+}
+
+unsafe fn make_host_call<P: IComponentTrait + IAudioProcessorTrait>(
+    proc: &P,
+    host: &vst3::ComPtr<IHostApplication>,
+    call: HostCall,
+) -> bool {
+    match call {
+        HostCall::Initialize => {
+            proc.initialize(host.cast().unwrap().as_ptr()) == vst3::Steinberg::kResultOk
+        }
+        HostCall::Terminate => proc.terminate() == vst3::Steinberg::kResultOk,
+        HostCall::SetupProcessing => {
+            proc.setupProcessing(&mut process_setup(&DEFAULT_ENV)) == vst3::Steinberg::kResultOk
+        }
+        HostCall::ActivateBusses(state) => {
+            let audio = proc.activateBus(
+                vst3::Steinberg::Vst::MediaTypes_::kAudio as i32,
+                vst3::Steinberg::Vst::BusDirections_::kOutput as i32,
+                0,
+                u8::from(state),
+            );
+            let event = proc.activateBus(
+                vst3::Steinberg::Vst::MediaTypes_::kEvent as i32,
+                vst3::Steinberg::Vst::BusDirections_::kInput as i32,
+                0,
+                u8::from(state),
+            );
+            assert_eq!(audio, event);
+            audio == vst3::Steinberg::kResultOk
+        }
+        HostCall::SetActive(state) => proc.setActive(u8::from(state)) == vst3::Steinberg::kResultOk,
+        HostCall::SetProcessing(state) => {
+            proc.setProcessing(u8::from(state)) == vst3::Steinberg::kResultOk
+        }
+        HostCall::Process => mock_process(2, vec![], vec![], proc).is_some(),
     }
 }
 
 #[test]
-fn defends_against_multiple_queues_for_same_param() {
-    let proc = dummy_synth();
+fn random_host_call_sequences_are_handled() {
+    let host = ComWrapper::new(dummy_host::Host::default())
+        .to_com_ptr::<IHostApplication>()
+        .unwrap();
+    for seed in 1..=32 {
+        let proc = dummy_synth();
+        let mut rng = XorShift(seed);
+        let mut expected = ExpectedLifecycle::default();
+        for step in 0..256 {
+            let call = HOST_CALLS[usize::try_from(rng.next() % HOST_CALLS.len() as u64).unwrap()];
+            let succeeded = unsafe { make_host_call(&proc, &host, call) };
+            assert_eq!(
+                succeeded,
+                expected.apply(call),
+                "Unexpected result for {call:?} at step {step} with seed {seed}"
+            );
+        }
+    }
+}
+
+#[test]
+fn parameter_changes_are_interleaved_with_events() {
+    let events = RefCell::new(Vec::new());
+    let proc = create_synth(
+        |_: &HostInfo| FakeComponent::new(|_| EventRecordingSynth { events: &events }),
+        [4; 16],
+        PARAMETER_CHANGE_EVENTS_SYNTH_OPTIONS,
+    );
     let host = ComWrapper::new(dummy_host::Host::default());
 
     unsafe {
         setup_proc(&proc, &host);
-        let data = mock_process(
-            2,
-            vec![Event {
-                sample_offset: 0,
-                data: Data::NoteOn {
-                    data: NoteData {
-                        id: NoteID::from_id(0),
-                        pitch: 64,
-                        velocity: 0.5,
-                        tuning: 0f32,
-                    },
+
+        let note_on = Event {
+            sample_offset: 100,
+            data: Data::NoteOn {
+                data: NoteData {
+                    id: NoteID::from_id(0),
+                    pitch: 64,
+                    velocity: 0.5,
+                    tuning: 0f32,
+                    channel: 0,
                 },
-            }],
+            },
+        };
+        let audio = mock_process(
+            2,
+            vec![note_on.clone()],
             vec![
                 ParameterValueQueueImpl {
-                    param_id: NUMERIC_ID.to_string(),
+                    param_id: SWITCH_ID.to_string(),
                     points: vec![ParameterValueQueuePoint {
-                        sample_offset: 99,
-                        value: ((1.0 - MIN_NUMERIC) / (MAX_NUMERIC - MIN_NUMERIC)) as f64,
+                        sample_offset: 300,
+                        value: 0.0,
                     }],
                 },
                 ParameterValueQueueImpl {
@@ -2120,488 +4663,456 @@ fn defends_against_multiple_queues_for_same_param() {
             ],
             &proc,
         );
-
-        assert!(data.is_none());
+        assert!(audio.is_some());
     }
-}
-
-#[test]
-fn defends_against_non_zero_time_parameter_change() {
-    let proc = dummy_synth();
-    let host = ComWrapper::new(dummy_host::Host::default());
-
-    unsafe {
-        setup_proc(&proc, &host);
-        let mut data = mock_no_audio_process_data(
-            vec![],
-            vec![ParameterValueQueueImpl {
-                param_id: NUMERIC_ID.to_string(),
-                points: vec![ParameterValueQueuePoint {
-                    sample_offset: 1,
-                    value: 1.0,
-                }],
-            }],
-        );
 
-        assert_ne!(
-            proc.process(&mut data.process_data),
-            vst3::Steinberg::kResultOk
-        );
-    }
+    // The parameter change coincident with the note on should come first.
+    assert_eq!(
+        events.into_inner(),
+        vec![
+            Event {
+                sample_offset: 100,
+                data: Data::ParameterChange {
+                    data: ParameterChangeData {
+                        id: hash_id(NUMERIC_ID),
+                        value: InternalValue::Numeric(MAX_NUMERIC),
+                    },
+                },
+            },
+            note_on,
+            Event {
+                sample_offset: 300,
+                data: Data::ParameterChange {
+                    data: ParameterChangeData {
+                        id: hash_id(SWITCH_ID),
+                        value: InternalValue::Switch(false),
+                    },
+                },
+            },
+        ]
+    );
 }
 
 #[test]
-fn defends_against_out_of_range_parameter_values() {
-    let proc = dummy_synth();
+fn parameter_changes_after_program_change_are_delivered_as_events() {
+    let events = RefCell::new(Vec::new());
+    let proc = create_synth(
+        |_: &HostInfo| FakeComponent {
+            factory_presets: fake_presets(),
+            ..FakeComponent::new(|_| EventRecordingSynth { events: &events })
+        },
+        [4; 16],
+        PARAMETER_CHANGE_EVENTS_SYNTH_OPTIONS,
+    );
     let host = ComWrapper::new(dummy_host::Host::default());
 
     unsafe {
         setup_proc(&proc, &host);
-        let mut data = mock_no_audio_process_data(
-            vec![],
-            vec![ParameterValueQueueImpl {
-                param_id: NUMERIC_ID.to_string(),
-                points: vec![ParameterValueQueuePoint {
-                    sample_offset: 0,
-                    value: -5.0,
-                }],
-            }],
-        );
-
-        assert_ne!(
-            proc.process(&mut data.process_data),
-            vst3::Steinberg::kResultOk
-        );
-    }
-}
-
-#[test]
-fn can_change_parameters_in_handle_events() {
-    let proc = dummy_synth();
-    let host = ComWrapper::new(dummy_host::Host::default());
 
-    unsafe {
-        setup_proc(&proc, &host);
-        assert_eq!(
-            proc.process(
-                &mut mock_no_audio_process_data(
-                    vec![],
-                    vec![
-                        ParameterValueQueueImpl {
-                            param_id: NUMERIC_ID.to_string(),
-                            points: vec![ParameterValueQueuePoint {
-                                sample_offset: 0,
-                                value: 1.0,
-                            }],
+        // The program change queue isn't a parameter of the component, so it
+        // shouldn't be delivered as an event, nor hide the changes after it.
+        let audio = mock_process(
+            2,
+            vec![],
+            vec![
+                program_queue(0.0),
+                ParameterValueQueueImpl {
+                    param_id: SWITCH_ID.to_string(),
+                    points: vec![
+                        ParameterValueQueuePoint {
+                            sample_offset: 50,
+                            value: 0.0,
                         },
-                        ParameterValueQueueImpl {
-                            param_id: ENUM_ID.to_string(),
-                            points: vec![ParameterValueQueuePoint {
-                                sample_offset: 0,
-                                value: 0.5,
-                            }],
+                        ParameterValueQueuePoint {
+                            sample_offset: 200,
+                            value: 1.0,
                         },
-                        ParameterValueQueueImpl {
-                            param_id: SWITCH_ID.to_string(),
-                            points: vec![ParameterValueQueuePoint {
-                                sample_offset: 0,
-                                value: 0.0,
-                            }],
-                        }
                     ],
-                )
-                .process_data
-            ),
-            vst3::Steinberg::kResultOk
-        );
-
-        let audio = mock_process(
-            2,
-            vec![Event {
-                sample_offset: 0,
-                data: Data::NoteOn {
-                    data: NoteData {
-                        id: NoteID::from_id(0),
-                        pitch: 64,
-                        velocity: 0.5,
-                        tuning: 0f32,
-                    },
                 },
-            }],
-            vec![ParameterValueQueueImpl {
-                param_id: SWITCH_ID.to_string(),
-                points: vec![ParameterValueQueuePoint {
-                    sample_offset: 100,
-                    value: 1.0,
-                }],
-            }],
+                ParameterValueQueueImpl {
+                    param_id: NUMERIC_ID.to_string(),
+                    points: vec![ParameterValueQueuePoint {
+                        sample_offset: 100,
+                        value: 1.0,
+                    }],
+                },
+            ],
             &proc,
         );
-
         assert!(audio.is_some());
-        assert_eq!(audio.as_ref().unwrap()[0][0], 0.0 as f32);
-        assert_eq!(audio.as_ref().unwrap()[0][100], 2.0 * MAX_NUMERIC as f32);
     }
-}
 
-#[test]
-fn defends_against_get_state_before_initialized() {
-    let proc = dummy_synth();
-    let stream = ComWrapper::new(Stream::new([]))
-        .to_com_ptr::<vst3::Steinberg::IBStream>()
-        .unwrap();
-    let result = unsafe { proc.getState(stream.as_ptr()) };
-    assert_ne!(result, vst3::Steinberg::kResultOk);
+    assert_eq!(
+        events.into_inner(),
+        vec![
+            Event {
+                sample_offset: 50,
+                data: Data::ParameterChange {
+                    data: ParameterChangeData {
+                        id: hash_id(SWITCH_ID),
+                        value: InternalValue::Switch(false),
+                    },
+                },
+            },
+            Event {
+                sample_offset: 100,
+                data: Data::ParameterChange {
+                    data: ParameterChangeData {
+                        id: hash_id(NUMERIC_ID),
+                        value: InternalValue::Numeric(MAX_NUMERIC),
+                    },
+                },
+            },
+            Event {
+                sample_offset: 200,
+                data: Data::ParameterChange {
+                    data: ParameterChangeData {
+                        id: hash_id(SWITCH_ID),
+                        value: InternalValue::Switch(true),
+                    },
+                },
+            },
+        ]
+    );
 }
 
 #[test]
-fn defends_against_get_state_with_null_stream() {
-    let proc = dummy_synth();
+fn synths_that_use_midi_channels_receive_note_channels() {
+    let voice_notes = RefCell::new(Vec::new());
+    let proc = create_synth(
+        |_: &HostInfo| {
+            FakeComponent::new(|env| ChannelPoolSynth {
+                poly: Poly::new_partitioned_by_channel(env, &[1, 1]),
+                voice_notes: &voice_notes,
+            })
+        },
+        [4; 16],
+        SynthOptions {
+            uses_midi_channels: true,
+            ..SynthOptions::DEFAULT
+        },
+    );
     let host = ComWrapper::new(dummy_host::Host::default());
+
+    let note_on = |channel| Event {
+        sample_offset: 0,
+        data: Data::NoteOn {
+            data: NoteData {
+                id: NoteID::from_channel_and_pitch(channel, 64),
+                pitch: 64,
+                velocity: 0.5,
+                tuning: 0f32,
+                channel,
+            },
+        },
+    };
     unsafe {
         setup_proc(&proc, &host);
-        assert_ne!(
-            proc.getState(std::ptr::null_mut()),
-            vst3::Steinberg::kResultOk
-        );
+
+        let audio = mock_process(2, vec![note_on(0), note_on(1)], vec![], &proc);
+        assert!(audio.is_some());
     }
+
+    // Each channel has its own single-voice pool, so both notes play even
+    // though they share a pitch.
+    let voice_notes = voice_notes.into_inner();
+    assert_eq!(voice_notes.len(), 2);
+    assert_eq!(voice_notes[0].map(|note| note.channel), Some(0));
+    assert_eq!(voice_notes[1].map(|note| note.channel), Some(1));
 }
 
 #[test]
-fn get_state_saves_data() {
-    let proc = dummy_synth();
+fn transport_is_read_from_process_context() {
+    use vst3::Steinberg::Vst::ProcessContext_::StatesAndFlags_;
+
+    let transports = RefCell::new(Vec::new());
+    let proc = create_synth(
+        |_: &HostInfo| {
+            FakeComponent::new(|_| TransportRecordingSynth {
+                transports: &transports,
+            })
+        },
+        [4; 16],
+        SynthOptions::DEFAULT,
+    );
     let host = ComWrapper::new(dummy_host::Host::default());
+
     unsafe {
+        assert_ne!(proc.getProcessContextRequirements(), 0);
+
         setup_proc(&proc, &host);
-        let stream = ComWrapper::new(Stream::new([]));
-        assert_eq!(
-            proc.getState(
-                stream
-                    .as_com_ref::<vst3::Steinberg::IBStream>()
-                    .unwrap()
-                    .as_ptr()
-            ),
-            vst3::Steinberg::kResultOk
-        );
-        assert!(stream.data().len() > 0);
+
+        let mut context: vst3::Steinberg::Vst::ProcessContext = std::mem::zeroed();
+        context.state = (StatesAndFlags_::kPlaying
+            | StatesAndFlags_::kTempoValid
+            | StatesAndFlags_::kTimeSigValid
+            | StatesAndFlags_::kProjectTimeMusicValid
+            | StatesAndFlags_::kBarPositionValid) as u32;
+        context.tempo = 132.0;
+        context.timeSigNumerator = 6;
+        context.timeSigDenominator = 8;
+        context.projectTimeMusic = 13.5;
+        context.barPositionMusic = 12.0;
+        assert!(mock_process_mod(2, vec![], vec![], &proc, |data| {
+            data.processContext = &mut context;
+        })
+        .is_some());
+
+        // Only the transport state is valid here.
+        context.state = StatesAndFlags_::kRecording as u32;
+        assert!(mock_process_mod(2, vec![], vec![], &proc, |data| {
+            data.processContext = &mut context;
+        })
+        .is_some());
+
+        // Hosts may not provide a context at all.
+        assert!(mock_process(2, vec![], vec![], &proc).is_some());
     }
-}
 
-#[test]
-fn defends_against_set_state_before_initialized() {
-    let proc = dummy_synth();
-    let stream = ComWrapper::new(Stream::new([]))
-        .to_com_ptr::<vst3::Steinberg::IBStream>()
-        .unwrap();
-    let result = unsafe { proc.setState(stream.as_ptr()) };
-    assert_ne!(result, vst3::Steinberg::kResultOk);
+    assert_eq!(
+        transports.into_inner(),
+        vec![
+            Transport {
+                tempo: Some(132.0),
+                time_signature: Some(TimeSignature {
+                    numerator: 6,
+                    denominator: 8,
+                }),
+                position: Some(13.5),
+                bar_start: Some(12.0),
+                playing: Some(true),
+                recording: Some(false),
+            },
+            Transport {
+                playing: Some(false),
+                recording: Some(true),
+                ..Default::default()
+            },
+            Transport::default(),
+        ]
+    );
 }
 
 #[test]
-fn defends_against_set_state_with_null_stream() {
-    let proc = dummy_synth();
+fn processor_can_depend_on_host() {
+    let proc = create_synth(
+        |_: &HostInfo| {
+            FakeComponent::new(|env| HostAwareSynth {
+                level: if env.host_name.as_deref() == Some("Dummy Host") {
+                    0.5
+                } else {
+                    0.25
+                },
+            })
+        },
+        [4; 16],
+        SynthOptions::DEFAULT,
+    );
     let host = ComWrapper::new(dummy_host::Host::default());
+
     unsafe {
         setup_proc(&proc, &host);
-        assert_ne!(
-            proc.setState(std::ptr::null_mut()),
-            vst3::Steinberg::kResultOk
-        );
+
+        let audio = mock_process(2, vec![], vec![], &proc).unwrap();
+        assert!(audio.iter().flatten().all(|x| *x == 0.5));
     }
 }
 
 #[test]
-fn set_state_sets_parameters() {
-    let proc1 = dummy_synth();
-    let proc2 = dummy_synth();
-    let proc3 = dummy_synth();
-    let host = ComWrapper::new(dummy_host::Host::default());
+fn effect_event_input_bus() {
+    let events = RefCell::new(Vec::new());
+    let plain = dummy_effect();
+    let with_events = create_effect(
+        |_: &HostInfo| FakeComponent::new(|_| EventRecordingEffect { events: &events }),
+        [4; 16],
+        SWITCH_ID,
+        EVENT_INPUT_EFFECT_OPTIONS,
+    );
+    let host = ComWrapper::new(dummy_host::Host::default())
+        .to_com_ptr::<IHostApplication>()
+        .unwrap();
+
     unsafe {
-        setup_proc(&proc1, &host);
-        setup_proc(&proc2, &host);
-        setup_proc(&proc3, &host);
         assert_eq!(
-            proc1.process(
-                &mut mock_no_audio_process_data(
-                    vec![],
-                    vec![
-                        ParameterValueQueueImpl {
-                            param_id: NUMERIC_ID.to_string(),
-                            points: vec![ParameterValueQueuePoint {
-                                sample_offset: 0,
-                                value: 1.0,
-                            }],
-                        },
-                        // Test that pitch bend parameter is _not_ saved
-                        ParameterValueQueueImpl {
-                            param_id: PITCH_BEND_PARAMETER.to_string(),
-                            points: vec![ParameterValueQueuePoint {
-                                sample_offset: 0,
-                                value: 1.0,
-                            }],
-                        }
-                    ],
-                )
-                .process_data
-            ),
+            plain.initialize(host.cast().unwrap().as_ptr()),
             vst3::Steinberg::kResultOk
         );
-
-        let stream = ComWrapper::new(Stream::new([]));
         assert_eq!(
-            proc1.getState(
-                stream
-                    .as_com_ref::<vst3::Steinberg::IBStream>()
-                    .unwrap()
-                    .as_ptr()
-            ),
+            with_events.initialize(host.cast().unwrap().as_ptr()),
             vst3::Steinberg::kResultOk
         );
         assert_eq!(
-            stream.seek(
-                0,
-                vst3::Steinberg::IBStream_::IStreamSeekMode_::kIBSeekSet as i32,
-                std::ptr::null_mut(),
+            plain.getBusCount(
+                vst3::Steinberg::Vst::MediaTypes_::kEvent as i32,
+                vst3::Steinberg::Vst::BusDirections_::kInput as i32
             ),
-            vst3::Steinberg::kResultOk
+            0
         );
-
-        // Here we test an instant-readback to make sure we don't have a bug where we
-        // only read the correct state after the next process call.
         assert_eq!(
-            proc2.setState(
-                stream
-                    .as_com_ref::<vst3::Steinberg::IBStream>()
-                    .unwrap()
-                    .as_ptr()
+            with_events.getBusCount(
+                vst3::Steinberg::Vst::MediaTypes_::kEvent as i32,
+                vst3::Steinberg::Vst::BusDirections_::kInput as i32
             ),
-            vst3::Steinberg::kResultOk
+            1
         );
-        let stream = ComWrapper::new(Stream::new([]));
+
+        let mut bus_info = vst3::Steinberg::Vst::BusInfo {
+            mediaType: 0,
+            direction: 0,
+            channelCount: 0,
+            name: [0; 128],
+            busType: 0,
+            flags: 0,
+        };
         assert_eq!(
-            proc2.getState(
-                stream
-                    .as_com_ref::<vst3::Steinberg::IBStream>()
-                    .unwrap()
-                    .as_ptr()
+            with_events.getBusInfo(
+                vst3::Steinberg::Vst::MediaTypes_::kEvent as i32,
+                vst3::Steinberg::Vst::BusDirections_::kInput as i32,
+                0,
+                &mut bus_info
             ),
             vst3::Steinberg::kResultOk
         );
         assert_eq!(
-            stream.seek(
+            bus_info.flags,
+            vst3::Steinberg::Vst::BusInfo_::BusFlags_::kDefaultActive
+        );
+        assert_ne!(
+            plain.activateBus(
+                vst3::Steinberg::Vst::MediaTypes_::kEvent as i32,
+                vst3::Steinberg::Vst::BusDirections_::kInput as i32,
                 0,
-                vst3::Steinberg::IBStream_::IStreamSeekMode_::kIBSeekSet as i32,
-                std::ptr::null_mut(),
+                1
             ),
             vst3::Steinberg::kResultOk
         );
         assert_eq!(
-            proc3.setState(
-                stream
-                    .as_com_ref::<vst3::Steinberg::IBStream>()
-                    .unwrap()
-                    .as_ptr()
+            with_events.activateBus(
+                vst3::Steinberg::Vst::MediaTypes_::kEvent as i32,
+                vst3::Steinberg::Vst::BusDirections_::kInput as i32,
+                0,
+                1
             ),
             vst3::Steinberg::kResultOk
         );
-
-        let audio = mock_process(
-            2,
-            vec![Event {
-                sample_offset: 10,
-                data: Data::NoteOn {
-                    data: NoteData {
-                        id: NoteID::from_id(0),
-                        pitch: 64,
-                        velocity: 0.5,
-                        tuning: 0f32,
-                    },
-                },
-            }],
-            vec![],
-            &proc3,
-        );
-
-        assert!(audio.is_some());
-
-        assert_eq!(audio.as_ref().unwrap()[0][0], 0.0);
-        assert_eq!(audio.as_ref().unwrap()[0][10], MAX_NUMERIC);
     }
 }
 
 #[test]
-fn get_state_sees_automation() {
-    let proc1 = dummy_synth();
-    let proc2 = dummy_synth();
-    let proc3 = dummy_synth();
+fn effect_with_event_input_receives_events() {
+    let events = RefCell::new(Vec::new());
+    let proc = create_effect(
+        |_: &HostInfo| FakeComponent::new(|_| EventRecordingEffect { events: &events }),
+        [4; 16],
+        SWITCH_ID,
+        EVENT_INPUT_EFFECT_OPTIONS,
+    );
     let host = ComWrapper::new(dummy_host::Host::default());
-    unsafe {
-        setup_proc(&proc1, &host);
-        setup_proc(&proc2, &host);
-        setup_proc(&proc3, &host);
-        assert_eq!(
-            proc1.process(
-                &mut mock_no_audio_process_data(
-                    vec![],
-                    vec![ParameterValueQueueImpl {
-                        param_id: NUMERIC_ID.to_string(),
-                        points: vec![ParameterValueQueuePoint {
-                            sample_offset: 0,
-                            value: 1.0,
-                        }],
-                    },],
-                )
-                .process_data
-            ),
-            vst3::Steinberg::kResultOk
-        );
 
-        let stream = ComWrapper::new(Stream::new([]));
-        assert_eq!(
-            proc1.getState(
-                stream
-                    .as_com_ref::<vst3::Steinberg::IBStream>()
-                    .unwrap()
-                    .as_ptr()
-            ),
-            vst3::Steinberg::kResultOk
-        );
-        assert_eq!(
-            stream.seek(
-                0,
-                vst3::Steinberg::IBStream_::IStreamSeekMode_::kIBSeekSet as i32,
-                std::ptr::null_mut(),
-            ),
-            vst3::Steinberg::kResultOk
-        );
+    let note_events = vec![
+        Event {
+            sample_offset: 10,
+            data: Data::NoteOn {
+                data: NoteData {
+                    id: NoteID::from_id(0),
+                    pitch: 64,
+                    velocity: 0.5,
+                    tuning: 0f32,
+                    channel: 0,
+                },
+            },
+        },
+        Event {
+            sample_offset: 20,
+            data: Data::NoteExpression {
+                data: NoteExpressionData {
+                    id: NoteID::from_id(0),
+                    expression: NoteExpression::Timbre(0.25),
+                },
+            },
+        },
+    ];
+    unsafe {
+        // Note we don't explicitly activate the event bus, since it's active by default.
+        setup_proc_effect(&proc, &host);
 
-        assert_eq!(
-            proc2.setState(
-                stream
-                    .as_com_ref::<vst3::Steinberg::IBStream>()
-                    .unwrap()
-                    .as_ptr()
-            ),
-            vst3::Steinberg::kResultOk
+        let audio = mock_process_effect_with_events(
+            vec![vec![1f32; 512]; 2],
+            note_events.clone(),
+            vec![],
+            &proc,
         );
+        assert!(audio.is_some());
+    }
 
-        assert_eq!(
-            proc2.process(
-                &mut mock_no_audio_process_data(
-                    vec![],
-                    vec![ParameterValueQueueImpl {
-                        param_id: ENUM_ID.to_string(),
-                        points: vec![ParameterValueQueuePoint {
-                            sample_offset: 0,
-                            value: 0.5,
-                        }],
-                    },],
-                )
-                .process_data
-            ),
-            vst3::Steinberg::kResultOk
-        );
+    assert_eq!(events.into_inner(), note_events);
+}
 
-        let stream = ComWrapper::new(Stream::new([]));
+#[test]
+fn effect_with_inactive_event_input_receives_no_events() {
+    let events = RefCell::new(Vec::new());
+    let proc = create_effect(
+        |_: &HostInfo| FakeComponent::new(|_| EventRecordingEffect { events: &events }),
+        [4; 16],
+        SWITCH_ID,
+        EVENT_INPUT_EFFECT_OPTIONS,
+    );
+    let host = ComWrapper::new(dummy_host::Host::default());
+    let host_ref = host.as_com_ref::<IHostApplication>().unwrap();
+
+    unsafe {
         assert_eq!(
-            proc2.getState(
-                stream
-                    .as_com_ref::<vst3::Steinberg::IBStream>()
-                    .unwrap()
-                    .as_ptr()
-            ),
+            proc.initialize(host_ref.cast().unwrap().as_ptr()),
             vst3::Steinberg::kResultOk
         );
         assert_eq!(
-            stream.seek(
-                0,
-                vst3::Steinberg::IBStream_::IStreamSeekMode_::kIBSeekSet as i32,
-                std::ptr::null_mut(),
-            ),
+            proc.setupProcessing(&mut process_setup(&DEFAULT_ENV)),
             vst3::Steinberg::kResultOk
         );
+        activate_effect_busses(&proc);
         assert_eq!(
-            proc3.setState(
-                stream
-                    .as_com_ref::<vst3::Steinberg::IBStream>()
-                    .unwrap()
-                    .as_ptr()
+            proc.activateBus(
+                vst3::Steinberg::Vst::MediaTypes_::kEvent as i32,
+                vst3::Steinberg::Vst::BusDirections_::kInput as i32,
+                0,
+                0
             ),
             vst3::Steinberg::kResultOk
         );
+        assert_eq!(proc.setActive(1u8), vst3::Steinberg::kResultOk);
+        assert_eq!(proc.setProcessing(1u8), vst3::Steinberg::kResultOk);
 
-        let audio = mock_process(
-            2,
+        let audio = mock_process_effect_with_events(
+            vec![vec![1f32; 512]; 2],
             vec![Event {
                 sample_offset: 10,
-                data: Data::NoteOn {
+                data: Data::NoteOff {
                     data: NoteData {
                         id: NoteID::from_id(0),
                         pitch: 64,
                         velocity: 0.5,
                         tuning: 0f32,
+                        channel: 0,
                     },
                 },
             }],
             vec![],
-            &proc3,
+            &proc,
         );
         assert!(audio.is_some());
-
-        assert_eq!(audio.as_ref().unwrap()[0][0], 0.0);
-        assert_eq!(audio.as_ref().unwrap()[0][10], 2.0 * MAX_NUMERIC);
     }
-}
-
-#[test]
-fn defends_against_invalid_state_data() {
-    let proc = dummy_synth();
-    let host = ComWrapper::new(dummy_host::Host::default());
-    unsafe {
-        setup_proc(&proc, &host);
 
-        // Note - this test makes a (currently valid) assumption that an empty state buffer
-        // is not a valid state. If the implementation of processor's serialization changes
-        // and this assumption becomes invalid, please replace the empty buffer with an
-        // invalid state buffer.
-        let invalid_stream = ComWrapper::new(Stream::new([]));
-        assert_ne!(
-            proc.setState(
-                invalid_stream
-                    .as_com_ref::<vst3::Steinberg::IBStream>()
-                    .unwrap()
-                    .as_ptr()
-            ),
-            vst3::Steinberg::kResultOk
-        );
-    }
+    assert!(events.into_inner().is_empty());
 }
 
-static INCOMPATIBLE_PARAMETERS: [StaticInfoRef; 1] = [InfoRef {
-    title: "Multiplier",
-    short_title: "Mult",
-    // This is incompatible since the previous version had a
-    // parameter of a different type with this ID
-    unique_id: ENUM_ID,
-    flags: Flags { automatable: true },
-    type_specific: TypeSpecificInfoRef::Numeric {
-        default: DEFAULT_NUMERIC,
-        valid_range: MIN_NUMERIC..=MAX_NUMERIC,
-        units: Some("Hz"),
-    },
-}];
-
-#[derive(Default)]
-struct IncompatibleComponent {}
-
-#[derive(Default)]
-struct IncompatibleSynth {}
+/// A synth that fills each of its outputs with a different constant.
+struct MultiOutputSynth {}
 
-impl Processor for IncompatibleSynth {
+impl Processor for MultiOutputSynth {
     fn set_processing(&mut self, _processing: bool) {}
 }
 
-impl Synth for IncompatibleSynth {
+impl Synth for MultiOutputSynth {
     fn handle_events<E: IntoIterator<Item = Data>, P: States>(
         &mut self,
         _events: E,
@@ -2609,406 +5120,423 @@ impl Synth for IncompatibleSynth {
     ) {
     }
 
-    fn process<E: IntoIterator<Item = Event>, P: BufferStates, O: BufferMut>(
+    fn process<E: Iterator<Item = Event>, P: BufferStates, O: BufferMut>(
         &mut self,
         _events: Events<E>,
         _parameters: P,
         _output: &mut O,
     ) {
-    }
-}
-
-impl Component for IncompatibleComponent {
-    type Processor = IncompatibleSynth;
-
-    fn create_processor(&self, _env: &ProcessingEnvironment) -> Self::Processor {
-        Default::default()
+        panic!("Synths with auxiliary outputs should be processed with `process_buses`");
     }
 
-    fn parameter_infos(&self) -> Vec<conformal_component::parameters::Info> {
-        conformal_component::parameters::to_infos(&INCOMPATIBLE_PARAMETERS)
+    fn process_buses<E: Iterator<Item = Event> + Clone, P: BufferStates, O: BufferMut>(
+        &mut self,
+        _events: Events<E>,
+        _parameters: P,
+        outputs: &mut [O],
+    ) {
+        let mut level = 1.0;
+        for output in outputs {
+            for channel in channels_mut(output) {
+                channel.fill(level);
+            }
+            level += 1.0;
+        }
     }
 }
 
+const MULTI_OUTPUT_SYNTH_OPTIONS: SynthOptions = SynthOptions {
+    aux_outputs: &["Kick", "Snare"],
+    ..SynthOptions::DEFAULT
+};
+
 #[test]
-fn defends_against_load_state_with_incompatible_parameters() {
-    let proc1 = dummy_synth();
-    let proc2 = create_synth(
-        |_: &HostInfo| -> IncompatibleComponent { Default::default() },
-        [5; 16],
+fn synth_aux_output_bus_info() {
+    let proc = create_synth(
+        |_: &HostInfo| FakeComponent::new(|_| MultiOutputSynth {}),
+        [4; 16],
+        MULTI_OUTPUT_SYNTH_OPTIONS,
     );
-
     let host = ComWrapper::new(dummy_host::Host::default());
+
     unsafe {
-        setup_proc(&proc1, &host);
-        setup_proc(&proc2, &host);
-        let stream = ComWrapper::new(Stream::new([]));
+        let host_ref = host.as_com_ref::<IHostApplication>().unwrap();
         assert_eq!(
-            proc1.getState(
-                stream
-                    .as_com_ref::<vst3::Steinberg::IBStream>()
-                    .unwrap()
-                    .as_ptr()
-            ),
+            proc.initialize(host_ref.cast().unwrap().as_ptr()),
             vst3::Steinberg::kResultOk
         );
         assert_eq!(
-            stream.seek(
+            proc.getBusCount(
+                vst3::Steinberg::Vst::MediaTypes_::kAudio as i32,
+                vst3::Steinberg::Vst::BusDirections_::kOutput as i32
+            ),
+            3
+        );
+
+        let mut bus = vst3::Steinberg::Vst::BusInfo {
+            mediaType: 0,
+            direction: 0,
+            channelCount: 0,
+            name: [0; 128],
+            busType: 0,
+            flags: 0,
+        };
+        assert_eq!(
+            proc.getBusInfo(
+                vst3::Steinberg::Vst::MediaTypes_::kAudio as i32,
+                vst3::Steinberg::Vst::BusDirections_::kOutput as i32,
                 0,
-                vst3::Steinberg::IBStream_::IStreamSeekMode_::kIBSeekSet as i32,
-                std::ptr::null_mut(),
+                &mut bus
             ),
             vst3::Steinberg::kResultOk
         );
-        assert_ne!(
-            proc2.setState(
-                stream
-                    .as_com_ref::<vst3::Steinberg::IBStream>()
-                    .unwrap()
-                    .as_ptr()
+        assert_eq!(bus.busType, vst3::Steinberg::Vst::BusTypes_::kMain as i32);
+
+        assert_eq!(
+            proc.getBusInfo(
+                vst3::Steinberg::Vst::MediaTypes_::kAudio as i32,
+                vst3::Steinberg::Vst::BusDirections_::kOutput as i32,
+                2,
+                &mut bus
             ),
             vst3::Steinberg::kResultOk
         );
-    }
-}
-
-#[derive(Default)]
-struct NewerComponent {}
-
-#[derive(Default)]
-struct NewerSynth {}
-
-impl Processor for NewerSynth {
-    fn set_processing(&mut self, _processing: bool) {}
-}
-
-impl Synth for NewerSynth {
-    fn handle_events<E: IntoIterator<Item = Data>, P: States>(
-        &mut self,
-        _events: E,
-        _parameters: P,
-    ) {
-    }
-
-    fn process<E: IntoIterator<Item = Event>, P: BufferStates, O: BufferMut>(
-        &mut self,
-        _events: Events<E>,
-        _parameters: P,
-        _output: &mut O,
-    ) {
-    }
-}
-
-static NEWER_PARAMETERS: [StaticInfoRef; 3] = [
-    InfoRef {
-        title: "Multiplier",
-        short_title: "Mult",
-        unique_id: "mult",
-        flags: Flags { automatable: true },
-        type_specific: TypeSpecificInfoRef::Numeric {
-            default: DEFAULT_NUMERIC,
-            valid_range: MIN_NUMERIC..=20.0,
-            units: Some("Hz"),
-        },
-    },
-    InfoRef {
-        title: "Enum Multiplier",
-        short_title: "Enum",
-        unique_id: "enum_mult",
-        flags: Flags { automatable: true },
-        type_specific: TypeSpecificInfoRef::Enum {
-            default: DEFAULT_ENUM,
-            values: &["1", "2", "3"],
-        },
-    },
-    InfoRef {
-        title: "Switch Multipler",
-        short_title: "Switch",
-        unique_id: "switch",
-        flags: Flags { automatable: true },
-        type_specific: TypeSpecificInfoRef::Switch {
-            default: DEFAULT_SWITCH,
-        },
-    },
-];
-
-impl Component for NewerComponent {
-    type Processor = NewerSynth;
-
-    fn create_processor(&self, _env: &ProcessingEnvironment) -> Self::Processor {
-        Default::default()
-    }
+        assert_eq!(bus.channelCount, 2);
+        assert_eq!(bus.busType, vst3::Steinberg::Vst::BusTypes_::kAux as i32);
+        assert_eq!(bus.flags, 0);
+        assert_eq!(
+            from_utf16_buffer(&bus.name)
+                .as_ref()
+                .map(|x: &String| x.as_str()),
+            Some("Snare")
+        );
 
-    fn parameter_infos(&self) -> Vec<conformal_component::parameters::Info> {
-        conformal_component::parameters::to_infos(&NEWER_PARAMETERS)
+        assert_eq!(
+            proc.getBusInfo(
+                vst3::Steinberg::Vst::MediaTypes_::kAudio as i32,
+                vst3::Steinberg::Vst::BusDirections_::kOutput as i32,
+                3,
+                &mut bus
+            ),
+            vst3::Steinberg::kInvalidArgument
+        );
     }
 }
 
 #[test]
-fn loading_too_new_parameters_loads_default_state() {
-    let proc1 = dummy_synth();
-    let proc2 = create_synth(
-        |_: &HostInfo| -> NewerComponent { Default::default() },
-        [5; 16],
+fn synth_aux_output_arrangements() {
+    let proc = create_synth(
+        |_: &HostInfo| FakeComponent::new(|_| MultiOutputSynth {}),
+        [4; 16],
+        MULTI_OUTPUT_SYNTH_OPTIONS,
     );
     let host = ComWrapper::new(dummy_host::Host::default());
+
     unsafe {
-        setup_proc(&proc1, &host);
-        setup_proc(&proc2, &host);
+        let host_ref = host.as_com_ref::<IHostApplication>().unwrap();
         assert_eq!(
-            proc1.process(
-                &mut mock_no_audio_process_data(
-                    vec![],
-                    vec![ParameterValueQueueImpl {
-                        param_id: NUMERIC_ID.to_string(),
-                        points: vec![ParameterValueQueuePoint {
-                            sample_offset: 0,
-                            value: 1.0, // Set it to the old max of 10!
-                        }],
-                    },],
-                )
-                .process_data
-            ),
+            proc.initialize(host_ref.cast().unwrap().as_ptr()),
             vst3::Steinberg::kResultOk
         );
+
+        // The host must arrange all outputs at once
+        let mut out_arrangement = vst3::Steinberg::Vst::SpeakerArr::kStereo;
+        assert_eq!(
+            proc.setBusArrangements(std::ptr::null_mut(), 0, &mut out_arrangement, 1),
+            vst3::Steinberg::kInvalidArgument
+        );
+
+        let mut out_arrangements = [
+            vst3::Steinberg::Vst::SpeakerArr::kStereo,
+            vst3::Steinberg::Vst::SpeakerArr::kMono,
+            vst3::Steinberg::Vst::SpeakerArr::kStereo,
+        ];
+        assert_eq!(
+            proc.setBusArrangements(std::ptr::null_mut(), 0, out_arrangements.as_mut_ptr(), 3),
+            vst3::Steinberg::kResultTrue
+        );
+
+        let mut arrangement = 0;
         assert_eq!(
-            proc2.process(
-                &mut mock_no_audio_process_data(
-                    vec![],
-                    vec![ParameterValueQueueImpl {
-                        param_id: NUMERIC_ID.to_string(),
-                        points: vec![ParameterValueQueuePoint {
-                            sample_offset: 0,
-                            value: 1.0, // Set it to the new max of 20!
-                        }],
-                    },],
-                )
-                .process_data
+            proc.getBusArrangement(
+                vst3::Steinberg::Vst::BusDirections_::kOutput as i32,
+                1,
+                &mut arrangement
             ),
             vst3::Steinberg::kResultOk
         );
+        assert_eq!(arrangement, vst3::Steinberg::Vst::SpeakerArr::kMono);
+    }
+}
 
-        let stream = ComWrapper::new(Stream::new([]));
+#[test]
+fn synth_renders_only_active_aux_outputs() {
+    let proc = create_synth(
+        |_: &HostInfo| FakeComponent::new(|_| MultiOutputSynth {}),
+        [4; 16],
+        MULTI_OUTPUT_SYNTH_OPTIONS,
+    );
+    let host = ComWrapper::new(dummy_host::Host::default());
+
+    unsafe {
+        let host_ref = host.as_com_ref::<IHostApplication>().unwrap();
         assert_eq!(
-            proc2.getState(
-                stream
-                    .as_com_ref::<vst3::Steinberg::IBStream>()
-                    .unwrap()
-                    .as_ptr()
-            ),
+            proc.initialize(host_ref.cast().unwrap().as_ptr()),
             vst3::Steinberg::kResultOk
         );
         assert_eq!(
-            stream.seek(
-                0,
-                vst3::Steinberg::IBStream_::IStreamSeekMode_::kIBSeekSet as i32,
-                std::ptr::null_mut(),
-            ),
+            proc.setupProcessing(&mut process_setup(&DEFAULT_ENV)),
             vst3::Steinberg::kResultOk
         );
+        let mut out_arrangements = [
+            vst3::Steinberg::Vst::SpeakerArr::kStereo,
+            vst3::Steinberg::Vst::SpeakerArr::kMono,
+            vst3::Steinberg::Vst::SpeakerArr::kStereo,
+        ];
         assert_eq!(
-            proc1.setState(
-                stream
-                    .as_com_ref::<vst3::Steinberg::IBStream>()
-                    .unwrap()
-                    .as_ptr()
+            proc.setBusArrangements(std::ptr::null_mut(), 0, out_arrangements.as_mut_ptr(), 3),
+            vst3::Steinberg::kResultTrue
+        );
+        activate_busses(&proc);
+
+        // Only the "Kick" output is routed, "Snare" stays inactive.
+        assert_eq!(
+            proc.activateBus(
+                vst3::Steinberg::Vst::MediaTypes_::kAudio as i32,
+                vst3::Steinberg::Vst::BusDirections_::kOutput as i32,
+                1,
+                1
             ),
             vst3::Steinberg::kResultOk
         );
+        assert_eq!(proc.setActive(1u8), vst3::Steinberg::kResultOk);
+        assert_eq!(proc.setProcessing(1u8), vst3::Steinberg::kResultOk);
 
-        // Now, since proc2 was a newer version than proc1, we expect proc1 to be re-set
-        // to its default state.
-        let audio = mock_process(
-            2,
-            vec![Event {
-                sample_offset: 10,
-                data: Data::NoteOn {
-                    data: NoteData {
-                        id: NoteID::from_id(0),
-                        pitch: 64,
-                        velocity: 0.5,
-                        tuning: 0f32,
-                    },
+        let mut main = vec![vec![0f32; SAMPLE_COUNT]; 2];
+        let mut kick = vec![vec![0f32; SAMPLE_COUNT]; 1];
+        let mut snare = vec![vec![0f32; SAMPLE_COUNT]; 2];
+        let mut channel_ptrs = [&mut main, &mut kick, &mut snare].map(|bus| {
+            bus.iter_mut()
+                .map(|channel| channel.as_mut_ptr())
+                .collect::<Vec<_>>()
+        });
+        let mut buses = channel_ptrs
+            .iter_mut()
+            .map(|ptrs| vst3::Steinberg::Vst::AudioBusBuffers {
+                numChannels: ptrs.len() as i32,
+                silenceFlags: 0,
+                __field0: vst3::Steinberg::Vst::AudioBusBuffers__type0 {
+                    channelBuffers32: ptrs.as_mut_ptr(),
                 },
-            }],
-            vec![],
-            &proc1,
-        );
-        assert!(audio.is_some());
+            })
+            .collect::<Vec<_>>();
+        assert!(mock_process_mod(2, vec![], vec![], &proc, |data| {
+            data.numOutputs = 3;
+            data.outputs = buses.as_mut_ptr();
+        })
+        .is_some());
 
-        assert_eq!(audio.as_ref().unwrap()[0][10], 1.0);
+        assert!(main.iter().flatten().all(|x| *x == 1.0));
+        assert!(kick.iter().flatten().all(|x| *x == 2.0));
+        assert!(snare.iter().flatten().all(|x| *x == 0.0));
+
+        // The host must pass all outputs, even inactive ones.
+        assert!(mock_process(2, vec![], vec![], &proc).is_none());
     }
 }
 
-static DUPLICATE_PARAMETERS: [StaticInfoRef; 2] = [
-    InfoRef {
-        title: "Multiplier",
-        short_title: "Mult",
-        unique_id: "mult",
-        flags: Flags { automatable: true },
-        type_specific: TypeSpecificInfoRef::Numeric {
-            default: DEFAULT_NUMERIC,
-            valid_range: MIN_NUMERIC..=20.0,
-            units: Some("Hz"),
-        },
-    },
-    InfoRef {
-        title: "Multiplier",
-        short_title: "Mult",
-        unique_id: "mult",
-        flags: Flags { automatable: true },
-        type_specific: TypeSpecificInfoRef::Numeric {
-            default: DEFAULT_NUMERIC,
-            valid_range: MIN_NUMERIC..=20.0,
-            units: Some("Hz"),
+#[test]
+#[should_panic]
+fn synth_rejects_aux_outputs_beyond_max() {
+    const DRUMS: [&str; MAX_AUX_OUTPUTS + 1] = ["Drum"; MAX_AUX_OUTPUTS + 1];
+    create_synth(
+        |_: &HostInfo| FakeComponent::new(|_| MultiOutputSynth {}),
+        [4; 16],
+        SynthOptions {
+            aux_outputs: &DRUMS,
+            ..SynthOptions::DEFAULT
         },
-    },
-];
-
-#[derive(Default)]
-struct DuplicateParameterComponent {}
-
-impl Component for DuplicateParameterComponent {
-    type Processor = IncompatibleSynth;
+    );
+}
 
-    fn create_processor(&self, _env: &ProcessingEnvironment) -> Self::Processor {
-        Default::default()
-    }
+fn fake_presets() -> Vec<Preset> {
+    vec![
+        Preset {
+            name: "Init".to_string(),
+            values: HashMap::new(),
+        },
+        Preset {
+            name: "Loud".to_string(),
+            values: HashMap::from([
+                (NUMERIC_ID.to_string(), Value::Numeric(5.0)),
+                (ENUM_ID.to_string(), Value::Enum("3".to_string())),
+            ]),
+        },
+    ]
+}
 
-    fn parameter_infos(&self) -> Vec<conformal_component::parameters::Info> {
-        conformal_component::parameters::to_infos(&DUPLICATE_PARAMETERS)
+fn program_queue(value: f64) -> ParameterValueQueueImpl {
+    ParameterValueQueueImpl {
+        param_id: programs::PARAMETER_ID.to_string(),
+        points: vec![ParameterValueQueuePoint {
+            sample_offset: 0,
+            value,
+        }],
     }
 }
 
-#[test]
-#[should_panic]
-fn panic_on_duplicate_ids() {
-    let processor = create_synth(
-        |_: &HostInfo| -> DuplicateParameterComponent { Default::default() },
-        [5; 16],
+unsafe fn get_state_values(
+    proc: &(impl IComponentTrait + IAudioProcessorTrait),
+) -> Result<HashMap<String, Value>, StateError> {
+    let stream = ComWrapper::new(Stream::new([]));
+    assert_eq!(
+        proc.getState(
+            stream
+                .as_com_ref::<vst3::Steinberg::IBStream>()
+                .unwrap()
+                .as_ptr()
+        ),
+        vst3::Steinberg::kResultOk
     );
-    let host = ComWrapper::new(dummy_host::Host::default());
-    unsafe {
-        processor.initialize(host.as_com_ref().unwrap().as_ptr());
-    }
+    deserialize_state(&fake_synth_component(), &stream.data())
 }
 
 #[test]
-fn supports_toggling_active_while_processing() {
-    // Note that this call pattern is _explicitly disallowed_ by the spec,
-    // but we support it anyway since some DAWs do it (tested Ableton 11.3.20).
-    let proc = dummy_synth();
+fn program_change_applies_factory_preset() {
+    let proc = create_synth(
+        |_: &HostInfo| FakeComponent {
+            factory_presets: fake_presets(),
+            ..fake_synth_component()
+        },
+        [4; 16],
+        SynthOptions::DEFAULT,
+    );
     let host = ComWrapper::new(dummy_host::Host::default());
-
     unsafe {
         setup_proc(&proc, &host);
+        assert_eq!(
+            proc.process(
+                &mut mock_no_audio_process_data(
+                    vec![],
+                    vec![
+                        program_queue(1.0),
+                        ParameterValueQueueImpl {
+                            param_id: SWITCH_ID.to_string(),
+                            points: vec![ParameterValueQueuePoint {
+                                sample_offset: 0,
+                                value: 0.0,
+                            }],
+                        },
+                    ],
+                )
+                .process_data
+            ),
+            vst3::Steinberg::kResultOk
+        );
 
-        proc.setActive(0);
-        proc.setActive(1);
+        // Other changes in the same buffer apply on top of the preset.
+        assert_eq!(
+            get_state_values(&proc),
+            Ok(HashMap::from([
+                (NUMERIC_ID.to_string(), Value::Numeric(5.0)),
+                (ENUM_ID.to_string(), Value::Enum("3".to_string())),
+                (SWITCH_ID.to_string(), Value::Switch(false)),
+            ]))
+        );
 
-        let audio = mock_process(
-            2,
-            vec![Event {
-                sample_offset: 100,
-                data: Data::NoteOn {
-                    data: NoteData {
-                        id: NoteID::from_id(0),
-                        pitch: 64,
-                        velocity: 0.5,
-                        tuning: 0f32,
-                    },
-                },
-            }],
-            vec![],
-            &proc,
+        // Parameters missing from a preset are reset to their defaults.
+        assert_eq!(
+            proc.process(
+                &mut mock_no_audio_process_data(vec![], vec![program_queue(0.0)]).process_data
+            ),
+            vst3::Steinberg::kResultOk
+        );
+        assert_eq!(
+            get_state_values(&proc),
+            Ok(HashMap::from([
+                (NUMERIC_ID.to_string(), Value::Numeric(DEFAULT_NUMERIC)),
+                (ENUM_ID.to_string(), Value::Enum("1".to_string())),
+                (SWITCH_ID.to_string(), Value::Switch(DEFAULT_SWITCH)),
+            ]))
         );
-        assert!(audio.is_some());
-        assert_eq!(audio.as_ref().unwrap()[0][0], 0.0);
-        assert_eq!(audio.as_ref().unwrap()[1][100], 1.0);
     }
 }
 
 #[test]
-fn supports_mpe_quirks() {
+fn program_change_rejected_without_factory_presets() {
     let proc = dummy_synth();
     let host = ComWrapper::new(dummy_host::Host::default());
-
     unsafe {
         setup_proc(&proc, &host);
-
-        let audio = mock_process(
-            2,
-            vec![Event {
-                sample_offset: 10,
-                data: Data::NoteOn {
-                    data: NoteData {
-                        id: NoteID::from_channel_for_mpe_quirks(1),
-                        pitch: 64,
-                        velocity: 0.5,
-                        tuning: 0f32,
-                    },
-                },
-            }],
-            vec![ParameterValueQueueImpl {
-                param_id: aftertouch_param_id(1).to_string(),
-                points: vec![ParameterValueQueuePoint {
-                    sample_offset: 10,
-                    value: 1.0,
-                }],
-            }],
-            &proc,
+        assert_eq!(
+            proc.process(
+                &mut mock_no_audio_process_data(vec![], vec![program_queue(1.0)]).process_data
+            ),
+            vst3::Steinberg::kInvalidArgument
         );
-
-        assert_approx_eq!(audio.as_ref().unwrap()[0][10], 2.0);
     }
 }
 
 #[test]
-fn supports_mpe_quirks_no_audio() {
-    let proc = dummy_synth();
+fn invalid_factory_preset_values_are_ignored() {
+    let proc = create_synth(
+        |_: &HostInfo| FakeComponent {
+            factory_presets: vec![
+                Preset {
+                    name: "Init".to_string(),
+                    values: HashMap::new(),
+                },
+                Preset {
+                    name: "Too Loud".to_string(),
+                    values: HashMap::from([
+                        (NUMERIC_ID.to_string(), Value::Numeric(MAX_NUMERIC + 1.0)),
+                        (ENUM_ID.to_string(), Value::Enum("3".to_string())),
+                    ]),
+                },
+            ],
+            ..fake_synth_component()
+        },
+        [5; 16],
+        SynthOptions::DEFAULT,
+    );
     let host = ComWrapper::new(dummy_host::Host::default());
-
     unsafe {
         setup_proc(&proc, &host);
         assert_eq!(
             proc.process(
-                &mut mock_no_audio_process_data(
-                    vec![Event {
-                        sample_offset: 0,
-                        data: Data::NoteOn {
-                            data: NoteData {
-                                id: NoteID::from_channel_for_mpe_quirks(1),
-                                pitch: 64,
-                                velocity: 0.5,
-                                tuning: 0f32,
-                            },
-                        },
-                    }],
-                    vec![],
-                )
-                .process_data
+                &mut mock_no_audio_process_data(vec![], vec![program_queue(1.0)]).process_data
             ),
             vst3::Steinberg::kResultOk
         );
+        assert_eq!(
+            get_state_values(&proc),
+            Ok(HashMap::from([
+                (NUMERIC_ID.to_string(), Value::Numeric(DEFAULT_NUMERIC)),
+                (ENUM_ID.to_string(), Value::Enum("3".to_string())),
+                (SWITCH_ID.to_string(), Value::Switch(DEFAULT_SWITCH)),
+            ]))
+        );
+    }
+}
+
+#[test]
+fn program_change_rejected_with_single_factory_preset() {
+    let proc = create_synth(
+        |_: &HostInfo| FakeComponent {
+            factory_presets: fake_presets()[..1].to_vec(),
+            ..fake_synth_component()
+        },
+        [4; 16],
+        SynthOptions::DEFAULT,
+    );
+    let host = ComWrapper::new(dummy_host::Host::default());
+    unsafe {
+        setup_proc(&proc, &host);
         assert_eq!(
             proc.process(
-                &mut mock_no_audio_process_data(
-                    vec![],
-                    vec![ParameterValueQueueImpl {
-                        param_id: aftertouch_param_id(1).to_string(),
-                        points: vec![ParameterValueQueuePoint {
-                            sample_offset: 0,
-                            value: 1.0, // Set it to the old max of 10!
-                        }],
-                    },],
-                )
-                .process_data
+                &mut mock_no_audio_process_data(vec![], vec![program_queue(1.0)]).process_data
             ),
-            vst3::Steinberg::kResultOk
+            vst3::Steinberg::kInvalidArgument
         );
-
-        let audio = mock_process(2, vec![], vec![], &proc);
-
-        assert_approx_eq!(audio.as_ref().unwrap()[0][10], 2.0);
     }
 }
@@ -0,0 +1,52 @@
+//! Factory presets, which VST3 calls "programs".
+//!
+//! Hosts select a program by changing a special "program change" parameter.
+//! The edit controller lists the programs through `IUnitInfo`, and both the edit
+//! controller and the processor apply the selected preset when this parameter changes.
+
+use conformal_component::parameters::{self, CurveHint, Flags, TypeSpecificInfo};
+use conformal_component::Preset;
+
+/// The unique ID of the parameter hosts use to select a program.
+pub const PARAMETER_ID: &str = "_conformal_internal_program";
+
+/// The ID of our program list. We only ever have one.
+pub const PROGRAM_LIST_ID: i32 = 0;
+
+/// The fewest presets we expose as programs.
+const MIN_PROGRAMS: usize = 2;
+
+/// Returns the presets we expose to the host.
+///
+/// A program list with a single program isn't useful, so we only expose
+/// presets if there are at least two. This also keeps the program change
+/// parameter a valid enum, see [`parameter`].
+pub fn exposed_presets(presets: Vec<Preset>) -> Vec<Preset> {
+    if presets.len() >= MIN_PROGRAMS {
+        presets
+    } else {
+        vec![]
+    }
+}
+
+/// Returns the parameter used to select a program, or `None` if there are too
+/// few programs.
+///
+/// The value of this parameter is the index of the selected preset. Like any
+/// enum parameter it needs at least two values, or normalizing it would divide
+/// by zero.
+pub fn parameter(presets: &[Preset]) -> Option<parameters::Info> {
+    (presets.len() >= MIN_PROGRAMS).then(|| parameters::Info {
+        unique_id: PARAMETER_ID.to_string(),
+        title: "Program".to_string(),
+        short_title: "Program".to_string(),
+        flags: Flags {
+            automatable: false,
+            curve_hint: CurveHint::Auto,
+        },
+        type_specific: TypeSpecificInfo::Enum {
+            default: 0,
+            values: presets.iter().map(|preset| preset.name.clone()).collect(),
+        },
+    })
+}
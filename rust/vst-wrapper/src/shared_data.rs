@@ -0,0 +1,104 @@
+use std::sync::OnceLock;
+
+use crate::{ComponentFactory, HostInfo};
+
+/// Immutable data that is built once and shared by many components.
+///
+/// This is useful for data that is expensive to build and identical for every
+/// instance of a plug-in, such as lookup tables or impulse responses. Since plug-in
+/// classes are declared as constants, this is meant to be stored in a `static`, so
+/// the data is shared by every instance of the plug-in in the process.
+///
+/// The data is built by `init` the first time it is needed. This is thread-safe -
+/// if several threads need the data at once, `init` is still only called once,
+/// and the other threads wait until it is done.
+///
+/// Usually this is used with a [`SharedDataFactory`].
+///
+/// # Examples
+///
+/// ```
+/// # use conformal_vst_wrapper::SharedData;
+/// fn build_table() -> Vec<f32> {
+///     (0..1024).map(|i| (i as f32 / 1024.0).sin()).collect()
+/// }
+///
+/// static TABLE: SharedData<Vec<f32>> = SharedData::new(build_table);
+///
+/// assert_eq!(TABLE.get().len(), 1024);
+/// assert!(std::ptr::eq(TABLE.get(), TABLE.get()));
+/// ```
+pub struct SharedData<D> {
+    data: OnceLock<D>,
+    init: fn() -> D,
+}
+
+impl<D> SharedData<D> {
+    /// Create a new [`SharedData`] that will be built by `init` when first needed.
+    #[must_use]
+    pub const fn new(init: fn() -> D) -> Self {
+        Self {
+            data: OnceLock::new(),
+            init,
+        }
+    }
+
+    /// Get the shared data, building it if this is the first time it's needed.
+    pub fn get(&self) -> &D {
+        self.data.get_or_init(self.init)
+    }
+}
+
+/// A [`ComponentFactory`] that hands [`SharedData`] to each component it creates.
+///
+/// The shared data is built the first time a component is created. Note that the
+/// wrapper creates components to query information such as parameters, so this may
+/// happen when the host scans the plug-in, before any audio is processed. If you would
+/// rather defer building the data until it's used, you can instead give each component
+/// a reference to the [`SharedData`] itself.
+///
+/// # Examples
+///
+/// ```
+/// # use conformal_vst_wrapper::{ComponentFactory, HostInfo, SharedData, SharedDataFactory};
+/// struct Component {
+///     table: &'static [f32],
+/// }
+///
+/// static TABLE: SharedData<Vec<f32>> = SharedData::new(|| vec![0.0; 1024]);
+///
+/// const FACTORY: SharedDataFactory<Vec<f32>, Component> = SharedDataFactory {
+///     shared: &TABLE,
+///     create: |_: &HostInfo, table: &'static Vec<f32>| Component { table },
+/// };
+///
+/// let a = FACTORY.create(&HostInfo::default());
+/// let b = FACTORY.create(&HostInfo::default());
+/// assert!(std::ptr::eq(a.table, b.table));
+/// ```
+pub struct SharedDataFactory<D: 'static, C> {
+    /// The data shared by all created components.
+    pub shared: &'static SharedData<D>,
+
+    /// Create a component, given the host and the shared data.
+    pub create: fn(&HostInfo, &'static D) -> C,
+}
+
+impl<D, C> Clone for SharedDataFactory<D, C> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<D, C> Copy for SharedDataFactory<D, C> {}
+
+impl<D: Send + Sync, C> ComponentFactory for SharedDataFactory<D, C> {
+    type Component = C;
+
+    fn create(&self, host: &HostInfo) -> C {
+        (self.create)(host, self.shared.get())
+    }
+}
+
+#[cfg(test)]
+mod tests;
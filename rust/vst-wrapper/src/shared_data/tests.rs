@@ -0,0 +1,40 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use super::{SharedData, SharedDataFactory};
+use crate::{ComponentFactory, HostInfo};
+
+static BUILD_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+fn build_table() -> Vec<f32> {
+    BUILD_COUNT.fetch_add(1, Ordering::SeqCst);
+    vec![0.5; 16]
+}
+
+static TABLE: SharedData<Vec<f32>> = SharedData::new(build_table);
+
+struct TableComponent {
+    table: &'static Vec<f32>,
+}
+
+const FACTORY: SharedDataFactory<Vec<f32>, TableComponent> = SharedDataFactory {
+    shared: &TABLE,
+    create: |_, table| TableComponent { table },
+};
+
+#[test]
+fn shared_data_is_built_once() {
+    let components = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..8)
+            .map(|_| scope.spawn(|| FACTORY.create(&HostInfo::default())))
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect::<Vec<_>>()
+    });
+    assert_eq!(BUILD_COUNT.load(Ordering::SeqCst), 1);
+    for component in &components {
+        assert!(std::ptr::eq(component.table, components[0].table));
+    }
+    assert_eq!(components[0].table.len(), 16);
+}
@@ -0,0 +1,225 @@
+//! Utilities for testing plug-ins through the wrapper.
+//!
+//! These are only available with the `test-utils` feature, which is meant to be
+//! enabled from `[dev-dependencies]`.
+
+use std::{collections::HashMap, hash::BuildHasher};
+
+use conformal_component::parameters::{hash_id, Value};
+use conformal_core::parameters::normalization::to_normalized;
+use vst3::{
+    ComPtr, ComWrapper,
+    Steinberg::{
+        IBStream, IPluginBaseTrait,
+        Vst::{IComponent, IComponentTrait, IEditControllerTrait, IHostApplication},
+    },
+};
+
+use crate::{
+    dummy_host, edit_controller,
+    fake_ibstream::Stream,
+    host_info,
+    processor::state::{deserialize_values, serialize_values},
+    ClassCategory,
+};
+
+unsafe fn get_state(component: &ComPtr<IComponent>) -> Vec<u8> {
+    let stream = ComWrapper::new(Stream::new([]));
+    assert_eq!(
+        component.getState(stream.as_com_ref::<IBStream>().unwrap().as_ptr()),
+        vst3::Steinberg::kResultOk
+    );
+    stream.data()
+}
+
+unsafe fn set_state(component: &ComPtr<IComponent>, state: &[u8]) {
+    let stream = ComWrapper::new(Stream::new(state.iter().copied()));
+    assert_eq!(
+        component.setState(stream.as_com_ref::<IBStream>().unwrap().as_ptr()),
+        vst3::Steinberg::kResultOk
+    );
+}
+
+/// Asserts that parameter values survive saving and then loading the plug-in's state.
+///
+/// This checks the same steps a host takes when saving and re-opening a project:
+///
+///  - Creates the processor for `class` through the wrapper, and sets its parameters
+///    to `overrides`. Any parameters not in `overrides` keep their defaults.
+///  - Saves the processor's state, and loads it into a freshly created processor
+///    and a freshly created edit controller.
+///  - Checks that the new processor saves the same values, and that the edit
+///    controller reports the same values to the host.
+///
+/// In VST3, the processor and the edit controller each save their own state. This
+/// wrapper saves all parameter values in the processor's state, and saves nothing
+/// in the edit controller's state. When loading a project, hosts pass the processor's
+/// state to the edit controller as well, so that's what this does too.
+///
+/// The plug-in is created with a dummy host, so components that customize their
+/// parameters based on [`crate::HostInfo`] are tested as they'd be in an unknown host.
+///
+/// # Panics
+///
+/// Panics if any parameter has a different value after loading the state, or if
+/// `overrides` contains values that don't match the plug-in's parameters.
+///
+/// # Examples
+///
+/// ```
+/// # use std::collections::HashMap;
+/// # use conformal_component::audio::{channels, channels_mut, Buffer, BufferMut};
+/// # use conformal_component::effect::Effect as EffectTrait;
+/// # use conformal_component::parameters::{self, BufferStates, Flags, InfoRef, TypeSpecificInfoRef, Value};
+/// # use conformal_component::{Component as ComponentTrait, ProcessingEnvironment, Processor};
+/// # use conformal_vst_wrapper::{ClassInfo, EffectClass, EffectOptions, HostInfo};
+/// # use conformal_vst_wrapper::test_utils::assert_state_roundtrip;
+/// # #[derive(Clone, Debug, Default)]
+/// # pub struct Effect {}
+/// # impl Processor for Effect {
+/// #     fn set_processing(&mut self, _processing: bool) {}
+/// # }
+/// # impl EffectTrait for Effect {
+/// #     fn handle_parameters<P: parameters::States>(&mut self, _: P) {}
+/// #     fn process<P: BufferStates, I: Buffer, O: BufferMut>(&mut self, _: P, _: &I, _: &mut O) {}
+/// # }
+/// # #[derive(Clone, Debug, Default)]
+/// # pub struct Component {}
+/// # impl ComponentTrait for Component {
+/// #     type Processor = Effect;
+/// #     fn parameter_infos(&self) -> Vec<parameters::Info> {
+/// #         parameters::to_infos(&[
+/// #             InfoRef {
+/// #                 title: "Bypass",
+/// #                 short_title: "Bypass",
+/// #                 unique_id: "bypass",
+/// #                 flags: Flags::default(),
+/// #                 type_specific: TypeSpecificInfoRef::Switch { default: false },
+/// #             },
+/// #             InfoRef {
+/// #                 title: "Gain",
+/// #                 short_title: "Gain",
+/// #                 unique_id: "gain",
+/// #                 flags: Flags::default(),
+/// #                 type_specific: TypeSpecificInfoRef::Numeric {
+/// #                     default: 100.,
+/// #                     valid_range: 0f32..=100.,
+/// #                     units: Some("%"),
+/// #                     options: Default::default(),
+/// #                 },
+/// #             },
+/// #         ])
+/// #     }
+/// #     fn create_processor(&self, _env: &ProcessingEnvironment) -> Self::Processor {
+/// #         Default::default()
+/// #     }
+/// # }
+/// let class = EffectClass {
+///     info: ClassInfo::new(
+///         "My effect",
+///         [1; 16],
+///         [2; 16],
+///         conformal_vst_wrapper::UiSize {
+///             width: 400,
+///             height: 400,
+///         },
+///     ),
+///     factory: |_: &HostInfo| -> Component { Default::default() },
+///     category: "Fx",
+///     bypass_id: "bypass",
+///     options: EffectOptions::DEFAULT,
+/// };
+///
+/// assert_state_roundtrip(
+///     &class,
+///     &HashMap::from([
+///         ("gain".to_string(), Value::Numeric(25.0)),
+///         ("bypass".to_string(), Value::Switch(true)),
+///     ]),
+/// );
+/// ```
+pub fn assert_state_roundtrip<H: BuildHasher>(
+    class: &dyn ClassCategory,
+    overrides: &HashMap<String, Value, H>,
+) {
+    let host = ComWrapper::new(dummy_host::Host::default())
+        .to_com_ptr::<IHostApplication>()
+        .unwrap();
+    let host_info = host_info::get(&host).unwrap();
+    let model = (class.create_parameter_model().for_host)(&host_info);
+    let infos = model.parameter_infos;
+    let version = model.state_version;
+    let deserialize = |state: &[u8]| {
+        deserialize_values(&infos, version, &model.upgrade_parameters, state)
+            .expect("Saved state could not be loaded")
+    };
+
+    let initial_state = serialize_values(infos.clone(), version, overrides)
+        .expect("Overrides must match the plug-in's parameters");
+    let expected = deserialize(&initial_state);
+
+    let create_processor = || {
+        let processor = class.create_processor(class.info().edit_controller_cid);
+        unsafe {
+            assert_eq!(
+                processor.initialize(host.cast().unwrap().as_ptr()),
+                vst3::Steinberg::kResultOk
+            );
+        }
+        processor.cast::<IComponent>().unwrap()
+    };
+
+    let processor = create_processor();
+    let saved = unsafe {
+        set_state(&processor, &initial_state);
+        get_state(&processor)
+    };
+    assert_eq!(
+        deserialize(&saved),
+        expected,
+        "Processor did not save its state"
+    );
+
+    let reloaded_processor = create_processor();
+    let resaved = unsafe {
+        set_state(&reloaded_processor, &saved);
+        get_state(&reloaded_processor)
+    };
+    assert_eq!(
+        deserialize(&resaved),
+        expected,
+        "State changed after loading it into a new processor"
+    );
+
+    let controller = edit_controller::create_with_pref_domain(
+        class.create_parameter_model(),
+        "conformal_state_roundtrip".to_string(),
+        class.info().ui_initial_size,
+        class.info().ui_size_constraints,
+        class.get_kind(),
+    );
+    unsafe {
+        assert_eq!(
+            controller.initialize(host.cast().unwrap().as_ptr()),
+            vst3::Steinberg::kResultOk
+        );
+        let stream = ComWrapper::new(Stream::new(saved));
+        assert_eq!(
+            controller.setComponentState(stream.as_com_ref::<IBStream>().unwrap().as_ptr()),
+            vst3::Steinberg::kResultOk
+        );
+    }
+    for info in &infos {
+        let value = &expected[&info.unique_id];
+        let normalized =
+            unsafe { controller.getParamNormalized(hash_id(&info.unique_id).internal_hash()) };
+        assert!(
+            (normalized - to_normalized(value, info).unwrap()).abs() < 1e-9,
+            "Edit controller has the wrong value for parameter {}",
+            info.unique_id
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,135 @@
+use std::collections::HashMap;
+
+use conformal_component::audio::BufferMut;
+use conformal_component::events::{Data, Event, Events};
+use conformal_component::parameters::{
+    self, BufferStates, CurveHint, Flags, InfoRef, NumericOptions, States, StaticInfoRef,
+    TypeSpecificInfoRef, Value,
+};
+use conformal_component::synth::Synth;
+use conformal_component::{Component, ProcessingEnvironment, Processor};
+
+use super::assert_state_roundtrip;
+use crate::{ClassInfo, HostInfo, SynthClass, SynthOptions};
+
+static PARAMETERS: [StaticInfoRef; 3] = [
+    InfoRef {
+        title: "Cutoff",
+        short_title: "Cutoff",
+        unique_id: "cutoff",
+        flags: Flags {
+            automatable: true,
+            curve_hint: CurveHint::Auto,
+        },
+        type_specific: TypeSpecificInfoRef::Numeric {
+            default: 1000.0,
+            valid_range: 20.0..=20000.0,
+            units: Some("Hz"),
+            options: NumericOptions::DEFAULT,
+        },
+    },
+    InfoRef {
+        title: "Shape",
+        short_title: "Shape",
+        unique_id: "shape",
+        flags: Flags {
+            automatable: true,
+            curve_hint: CurveHint::Auto,
+        },
+        type_specific: TypeSpecificInfoRef::Enum {
+            default: 0,
+            values: &["Saw", "Square", "Triangle"],
+        },
+    },
+    InfoRef {
+        title: "Mono",
+        short_title: "Mono",
+        unique_id: "mono",
+        flags: Flags {
+            automatable: false,
+            curve_hint: CurveHint::Auto,
+        },
+        type_specific: TypeSpecificInfoRef::Switch { default: false },
+    },
+];
+
+#[derive(Default)]
+struct DummySynth {}
+
+impl Processor for DummySynth {
+    fn set_processing(&mut self, _processing: bool) {}
+}
+
+impl Synth for DummySynth {
+    fn handle_events<E: IntoIterator<Item = Data>, P: States>(
+        &mut self,
+        _events: E,
+        _parameters: P,
+    ) {
+    }
+
+    fn process<E: IntoIterator<Item = Event>, P: BufferStates, O: BufferMut>(
+        &mut self,
+        _events: Events<E>,
+        _parameters: P,
+        _output: &mut O,
+    ) {
+    }
+}
+
+struct DummyComponent {}
+
+impl Component for DummyComponent {
+    type Processor = DummySynth;
+
+    fn parameter_infos(&self) -> Vec<parameters::Info> {
+        parameters::to_infos(&PARAMETERS)
+    }
+
+    fn create_processor(&self, _env: &ProcessingEnvironment) -> Self::Processor {
+        Default::default()
+    }
+}
+
+fn dummy_class() -> SynthClass<impl Fn(&HostInfo) -> DummyComponent + Clone> {
+    SynthClass {
+        info: ClassInfo {
+            name: "Test Synth",
+            cid: [1; 16],
+            edit_controller_cid: [2; 16],
+            ui_initial_size: crate::UiSize {
+                width: 400,
+                height: 400,
+            },
+            ui_size_constraints: None,
+        },
+        factory: |_: &HostInfo| DummyComponent {},
+        options: SynthOptions::DEFAULT,
+    }
+}
+
+#[test]
+fn defaults_roundtrip() {
+    assert_state_roundtrip(&dummy_class(), &HashMap::new());
+}
+
+#[test]
+fn overrides_roundtrip() {
+    assert_state_roundtrip(
+        &dummy_class(),
+        &HashMap::from([
+            ("cutoff".to_string(), Value::Numeric(440.0)),
+            ("shape".to_string(), Value::Enum("Triangle".to_string())),
+            ("mono".to_string(), Value::Switch(true)),
+        ]),
+    );
+}
+
+#[test]
+#[should_panic(expected = "Overrides must match the plug-in's parameters")]
+fn rejects_invalid_overrides() {
+    assert_state_roundtrip(
+        &dummy_class(),
+        &HashMap::from([("shape".to_string(), Value::Enum("Sine".to_string()))]),
+    );
+}
@@ -0,0 +1,117 @@
+use conformal_component::{
+    audio::{Buffer, BufferMut, ChannelLayout},
+    effect::Effect,
+    parameters::{BufferStates, Flags, Info, States, TypeSpecificInfo},
+    Component, ProcessingEnvironment, Processor,
+};
+
+use crate::{
+    create_parameter_model_internal, ClassCategory, ClassInfo, EffectClass, EffectOptions,
+    HostInfo, UiSize,
+};
+
+/// A component that only has an extra parameter in one specific host.
+struct HostDependentComponent {
+    extra_parameter: bool,
+}
+
+fn switch_info(unique_id: &str) -> Info {
+    Info {
+        unique_id: unique_id.to_string(),
+        title: unique_id.to_string(),
+        short_title: unique_id.to_string(),
+        flags: Flags::default(),
+        type_specific: TypeSpecificInfo::Switch { default: false },
+    }
+}
+
+impl Component for HostDependentComponent {
+    type Processor = ();
+
+    fn parameter_infos(&self) -> Vec<Info> {
+        let mut infos = vec![switch_info("always")];
+        if self.extra_parameter {
+            infos.push(switch_info("special_host_only"));
+        }
+        infos
+    }
+
+    fn create_processor(&self, _env: &ProcessingEnvironment) -> Self::Processor {}
+}
+
+#[test]
+fn host_agnostic_parameter_infos_uses_representative_host() {
+    let model = create_parameter_model_internal(|host: &HostInfo| HostDependentComponent {
+        extra_parameter: host.name == "Special Host",
+    });
+    assert_eq!(
+        model
+            .host_agnostic_parameter_infos()
+            .into_iter()
+            .map(|info| info.unique_id)
+            .collect::<Vec<_>>(),
+        vec!["always".to_string()]
+    );
+    assert_eq!(
+        (model.for_host)(&HostInfo {
+            name: "Special Host".to_string(),
+        })
+        .parameter_infos
+        .len(),
+        2
+    );
+}
+
+struct SilentEffect;
+
+impl Processor for SilentEffect {
+    fn set_processing(&mut self, _processing: bool) {}
+}
+
+impl Effect for SilentEffect {
+    fn handle_parameters<P: States>(&mut self, _parameters: P) {}
+
+    fn process<P: BufferStates, I: Buffer, O: BufferMut>(
+        &mut self,
+        _parameters: P,
+        _input: &I,
+        _output: &mut O,
+    ) {
+    }
+}
+
+/// A component that is invalid, since it doesn't support stereo.
+struct MonoOnlyComponent;
+
+impl Component for MonoOnlyComponent {
+    type Processor = SilentEffect;
+
+    fn supported_channel_layouts(&self) -> Vec<ChannelLayout> {
+        vec![ChannelLayout::Mono]
+    }
+
+    fn create_processor(&self, _env: &ProcessingEnvironment) -> Self::Processor {
+        SilentEffect
+    }
+}
+
+#[test]
+#[should_panic(expected = "Invalid component")]
+fn invalid_component_panics_when_class_is_constructed() {
+    let class = EffectClass {
+        info: ClassInfo::new(
+            "Mono only",
+            [1; 16],
+            [2; 16],
+            UiSize {
+                width: 400,
+                height: 400,
+            },
+        ),
+        factory: |_: &HostInfo| MonoOnlyComponent,
+        category: "Fx",
+        bypass_id: "bypass",
+        options: EffectOptions::DEFAULT,
+    };
+    class.create_parameter_model();
+}
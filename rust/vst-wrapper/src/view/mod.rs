@@ -8,6 +8,12 @@ use conformal_component::parameters;
 use conformal_core::parameters::store;
 use conformal_ui::{self, raw_window_handle, Size, Ui};
 
+use crate::UiSizeConstraints;
+
+mod size;
+
+use size::constrain_size;
+
 // Only include tests in test config on macos
 #[cfg(all(test, target_os = "macos"))]
 mod tests;
@@ -28,6 +34,11 @@ struct View<S> {
     domain: String,
 
     initial_size: Size,
+
+    /// The current size of the UI, as most recently set by the host.
+    size: Size,
+
+    size_constraints: Option<UiSizeConstraints>,
 }
 
 struct ViewCell<S>(RefCell<View<S>>);
@@ -38,6 +49,12 @@ impl<S: store::Store + 'static> store::Listener for ViewCell<S> {
             ui.update_parameter(unique_id, value);
         }
     }
+
+    fn ui_state_changed(&self, state: &[u8]) {
+        if let Some(ui) = self.0.borrow_mut().ui.as_mut() {
+            ui.update_ui_state(state);
+        }
+    }
 }
 
 struct SharedView<S>(rc::Rc<ViewCell<S>>);
@@ -76,18 +93,29 @@ impl<S: store::Store> conformal_ui::ParameterStore for SharedStore<S> {
     fn get_info(&self, unique_id: &str) -> Option<parameters::Info> {
         self.0.borrow().get_info(unique_id)
     }
+
+    fn get_ui_state(&self) -> Vec<u8> {
+        self.0.borrow().get_ui_state()
+    }
+
+    fn set_ui_state(&mut self, state: &[u8]) {
+        self.0.borrow_mut().set_ui_state(state);
+    }
 }
 
 pub fn create<S: store::Store + 'static>(
     store: S,
     domain: String,
     initial_size: Size,
+    size_constraints: Option<UiSizeConstraints>,
 ) -> ComPtr<IPlugView> {
     let view = SharedView(rc::Rc::new(ViewCell(RefCell::new(View {
         store: SharedStore(rc::Rc::new(RefCell::new(store))),
         ui: Default::default(),
         domain,
         initial_size,
+        size: initial_size,
+        size_constraints,
     }))));
     let view_as_listener: rc::Rc<dyn store::Listener> = view.clone().0;
     view.borrow_mut()
@@ -156,8 +184,8 @@ impl<S: store::Store + 'static> IPlugViewTrait for SharedView<S> {
             let handle = to_window_handle(&platform_type, parent);
             let store = self.borrow().store.clone();
             let domain = self.borrow().domain.clone();
-            let initial_size = self.borrow().initial_size;
-            self.borrow_mut().ui = Ui::new(handle, store, domain.as_str(), initial_size).ok();
+            let size = self.borrow().size;
+            self.borrow_mut().ui = Ui::new(handle, store, domain.as_str(), size).ok();
             return vst3::Steinberg::kResultOk;
         }
         vst3::Steinberg::kInvalidArgument
@@ -199,14 +227,25 @@ impl<S: store::Store + 'static> IPlugViewTrait for SharedView<S> {
     unsafe fn getSize(&self, size: *mut vst3::Steinberg::ViewRect) -> vst3::Steinberg::tresult {
         (*size).top = 0;
         (*size).left = 0;
-        (*size).right = self.borrow().initial_size.width;
-        (*size).bottom = self.borrow().initial_size.height;
-        (*size).bottom = 400;
+        (*size).right = self.borrow().size.width;
+        (*size).bottom = self.borrow().size.height;
         vst3::Steinberg::kResultOk
     }
 
-    unsafe fn onSize(&self, _new_size: *mut vst3::Steinberg::ViewRect) -> vst3::Steinberg::tresult {
-        vst3::Steinberg::kNotImplemented
+    unsafe fn onSize(&self, new_size: *mut vst3::Steinberg::ViewRect) -> vst3::Steinberg::tresult {
+        if new_size.is_null() {
+            return vst3::Steinberg::kInvalidArgument;
+        }
+        let size = Size {
+            width: (*new_size).right - (*new_size).left,
+            height: (*new_size).bottom - (*new_size).top,
+        };
+        let mut view = self.borrow_mut();
+        view.size = size;
+        if let Some(ui) = view.ui.as_mut() {
+            ui.set_size(size);
+        }
+        vst3::Steinberg::kResultOk
     }
 
     unsafe fn onFocus(&self, _state: vst3::Steinberg::TBool) -> vst3::Steinberg::tresult {
@@ -225,14 +264,35 @@ impl<S: store::Store + 'static> IPlugViewTrait for SharedView<S> {
     }
 
     unsafe fn canResize(&self) -> vst3::Steinberg::tresult {
-        vst3::Steinberg::kResultFalse
+        // Only classes that declare size constraints opt in to resizing.
+        if self.borrow().size_constraints.is_some() {
+            vst3::Steinberg::kResultTrue
+        } else {
+            vst3::Steinberg::kResultFalse
+        }
     }
 
     unsafe fn checkSizeConstraint(
         &self,
-        _rect: *mut vst3::Steinberg::ViewRect,
+        rect: *mut vst3::Steinberg::ViewRect,
     ) -> vst3::Steinberg::tresult {
-        vst3::Steinberg::kNotImplemented
+        let Some(constraints) = self.borrow().size_constraints else {
+            return vst3::Steinberg::kNotImplemented;
+        };
+        if rect.is_null() {
+            return vst3::Steinberg::kInvalidArgument;
+        }
+        let size = constrain_size(
+            Size {
+                width: (*rect).right - (*rect).left,
+                height: (*rect).bottom - (*rect).top,
+            },
+            self.borrow().initial_size,
+            &constraints,
+        );
+        (*rect).right = (*rect).left + size.width;
+        (*rect).bottom = (*rect).top + size.height;
+        vst3::Steinberg::kResultTrue
     }
 }
 
@@ -0,0 +1,41 @@
+//! Fitting host-chosen UI sizes to a class's [`UiSizeConstraints`].
+
+use conformal_ui::Size;
+
+use crate::UiSizeConstraints;
+
+#[cfg(test)]
+mod tests;
+
+/// Returns the closest size to `size` that satisfies `constraints`.
+///
+/// If the constraints are inconsistent (for example, `min` is larger than `max`),
+/// the minimum wins.
+// Sizes are rounded to whole pixels, so we expect truncation here.
+#[allow(clippy::cast_possible_truncation)]
+pub fn constrain_size(size: Size, initial_size: Size, constraints: &UiSizeConstraints) -> Size {
+    let min = constraints.min.unwrap_or(Size {
+        width: 0,
+        height: 0,
+    });
+    let max = constraints.max.unwrap_or(Size {
+        width: i32::MAX,
+        height: i32::MAX,
+    });
+    if constraints.keep_aspect_ratio && initial_size.width > 0 && initial_size.height > 0 {
+        let ratio = f64::from(initial_size.width) / f64::from(initial_size.height);
+        // Find the range of widths where both the width and the matching height are in bounds.
+        let min_width = f64::from(min.width).max(f64::from(min.height) * ratio);
+        let max_width = f64::from(max.width).min(f64::from(max.height) * ratio);
+        let width = f64::from(size.width).min(max_width).max(min_width).round();
+        Size {
+            width: width as i32,
+            height: (width / ratio).round() as i32,
+        }
+    } else {
+        Size {
+            width: size.width.min(max.width).max(min.width),
+            height: size.height.min(max.height).max(min.height),
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use super::constrain_size;
+use crate::UiSizeConstraints;
+
+fn size(width: i32, height: i32) -> conformal_ui::Size {
+    conformal_ui::Size { width, height }
+}
+
+fn constrained(requested: conformal_ui::Size, constraints: &UiSizeConstraints) -> (i32, i32) {
+    let constrained = constrain_size(requested, size(400, 200), constraints);
+    (constrained.width, constrained.height)
+}
+
+#[test]
+fn no_constraints_keep_size() {
+    assert_eq!(
+        constrained(size(1000, 50), &UiSizeConstraints::default()),
+        (1000, 50)
+    );
+}
+
+#[test]
+fn clamps_to_min_and_max() {
+    let constraints = UiSizeConstraints {
+        min: Some(size(200, 100)),
+        max: Some(size(800, 400)),
+        keep_aspect_ratio: false,
+    };
+    assert_eq!(constrained(size(1000, 50), &constraints), (800, 100));
+    assert_eq!(constrained(size(300, 300), &constraints), (300, 300));
+}
+
+#[test]
+fn min_wins_for_inconsistent_constraints() {
+    let constraints = UiSizeConstraints {
+        min: Some(size(500, 500)),
+        max: Some(size(100, 100)),
+        keep_aspect_ratio: false,
+    };
+    assert_eq!(constrained(size(300, 300), &constraints), (500, 500));
+}
+
+#[test]
+fn keeps_aspect_ratio() {
+    let constraints = UiSizeConstraints {
+        keep_aspect_ratio: true,
+        ..Default::default()
+    };
+    assert_eq!(constrained(size(600, 600), &constraints), (600, 300));
+}
+
+#[test]
+fn keeps_aspect_ratio_within_bounds() {
+    let constraints = UiSizeConstraints {
+        min: Some(size(200, 100)),
+        max: Some(size(1000, 300)),
+        keep_aspect_ratio: true,
+    };
+    // The height limit is stricter than the width limit here.
+    assert_eq!(constrained(size(1000, 500), &constraints), (600, 300));
+    assert_eq!(constrained(size(10, 10), &constraints), (200, 100));
+}
@@ -1,6 +1,7 @@
 use vst3::Steinberg::IPlugViewTrait;
 
 use super::create;
+use crate::UiSizeConstraints;
 use conformal_component::parameters;
 use conformal_core::parameters::store;
 struct DummyStore;
@@ -27,6 +28,12 @@ impl store::Store for DummyStore {
     fn get_info(&self, _unique_id: &str) -> Option<parameters::Info> {
         None
     }
+
+    fn get_ui_state(&self) -> Vec<u8> {
+        vec![]
+    }
+
+    fn set_ui_state(&mut self, _state: &[u8]) {}
 }
 
 #[test]
@@ -38,6 +45,7 @@ fn nsview_platform_supported() {
             width: 100,
             height: 100,
         },
+        None,
     );
     let nsview = std::ffi::CString::new("NSView").unwrap();
     unsafe {
@@ -57,6 +65,7 @@ fn bananas_platform_not_supported() {
             width: 100,
             height: 100,
         },
+        None,
     );
     // Maybe some day, we will support bananas...
     let nsview = std::ffi::CString::new("Bananas").unwrap();
@@ -77,6 +86,7 @@ fn defends_against_null_parent() {
             width: 100,
             height: 100,
         },
+        None,
     );
     let nsview = std::ffi::CString::new("NSView").unwrap();
     assert_ne!(
@@ -84,3 +94,122 @@ fn defends_against_null_parent() {
         vst3::Steinberg::kResultOk
     );
 }
+
+fn size(width: i32, height: i32) -> conformal_ui::Size {
+    conformal_ui::Size { width, height }
+}
+
+fn check_size_constraint(
+    constraints: Option<UiSizeConstraints>,
+    width: i32,
+    height: i32,
+) -> (vst3::Steinberg::tresult, i32, i32) {
+    let v = create(
+        DummyStore {},
+        "test".to_string(),
+        size(400, 200),
+        constraints,
+    );
+    let mut rect = vst3::Steinberg::ViewRect {
+        left: 10,
+        top: 20,
+        right: 10 + width,
+        bottom: 20 + height,
+    };
+    let result = unsafe { v.checkSizeConstraint(&mut rect) };
+    assert_eq!(rect.left, 10);
+    assert_eq!(rect.top, 20);
+    (result, rect.right - rect.left, rect.bottom - rect.top)
+}
+
+#[test]
+fn check_size_constraint_not_implemented_without_constraints() {
+    assert_eq!(
+        check_size_constraint(None, 1000, 1000),
+        (vst3::Steinberg::kNotImplemented, 1000, 1000)
+    );
+}
+
+#[test]
+fn check_size_constraint_reports_declared_constraints() {
+    let constraints = Some(UiSizeConstraints {
+        min: Some(size(200, 100)),
+        max: Some(size(800, 400)),
+        keep_aspect_ratio: false,
+    });
+    assert_eq!(
+        check_size_constraint(constraints, 1000, 50),
+        (vst3::Steinberg::kResultTrue, 800, 100)
+    );
+    assert_eq!(
+        check_size_constraint(constraints, 300, 300),
+        (vst3::Steinberg::kResultTrue, 300, 300)
+    );
+}
+
+#[test]
+fn check_size_constraint_keeps_aspect_ratio() {
+    let constraints = Some(UiSizeConstraints {
+        keep_aspect_ratio: true,
+        ..Default::default()
+    });
+    assert_eq!(
+        check_size_constraint(constraints, 600, 600),
+        (vst3::Steinberg::kResultTrue, 600, 300)
+    );
+}
+
+fn get_size(v: &vst3::ComPtr<vst3::Steinberg::IPlugView>) -> (i32, i32) {
+    let mut rect = vst3::Steinberg::ViewRect {
+        left: 0,
+        top: 0,
+        right: 0,
+        bottom: 0,
+    };
+    assert_eq!(unsafe { v.getSize(&mut rect) }, vst3::Steinberg::kResultOk);
+    (rect.right - rect.left, rect.bottom - rect.top)
+}
+
+#[test]
+fn can_resize_only_with_constraints() {
+    let fixed = create(DummyStore {}, "test".to_string(), size(400, 200), None);
+    assert_eq!(unsafe { fixed.canResize() }, vst3::Steinberg::kResultFalse);
+    let resizable = create(
+        DummyStore {},
+        "test".to_string(),
+        size(400, 200),
+        Some(UiSizeConstraints::default()),
+    );
+    assert_eq!(
+        unsafe { resizable.canResize() },
+        vst3::Steinberg::kResultTrue
+    );
+}
+
+#[test]
+fn get_size_reports_size_from_host() {
+    let v = create(
+        DummyStore {},
+        "test".to_string(),
+        size(400, 200),
+        Some(UiSizeConstraints::default()),
+    );
+    assert_eq!(get_size(&v), (400, 200));
+    let mut rect = vst3::Steinberg::ViewRect {
+        left: 10,
+        top: 20,
+        right: 610,
+        bottom: 320,
+    };
+    assert_eq!(unsafe { v.onSize(&mut rect) }, vst3::Steinberg::kResultOk);
+    assert_eq!(get_size(&v), (600, 300));
+}
+
+#[test]
+fn defends_against_null_size() {
+    let v = create(DummyStore {}, "test".to_string(), size(400, 200), None);
+    assert_eq!(
+        unsafe { v.onSize(std::ptr::null_mut()) },
+        vst3::Steinberg::kInvalidArgument
+    );
+}
@@ -14,7 +14,9 @@
 
 use conformal_component::audio::{channels, channels_mut, Buffer, BufferMut};
 use conformal_component::effect::Effect as EffectTrait;
-use conformal_component::parameters::{self, BufferStates, Flags, InfoRef, TypeSpecificInfoRef};
+use conformal_component::parameters::{
+    self, BufferStates, CurveHint, Flags, InfoRef, NumericOptions, TypeSpecificInfoRef,
+};
 use conformal_component::pzip;
 use conformal_component::{Component as ComponentTrait, ProcessingEnvironment, Processor};
 
@@ -23,18 +25,25 @@ const PARAMETERS: [InfoRef<'static, &'static str>; 2] = [
         title: "Bypass",
         short_title: "Bypass",
         unique_id: "bypass",
-        flags: Flags { automatable: true },
+        flags: Flags {
+            automatable: true,
+            curve_hint: CurveHint::Auto,
+        },
         type_specific: TypeSpecificInfoRef::Switch { default: false },
     },
     InfoRef {
         title: "Gain",
         short_title: "Gain",
         unique_id: "gain",
-        flags: Flags { automatable: true },
+        flags: Flags {
+            automatable: true,
+            curve_hint: CurveHint::Auto,
+        },
         type_specific: TypeSpecificInfoRef::Numeric {
             default: 100.,
             valid_range: 0f32..=100.,
             units: Some("%"),
+            options: NumericOptions::DEFAULT,
         },
     },
 ];
@@ -80,3 +89,14 @@ impl ComponentTrait for Component {
         Default::default()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Component;
+    use conformal_component::validate_component;
+
+    #[test]
+    fn component_is_valid() {
+        assert_eq!(validate_component(&Component::default()), Ok(()));
+    }
+}
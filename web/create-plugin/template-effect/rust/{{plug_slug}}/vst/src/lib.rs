@@ -14,7 +14,7 @@
 
 use {{plug_slug}}_component::Component;
 
-use conformal_vst_wrapper::{ClassID, ClassInfo, EffectClass, HostInfo, Info};
+use conformal_vst_wrapper::{ClassID, ClassInfo, EffectClass, EffectOptions, HostInfo, Info};
 
 const CID: ClassID = [
     {{class_id}}
@@ -23,23 +23,24 @@ const EDIT_CONTROLLER_CID: ClassID = [
     {{edit_class_id}}
 ];
 
+const CLASS: EffectClass<fn(&HostInfo) -> Component> = EffectClass {
+    info: ClassInfo::new(
+        "{{plug_name}}",
+        CID,
+        EDIT_CONTROLLER_CID,
+        conformal_vst_wrapper::UiSize {
+            width: 400,
+            height: 400,
+        },
+    ),
+    factory: |_: &HostInfo| -> Component { Default::default() },
+    category: "Fx",
+    bypass_id: "bypass",
+    options: EffectOptions::DEFAULT,
+};
+
 conformal_vst_wrapper::wrap_factory!(
-    &const {
-        [&EffectClass {
-            info: ClassInfo {
-                name: "{{plug_name}}",
-                cid: CID,
-                edit_controller_cid: EDIT_CONTROLLER_CID,
-                ui_initial_size: conformal_vst_wrapper::UiSize {
-                    width: 400,
-                    height: 400,
-                },
-            },
-            factory: |_: &HostInfo| -> Component { Default::default() },
-            category: "Fx",
-            bypass_id: "bypass",
-        }]
-    },
+    &const { [&CLASS] },
     Info {
         vendor: "{{vendor_name}}",
         url: "{{task_marker}} add URL",
@@ -47,3 +48,22 @@ conformal_vst_wrapper::wrap_factory!(
         version: "1.0.0",
     }
 );
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use conformal_component::parameters::Value;
+    use conformal_vst_wrapper::test_utils::assert_state_roundtrip;
+
+    #[test]
+    fn state_roundtrip() {
+        assert_state_roundtrip(
+            &super::CLASS,
+            &HashMap::from([
+                ("gain".to_string(), Value::Numeric(50.0)),
+                ("bypass".to_string(), Value::Switch(true)),
+            ]),
+        );
+    }
+}
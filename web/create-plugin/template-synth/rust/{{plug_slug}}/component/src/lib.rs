@@ -14,7 +14,9 @@
 
 use conformal_component::audio::BufferMut;
 use conformal_component::events::{self, Event, Events, NoteData};
-use conformal_component::parameters::{self, BufferStates, Flags, InfoRef, TypeSpecificInfoRef};
+use conformal_component::parameters::{
+    self, BufferStates, CurveHint, Flags, InfoRef, NumericOptions, TypeSpecificInfoRef,
+};
 use conformal_component::synth::Synth as SynthTrait;
 use conformal_component::{pzip, Component as ComponentTrait, ProcessingEnvironment, Processor};
 use conformal_poly::{self, EventData, Poly, Voice as VoiceTrait};
@@ -24,11 +26,15 @@ const PARAMETERS: [InfoRef<'static, &'static str>; 1] = [InfoRef {
     title: "Gain",
     short_title: "Gain",
     unique_id: "gain",
-    flags: Flags { automatable: true },
+    flags: Flags {
+        automatable: true,
+        curve_hint: CurveHint::Auto,
+    },
     type_specific: TypeSpecificInfoRef::Numeric {
         default: 100.,
         valid_range: 0f32..=100.,
         units: Some("%"),
+        options: NumericOptions::DEFAULT,
     },
 }];
 
@@ -100,6 +106,9 @@ impl VoiceTrait for Voice {
         match event {
             EventData::NoteOn {
                 data: NoteData { pitch, .. },
+            }
+            | EventData::Legato {
+                data: NoteData { pitch, .. },
             } => {
                 self.pitch = Some(f32::from(*pitch));
             }
@@ -119,7 +128,7 @@ impl VoiceTrait for Voice {
         >,
         _data: Self::SharedData<'_>,
         output: &mut [f32],
-    ) {
+    ) -> usize {
         let mut events = events.into_iter().peekable();
         for ((index, sample), (gain, global_pitch_bend), expression) in izip!(
             output.iter_mut().enumerate(),
@@ -147,6 +156,7 @@ impl VoiceTrait for Voice {
                 self.phase -= self.phase.floor();
             }
         }
+        output.len()
     }
 
     fn quiescent(&self) -> bool {
@@ -172,3 +182,14 @@ impl ComponentTrait for Component {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Component;
+    use conformal_component::validate_component;
+
+    #[test]
+    fn component_is_valid() {
+        assert_eq!(validate_component(&Component::default()), Ok(()));
+    }
+}
@@ -17,6 +17,11 @@ const mockGeneric = (infos: Map<string, Info>): Family<Value> =>
       return atom<Value>(info.type_specific.default);
     }
 
+    if (path === "ui-state") {
+      // Mock stores start with no UI state
+      return atom<Value>(new Uint8Array());
+    }
+
     const prefsPath = path.match(/^prefs\/(.*)$/);
     if (prefsPath) {
       // All prefs are "false" in mock stores